@@ -0,0 +1,50 @@
+#version 330 core
+
+in VERTEX_SHADER_OUTPUT {
+  vec2 TexCoords;
+} IN;
+
+out vec4 Color;
+
+uniform sampler2D uTexture;
+uniform vec2 uResolution;
+uniform float uTime;
+uniform int uDistortionCount;
+uniform vec4 uDistortions[8]; // center in pixels, radius, strength
+uniform float uScanlineIntensity; // how dark the gaps between the rows get, 0 turns them off
+uniform float uCurvature; // barrel distortion towards the corners
+uniform float uAberration; // red and blue shift apart by this many texels at the edges
+
+void main() {
+  // the quad covers the letterboxed viewport, so the middle of the texture is the middle of the image
+  vec2 coords = IN.TexCoords;
+  vec2 centered = coords * 2.0 - 1.0;
+  centered *= 1.0 + uCurvature * dot(centered, centered);
+  coords = centered * 0.5 + 0.5;
+  if (any(lessThan(coords, vec2(0.0))) || any(greaterThan(coords, vec2(1.0)))) {
+    Color = vec4(0.0, 0.0, 0.0, 1.0);
+    return;
+  }
+
+  if (uDistortionCount > 0) {
+    vec2 pixel = coords * uResolution;
+    float intensity = 0.0;
+    for (int i = 0; i < uDistortionCount; i++) {
+      float falloff = 1.0 - smoothstep(0.0, uDistortions[i].z, distance(pixel, uDistortions[i].xy));
+      intensity += falloff * uDistortions[i].w;
+    }
+    vec2 wobble = vec2(sin(pixel.y * 0.35 + uTime * 9.0), cos(pixel.x * 0.3 + uTime * 7.0));
+    coords += wobble * intensity / uResolution;
+  }
+
+  vec2 texels = vec2(textureSize(uTexture, 0));
+  vec2 shift = centered * uAberration / texels;
+  Color = vec4(
+    texture(uTexture, coords + shift).r,
+    texture(uTexture, coords).g,
+    texture(uTexture, coords - shift).b,
+    1.0
+  );
+  float row = 0.5 + 0.5 * cos(coords.y * texels.y * 6.2831853);
+  Color.rgb *= 1.0 - uScanlineIntensity * row;
+}
@@ -0,0 +1,11 @@
+#version 330 core
+
+in VERTEX_SHADER_OUTPUT {
+  vec4 Color;
+} IN;
+
+out vec4 Color;
+
+void main() {
+  Color = IN.Color;
+}
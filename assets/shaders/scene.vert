@@ -0,0 +1,16 @@
+#version 330 core
+
+layout (location = 0) in mat4 Transform;
+layout (location = 4) in vec4 Color;
+layout (location = 5) in vec2 Position;
+
+uniform mat4 uMVP;
+
+out VERTEX_SHADER_OUTPUT {
+  vec4 Color;
+} OUT;
+
+void main() {
+  gl_Position = uMVP * Transform * vec4(Position, 0.0, 1.0);
+  OUT.Color = Color;
+}
@@ -0,0 +1,23 @@
+#version 330 core
+
+in VERTEX_SHADER_OUTPUT {
+  vec2 TexCoords;
+  vec4 Color;
+} IN;
+
+out vec4 Color;
+
+uniform sampler2D uTexture;
+uniform float uSmoothing;
+uniform float uOutlineWidth;
+uniform vec4 uOutlineColor;
+
+void main() {
+  float distance = texture(uTexture, IN.TexCoords).r;
+  float smoothing = max(fwidth(distance) * uSmoothing, 0.0001);
+  float fill = smoothstep(0.5 - smoothing, 0.5 + smoothing, distance);
+  float edge = 0.5 - uOutlineWidth;
+  float outline = smoothstep(edge - smoothing, edge + smoothing, distance);
+  vec4 color = mix(uOutlineColor, IN.Color, fill);
+  Color = vec4(color.rgb, color.a * outline);
+}
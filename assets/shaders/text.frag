@@ -0,0 +1,15 @@
+#version 330 core
+
+in VERTEX_SHADER_OUTPUT {
+  vec2 TexCoords;
+  vec4 Color;
+} IN;
+
+out vec4 Color;
+
+uniform sampler2D uTexture;
+
+void main() {
+  vec4 sampled = vec4(1.0, 1.0, 1.0, texture(uTexture, IN.TexCoords).r);
+  Color = IN.Color * sampled;
+}
@@ -0,0 +1,17 @@
+#version 330 core
+
+layout (location = 0) in vec4 PosTex;
+layout (location = 1) in vec4 Color;
+
+uniform mat4 uProjection;
+
+out VERTEX_SHADER_OUTPUT {
+  vec2 TexCoords;
+  vec4 Color;
+} OUT;
+
+void main() {
+  gl_Position = uProjection * vec4(PosTex.xy, 0.0, 1.0);
+  OUT.TexCoords = PosTex.zw;
+  OUT.Color = Color;
+}
@@ -13,6 +13,7 @@ fn main() {
     Fallbacks::All,
     [
       "GL_NV_command_list", // additional extension we want to use
+      "GL_KHR_debug",       // debug output callback, core since 4.3
     ],
   )
   .write_bindings(StructGenerator, &mut file_gl)
@@ -0,0 +1,623 @@
+use crate::{
+  audio::{audio_system, BoostLoop},
+  audit::{Divergence, Mismatch, Snapshot},
+  camera::{camera_follow_system, CameraFollow, CameraMode},
+  balance::GameBalance,
+  components::{AmmoPickup, AttackPickup, BoostPickup, BoundsMode, Enemy, Player, Projectile},
+  draw::GeometryCache,
+  environment::{AUDIT_INTERVAL, PARTICLE_POOL_CAP, TEXT_BUFFER_GLYPHS},
+  events::{event_count_system, event_update_system, insert_event_channels},
+  gallery::gallery_system,
+  health::SystemHealth,
+  inspector::Inspector,
+  memory::{memory_steward_system, MemorySteward},
+  menus::{
+    console_menu_system, controls_menu_system, pause_menu_system, practice_menu_system, profiles_menu_system,
+    quit_dialog_system, shop_menu_system, stats_menu_system, Menus,
+  },
+  modifiers::Modifiers,
+  objectives::{objectives_hud_system, objectives_system, Objectives},
+  output_files::output_files_system,
+  profiles::Profile,
+  prompts::{input_device_system, InputDevice},
+  render::{
+    calculate_size_for_circles, calculate_size_for_lines, calculate_size_for_particles, calculate_size_for_quads,
+    calculate_size_for_screen, MyTextVertex, MyVertex,
+  },
+  replay::{Replay, ReplayFrame},
+  resources::*,
+  rumble::{rumble_system, Rumble},
+  scenario::{stress_scenario_system, StressScenario},
+  spatial::SpatialGrid,
+  practice::practice_system,
+  shop::skill_points_system,
+  starfield::draw_starfield_system,
+  stats::{run_stats_system, RunStats},
+  systems::*,
+  tasks::{main_thread_tasks_system, MainThreadTasks},
+  ui::{draw_ui_system, ui_frame_system, UiContext},
+};
+use bevy_ecs::{prelude::*, world::World};
+use rand::SeedableRng;
+use sdl2::keyboard::Keycode;
+use std::{collections::HashSet, mem::size_of, time::Duration};
+
+/// Inserts every resource the simulation needs, except the draw buffers which need a GL context.
+pub fn build_world(seed: u64, health: &SystemHealth, stress: bool, modifiers: &Modifiers) -> World {
+  let mut world = World::default();
+  world.insert_resource(Time::default());
+  world.insert_resource(TimeScale::default());
+  world.insert_resource(SpawnSequence::default());
+  world.insert_resource(ParticlePool::new(PARTICLE_POOL_CAP));
+  world.insert_resource(Randoms(rand::rngs::SmallRng::seed_from_u64(seed)));
+  world.insert_resource(Objectives::new(seed));
+  world.insert_resource(GameBalance::default());
+  world.insert_resource(*modifiers);
+  world.insert_resource(EntitySpawnTimer::new(modifiers));
+  world.insert_resource(PickupPity::default());
+  world.insert_resource(Score::default());
+  world.insert_resource(Bomb::default());
+  world.insert_resource(Respawn::default());
+  world.insert_resource(Deaths::default());
+  world.insert_resource(Phase::default());
+  world.insert_resource(GameState::default());
+  world.insert_resource(Idle::default());
+  world.insert_resource(KeyCodes(HashSet::<Keycode>::default()));
+  world.insert_resource(InputState::default());
+  world.insert_resource(KeyBindings::default());
+  world.insert_resource(GamepadState::default());
+  world.insert_resource(MouseInput::default());
+  world.insert_resource(InputDevice::default());
+  world.insert_resource(UiContext::default());
+  world.insert_resource(Menus::default());
+  world.insert_resource(Profile::session());
+  world.insert_resource(RunStats::default());
+  world.insert_resource(Camera::default());
+  world.insert_resource(CameraFollow::new(CameraMode::Fixed, &WorldBounds::default()));
+  world.insert_resource(Shake::new(seed));
+  world.insert_resource(ZoomPunch::default());
+  world.insert_resource(Flash::default());
+  world.insert_resource(FlashGuard::default());
+  world.insert_resource(Distortions::default());
+  world.insert_resource(PostFx::default());
+  world.insert_resource(RenderAlpha::default());
+  world.insert_resource(Minimap::default());
+  world.insert_resource(InputDisplay::default());
+  world.insert_resource(FrameStats::default());
+  world.insert_resource(BoostLoop::default());
+  world.insert_resource(Rumble::default());
+  world.insert_resource(Heatmap::default());
+  world.insert_resource(SpatialGrid::default());
+  world.insert_resource(TitleLogo::default());
+  world.insert_resource(CurvePanel::default());
+  world.insert_resource(Inspector::default());
+  world.insert_resource(WorldBounds::default());
+  world.insert_resource(BoundsMode::default());
+  world.insert_resource(health.clone());
+  world.insert_resource(DurationWrapper(Duration::default()));
+  insert_event_channels(&mut world);
+  world.insert_resource(Strokes::default());
+  world.insert_resource(Fills::default());
+  world.insert_resource(GlyphMeshes::default());
+  world.insert_resource(GeometryCache::default());
+  world.insert_resource(MainThreadTasks::default());
+  world.insert_resource(memory_steward());
+  if stress {
+    world.insert_resource(StressScenario::default());
+  }
+
+  world
+}
+
+/// The draw buffers are registered before they exist, they are only accessed once the game schedule
+/// runs.
+fn memory_steward() -> MemorySteward {
+  let mut steward = MemorySteward::default();
+  steward.register("circle vertices", 4096, |world| {
+    &mut world.resource_mut::<CircleGeometry>().into_inner().vertex_buffer.vertices
+  });
+  steward.register("circle indices", 8192, |world| {
+    &mut world.resource_mut::<CircleGeometry>().into_inner().vertex_buffer.indices
+  });
+  steward.register("quad vertices", 4096, |world| {
+    &mut world.resource_mut::<QuadGeometry>().into_inner().vertex_buffer.vertices
+  });
+  steward.register("quad indices", 8192, |world| {
+    &mut world.resource_mut::<QuadGeometry>().into_inner().vertex_buffer.indices
+  });
+  steward.register("line vertices", 1024, |world| {
+    &mut world.resource_mut::<LineGeometry>().into_inner().vertex_buffer.vertices
+  });
+  steward.register("line indices", 2048, |world| {
+    &mut world.resource_mut::<LineGeometry>().into_inner().vertex_buffer.indices
+  });
+  steward.register("particle vertices", 1024, |world| {
+    &mut world.resource_mut::<ParticleGeometry>().into_inner().vertex_buffer.vertices
+  });
+  steward.register("particle indices", 2048, |world| {
+    &mut world.resource_mut::<ParticleGeometry>().into_inner().vertex_buffer.indices
+  });
+  steward.register("screen vertices", 4096, |world| {
+    &mut world.resource_mut::<ScreenGeometry>().into_inner().vertex_buffer.vertices
+  });
+  steward.register("screen indices", 8192, |world| {
+    &mut world.resource_mut::<ScreenGeometry>().into_inner().vertex_buffer.indices
+  });
+  steward.register("bitmap text vertices", 1024, |world| {
+    &mut world.resource_mut::<TextBuffers>().into_inner().bitmap.vertex_buffer
+  });
+  steward.register("bitmap text indices", 1536, |world| {
+    &mut world.resource_mut::<TextBuffers>().into_inner().bitmap.index_buffer
+  });
+  steward.register("sdf text vertices", 1024, |world| {
+    &mut world.resource_mut::<TextBuffers>().into_inner().sdf.vertex_buffer
+  });
+  steward.register("sdf text indices", 1536, |world| {
+    &mut world.resource_mut::<TextBuffers>().into_inner().sdf.index_buffer
+  });
+
+  register_gpu_buffers::<Circle>(&mut steward, ["circle vbo", "circle ebo"], calculate_size_for_circles());
+  register_gpu_buffers::<Quad>(&mut steward, ["quad vbo", "quad ebo"], calculate_size_for_quads());
+  register_gpu_buffers::<Line>(&mut steward, ["line vbo", "line ebo"], calculate_size_for_lines());
+  register_gpu_buffers::<Particle>(&mut steward, ["particle vbo", "particle ebo"], calculate_size_for_particles());
+  register_gpu_buffers::<Screen>(&mut steward, ["screen vbo", "screen ebo"], calculate_size_for_screen());
+  steward.register("text vbo", 4 * size_of::<MyTextVertex>() * TEXT_BUFFER_GLYPHS, |world| {
+    &mut world.resource_mut::<TextBuffers>().into_inner().vbo_allocation
+  });
+  steward.register("text ebo", 6 * size_of::<u32>() * TEXT_BUFFER_GLYPHS, |world| {
+    &mut world.resource_mut::<TextBuffers>().into_inner().ebo_allocation
+  });
+
+  steward
+}
+
+/// The vbo and ebo of a [`DrawBuffers`], resting at the size [`crate::render::create_draw_buffer`]
+/// allocates them with.
+fn register_gpu_buffers<T>(steward: &mut MemorySteward, [vbo, ebo]: [&'static str; 2], capacity: BufferCapacity)
+where
+  DrawBuffers<T>: Resource,
+{
+  steward.register(vbo, size_of::<MyVertex>() * capacity.vertices, |world| {
+    &mut world.resource_mut::<DrawBuffers<T>>().into_inner().vbo_allocation
+  });
+  steward.register(ebo, size_of::<u32>() * capacity.indices, |world| {
+    &mut world.resource_mut::<DrawBuffers<T>>().into_inner().ebo_allocation
+  });
+}
+
+/// Draw buffers that are never uploaded, for worlds that only simulate. Call [`clear_draw_buffers`]
+/// after every tick, nothing else empties them.
+pub fn insert_detached_buffers(world: &mut World) {
+  world.insert_resource(CircleGeometry::new(0, 0, 0));
+  world.insert_resource(QuadGeometry::new(0, 0, 0));
+  world.insert_resource(LineGeometry::new(0, 0, 0));
+  world.insert_resource(ParticleGeometry::new(0, 0, 0));
+  world.insert_resource(ScreenGeometry::new(0, 0, 0));
+  world.insert_resource(TextBuffers {
+    vao: 0,
+    vbo: 0,
+    ebo: 0,
+    vbo_allocation: GpuAllocation::default(),
+    ebo_allocation: GpuAllocation::default(),
+    bitmap: TextLayer::default(),
+    sdf: TextLayer::default(),
+  });
+}
+
+/// A world that only simulates, [`build_world`] with draw buffers that don't need a GL context. Used
+/// by `--headless` and the lockstep audit, the players still need [`build_startup_schedule`].
+pub fn build_headless_world(seed: u64, health: &SystemHealth, stress: bool, modifiers: &Modifiers) -> World {
+  let mut world = build_world(seed, health, stress, modifiers);
+  insert_detached_buffers(&mut world);
+  world
+}
+
+/// Runs `ticks` ticks of `step` each on a world from [`build_headless_world`], holding down the keys
+/// `input` returns for each tick. What the main loop does per tick, without a window or a renderer.
+pub fn run_ticks(
+  world: &mut World,
+  schedule: &mut Schedule,
+  ticks: u64,
+  step: Duration,
+  mut input: impl FnMut(u64) -> HashSet<Keycode>,
+) {
+  for tick in 0..ticks {
+    *world.resource_mut() = DurationWrapper(step);
+    *world.resource_mut() = KeyCodes(input(tick));
+    record_frame(world);
+    schedule.run(world);
+    clear_draw_buffers(world);
+    replay_checkpoint(world);
+  }
+}
+
+/// Adds the input `world` is about to tick with to the [`Replay`] it records, if it records one.
+pub fn record_frame(world: &mut World) {
+  let Some(replay) = world.get_resource::<Replay>().filter(|replay| !replay.is_playing()) else {
+    return;
+  };
+  let frame = replay.capture(
+    world.resource::<DurationWrapper>().0,
+    world.resource::<KeyCodes>(),
+    world.resource::<MouseInput>(),
+    world.resource::<GamepadState>(),
+    *world.resource::<GameState>(),
+  );
+  world.resource_mut::<Replay>().frames.push(frame);
+}
+
+/// Feeds `world` the input of a [`Replay`] frame in place of the polled one.
+pub fn play_frame(world: &mut World, frame: &ReplayFrame) {
+  let input = world.resource::<Replay>().input(frame);
+  *world.resource_mut() = KeyCodes(input.keycodes);
+  *world.resource_mut() = input.mouse;
+  *world.resource_mut() = input.gamepad;
+  *world.resource_mut() = input.state;
+}
+
+/// A world set up with the options `replay` was recorded with, the replay goes in with it ready to play.
+pub fn build_replay_world(replay: Replay, health: &SystemHealth) -> World {
+  let mut world = build_headless_world(replay.seed, health, false, &replay.modifiers);
+  world.resource_mut::<PickupPity>().enabled = !replay.pure_rng;
+  world.insert_resource(replay.bindings.clone());
+  world.insert_resource(replay.bounds_mode);
+  world.insert_resource(replay);
+  build_startup_schedule().run(&mut world);
+  world
+}
+
+/// Plays the [`Replay`] in `world` to the end as fast as it goes. How many ticks that took, or the first
+/// checkpoint the run doesn't match.
+pub fn play_replay(world: &mut World, schedule: &mut Schedule) -> Result<u64, Mismatch> {
+  while let Some(frame) = world.resource_mut::<Replay>().next_frame() {
+    *world.resource_mut() = DurationWrapper(frame.dt);
+    play_frame(world, &frame);
+    schedule.run(world);
+    clear_draw_buffers(world);
+    if let Some(mismatch) = replay_checkpoint(world) {
+      return Err(mismatch);
+    }
+  }
+  finish_replay(world)
+}
+
+/// Every [`AUDIT_INTERVAL`] ticks of the [`Replay`] in `world` records or checks a checkpoint, see
+/// [`Replay::check`].
+pub fn replay_checkpoint(world: &mut World) -> Option<Mismatch> {
+  let tick = world.get_resource::<Replay>()?.tick();
+  if tick == 0 || !tick.is_multiple_of(AUDIT_INTERVAL) {
+    return None;
+  }
+  let checkpoint = Snapshot::capture(world, tick).checkpoint();
+  world.resource_mut::<Replay>().check(checkpoint)
+}
+
+/// Records or checks the checkpoint after the last frame of the [`Replay`] in `world`, returns the ticks
+/// it ran for.
+pub fn finish_replay(world: &mut World) -> Result<u64, Mismatch> {
+  let tick = world.resource::<Replay>().tick();
+  let snapshot = Snapshot::capture(world, tick);
+  let mut replay = world.resource_mut::<Replay>();
+  if !replay.is_playing() {
+    replay.final_hash = snapshot.hash();
+  }
+  match replay.check(snapshot.checkpoint()) {
+    Some(mismatch) => Err(mismatch),
+    None => Ok(tick),
+  }
+}
+
+/// `--audit-lockstep`, a second world fed the same input has to stay in lockstep with the first.
+pub struct Lockstep {
+  world: World,
+  schedule: Schedule,
+}
+
+impl Lockstep {
+  pub fn new(world: World, schedule: Schedule) -> Self {
+    Self { world, schedule }
+  }
+
+  /// Runs the tick `main` just ran with the same input. Every [`AUDIT_INTERVAL`] ticks compares the two
+  /// worlds, returning where they first differ.
+  pub fn follow(&mut self, main: &mut World, tick: u64) -> Option<Divergence> {
+    *self.world.resource_mut() = DurationWrapper(main.resource::<DurationWrapper>().0);
+    *self.world.resource_mut() = KeyCodes(main.resource::<KeyCodes>().0.clone());
+    *self.world.resource_mut() = main.resource::<MouseInput>().clone();
+    *self.world.resource_mut() = main.resource::<GamepadState>().clone();
+    *self.world.resource_mut() = *main.resource::<GameState>();
+    self.schedule.run(&mut self.world);
+    clear_draw_buffers(&mut self.world);
+
+    if !tick.is_multiple_of(AUDIT_INTERVAL) {
+      return None;
+    }
+    Snapshot::capture(main, tick).diff(&Snapshot::capture(&mut self.world, tick))
+  }
+}
+
+pub fn clear_draw_buffers(world: &mut World) {
+  world.resource_mut::<CircleGeometry>().clear();
+  world.resource_mut::<QuadGeometry>().clear();
+  world.resource_mut::<LineGeometry>().clear();
+  world.resource_mut::<ParticleGeometry>().clear();
+  world.resource_mut::<ScreenGeometry>().clear();
+  world.resource_mut::<TextBuffers>().clear();
+}
+
+pub fn build_startup_schedule() -> Schedule {
+  let mut schedule = Schedule::default();
+  schedule.add_stage(
+    "startup",
+    SystemStage::single_threaded().with_system(player_spawn_system),
+  );
+
+  schedule
+}
+
+/// Tessellates the entities between the last two ticks, see [`RenderAlpha`]. It only reads components.
+fn draw_stage(health: &SystemHealth) -> SystemStage {
+  let mut stage = SystemStage::parallel();
+  stage.add_system(health.cosmetic(draw_starfield_system));
+  stage.add_system(draw_player_system);
+  stage.add_system(health.cosmetic(draw_shooting_system));
+  stage.add_system(health.cosmetic(draw_tick_effects_system));
+  stage.add_system(health.cosmetic(draw_trail_effects_system));
+  stage.add_system(draw_projectiles_system);
+  stage.add_system(draw_dead_projectiles_system);
+  stage.add_system(draw_ammo_pickups_system);
+  stage.add_system(draw_boost_pickups_system);
+  stage.add_system(draw_collected_boost_pickups_system);
+  stage.add_system(draw_attack_pickups_system);
+  stage.add_system(draw_enemies_system);
+  stage.add_system(draw_shockwaves_system);
+  stage.add_system(draw_burn_zones_system);
+  stage.add_system(draw_lasers_system);
+  stage.add_system(draw_despawn_animations_system);
+  stage.add_system(health.cosmetic(draw_explosions_system));
+  stage.add_system(health.cosmetic(draw_screen_flash_system));
+
+  stage
+}
+
+/// Only the systems that draw, with time never advanced everything stays frozen.
+pub fn build_gallery_schedule(health: &SystemHealth) -> Schedule {
+  let mut schedule = Schedule::default();
+  schedule.add_stage(
+    "previous",
+    SystemStage::single_threaded().with_system(previous_transform_system),
+  );
+  schedule.add_stage_after("previous", "gallery", {
+    let mut stage = SystemStage::single_threaded();
+    stage.add_system(player_system);
+    stage.add_system(health.cosmetic(shooting_system));
+    stage.add_system(projectile_system);
+    stage.add_system(projectile_death_system);
+    stage.add_system(ammo_pickup_system);
+    stage.add_system(boost_pickup_system);
+    stage.add_system(attack_pickup_system);
+    stage.add_system(enemy_system);
+    stage.add_system(health.cosmetic(explosion_system));
+    stage.add_system(health.cosmetic(minimap_system));
+    stage.add_system(health.cosmetic(input_display_system));
+    stage.add_system(gallery_system);
+
+    stage
+  });
+  schedule.add_stage_after(
+    "gallery",
+    "despawn",
+    SystemStage::single_threaded().with_system(despawn_system),
+  );
+  schedule.add_stage_after(
+    "despawn",
+    "tasks",
+    SystemStage::single_threaded().with_system(main_thread_tasks_system),
+  );
+
+  schedule
+}
+
+/// A stage whose systems run one after the other, in the order they were added. Bevy orders only the
+/// systems it is told to and the rest by hash order, different every run. Which system spawns first
+/// or draws from [`Randoms`] first has to stay the same for replays and the lockstep audit.
+struct Sequence {
+  stage: SystemStage,
+  labels: Vec<&'static str>,
+}
+
+impl Sequence {
+  fn new() -> Self {
+    Self {
+      stage: SystemStage::single_threaded(),
+      labels: Vec::new(),
+    }
+  }
+
+  fn add<S: IntoSystemDescriptor<Params> + 'static, Params>(&mut self, system: S) -> &mut Self {
+    let label = std::any::type_name::<S>();
+    assert!(!self.labels.contains(&label), "{} added twice", label);
+    let system = match self.labels.last() {
+      Some(&previous) => system.label(label).after(previous),
+      None => system.label(label),
+    };
+    self.stage.add_system(system);
+    self.labels.push(label);
+    self
+  }
+}
+
+pub fn build_game_schedule(health: &SystemHealth, stress: bool) -> Schedule {
+  let mut schedule = Schedule::default();
+  schedule.add_stage("events", {
+    let mut stage = Sequence::new();
+    stage.add(event_update_system);
+    stage.add(previous_transform_system);
+    stage.add(timing_system);
+    stage.add(input_system);
+    stage.add(ui_frame_system);
+    stage.add(spawn_seq_system);
+
+    stage.stage
+  });
+  schedule.add_stage_after("events", "game", {
+    let mut stage = Sequence::new();
+    stage.add(player_system);
+    stage.add(bomb_system);
+    stage.add(movement_system::<With<Player>>);
+    stage.add(player_bounds_system);
+    stage.add(health.cosmetic(shooting_system));
+    stage.add(tick_effect_spawn_system);
+    stage.add(health.cosmetic(tick_effect_system));
+    stage.add(projectile_spawn_system);
+    stage.add(homing_projectile_system);
+    stage.add(enemy_system);
+    stage.add(movement_system::<With<Enemy>>);
+    stage.add(projectile_system);
+    stage.add(hostile_sweep_system);
+    stage.add(movement_system::<With<Projectile>>);
+    stage.add(projectile_death_system);
+    stage.add(laser_hazard_spawn_system);
+    stage.add(practice_system);
+    stage.add(flash_guard_system);
+    stage.add(laser_hazard_system);
+    stage.add(player_explosion_spawn_system);
+    stage.add(trail_effect_spawn_system);
+    stage.add(spatial_index_system);
+    stage.add(collision_system);
+    stage.add(hostile_projectile_system);
+    stage.add(shockwave_system);
+    stage.add(burn_zone_spawn_system);
+    stage.add(burn_zone_system);
+    stage.add(damage_system);
+    stage.add(ammo_pickup_system);
+    stage.add(movement_system::<With<AmmoPickup>>);
+    stage.add(boost_pickup_system);
+    stage.add(movement_system::<With<BoostPickup>>);
+    stage.add(attack_pickup_system);
+    stage.add(movement_system::<With<AttackPickup>>);
+    stage.add(pickup_pity_system);
+    stage.add(health.cosmetic(trail_effect_system));
+    stage.add(health.cosmetic(camera_follow_system));
+    stage.add(health.cosmetic(camera_shake_system));
+    stage.add(health.cosmetic(rumble_system));
+    stage.add(health.cosmetic(camera_zoom_system));
+    stage.add(health.cosmetic(screen_flash_system));
+    stage.add(ammo_pickup_spawn_system);
+    stage.add(health.cosmetic(explosion_system));
+    stage.add(health.cosmetic(effect_movement_system));
+    stage.add(distortion_system);
+    stage.add(boost_pickup_spawn_system);
+    stage.add(enemy_spawn_system);
+    stage.add(attack_pickup_spawn_system);
+    stage.add(score_system);
+    stage.add(objectives_system);
+    stage.add(output_files_system);
+    stage.add(notification_system);
+    stage.add(health.cosmetic(audio_system));
+    stage.add(high_score_system);
+    stage.add(skill_points_system);
+    stage.add(run_stats_system);
+    stage.add(health.cosmetic(high_score_table_system));
+    stage.add(health.cosmetic(draw_text_system));
+    stage.add(health.cosmetic(minimap_system));
+    stage.add(health.cosmetic(input_display_system));
+    stage.add(health.cosmetic(input_device_system));
+    stage.add(health.cosmetic(hud_system));
+    stage.add(health.cosmetic(objectives_hud_system));
+    stage.add(health.cosmetic(boost_ui_system));
+    stage.add(health.cosmetic(frame_stats_system));
+    stage.add(health.cosmetic(replay_system));
+    stage.add(health.cosmetic(modifiers_system));
+    stage.add(health.cosmetic(pause_system));
+    stage.add(health.cosmetic(pause_menu_system));
+    stage.add(health.cosmetic(controls_menu_system));
+    stage.add(health.cosmetic(profiles_menu_system));
+    stage.add(health.cosmetic(stats_menu_system));
+    stage.add(health.cosmetic(shop_menu_system));
+    stage.add(health.cosmetic(practice_menu_system));
+    stage.add(health.cosmetic(console_menu_system));
+    stage.add(health.cosmetic(quit_dialog_system));
+    stage.add(health.cosmetic(draw_ui_system));
+    stage.add(health.cosmetic(heatmap_system));
+    stage.add(health.cosmetic(title_logo_system));
+    stage.add(health.cosmetic(curve_panel_system));
+    stage.add(system_health_system);
+    stage.add(despawn_animation_system);
+    stage.add(idle_system);
+    if stress {
+      stage.add(stress_scenario_system);
+    }
+    stage.add(event_count_system);
+
+    stage.stage
+  });
+  schedule.add_stage_after(
+    "game",
+    "despawn",
+    SystemStage::single_threaded().with_system(despawn_system),
+  );
+  // its own stage, so every command of the game stage is applied before it clears the playfield
+  schedule.add_stage_after("despawn", "respawn", {
+    let mut stage = Sequence::new();
+    stage.add(player_respawn_system);
+    stage.add(scope_cleanup_system);
+    if cfg!(debug_assertions) {
+      stage.add(untagged_spawn_system);
+    }
+
+    stage.stage
+  });
+  schedule.add_stage_after(
+    "respawn",
+    "tasks",
+    SystemStage::single_threaded().with_system(main_thread_tasks_system),
+  );
+
+  schedule
+}
+
+/// Runs once per frame after the ticks, with [`RenderAlpha`] set. The memory steward comes last, so it
+/// sees all of the frame's geometry.
+pub fn build_draw_schedule(health: &SystemHealth) -> Schedule {
+  let mut schedule = Schedule::default();
+  schedule.add_stage("draw", draw_stage(health));
+  schedule.add_stage_after(
+    "draw",
+    "memory",
+    SystemStage::single_threaded().with_system(memory_steward_system),
+  );
+
+  schedule
+}
+
+/// How far the ticks filled each draw buffer, see [`truncate_draw_buffers`].
+pub type TickGeometry = [(usize, usize); 5];
+
+pub fn tick_geometry(world: &World) -> TickGeometry {
+  fn len<T>(buffers: &DrawBuffers<T>) -> (usize, usize) {
+    (buffers.vertex_buffer.vertices.len(), buffers.vertex_buffer.indices.len())
+  }
+  [
+    len(world.resource::<CircleGeometry>()),
+    len(world.resource::<QuadGeometry>()),
+    len(world.resource::<LineGeometry>()),
+    len(world.resource::<ParticleGeometry>()),
+    len(world.resource::<ScreenGeometry>()),
+  ]
+}
+
+/// Drops what the draw schedule added after the ticks. The tick geometry stays, a frame without a tick
+/// draws it again.
+pub fn truncate_draw_buffers(world: &mut World, tick: TickGeometry) {
+  fn truncate<T>(buffers: &mut DrawBuffers<T>, (vertices, indices): (usize, usize)) {
+    buffers.vertex_buffer.vertices.truncate(vertices);
+    buffers.vertex_buffer.indices.truncate(indices);
+  }
+  truncate(world.resource_mut::<CircleGeometry>().into_inner(), tick[0]);
+  truncate(world.resource_mut::<QuadGeometry>().into_inner(), tick[1]);
+  truncate(world.resource_mut::<LineGeometry>().into_inner(), tick[2]);
+  truncate(world.resource_mut::<ParticleGeometry>().into_inner(), tick[3]);
+  truncate(world.resource_mut::<ScreenGeometry>().into_inner(), tick[4]);
+}
+
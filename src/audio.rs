@@ -0,0 +1,158 @@
+use crate::{
+  components::{Boost, PlayerId},
+  environment::{AUDIO_CHANNELS, AUDIO_CHUNK_SIZE, SFX_DIR},
+  events::{GameEvents, Pickup, PickupEvent},
+  input::Thrust,
+  pack::load_asset,
+  resources::InputState,
+};
+use bevy_ecs::prelude::*;
+use sdl2::{
+  mixer::{Channel, Chunk, LoaderRWops, DEFAULT_CHANNELS, DEFAULT_FORMAT, DEFAULT_FREQUENCY},
+  rwops::RWops,
+  AudioSubsystem, Sdl,
+};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sfx {
+  Shoot,
+  Pickup,
+  Explosion,
+  BoostLoop,
+}
+
+impl Sfx {
+  pub const ALL: [Sfx; 4] = [Sfx::Shoot, Sfx::Pickup, Sfx::Explosion, Sfx::BoostLoop];
+
+  pub fn path(self) -> String {
+    format!("{}/{}", SFX_DIR, self.file_name())
+  }
+
+  fn file_name(self) -> &'static str {
+    match self {
+      Sfx::Shoot => "shoot.wav",
+      Sfx::Pickup => "pickup.wav",
+      Sfx::Explosion => "explosion.wav",
+      Sfx::BoostLoop => "boost_loop.wav",
+    }
+  }
+}
+
+/// Keeps the audio device open, closes it when dropped. Drop it after the world, the chunks in
+/// [`AudioAssets`] need the device to be freed.
+pub struct Audio {
+  _subsystem: AudioSubsystem,
+}
+
+impl Drop for Audio {
+  fn drop(&mut self) {
+    sdl2::mixer::close_audio();
+  }
+}
+
+/// Opens the audio device, without one the game runs silent.
+pub fn init(sdl: &Sdl) -> Option<Audio> {
+  let subsystem = sdl
+    .audio()
+    .and_then(|subsystem| {
+      sdl2::mixer::open_audio(DEFAULT_FREQUENCY, DEFAULT_FORMAT, DEFAULT_CHANNELS, AUDIO_CHUNK_SIZE)?;
+      Ok(subsystem)
+    })
+    .map_err(|e| eprintln!("could not open audio device, playing without sound: {}", e))
+    .ok()?;
+  sdl2::mixer::allocate_channels(AUDIO_CHANNELS);
+
+  Some(Audio { _subsystem: subsystem })
+}
+
+/// Every sound effect of [`SFX_DIR`], loose, from the pack or the copy in the binary. A chunk isn't
+/// `Send`, so this is a non-send resource and [`audio_system`] runs on the main thread.
+#[derive(Default)]
+pub struct AudioAssets {
+  chunks: HashMap<Sfx, Chunk>,
+}
+
+impl AudioAssets {
+  /// Loads the sound of `sfx` through [`load_asset`], one step of the startup task that loads them
+  /// all. A broken file is reported and left out, its sound is skipped.
+  pub fn load(&mut self, sfx: Sfx) {
+    let path = sfx.path();
+    let bytes = load_asset(&path).map_err(|e| e.to_string());
+    match bytes.and_then(|bytes| RWops::from_bytes(&bytes)?.load_wav()) {
+      Ok(chunk) => {
+        self.chunks.insert(sfx, chunk);
+      }
+      Err(e) => eprintln!("could not load sound {}: {}", path, e),
+    }
+  }
+
+  fn play(&self, sfx: Sfx, loops: i32) -> Option<Channel> {
+    let chunk = self.chunks.get(&sfx)?;
+    Channel::all().play(chunk, loops).ok()
+  }
+}
+
+/// The channel the boost loop plays on, while it plays.
+#[derive(Debug, Default, Resource)]
+pub struct BoostLoop(pub Option<Channel>);
+
+/// Plays a sound for the events of the tick and loops the boost sound while any player boosts or
+/// brakes. Does nothing in worlds without [`AudioAssets`], like the lockstep audit's.
+pub fn audio_system(
+  assets: Option<NonSend<AudioAssets>>,
+  mut boost_loop: ResMut<BoostLoop>,
+  mut event_reader: EventReader<GameEvents>,
+  mut pickups: EventReader<PickupEvent>,
+  query: Query<(&PlayerId, &Boost)>,
+  input: Res<InputState>,
+) {
+  let Some(assets) = assets else {
+    return;
+  };
+
+  for event in event_reader.iter() {
+    let sfx = match event {
+      GameEvents::Shot(_) => Sfx::Shoot,
+      GameEvents::PlayerDeath(..) | GameEvents::BombDetonated => Sfx::Explosion,
+      GameEvents::EnemyDeath(..)
+      | GameEvents::AmmoChanged
+      | GameEvents::WallImpact
+      | GameEvents::PlayerContact { .. }
+      | GameEvents::Flash { .. }
+      | GameEvents::Shake(_)
+      | GameEvents::SlowMotion(_) => continue,
+    };
+    assets.play(sfx, 0);
+  }
+  for PickupEvent(pickup) in pickups.iter() {
+    if matches!(pickup, Pickup::Ammo | Pickup::Boost) {
+      assets.play(Sfx::Pickup, 0);
+    }
+  }
+
+  let boosting = query
+    .iter()
+    .any(|(id, boost)| input.player(*id).thrust != Thrust::Cruise && boost.can_boost());
+  match (boosting, boost_loop.0) {
+    (true, None) => boost_loop.0 = assets.play(Sfx::BoostLoop, -1),
+    (false, Some(channel)) => {
+      channel.halt();
+      boost_loop.0 = None;
+    }
+    _ => {}
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn every_sound_has_a_wav_asset() {
+    for sfx in Sfx::ALL {
+      let bytes = load_asset(&sfx.path()).unwrap();
+      assert!(bytes.starts_with(b"RIFF") && &bytes[8..12] == b"WAVE", "{:?}", sfx);
+    }
+  }
+}
@@ -0,0 +1,305 @@
+use crate::{components::*, resources::*};
+use bevy_ecs::prelude::*;
+use rand::RngCore;
+use std::{fmt, hash::Hasher, time::Duration};
+
+/// FNV-1a. Unlike `DefaultHasher` its output is pinned down, so hashes compare across runs and builds.
+struct Fnv(u64);
+
+impl Default for Fnv {
+  fn default() -> Self {
+    Fnv(0xcbf2_9ce4_8422_2325)
+  }
+}
+
+impl Hasher for Fnv {
+  fn finish(&self) -> u64 {
+    self.0
+  }
+
+  fn write(&mut self, bytes: &[u8]) {
+    for byte in bytes {
+      self.0 ^= *byte as u64;
+      self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+    }
+  }
+}
+
+fn write_floats(hasher: &mut Fnv, values: &[f32]) {
+  for value in values {
+    hasher.write_u32(value.to_bits());
+  }
+}
+
+fn write_duration(hasher: &mut Fnv, duration: Duration) {
+  hasher.write_u128(duration.as_nanos());
+}
+
+fn write_timer(hasher: &mut Fnv, timer: &Timer) {
+  write_duration(hasher, timer.elapsed);
+  write_duration(hasher, timer.duration);
+  hasher.write_u8(timer.finished as u8);
+}
+
+/// Everything a [`Snapshot`] hashes, in the order a [`Checkpoint`] keeps their digests.
+pub const PARTS: [&str; 18] = [
+  "Transform",
+  "Player",
+  "Velocity",
+  "AngularVelocity",
+  "Projectile",
+  "Lifetime",
+  "AmmoPickup",
+  "BoostPickup",
+  "AttackPickup",
+  "Attack",
+  "Enemy",
+  "Invulnerable",
+  "Boost",
+  "Time",
+  "PickupPity",
+  "Score",
+  "Ammo",
+  "Randoms",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Entry {
+  entity: Option<Entity>,
+  component: &'static str,
+  hash: u64,
+}
+
+/// Hashes of the gameplay relevant state of one tick, one per component or resource. Cosmetic state
+/// (effects, camera, shake) is left out on purpose, it is allowed to differ.
+pub struct Snapshot {
+  pub tick: u64,
+  entries: Vec<Entry>,
+}
+
+impl Snapshot {
+  pub fn capture(world: &mut World, tick: u64) -> Self {
+    let mut entries = Vec::new();
+
+    capture_component::<Transform>(world, &mut entries, "Transform", |transform, hasher| {
+      write_floats(hasher, &transform.translation().to_array());
+      write_floats(hasher, &[transform.rotation.radians(), transform.center_rotation.radians()]);
+    });
+    capture_component::<Player>(world, &mut entries, "Player", |player, hasher| {
+      write_floats(hasher, &[player.movement_speed, player.rotation_speed]);
+      if let Some(turn) = &player.brake_turn {
+        write_floats(hasher, &[turn.rotation, turn.elapsed]);
+        hasher.write_u8(turn.triggered as u8);
+      }
+    });
+    capture_component::<Velocity>(world, &mut entries, "Velocity", |velocity, hasher| match velocity {
+      Velocity::Linear(velocity) => write_floats(hasher, &velocity.to_render().to_array()),
+      Velocity::Heading(speed) => write_floats(hasher, &[*speed]),
+    });
+    capture_component::<AngularVelocity>(world, &mut entries, "AngularVelocity", |velocity, hasher| {
+      write_floats(hasher, &[velocity.0]);
+    });
+    capture_component::<Projectile>(world, &mut entries, "Projectile", |projectile, hasher| {
+      write_timer(hasher, &projectile.trail);
+    });
+    capture_component::<Lifetime>(world, &mut entries, "Lifetime", |lifetime, hasher| {
+      write_timer(hasher, &lifetime.0);
+    });
+    capture_component::<AmmoPickup>(world, &mut entries, "AmmoPickup", |pickup, hasher| {
+      write_floats(hasher, &[pickup.rotation_speed]);
+      write_timer(hasher, &pickup.timer);
+    });
+    capture_component::<BoostPickup>(world, &mut entries, "BoostPickup", |pickup, hasher| {
+      write_timer(hasher, &pickup.collected_timer);
+      write_timer(hasher, &pickup.blink_timer);
+      hasher.write_u8(pickup.visible as u8);
+    });
+    capture_component::<AttackPickup>(world, &mut entries, "AttackPickup", |pickup, hasher| {
+      hasher.write_u8(pickup.attack as u8);
+    });
+    capture_component::<Attack>(world, &mut entries, "Attack", |attack, hasher| {
+      hasher.write_u8(*attack as u8);
+    });
+    capture_component::<Enemy>(world, &mut entries, "Enemy", |enemy, hasher| {
+      hasher.write_u8(enemy.kind as u8);
+    });
+    capture_component::<Invulnerable>(world, &mut entries, "Invulnerable", |invulnerable, hasher| {
+      write_timer(hasher, &invulnerable.timer);
+    });
+    capture_component::<Boost>(world, &mut entries, "Boost", |boost, hasher| {
+      write_floats(hasher, &[boost.boost, boost.cooldown.unwrap_or(-1.0)]);
+    });
+    capture_component::<Ammo>(world, &mut entries, "Ammo", |ammo, hasher| {
+      hasher.write_u32(ammo.current);
+    });
+    entries.sort_by_key(|entry| (entry.entity, entry.component));
+
+    let mut hasher = Fnv::default();
+    let time = world.resource::<Time>();
+    write_duration(&mut hasher, **time);
+    write_floats(&mut hasher, &[time.scale(TimeDomain::Gameplay)]);
+    for (slow_motion, elapsed) in world.resource::<TimeScale>().active() {
+      write_floats(&mut hasher, &[slow_motion.factor, slow_motion.duration]);
+      write_duration(&mut hasher, *elapsed);
+    }
+    entries.push(Entry {
+      entity: None,
+      component: "Time",
+      hash: hasher.finish(),
+    });
+
+    let mut hasher = Fnv::default();
+    let pity = world.resource::<PickupPity>();
+    hasher.write_u8(pity.enabled as u8);
+    write_floats(&mut hasher, &[pity.since_ammo, pity.since_boost]);
+    hasher.write_u8(pity.boost_full as u8);
+    entries.push(Entry {
+      entity: None,
+      component: "PickupPity",
+      hash: hasher.finish(),
+    });
+
+    let mut hasher = Fnv::default();
+    hasher.write_u32(world.resource::<Score>().0);
+    entries.push(Entry {
+      entity: None,
+      component: "Score",
+      hash: hasher.finish(),
+    });
+
+    // the next draw stands in for the stream position, the clone keeps the real stream untouched
+    let mut hasher = Fnv::default();
+    hasher.write_u64(world.resource::<Randoms>().0.clone().next_u64());
+    entries.push(Entry {
+      entity: None,
+      component: "Randoms",
+      hash: hasher.finish(),
+    });
+
+    Self { tick, entries }
+  }
+
+  pub fn hash(&self) -> u64 {
+    let mut hasher = Fnv::default();
+    for entry in &self.entries {
+      hasher.write_u64(entry.entity.map_or(u64::MAX, Entity::to_bits));
+      hasher.write(entry.component.as_bytes());
+      hasher.write_u64(entry.hash);
+    }
+    hasher.finish()
+  }
+
+  /// The entries folded into one digest per part, small enough for a replay to keep one every
+  /// [`crate::environment::AUDIT_INTERVAL`] ticks.
+  pub fn checkpoint(&self) -> Checkpoint {
+    let mut hashers = PARTS.map(|_| Fnv::default());
+    for entry in &self.entries {
+      let part = PARTS.iter().position(|part| *part == entry.component).expect("component missing from PARTS");
+      hashers[part].write_u64(entry.entity.map_or(u64::MAX, Entity::to_bits));
+      hashers[part].write_u64(entry.hash);
+    }
+    Checkpoint {
+      tick: self.tick,
+      digests: hashers.map(|hasher| hasher.finish()),
+    }
+  }
+
+  /// The first entry that differs, in entity order.
+  pub fn diff(&self, other: &Snapshot) -> Option<Divergence> {
+    let mut ours = self.entries.iter();
+    let mut theirs = other.entries.iter();
+    loop {
+      match (ours.next(), theirs.next()) {
+        (None, None) => return None,
+        (a, b) if a == b => continue,
+        (a, b) => {
+          return Some(Divergence {
+            tick: self.tick,
+            ours: a.map(|entry| (entry.entity, entry.component)),
+            theirs: b.map(|entry| (entry.entity, entry.component)),
+          })
+        }
+      }
+    }
+  }
+}
+
+fn capture_component<C: Component>(
+  world: &mut World,
+  entries: &mut Vec<Entry>,
+  component: &'static str,
+  write: impl Fn(&C, &mut Fnv),
+) {
+  let mut query = world.query::<(Entity, &C)>();
+  for (entity, value) in query.iter(world) {
+    let mut hasher = Fnv::default();
+    write(value, &mut hasher);
+    entries.push(Entry {
+      entity: Some(entity),
+      component,
+      hash: hasher.finish(),
+    });
+  }
+}
+
+pub struct Divergence {
+  pub tick: u64,
+  ours: Option<(Option<Entity>, &'static str)>,
+  theirs: Option<(Option<Entity>, &'static str)>,
+}
+
+impl fmt::Display for Divergence {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fn describe(entry: &Option<(Option<Entity>, &'static str)>) -> String {
+      match entry {
+        Some((Some(entity), component)) => format!("{} on {:?}", component, entity),
+        Some((None, resource)) => format!("resource {}", resource),
+        None => String::from("nothing"),
+      }
+    }
+
+    if self.ours == self.theirs {
+      write!(f, "tick {}: {} differs", self.tick, describe(&self.ours))
+    } else {
+      write!(
+        f,
+        "tick {}: {} in one world, {} in the other",
+        self.tick,
+        describe(&self.ours),
+        describe(&self.theirs)
+      )
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+  pub tick: u64,
+  pub digests: [u64; PARTS.len()],
+}
+
+impl Checkpoint {
+  /// The parts that differ, in [`PARTS`] order.
+  pub fn diff(&self, other: &Checkpoint) -> Option<Mismatch> {
+    let parts = PARTS
+      .iter()
+      .zip(self.digests.iter().zip(&other.digests))
+      .filter(|(_, (ours, theirs))| ours != theirs)
+      .map(|(part, _)| *part)
+      .collect::<Vec<_>>();
+    (!parts.is_empty()).then_some(Mismatch { tick: self.tick, parts })
+  }
+}
+
+/// A replay that didn't play out like it was recorded.
+#[derive(Debug)]
+pub struct Mismatch {
+  pub tick: u64,
+  pub parts: Vec<&'static str>,
+}
+
+impl fmt::Display for Mismatch {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "tick {}: {} differ", self.tick, self.parts.join(", "))
+  }
+}
@@ -0,0 +1,317 @@
+use crate::{
+  environment::{
+    BOMB_DURATION, BOMB_IMPULSE, BOMB_RADIUS, BURN_DAMAGE, BURN_ZONE_DURATION, BURN_ZONE_RADIUS, CONSOLE_LOG_SHOWN,
+    SCORE_ENEMY, SCORE_ENEMY_BOMBED,
+  },
+  error::{find_asset, Error},
+  modifiers::ron_fields,
+  output_files::write_atomic,
+  practice::{Practice, PracticeCommand},
+};
+use bevy_ecs::prelude::*;
+use std::{io::Write, path::PathBuf};
+
+pub const BALANCE_FILE: &str = "balance.ron";
+
+/// Tuning values of the simulation, the defaults of environment.rs overridden by [`BALANCE_FILE`] and
+/// live from the [`Console`]. Any other than the defaults make a practice run, see
+/// [`crate::practice::practice_system`].
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+pub struct GameBalance {
+  pub score_enemy: u32,
+  pub score_enemy_bombed: u32,
+  pub bomb_radius: f32,
+  pub bomb_duration: f32,
+  pub bomb_impulse: f32,
+  pub burn_zone_radius: f32,
+  pub burn_zone_duration: f32,
+  pub burn_damage: u32,
+}
+
+impl Default for GameBalance {
+  fn default() -> Self {
+    Self {
+      score_enemy: SCORE_ENEMY,
+      score_enemy_bombed: SCORE_ENEMY_BOMBED,
+      bomb_radius: BOMB_RADIUS,
+      bomb_duration: BOMB_DURATION,
+      bomb_impulse: BOMB_IMPULSE,
+      burn_zone_radius: BURN_ZONE_RADIUS,
+      burn_zone_duration: BURN_ZONE_DURATION,
+      burn_damage: BURN_DAMAGE,
+    }
+  }
+}
+
+/// Gets at a field of [`GameBalance`] with its type.
+#[derive(Debug, Clone, Copy)]
+enum Accessor {
+  Float(fn(&mut GameBalance) -> &mut f32),
+  Count(fn(&mut GameBalance) -> &mut u32),
+}
+
+/// A field of [`GameBalance`] by name, for the console and [`BALANCE_FILE`].
+#[derive(Debug)]
+pub struct Field {
+  pub name: &'static str,
+  accessor: Accessor,
+}
+
+macro_rules! field {
+  ($name:ident: $kind:ident($ty:ty)) => {
+    Field {
+      name: stringify!($name),
+      accessor: Accessor::$kind({
+        fn access(balance: &mut GameBalance) -> &mut $ty {
+          &mut balance.$name
+        }
+        access
+      }),
+    }
+  };
+}
+
+/// Every field of [`GameBalance`], a test fails on one missing.
+pub const FIELDS: [Field; 8] = [
+  field!(score_enemy: Count(u32)),
+  field!(score_enemy_bombed: Count(u32)),
+  field!(bomb_radius: Float(f32)),
+  field!(bomb_duration: Float(f32)),
+  field!(bomb_impulse: Float(f32)),
+  field!(burn_zone_radius: Float(f32)),
+  field!(burn_zone_duration: Float(f32)),
+  field!(burn_damage: Count(u32)),
+];
+
+/// The field called `name`, dashes stand in for underscores, which the console can't type.
+pub fn field(name: &str) -> Option<&'static Field> {
+  FIELDS.iter().find(|field| field.name == name.replace('-', "_"))
+}
+
+impl Field {
+  pub fn get(&self, balance: &GameBalance) -> String {
+    let mut balance = *balance;
+    match self.accessor {
+      Accessor::Float(access) => access(&mut balance).to_string(),
+      Accessor::Count(access) => access(&mut balance).to_string(),
+    }
+  }
+
+  /// Parses `value` as the field's type, the caller validates the whole.
+  pub fn set(&self, balance: &mut GameBalance, value: &str) -> Result<(), Error> {
+    let invalid = |reason| Error::InvalidBalance { reason };
+    match self.accessor {
+      Accessor::Float(access) => *access(balance) = value.parse().map_err(|_| invalid("expected a number"))?,
+      Accessor::Count(access) => *access(balance) = value.parse().map_err(|_| invalid("expected a whole number"))?,
+    }
+    Ok(())
+  }
+}
+
+impl GameBalance {
+  /// The RON subset of [`crate::modifiers::BUILD_FILE`], with the names of [`FIELDS`] as keys. Keys left
+  /// out keep their default.
+  pub fn parse(text: &str) -> Result<Self, Error> {
+    let mut balance = Self::default();
+    for (key, value) in ron_fields(text).map_err(|reason| Error::InvalidBalance { reason })? {
+      let field = field(&key).ok_or(Error::InvalidBalance { reason: "unknown key" })?;
+      field.set(&mut balance, &value)?;
+    }
+    balance.validate()?;
+    Ok(balance)
+  }
+
+  /// Radii, durations and the impulse have to be positive.
+  pub fn validate(&self) -> Result<(), Error> {
+    let floats = [
+      self.bomb_radius,
+      self.bomb_duration,
+      self.bomb_impulse,
+      self.burn_zone_radius,
+      self.burn_zone_duration,
+    ];
+    if !floats.iter().all(|value| value.is_finite() && *value > 0.0) {
+      return Err(Error::InvalidBalance { reason: "radii, durations and impulses have to be positive" });
+    }
+    Ok(())
+  }
+
+  fn read(path: &PathBuf) -> Result<Self, Error> {
+    Self::parse(&std::fs::read_to_string(path)?)
+  }
+}
+
+/// The balance console of the pause menu. Remembers what [`BALANCE_FILE`] says, to diff against and
+/// start from, and the last lines of commands and answers. Changes go through
+/// [`Practice::dispatch`] like the panel's tools and tag the run the same way.
+#[derive(Debug, Default, Resource)]
+pub struct Console {
+  /// Where the file was found, saving writes there.
+  path: Option<PathBuf>,
+  file: GameBalance,
+  pub log: Vec<String>,
+  /// The last command asked to save, the next save writes.
+  confirm_save: bool,
+}
+
+impl Console {
+  /// Reads [`BALANCE_FILE`] if there is one, a broken file is reported and the defaults are used.
+  pub fn load() -> Self {
+    let path = find_asset(BALANCE_FILE).ok();
+    let file = match path.as_ref().map(GameBalance::read) {
+      Some(Ok(balance)) => balance,
+      Some(Err(e)) => {
+        eprintln!("{}, playing with the default balance", e);
+        GameBalance::default()
+      }
+      None => GameBalance::default(),
+    };
+    Self {
+      path,
+      file,
+      ..Default::default()
+    }
+  }
+
+  /// The balance of the file as it was last read or saved.
+  pub fn file(&self) -> GameBalance {
+    self.file
+  }
+
+  /// Runs a command line against `live`, or the balance a command before queued.
+  pub fn run(&mut self, line: &str, live: &GameBalance, practice: &mut Practice) {
+    let live = practice.balance(live);
+    self.log.push(format!("> {}", line));
+    let answer = self.answer(line, &live, practice).unwrap_or_else(|e| e.to_string());
+    self.log.extend(answer.lines().map(String::from));
+    let excess = self.log.len().saturating_sub(CONSOLE_LOG_SHOWN);
+    self.log.drain(..excess);
+  }
+
+  fn answer(&mut self, line: &str, live: &GameBalance, practice: &mut Practice) -> Result<String, Error> {
+    let confirm_save = std::mem::take(&mut self.confirm_save);
+    let unknown_field = Error::InvalidCommand { reason: "unknown field" };
+    match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+      ["balance", "reload"] => {
+        let path = find_asset(BALANCE_FILE)?;
+        self.file = GameBalance::read(&path)?;
+        self.path = Some(path);
+        practice.dispatch(PracticeCommand::Balance(self.file));
+        Ok(String::from("reloaded"))
+      }
+      ["balance", "set", name, value] => {
+        let field = field(name).ok_or(unknown_field)?;
+        let mut balance = *live;
+        field.set(&mut balance, value)?;
+        balance.validate()?;
+        practice.dispatch(PracticeCommand::Balance(balance));
+        Ok(format!("{} = {}", field.name, field.get(&balance)))
+      }
+      ["balance", "get", name] => {
+        let field = field(name).ok_or(unknown_field)?;
+        Ok(format!("{} = {}", field.name, field.get(live)))
+      }
+      ["balance", "diff"] => {
+        let changed = FIELDS.iter().filter(|field| field.get(&self.file) != field.get(live));
+        let lines = changed
+          .map(|field| format!("{} {} -> {}", field.name, field.get(&self.file), field.get(live)))
+          .collect::<Vec<_>>();
+        Ok(if lines.is_empty() { String::from("same as the file") } else { lines.join("\n") })
+      }
+      ["balance", "save"] if confirm_save => {
+        let path = match &self.path {
+          Some(path) => path.clone(),
+          None => std::env::current_dir()?.join(BALANCE_FILE),
+        };
+        write_atomic(&path, |out| {
+          writeln!(out, "GameBalance(")?;
+          FIELDS.iter().try_for_each(|field| writeln!(out, "  {}: {},", field.name, field.get(live)))?;
+          writeln!(out, ")")
+        })?;
+        self.file = *live;
+        self.path = Some(path);
+        Ok(String::from("saved"))
+      }
+      ["balance", "save"] => {
+        self.confirm_save = true;
+        Ok(format!("balance save again to overwrite {}", BALANCE_FILE))
+      }
+      _ => Err(Error::InvalidCommand { reason: "expected balance reload, set, get, diff or save" }),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn every_field_is_in_the_registry() {
+    let debug = format!("{:?}", GameBalance::default());
+    let body = debug.trim_start_matches("GameBalance {").trim_end_matches('}');
+    let names = body.split(',').map(|field| field.split(':').next().unwrap().trim()).collect::<Vec<_>>();
+    for name in &names {
+      assert!(field(name).is_some(), "{} has no entry in FIELDS", name);
+    }
+    assert_eq!(names.len(), FIELDS.len());
+
+    // and every entry reads and writes its own field
+    for (i, field) in FIELDS.iter().enumerate() {
+      let mut balance = GameBalance::default();
+      field.set(&mut balance, "7").unwrap();
+      let changed = FIELDS.iter().filter(|other| other.get(&balance) != other.get(&GameBalance::default()));
+      assert_eq!(changed.map(|other| other.name).collect::<Vec<_>>(), [field.name], "entry {}", i);
+    }
+  }
+
+  #[test]
+  fn the_console_changes_the_balance_through_practice() {
+    let (mut console, mut practice, live) = (Console::default(), Practice::default(), GameBalance::default());
+    console.run("balance set bomb-radius 200", &live, &mut practice);
+    console.run("balance set score_enemy 1.5", &live, &mut practice);
+    console.run("balance set bomb_radius -1", &live, &mut practice);
+    console.run("balance get bomb_radius", &live, &mut practice);
+    // the oldest lines make room
+    assert_eq!(
+      console.log,
+      [
+        "> balance set score_enemy 1.5",
+        "invalid balance: expected a whole number",
+        "> balance set bomb_radius -1",
+        "invalid balance: radii, durations and impulses have to be positive",
+        "> balance get bomb_radius",
+        "bomb_radius = 200",
+      ]
+    );
+    console.run("balance diff", &live, &mut practice);
+    assert_eq!(console.log.last().unwrap(), "bomb_radius 160 -> 200");
+    assert_eq!(practice.balance(&live).bomb_radius, 200.0);
+  }
+
+  #[test]
+  fn saving_asks_first_and_writes_what_parses_back() {
+    let dir = std::env::temp_dir().join(format!("bytepath-balance-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut console = Console {
+      path: Some(dir.join(BALANCE_FILE)),
+      ..Default::default()
+    };
+    let live = GameBalance {
+      burn_damage: 3,
+      ..Default::default()
+    };
+    let mut practice = Practice::default();
+    console.run("balance save", &live, &mut practice);
+    assert!(!dir.join(BALANCE_FILE).exists());
+    console.run("balance get burn_damage", &live, &mut practice);
+    console.run("balance save", &live, &mut practice);
+    assert!(!dir.join(BALANCE_FILE).exists());
+    console.run("balance save", &live, &mut practice);
+
+    let text = std::fs::read_to_string(dir.join(BALANCE_FILE)).unwrap();
+    assert_eq!(GameBalance::parse(&text).unwrap(), live);
+    assert_eq!(console.file(), live);
+    assert!(GameBalance::parse("(burn_damage: -1)").is_err());
+    assert!(GameBalance::parse("(bomb_radius: 0)").is_err());
+  }
+}
@@ -0,0 +1,238 @@
+use crate::{
+  components::{Despawning, Enemy, Hostile, Player, Transform, Velocity},
+  environment::{
+    CAMERA_EASE_RATE, CAMERA_FOLLOW_ZOOM, CAMERA_FRAME_MARGIN, CAMERA_PLAYER_BIAS, SCREEN_HEIGHT, SCREEN_WIDTH,
+    THREAT_LOOKAHEAD_SPEED, THREAT_RANGE,
+  },
+  resources::{Time, WorldBounds},
+};
+use bevy_ecs::prelude::*;
+
+/// How the camera moves during a run, see [`crate::settings::Settings::camera`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CameraMode {
+  /// The whole playfield, never moving but for shakes and punches.
+  #[default]
+  Fixed,
+  /// Zoomed in on the player, easing out to keep the nearest threat in view too.
+  Smart,
+}
+
+/// Where the camera looks and how far it's zoomed in, before any shake or zoom punch. Eased towards
+/// the framing [`camera_follow_system`] solves for every tick.
+#[derive(Debug, Resource)]
+pub struct CameraFollow {
+  pub mode: CameraMode,
+  pub center: glam::Vec2,
+  pub zoom: f32,
+}
+
+impl CameraFollow {
+  pub fn new(mode: CameraMode, bounds: &WorldBounds) -> Self {
+    Self {
+      mode,
+      center: bounds.center(),
+      zoom: 1.0,
+    }
+  }
+
+  /// What [`crate::resources::Camera::camera_pos`] is without a shake, the render zooms around the
+  /// middle of the screen.
+  pub fn offset(&self) -> glam::Vec2 {
+    self.center - glam::vec2(SCREEN_WIDTH as f32, SCREEN_HEIGHT as f32) / 2.0
+  }
+}
+
+/// Something the player should see coming, relative to nothing, in world units and per second.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Threat {
+  pub position: glam::Vec2,
+  pub velocity: glam::Vec2,
+}
+
+/// The most relevant of `threats` for a player at `position` moving by `velocity`, none within
+/// [`THREAT_RANGE`]. A threat closing in counts as near as it gets in the time to impact at
+/// [`THREAT_LOOKAHEAD_SPEED`], so a fast projectile beats a closer rock drifting by. Behind or ahead
+/// makes no difference.
+pub fn select_threat(position: glam::Vec2, velocity: glam::Vec2, threats: &[Threat]) -> Option<usize> {
+  let relevance = |threat: &Threat| {
+    let (offset, closing) = (threat.position - position, velocity - threat.velocity);
+    let distance = offset.length();
+    let speed = if distance > f32::EPSILON { closing.dot(offset) / distance } else { 0.0 };
+    if speed <= 0.0 {
+      return distance;
+    }
+    distance.min(distance / speed * THREAT_LOOKAHEAD_SPEED)
+  };
+  threats
+    .iter()
+    .enumerate()
+    .filter(|(_, threat)| threat.position.distance(position) <= THREAT_RANGE)
+    .map(|(i, threat)| (i, relevance(threat)))
+    .min_by(|(_, a), (_, b)| a.total_cmp(b))
+    .map(|(i, _)| i)
+}
+
+/// The center and zoom that show `player` and `threat` with [`CAMERA_FRAME_MARGIN`] around them, the
+/// center [`CAMERA_PLAYER_BIAS`] of the way towards the player. The zoom stays within `zoom_range`
+/// and the view within `bounds`, a view larger than the bounds is centered on them.
+pub fn frame(
+  player: glam::Vec2,
+  threat: Option<glam::Vec2>,
+  viewport: glam::Vec2,
+  bounds: &WorldBounds,
+  zoom_range: (f32, f32),
+) -> (glam::Vec2, f32) {
+  let center = threat.map_or(player, |threat| player.lerp(threat, 1.0 - CAMERA_PLAYER_BIAS));
+  let reach = threat.map_or(glam::Vec2::ZERO, |threat| {
+    (player - center).abs().max((threat - center).abs())
+  }) + glam::Vec2::splat(CAMERA_FRAME_MARGIN);
+  let fits = viewport / 2.0 / reach;
+  let zoom = fits.x.min(fits.y).clamp(zoom_range.0, zoom_range.1);
+
+  let half = viewport / 2.0 / zoom;
+  let clamp_axis = |center: f32, half: f32, min: f32, max: f32| {
+    if max - min <= half * 2.0 {
+      (min + max) / 2.0
+    } else {
+      center.clamp(min + half, max - half)
+    }
+  };
+  let center = glam::vec2(
+    clamp_axis(center.x, half.x, bounds.min.x, bounds.max.x),
+    clamp_axis(center.y, half.y, bounds.min.y, bounds.max.y),
+  );
+  (center, zoom)
+}
+
+fn world_velocity(velocity: &Velocity, transform: &Transform) -> glam::Vec2 {
+  match velocity {
+    Velocity::Linear(velocity) => velocity.to_render(),
+    Velocity::Heading(speed) => transform.rotation.forward_by(*speed).to_render(),
+  }
+}
+
+/// Eases the [`CameraFollow`] towards the framing of the players and their nearest threat, in raw time
+/// like the shake. Once every player is gone it eases out to the whole playfield, under the death's
+/// zoom punch and slow motion. The fixed camera shows the whole playfield all the time.
+pub fn camera_follow_system(
+  mut follow: ResMut<CameraFollow>,
+  players: Query<(&Transform, &Velocity), (With<Player>, Without<Despawning>)>,
+  threats: Query<(&Transform, &Velocity), (Or<(With<Enemy>, With<Hostile>)>, Without<Despawning>)>,
+  bounds: Res<WorldBounds>,
+  time: Res<Time>,
+) {
+  let viewport = glam::vec2(SCREEN_WIDTH as f32, SCREEN_HEIGHT as f32);
+  let count = players.iter().count();
+  let (center, zoom) = match (follow.mode, count) {
+    (CameraMode::Smart, 1..) => {
+      let position = players.iter().map(|(transform, _)| transform.position.to_render()).sum::<glam::Vec2>();
+      let velocity = players.iter().map(|(transform, velocity)| world_velocity(velocity, transform));
+      let (position, velocity) = (position / count as f32, velocity.sum::<glam::Vec2>() / count as f32);
+      let candidates = threats
+        .iter()
+        .map(|(transform, velocity)| Threat {
+          position: transform.position.to_render(),
+          velocity: world_velocity(velocity, transform),
+        })
+        .collect::<Vec<_>>();
+      let threat = select_threat(position, velocity, &candidates).map(|i| candidates[i].position);
+      frame(position, threat, viewport, &bounds, (1.0, CAMERA_FOLLOW_ZOOM))
+    }
+    _ => (bounds.center(), 1.0),
+  };
+
+  let t = 1.0 - (-CAMERA_EASE_RATE * time.raw().as_secs_f32()).exp();
+  follow.center = follow.center.lerp(center, t);
+  follow.zoom += (zoom - follow.zoom) * t;
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const VIEWPORT: glam::Vec2 = glam::vec2(480.0, 270.0);
+  const ZOOMS: (f32, f32) = (1.0, CAMERA_FOLLOW_ZOOM);
+
+  fn bounds() -> WorldBounds {
+    WorldBounds {
+      min: glam::Vec2::ZERO,
+      max: VIEWPORT,
+    }
+  }
+
+  fn still(x: f32, y: f32) -> Threat {
+    Threat {
+      position: glam::vec2(x, y),
+      velocity: glam::Vec2::ZERO,
+    }
+  }
+
+  /// Whether `point` is on screen with the camera at `center` and `zoom`.
+  fn in_view(point: glam::Vec2, (center, zoom): (glam::Vec2, f32)) -> bool {
+    ((point - center).abs() * zoom).cmple(VIEWPORT / 2.0).all()
+  }
+
+  fn within_bounds((center, zoom): (glam::Vec2, f32)) -> bool {
+    let half = VIEWPORT / 2.0 / zoom;
+    (center - half).cmpge(bounds().min - 1e-3).all() && (center + half).cmple(bounds().max + 1e-3).all()
+  }
+
+  #[test]
+  fn the_nearest_threat_counts_unless_another_closes_in_faster() {
+    let player = glam::vec2(240.0, 135.0);
+    assert_eq!(select_threat(player, glam::Vec2::ZERO, &[]), None);
+    assert_eq!(select_threat(player, glam::Vec2::ZERO, &[still(240.0 + THREAT_RANGE + 1.0, 135.0)]), None);
+
+    // behind a player flying right is as near as ahead
+    let threats = [still(300.0, 135.0), still(200.0, 135.0)];
+    assert_eq!(select_threat(player, glam::vec2(80.0, 0.0), &threats), Some(1));
+
+    // a projectile coming in fast beats a rock drifting by closer
+    let projectile = Threat {
+      position: glam::vec2(240.0, 135.0 + 90.0),
+      velocity: glam::vec2(0.0, -400.0),
+    };
+    let rock = Threat {
+      position: glam::vec2(240.0 + 50.0, 135.0),
+      velocity: glam::vec2(0.0, 30.0),
+    };
+    assert_eq!(select_threat(player, glam::Vec2::ZERO, &[rock, projectile]), Some(1));
+  }
+
+  #[test]
+  fn without_a_threat_the_player_is_centered_at_the_closest_zoom() {
+    let player = glam::vec2(240.0, 135.0);
+    assert_eq!(frame(player, None, VIEWPORT, &bounds(), ZOOMS), (player, CAMERA_FOLLOW_ZOOM));
+
+    // near an edge the view stops at the bounds
+    let framing = frame(glam::vec2(10.0, 10.0), None, VIEWPORT, &bounds(), ZOOMS);
+    assert!(within_bounds(framing));
+    assert!(in_view(glam::vec2(10.0, 10.0), framing));
+  }
+
+  #[test]
+  fn a_threat_stays_in_view_with_the_player() {
+    let player = glam::vec2(240.0, 135.0);
+    let threats = [
+      glam::vec2(260.0, 140.0),
+      // behind, at the edge of the screen and in the corner
+      glam::vec2(100.0, 135.0),
+      glam::vec2(480.0, 135.0),
+      glam::vec2(0.0, 270.0),
+    ];
+    for threat in threats {
+      let framing = frame(player, Some(threat), VIEWPORT, &bounds(), ZOOMS);
+      assert!(in_view(player, framing) && in_view(threat, framing), "{:?}", threat);
+      assert!(within_bounds(framing), "{:?}", threat);
+      assert!((1.0..=CAMERA_FOLLOW_ZOOM).contains(&framing.1));
+    }
+
+    // a close one keeps the zoom in, the center leans towards the player
+    let (center, zoom) = frame(player, Some(threats[0]), VIEWPORT, &bounds(), ZOOMS);
+    assert_eq!(zoom, CAMERA_FOLLOW_ZOOM);
+    assert!(center.distance(player) < center.distance(threats[0]));
+    // one in the corner zooms about all the way out
+    assert!(frame(player, Some(threats[3]), VIEWPORT, &bounds(), ZOOMS).1 < 1.01);
+  }
+}
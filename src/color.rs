@@ -1,4 +1,4 @@
-#[derive(Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct ColorGl {
   pub r: f32,
   pub g: f32,
@@ -7,6 +7,24 @@ pub struct ColorGl {
 }
 
 impl ColorGl {
+  pub fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+    Self { r, g, b, a }
+  }
+
+  pub fn with_alpha(self, a: f32) -> Self {
+    Self { a, ..self }
+  }
+
+  /// `self` at `t` = 0 to `other` at `t` = 1, per channel and unclamped.
+  pub fn lerp(self, other: ColorGl, t: f32) -> Self {
+    Self {
+      r: self.r + (other.r - self.r) * t,
+      g: self.g + (other.g - self.g) * t,
+      b: self.b + (other.b - self.b) * t,
+      a: self.a + (other.a - self.a) * t,
+    }
+  }
+
   pub fn to_array(self) -> [f32; 4] {
     [self.r, self.g, self.b, self.a]
   }
@@ -14,11 +32,49 @@ impl ColorGl {
 
 impl From<(u8, u8, u8)> for ColorGl {
   fn from((r, g, b): (u8, u8, u8)) -> ColorGl {
-    ColorGl {
-      r: r as f32 / 255.0,
-      g: g as f32 / 255.0,
-      b: b as f32 / 255.0,
-      a: 1.0,
+    ColorGl::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, 1.0)
+  }
+}
+
+impl From<(u8, u8, u8, u8)> for ColorGl {
+  fn from((r, g, b, a): (u8, u8, u8, u8)) -> ColorGl {
+    ColorGl::from((r, g, b)).with_alpha(a as f32 / 255.0)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Back to bytes the way a framebuffer stores them.
+  fn to_bytes(color: ColorGl) -> [u8; 4] {
+    color.to_array().map(|channel| (channel * 255.0).round() as u8)
+  }
+
+  #[test]
+  fn bytes_survive_the_trip_through_floats() {
+    for value in 0..=255u8 {
+      let rgba = (value, 255 - value, value / 2, value);
+      assert_eq!(to_bytes(ColorGl::from(rgba)), [rgba.0, rgba.1, rgba.2, rgba.3]);
     }
+    // without an alpha it is opaque
+    assert_eq!(ColorGl::from((255, 0, 128)), ColorGl::from((255, 0, 128, 255)));
+    assert_eq!(ColorGl::from((0, 0, 0)).to_array(), [0.0, 0.0, 0.0, 1.0]);
+  }
+
+  #[test]
+  fn to_array_keeps_the_channel_order() {
+    assert_eq!(ColorGl::new(0.1, 0.2, 0.3, 0.4).to_array(), [0.1, 0.2, 0.3, 0.4]);
+    assert_eq!(ColorGl::new(0.1, 0.2, 0.3, 0.4).with_alpha(1.0), ColorGl::new(0.1, 0.2, 0.3, 1.0));
+  }
+
+  #[test]
+  fn lerp_hits_both_ends_and_extrapolates_past_them() {
+    let (black, white) = (ColorGl::new(0.0, 0.0, 0.0, 0.0), ColorGl::new(1.0, 1.0, 1.0, 1.0));
+    assert_eq!(black.lerp(white, 0.0), black);
+    assert_eq!(black.lerp(white, 1.0), white);
+    assert_eq!(black.lerp(white, 0.25), ColorGl::new(0.25, 0.25, 0.25, 0.25));
+    assert_eq!(black.lerp(white, 2.0), ColorGl::new(2.0, 2.0, 2.0, 2.0));
+    assert_eq!(white.lerp(black, -1.0), ColorGl::new(2.0, 2.0, 2.0, 2.0));
   }
 }
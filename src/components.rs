@@ -1,15 +1,246 @@
-use crate::{color::ColorGl, easings::EasingFunction, Timer};
+use crate::{
+  color::ColorGl,
+  easings::Easing,
+  environment::{
+    ATTACK_SPREAD_ANGLE, BOOST_PICKUP_BLINK_DELAY, BOOST_PICKUP_BLINK_INTERVAL, BOOST_PICKUP_COLLECT_DURATION,
+    DEAD_PROJECTILE_HEIGHT, DEAD_PROJECTILE_WIDTH, INTERPOLATION_SNAP_DISTANCE, INVULNERABLE_BLINK_INTERVAL,
+    PLAYER_COLORS, PROJECTILE_INTERVAL, PROJECTILE_RADIUS, PROJECTILE_TRAIL_INTERVAL, RAPID_PROJECTILE_INTERVAL,
+    Z_BIAS_BAND, Z_BIAS_STEP,
+  },
+  resources::Timer,
+  units::{Angle, WorldPos, WorldVec},
+};
 use bevy_ecs::prelude::*;
+use std::{
+  f32::consts::{PI, TAU},
+  time::Duration,
+};
 
 #[derive(Component, Debug)]
 pub struct Player {
   pub movement_speed: f32,
   pub rotation_speed: f32,
+  pub brake_turn: Option<BrakeTurn>,
+  pub bounds_mode: BoundsMode,
+  /// Held at the edge by [`BoundsMode::Clamp`] last tick, it only hits the wall once.
+  pub against_wall: bool,
 }
 
+impl Player {
+  pub const SHAPE: Shape = Shape::Circle {
+    radius: 12.0,
+    stroke: 1.5,
+  };
+}
+
+/// Which player a ship belongs to, 0 for the first. Picks its key bindings and appearance.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PlayerId(pub u8);
+
+/// The color of the ship and its effects, [`PLAYER_COLORS`] by [`PlayerId`].
+#[derive(Component, Debug, Clone, Copy)]
+pub struct PlayerAppearance {
+  pub color: ColorGl,
+}
+
+impl PlayerAppearance {
+  pub fn of(id: PlayerId) -> Self {
+    Self {
+      color: ColorGl::from(PLAYER_COLORS[id.0 as usize % PLAYER_COLORS.len()]),
+    }
+  }
+}
+
+/// What happens to the player at the edges of the playfield. Also a resource, the mode the next
+/// player spawns with, picked with `--bounds`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Resource)]
+pub enum BoundsMode {
+  /// Leaves on one side and comes back on the other, once the whole ship is out.
+  #[default]
+  Wrap,
+  /// Stops with the ship touching the edge.
+  Clamp,
+  /// Nothing holds it back.
+  Free,
+}
+
+/// How far the player turned since braking started, for the handbrake turn.
+#[derive(Debug, Default)]
+pub struct BrakeTurn {
+  pub rotation: f32,
+  pub elapsed: f32,
+  pub triggered: bool,
+}
+
+/// Where an entity moved from during the current tick, contact tests sweep from here to its
+/// translation so a fast mover can't skip over what it passed.
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct Sweep {
+  pub from: WorldPos,
+}
+
+/// A circle around the translation. Only entities with one take part in collision tests, see
+/// [`crate::systems::collision_system`].
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Collider {
+  pub radius: f32,
+}
+
+/// How an entity moves, `movement_system` integrates it into the [`Transform`].
+#[derive(Component, Debug, Clone, Copy)]
+pub enum Velocity {
+  /// World units per second.
+  Linear(WorldVec),
+  /// World units per second along the rotation, turning the entity turns where it goes.
+  Heading(f32),
+}
+
+impl Velocity {
+  pub fn linear(self, rotation: Angle) -> WorldVec {
+    match self {
+      Velocity::Linear(velocity) => velocity,
+      Velocity::Heading(speed) => rotation.forward_by(speed),
+    }
+  }
+
+  /// Turns a linear velocity along with the entity, a heading follows its rotation anyway.
+  pub fn turn(&mut self, angle: Angle) {
+    if let Velocity::Linear(velocity) = self {
+      *velocity = angle.rotate(*velocity);
+    }
+  }
+}
+
+/// Radians per second added to [`Transform::center_rotation`] by `movement_system`.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct AngularVelocity(pub f32);
+
+/// A projectile fired at the players rather than by them, it kills a player it touches, see
+/// [`crate::systems::hostile_projectile_system`]. Nothing fires any yet, a bomb clears them, see
+/// [`crate::systems::shockwave_system`].
+#[derive(Component, Debug)]
+pub struct Hostile;
+
 #[derive(Component, Debug)]
 pub struct Projectile {
-  pub movement_speed: f32,
+  /// Drops a trail puff every time it finishes.
+  pub trail: Timer,
+}
+
+impl Projectile {
+  pub fn bundle(velocity: WorldVec) -> (Self, Velocity, Shape, Collider, ScopeTag) {
+    (
+      Self {
+        trail: Timer::from_seconds(PROJECTILE_TRAIL_INTERVAL, true),
+      },
+      Velocity::Linear(velocity),
+      Self::SHAPE,
+      Collider {
+        radius: PROJECTILE_RADIUS,
+      },
+      ScopeTag(GameScope::Run),
+    )
+  }
+
+  pub const SHAPE: Shape = Shape::Circle {
+    radius: PROJECTILE_RADIUS,
+    stroke: 1.0,
+  };
+}
+
+/// What the draw stage tessellates for an entity, in the space of its [`Transform`]. Simulation
+/// systems may change it, the draw systems only read it.
+#[derive(Component, Debug, Clone, Copy)]
+pub enum Shape {
+  /// Outlined, `stroke` is the line width.
+  Circle { radius: f32, stroke: f32 },
+  /// Spans from the origin to `size`.
+  Rect { size: glam::Vec2, fill: bool },
+  /// Runs from the origin along the local y axis.
+  Line { length: f32, width: f32 },
+}
+
+/// Lasers pass through the player while it lasts, removed once the timer finishes.
+#[derive(Component, Debug)]
+pub struct Invulnerable {
+  pub timer: Timer,
+}
+
+impl Invulnerable {
+  /// Hidden every other [`INVULNERABLE_BLINK_INTERVAL`].
+  pub fn visible(&self) -> bool {
+    ((self.timer.elapsed.as_secs_f32() / INVULNERABLE_BLINK_INTERVAL) as u32).is_multiple_of(2)
+  }
+}
+
+/// Steers a projectile towards the nearest enemy or pickup.
+#[derive(Component, Debug)]
+pub struct Homing;
+
+/// Ends a projectile through its [`DeathAction`] once it runs out, without one it flies until it leaves
+/// the playfield or hits something.
+#[derive(Component, Debug)]
+pub struct Lifetime(pub Timer);
+
+/// How the player shoots. Granted by attack pickups, lost once the ammo runs out.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Attack {
+  #[default]
+  Neutral,
+  Double,
+  Triple,
+  Rapid,
+  Homing,
+}
+
+impl Attack {
+  pub const ALL: [Attack; 5] = [Attack::Neutral, Attack::Double, Attack::Triple, Attack::Rapid, Attack::Homing];
+  /// What attack pickups hand out.
+  pub const PICKUPS: [Attack; 4] = [Attack::Double, Attack::Triple, Attack::Rapid, Attack::Homing];
+
+  /// Degrees off the ship's heading, one shot each.
+  pub fn angles(self) -> &'static [f32] {
+    match self {
+      Attack::Double => &[-ATTACK_SPREAD_ANGLE / 2.0, ATTACK_SPREAD_ANGLE / 2.0],
+      Attack::Triple => &[-ATTACK_SPREAD_ANGLE, 0.0, ATTACK_SPREAD_ANGLE],
+      Attack::Neutral | Attack::Rapid | Attack::Homing => &[0.0],
+    }
+  }
+
+  /// Seconds between shots.
+  /// Homing shots fizzle out, the others splat.
+  pub fn death_action(self) -> DeathAction {
+    match self {
+      Attack::Homing => DeathAction::Fizzle,
+      _ => DeathAction::Splat,
+    }
+  }
+
+  pub fn interval(self) -> f32 {
+    match self {
+      Attack::Rapid => RAPID_PROJECTILE_INTERVAL,
+      _ => PROJECTILE_INTERVAL,
+    }
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Attack::Neutral => "Neutral",
+      Attack::Double => "Double",
+      Attack::Triple => "Triple",
+      Attack::Rapid => "Rapid",
+      Attack::Homing => "Homing",
+    }
+  }
+}
+
+/// What a projectile leaves behind once it is removed.
+#[derive(Component, Debug, Copy, Clone, PartialEq)]
+pub enum DeathAction {
+  Splat,
+  Fizzle,
+  RadialBurst { count: usize, speed: f32 },
+  Nothing,
 }
 
 #[derive(Component, Debug)]
@@ -17,72 +248,284 @@ pub struct DeadProjectile {
   pub timer: Timer,
 }
 
+impl DeadProjectile {
+  pub const SHAPE: Shape = Shape::Rect {
+    size: glam::Vec2::new(DEAD_PROJECTILE_WIDTH, DEAD_PROJECTILE_HEIGHT),
+    fill: true,
+  };
+}
+
 #[derive(Component)]
 pub struct ExplosionEffect {
   pub color: ColorGl,
+  /// Fades out over the interpolation instead of staying opaque until it ends.
+  pub fade: bool,
+}
+
+impl ExplosionEffect {
+  /// `interpolation` tracks speed, length and width, `explosion_system` keeps the line in step with it.
+  pub fn bundle(color: ColorGl, interpolation: Interpolation) -> (Self, Shape, Velocity, Interpolation) {
+    (
+      Self { color, fade: true },
+      Shape::Line {
+        length: 0.0,
+        width: 0.0,
+      },
+      Velocity::Heading(0.0),
+      interpolation,
+    )
+  }
+}
+
+/// How long an entity lives, see [`crate::resources::SCOPES_KEPT`]. Every spawn sets one.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScopeTag(pub GameScope);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameScope {
+  /// The playfield of a run, the players, what they fight and collect, cleared when the next run starts.
+  Run,
+  /// Effects, notices and pooled particles, they end on their own.
+  Persistent,
 }
 
+/// Flashes over the player it belongs to.
 #[derive(Component)]
-pub struct TickEffect;
+pub struct TickEffect(pub Entity);
+
+/// Pooled particles are released instead of despawned, see [`crate::resources::ParticlePool`].
+#[derive(Component, Debug)]
+pub struct Active(pub bool);
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LaserPhase {
+  Telegraph,
+  Firing,
+  Fade,
+}
+
+#[derive(Component, Debug)]
+pub struct LaserHazard {
+  pub phase: LaserPhase,
+  pub timer: Timer,
+  pub start: WorldPos,
+  pub end: WorldPos,
+}
+
+/// Puffed by this player or projectile, a boosting player turns its puffs the boost color.
 #[derive(Component)]
-pub struct TrailEffect;
+pub struct TrailEffect(pub Entity);
+
+/// Spawn order of an effect, overlapping effects of the same layer are stacked by it so they don't
+/// swap places from frame to frame.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SpawnSeq(pub u64);
+
+impl SpawnSeq {
+  /// Added to the layer's z, later effects are drawn on top. Repeats every [`Z_BIAS_BAND`] effects
+  /// and stays below half the distance between two layers.
+  pub fn z_bias(self) -> f32 {
+    (self.0 % Z_BIAS_BAND) as f32 * Z_BIAS_STEP
+  }
+}
 
 #[derive(Component, Debug, Default, Copy, Clone)]
 pub struct Transform {
-  pub rotation: glam::Quat,
-  pub translation: glam::Vec3,
-  pub center_rotation: glam::Quat,
+  pub rotation: Angle,
+  pub position: WorldPos,
+  /// Draw order, never part of gameplay.
+  pub z: f32,
+  pub center_rotation: Angle,
 }
 
 impl Transform {
+  pub fn translation(&self) -> glam::Vec3 {
+    self.position.to_render().extend(self.z)
+  }
+
   pub fn mat4(&self) -> glam::Mat4 {
-    glam::Mat4::from_rotation_translation(self.rotation, self.translation)
+    glam::Mat4::from_rotation_translation(self.rotation.to_render(), self.translation())
   }
 
   pub fn mat4_center(&self) -> glam::Mat4 {
-    glam::Mat4::from_rotation_translation(self.center_rotation, self.translation)
+    glam::Mat4::from_rotation_translation(self.center_rotation.to_render(), self.translation())
   }
+
+  /// `alpha` of the way from `previous` to this one. Both rotations turn the short way round, the
+  /// same as a slerp about the z axis. Further than [`INTERPOLATION_SNAP_DISTANCE`] apart it snaps.
+  pub fn lerp_from(&self, previous: &Transform, alpha: f32) -> Transform {
+    let (from, to) = (previous.position.to_render(), self.position.to_render());
+    if from.distance_squared(to) > INTERPOLATION_SNAP_DISTANCE * INTERPOLATION_SNAP_DISTANCE {
+      return *self;
+    }
+    let turn = |from: Angle, to: Angle| {
+      let delta = (to.radians() - from.radians() + PI).rem_euclid(TAU) - PI;
+      Angle::from_radians(from.radians() + delta * alpha)
+    };
+
+    Transform {
+      rotation: turn(previous.rotation, self.rotation),
+      position: WorldPos::from(from.lerp(to, alpha)),
+      z: self.z,
+      center_rotation: turn(previous.center_rotation, self.center_rotation),
+    }
+  }
+}
+
+/// The [`Transform`] as of the end of the tick before, for drawing between two ticks. Entities get it
+/// one tick after they spawn and are drawn where they are until then.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct PreviousTransform(pub Transform);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterpolationMode {
+  Once,
+  Loop,
+  /// Begin to end and back, a cycle takes twice the duration.
+  PingPong,
 }
 
 #[derive(Component, Debug)]
 pub struct Interpolation {
   time: f32,
   duration: f32,
+  delay: f32,
   begin_end: Vec<(f32, f32)>,
-  repeating: bool,
+  mode: InterpolationMode,
+  /// What the system evaluating it last asked for, none before the first [`Self::eval`].
+  easing: Option<Easing>,
+  /// Eases with this instead, set by the [`crate::inspector::Inspector`].
+  easing_override: Option<Easing>,
 }
 
 impl Interpolation {
-  pub fn new(begin_end: Vec<(f32, f32)>, duration: f32, repeating: bool) -> Self {
+  const MIN_DURATION: f32 = 1e-3;
+
+  /// Durations below [`Self::MIN_DURATION`] are raised to it, a zero duration would divide by zero.
+  /// Like a bad delta in [`Self::eval`] it is logged in debug builds rather than asserted, a tween too
+  /// short to see isn't worth a crash.
+  pub fn new(begin_end: Vec<(f32, f32)>, duration: f32, mode: InterpolationMode) -> Self {
+    if cfg!(debug_assertions) && (duration.is_nan() || duration <= 0.0) {
+      eprintln!("Interpolation with duration {}, raising it to {}", duration, Self::MIN_DURATION);
+    }
     Interpolation {
       time: 0.0,
-      duration,
+      duration: duration.max(Self::MIN_DURATION),
+      delay: 0.0,
       begin_end,
-      repeating,
+      mode,
+      easing: None,
+      easing_override: None,
     }
   }
 
-  pub fn eval(&mut self, t: f32, easing_fn: EasingFunction) -> (Vec<f32>, bool) {
-    self.time += t;
-    let mut finished = false;
-    if self.time >= self.duration {
-      if self.repeating {
-        self.time = 0.0;
+  /// Holds the begin values for `delay` seconds before the first cycle, later cycles start right away.
+  pub fn with_delay(self, delay: f32) -> Self {
+    Self {
+      delay: delay.max(0.0),
+      ..self
+    }
+  }
+
+  /// How far a [`InterpolationMode::Once`] interpolation got, from 0 to 1 before easing. The delay
+  /// counts as 0.
+  pub fn progress(&self) -> f32 {
+    ((self.time - self.delay) / self.duration).clamp(0.0, 1.0)
+  }
+
+  /// Where in the cycle the last [`Self::eval`] left it, from 0 to 1 before easing. Ping pong goes
+  /// back to 0 in the second half of the cycle.
+  pub fn phase(&self) -> f32 {
+    let local = (self.time - self.delay).max(0.0);
+    let phase = match self.mode {
+      InterpolationMode::PingPong if local > self.duration => 2.0 - local / self.duration,
+      _ => local / self.duration,
+    };
+    phase.clamp(0.0, 1.0)
+  }
+
+  /// The easing it's evaluated with, the override if there is one.
+  pub fn easing(&self) -> Option<Easing> {
+    self.easing_override.or(self.easing)
+  }
+
+  pub fn easing_override(&self) -> Option<Easing> {
+    self.easing_override
+  }
+
+  /// Eases with `easing` instead of what the systems ask for, none goes back to theirs.
+  pub fn override_easing(&mut self, easing: Option<Easing>) {
+    self.easing_override = easing;
+  }
+
+  /// Advances by `t` and returns one eased value per track. Without tracks it is finished right away,
+  /// a NaN or infinite `t` counts as zero. Once finishes when delay and duration have passed and stays
+  /// at the end values, the repeating modes report finished on every tick a cycle completes and carry
+  /// the overshoot into the next one.
+  pub fn eval(&mut self, t: f32, easing: Easing) -> (Vec<f32>, bool) {
+    self.easing = Some(easing);
+    if self.begin_end.is_empty() {
+      return (Vec::new(), true);
+    }
+    if !t.is_finite() {
+      if cfg!(debug_assertions) {
+        eprintln!("Interpolation advanced by {}, ignoring it", t);
       }
-      finished = true;
+    } else {
+      self.time += t.max(0.0);
+    }
+    let cycle = match self.mode {
+      InterpolationMode::Once | InterpolationMode::Loop => self.duration,
+      InterpolationMode::PingPong => 2.0 * self.duration,
+    };
+    let local = (self.time - self.delay).max(0.0);
+    let finished = local >= cycle;
+    if finished && self.mode != InterpolationMode::Once {
+      self.time = self.delay + local % cycle;
+    }
+    (self.values(easing), finished)
+  }
+
+  /// One eased value per track where the last [`Interpolation::eval`] left them, for the draw systems.
+  pub fn values(&self, easing: Easing) -> Vec<f32> {
+    let easing = self.easing_override.unwrap_or(easing).function()(self.phase());
+    self
+      .begin_end
+      .iter()
+      .map(|&(begin, end)| (1.0 - easing) * begin + easing * end)
+      .collect()
+  }
+}
+
+/// A player's shots left, spent by the projectile spawner and refilled by the ammo pickups it collects.
+#[derive(Component, Debug)]
+pub struct Ammo {
+  pub current: u32,
+  pub max: u32,
+}
+
+impl Ammo {
+  /// Full, see [`crate::modifiers::Modifiers::ammo_max`].
+  pub fn new(max: u32) -> Self {
+    Self { current: max, max }
+  }
+
+  /// Spends `amount` if there is that much left, otherwise spends nothing and returns false.
+  pub fn consume(&mut self, amount: u32) -> bool {
+    match self.current.checked_sub(amount) {
+      Some(left) => {
+        self.current = left;
+        true
+      }
+      None => false,
     }
-    (
-      self
-        .begin_end
-        .iter()
-        .map(|&(begin, end)| {
-          let easing = (easing_fn)(self.time / self.duration);
-          (1.0 - easing) * begin + easing * end
-        })
-        .collect(),
-      finished,
-    )
+  }
+
+  /// Adds `amount` up to the maximum, returns whether anything changed.
+  pub fn add(&mut self, amount: u32) -> bool {
+    let before = self.current;
+    self.current = self.current.saturating_add(amount).min(self.max);
+    self.current != before
   }
 }
 
@@ -96,13 +539,28 @@ pub struct Boost {
   pub cooldown_sec: Option<f32>,
 }
 
+/// Boosting or braking drains it, running dry locks it for `cooldown_sec`. It regenerates all the
+/// time, so it is back above zero once the cooldown is over and only draining can lock it again.
 impl Boost {
-  pub fn is_empty(&self) -> bool {
-    self.boost < 0.0
+  /// Spends `dt` seconds worth of boost, never below zero. Reaching zero starts the cooldown.
+  pub fn drain(&mut self, dt: f32) {
+    self.boost = (self.boost - self.dec_amount * dt).max(0.0);
+    if self.boost == 0.0 && self.cooldown.is_none() {
+      self.cooldown = self.cooldown_sec;
+    }
+  }
+
+  pub fn regen(&mut self, dt: f32) {
+    self.boost = self.max_boost.min(self.boost + self.inc_amount * dt);
   }
 
-  pub fn no_cooldown(&self) -> bool {
-    self.cooldown.is_none()
+  pub fn update_cooldown(&mut self, dt: f32) {
+    if let Some(cooldown) = self.cooldown.take() {
+      let cooldown = cooldown - dt;
+      if cooldown > 0.0 {
+        self.cooldown.replace(cooldown);
+      }
+    }
   }
 
   pub fn can_boost(&self) -> bool {
@@ -125,23 +583,344 @@ impl Default for Boost {
 
 #[derive(Component, Debug)]
 pub struct AmmoPickup {
-  pub movement_speed: f32,
   pub rotation_speed: f32,
-  pub center_rotation_speed: f32,
   pub timer: Timer,
 }
 
+/// Once collected it keeps drifting while it pulses and blinks, and shatters when
+/// [`BoostPickup::collected_timer`] runs out.
 #[derive(Component, Debug)]
 pub struct BoostPickup {
-  pub movement_speed: f32,
-  pub movement_direction: f32,
-  pub center_rotation_speed: f32,
-  pub timer: Timer,
+  pub collected_timer: Timer,
+  pub blink_timer: Timer,
   pub visible: bool,
 }
 
+impl BoostPickup {
+  /// Blinks after [`BOOST_PICKUP_BLINK_DELAY`] of being collected, until it shatters.
+  pub fn is_blinking(&self) -> bool {
+    self.collected_timer.elapsed >= Duration::from_secs_f32(BOOST_PICKUP_BLINK_DELAY)
+  }
+}
+
+impl Default for BoostPickup {
+  fn default() -> Self {
+    Self {
+      collected_timer: Timer::from_seconds(BOOST_PICKUP_COLLECT_DURATION, false),
+      blink_timer: Timer::from_seconds(BOOST_PICKUP_BLINK_INTERVAL, true),
+      visible: true,
+    }
+  }
+}
+
+/// Drifts across the playfield like a boost pickup and hands out its attack.
+#[derive(Component, Debug)]
+pub struct AttackPickup {
+  pub attack: Attack,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnemyKind {
+  /// Drifts across the playfield, dies to a single projectile.
+  Rock,
+}
+
+impl EnemyKind {
+  /// Pushed away by a bomb's shockwave instead of destroyed. Every kind so far is a minor one.
+  pub fn bomb_proof(self) -> bool {
+    match self {
+      EnemyKind::Rock => false,
+    }
+  }
+}
+
+#[derive(Component, Debug)]
+pub struct Enemy {
+  pub kind: EnemyKind,
+}
+
+/// The side an entity is on, a [`BurnZone`] only hurts the other one.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Faction {
+  Players,
+  Enemies,
+}
+
+/// What chip damage wears down, an entity out of it dies, see [`crate::systems::damage_system`]. A
+/// projectile still breaks an enemy at once.
+#[derive(Component, Debug)]
+pub struct Health(pub u32);
+
+/// A burning patch left by a large explosion, hurting the damageable entities of the other faction
+/// inside `radius` every time `tick` finishes. Its [`Lifetime`] ends it.
+#[derive(Component, Debug)]
+pub struct BurnZone {
+  pub radius: f32,
+  pub tick: Timer,
+  pub faction: Faction,
+}
+
+/// Counts down from the last burn, no zone burns the entity again until it finished. Overlapping
+/// zones burn no faster than one.
+#[derive(Component, Debug)]
+pub struct Burnt(pub Timer);
+
+/// The ring of a bomb, its [`Interpolation`] grows the radius. `radius` is where the ring was at the
+/// end of the tick before, the ring swept everything between it and the current radius.
+#[derive(Component, Debug, Default)]
+pub struct Shockwave {
+  pub radius: f32,
+}
+
 #[derive(Component, Debug)]
 pub struct Text {
   pub text: String,
   pub timer: Timer,
 }
+
+#[derive(Debug, Copy, Clone)]
+pub enum ExitAnim {
+  Shrink,
+  Pop,
+  Shatter(usize),
+}
+
+/// An exit animation, the shape's bounding box at `transform` shrinking, popping or shattering.
+pub struct Exit {
+  pub animation: ExitAnim,
+  pub transform: glam::Mat4,
+  pub size: glam::Vec2,
+  pub color: ColorGl,
+}
+
+/// Marks an entity on its way out. The game stage never despawns directly, two systems despawning the
+/// same entity, or one inserting into it after another despawned it, fail once the commands are
+/// applied. Marking it twice is harmless. Without an exit `despawn_system` despawns it right after
+/// the game stage, with one `despawn_animation_system` plays it first and behavior systems skip the
+/// entity meanwhile.
+#[derive(Component)]
+pub struct Despawning {
+  pub exit: Option<Exit>,
+}
+
+impl Despawning {
+  /// Gone once the game stage is done.
+  pub fn now() -> Self {
+    Self { exit: None }
+  }
+
+  /// `transform` places the shape's bounding box, which spans from the origin to `size`.
+  pub fn bundle(
+    animation: ExitAnim,
+    transform: glam::Mat4,
+    size: glam::Vec2,
+    color: ColorGl,
+  ) -> (Self, Interpolation) {
+    let interpolation = match animation {
+      ExitAnim::Shrink => Interpolation::new(vec![(1.0, 0.0)], 0.15, InterpolationMode::Once),
+      ExitAnim::Pop => Interpolation::new(vec![(1.0, 1.6)], 0.1, InterpolationMode::Once),
+      // shatters on its first frame, the interpolation is never played
+      ExitAnim::Shatter(_) => Interpolation::new(vec![(0.0, 0.0)], 1.0, InterpolationMode::Once),
+    };
+
+    (
+      Self {
+        exit: Some(Exit {
+          animation,
+          transform,
+          size,
+          color,
+        }),
+      },
+      interpolation,
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// A tick of `player_system` with boost held down or not, whether it boosted.
+  fn tick(boost: &mut Boost, held: bool, dt: f32) -> bool {
+    let boosting = held && boost.can_boost();
+    if boosting {
+      boost.drain(dt);
+    }
+    boost.update_cooldown(dt);
+    boost.regen(dt);
+    boosting
+  }
+
+  #[test]
+  fn boost_drains_locks_for_the_cooldown_and_recharges() {
+    // an eighth of a second adds up exactly
+    let dt = 0.125;
+    let mut boost = Boost::default();
+
+    // held down it drains 50 and regains 10 per second, from 100 it runs dry in the 20th tick
+    let mut boosted = 0;
+    while boost.can_boost() {
+      assert!(tick(&mut boost, true, dt));
+      boosted += 1;
+    }
+    assert_eq!(boosted, 20);
+    // the tick that ran dry counts towards the cooldown, and regenerated like every other
+    let mut locked = 1;
+    while !boost.can_boost() {
+      assert!(!tick(&mut boost, true, dt));
+      locked += 1;
+    }
+    assert_eq!(locked as f32 * dt, boost.cooldown_sec.unwrap());
+    assert_eq!(boost.boost, boost.inc_amount * boost.cooldown_sec.unwrap());
+
+    // let go it fills back up and stays full
+    for _ in 0..100 {
+      tick(&mut boost, false, dt);
+    }
+    assert_eq!(boost.boost, boost.max_boost);
+
+    // drained short of zero it never locks
+    for _ in 0..10 {
+      assert!(tick(&mut boost, true, dt));
+    }
+    assert!(boost.can_boost() && boost.cooldown.is_none());
+  }
+
+  #[test]
+  fn ammo_stays_between_zero_and_max() {
+    let mut ammo = Ammo::new(10);
+    assert_eq!(ammo.current, 10);
+    // full, nothing to add
+    assert!(!ammo.add(5));
+    assert_eq!(ammo.current, 10);
+
+    assert!(ammo.consume(3));
+    assert!(ammo.add(5));
+    assert_eq!(ammo.current, 10);
+
+    // a shot costing more than is left spends nothing, one costing exactly that empties it
+    ammo.current = 2;
+    assert!(!ammo.consume(3));
+    assert_eq!(ammo.current, 2);
+    assert!(ammo.consume(2));
+    assert_eq!(ammo.current, 0);
+    assert!(!ammo.consume(1));
+    assert!(ammo.consume(0));
+    assert_eq!(ammo.current, 0);
+
+    // adding can't overflow past the maximum
+    assert!(ammo.add(u32::MAX));
+    assert_eq!(ammo.current, 10);
+  }
+
+  #[track_caller]
+  fn assert_values(interpolation: &Interpolation, expected: f32) {
+    let value = interpolation.values(Easing::Linear)[0];
+    assert!((value - expected).abs() < 1e-4, "{} != {}", value, expected);
+  }
+
+  #[test]
+  fn a_once_interpolation_finishes_on_its_end_value() {
+    let mut interpolation = Interpolation::new(vec![(0.0, 10.0)], 1.0, InterpolationMode::Once);
+    let (values, done) = interpolation.eval(0.75, Easing::Linear);
+    assert_eq!((values, done), (vec![7.5], false));
+    // the tick past the end doesn't ease past it
+    let (values, done) = interpolation.eval(0.75, Easing::Linear);
+    assert_eq!((values, done), (vec![10.0], true));
+    assert_eq!(interpolation.progress(), 1.0);
+    // and stays there
+    assert_eq!(interpolation.eval(5.0, Easing::Linear), (vec![10.0], true));
+  }
+
+  #[test]
+  fn ping_pong_turns_back_at_the_end_and_finishes_at_the_begin() {
+    let mut interpolation = Interpolation::new(vec![(0.0, 10.0)], 1.0, InterpolationMode::PingPong);
+    assert!(!interpolation.eval(0.5, Easing::Linear).1);
+    assert_values(&interpolation, 5.0);
+    assert!(!interpolation.eval(0.75, Easing::Linear).1);
+    assert_values(&interpolation, 7.5);
+    // the way back ends the cycle, the overshoot starts the next one on the way out again
+    assert!(!interpolation.eval(0.5, Easing::Linear).1);
+    assert_values(&interpolation, 2.5);
+    assert!(interpolation.eval(0.5, Easing::Linear).1);
+    assert_values(&interpolation, 2.5);
+    assert!(!interpolation.eval(0.5, Easing::Linear).1);
+    assert_values(&interpolation, 7.5);
+  }
+
+  #[test]
+  fn a_delay_holds_the_begin_values_before_the_first_cycle_only() {
+    let mut interpolation = Interpolation::new(vec![(2.0, 4.0)], 1.0, InterpolationMode::Loop).with_delay(0.5);
+    assert_eq!(interpolation.eval(0.4, Easing::Linear), (vec![2.0], false));
+    assert_eq!(interpolation.progress(), 0.0);
+    assert!(!interpolation.eval(0.6, Easing::Linear).1);
+    assert_values(&interpolation, 3.0);
+    // a loop finishes a cycle after delay and duration, the next cycle starts without the delay
+    assert!(interpolation.eval(0.5, Easing::Linear).1);
+    assert_values(&interpolation, 2.0);
+    assert!(!interpolation.eval(0.25, Easing::Linear).1);
+    assert_values(&interpolation, 2.5);
+
+    // once only finishes after both as well
+    let mut once = Interpolation::new(vec![(0.0, 1.0)], 1.0, InterpolationMode::Once).with_delay(0.5);
+    assert!(!once.eval(1.25, Easing::Linear).1);
+    assert!(once.eval(0.25, Easing::Linear).1);
+  }
+
+  #[test]
+  fn a_zero_duration_finishes_at_once_on_the_end_values() {
+    for duration in [0.0, -1.0, f32::NAN] {
+      for mode in [InterpolationMode::Once, InterpolationMode::Loop, InterpolationMode::PingPong] {
+        let mut interpolation = Interpolation::new(vec![(1.0, 3.0)], duration, mode);
+        let (values, done) = interpolation.eval(0.016, Easing::InOutCubic);
+        assert!(done, "{} {:?}", duration, mode);
+        assert!(values.iter().all(|value| (1.0..=3.0).contains(value)), "{:?}", values);
+        assert!((0.0..=1.0).contains(&interpolation.phase()));
+      }
+    }
+  }
+
+  #[test]
+  fn an_interpolation_without_tracks_is_finished_and_empty() {
+    let mut interpolation = Interpolation::new(Vec::new(), 1.0, InterpolationMode::Loop);
+    assert_eq!(interpolation.eval(0.0, Easing::Linear), (Vec::new(), true));
+    assert_eq!(interpolation.eval(f32::NAN, Easing::Linear), (Vec::new(), true));
+    assert!(interpolation.values(Easing::Linear).is_empty());
+  }
+
+  #[test]
+  fn bad_deltas_count_as_zero() {
+    let mut interpolation = Interpolation::new(vec![(0.0, 10.0)], 1.0, InterpolationMode::Once);
+    interpolation.eval(0.5, Easing::Linear);
+    for dt in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY, -1.0] {
+      assert_eq!(interpolation.eval(dt, Easing::Linear), (vec![5.0], false), "{}", dt);
+    }
+  }
+
+  #[test]
+  fn a_huge_delta_lands_on_valid_values() {
+    for mode in [InterpolationMode::Once, InterpolationMode::Loop, InterpolationMode::PingPong] {
+      let mut interpolation = Interpolation::new(vec![(0.0, 10.0)], 0.25, mode).with_delay(0.1);
+      for dt in [1e6, f32::MAX, 1e-3] {
+        let (values, _) = interpolation.eval(dt, Easing::InOutCubic);
+        assert!(values.iter().all(|value| (0.0..=10.0).contains(value)), "{:?} {} {:?}", mode, dt, values);
+      }
+    }
+    // once is over however far past the end it went
+    let mut once = Interpolation::new(vec![(0.0, 10.0)], 0.25, InterpolationMode::Once);
+    assert_eq!(once.eval(f32::MAX, Easing::Linear), (vec![10.0], true));
+  }
+
+  #[test]
+  fn later_effects_stack_higher_within_a_band_and_stay_below_the_next_layer() {
+    assert_eq!(SpawnSeq(0).z_bias(), 0.0);
+    for seq in 1..Z_BIAS_BAND {
+      assert!(SpawnSeq(seq).z_bias() > SpawnSeq(seq - 1).z_bias());
+    }
+    assert!(SpawnSeq(Z_BIAS_BAND - 1).z_bias() < 0.5);
+    // the band repeats instead of growing into the layer above
+    assert_eq!(SpawnSeq(Z_BIAS_BAND).z_bias(), 0.0);
+    assert_eq!(SpawnSeq(u64::MAX).z_bias(), SpawnSeq(Z_BIAS_BAND - 1).z_bias());
+  }
+}
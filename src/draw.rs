@@ -0,0 +1,115 @@
+use crate::{
+  easings::ease_in_out_cubic,
+  environment::{CIRCLE_CACHE_STEP, CIRCLE_SEGMENTS_MAX, CIRCLE_SEGMENTS_MIN, CIRCLE_SEGMENTS_PER_UNIT},
+  render::WithTransformColor,
+  resources::{DrawBuffers, Fills, Strokes},
+};
+use bevy_ecs::prelude::Resource;
+use lyon::{
+  math::Point,
+  tessellation::{
+    geometry_builder::simple_builder, BuffersBuilder, FillGeometryBuilder, FillOptions, StrokeGeometryBuilder,
+    StrokeOptions, TessellationResult, VertexBuffers,
+  },
+};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy)]
+pub enum CircleStyle {
+  Fill,
+  /// Outlined, the value is the line width.
+  Stroke(f32),
+}
+
+/// About [`CIRCLE_SEGMENTS_PER_UNIT`] segments per unit of radius. Lyon's default tolerance is the
+/// same for every radius, too fine for a projectile and too coarse for the ship.
+pub fn circle_tolerance(radius: f32) -> f32 {
+  let segments = (radius * CIRCLE_SEGMENTS_PER_UNIT).clamp(CIRCLE_SEGMENTS_MIN, CIRCLE_SEGMENTS_MAX);
+  (radius * (1.0 - (std::f32::consts::PI / segments).cos())).max(1e-3)
+}
+
+/// Alpha of something fading out, fully opaque at `progress` 0 and gone at 1.
+pub fn fade_alpha(progress: f32) -> f32 {
+  1.0 - ease_in_out_cubic(progress.clamp(0.0, 1.0))
+}
+
+fn tessellate_circle<B: FillGeometryBuilder + StrokeGeometryBuilder>(
+  fills: &mut Fills,
+  strokes: &mut Strokes,
+  radius: f32,
+  style: CircleStyle,
+  output: &mut B,
+) -> TessellationResult {
+  let center = Point::new(0.0, 0.0);
+  let tolerance = circle_tolerance(radius);
+  match style {
+    CircleStyle::Fill => fills.tessellate_circle(center, radius, &FillOptions::tolerance(tolerance), output),
+    CircleStyle::Stroke(width) => {
+      let options = StrokeOptions::tolerance(tolerance).with_line_width(width);
+      strokes.tessellate_circle(center, radius, &options, output)
+    }
+  }
+}
+
+/// Circles tessellated once per radius and style, both rounded to [`CIRCLE_CACHE_STEP`]. Drawing copies
+/// the cached vertices with a transform, like [`crate::resources::GlyphMeshes`]. Toggled with F4, off
+/// tessellates every circle every frame to compare against.
+#[derive(Resource)]
+pub struct GeometryCache {
+  pub enabled: bool,
+  circles: HashMap<(u32, Option<u32>), VertexBuffers<Point, u16>>,
+}
+
+impl Default for GeometryCache {
+  fn default() -> Self {
+    Self {
+      enabled: true,
+      circles: HashMap::new(),
+    }
+  }
+}
+
+impl GeometryCache {
+  /// Circle meshes cached so far.
+  pub fn circles(&self) -> usize {
+    self.circles.len()
+  }
+}
+
+fn step(value: f32) -> u32 {
+  (value / CIRCLE_CACHE_STEP).round() as u32
+}
+
+/// A circle around the origin of `with.transform`.
+pub fn draw_circle<G>(
+  buffers: &mut DrawBuffers<G>,
+  cache: &mut GeometryCache,
+  fills: &mut Fills,
+  strokes: &mut Strokes,
+  radius: f32,
+  style: CircleStyle,
+  with: WithTransformColor,
+) {
+  if !cache.enabled {
+    let mut builder = BuffersBuilder::new(&mut buffers.vertex_buffer, with);
+    tessellate_circle(fills, strokes, radius, style, &mut builder).ok();
+    return;
+  }
+
+  let key = match style {
+    CircleStyle::Fill => (step(radius), None),
+    CircleStyle::Stroke(width) => (step(radius), Some(step(width))),
+  };
+  let mesh = cache.circles.entry(key).or_insert_with(|| {
+    let style = match key.1 {
+      None => CircleStyle::Fill,
+      Some(width) => CircleStyle::Stroke(width as f32 * CIRCLE_CACHE_STEP),
+    };
+    let mut mesh = VertexBuffers::new();
+    tessellate_circle(fills, strokes, key.0 as f32 * CIRCLE_CACHE_STEP, style, &mut simple_builder(&mut mesh)).ok();
+    mesh
+  });
+  let offset = buffers.vertex_buffer.vertices.len() as u32;
+  buffers.vertex_buffer.vertices.extend(mesh.vertices.iter().map(|position| with.vertex(*position)));
+  buffers.vertex_buffer.indices.extend(mesh.indices.iter().map(|index| u32::from(*index) + offset));
+}
@@ -1,5 +1,36 @@
 pub type EasingFunction = fn(f32) -> f32;
 
+/// The easings an [`crate::components::Interpolation`] eases with, in the order the curve panel plots
+/// them and its override cycles through them. Add new easings here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+  Linear,
+  InOutCubic,
+}
+
+impl Easing {
+  pub const ALL: [Easing; 2] = [Easing::Linear, Easing::InOutCubic];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Easing::Linear => "linear",
+      Easing::InOutCubic => "in out cubic",
+    }
+  }
+
+  pub fn function(self) -> EasingFunction {
+    match self {
+      Easing::Linear => linear,
+      Easing::InOutCubic => ease_in_out_cubic,
+    }
+  }
+
+  /// The one after this in [`Self::ALL`], the first after the last.
+  pub fn next(self) -> Self {
+    Self::ALL[(self as usize + 1) % Self::ALL.len()]
+  }
+}
+
 pub fn ease_in_out_cubic(x: f32) -> f32 {
   if x < 0.5 {
     4.0 * x * x * x
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 type RawColor = (u8, u8, u8);
 
 pub const SCREEN_RENDER_WIDTH: u32 = 1920 / 2;
@@ -10,10 +12,331 @@ pub const RGB_COLOR_BOOST: RawColor = (76, 195, 217);
 pub const RGB_COLOR_TRAIL: RawColor = (255, 198, 93);
 pub const RGB_COLOR_AMMO_PICKUP: RawColor = (123, 200, 164);
 pub const RGB_COLOR_DEATH: RawColor = (241, 103, 69);
+pub const RGB_COLOR_ENEMY: RawColor = RGB_COLOR_DEATH;
 pub const Z_INDEX_PLAYER: f32 = 10.0;
 pub const Z_INDEX_AMMO_PICKUP: f32 = Z_INDEX_PLAYER - 1.0;
 pub const Z_INDEX_BOOST_PICKUP: f32 = Z_INDEX_PLAYER - 2.0;
+pub const Z_INDEX_ENEMY: f32 = Z_INDEX_PLAYER - 3.0;
 pub const Z_INDEX_TRAIL_EFFECT: f32 = Z_INDEX_PLAYER + 1.0;
+pub const Z_INDEX_STARFIELD: f32 = 1.0;
+pub const RGB_COLOR_STAR: RawColor = (180, 190, 220);
 pub const SLOW_DOWN_DURATION_ON_DEATH: f32 = 2.5;
 pub const DEAD_PROJECTILE_WIDTH: f32 = 6.0;
 pub const DEAD_PROJECTILE_HEIGHT: f32 = 3.0;
+pub const DEAD_PROJECTILE_FADE_DURATION: f32 = 0.1;
+pub const Z_INDEX_MINIMAP: f32 = 90.0;
+pub const MINIMAP_WIDTH: f32 = 80.0;
+pub const MINIMAP_HEIGHT: f32 = 45.0;
+pub const MINIMAP_MARGIN: f32 = 4.0;
+pub const MINIMAP_CELL_SIZE: f32 = 4.0;
+pub const MINIMAP_MAX_DOTS: usize = 64;
+pub const Z_INDEX_LASER: f32 = Z_INDEX_PLAYER + 2.0;
+pub const LASER_TELEGRAPH_DURATION: f32 = 1.5;
+pub const LASER_FIRING_DURATION: f32 = 0.4;
+pub const LASER_FADE_DURATION: f32 = 0.2;
+pub const LASER_WIDTH: f32 = 6.0;
+pub const PLAYER_RADIUS: f32 = 12.0;
+pub const AMMO_PICKUP_RADIUS: f32 = 8.0;
+pub const BOOST_PICKUP_RADIUS: f32 = 6.0;
+pub const ROCK_RADIUS: f32 = 8.0;
+pub const PROJECTILE_RADIUS: f32 = 2.5;
+pub const THOUSANDS_SEPARATOR: char = ',';
+pub const AUDIT_INTERVAL: u64 = 60;
+pub const BACKGROUND_TIME_SCALE: f32 = 0.3;
+pub const TEXT_FONT_SIZE: u32 = 32;
+pub const SDF_FONT_SIZE: u32 = 64;
+pub const SDF_SPREAD: usize = 8;
+pub const SDF_SMOOTHING: f32 = 0.7;
+pub const SDF_OUTLINE_WIDTH: f32 = 0.2;
+pub const RGB_COLOR_TEXT_OUTLINE: RawColor = RGB_CLEAR_COLOR;
+pub const MAX_DISTORTIONS: usize = 8;
+pub const DISTORTION_DURATION: f32 = 0.5;
+pub const Z_INDEX_INPUT_DISPLAY: f32 = 90.0;
+pub const INPUT_DISPLAY_MARGIN: f32 = 4.0;
+pub const INPUT_KEY_SIZE: f32 = 6.0;
+pub const INPUT_HISTORY_TICKS: usize = 120;
+pub const FLASH_GUARD_WINDOW: f32 = 1.0;
+pub const FLASH_GUARD_MAX_FLASHES: usize = 3;
+pub const FLASH_GUARD_CLAMP_DURATION: f32 = 5.0;
+pub const BRAKE_BOOST_COST: f32 = 0.5;
+pub const BRAKE_TURN_MULTIPLIER: f32 = 1.3;
+pub const HANDBRAKE_ANGLE: f32 = 120.0;
+pub const HANDBRAKE_WINDOW: f32 = 0.5;
+pub const Z_INDEX_HEATMAP: f32 = 80.0;
+pub const HEATMAP_COLUMNS: usize = 48;
+pub const HEATMAP_ROWS: usize = 27;
+pub const Z_INDEX_LOGO: f32 = 95.0;
+pub const LOGO_REVEAL_DURATION: f32 = 1.5;
+pub const MAX_SPAWN_DENSITY_SCALE: f32 = 4.0;
+pub const PITY_THRESHOLD: f32 = 10.0;
+pub const PITY_RAMP: f32 = 10.0;
+pub const PITY_MAX_RATE: f32 = 3.0;
+pub const PITY_SATURATED_RATE: f32 = 0.75;
+pub const STRESS_RUNS_DIR: &str = "stress_runs";
+pub const STRESS_RUNS_KEPT: usize = 20;
+pub const MAIN_THREAD_TASK_BUDGET: Duration = Duration::from_millis(2);
+/// Estimated time to compute the distance field of one glyph.
+pub const SDF_GLYPH_COST: Duration = Duration::from_micros(400);
+/// Estimated time to read and decode one sound effect.
+pub const SFX_LOAD_COST: Duration = Duration::from_micros(800);
+/// Stars behind the playfield, see [`crate::starfield::Starfield`], and how many one task step scatters.
+pub const STARFIELD_STARS: usize = 240;
+pub const STARFIELD_CHUNK: usize = 60;
+/// Estimated time to scatter one chunk of stars.
+pub const STARFIELD_CHUNK_COST: Duration = Duration::from_micros(50);
+/// Room for entries the [`crate::spatial::SpatialGrid`] may hold on to beyond four times what it holds,
+/// more is given back a row per task step.
+pub const SPATIAL_GRID_SPARE: usize = 1024;
+/// Estimated time to shrink one row of the spatial grid.
+pub const SPATIAL_GRID_ROW_COST: Duration = Duration::from_micros(20);
+pub const Z_INDEX_CURVE_PLOT: f32 = 90.0;
+pub const CURVE_PLOT_SIZE: f32 = 40.0;
+pub const CURVE_PLOT_MARGIN: f32 = 8.0;
+pub const CURVE_PLOT_SAMPLES: usize = 64;
+/// Per tick displacement above which contact tests sweep the whole move instead of its end.
+pub const MIN_SWEEP_DISTANCE: f32 = 4.0;
+pub const MEMORY_CHECK_INTERVAL: f32 = 10.0;
+pub const MEMORY_OVERSIZE_FACTOR: usize = 2;
+pub const MEMORY_OVERSIZED_CHECKS: u32 = 3;
+pub const Z_BIAS_BAND: u64 = 128;
+/// Coarse enough for a 16 bit depth buffer over the -100..100 depth range.
+pub const Z_BIAS_STEP: f32 = 1.0 / 256.0;
+pub const AMMO_MAX: u32 = 100;
+pub const AMMO_PER_SHOT: u32 = 1;
+pub const AMMO_PICKUP_AMOUNT: u32 = 5;
+pub const HUD_TEXT_SCALE: f32 = 0.75;
+pub const HUD_LINE_HEIGHT: f32 = 20.0;
+pub const RGB_COLOR_HUD_BOOST: RawColor = RGB_COLOR_BOOST;
+pub const RGB_COLOR_HUD_AMMO: RawColor = RGB_COLOR_AMMO_PICKUP;
+pub const RGB_COLOR_HUD_DEAD: RawColor = RGB_COLOR_DEATH;
+pub const SCORE_ENEMY: u32 = 100;
+pub const SCORE_AMMO_PICKUP: u32 = 10;
+pub const SCORE_BOOST_PICKUP: u32 = 25;
+pub const SCORE_SURVIVAL: u32 = 50;
+/// Seconds alive per [`SCORE_SURVIVAL`].
+pub const SCORE_SURVIVAL_INTERVAL: f32 = 10.0;
+pub const RESPAWN_DELAY: f32 = SLOW_DOWN_DURATION_ON_DEATH;
+/// Default of `--deadzone`, how far the stick has to be pushed before it turns.
+pub const GAMEPAD_DEADZONE: f32 = 0.15;
+/// Seconds the stick has to be held before prompts switch to the controller, see
+/// [`crate::prompts::InputDevice::update`].
+pub const DEVICE_SWITCH_DEBOUNCE: f32 = 0.25;
+/// Share of the time the rumble motors may run, and the seconds they may run in one go before they
+/// have to rest, see [`crate::rumble::Rumble::advance`].
+pub const RUMBLE_DUTY_CYCLE: f32 = 0.5;
+pub const RUMBLE_BURST: f32 = 2.0;
+/// How long a rumble sent to the controller lasts, it is sent again every tick while it plays. A
+/// stalled game doesn't leave the motors running.
+pub const RUMBLE_REFRESH_MS: u32 = 100;
+pub const PROJECTILE_INTERVAL: f32 = 0.25;
+pub const RAPID_PROJECTILE_INTERVAL: f32 = 0.12;
+/// Degrees between the shots of a spread attack.
+pub const ATTACK_SPREAD_ANGLE: f32 = 15.0;
+/// Radians per second a homing projectile turns towards its target.
+pub const HOMING_TURN_SPEED: f32 = 2.0 * std::f32::consts::PI;
+/// Seconds a homing projectile flies before it fizzles out, it would circle a target it can't reach.
+pub const HOMING_PROJECTILE_LIFETIME: f32 = 3.0;
+pub const ATTACK_PICKUP_RADIUS: f32 = 8.0;
+pub const RGB_COLOR_ATTACK_PICKUP: RawColor = (222, 173, 255);
+pub const Z_INDEX_ATTACK_PICKUP: f32 = Z_INDEX_PLAYER - 4.0;
+pub const SCORE_ATTACK_PICKUP: u32 = 25;
+/// Particles per [`crate::resources::ParticleKind`], once all are in use new ones are dropped. 500
+/// explosions of up to 12 lines, the budget of [`PARTICLE_BUFFER_VERTICES`].
+pub const PARTICLE_POOL_CAP: usize = 6_000;
+pub const PAUSED_TEXT_SCALE: f32 = 2.0;
+/// Seconds a freshly spawned player can't die, blinking every [`INVULNERABLE_BLINK_INTERVAL`].
+pub const INVULNERABLE_DURATION: f32 = 2.0;
+pub const INVULNERABLE_BLINK_INTERVAL: f32 = 0.1;
+/// Seconds between trail puffs, shorter while boosting for a denser trail.
+pub const TRAIL_INTERVAL: f32 = 0.016;
+pub const TRAIL_BOOST_INTERVAL: f32 = 0.008;
+/// Extra zoom at the peak of a punch, see [`crate::resources::ZoomPunch`].
+pub const ZOOM_PUNCH_DEATH: f32 = 0.05;
+pub const ZOOM_PUNCH_KILL: f32 = 0.02;
+pub const ZOOM_PUNCH_BOMB: f32 = 0.035;
+pub const ZOOM_PUNCH_DURATION: f32 = 0.3;
+/// Seconds from the start of a punch to its peak, a few frames.
+pub const ZOOM_PUNCH_RISE: f32 = 0.05;
+/// Full screen flashes per event, each shown for its duration in raw seconds.
+pub const RGB_COLOR_FLASH_DEATH: RawColor = RGB_COLOR_PLAYER;
+pub const FLASH_DURATION_DEATH: f32 = 0.05;
+pub const RGB_COLOR_FLASH_BOOST: RawColor = RGB_COLOR_BOOST;
+pub const FLASH_DURATION_BOOST: f32 = 0.03;
+/// The boost gauge in the bottom left corner, in screen space so it doesn't shake or zoom.
+pub const Z_INDEX_BOOST_BAR: f32 = 90.0;
+pub const BOOST_BAR_MARGIN: f32 = 4.0;
+pub const BOOST_BAR_WIDTH: f32 = 48.0;
+pub const BOOST_BAR_HEIGHT: f32 = 5.0;
+/// Frames the F3 overlay averages over, two seconds at 60fps.
+pub const FRAME_STATS_WINDOW: usize = 120;
+pub const RGB_COLOR_FRAME_STATS: RawColor = RGB_COLOR_AMMO_PICKUP;
+/// Speed of a shot relative to the player, it inherits the player's velocity on top.
+pub const PROJECTILE_SPEED: f32 = 100.0;
+/// Seconds between the trail puffs a projectile drops.
+pub const PROJECTILE_TRAIL_INTERVAL: f32 = 0.03;
+/// Circle outline segments per unit of radius, kept within the min and max.
+pub const CIRCLE_SEGMENTS_PER_UNIT: f32 = 3.0;
+pub const CIRCLE_SEGMENTS_MIN: f32 = 8.0;
+pub const CIRCLE_SEGMENTS_MAX: f32 = 64.0;
+/// Radii and stroke widths are rounded to this step for [`crate::draw::GeometryCache`].
+pub const CIRCLE_CACHE_STEP: f32 = 0.25;
+/// Seconds from collecting a boost pickup until it shatters, it stays solid for the blink delay and
+/// blinks every blink interval after.
+pub const BOOST_PICKUP_COLLECT_DURATION: f32 = 0.5;
+pub const BOOST_PICKUP_BLINK_DELAY: f32 = 0.15;
+pub const BOOST_PICKUP_BLINK_INTERVAL: f32 = 0.05;
+/// Sound effects are looked up here, like every other asset, see [`crate::error::find_asset`].
+pub const SFX_DIR: &str = "assets/sfx";
+pub const AUDIO_CHANNELS: i32 = 16;
+pub const AUDIO_CHUNK_SIZE: i32 = 1024;
+/// Kept in the platform's data dir, see [`crate::highscores::HighScores`].
+pub const HIGH_SCORES_FILE: &str = "highscores.json";
+/// Lifetime stats of a profile, see [`crate::stats::LifetimeStats`].
+pub const STATS_FILE: &str = "stats.cfg";
+/// Upgrades and skill points of a profile, see [`crate::shop::PlayerUpgrades`].
+pub const UPGRADES_FILE: &str = "upgrades.cfg";
+pub const UPGRADE_MAX_LEVEL: u32 = 5;
+/// Points a run has to score per skill point it earns.
+pub const SCORE_PER_SKILL_POINT: u32 = 10;
+/// Rocks a wave of the practice panel spawns.
+pub const PRACTICE_ROCKS: u32 = 6;
+/// Gameplay seconds of a wave, see [`crate::objectives::Objectives`].
+pub const WAVE_DURATION: f32 = 30.0;
+/// Paid for every objective of a wave that was done.
+pub const OBJECTIVE_BONUS_SCORE: u32 = 100;
+pub const OBJECTIVE_BONUS_SKILL_POINTS: u64 = 5;
+/// Below this speed a player stands still.
+pub const STILL_SPEED: f32 = 10.0;
+/// Gameplay seconds a player may stand still before breaking [`crate::objectives::Objective::KeepMoving`].
+pub const KEEP_MOVING_GRACE: f32 = 0.5;
+/// The smart camera's zoom on the player alone, it eases out to 1 to keep a threat in view too. See
+/// [`crate::camera::frame`].
+pub const CAMERA_FOLLOW_ZOOM: f32 = 1.6;
+/// Space kept around the player and the threat, in world units.
+pub const CAMERA_FRAME_MARGIN: f32 = 40.0;
+/// How far the camera's center is from the threat, from 0.5 halfway to 1 on the player.
+pub const CAMERA_PLAYER_BIAS: f32 = 0.7;
+/// How quickly the camera closes in on its framing, per raw second.
+pub const CAMERA_EASE_RATE: f32 = 4.0;
+/// Hostiles further away from the players are no threat to frame.
+pub const THREAT_RANGE: f32 = 200.0;
+/// A threat closing in is as near as the distance covered at this speed until it hits.
+pub const THREAT_LOOKAHEAD_SPEED: f32 = 100.0;
+/// Lines of the balance console on screen, commands and answers alike.
+pub const CONSOLE_LOG_SHOWN: usize = 6;
+pub const CONSOLE_LINE_MAX_LEN: usize = 40;
+/// Also what fits on a menu row.
+pub const PROFILE_NAME_MAX_LEN: usize = 24;
+pub const HIGH_SCORES_KEPT: usize = 10;
+/// Entries of the table shown during the death slow-mo.
+pub const HIGH_SCORES_SHOWN: usize = 5;
+pub const RGB_COLOR_HIGH_SCORE_LATEST: RawColor = RGB_COLOR_BOOST;
+pub const RGB_COLOR_REPLAY: RawColor = RGB_COLOR_AMMO_PICKUP;
+pub const REPLAYS_DIR: &str = "replays";
+pub const REPLAYS_KEPT: usize = 10;
+/// Files the background writer queues before [`crate::output_files::Backpressure`] kicks in.
+pub const OUTPUT_QUEUE_CAPACITY: usize = 16;
+/// Per second, how quickly an ammo pickup closes the angle to the player, within its rotation speed.
+pub const AMMO_PICKUP_STEER_SMOOTHING: f32 = 8.0;
+pub const RGB_COLOR_PLAYER_TWO: RawColor = (255, 140, 200);
+/// Ship color by [`crate::components::PlayerId`], see [`crate::components::PlayerAppearance`].
+pub const PLAYER_COLORS: [RawColor; 2] = [RGB_COLOR_PLAYER, RGB_COLOR_PLAYER_TWO];
+/// Horizontal distance between the ships of a co-op spawn, centered on the playfield.
+pub const CO_OP_SPAWN_SPACING: f32 = 64.0;
+/// Side of a [`crate::spatial::SpatialGrid`] cell, about the size of the largest collider.
+pub const SPATIAL_GRID_CELL_SIZE: f32 = 32.0;
+/// What each draw buffer is allocated for up front, see [`crate::resources::BufferCapacity`]. A frame
+/// past its budget still draws, the buffer grows, but debug builds stop so the budgets keep up with
+/// what the systems draw. 10000 stroked circles of radius 16, 66 vertices and 192 indices each.
+pub const CIRCLE_BUFFER_VERTICES: usize = 660_000;
+pub const CIRCLE_BUFFER_INDICES: usize = 1_920_000;
+/// 100000 rectangles of 4 vertices, glyph meshes and filled circles take more indices per vertex.
+pub const QUAD_BUFFER_VERTICES: usize = 400_000;
+pub const QUAD_BUFFER_INDICES: usize = 900_000;
+/// 10000 opaque lines. A stroked line is 4 vertices and 6 indices, whatever its length and width.
+pub const LINE_BUFFER_VERTICES: usize = 40_000;
+pub const LINE_BUFFER_INDICES: usize = 60_000;
+/// Fading explosion lines and splat flashes, 500 explosions of up to 12 lines take 24000 vertices.
+pub const PARTICLE_BUFFER_VERTICES: usize = 40_000;
+pub const PARTICLE_BUFFER_INDICES: usize = 60_000;
+/// The minimap, the gauges, the input display, the heatmap and the curve panel.
+pub const SCREEN_BUFFER_VERTICES: usize = 40_000;
+pub const SCREEN_BUFFER_INDICES: usize = 60_000;
+/// Glyphs the text buffer is allocated for up front, 4 vertices and 6 indices each. It grows once a
+/// frame queues more.
+pub const TEXT_BUFFER_GLYPHS: usize = 2_500;
+/// How long the active modifiers of the build show after the players spawn, in seconds.
+pub const MODIFIERS_SHOWN_DURATION: f32 = 3.0;
+/// The GLSL sources, see [`crate::shaders::Program`].
+pub const SHADER_DIR: &str = "assets/shaders";
+/// Seconds between checks whether a shader file changed.
+pub const SHADER_POLL_INTERVAL: f32 = 1.0;
+/// Scanline intensity, curvature and aberration of the post-processing presets, see
+/// [`crate::resources::PostFx`].
+pub const POST_FX_SUBTLE: (f32, f32, f32) = (0.15, 0.02, 0.4);
+pub const POST_FX_HEAVY: (f32, f32, f32) = (0.45, 0.08, 1.2);
+/// Simulation ticks per second, each tick advances the world by the fixed timestep of one over this.
+/// The settings file can change it, see [`crate::settings::Settings::fixed_timestep`].
+pub const TICK_RATE: u32 = 60;
+/// Entities that moved further in one tick, by wrapping around or being reused from a pool, are
+/// drawn where they are instead of interpolated across the playfield.
+pub const INTERPOLATION_SNAP_DISTANCE: f32 = 64.0;
+/// Camera shakes per event in pixels and raw seconds, see [`crate::events::ShakeRequest`].
+pub const SHAKE_AMPLITUDE_DEATH: f32 = 10.0;
+pub const SHAKE_DURATION_DEATH: f32 = 0.6;
+pub const SHAKE_AMPLITUDE_PROJECTILE_DEATH: f32 = 2.0;
+pub const SHAKE_DURATION_PROJECTILE_DEATH: f32 = 0.15;
+pub const SHAKE_AMPLITUDE_BOMB: f32 = 6.0;
+pub const SHAKE_DURATION_BOMB: f32 = 0.4;
+/// Noise samples per second of a shake, the offset is interpolated between them.
+pub const SHAKE_FREQUENCY: f32 = 60.0;
+/// Shakes playing at once, a volley splatting on the wall shouldn't grow the list without bound.
+pub const SHAKE_MAX_ACTIVE: usize = 16;
+/// Gameplay speed at the start of a slow motion, see [`crate::events::SlowMotion`]. The death one
+/// lasts [`SLOW_DOWN_DURATION_ON_DEATH`], a kill gets a brief hit-stop.
+pub const SLOW_DOWN_FACTOR_ON_DEATH: f32 = 0.15;
+pub const SLOW_DOWN_FACTOR_ON_KILL: f32 = 0.5;
+pub const SLOW_DOWN_DURATION_ON_KILL: f32 = 0.1;
+/// Ticks a frame may catch up on. A longer hitch is dropped instead of running ever more ticks to
+/// make up for it, each taking longer than the time it covers.
+pub const MAX_TICKS_PER_FRAME: u32 = 5;
+/// A bomb charge every this many points of a run, up to [`BOMB_MAX_CHARGES`], see
+/// [`crate::resources::Bomb`].
+pub const BOMB_CHARGE_SCORE: u32 = 2_000;
+pub const BOMB_MAX_CHARGES: u32 = 3;
+/// The shockwave grows to this radius over [`BOMB_DURATION`] seconds.
+pub const BOMB_RADIUS: f32 = 160.0;
+pub const BOMB_DURATION: f32 = 0.6;
+/// Seconds the bomber can't be hit.
+pub const BOMB_INVULNERABLE_DURATION: f32 = 0.5;
+/// Speed the shockwave adds to enemies too heavy to destroy, away from its center.
+pub const BOMB_IMPULSE: f32 = 120.0;
+/// Points for an enemy the bomb destroys, shooting it is worth [`SCORE_ENEMY`].
+pub const SCORE_ENEMY_BOMBED: u32 = 25;
+pub const RGB_COLOR_SHOCKWAVE: RawColor = RGB_COLOR_TRAIL;
+/// A shattered rock burns for [`BURN_ZONE_DURATION`] seconds, see [`crate::components::BurnZone`].
+pub const BURN_ZONE_RADIUS: f32 = 24.0;
+pub const BURN_ZONE_DURATION: f32 = 3.0;
+/// Seconds between two burns, of a zone and of anything it burns.
+pub const BURN_TICK: f32 = 0.5;
+pub const BURN_DAMAGE: u32 = 1;
+/// Two burns break a rock.
+pub const ROCK_HEALTH: u32 = 2;
+/// Dashes of the ring and the radians per second it turns.
+pub const BURN_ZONE_DASHES: usize = 12;
+pub const BURN_ZONE_SPIN: f32 = 0.4;
+/// Embers flickering inside the ring.
+pub const BURN_ZONE_EMBERS: usize = 5;
+pub const RGB_COLOR_BURN: RawColor = (255, 120, 40);
+pub const Z_INDEX_BURN_ZONE: f32 = Z_INDEX_PLAYER - 5.0;
+pub const RGB_COLOR_HUD_BOMB: RawColor = RGB_COLOR_SHOCKWAVE;
+pub const Z_INDEX_SHOCKWAVE: f32 = Z_INDEX_PLAYER + 3.0;
+pub const Z_INDEX_UI: f32 = 96.0;
+pub const RGB_COLOR_UI_FOCUS: RawColor = RGB_COLOR_BOOST;
+/// Baseline to baseline of the rows of a menu, in text units.
+pub const UI_ROW_SPACING: f32 = 24.0;
+pub const CONTROLS_MENU_WIDTH: f32 = 240.0;
+/// Between the labels and the values of two column rows, in text units.
+pub const UI_COLUMN_GAP: f32 = 24.0;
+/// Rows of the stats page on screen at once, the rest scroll.
+pub const STATS_ROWS_SHOWN: usize = 8;
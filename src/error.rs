@@ -0,0 +1,180 @@
+use crate::{audit::Mismatch, render::gl::types::GLenum};
+use freetype as ft;
+use std::{fmt, path::PathBuf};
+
+#[derive(Debug)]
+pub enum Error {
+  Sdl(String),
+  ShaderCompile { stage: &'static str, log: String },
+  ProgramLink { log: String },
+  FramebufferIncomplete(GLenum),
+  AssetNotFound { path: PathBuf, searched: Vec<PathBuf> },
+  CorruptPack { reason: &'static str },
+  InvalidSettings { line: usize, reason: &'static str },
+  InvalidBindings { line: usize, reason: &'static str },
+  InvalidHighScores { reason: &'static str },
+  InvalidReplay { reason: &'static str },
+  InvalidBuild { reason: &'static str },
+  InvalidProfile { reason: &'static str },
+  InvalidStats { line: usize, reason: &'static str },
+  InvalidUpgrades { line: usize, reason: &'static str },
+  Purchase { reason: &'static str },
+  InvalidBalance { reason: &'static str },
+  InvalidCommand { reason: &'static str },
+  Font(ft::Error),
+  Io(std::io::Error),
+  InvalidArgument { flag: &'static str, value: String },
+  FrameBudgetExceeded { p99_ms: f32, budget_ms: f32 },
+  ReplayDiverged(Mismatch),
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Error::Sdl(message) => write!(f, "SDL error: {}", message),
+      Error::ShaderCompile { stage, log } => write!(f, "could not compile {} shader:\n{}", stage, log.trim_end()),
+      Error::ProgramLink { log } => write!(f, "could not link shader program:\n{}", log.trim_end()),
+      Error::FramebufferIncomplete(status) => write!(f, "framebuffer is not complete (status 0x{:x})", status),
+      Error::AssetNotFound { path, searched } => {
+        write!(f, "could not find asset {}, searched:", path.display())?;
+        for dir in searched {
+          write!(f, "\n  {}", dir.display())?;
+        }
+        Ok(())
+      }
+      Error::CorruptPack { reason } => write!(f, "could not read asset pack: {}", reason),
+      Error::InvalidSettings { line, reason } => write!(f, "invalid settings on line {}: {}", line, reason),
+      Error::InvalidBindings { line, reason } => write!(f, "invalid bindings on line {}: {}", line, reason),
+      Error::InvalidHighScores { reason } => write!(f, "invalid high scores: {}", reason),
+      Error::InvalidReplay { reason } => write!(f, "invalid replay: {}", reason),
+      Error::InvalidBuild { reason } => write!(f, "invalid build: {}", reason),
+      Error::InvalidProfile { reason } => write!(f, "invalid profile: {}", reason),
+      Error::InvalidStats { line, reason } => write!(f, "invalid stats on line {}: {}", line, reason),
+      Error::InvalidUpgrades { line, reason } => write!(f, "invalid upgrades on line {}: {}", line, reason),
+      Error::Purchase { reason } => write!(f, "could not buy: {}", reason),
+      Error::InvalidBalance { reason } => write!(f, "invalid balance: {}", reason),
+      Error::InvalidCommand { reason } => write!(f, "invalid command: {}", reason),
+      Error::Font(e) => write!(f, "could not load font: {}", e),
+      Error::Io(e) => write!(f, "io error: {}", e),
+      Error::InvalidArgument { flag, value } => write!(f, "invalid value {:?} for {}", value, flag),
+      Error::FrameBudgetExceeded { p99_ms, budget_ms } => {
+        write!(f, "p99 frame time {:.3}ms exceeds budget {:.3}ms", p99_ms, budget_ms)
+      }
+      Error::ReplayDiverged(mismatch) => write!(f, "replay diverged from the recording at {}", mismatch),
+    }
+  }
+}
+
+impl std::error::Error for Error {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Error::Font(e) => Some(e),
+      Error::Io(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+impl From<ft::Error> for Error {
+  fn from(e: ft::Error) -> Self {
+    Error::Font(e)
+  }
+}
+
+impl From<std::io::Error> for Error {
+  fn from(e: std::io::Error) -> Self {
+    Error::Io(e)
+  }
+}
+
+/// Looks for an asset next to the working directory first and next to the executable second.
+pub fn find_asset(path: &str) -> Result<PathBuf, Error> {
+  let mut searched = vec![std::env::current_dir()?];
+  if let Some(dir) = std::env::current_exe()?.parent() {
+    searched.push(dir.to_path_buf());
+  }
+
+  searched
+    .iter()
+    .map(|dir| dir.join(path))
+    .find(|candidate| candidate.is_file())
+    .ok_or_else(|| Error::AssetNotFound {
+      path: PathBuf::from(path),
+      searched,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::error::Error as _;
+
+  #[test]
+  fn errors_read_as_one_line_per_problem() {
+    let cases = [
+      (Error::Sdl("no video device".into()), "SDL error: no video device"),
+      (
+        Error::ShaderCompile {
+          stage: "fragment",
+          log: "0:3: syntax error\n\n".into(),
+        },
+        "could not compile fragment shader:\n0:3: syntax error",
+      ),
+      (Error::FramebufferIncomplete(0x8cd6), "framebuffer is not complete (status 0x8cd6)"),
+      (
+        Error::InvalidSettings {
+          line: 4,
+          reason: "expected a number",
+        },
+        "invalid settings on line 4: expected a number",
+      ),
+      (
+        Error::InvalidArgument {
+          flag: "--seed",
+          value: "abc".into(),
+        },
+        "invalid value \"abc\" for --seed",
+      ),
+      (
+        Error::FrameBudgetExceeded {
+          p99_ms: 17.25,
+          budget_ms: 16.6667,
+        },
+        "p99 frame time 17.250ms exceeds budget 16.667ms",
+      ),
+    ];
+    for (error, expected) in cases {
+      assert_eq!(error.to_string(), expected);
+    }
+  }
+
+  #[test]
+  fn a_missing_asset_lists_where_it_was_looked_for() {
+    let error = Error::AssetNotFound {
+      path: PathBuf::from("m5x7.ttf"),
+      searched: vec![PathBuf::from("/game"), PathBuf::from("/usr/bin")],
+    };
+    assert_eq!(error.to_string(), "could not find asset m5x7.ttf, searched:\n  /game\n  /usr/bin");
+    let nowhere = Error::AssetNotFound {
+      path: PathBuf::from("m5x7.ttf"),
+      searched: Vec::new(),
+    };
+    assert_eq!(nowhere.to_string(), "could not find asset m5x7.ttf, searched:");
+  }
+
+  #[test]
+  fn io_and_font_errors_convert_and_keep_their_source() {
+    let read = || -> Result<Vec<u8>, Error> { Ok(std::fs::read("/nonexistent/bytepath")?) };
+    let error = read().unwrap_err();
+    assert!(matches!(&error, Error::Io(e) if e.kind() == std::io::ErrorKind::NotFound));
+    assert!(error.to_string().starts_with("io error: "));
+    assert!(error.source().is_some());
+
+    let error = Error::from(ft::Error::CannotOpenResource);
+    assert!(matches!(error, Error::Font(ft::Error::CannotOpenResource)));
+    assert_eq!(error.to_string(), "could not load font: Cannot open resource");
+    assert!(error.source().is_some());
+
+    assert!(Error::Purchase { reason: "the upgrade is maxed out" }.source().is_none());
+  }
+}
@@ -1,3 +1,248 @@
+use crate::{
+  color::ColorGl,
+  components::Attack,
+  easings::ease_in_out_cubic,
+  environment::{
+    SHAKE_AMPLITUDE_BOMB, SHAKE_AMPLITUDE_DEATH, SHAKE_AMPLITUDE_PROJECTILE_DEATH, SHAKE_DURATION_BOMB,
+    SHAKE_DURATION_DEATH, SHAKE_DURATION_PROJECTILE_DEATH, SLOW_DOWN_DURATION_ON_DEATH, SLOW_DOWN_DURATION_ON_KILL,
+    SLOW_DOWN_FACTOR_ON_DEATH, SLOW_DOWN_FACTOR_ON_KILL,
+  },
+  units::WorldPos,
+};
+use bevy_ecs::{event::Events, prelude::*};
+
+/// The signals of the game many systems react to. What only one or two systems read each has a
+/// channel of its own, [`DamageEvent`], [`PickupEvent`], [`SpawnRequest`] and [`NotificationEvent`], so
+/// the readers of one don't match their way through the others.
 pub enum GameEvents {
-  PlayerDeath,
+  /// This player died, the entity is despawned right after the game stage.
+  PlayerDeath(Entity, DeathCause),
+  /// The player fired a volley with this attack, however many projectiles it had.
+  Shot(Attack),
+  /// An enemy was destroyed at this position.
+  EnemyDeath(WorldPos, KilledBy),
+  /// A player set off a bomb, its shockwave starts growing.
+  BombDetonated,
+  /// A shot or a pickup changed [`crate::components::Ammo`].
+  AmmoChanged,
+  /// A player ran into the edge of the playfield, see [`crate::components::BoundsMode::Clamp`].
+  WallImpact,
+  /// `player` swept into the collider of `other` during the tick.
+  PlayerContact { player: Entity, other: Entity },
+  /// Covers the screen in `color` for `duration` raw seconds, replacing a flash still showing.
+  Flash { color: ColorGl, duration: f32 },
+  /// Starts a camera shake on top of the ones already playing.
+  Shake(ShakeRequest),
+  /// Slows gameplay down on top of the slow motions already playing.
+  SlowMotion(SlowMotion),
+}
+
+/// Chip damage to the [`crate::components::Health`] of `target`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DamageEvent {
+  pub target: Entity,
+  pub amount: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pickup {
+  Ammo,
+  Boost,
+  Attack,
+}
+
+/// A player collected a pickup of this kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PickupEvent(pub Pickup);
+
+/// Something gameplay left behind, spawned by the system that owns it rather than where it happened.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpawnRequest {
+  /// A [`crate::components::BurnZone`] of the players, where a rock they shot or bombed shattered.
+  BurnZone(WorldPos),
+}
+
+/// A notice floating up from `position` for a while, see [`crate::systems::notification_system`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NotificationEvent {
+  pub notice: Notice,
+  pub position: WorldPos,
+}
+
+/// What a [`NotificationEvent`] says, the text is only put together where it's shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Notice {
+  Boost,
+  Attack(Attack),
+  Points(u32),
+  WaveBonus(u32),
+  /// The [`crate::output_files::OutputWriter`] couldn't write this kind of file.
+  NotSaved(&'static str),
+}
+
+impl Notice {
+  pub fn text(self) -> String {
+    match self {
+      Notice::Boost => String::from("+Boost"),
+      Notice::Attack(attack) => format!("+{}", attack.name()),
+      Notice::Points(points) => format!("+{}", points),
+      Notice::WaveBonus(points) => format!("WAVE BONUS +{}", points),
+      Notice::NotSaved(what) => format!("{} NOT SAVED", what.to_uppercase()),
+    }
+  }
+
+  /// Seconds it stays up.
+  pub fn duration(self) -> f32 {
+    match self {
+      Notice::WaveBonus(_) | Notice::NotSaved(_) => 2.0,
+      _ => 1.0,
+    }
+  }
+}
+
+/// The names of the event channels in the order of [`EventCounts`].
+pub const CHANNELS: [&str; 5] = ["GAME", "DAMAGE", "PICKUP", "SPAWN", "NOTICE"];
+
+/// Inserts every event channel, [`event_update_system`] swaps their buffers.
+pub fn insert_event_channels(world: &mut World) {
+  world.insert_resource(Events::<GameEvents>::default());
+  world.insert_resource(Events::<DamageEvent>::default());
+  world.insert_resource(Events::<PickupEvent>::default());
+  world.insert_resource(Events::<SpawnRequest>::default());
+  world.insert_resource(Events::<NotificationEvent>::default());
+  world.insert_resource(EventCounts::default());
+}
+
+/// Events are read for two ticks, the ones older than that are dropped here.
+pub fn event_update_system(
+  mut game: ResMut<Events<GameEvents>>,
+  mut damage: ResMut<Events<DamageEvent>>,
+  mut pickups: ResMut<Events<PickupEvent>>,
+  mut spawns: ResMut<Events<SpawnRequest>>,
+  mut notices: ResMut<Events<NotificationEvent>>,
+) {
+  game.update();
+  damage.update();
+  pickups.update();
+  spawns.update();
+  notices.update();
+}
+
+/// The events sent per [`CHANNELS`], shown with F3 so a system sending far more than it used to stands
+/// out.
+#[derive(Debug, Default, Resource)]
+pub struct EventCounts {
+  /// Sent during the frame so far, its ticks add up.
+  frame: [u32; CHANNELS.len()],
+  /// Sent during the last whole frame.
+  pub last_frame: [u32; CHANNELS.len()],
+  /// Sent since the world was built.
+  pub total: [u64; CHANNELS.len()],
+}
+
+impl EventCounts {
+  /// Called by the main loop after the ticks of a frame.
+  pub fn end_frame(&mut self) {
+    self.last_frame = std::mem::take(&mut self.frame);
+  }
+}
+
+/// Counts the events of the tick, last in the game stage to see them all.
+pub fn event_count_system(
+  mut counts: ResMut<EventCounts>,
+  game: EventReader<GameEvents>,
+  damage: EventReader<DamageEvent>,
+  pickups: EventReader<PickupEvent>,
+  spawns: EventReader<SpawnRequest>,
+  notices: EventReader<NotificationEvent>,
+) {
+  let tick = [game.len(), damage.len(), pickups.len(), spawns.len(), notices.len()];
+  game.clear();
+  damage.clear();
+  pickups.clear();
+  spawns.clear();
+  notices.clear();
+  for (i, sent) in tick.into_iter().enumerate() {
+    counts.frame[i] += sent as u32;
+    counts.total[i] += sent as u64;
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeathCause {
+  SelfDestruct,
+  Laser,
+  /// A [`crate::components::Hostile`] projectile.
+  Projectile,
+}
+
+impl DeathCause {
+  pub const ALL: [DeathCause; 3] = [DeathCause::SelfDestruct, DeathCause::Laser, DeathCause::Projectile];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      DeathCause::SelfDestruct => "self_destruct",
+      DeathCause::Laser => "laser",
+      DeathCause::Projectile => "projectile",
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KilledBy {
+  Projectile,
+  /// Worth less, see [`crate::environment::SCORE_ENEMY_BOMBED`].
+  Bomb,
+  /// Worn down by a [`crate::components::BurnZone`], worth as little as a bombed one.
+  Burn,
+}
+
+/// Moves the camera by up to `amplitude` pixels of noise, fading out over `duration` raw seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShakeRequest {
+  pub amplitude: f32,
+  pub duration: f32,
+}
+
+impl ShakeRequest {
+  pub const DEATH: Self = Self {
+    amplitude: SHAKE_AMPLITUDE_DEATH,
+    duration: SHAKE_DURATION_DEATH,
+  };
+  pub const PROJECTILE_DEATH: Self = Self {
+    amplitude: SHAKE_AMPLITUDE_PROJECTILE_DEATH,
+    duration: SHAKE_DURATION_PROJECTILE_DEATH,
+  };
+  pub const BOMB: Self = Self {
+    amplitude: SHAKE_AMPLITUDE_BOMB,
+    duration: SHAKE_DURATION_BOMB,
+  };
+}
+
+/// Gameplay at `factor` of its speed, eased back to full speed over `duration` raw seconds. While
+/// several play, the slowest one sets the speed, see [`crate::resources::TimeScale`].
+#[derive(Debug, Clone, Copy)]
+pub struct SlowMotion {
+  pub factor: f32,
+  pub duration: f32,
+  pub easing: fn(f32) -> f32,
+}
+
+impl SlowMotion {
+  pub const DEATH: Self = Self {
+    factor: SLOW_DOWN_FACTOR_ON_DEATH,
+    duration: SLOW_DOWN_DURATION_ON_DEATH,
+    easing: ease_in_out_cubic,
+  };
+  pub const KILL: Self = Self {
+    factor: SLOW_DOWN_FACTOR_ON_KILL,
+    duration: SLOW_DOWN_DURATION_ON_KILL,
+    easing: ease_in_out_cubic,
+  };
+
+  /// The speed `elapsed` raw seconds in.
+  pub fn factor_at(&self, elapsed: f32) -> f32 {
+    let easing = (self.easing)((elapsed / self.duration).min(1.0));
+    (1.0 - easing) * self.factor + easing
+  }
 }
@@ -0,0 +1,145 @@
+use crate::environment::THOUSANDS_SEPARATOR;
+use std::{fmt::Write, time::Duration};
+
+fn digits(mut value: u64, buffer: &mut [u8; 20]) -> &[u8] {
+  let mut start = buffer.len();
+  loop {
+    start -= 1;
+    buffer[start] = b'0' + (value % 10) as u8;
+    value /= 10;
+    if value == 0 {
+      break;
+    }
+  }
+  &buffer[start..]
+}
+
+/// Writes `value` with thousands separators, e.g. `1,234,567`.
+pub fn write_score(out: &mut impl Write, value: u64) -> std::fmt::Result {
+  let mut buffer = [0u8; 20];
+  let digits = digits(value, &mut buffer);
+  for (i, digit) in digits.iter().enumerate() {
+    if i > 0 && (digits.len() - i).is_multiple_of(3) {
+      out.write_char(THOUSANDS_SEPARATOR)?;
+    }
+    out.write_char(*digit as char)?;
+  }
+  Ok(())
+}
+
+/// Writes `M:SS.t` below an hour and `H:MM:SS` from an hour on.
+pub fn write_time(out: &mut impl Write, duration: Duration) -> std::fmt::Result {
+  let tenths = duration.as_millis() / 100;
+  let seconds = tenths / 10;
+  if seconds < 3600 {
+    write!(out, "{}:{:02}.{}", seconds / 60, seconds % 60, tenths % 10)
+  } else {
+    write!(out, "{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+  }
+}
+
+/// Writes `template` with every `{name}` replaced by what `lookup` returns for it. Names it doesn't know
+/// are written as they are, braces included.
+pub fn write_substituted(
+  out: &mut impl Write,
+  template: &str,
+  lookup: impl Fn(&str) -> Option<String>,
+) -> std::fmt::Result {
+  let mut rest = template;
+  while let Some(start) = rest.find('{') {
+    let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+      break;
+    };
+    out.write_str(&rest[..start])?;
+    match lookup(&rest[start + 1..end]) {
+      Some(value) => out.write_str(&value)?,
+      None => out.write_str(&rest[start..=end])?,
+    }
+    rest = &rest[end + 1..];
+  }
+  out.write_str(rest)
+}
+
+/// Writes a short form for tight spaces, e.g. `999`, `12.3k`, `1.2M`. Truncates instead of
+/// rounding so `999_999` never turns into `1000.0k`.
+pub fn write_abbreviated(out: &mut impl Write, value: u64) -> std::fmt::Result {
+  const SUFFIXES: [char; 6] = ['k', 'M', 'G', 'T', 'P', 'E'];
+
+  if value < 1000 {
+    return write!(out, "{}", value);
+  }
+
+  let mut unit = 0;
+  let mut scaled = value / 1000;
+  let mut remainder = value % 1000;
+  while scaled >= 1000 {
+    remainder = scaled % 1000;
+    scaled /= 1000;
+    unit += 1;
+  }
+  write!(out, "{}.{}{}", scaled, remainder / 100, SUFFIXES[unit])
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn formatted(write: impl Fn(&mut String) -> std::fmt::Result) -> String {
+    let mut out = String::new();
+    write(&mut out).unwrap();
+    out
+  }
+
+  // every helper takes unsigned values, there are no negatives to format
+
+  #[test]
+  fn scores_group_thousands_from_the_right() {
+    let score = |value| formatted(|out| write_score(out, value));
+    assert_eq!(score(0), "0");
+    assert_eq!(score(999), "999");
+    assert_eq!(score(1000), "1,000");
+    assert_eq!(score(12_345), "12,345");
+    assert_eq!(score(999_999), "999,999");
+    assert_eq!(score(1_000_000), "1,000,000");
+    assert_eq!(score(u64::MAX), "18,446,744,073,709,551,615");
+  }
+
+  #[test]
+  fn times_cut_off_tenths_and_switch_format_at_an_hour() {
+    let time = |millis| formatted(|out| write_time(out, Duration::from_millis(millis)));
+    assert_eq!(time(0), "0:00.0");
+    assert_eq!(time(99), "0:00.0");
+    assert_eq!(time(59_999), "0:59.9");
+    assert_eq!(time(60_000), "1:00.0");
+    assert_eq!(time(3_599_999), "59:59.9");
+    assert_eq!(time(3_600_000), "1:00:00");
+    assert_eq!(time(360_000_000), "100:00:00");
+    let longest = formatted(|out| write_time(out, Duration::MAX));
+    assert!(longest.ends_with(":15"), "{}", longest);
+  }
+
+  #[test]
+  fn abbreviations_truncate_at_every_unit_boundary() {
+    let short = |value| formatted(|out| write_abbreviated(out, value));
+    assert_eq!(short(0), "0");
+    assert_eq!(short(999), "999");
+    assert_eq!(short(1000), "1.0k");
+    assert_eq!(short(1099), "1.0k");
+    assert_eq!(short(12_345), "12.3k");
+    assert_eq!(short(999_999), "999.9k");
+    assert_eq!(short(1_000_000), "1.0M");
+    assert_eq!(short(1_999_999_999), "1.9G");
+    assert_eq!(short(u64::MAX), "18.4E");
+  }
+
+  #[test]
+  fn substitution_keeps_what_it_cannot_replace() {
+    let substituted =
+      |template| formatted(|out| write_substituted(out, template, |name| (name == "n").then(|| "3".to_string())));
+    assert_eq!(substituted(""), "");
+    assert_eq!(substituted("{n} of {n}"), "3 of 3");
+    assert_eq!(substituted("{m} {n}"), "{m} 3");
+    assert_eq!(substituted("open {n"), "open {n");
+    assert_eq!(substituted("}{}{n}"), "}{}3");
+  }
+}
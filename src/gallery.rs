@@ -0,0 +1,282 @@
+use crate::{
+  color::ColorGl,
+  components::*,
+  environment::*,
+  render::WithTransformColor,
+  resources::*,
+  units::{Angle, WorldPos, WorldVec},
+};
+use bevy_ecs::prelude::*;
+use lyon::{
+  geom::{Box2D, Size},
+  lyon_tessellation::FillOptions,
+  tessellation::BuffersBuilder,
+};
+use sdl2::keyboard::Keycode;
+use std::time::Duration;
+
+const GALLERY_COLUMNS: usize = 6;
+const GALLERY_PAN_SPEED: f32 = 200.0;
+
+#[derive(Debug, Clone, Copy)]
+enum Exemplar {
+  Ship(f32),
+  Projectile,
+  DeadProjectile(f32),
+  AmmoPickup,
+  AmmoPickupPop,
+  BoostPickup,
+  BoostPickupCollected,
+  AttackPickup,
+  Rock,
+  Explosion(f32),
+  Text(TextStyle, f32),
+  Swatch((u8, u8, u8)),
+}
+
+/// One of every visual, frozen in a representative phase. Add new visuals here.
+const EXEMPLARS: [(&str, Exemplar); 26] = [
+  ("ship 0", Exemplar::Ship(0.0)),
+  ("ship 45", Exemplar::Ship(45.0)),
+  ("ship 90", Exemplar::Ship(90.0)),
+  ("ship 180", Exemplar::Ship(180.0)),
+  ("projectile", Exemplar::Projectile),
+  ("splat hit", Exemplar::DeadProjectile(0.0)),
+  ("splat fade", Exemplar::DeadProjectile(0.15)),
+  ("ammo", Exemplar::AmmoPickup),
+  ("ammo pop", Exemplar::AmmoPickupPop),
+  ("boost", Exemplar::BoostPickup),
+  ("boost taken", Exemplar::BoostPickupCollected),
+  ("attack", Exemplar::AttackPickup),
+  ("rock", Exemplar::Rock),
+  ("explosion 1", Exemplar::Explosion(1.0)),
+  ("explosion 2", Exemplar::Explosion(2.0)),
+  ("explosion 3", Exemplar::Explosion(3.0)),
+  ("bitmap", Exemplar::Text(TextStyle::Bitmap, 1.0)),
+  ("sdf", Exemplar::Text(TextStyle::Sdf, 1.0)),
+  ("sdf 2x", Exemplar::Text(TextStyle::Sdf, 2.0)),
+  ("clear", Exemplar::Swatch(RGB_CLEAR_COLOR)),
+  ("player", Exemplar::Swatch(RGB_COLOR_PLAYER)),
+  ("boost", Exemplar::Swatch(RGB_COLOR_BOOST)),
+  ("trail", Exemplar::Swatch(RGB_COLOR_TRAIL)),
+  ("ammo", Exemplar::Swatch(RGB_COLOR_AMMO_PICKUP)),
+  ("death", Exemplar::Swatch(RGB_COLOR_DEATH)),
+  ("attack", Exemplar::Swatch(RGB_COLOR_ATTACK_PICKUP)),
+];
+
+/// Labels, text samples and swatches of the gallery, the entity exemplars draw themselves.
+#[derive(Resource, Default)]
+pub struct Gallery {
+  cells: Vec<(glam::Vec2, &'static str, Exemplar)>,
+}
+
+fn cell_center(i: usize, bounds: &WorldBounds) -> glam::Vec2 {
+  let rows = EXEMPLARS.len().div_ceil(GALLERY_COLUMNS);
+  let cell_size = bounds.size() / glam::vec2(GALLERY_COLUMNS as f32, rows as f32);
+  let cell = glam::vec2((i % GALLERY_COLUMNS) as f32, (rows - 1 - i / GALLERY_COLUMNS) as f32);
+  bounds.min + (cell + 0.5) * cell_size
+}
+
+/// Lays out [`EXEMPLARS`] in a grid and returns how many there are. Time is never advanced in the
+/// gallery, so the regular systems draw every exemplar frozen in its phase.
+pub fn spawn_gallery(world: &mut World) -> usize {
+  let bounds = world.resource::<WorldBounds>();
+  let centers = (0..EXEMPLARS.len()).map(|i| cell_center(i, bounds)).collect::<Vec<_>>();
+  let mut gallery = Gallery::default();
+
+  for ((label, exemplar), center) in EXEMPLARS.into_iter().zip(centers) {
+    gallery.cells.push((center, label, exemplar));
+    let transform = Transform {
+      position: WorldPos::from(center),
+      z: Z_INDEX_PLAYER,
+      ..Default::default()
+    };
+
+    match exemplar {
+      Exemplar::Ship(degrees) => {
+        world.spawn((
+          ScopeTag(GameScope::Persistent),
+          Player {
+            movement_speed: 100.0,
+            rotation_speed: 360.0f32.to_radians(),
+            brake_turn: None,
+            bounds_mode: BoundsMode::Free,
+            against_wall: false,
+          },
+          Velocity::Linear(WorldVec::ZERO),
+          Transform {
+            rotation: Angle::from_degrees(degrees),
+            ..transform
+          },
+          Player::SHAPE,
+          PlayerId(0),
+          PlayerAppearance::of(PlayerId(0)),
+          Boost::default(),
+          Sweep::default(),
+          Interpolation::new(vec![(8.0, 0.0)], 0.24, InterpolationMode::Loop),
+        ));
+      }
+      Exemplar::Projectile => {
+        world.spawn((transform, Projectile::bundle(WorldVec::ZERO), DeathAction::Nothing));
+      }
+      Exemplar::DeadProjectile(elapsed) => {
+        let mut timer = Timer::from_seconds(0.25, false);
+        timer.elapsed = Duration::from_secs_f32(elapsed);
+        world.spawn((ScopeTag(GameScope::Persistent), transform, DeadProjectile { timer }, DeadProjectile::SHAPE));
+      }
+      Exemplar::AmmoPickup | Exemplar::AmmoPickupPop => {
+        let mut timer = Timer::from_seconds(0.15, false);
+        if let Exemplar::AmmoPickupPop = exemplar {
+          timer.elapsed = Duration::from_secs_f32(0.05);
+        }
+        world.spawn((
+          ScopeTag(GameScope::Persistent),
+          AmmoPickup {
+            rotation_speed: 0.0,
+            timer,
+          },
+          transform,
+          Velocity::Heading(0.0),
+          AngularVelocity(0.0),
+        ));
+      }
+      Exemplar::BoostPickup | Exemplar::BoostPickupCollected => {
+        let mut entity = world.spawn((
+          ScopeTag(GameScope::Persistent),
+          BoostPickup::default(),
+          transform,
+          Velocity::Linear(WorldVec::ZERO),
+          AngularVelocity(0.0),
+        ));
+        if let Exemplar::BoostPickupCollected = exemplar {
+          entity.insert(Interpolation::new(vec![(1.0, 2.0)], 0.3, InterpolationMode::Once));
+        }
+      }
+      Exemplar::AttackPickup => {
+        world.spawn((
+          ScopeTag(GameScope::Persistent),
+          AttackPickup { attack: Attack::Homing },
+          transform,
+          Velocity::Linear(WorldVec::ZERO),
+          AngularVelocity(0.0),
+          Collider {
+            radius: ATTACK_PICKUP_RADIUS,
+          },
+        ));
+      }
+      Exemplar::Rock => {
+        world.spawn((
+          ScopeTag(GameScope::Persistent),
+          Enemy { kind: EnemyKind::Rock },
+          transform,
+          Velocity::Linear(WorldVec::ZERO),
+          AngularVelocity(0.0),
+          Collider { radius: ROCK_RADIUS },
+        ));
+      }
+      Exemplar::Explosion(width) => {
+        world.spawn((
+          ScopeTag(GameScope::Persistent),
+          transform,
+          ExplosionEffect::bundle(
+            ColorGl::from(RGB_COLOR_PLAYER),
+            Interpolation::new(vec![(0.0, 0.0), (12.0, 0.0), (width, 0.0)], 1.0, InterpolationMode::Once),
+          ),
+        ));
+      }
+      Exemplar::Text(..) | Exemplar::Swatch(_) => {}
+    }
+  }
+
+  world.insert_resource(gallery);
+  EXEMPLARS.len()
+}
+
+/// Pans with the arrow keys and draws what has no entity of its own. Pans in raw time, the gallery
+/// never advances [`Time`].
+pub fn gallery_system(
+  gallery: Res<Gallery>,
+  keycodes: Res<KeyCodes>,
+  raw_time: Res<DurationWrapper>,
+  mut camera: ResMut<Camera>,
+  mut quads: ResMut<QuadGeometry>,
+  mut fills: ResMut<Fills>,
+  mut texts: ResMut<TextBuffers>,
+) {
+  let mut pan = glam::Vec2::ZERO;
+  for keycode in keycodes.iter() {
+    match keycode {
+      Keycode::Left => pan.x -= 1.0,
+      Keycode::Right => pan.x += 1.0,
+      Keycode::Down => pan.y -= 1.0,
+      Keycode::Up => pan.y += 1.0,
+      _ => {}
+    }
+  }
+  let pan = pan * GALLERY_PAN_SPEED * raw_time.as_secs_f32();
+  camera.camera_pos += pan.extend(0.0);
+
+  // text is drawn in screen space at twice the resolution, so it has to follow the camera itself
+  let to_text = |position: glam::Vec2| (position - camera.camera_pos.truncate()) * 2.0;
+  for (center, label, exemplar) in &gallery.cells {
+    let label_at = to_text(*center + glam::vec2(-30.0, -28.0));
+    texts.build_text(label, label_at.x, label_at.y, 1.0, ColorGl::from(RGB_COLOR_PLAYER), TextStyle::Bitmap);
+
+    match exemplar {
+      Exemplar::Text(style, scale) => {
+        let at = to_text(*center + glam::vec2(-30.0, 0.0));
+        texts.build_text("Abc 123", at.x, at.y, *scale, ColorGl::from(RGB_COLOR_BOOST), *style);
+      }
+      Exemplar::Swatch(color) => {
+        fills
+          .tessellate_rectangle(
+            &Box2D::from_size(Size::new(24.0, 24.0)),
+            &FillOptions::default(),
+            &mut BuffersBuilder::new(
+              &mut quads.vertex_buffer,
+              WithTransformColor {
+                transform: glam::Mat4::from_translation((*center - 12.0).extend(Z_INDEX_PLAYER)),
+                color_rgba: ColorGl::from(*color),
+              },
+            ),
+          )
+          .ok();
+      }
+      _ => {}
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn every_exemplar_gets_a_cell_and_the_visuals_an_entity() {
+    let mut world = World::new();
+    world.insert_resource(WorldBounds::default());
+
+    assert_eq!(spawn_gallery(&mut world), EXEMPLARS.len());
+    assert_eq!(world.resource::<Gallery>().cells.len(), EXEMPLARS.len());
+    // texts and swatches are drawn by the gallery itself
+    let drawn_by_gallery = EXEMPLARS
+      .iter()
+      .filter(|(_, exemplar)| matches!(exemplar, Exemplar::Text(..) | Exemplar::Swatch(_)))
+      .count();
+    assert_eq!(world.entities().len() as usize, EXEMPLARS.len() - drawn_by_gallery);
+    assert_eq!(world.query::<&Player>().iter(&world).count(), 4);
+  }
+
+  #[test]
+  fn cells_fill_the_playfield_without_overlapping() {
+    let bounds = WorldBounds::default();
+    let centers = (0..EXEMPLARS.len()).map(|i| cell_center(i, &bounds)).collect::<Vec<_>>();
+    for (i, center) in centers.iter().enumerate() {
+      assert!(center.cmpgt(bounds.min).all() && center.cmplt(bounds.max).all(), "{}", center);
+      assert!(centers[..i].iter().all(|other| other.distance(*center) > 1.0));
+    }
+    // the first row is at the top
+    assert!(centers[0].y > centers[GALLERY_COLUMNS].y);
+    assert_eq!(centers[0].y, centers[GALLERY_COLUMNS - 1].y);
+  }
+}
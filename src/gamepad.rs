@@ -0,0 +1,112 @@
+use crate::{
+  environment::RUMBLE_REFRESH_MS,
+  math::deadzone,
+  prompts::PadStyle,
+  resources::GamepadState,
+  rumble::{Motors, Rumbler},
+};
+use sdl2::{
+  controller::{Axis, Button, GameController},
+  GameControllerSubsystem,
+};
+
+/// Everything a binding can name, Start and Guide stay with the pause toggle and the system.
+const BUTTONS: [Button; 13] = [
+  Button::A,
+  Button::B,
+  Button::X,
+  Button::Y,
+  Button::Back,
+  Button::LeftStick,
+  Button::RightStick,
+  Button::LeftShoulder,
+  Button::RightShoulder,
+  Button::DPadUp,
+  Button::DPadDown,
+  Button::DPadLeft,
+  Button::DPadRight,
+];
+
+/// The controllers open right now. SDL reports the ones connected at startup as added too, so startup
+/// and hot-plugging take the same path.
+pub struct Gamepads {
+  subsystem: GameControllerSubsystem,
+  open: Vec<(GameController, PadStyle)>,
+  deadzone: f32,
+}
+
+impl Gamepads {
+  pub fn new(subsystem: GameControllerSubsystem, deadzone: f32) -> Self {
+    Self {
+      subsystem,
+      open: Vec::new(),
+      deadzone,
+    }
+  }
+
+  /// `which` is a joystick index.
+  pub fn added(&mut self, which: u32) {
+    match self.subsystem.open(which) {
+      Ok(controller) => {
+        if !self.open.iter().any(|(open, _)| open.instance_id() == controller.instance_id()) {
+          self.open.push((controller, pad_style(which)));
+        }
+      }
+      Err(e) => eprintln!("could not open controller {}: {}", which, e),
+    }
+  }
+
+  /// `which` is an instance id, unlike in [`Self::added`].
+  pub fn removed(&mut self, which: u32) {
+    self.open.retain(|(open, _)| open.instance_id() != which);
+  }
+
+  /// The labels of the controller plugged in last.
+  pub fn style(&self) -> PadStyle {
+    self.open.last().map_or(PadStyle::Generic, |(_, style)| *style)
+  }
+
+  /// Every open controller merged into one: the stick pushed the furthest, the buttons held on any.
+  pub fn poll(&self) -> GamepadState {
+    let mut state = GamepadState::default();
+    for (controller, _) in &self.open {
+      let axis = |axis| (controller.axis(axis) as f32 / i16::MAX as f32).clamp(-1.0, 1.0);
+      // SDL's y axis points down
+      let stick = deadzone(glam::vec2(axis(Axis::LeftX), -axis(Axis::LeftY)), self.deadzone);
+      if stick.length_squared() > state.stick.length_squared() {
+        state.stick = stick;
+      }
+      let held = BUTTONS.into_iter().filter(|button| controller.button(*button));
+      state.buttons.extend(held);
+    }
+    state
+  }
+}
+
+/// The labels SDL knows the controller at joystick index `which` to have. The sdl2 crate doesn't wrap the
+/// controller type yet.
+fn pad_style(which: u32) -> PadStyle {
+  use sdl2::sys::SDL_GameControllerType::*;
+  match unsafe { sdl2::sys::SDL_GameControllerTypeForIndex(which as i32) } {
+    SDL_CONTROLLER_TYPE_XBOX360 | SDL_CONTROLLER_TYPE_XBOXONE => PadStyle::Xbox,
+    SDL_CONTROLLER_TYPE_PS3 | SDL_CONTROLLER_TYPE_PS4 | SDL_CONTROLLER_TYPE_PS5 => PadStyle::PlayStation,
+    SDL_CONTROLLER_TYPE_NINTENDO_SWITCH_PRO => PadStyle::Nintendo,
+    _ => PadStyle::Generic,
+  }
+}
+
+/// Controllers without rumble motors turn the request down, they stay still.
+impl Rumbler for Gamepads {
+  fn rumble(&mut self, motors: Motors) {
+    let strength = |level: f32| (level * u16::MAX as f32) as u16;
+    for (controller, _) in &mut self.open {
+      controller.set_rumble(strength(motors.low), strength(motors.high), RUMBLE_REFRESH_MS).ok();
+    }
+  }
+
+  fn stop(&mut self) {
+    for (controller, _) in &mut self.open {
+      controller.set_rumble(0, 0, 0).ok();
+    }
+  }
+}
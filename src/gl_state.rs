@@ -0,0 +1,264 @@
+use crate::render::{
+  gl::{self, types::*},
+  Gl,
+};
+
+const TRACKED_TEXTURE_UNITS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+  Disabled,
+  /// Straight alpha, `src * a + dst * (1 - a)`.
+  Alpha,
+}
+
+/// The GL state the render passes depend on. Every change goes through the setters, which skip
+/// calls that would not change anything, so a pass can set what it needs without knowing what ran
+/// before it. `None` means unknown, the next setter call always reaches GL.
+#[derive(Debug, Default)]
+pub struct GlState {
+  program: Option<GLuint>,
+  vao: Option<GLuint>,
+  active_unit: Option<usize>,
+  textures: [Option<GLuint>; TRACKED_TEXTURE_UNITS],
+  blend: Option<BlendMode>,
+  depth: Option<bool>,
+  depth_write: Option<bool>,
+}
+
+impl GlState {
+  /// Puts GL into the default state the tracker assumes, whatever happened since the last frame.
+  pub fn reset_to_known(&mut self, gl: &Gl) {
+    *self = GlState::default();
+    for unit in (0..TRACKED_TEXTURE_UNITS).rev() {
+      self.bind_texture(gl, unit, 0);
+    }
+    self.bind_program(gl, 0);
+    self.bind_vao(gl, 0);
+    self.set_blend(gl, BlendMode::Disabled);
+    self.set_depth(gl, false);
+    self.set_depth_write(gl, true);
+  }
+
+  pub fn bind_program(&mut self, gl: &Gl, program: GLuint) {
+    if self.program != Some(program) {
+      unsafe { gl.UseProgram(program) };
+      self.program = Some(program);
+    }
+  }
+
+  pub fn bind_vao(&mut self, gl: &Gl, vao: GLuint) {
+    if self.vao != Some(vao) {
+      unsafe { gl.BindVertexArray(vao) };
+      self.vao = Some(vao);
+    }
+  }
+
+  /// Binds a 2d texture to `unit`, which stays the active unit afterwards.
+  pub fn bind_texture(&mut self, gl: &Gl, unit: usize, texture: GLuint) {
+    if self.active_unit != Some(unit) {
+      unsafe { gl.ActiveTexture(gl::TEXTURE0 + unit as GLenum) };
+      self.active_unit = Some(unit);
+    }
+    if self.textures[unit] != Some(texture) {
+      unsafe { gl.BindTexture(gl::TEXTURE_2D, texture) };
+      self.textures[unit] = Some(texture);
+    }
+  }
+
+  pub fn set_blend(&mut self, gl: &Gl, mode: BlendMode) {
+    if self.blend != Some(mode) {
+      unsafe {
+        match mode {
+          BlendMode::Disabled => gl.Disable(gl::BLEND),
+          BlendMode::Alpha => {
+            gl.Enable(gl::BLEND);
+            gl.BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+          }
+        }
+      }
+      self.blend = Some(mode);
+    }
+  }
+
+  pub fn set_depth(&mut self, gl: &Gl, enabled: bool) {
+    if self.depth != Some(enabled) {
+      unsafe {
+        match enabled {
+          true => gl.Enable(gl::DEPTH_TEST),
+          false => gl.Disable(gl::DEPTH_TEST),
+        }
+      }
+      self.depth = Some(enabled);
+    }
+  }
+
+  /// Whether passing fragments write their depth, translucent geometry leaves it alone so it
+  /// doesn't hide what is drawn after it.
+  pub fn set_depth_write(&mut self, gl: &Gl, enabled: bool) {
+    if self.depth_write != Some(enabled) {
+      unsafe { gl.DepthMask(if enabled { gl::TRUE } else { gl::FALSE }) };
+      self.depth_write = Some(enabled);
+    }
+  }
+
+  /// Debug builds only, panics when GL disagrees with the tracker, which means a raw call after
+  /// `pass` bypassed it.
+  pub fn validate(&self, gl: &Gl, pass: &str) {
+    if !cfg!(debug_assertions) {
+      return;
+    }
+
+    let integer = |name: GLenum| {
+      let mut value = 0;
+      unsafe { gl.GetIntegerv(name, &mut value) };
+      value as GLuint
+    };
+    let enabled = |capability: GLenum| unsafe { gl.IsEnabled(capability) == gl::TRUE };
+    let check = |what: &str, tracked: Option<GLuint>, actual: GLuint| {
+      if let Some(tracked) = tracked {
+        assert_eq!(tracked, actual, "{} changed behind the state tracker in the {} pass", what, pass);
+      }
+    };
+
+    check("program", self.program, integer(gl::CURRENT_PROGRAM));
+    check("vertex array", self.vao, integer(gl::VERTEX_ARRAY_BINDING));
+    let active = integer(gl::ACTIVE_TEXTURE);
+    check("active texture unit", self.active_unit.map(|unit| gl::TEXTURE0 + unit as GLenum), active);
+    for (unit, texture) in self.textures.iter().enumerate() {
+      unsafe { gl.ActiveTexture(gl::TEXTURE0 + unit as GLenum) };
+      check("texture binding", *texture, integer(gl::TEXTURE_BINDING_2D));
+    }
+    unsafe { gl.ActiveTexture(active) };
+
+    check("depth test", self.depth.map(GLuint::from), enabled(gl::DEPTH_TEST) as GLuint);
+    let mut depth_write = gl::FALSE;
+    unsafe { gl.GetBooleanv(gl::DEPTH_WRITEMASK, &mut depth_write) };
+    check("depth write", self.depth_write.map(GLuint::from), GLuint::from(depth_write == gl::TRUE));
+    let blend = match enabled(gl::BLEND) {
+      false => BlendMode::Disabled,
+      true if (integer(gl::BLEND_SRC_RGB), integer(gl::BLEND_DST_RGB)) == (gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA) => {
+        BlendMode::Alpha
+      }
+      true => panic!("unknown blend function in the {} pass", pass),
+    };
+    if let Some(tracked) = self.blend {
+      assert_eq!(tracked, blend, "blending changed behind the state tracker in the {} pass", pass);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::cell::RefCell;
+
+  thread_local! {
+    static CALLS: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+  }
+
+  fn record(call: &'static str) {
+    CALLS.with(|calls| calls.borrow_mut().push(call));
+  }
+
+  extern "system" fn use_program(_: GLuint) {
+    record("UseProgram");
+  }
+  extern "system" fn bind_vertex_array(_: GLuint) {
+    record("BindVertexArray");
+  }
+  extern "system" fn active_texture(_: GLenum) {
+    record("ActiveTexture");
+  }
+  extern "system" fn bind_texture(_: GLenum, _: GLuint) {
+    record("BindTexture");
+  }
+  extern "system" fn enable(_: GLenum) {
+    record("Enable");
+  }
+  extern "system" fn disable(_: GLenum) {
+    record("Disable");
+  }
+  extern "system" fn blend_func(_: GLenum, _: GLenum) {
+    record("BlendFunc");
+  }
+  extern "system" fn depth_mask(_: GLboolean) {
+    record("DepthMask");
+  }
+
+  /// A GL that only records which of the tracked calls reached it.
+  fn recording_gl() -> Gl {
+    Gl::load_with(|name| match name {
+      "glUseProgram" => use_program as *const GLvoid,
+      "glBindVertexArray" => bind_vertex_array as *const GLvoid,
+      "glActiveTexture" => active_texture as *const GLvoid,
+      "glBindTexture" => bind_texture as *const GLvoid,
+      "glEnable" => enable as *const GLvoid,
+      "glDisable" => disable as *const GLvoid,
+      "glBlendFunc" => blend_func as *const GLvoid,
+      "glDepthMask" => depth_mask as *const GLvoid,
+      _ => std::ptr::null(),
+    })
+  }
+
+  fn calls() -> Vec<&'static str> {
+    CALLS.with(|calls| std::mem::take(&mut *calls.borrow_mut()))
+  }
+
+  #[test]
+  fn setting_what_is_already_set_never_reaches_gl() {
+    let gl = recording_gl();
+    let mut state = GlState::default();
+    state.reset_to_known(&gl);
+    calls();
+
+    state.bind_program(&gl, 0);
+    state.bind_vao(&gl, 0);
+    state.bind_texture(&gl, 0, 0);
+    state.set_blend(&gl, BlendMode::Disabled);
+    state.set_depth(&gl, false);
+    state.set_depth_write(&gl, true);
+    assert!(calls().is_empty());
+
+    state.bind_program(&gl, 3);
+    state.bind_program(&gl, 3);
+    state.set_blend(&gl, BlendMode::Alpha);
+    state.set_blend(&gl, BlendMode::Alpha);
+    state.set_depth_write(&gl, false);
+    state.set_depth_write(&gl, false);
+    assert_eq!(calls(), ["UseProgram", "Enable", "BlendFunc", "DepthMask"]);
+  }
+
+  #[test]
+  fn textures_switch_units_only_when_they_have_to() {
+    let gl = recording_gl();
+    let mut state = GlState::default();
+    state.reset_to_known(&gl);
+    calls();
+
+    // the reset leaves unit 0 active
+    state.bind_texture(&gl, 0, 7);
+    assert_eq!(calls(), ["BindTexture"]);
+    // another unit with what it already holds only switches the unit
+    state.bind_texture(&gl, 1, 0);
+    assert_eq!(calls(), ["ActiveTexture"]);
+    state.bind_texture(&gl, 0, 7);
+    assert_eq!(calls(), ["ActiveTexture"]);
+  }
+
+  #[test]
+  fn an_unknown_state_always_reaches_gl() {
+    let gl = recording_gl();
+    let mut state = GlState::default();
+    state.bind_program(&gl, 0);
+    state.set_depth(&gl, false);
+    state.bind_texture(&gl, 2, 0);
+    assert_eq!(calls(), ["UseProgram", "Disable", "ActiveTexture", "BindTexture"]);
+
+    // a reset forgets everything and sets it all again, even what it already knew
+    state.reset_to_known(&gl);
+    let reset = calls();
+    assert_eq!(reset.iter().filter(|&&call| call == "BindTexture").count(), TRACKED_TEXTURE_UNITS);
+    assert!(reset.contains(&"UseProgram") && reset.contains(&"DepthMask"));
+  }
+}
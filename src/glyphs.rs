@@ -0,0 +1,167 @@
+use lyon::{
+  math::point,
+  path::{Path, Winding},
+};
+use PathCommand::*;
+
+#[derive(Debug, Clone, Copy)]
+pub enum PathCommand {
+  MoveTo(f32, f32),
+  LineTo(f32, f32),
+  Close,
+  Circle(f32, f32, f32),
+}
+
+/// Diamond with a dot in the middle.
+const AMMO: &[PathCommand] = &[
+  MoveTo(0.0, 4.0),
+  LineTo(4.0, 0.0),
+  LineTo(0.0, -4.0),
+  LineTo(-4.0, 0.0),
+  Close,
+  Circle(0.0, 0.0, 1.0),
+];
+
+/// Two chevrons pointing up.
+const BOOST: &[PathCommand] = &[
+  MoveTo(-3.0, 0.5),
+  LineTo(0.0, 3.5),
+  LineTo(3.0, 0.5),
+  LineTo(3.0, -1.0),
+  LineTo(0.0, 2.0),
+  LineTo(-3.0, -1.0),
+  Close,
+  MoveTo(-3.0, -2.5),
+  LineTo(0.0, 0.5),
+  LineTo(3.0, -2.5),
+  LineTo(3.0, -4.0),
+  LineTo(0.0, -1.0),
+  LineTo(-3.0, -4.0),
+  Close,
+];
+
+/// Arrowhead with a dot in the notch.
+const ATTACK: &[PathCommand] = &[
+  MoveTo(0.0, 5.0),
+  LineTo(4.5, -4.0),
+  LineTo(0.0, -1.5),
+  LineTo(-4.5, -4.0),
+  Close,
+  Circle(0.0, -4.0, 1.0),
+];
+
+/// Lopsided octagon.
+const ROCK: &[PathCommand] = &[
+  MoveTo(8.0, 1.0),
+  LineTo(4.5, 6.5),
+  LineTo(-1.0, 8.0),
+  LineTo(-6.0, 5.0),
+  LineTo(-8.0, -1.0),
+  LineTo(-5.0, -6.5),
+  LineTo(1.5, -8.0),
+  LineTo(6.5, -4.5),
+  Close,
+];
+
+/// Pickup and enemy outlines, centered on the origin. A new pickup adds data here, not tessellation code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GlyphId {
+  Ammo,
+  Boost,
+  Attack,
+  Rock,
+}
+
+impl GlyphId {
+  pub const ALL: [GlyphId; 4] = [GlyphId::Ammo, GlyphId::Boost, GlyphId::Attack, GlyphId::Rock];
+
+  pub fn commands(self) -> &'static [PathCommand] {
+    match self {
+      GlyphId::Ammo => AMMO,
+      GlyphId::Boost => BOOST,
+      GlyphId::Attack => ATTACK,
+      GlyphId::Rock => ROCK,
+    }
+  }
+}
+
+pub fn build_path(commands: &[PathCommand]) -> Path {
+  let mut builder = Path::builder();
+  let mut open = false;
+  for command in commands {
+    match *command {
+      MoveTo(x, y) => {
+        if open {
+          builder.end(false);
+        }
+        builder.begin(point(x, y));
+        open = true;
+      }
+      LineTo(x, y) => {
+        builder.line_to(point(x, y));
+      }
+      Close => {
+        builder.close();
+        open = false;
+      }
+      Circle(x, y, radius) => {
+        if open {
+          builder.end(false);
+          open = false;
+        }
+        builder.add_circle(point(x, y), radius, Winding::Positive);
+      }
+    }
+  }
+  if open {
+    builder.end(false);
+  }
+
+  builder.build()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use lyon::path::Event;
+
+  /// Whether each sub path of `path` was closed, in order.
+  fn closed(path: &Path) -> Vec<bool> {
+    path
+      .iter()
+      .filter_map(|event| match event {
+        Event::End { close, .. } => Some(close),
+        _ => None,
+      })
+      .collect()
+  }
+
+  #[test]
+  fn every_glyph_is_closed_and_fits_its_outline() {
+    for glyph in GlyphId::ALL {
+      let path = build_path(glyph.commands());
+      assert!(closed(&path).iter().all(|&close| close), "{:?}", glyph);
+      let bounds = lyon::algorithms::aabb::bounding_box(&path);
+      assert!(bounds.min.x >= -8.0 && bounds.min.y >= -8.0, "{:?}", glyph);
+      assert!(bounds.max.x <= 8.0 && bounds.max.y <= 8.0, "{:?}", glyph);
+    }
+    assert_eq!(closed(&build_path(GlyphId::Boost.commands())).len(), 2);
+    assert_eq!(closed(&build_path(GlyphId::Ammo.commands())).len(), 2);
+  }
+
+  #[test]
+  fn open_sub_paths_are_ended_instead_of_joined() {
+    // a move ends the open path before it, a circle too, and the last one is ended at the end
+    let path = build_path(&[
+      MoveTo(0.0, 0.0),
+      LineTo(1.0, 0.0),
+      MoveTo(0.0, 1.0),
+      LineTo(1.0, 1.0),
+      Circle(0.0, 0.0, 1.0),
+      MoveTo(0.0, 2.0),
+      LineTo(1.0, 2.0),
+    ]);
+    assert_eq!(closed(&path), vec![false, false, true, false]);
+    assert_eq!(closed(&build_path(&[])), Vec::<bool>::new());
+  }
+}
@@ -0,0 +1,313 @@
+use crate::resources::{
+  CircleGeometry, DrawBuffers, LineGeometry, ParticleGeometry, QuadGeometry, ScreenGeometry, TextBuffers, TextLayer,
+};
+use bevy_ecs::{
+  archetype::ArchetypeComponentId,
+  component::ComponentId,
+  prelude::*,
+  query::Access,
+  schedule::SystemLabelId,
+  system::{IntoSystem, System},
+};
+use std::{
+  any::{Any, TypeId},
+  borrow::Cow,
+  cell::Cell,
+  collections::BTreeMap,
+  panic::{catch_unwind, AssertUnwindSafe},
+  sync::{Arc, Mutex, Once},
+};
+
+const MAX_CONSECUTIVE_FAILURES: u8 = 3;
+
+thread_local! {
+  static IN_COSMETIC_SYSTEM: Cell<bool> = const { Cell::new(false) };
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct HealthState {
+  pub failures: u32,
+  pub consecutive_failures: u8,
+  pub disabled: bool,
+  pub last_error: Option<String>,
+}
+
+/// Tracks panics of cosmetic systems. Gameplay systems are registered as usual and stay fail-fast,
+/// cosmetic ones are registered through [`SystemHealth::cosmetic`] so a panic only costs a frame of
+/// their output.
+#[derive(Debug, Default, Clone, Resource)]
+pub struct SystemHealth {
+  states: Arc<Mutex<BTreeMap<Cow<'static, str>, HealthState>>>,
+}
+
+impl SystemHealth {
+  pub fn cosmetic<Params, T>(&self, system: T) -> Cosmetic<T::System>
+  where
+    T: IntoSystem<(), (), Params> + Clone + Send + Sync + 'static,
+  {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+      let default_hook = std::panic::take_hook();
+      std::panic::set_hook(Box::new(move |info| {
+        // cosmetic panics are reported by the wrapper, once
+        if !IN_COSMETIC_SYSTEM.with(|flag| flag.get()) {
+          default_hook(info);
+        }
+      }));
+    });
+
+    Cosmetic {
+      system: IntoSystem::into_system(system.clone()),
+      rebuild: Box::new(move || IntoSystem::into_system(system.clone())),
+      health: self.clone(),
+      panicked: false,
+    }
+  }
+
+  pub fn failing(&self) -> Vec<(Cow<'static, str>, HealthState)> {
+    self
+      .states
+      .lock()
+      .unwrap()
+      .iter()
+      .filter(|(_, state)| state.failures > 0)
+      .map(|(name, state)| (name.clone(), state.clone()))
+      .collect()
+  }
+
+  fn is_disabled(&self, name: &str) -> bool {
+    self
+      .states
+      .lock()
+      .unwrap()
+      .get(name)
+      .is_some_and(|state| state.disabled)
+  }
+
+  fn report(&self, name: Cow<'static, str>, result: Result<(), Box<dyn Any + Send>>) {
+    let mut states = self.states.lock().unwrap();
+    match result {
+      Ok(()) => {
+        if let Some(state) = states.get_mut(&name) {
+          state.consecutive_failures = 0;
+        }
+      }
+      Err(payload) => {
+        let message = payload
+          .downcast_ref::<&str>()
+          .map(|message| message.to_string())
+          .or_else(|| payload.downcast_ref::<String>().cloned())
+          .unwrap_or_else(|| String::from("unknown panic"));
+        let state = states.entry(name.clone()).or_default();
+        if state.last_error.as_ref() != Some(&message) {
+          eprintln!("cosmetic system {} panicked: {}", name, message);
+        }
+        state.last_error = Some(message);
+        state.failures += 1;
+        state.consecutive_failures += 1;
+        if state.consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
+          eprintln!("cosmetic system {} disabled for this session", name);
+          state.disabled = true;
+        }
+      }
+    }
+  }
+}
+
+pub struct Cosmetic<S> {
+  system: S,
+  /// A fresh copy of `system`, bevy has no way to throw away the commands a system queued.
+  rebuild: Box<dyn Fn() -> S + Send + Sync>,
+  health: SystemHealth,
+  /// Set by a panicking run, its commands are dropped instead of applied.
+  panicked: bool,
+}
+
+/// A draw buffer a cosmetic system can leave half written when it panics.
+trait Rewind: Resource {
+  fn mark(&self) -> [usize; 4];
+  fn rewind(&mut self, mark: [usize; 4]);
+}
+
+impl<T> Rewind for DrawBuffers<T>
+where
+  DrawBuffers<T>: Resource,
+{
+  fn mark(&self) -> [usize; 4] {
+    [self.vertex_buffer.vertices.len(), self.vertex_buffer.indices.len(), 0, 0]
+  }
+
+  fn rewind(&mut self, [vertices, indices, ..]: [usize; 4]) {
+    self.vertex_buffer.vertices.truncate(vertices);
+    self.vertex_buffer.indices.truncate(indices);
+  }
+}
+
+impl Rewind for TextBuffers {
+  fn mark(&self) -> [usize; 4] {
+    let len = |layer: &TextLayer| [layer.vertex_buffer.len(), layer.index_buffer.len()];
+    let ([a, b], [c, d]) = (len(&self.bitmap), len(&self.sdf));
+    [a, b, c, d]
+  }
+
+  fn rewind(&mut self, [a, b, c, d]: [usize; 4]) {
+    self.bitmap.vertex_buffer.truncate(a);
+    self.bitmap.index_buffer.truncate(b);
+    self.sdf.vertex_buffer.truncate(c);
+    self.sdf.index_buffer.truncate(d);
+  }
+}
+
+type Marks = Vec<(unsafe fn(&World, [usize; 4]), [usize; 4])>;
+
+/// Remembers how full `R` is if the system with `access` may write to it.
+fn mark<R: Rewind>(world: &World, access: &Access<ComponentId>, marks: &mut Marks) {
+  let Some(id) = world.components().get_resource_id(TypeId::of::<R>()) else {
+    return;
+  };
+  if let Some(buffers) = world.get_resource::<R>().filter(|_| access.has_write(id)) {
+    marks.push((rewind::<R>, buffers.mark()));
+  }
+}
+
+/// # Safety
+/// Only for a resource the running system has write access to, see [`mark`].
+unsafe fn rewind<R: Rewind>(world: &World, mark: [usize; 4]) {
+  if let Some(mut buffers) = world.get_resource_unchecked_mut::<R>() {
+    buffers.rewind(mark);
+  }
+}
+
+fn marks(world: &World, access: &Access<ComponentId>) -> Marks {
+  let mut marks = Marks::new();
+  mark::<CircleGeometry>(world, access, &mut marks);
+  mark::<QuadGeometry>(world, access, &mut marks);
+  mark::<LineGeometry>(world, access, &mut marks);
+  mark::<ParticleGeometry>(world, access, &mut marks);
+  mark::<ScreenGeometry>(world, access, &mut marks);
+  mark::<TextBuffers>(world, access, &mut marks);
+  marks
+}
+
+impl<S: System<In = (), Out = ()>> System for Cosmetic<S> {
+  type In = ();
+  type Out = ();
+
+  fn name(&self) -> Cow<'static, str> {
+    self.system.name()
+  }
+
+  fn component_access(&self) -> &Access<ComponentId> {
+    self.system.component_access()
+  }
+
+  fn archetype_component_access(&self) -> &Access<ArchetypeComponentId> {
+    self.system.archetype_component_access()
+  }
+
+  fn is_send(&self) -> bool {
+    self.system.is_send()
+  }
+
+  fn is_exclusive(&self) -> bool {
+    self.system.is_exclusive()
+  }
+
+  unsafe fn run_unsafe(&mut self, input: Self::In, world: &World) -> Self::Out {
+    let name = self.system.name();
+    if self.health.is_disabled(&name) {
+      return;
+    }
+
+    // a panic can leave a shape half tessellated, the frame goes without the system's output instead
+    let marks = marks(world, self.system.component_access());
+    IN_COSMETIC_SYSTEM.with(|flag| flag.set(true));
+    let result = catch_unwind(AssertUnwindSafe(|| self.system.run_unsafe(input, world)));
+    IN_COSMETIC_SYSTEM.with(|flag| flag.set(false));
+
+    if result.is_err() {
+      for (rewind, mark) in marks {
+        rewind(world, mark);
+      }
+      self.panicked = true;
+    }
+    self.health.report(name, result);
+  }
+
+  /// Drops the commands of a panicked run along with the system, a fresh one takes its place with
+  /// its `Local`s reset. Entities the dropped commands spawned are left behind empty.
+  fn apply_buffers(&mut self, world: &mut World) {
+    if std::mem::take(&mut self.panicked) {
+      let mut system = (self.rebuild)();
+      system.initialize(world);
+      self.system = system;
+    } else {
+      self.system.apply_buffers(world);
+    }
+  }
+
+  fn initialize(&mut self, world: &mut World) {
+    self.system.initialize(world)
+  }
+
+  fn update_archetype_component_access(&mut self, world: &World) {
+    self.system.update_archetype_component_access(world)
+  }
+
+  fn check_change_tick(&mut self, change_tick: u32) {
+    self.system.check_change_tick(change_tick)
+  }
+
+  fn default_labels(&self) -> Vec<SystemLabelId> {
+    self.system.default_labels()
+  }
+
+  fn get_last_change_tick(&self) -> u32 {
+    self.system.get_last_change_tick()
+  }
+
+  fn set_last_change_tick(&mut self, last_change_tick: u32) {
+    self.system.set_last_change_tick(last_change_tick)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[derive(Component)]
+  struct Spark;
+
+  #[derive(Default, Resource)]
+  struct Runs(u32);
+
+  fn sparks_then_panics(mut commands: Commands, mut lines: ResMut<LineGeometry>, mut runs: ResMut<Runs>) {
+    runs.0 += 1;
+    commands.spawn(Spark);
+    lines.vertex_buffer.indices.extend([0, 1, 2]);
+    panic!("half way through");
+  }
+
+  #[test]
+  fn a_panicking_system_drops_its_output_and_is_disabled_after_three_in_a_row() {
+    let health = SystemHealth::default();
+    let mut world = World::default();
+    world.insert_resource(LineGeometry::new(0, 0, 0));
+    world.init_resource::<Runs>();
+    let mut stage = SystemStage::single_threaded().with_system(health.cosmetic(sparks_then_panics));
+
+    for run in 1..=MAX_CONSECUTIVE_FAILURES as u32 {
+      stage.run(&mut world);
+      assert_eq!(world.resource::<Runs>().0, run);
+      assert!(world.resource::<LineGeometry>().vertex_buffer.indices.is_empty());
+      assert_eq!(world.query::<&Spark>().iter(&world).count(), 0);
+    }
+    let (name, state) = health.failing().pop().unwrap();
+    assert!(name.ends_with("sparks_then_panics"));
+    assert!(state.disabled);
+    assert_eq!(state.last_error.as_deref(), Some("half way through"));
+
+    stage.run(&mut world);
+    assert_eq!(world.resource::<Runs>().0, MAX_CONSECUTIVE_FAILURES as u32);
+  }
+}
@@ -0,0 +1,83 @@
+use crate::{
+  color::ColorGl,
+  environment::{HEATMAP_COLUMNS, HEATMAP_ROWS, RGB_CLEAR_COLOR, RGB_COLOR_DEATH, RGB_COLOR_TRAIL},
+  resources::WorldBounds,
+  units::WorldPos,
+};
+
+/// Index of the grid cell holding `position`, `None` outside of the playfield.
+pub fn cell_index(bounds: &WorldBounds, position: WorldPos) -> Option<usize> {
+  if bounds.is_outside(position, 0.0) {
+    return None;
+  }
+  let (offset, size) = (position - bounds.corners(0.0).0, bounds.size());
+  let column = ((offset.x() / size.x * HEATMAP_COLUMNS as f32) as usize).min(HEATMAP_COLUMNS - 1);
+  let row = ((offset.y() / size.y * HEATMAP_ROWS as f32) as usize).min(HEATMAP_ROWS - 1);
+  Some(row * HEATMAP_COLUMNS + column)
+}
+
+/// Log scaled `count / max` in [0, 1], so the cells around the spawn point don't wash out the rest.
+pub fn normalize(count: u32, max: u32) -> f32 {
+  if max == 0 {
+    return 0.0;
+  }
+  (count as f32).ln_1p() / (max as f32).ln_1p()
+}
+
+/// Background to trail orange to death red. The scene has no blending, so the background color stands
+/// in for transparent.
+pub fn heat_color(heat: f32) -> ColorGl {
+  let heat = heat.clamp(0.0, 1.0);
+  if heat < 0.5 {
+    ColorGl::from(RGB_CLEAR_COLOR).lerp(ColorGl::from(RGB_COLOR_TRAIL), heat * 2.0)
+  } else {
+    ColorGl::from(RGB_COLOR_TRAIL).lerp(ColorGl::from(RGB_COLOR_DEATH), (heat - 0.5) * 2.0)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn positions_bin_row_by_row_and_the_far_edges_stay_in_the_grid() {
+    // cells of 10 by 10, away from the origin
+    let bounds = WorldBounds {
+      min: glam::vec2(10.0, 20.0),
+      max: glam::vec2(10.0 + 10.0 * HEATMAP_COLUMNS as f32, 20.0 + 10.0 * HEATMAP_ROWS as f32),
+    };
+    let cell = |x, y| cell_index(&bounds, WorldPos::new(x, y));
+    assert_eq!(cell(10.0, 20.0), Some(0));
+    assert_eq!(cell(19.5, 29.5), Some(0));
+    assert_eq!(cell(20.0, 20.0), Some(1));
+    assert_eq!(cell(10.0, 30.0), Some(HEATMAP_COLUMNS));
+    assert_eq!(cell(bounds.max.x, bounds.max.y), Some(HEATMAP_COLUMNS * HEATMAP_ROWS - 1));
+    assert_eq!(cell(9.0, 25.0), None);
+    assert_eq!(cell(15.0, bounds.max.y + 1.0), None);
+  }
+
+  #[test]
+  fn counts_scale_logarithmically_up_to_the_busiest_cell() {
+    assert_eq!(normalize(0, 0), 0.0);
+    assert_eq!(normalize(3, 0), 0.0);
+    assert_eq!(normalize(0, 100), 0.0);
+    assert_eq!(normalize(100, 100), 1.0);
+    // a single visit next to a hundred still shows, a linear scale would make it 0.01
+    let once = normalize(1, 100);
+    assert!((once - 2f32.ln() / 101f32.ln()).abs() < 1e-6, "{}", once);
+    assert!(normalize(10, 100) > once && normalize(10, 100) < normalize(50, 100));
+  }
+
+  #[test]
+  fn heat_runs_from_the_background_over_the_trail_to_death_red() {
+    let close = |a: ColorGl, b: ColorGl| a.to_array().iter().zip(b.to_array()).all(|(a, b)| (a - b).abs() < 1e-6);
+    let (clear, trail, death) = (RGB_CLEAR_COLOR.into(), RGB_COLOR_TRAIL.into(), RGB_COLOR_DEATH.into());
+    assert_eq!(heat_color(0.0), clear);
+    assert_eq!(heat_color(0.5), trail);
+    assert!(close(heat_color(1.0), death));
+    assert!(close(heat_color(0.25), ColorGl::from(RGB_CLEAR_COLOR).lerp(trail, 0.5)));
+    // out of range clamps
+    assert_eq!(heat_color(-1.0), clear);
+    assert!(close(heat_color(7.0), death));
+  }
+}
@@ -0,0 +1,126 @@
+use crate::{
+  environment::{HIGH_SCORES_FILE, HIGH_SCORES_KEPT},
+  error::Error,
+  output_files::{Backpressure, OutputWriter, WriteJob},
+  profiles::Profile,
+};
+use bevy_ecs::prelude::*;
+use std::{
+  io::Write,
+  path::PathBuf,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HighScore {
+  pub score: u32,
+  /// Seconds since the epoch.
+  pub timestamp: u64,
+}
+
+/// The best [`HIGH_SCORES_KEPT`] runs, best first, in the [`HIGH_SCORES_FILE`] of the profile. Without
+/// a data dir the table only lasts for the session.
+#[derive(Debug, Default, Resource)]
+pub struct HighScores {
+  pub entries: Vec<HighScore>,
+  /// Where the last run landed in the table, if it made it.
+  pub latest: Option<usize>,
+  path: Option<PathBuf>,
+}
+
+impl HighScores {
+  /// Reads the profile's table. A missing or broken file gives an empty table, a broken one is reported
+  /// and overwritten by the next save.
+  pub fn load(profile: &Profile) -> Self {
+    let path = profile.file(HIGH_SCORES_FILE);
+    let entries = match path.as_ref().map(std::fs::read_to_string) {
+      Some(Ok(text)) => parse(&text).unwrap_or_else(|e| {
+        eprintln!("{}, starting with an empty table", e);
+        Vec::new()
+      }),
+      _ => Vec::new(),
+    };
+
+    Self {
+      entries,
+      latest: None,
+      path,
+    }
+  }
+
+  /// Puts `score` in its place, after the runs with the same score, and drops what falls off the end.
+  pub fn insert(&mut self, score: u32) {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let index = self.entries.partition_point(|entry| entry.score >= score);
+    self.entries.insert(index, HighScore { score, timestamp });
+    self.entries.truncate(HIGH_SCORES_KEPT);
+    self.latest = (index < HIGH_SCORES_KEPT).then_some(index);
+  }
+
+  pub fn save(&self, writer: Option<&OutputWriter>) {
+    let Some(path) = &self.path else {
+      return;
+    };
+    let mut bytes = Vec::new();
+    write(&mut bytes, &self.entries).unwrap();
+    let job = WriteJob {
+      backpressure: Backpressure::DropOldest,
+      ..WriteJob::new(path.clone(), bytes, "high scores")
+    };
+    job.submit(writer);
+  }
+}
+
+/// One object per line, `[{"score": 1200, "timestamp": 1700000000}, ...]`.
+fn write(out: &mut impl Write, entries: &[HighScore]) -> std::io::Result<()> {
+  writeln!(out, "[")?;
+  for (i, entry) in entries.iter().enumerate() {
+    let separator = if i + 1 < entries.len() { "," } else { "" };
+    writeln!(out, "  {{\"score\": {}, \"timestamp\": {}}}{}", entry.score, entry.timestamp, separator)?;
+  }
+  writeln!(out, "]")
+}
+
+/// The subset of JSON [`write`] produces: an array of objects with an integer `score` and `timestamp`,
+/// in any order and with any whitespace. Entries come back sorted and cut to [`HIGH_SCORES_KEPT`].
+pub fn parse(text: &str) -> Result<Vec<HighScore>, Error> {
+  let invalid = |reason| Error::InvalidHighScores { reason };
+  let body = text
+    .trim()
+    .strip_prefix('[')
+    .and_then(|text| text.strip_suffix(']'))
+    .ok_or_else(|| invalid("expected an array"))?;
+
+  let mut entries = Vec::new();
+  let mut rest = body.trim();
+  while !rest.is_empty() {
+    let object = rest.strip_prefix('{').ok_or_else(|| invalid("expected an object"))?;
+    let (fields, after) = object.split_once('}').ok_or_else(|| invalid("unclosed object"))?;
+
+    let (mut score, mut timestamp) = (None, None);
+    for field in fields.split(',') {
+      let (key, value) = field.split_once(':').ok_or_else(|| invalid("expected \"key\": value"))?;
+      let value = value.trim().parse::<u64>().map_err(|_| invalid("expected an unsigned integer"))?;
+      match key.trim() {
+        "\"score\"" => score = Some(u32::try_from(value).map_err(|_| invalid("score out of range"))?),
+        "\"timestamp\"" => timestamp = Some(value),
+        _ => return Err(invalid("unknown key")),
+      }
+    }
+    entries.push(HighScore {
+      score: score.ok_or_else(|| invalid("missing score"))?,
+      timestamp: timestamp.ok_or_else(|| invalid("missing timestamp"))?,
+    });
+
+    rest = after.trim();
+    rest = match rest.strip_prefix(',') {
+      Some(next) => next.trim_start(),
+      None if rest.is_empty() => rest,
+      None => return Err(invalid("expected a comma between objects")),
+    };
+  }
+
+  entries.sort_by_key(|entry| std::cmp::Reverse(entry.score));
+  entries.truncate(HIGH_SCORES_KEPT);
+  Ok(entries)
+}
@@ -0,0 +1,345 @@
+use crate::resources::{GamepadState, MouseInput};
+use sdl2::{controller::Button, keyboard::Keycode};
+use std::{collections::HashSet, fmt};
+use InputSource::{ControllerButton, Key};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+  TurnLeft,
+  TurnRight,
+  Boost,
+  Brake,
+  Shoot,
+  SelfDestruct,
+  Bomb,
+}
+
+impl Action {
+  pub const ALL: [Action; 7] = [
+    Action::TurnLeft,
+    Action::TurnRight,
+    Action::Boost,
+    Action::Brake,
+    Action::Shoot,
+    Action::SelfDestruct,
+    Action::Bomb,
+  ];
+
+  /// As written in the bindings file.
+  pub fn name(self) -> &'static str {
+    match self {
+      Action::TurnLeft => "turn_left",
+      Action::TurnRight => "turn_right",
+      Action::Boost => "boost",
+      Action::Brake => "brake",
+      Action::Shoot => "shoot",
+      Action::SelfDestruct => "self_destruct",
+      Action::Bomb => "bomb",
+    }
+  }
+
+  pub fn from_name(name: &str) -> Option<Action> {
+    Action::ALL.into_iter().find(|action| action.name() == name)
+  }
+}
+
+/// Anything an action can be bound to. Mouse buttons go by SDL's numbers, 1 to 3 are left, middle and
+/// right and 4 and 5 the extra buttons. The wheel only ever pulses, see [`WheelPulses`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputSource {
+  Key(Keycode),
+  MouseButton(u8),
+  WheelUp,
+  WheelDown,
+  ControllerButton(Button),
+}
+
+const MOUSE_BUTTON_NAMES: [&str; 5] = ["left", "middle", "right", "x1", "x2"];
+
+impl InputSource {
+  /// Reads what [`fmt::Display`] writes, e.g. `key:Space`, `mouse:middle`, `wheel:up` or `pad:a`. Keys
+  /// and buttons go by SDL's names, except the comma key which is `key:Comma` so lists can split on it.
+  pub fn parse(text: &str) -> Option<InputSource> {
+    let (kind, name) = text.trim().split_once(':')?;
+    match (kind, name) {
+      ("key", "Comma") => Some(InputSource::Key(Keycode::Comma)),
+      ("key", name) => Keycode::from_name(name).map(InputSource::Key),
+      ("mouse", name) => {
+        let i = MOUSE_BUTTON_NAMES.iter().position(|known| *known == name)?;
+        Some(InputSource::MouseButton(i as u8 + 1))
+      }
+      ("wheel", "up") => Some(InputSource::WheelUp),
+      ("wheel", "down") => Some(InputSource::WheelDown),
+      ("pad", name) => Button::from_string(name).map(InputSource::ControllerButton),
+      _ => None,
+    }
+  }
+
+  /// Whether the source is down this tick, the wheel only on the tick of its pulse.
+  pub fn is_held(self, keycodes: &HashSet<Keycode>, mouse: &MouseInput, gamepad: &GamepadState) -> bool {
+    match self {
+      InputSource::Key(keycode) => keycodes.contains(&keycode),
+      InputSource::MouseButton(button) => mouse.buttons.contains(&button),
+      InputSource::WheelUp => mouse.wheel_up,
+      InputSource::WheelDown => mouse.wheel_down,
+      InputSource::ControllerButton(button) => gamepad.buttons.contains(&button),
+    }
+  }
+}
+
+impl fmt::Display for InputSource {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      InputSource::Key(Keycode::Comma) => write!(f, "key:Comma"),
+      InputSource::Key(keycode) => write!(f, "key:{}", keycode.name()),
+      InputSource::MouseButton(button) => match MOUSE_BUTTON_NAMES.get((*button as usize).wrapping_sub(1)) {
+        Some(name) => write!(f, "mouse:{}", name),
+        None => write!(f, "mouse:{}", button),
+      },
+      InputSource::WheelUp => write!(f, "wheel:up"),
+      InputSource::WheelDown => write!(f, "wheel:down"),
+      InputSource::ControllerButton(button) => write!(f, "pad:{}", button.string()),
+    }
+  }
+}
+
+/// Wheel notches arrive as events between ticks. Every notch in a direction since the last tick makes
+/// one pulse, a source held for exactly the next tick and released on the one after, so an action bound
+/// to it is pressed once per frame the wheel turned in. A frame without a tick passes the pulse on.
+#[derive(Debug, Default)]
+pub struct WheelPulses {
+  up: bool,
+  down: bool,
+}
+
+impl WheelPulses {
+  /// `y` as SDL reports it, positive away from the user.
+  pub fn add(&mut self, y: i32) {
+    self.up |= y > 0;
+    self.down |= y < 0;
+  }
+
+  /// The pulses of the next tick, up and down.
+  pub fn take(&mut self) -> (bool, bool) {
+    (std::mem::take(&mut self.up), std::mem::take(&mut self.down))
+  }
+}
+
+/// The default keys, see [`crate::resources::KeyBindings`] for others.
+pub const BINDINGS: [(InputSource, Action); 7] = [
+  (Key(Keycode::Left), Action::TurnLeft),
+  (Key(Keycode::Right), Action::TurnRight),
+  (Key(Keycode::Up), Action::Boost),
+  (Key(Keycode::Down), Action::Brake),
+  (Key(Keycode::Space), Action::Shoot),
+  (Key(Keycode::S), Action::SelfDestruct),
+  (Key(Keycode::B), Action::Bomb),
+];
+
+/// `--keys wasd`, self-destruct moves to X as S brakes.
+pub const WASD_BINDINGS: [(InputSource, Action); 7] = [
+  (Key(Keycode::A), Action::TurnLeft),
+  (Key(Keycode::D), Action::TurnRight),
+  (Key(Keycode::W), Action::Boost),
+  (Key(Keycode::S), Action::Brake),
+  (Key(Keycode::Space), Action::Shoot),
+  (Key(Keycode::X), Action::SelfDestruct),
+  (Key(Keycode::B), Action::Bomb),
+];
+
+/// `--co-op`, one set per [`crate::components::PlayerId`]. Neither shares a key with the other, the
+/// first player keeps the arrows and the second takes the wasd side of the keyboard, both bomb with the
+/// key next to their shoot key.
+pub const CO_OP_BINDINGS: [[(InputSource, Action); 7]; 2] = [
+  [
+    (Key(Keycode::Left), Action::TurnLeft),
+    (Key(Keycode::Right), Action::TurnRight),
+    (Key(Keycode::Up), Action::Boost),
+    (Key(Keycode::Down), Action::Brake),
+    (Key(Keycode::RCtrl), Action::Shoot),
+    (Key(Keycode::RShift), Action::SelfDestruct),
+    (Key(Keycode::Return), Action::Bomb),
+  ],
+  [
+    (Key(Keycode::A), Action::TurnLeft),
+    (Key(Keycode::D), Action::TurnRight),
+    (Key(Keycode::W), Action::Boost),
+    (Key(Keycode::S), Action::Brake),
+    (Key(Keycode::LShift), Action::Shoot),
+    (Key(Keycode::X), Action::SelfDestruct),
+    (Key(Keycode::Q), Action::Bomb),
+  ],
+];
+
+/// The first player's controller buttons, turning is also bound to the left stick, see [`merge_stick`].
+pub const PAD_BINDINGS: [(InputSource, Action); 7] = [
+  (ControllerButton(Button::DPadLeft), Action::TurnLeft),
+  (ControllerButton(Button::DPadRight), Action::TurnRight),
+  (ControllerButton(Button::RightShoulder), Action::Boost),
+  (ControllerButton(Button::LeftShoulder), Action::Brake),
+  (ControllerButton(Button::A), Action::Shoot),
+  (ControllerButton(Button::Back), Action::SelfDestruct),
+  (ControllerButton(Button::Y), Action::Bomb),
+];
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Thrust {
+  #[default]
+  Cruise,
+  Boost,
+  Brake,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PlayerInput {
+  pub thrust: Thrust,
+  /// 1 turns left, -1 turns right.
+  pub turn: f32,
+  pub shoot: bool,
+  pub self_destruct: bool,
+  /// Held, the bomb goes off on the tick it is pressed, see [`crate::systems::bomb_system`].
+  pub bomb: bool,
+}
+
+/// Resolves held sources, each with the tick it was pressed on, into one tick of input. Conflicts are
+/// resolved here and nowhere else, whatever kind of source is held:
+/// - an action bound to several held sources counts once, as pressed when the first of them was
+/// - boost and brake held together cancel into cruising, neither drains boost
+/// - left and right held together turn towards the later press, pressed on the same tick they cancel
+pub fn resolve(
+  held: impl IntoIterator<Item = (InputSource, u64)>,
+  bindings: &[(InputSource, Action)],
+) -> PlayerInput {
+  let mut pressed_at = [None::<u64>; 7];
+  for (source, tick) in held {
+    for (_, action) in bindings.iter().filter(|(bound, _)| *bound == source) {
+      let slot = &mut pressed_at[*action as usize];
+      *slot = Some(slot.map_or(tick, |earlier| earlier.min(tick)));
+    }
+  }
+  let action = |action: Action| pressed_at[action as usize];
+
+  let thrust = match (action(Action::Boost), action(Action::Brake)) {
+    (Some(_), None) => Thrust::Boost,
+    (None, Some(_)) => Thrust::Brake,
+    _ => Thrust::Cruise,
+  };
+  let turn = match (action(Action::TurnLeft), action(Action::TurnRight)) {
+    (Some(_), None) => 1.0,
+    (None, Some(_)) => -1.0,
+    (Some(left), Some(right)) if left > right => 1.0,
+    (Some(left), Some(right)) if right > left => -1.0,
+    _ => 0.0,
+  };
+
+  PlayerInput {
+    thrust,
+    turn,
+    shoot: action(Action::Shoot).is_some(),
+    self_destruct: action(Action::SelfDestruct).is_some(),
+    bomb: action(Action::Bomb).is_some(),
+  }
+}
+
+/// Turns with the stick when nothing bound to turning is held, as fast as it is pushed.
+pub fn merge_stick(input: PlayerInput, stick: glam::Vec2) -> PlayerInput {
+  PlayerInput {
+    turn: if input.turn == 0.0 { -stick.x } else { input.turn },
+    ..input
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use InputSource::{MouseButton, WheelDown, WheelUp};
+
+  #[test]
+  fn sources_read_back_what_they_write() {
+    let sources = [
+      Key(Keycode::Space),
+      Key(Keycode::Left),
+      Key(Keycode::J),
+      Key(Keycode::Comma),
+      MouseButton(1),
+      MouseButton(5),
+      WheelUp,
+      WheelDown,
+      ControllerButton(Button::A),
+      ControllerButton(Button::DPadLeft),
+    ];
+    for source in sources {
+      assert_eq!(InputSource::parse(&source.to_string()), Some(source), "{}", source);
+    }
+    assert_eq!(Key(Keycode::Comma).to_string(), "key:Comma");
+    assert_eq!(MouseButton(2).to_string(), "mouse:middle");
+    assert_eq!(InputSource::parse("  wheel:up "), Some(WheelUp));
+  }
+
+  #[test]
+  fn unknown_sources_dont_parse() {
+    for text in ["", "Space", "key:", "key:NoSuchKey", "mouse:x3", "mouse:6", "wheel:left", "pad:nope", "joy:a"] {
+      assert_eq!(InputSource::parse(text), None, "{}", text);
+    }
+    // a button past the named ones still writes something to look up
+    assert_eq!(MouseButton(9).to_string(), "mouse:9");
+  }
+
+  #[test]
+  fn actions_go_by_their_names() {
+    for action in Action::ALL {
+      assert_eq!(Action::from_name(action.name()), Some(action));
+    }
+    assert_eq!(Action::from_name("Shoot"), None);
+  }
+
+  #[test]
+  fn a_wheel_pulse_lasts_one_tick_however_many_notches_made_it() {
+    let mut pulses = WheelPulses::default();
+    assert_eq!(pulses.take(), (false, false));
+    pulses.add(1);
+    pulses.add(3);
+    pulses.add(-1);
+    assert_eq!(pulses.take(), (true, true));
+    assert_eq!(pulses.take(), (false, false));
+    pulses.add(0);
+    assert_eq!(pulses.take(), (false, false));
+  }
+
+  #[test]
+  fn opposite_actions_resolve_without_fighting() {
+    let bindings = [&BINDINGS[..], &PAD_BINDINGS[..]].concat();
+    let input = |held: &[(InputSource, u64)]| resolve(held.iter().copied(), &bindings);
+
+    assert_eq!(input(&[]), PlayerInput::default());
+    assert_eq!(input(&[(Key(Keycode::Up), 3), (Key(Keycode::Down), 5)]).thrust, Thrust::Cruise);
+    assert_eq!(input(&[(Key(Keycode::Up), 3)]).thrust, Thrust::Boost);
+    // the later turn wins, on the same tick they cancel
+    assert_eq!(input(&[(Key(Keycode::Left), 2), (Key(Keycode::Right), 7)]).turn, -1.0);
+    assert_eq!(input(&[(Key(Keycode::Left), 7), (Key(Keycode::Right), 2)]).turn, 1.0);
+    assert_eq!(input(&[(Key(Keycode::Left), 4), (Key(Keycode::Right), 4)]).turn, 0.0);
+  }
+
+  #[test]
+  fn an_action_held_twice_counts_from_its_first_press() {
+    let bindings = [&BINDINGS[..], &PAD_BINDINGS[..]].concat();
+    // the pad's left was pressed first, so the later right on the keyboard still wins over both
+    let held = [
+      (ControllerButton(Button::DPadLeft), 1),
+      (Key(Keycode::Right), 5),
+      (Key(Keycode::Left), 9),
+    ];
+    assert_eq!(resolve(held, &bindings).turn, -1.0);
+    // sources nothing is bound to change nothing
+    let input = resolve([(Key(Keycode::Space), 0), (Key(Keycode::Z), 0), (WheelUp, 0)], &bindings);
+    assert_eq!(input, PlayerInput { shoot: true, ..PlayerInput::default() });
+  }
+
+  #[test]
+  fn the_stick_only_turns_without_a_bound_turn() {
+    let still = PlayerInput::default();
+    assert_eq!(merge_stick(still, glam::vec2(0.5, 0.0)).turn, -0.5);
+    let turning = PlayerInput { turn: 1.0, ..still };
+    assert_eq!(merge_stick(turning, glam::vec2(0.5, 0.0)).turn, 1.0);
+  }
+}
@@ -0,0 +1,83 @@
+use crate::environment::INPUT_HISTORY_TICKS;
+use sdl2::keyboard::Keycode;
+use std::collections::{HashSet, VecDeque};
+
+/// The shown actions in history row order, each with its cell in the key cluster.
+pub const ACTIONS: [(Keycode, (f32, f32)); 5] = [
+  (Keycode::Left, (0.0, 0.0)),
+  (Keycode::Down, (1.0, 0.0)),
+  (Keycode::Right, (2.0, 0.0)),
+  (Keycode::Up, (1.0, 1.0)),
+  (Keycode::Space, (3.0, 0.0)),
+];
+
+/// One bit per entry of [`ACTIONS`].
+pub fn input_mask(keycodes: &HashSet<Keycode>) -> u8 {
+  ACTIONS
+    .iter()
+    .enumerate()
+    .filter(|(_, (keycode, _))| keycodes.contains(keycode))
+    .fold(0, |mask, (i, _)| mask | 1 << i)
+}
+
+/// The input masks of the last [`INPUT_HISTORY_TICKS`] ticks, oldest first.
+#[derive(Debug, Default)]
+pub struct InputHistory {
+  ticks: VecDeque<u8>,
+}
+
+impl InputHistory {
+  pub fn push(&mut self, mask: u8) {
+    if self.ticks.len() == INPUT_HISTORY_TICKS {
+      self.ticks.pop_front();
+    }
+    self.ticks.push_back(mask);
+  }
+
+  pub fn latest(&self) -> u8 {
+    self.ticks.back().copied().unwrap_or_default()
+  }
+
+  /// Consecutive ticks with the same mask collapsed into `(mask, length)`, oldest first.
+  pub fn runs(&self) -> Vec<(u8, usize)> {
+    let mut runs = Vec::<(u8, usize)>::new();
+    for &mask in &self.ticks {
+      match runs.last_mut() {
+        Some((last, length)) if *last == mask => *length += 1,
+        _ => runs.push((mask, 1)),
+      }
+    }
+    runs
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn masks_follow_the_action_order_and_ignore_other_keys() {
+    assert_eq!(input_mask(&HashSet::new()), 0);
+    assert_eq!(input_mask(&HashSet::from([Keycode::Left])), 0b00001);
+    assert_eq!(input_mask(&HashSet::from([Keycode::Up, Keycode::Space, Keycode::A])), 0b11000);
+    assert_eq!(input_mask(&ACTIONS.iter().map(|(keycode, _)| *keycode).collect()), 0b11111);
+  }
+
+  #[test]
+  fn history_collapses_runs_and_forgets_the_oldest_ticks() {
+    let mut history = InputHistory::default();
+    assert_eq!(history.latest(), 0);
+    assert!(history.runs().is_empty());
+
+    for mask in [1, 1, 0, 3, 3, 3] {
+      history.push(mask);
+    }
+    assert_eq!(history.latest(), 3);
+    assert_eq!(history.runs(), vec![(1, 2), (0, 1), (3, 3)]);
+
+    for _ in 0..INPUT_HISTORY_TICKS - 1 {
+      history.push(4);
+    }
+    assert_eq!(history.runs(), vec![(3, 1), (4, INPUT_HISTORY_TICKS - 1)]);
+  }
+}
@@ -0,0 +1,108 @@
+use crate::{
+  components::Interpolation,
+  easings::Easing,
+  practice::{Practice, PracticeCommand},
+};
+use bevy_ecs::prelude::*;
+
+/// The entity the curve panel inspects, one of those with an [`Interpolation`]. Its easing can be
+/// overridden live to compare the feel of the others, which goes through [`Practice::dispatch`] like
+/// the other tools and is reverted once it's deselected. Worlds without practice tools can inspect
+/// but not override.
+#[derive(Debug, Default, Resource)]
+pub struct Inspector {
+  pub selected: Option<Entity>,
+  /// The override asked for the selected entity, it only applies with the next tick.
+  overridden: Option<Easing>,
+}
+
+impl Inspector {
+  /// Inspects the entity after the selected one, in the order of their ids, none after the last.
+  pub fn select_next(world: &mut World) {
+    let selected = world.resource::<Inspector>().selected;
+    let mut entities = world.query_filtered::<Entity, With<Interpolation>>().iter(world).collect::<Vec<_>>();
+    entities.sort();
+    let next = entities.into_iter().find(|entity| selected.is_none_or(|selected| *entity > selected));
+    Self::select(world, next);
+  }
+
+  /// Inspects `entity`, reverting the override of the one inspected before.
+  pub fn select(world: &mut World, entity: Option<Entity>) {
+    let mut inspector = world.resource_mut::<Inspector>();
+    let (previous, overridden) = (inspector.selected, inspector.overridden.take());
+    inspector.selected = entity;
+    if let (Some(previous), Some(_)) = (previous, overridden) {
+      if let Some(mut practice) = world.get_resource_mut::<Practice>() {
+        practice.dispatch(PracticeCommand::Easing(previous, None));
+      }
+    }
+  }
+
+  /// Overrides the easing of the inspected entity with the next one of [`Easing::ALL`], returns it.
+  /// Nothing is overridden without a selection or the practice tools.
+  pub fn cycle_easing(world: &mut World) -> Option<Easing> {
+    let inspector = world.resource::<Inspector>();
+    let (selected, overridden) = (inspector.selected?, inspector.overridden);
+    let easing = overridden.or(world.get::<Interpolation>(selected)?.easing()).unwrap_or(Easing::Linear).next();
+    world.get_resource_mut::<Practice>()?.dispatch(PracticeCommand::Easing(selected, Some(easing)));
+    world.resource_mut::<Inspector>().overridden = Some(easing);
+    Some(easing)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    balance::GameBalance,
+    components::{InterpolationMode, Transform},
+    practice::practice_system,
+    resources::{Randoms, WorldBounds},
+    stats::RunStats,
+  };
+  use rand::{rngs::SmallRng, SeedableRng};
+
+  /// Runs the practice tools, then advances every interpolation by `dt` eased in out, the value of each.
+  fn tick(world: &mut World, dt: f32) -> Vec<f32> {
+    let mut system = IntoSystem::into_system(practice_system);
+    system.initialize(world);
+    system.run((), world);
+    system.apply_buffers(world);
+    let mut query = world.query::<&mut Interpolation>();
+    query.iter_mut(world).map(|mut interpolation| interpolation.eval(dt, Easing::InOutCubic).0[0]).collect()
+  }
+
+  #[test]
+  fn the_override_applies_to_the_inspected_entity_until_it_is_deselected() {
+    let mut world = World::new();
+    world.insert_resource(Inspector::default());
+    world.insert_resource(Practice::default());
+    world.insert_resource(RunStats::default());
+    world.insert_resource(GameBalance::default());
+    world.insert_resource(Randoms(SmallRng::seed_from_u64(0)));
+    world.insert_resource(WorldBounds::default());
+    let interpolation = || Interpolation::new(vec![(0.0, 1.0)], 1.0, InterpolationMode::Loop);
+    let entities = [(); 3].map(|_| world.spawn(interpolation()).id());
+    world.spawn(Transform::default());
+
+    // every entity with an interpolation in turn, then none
+    for expected in entities.map(Some).into_iter().chain([None, Some(entities[0])]) {
+      Inspector::select_next(&mut world);
+      assert_eq!(world.resource::<Inspector>().selected, expected);
+    }
+    Inspector::select_next(&mut world);
+    assert_eq!(tick(&mut world, 0.25), [0.0625; 3]);
+    assert!(!world.resource::<Practice>().used);
+
+    assert_eq!(Inspector::cycle_easing(&mut world), Some(Easing::Linear));
+    assert_eq!(tick(&mut world, 0.5), [0.9375, 0.75, 0.9375]);
+    assert_eq!(world.get::<Interpolation>(entities[1]).unwrap().easing(), Some(Easing::Linear));
+    assert!(world.resource::<Practice>().used);
+
+    // deselecting reverts it with the next tick
+    Inspector::select_next(&mut world);
+    assert_eq!(world.resource::<Inspector>().selected, Some(entities[2]));
+    assert_eq!(tick(&mut world, 0.0), [0.9375; 3]);
+    assert!(world.query::<&Interpolation>().iter(&world).all(|i| i.easing_override().is_none()));
+  }
+}
@@ -0,0 +1,53 @@
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
+pub mod app;
+pub mod audio;
+pub mod audit;
+pub mod balance;
+pub mod camera;
+pub mod color;
+pub mod components;
+pub mod draw;
+pub mod easings;
+pub mod environment;
+pub mod error;
+pub mod events;
+pub mod format;
+pub mod gallery;
+pub mod gamepad;
+pub mod gl_state;
+pub mod glyphs;
+pub mod health;
+pub mod heatmap;
+pub mod highscores;
+pub mod input;
+pub mod input_display;
+pub mod inspector;
+pub mod logo;
+pub mod math;
+pub mod memory;
+pub mod menus;
+pub mod minimap;
+pub mod modifiers;
+pub mod objectives;
+pub mod output_files;
+pub mod pack;
+pub mod practice;
+pub mod profiles;
+pub mod prompts;
+pub mod render;
+pub mod replay;
+pub mod resources;
+pub mod rumble;
+pub mod scenario;
+pub mod sdf;
+pub mod settings;
+pub mod shaders;
+pub mod shop;
+pub mod spatial;
+pub mod starfield;
+pub mod stats;
+pub mod systems;
+pub mod tasks;
+pub mod ui;
+pub mod units;
@@ -0,0 +1,108 @@
+/// "BYTEPATH" as polylines on a 4x6 grid, y up.
+const B: &[&[(f32, f32)]] = &[
+  &[(0.0, 0.0), (0.0, 6.0), (3.0, 6.0), (4.0, 5.0), (4.0, 4.0), (3.0, 3.0), (0.0, 3.0)],
+  &[(3.0, 3.0), (4.0, 2.0), (4.0, 1.0), (3.0, 0.0), (0.0, 0.0)],
+];
+const Y: &[&[(f32, f32)]] = &[&[(0.0, 6.0), (2.0, 3.0), (4.0, 6.0)], &[(2.0, 3.0), (2.0, 0.0)]];
+const T: &[&[(f32, f32)]] = &[&[(0.0, 6.0), (4.0, 6.0)], &[(2.0, 6.0), (2.0, 0.0)]];
+const E: &[&[(f32, f32)]] = &[
+  &[(4.0, 6.0), (0.0, 6.0), (0.0, 0.0), (4.0, 0.0)],
+  &[(0.0, 3.0), (3.0, 3.0)],
+];
+const P: &[&[(f32, f32)]] = &[&[
+  (0.0, 0.0),
+  (0.0, 6.0),
+  (3.0, 6.0),
+  (4.0, 5.0),
+  (4.0, 4.0),
+  (3.0, 3.0),
+  (0.0, 3.0),
+]];
+const A: &[&[(f32, f32)]] = &[
+  &[(0.0, 0.0), (0.0, 4.0), (2.0, 6.0), (4.0, 4.0), (4.0, 0.0)],
+  &[(0.0, 3.0), (4.0, 3.0)],
+];
+const H: &[&[(f32, f32)]] = &[
+  &[(0.0, 0.0), (0.0, 6.0)],
+  &[(4.0, 0.0), (4.0, 6.0)],
+  &[(0.0, 3.0), (4.0, 3.0)],
+];
+
+pub const LETTERS: [&[&[(f32, f32)]]; 8] = [B, Y, T, E, P, A, T, H];
+pub const LETTER_WIDTH: f32 = 4.0;
+pub const LETTER_HEIGHT: f32 = 6.0;
+pub const LETTER_SPACING: f32 = 2.0;
+
+pub fn logo_width() -> f32 {
+  LETTERS.len() as f32 * (LETTER_WIDTH + LETTER_SPACING) - LETTER_SPACING
+}
+
+pub fn polyline_length(points: &[(f32, f32)]) -> f32 {
+  points
+    .windows(2)
+    .map(|segment| glam::Vec2::from(segment[0]).distance(glam::Vec2::from(segment[1])))
+    .sum()
+}
+
+/// The first `length` units of the polyline, ending in an interpolated point.
+pub fn truncate_polyline(points: &[(f32, f32)], mut length: f32) -> Vec<glam::Vec2> {
+  let mut truncated = Vec::new();
+  let Some(&first) = points.first() else {
+    return truncated;
+  };
+  truncated.push(glam::Vec2::from(first));
+
+  for segment in points.windows(2) {
+    let (a, b) = (glam::Vec2::from(segment[0]), glam::Vec2::from(segment[1]));
+    let segment_length = a.distance(b);
+    if length <= 0.0 {
+      break;
+    }
+    if length < segment_length {
+      truncated.push(a.lerp(b, length / segment_length));
+      break;
+    }
+    truncated.push(b);
+    length -= segment_length;
+  }
+
+  truncated
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use glam::vec2;
+
+  const ELL: &[(f32, f32)] = &[(0.0, 6.0), (0.0, 0.0), (4.0, 0.0)];
+
+  #[test]
+  fn every_letter_stays_inside_its_cell() {
+    for strokes in LETTERS {
+      for &(x, y) in strokes.iter().copied().flatten() {
+        assert!((0.0..=LETTER_WIDTH).contains(&x) && (0.0..=LETTER_HEIGHT).contains(&y), "{}, {}", x, y);
+      }
+    }
+    assert_eq!(logo_width(), 8.0 * 4.0 + 7.0 * 2.0);
+  }
+
+  #[test]
+  fn lengths_add_up_the_segments() {
+    assert_eq!(polyline_length(ELL), 10.0);
+    assert_eq!(polyline_length(&[(0.0, 0.0), (3.0, 4.0)]), 5.0);
+    assert_eq!(polyline_length(&[(1.0, 1.0)]), 0.0);
+    assert_eq!(polyline_length(&[]), 0.0);
+  }
+
+  #[test]
+  fn truncating_ends_partway_along_a_segment() {
+    assert_eq!(truncate_polyline(ELL, 0.0), vec![vec2(0.0, 6.0)]);
+    assert_eq!(truncate_polyline(ELL, 3.0), vec![vec2(0.0, 6.0), vec2(0.0, 3.0)]);
+    // right on a corner, and a little past it
+    assert_eq!(truncate_polyline(ELL, 6.0), vec![vec2(0.0, 6.0), vec2(0.0, 0.0)]);
+    assert_eq!(truncate_polyline(ELL, 7.0), vec![vec2(0.0, 6.0), vec2(0.0, 0.0), vec2(1.0, 0.0)]);
+    // longer than the polyline, or nothing to truncate
+    assert_eq!(truncate_polyline(ELL, 100.0), vec![vec2(0.0, 6.0), vec2(0.0, 0.0), vec2(4.0, 0.0)]);
+    assert!(truncate_polyline(&[], 5.0).is_empty());
+  }
+}
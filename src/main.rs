@@ -1,163 +1,809 @@
-mod color;
-mod components;
-mod easings;
-mod environment;
-mod events;
-mod render;
-mod resources;
-mod systems;
-
-use crate::{
-  environment::{RGB_CLEAR_COLOR, SCREEN_RENDER_HEIGHT, SCREEN_RENDER_WIDTH},
-  events::GameEvents,
-  render::{calculate_size_for_lines, calculate_size_for_quads, create_text_buffer, Gl},
+use bytepath::{
+  app::{
+    build_draw_schedule, build_gallery_schedule, build_game_schedule, build_headless_world, build_replay_world,
+    build_startup_schedule, build_world, clear_draw_buffers, finish_replay, play_frame, play_replay,
+    record_frame, replay_checkpoint, run_ticks, tick_geometry, truncate_draw_buffers, Lockstep,
+  },
+  audio::{self, AudioAssets, Sfx},
+  balance::Console,
+  camera::CameraFollow,
+  components::{self, BoundsMode},
+  draw::GeometryCache,
+  environment::{
+    GAMEPAD_DEADZONE, MAX_TICKS_PER_FRAME, OUTPUT_QUEUE_CAPACITY, REPLAYS_DIR, REPLAYS_KEPT,
+    SDF_GLYPH_COST, SFX_LOAD_COST, STARFIELD_CHUNK_COST, STRESS_RUNS_DIR, STRESS_RUNS_KEPT,
+  },
+  error::Error,
+  events::EventCounts,
+  gallery::spawn_gallery,
+  gamepad::Gamepads,
+  health::SystemHealth,
+  highscores::HighScores,
+  input::{InputSource, WheelPulses},
+  inspector::Inspector,
+  menus::Menus,
+  modifiers::Modifiers,
+  output_files::{prune, unique_path, write_atomic, OutputWriter, Retention, WriteJob},
+  pack::pack_assets,
+  practice::Practice,
+  profiles::{open_profile, Profiles},
+  prompts::InputDevice,
+  render::{
+    self, calculate_size_for_circles, calculate_size_for_lines, calculate_size_for_particles, calculate_size_for_quads,
+    calculate_size_for_screen, create_draw_buffer, create_text_buffer, Gl, SdfAtlasJob,
+  },
+  replay::Replay,
   resources::*,
-  systems::*,
+  rumble::Rumble,
+  scenario::{FrameSample, StressRecorder, StressScenario},
+  settings::Settings,
+  shaders::ShaderWatcher,
+  shop::PlayerUpgrades,
+  starfield::Starfield,
+  stats::{finish_run, LifetimeStats, RunStats},
+  tasks::{MainThreadTasks, TaskStatus},
+  ui::{UiContext, UiInput},
+  units,
 };
-use bevy_ecs::{event::Events, prelude::*, system::SystemState, world::World};
-use lyon::tessellation::{FillTessellator, StrokeTessellator};
-use rand::SeedableRng;
-use render::{calculate_size_for_circles, create_draw_buffer};
+use bevy_ecs::{prelude::*, system::SystemState};
 use sdl2::{
+  controller::Button,
   event::{Event, WindowEvent},
   keyboard::Keycode,
-  video::GLProfile,
+  messagebox::{show_simple_message_box, MessageBoxFlag},
+  mouse::MouseWheelDirection,
+  video::{GLProfile, SwapInterval},
 };
 use std::{
   collections::HashSet,
+  io::Write,
+  path::Path,
   time::{Duration, Instant},
 };
-use systems::shooting_system;
 
-fn main() -> Result<(), String> {
-  let sdl_context = sdl2::init()?;
-  let sdl_video = sdl_context.video()?;
+fn arg_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+  args
+    .iter()
+    .position(|arg| arg == flag)
+    .and_then(|i| args.get(i + 1))
+    .map(String::as_str)
+}
+
+/// What `event` means to the menus. Mouse positions come in window coordinates, y down, and differ from
+/// drawable pixels on high dpi displays.
+fn ui_inputs(event: &Event, window: &sdl2::video::Window, viewport: render::Viewport) -> Vec<UiInput> {
+  let pointer = |x: i32, y: i32| {
+    let (width, _) = window.size();
+    let (drawable_width, drawable_height) = window.drawable_size();
+    let scale = drawable_width as f32 / width.max(1) as f32;
+    viewport.to_text(glam::vec2(x as f32 * scale, drawable_height as f32 - y as f32 * scale))
+  };
+  match *event {
+    Event::KeyDown {
+      keycode: Some(keycode), ..
+    } => vec![UiInput::Press(InputSource::Key(keycode))],
+    Event::ControllerButtonDown { button, .. } => vec![UiInput::Press(InputSource::ControllerButton(button))],
+    Event::MouseWheel { y, direction, .. } => {
+      let y = if direction == MouseWheelDirection::Flipped { -y } else { y };
+      match y.signum() {
+        1 => vec![UiInput::Press(InputSource::WheelUp)],
+        -1 => vec![UiInput::Press(InputSource::WheelDown)],
+        _ => Vec::new(),
+      }
+    }
+    Event::MouseMotion { x, y, .. } => vec![UiInput::Pointer(pointer(x, y))],
+    // the button for a key capture, the click for the buttons
+    Event::MouseButtonDown { mouse_btn, x, y, .. } => vec![
+      UiInput::Press(InputSource::MouseButton(mouse_btn as u8)),
+      UiInput::Click(pointer(x, y)),
+    ],
+    _ => Vec::new(),
+  }
+}
+
+/// Resize events carry logical units, the viewport needs the drawable size.
+fn update_window_size(window: &sdl2::video::Window, opengl_ctx: &mut render::OpenglCtx) {
+  let (w, h) = window.drawable_size();
+  opengl_ctx.drawable_size = (w as i32, h as i32);
+  opengl_ctx.viewport = render::Viewport::letterbox(opengl_ctx.drawable_size);
+}
+
+/// `--headless <ticks>`, simulates without a window and prints how the run ended. The player shoots
+/// the whole time and turns for half of every second, enough to touch most of the systems. With
+/// `--scenario stress` it stops early once the scenario is over and reports the tick times, there is
+/// no frame to time without a window.
+fn run_headless(
+  seed: u64,
+  ticks: u64,
+  step: Duration,
+  key_bindings: KeyBindings,
+  bounds_mode: BoundsMode,
+  stress: Option<f32>,
+) -> Result<(), Error> {
+  let health = SystemHealth::default();
+  let mut world = build_headless_world(seed, &health, stress.is_some(), &Modifiers::default());
+  world.insert_resource(key_bindings);
+  world.insert_resource(bounds_mode);
+  build_startup_schedule().run(&mut world);
+  let mut schedule = build_game_schedule(&health, stress.is_some());
+  let mut stress_recorder = StressRecorder::default();
+
+  let ticks_per_second = (Duration::from_secs(1).as_nanos() / step.as_nanos()).max(1) as u64;
+  let mut simulated = 0;
+  while simulated < ticks {
+    if stress.is_some() && world.resource::<StressScenario>().finished() {
+      break;
+    }
+    let started = Instant::now();
+    run_ticks(&mut world, &mut schedule, 1, step, |_| {
+      let mut keys = HashSet::from([Keycode::Space]);
+      if simulated % ticks_per_second < ticks_per_second / 2 {
+        keys.insert(Keycode::Left);
+      }
+      keys
+    });
+    simulated += 1;
+    if stress.is_some() {
+      stress_recorder.record(FrameSample {
+        frame_time: started.elapsed(),
+        sim_time: started.elapsed(),
+        render_time: Duration::ZERO,
+        vertices: 0,
+        entities: world.entities().len(),
+      });
+    }
+  }
+  println!(
+    "simulated {} ticks with seed {}: {} entities, score {}",
+    simulated,
+    seed,
+    world.entities().len(),
+    world.resource::<Score>().0
+  );
+
+  match stress {
+    Some(budget_ms) => finish_stress(&stress_recorder, &world, budget_ms),
+    None => Ok(()),
+  }
+}
+
+/// Writes the frames of a stress run into a CSV and prints their summary. A p99 over `budget_ms` fails
+/// the run.
+fn finish_stress(recorder: &StressRecorder, world: &World, budget_ms: f32) -> Result<(), Error> {
+  let dir = std::path::Path::new(STRESS_RUNS_DIR);
+  std::fs::create_dir_all(dir)?;
+  let path = unique_path(dir, "stress", "csv");
+  recorder.write_csv(&path)?;
+  prune(dir, "stress", "csv", STRESS_RUNS_KEPT)?;
+  println!("wrote {}", path.display());
+  let summary = recorder.summary();
+  println!("{}", summary);
+  println!("particles spawned: {}", world.resource::<ParticlePool>().spawned());
+  if summary.p99_ms > budget_ms {
+    return Err(Error::FrameBudgetExceeded {
+      p99_ms: summary.p99_ms,
+      budget_ms,
+    });
+  }
+  Ok(())
+}
+
+/// `--verify-replay <file>`, plays a replay without a window and checks it against the checkpoints it
+/// was recorded with.
+fn verify_replay(path: &str) -> Result<(), Error> {
+  let replay = Replay::parse(&std::fs::read(path)?)?;
+  let health = SystemHealth::default();
+  let mut world = build_replay_world(replay, &health);
+  let mut schedule = build_game_schedule(&health, false);
+  let ticks = play_replay(&mut world, &mut schedule).map_err(Error::ReplayDiverged)?;
+  println!("replay matches the recording after {} ticks", ticks);
+  Ok(())
+}
+
+/// `--export-profile <file>` writes the profile `--profile` names, or the last used, into one file.
+/// `--import-profile <file>` adds the profile of one and `--delete-profile <name>` asks for the name to
+/// be typed again before it deletes. None without any of them.
+fn profile_command(args: &[String], profiles: Option<&Profiles>) -> Option<Result<(), Error>> {
+  let commands = ["--export-profile", "--import-profile", "--delete-profile"];
+  let (flag, value) = commands.into_iter().find_map(|flag| Some((flag, arg_value(args, flag)?)))?;
+  let Some(profiles) = profiles else {
+    return Some(Err(Error::InvalidProfile { reason: "there is no data dir to keep profiles in" }));
+  };
+  if let Err(e) = profiles.migrate() {
+    return Some(Err(e.into()));
+  }
+  Some(match flag {
+    "--export-profile" => {
+      let name = arg_value(args, "--profile").map_or_else(|| profiles.last_used(), str::to_string);
+      let mut exported = Vec::new();
+      profiles
+        .export(&name, &mut exported)
+        .and_then(|_| Ok(write_atomic(Path::new(value), |out| out.write_all(&exported))?))
+        .map(|_| println!("exported profile {} to {}", name, value))
+    }
+    "--import-profile" => std::fs::read(value)
+      .map_err(Error::from)
+      .and_then(|bytes| profiles.import(&bytes))
+      .map(|name| println!("imported profile {}", name)),
+    _ => {
+      println!("type {} again to delete it for good:", value);
+      let mut confirmation = String::new();
+      std::io::stdin()
+        .read_line(&mut confirmation)
+        .map_err(Error::from)
+        .and_then(|_| profiles.delete(value, confirmation.trim()))
+        .map(|_| println!("deleted profile {}", value))
+    }
+  })
+}
+
+fn main() {
+  if let Err(e) = run() {
+    eprintln!("{}", e);
+    if !matches!(e, Error::FrameBudgetExceeded { .. } | Error::ReplayDiverged(_)) {
+      let _ = show_simple_message_box(MessageBoxFlag::ERROR, "bytepath", &e.to_string(), None);
+    }
+    std::process::exit(1);
+  }
+}
+
+fn run() -> Result<(), Error> {
+  let args = std::env::args().collect::<Vec<_>>();
+  if let Some(path) = arg_value(&args, "--pack-assets") {
+    return pack_assets(path);
+  }
+  if let Some(path) = arg_value(&args, "--verify-replay") {
+    return verify_replay(path);
+  }
+  // headless runs keep off the data dir, they play with the shared files only
+  let headless = args.iter().any(|arg| arg == "--headless");
+  let profiles = (!headless).then(Profiles::in_data_dir).flatten();
+  if let Some(result) = profile_command(&args, profiles.as_ref()) {
+    return result;
+  }
+  // everything the player keeps goes into the profile, `--profile <name>` switches to it or makes it
+  let profile = open_profile(profiles.as_ref(), arg_value(&args, "--profile"))?;
+  let stress = arg_value(&args, "--scenario") == Some("stress");
+  let gallery = arg_value(&args, "--scenario") == Some("gallery");
+  let budget_ms = match arg_value(&args, "--budget-ms") {
+    Some(budget) => budget.parse::<f32>().map_err(|_| Error::InvalidArgument {
+      flag: "--budget-ms",
+      value: budget.to_string(),
+    })?,
+    None => 1000.0 / 60.0,
+  };
+  let deadzone = match arg_value(&args, "--deadzone") {
+    Some(deadzone) => deadzone
+      .parse::<f32>()
+      .ok()
+      .filter(|deadzone| (0.0..1.0).contains(deadzone))
+      .ok_or_else(|| Error::InvalidArgument {
+        flag: "--deadzone",
+        value: deadzone.to_string(),
+      })?,
+    None => GAMEPAD_DEADZONE,
+  };
+  // a replay brings the options it was recorded with, they override the ones given
+  let replay = match arg_value(&args, "--replay") {
+    Some(path) => Some(Replay::parse(&std::fs::read(path)?)?),
+    None => None,
+  };
+  let wasd = match arg_value(&args, "--keys") {
+    None | Some("arrows") => false,
+    Some("wasd") => true,
+    Some(keys) => {
+      return Err(Error::InvalidArgument {
+        flag: "--keys",
+        value: keys.to_string(),
+      })
+    }
+  };
+  // two players on one keyboard, each with a layout of their own whatever `--keys` says
+  let co_op = args.iter().any(|arg| arg == "--co-op");
+  // a bindings file replaces the layout the flags pick, a replay brings the bindings it was recorded with
+  let key_bindings = match &replay {
+    Some(replay) => replay.bindings.clone(),
+    None => KeyBindings::load(KeyBindings::layout(co_op, wasd), &profile),
+  };
+
+  let bounds_mode = match arg_value(&args, "--bounds") {
+    _ if replay.is_some() => replay.as_ref().map_or(BoundsMode::Wrap, |replay| replay.bounds_mode),
+    None | Some("wrap") => BoundsMode::Wrap,
+    Some("clamp") => BoundsMode::Clamp,
+    Some("free") => BoundsMode::Free,
+    Some(bounds) => {
+      return Err(Error::InvalidArgument {
+        flag: "--bounds",
+        value: bounds.to_string(),
+      })
+    }
+  };
+
+  let settings = Settings::load(&profile);
+
+  let seed = match arg_value(&args, "--seed") {
+    _ if replay.is_some() => replay.as_ref().map_or(0, |replay| replay.seed),
+    Some(seed) => seed.parse::<u64>().map_err(|_| Error::InvalidArgument {
+      flag: "--seed",
+      value: seed.to_string(),
+    })?,
+    None if stress => 0,
+    None => rand::random(),
+  };
+
+  if let Some(ticks) = arg_value(&args, "--headless") {
+    let ticks = ticks.parse::<u64>().map_err(|_| Error::InvalidArgument {
+      flag: "--headless",
+      value: ticks.to_string(),
+    })?;
+    let stress = stress.then_some(budget_ms);
+    return run_headless(seed, ticks, settings.fixed_timestep(), key_bindings, bounds_mode, stress);
+  }
+
+  let sdl_context = sdl2::init().map_err(Error::Sdl)?;
+  // declared before the world so it is dropped after the chunks
+  let audio = audio::init(&sdl_context);
+  let sdl_video = sdl_context.video().map_err(Error::Sdl)?;
   let gl_attr = sdl_video.gl_attr();
   gl_attr.set_context_profile(GLProfile::Core);
   gl_attr.set_context_version(3, 3);
-  let sdl_window = sdl_video
-    .window("bytepath", SCREEN_RENDER_WIDTH, SCREEN_RENDER_HEIGHT)
-    .opengl()
-    .resizable()
-    .position_centered()
-    .build()
-    .map_err(|e| e.to_string())?;
-  let _ctx = sdl_window.gl_create_context()?;
+  if cfg!(debug_assertions) {
+    // some drivers only send debug output to debug contexts
+    gl_attr.set_context_flags().debug().set();
+  }
+  let mut window_builder = sdl_video.window("bytepath", settings.window_width, settings.window_height);
+  window_builder.opengl().allow_highdpi().resizable().position_centered();
+  if settings.fullscreen {
+    window_builder.fullscreen_desktop();
+  }
+  let sdl_window = window_builder.build().map_err(|e| Error::Sdl(e.to_string()))?;
+  let _ctx = sdl_window.gl_create_context().map_err(Error::Sdl)?;
+  // stress runs measure frame times, waiting for the display would swamp them
+  let swap_interval = if settings.vsync && !stress {
+    SwapInterval::VSync
+  } else {
+    SwapInterval::Immediate
+  };
+  if let Err(e) = sdl_video.gl_set_swap_interval(swap_interval) {
+    eprintln!("could not set swap interval {:?}: {}", swap_interval, e);
+  }
   let gl = Gl::load_with(|name| sdl_video.gl_get_proc_address(name) as *const _);
   debug_assert_eq!(gl_attr.context_profile(), GLProfile::Core);
   debug_assert_eq!(gl_attr.context_version(), (3, 3));
-  let mut opengl_ctx = render::init(&gl)?;
-
-  let mut world = World::default();
-  world.insert_resource(Time::default());
-  world.insert_resource(Randoms(rand::rngs::SmallRng::from_entropy()));
-  world.insert_resource(EntitySpawnTimer::default());
-  world.insert_resource(KeyCodes(HashSet::<Keycode>::default()));
-  world.insert_resource(Camera::default());
-  world.insert_resource(Shake::default());
-  world.insert_resource(Flash::default());
-  world.insert_resource(DurationWrapper(Duration::default()));
-  world.insert_resource(Events::<GameEvents>::default());
-  world.insert_resource(Strokes(StrokeTessellator::new()));
-  world.insert_resource(Fills(FillTessellator::new()));
+  let mut opengl_ctx = render::init(&gl, &settings)?;
+  update_window_size(&sdl_window, &mut opengl_ctx);
+
+  let health = SystemHealth::default();
+  let pure_rng = match &replay {
+    Some(replay) => replay.pure_rng,
+    None => args.iter().any(|arg| arg == "--pure-rng"),
+  };
+  // a replay plays with the build it was recorded with, stress runs are the same every time. the
+  // upgrades bought apply from the start on, the replay records them as part of the build
+  let mut upgrades = (!stress && replay.is_none()).then(|| PlayerUpgrades::load(&profile));
+  let modifiers = match (&replay, &mut upgrades) {
+    (Some(replay), _) => replay.modifiers,
+    (None, Some(upgrades)) => {
+      upgrades.start_run();
+      upgrades.applied_to(Modifiers::load())
+    }
+    (None, None) => Modifiers::default(),
+  };
+  let mut world = build_world(seed, &health, stress, &modifiers);
+  world.resource_mut::<PickupPity>().enabled = !pure_rng;
+  world.insert_resource(key_bindings.clone());
+  world.insert_resource(bounds_mode);
+  world.insert_resource(opengl_ctx.caps);
+  world.insert_resource(profile.clone());
+  if let Some(profiles) = profiles {
+    world.insert_resource(profiles);
+  }
+  world.resource_mut::<PostFx>().text_in_scene = settings.post_fx_text;
+  world.resource_mut::<Rumble>().intensity = settings.rumble as f32 / 100.0;
+  world.resource_mut::<CameraFollow>().mode = settings.camera;
+  if !stress && !gallery && settings.idle_pause > 0 {
+    world.resource_mut::<Idle>().timeout = Some(Duration::from_secs(settings.idle_pause.into()));
+  }
+  if audio.is_some() {
+    world.insert_non_send_resource(AudioAssets::default());
+    let mut sounds = Sfx::ALL.into_iter();
+    world.resource_mut::<MainThreadTasks>().push("sounds", SFX_LOAD_COST, move |world| match sounds.next() {
+      Some(sfx) => {
+        world.non_send_resource_mut::<AudioAssets>().load(sfx);
+        TaskStatus::Continue
+      }
+      None => TaskStatus::Done,
+    });
+  }
+  world.insert_resource(OutputWriter::spawn(OUTPUT_QUEUE_CAPACITY));
+  // stress runs and replays would fill the table with deaths that already happened or never did
+  if !stress && replay.is_none() {
+    world.insert_resource(HighScores::load(&profile));
+    world.insert_resource(LifetimeStats::load(&profile));
+    // the balance console changes the simulation, it comes with the practice tools
+    if !args.iter().any(|arg| arg == "--audit-lockstep") {
+      let console = Console::load();
+      world.insert_resource(console.file());
+      world.insert_resource(console);
+      world.insert_resource(Practice::default());
+    }
+  }
+  if let Some(upgrades) = upgrades {
+    world.insert_resource(upgrades);
+  }
+  match replay {
+    Some(replay) => world.insert_resource(replay),
+    None if !stress && !gallery => {
+      world.insert_resource(Replay::record(seed, bounds_mode, key_bindings.clone(), pure_rng, modifiers))
+    }
+    None => {}
+  }
+  if opengl_ctx.caps.framebuffer == FramebufferMode::Direct {
+    eprintln!("no low res framebuffer available, rendering at native resolution");
+    world.spawn((
+      components::ScopeTag(components::GameScope::Persistent),
+      components::Text {
+        text: String::from("low res mode unavailable"),
+        timer: Timer::from_seconds(5.0, false),
+      },
+      components::Transform {
+        position: units::WorldPos::new(8.0, 24.0),
+        ..Default::default()
+      },
+    ));
+  }
   world.insert_resource(create_draw_buffer::<Circle>(
     &gl,
-    &opengl_ctx,
+    &mut opengl_ctx,
     calculate_size_for_circles,
   ));
-  world.insert_resource(create_draw_buffer::<Quad>(&gl, &opengl_ctx, calculate_size_for_quads));
-  world.insert_resource(create_draw_buffer::<Line>(&gl, &opengl_ctx, calculate_size_for_lines));
-  world.insert_resource(create_text_buffer(&gl, &opengl_ctx));
+  world.insert_resource(create_draw_buffer::<Quad>(&gl, &mut opengl_ctx, calculate_size_for_quads));
+  world.insert_resource(create_draw_buffer::<Line>(&gl, &mut opengl_ctx, calculate_size_for_lines));
+  world.insert_resource(create_draw_buffer::<Particle>(
+    &gl,
+    &mut opengl_ctx,
+    calculate_size_for_particles,
+  ));
+  world.insert_resource(create_draw_buffer::<Screen>(&gl, &mut opengl_ctx, calculate_size_for_screen));
+  let (text_buffers, mut sdf_job) = create_text_buffer(&gl, &mut opengl_ctx)?;
+  world.insert_resource(text_buffers);
+  world
+    .resource_mut::<MainThreadTasks>()
+    .push("sdf atlas", SDF_GLYPH_COST, move |world| match sdf_job.step() {
+      TaskStatus::Continue => TaskStatus::Continue,
+      TaskStatus::Done => {
+        world.insert_resource(std::mem::take(&mut sdf_job));
+        TaskStatus::Done
+      }
+    });
+  if !gallery {
+    world.insert_resource(Starfield::new(seed));
+    world.resource_mut::<MainThreadTasks>().push("starfield", STARFIELD_CHUNK_COST, |world| {
+      world.resource_scope(|world, mut starfield: Mut<Starfield>| starfield.generate(world.resource::<WorldBounds>()))
+    });
+  }
 
   let mut render_state = SystemState::<render::RenderSystemState>::new(&mut world);
 
-  let mut startup_schedule = Schedule::default();
-  startup_schedule.add_stage(
-    "startup",
-    SystemStage::single_threaded().with_system(player_spawn_system),
-  );
+  let mut game_schedule = if gallery {
+    println!("gallery with {} exemplars", spawn_gallery(&mut world));
+    build_gallery_schedule(&health)
+  } else {
+    build_startup_schedule().run(&mut world);
+    build_game_schedule(&health, stress)
+  };
+  let mut draw_schedule = build_draw_schedule(&health);
+  let mut stress_recorder = StressRecorder::default();
 
-  let mut game_schedule = Schedule::default();
-  game_schedule.add_stage("events", {
-    let mut stage = SystemStage::parallel();
-    stage.add_system(Events::<GameEvents>::update_system);
-    stage.add_system(timing_system.after(Events::<GameEvents>::update_system));
-
-    stage
-  });
-  game_schedule.add_stage_after("events", "game", {
-    let mut stage = SystemStage::parallel();
-    stage.add_system(player_system);
-    stage.add_system(shooting_system.after(player_system));
-    stage.add_system(tick_effect_spawn_system.after(player_system));
-    stage.add_system(tick_effect_system.after(player_system));
-    stage.add_system(projectile_spawn_system.after(player_system));
-    stage.add_system(projectile_system.after(player_system));
-    stage.add_system(projectile_death_system.after(projectile_system));
-    stage.add_system(player_explosion_spawn_system.after(player_system));
-    stage.add_system(trail_effect_spawn_system.after(player_system));
-    stage.add_system(ammo_pickup_system.after(player_system));
-    stage.add_system(boost_pickup_system.after(player_system));
-    stage.add_system(trail_effect_system.after(trail_effect_spawn_system));
-    stage.add_system(camera_shake_system);
-    stage.add_system(screen_flash_system);
-    stage.add_system(ammo_pickup_spawn_system);
-    stage.add_system(explosion_system);
-    stage.add_system(boost_pickup_spawn_system);
-    stage.add_system(draw_text_system);
-
-    stage
+  // paranoid mode, a second world fed the same input has to stay in lockstep with the first
+  let mut shadow = args.iter().any(|arg| arg == "--audit-lockstep").then(|| {
+    println!("auditing determinism with seed {}", seed);
+    let health = SystemHealth::default();
+    let mut shadow_world = build_headless_world(seed, &health, stress, &modifiers);
+    shadow_world.resource_mut::<PickupPity>().enabled = !pure_rng;
+    shadow_world.insert_resource(key_bindings);
+    shadow_world.insert_resource(bounds_mode);
+    build_startup_schedule().run(&mut shadow_world);
+    Lockstep::new(shadow_world, build_game_schedule(&health, stress))
   });
+  let mut tick = 0u64;
 
-  startup_schedule.run(&mut world);
-
-  let frame_dt = Duration::new(0, 1_000_000_000u32 / 60);
+  let step = settings.fixed_timestep();
+  let mut accumulator = Duration::ZERO;
   let mut last_time = Instant::now();
-  let mut event_pump = sdl_context.event_pump()?;
+  let mut event_pump = sdl_context.event_pump().map_err(Error::Sdl)?;
+  let mut gamepads = Gamepads::new(sdl_context.game_controller().map_err(Error::Sdl)?, deadzone);
+  let mut shader_watcher = ShaderWatcher::new();
+  let mut wheel = WheelPulses::default();
 
   'running: loop {
     let current_time = Instant::now();
-    let mut frame_time = current_time - last_time;
+    let frame_time = current_time - last_time;
     last_time = current_time;
+    accumulator = (accumulator + frame_time).min(step * MAX_TICKS_PER_FRAME);
+    let mut sub_steps = 0;
 
-    while frame_time.as_secs_f32() > 0.0 {
-      let dt = std::cmp::min(frame_time, frame_dt);
+    if stress && world.resource::<StressScenario>().finished() {
+      break 'running;
+    }
+
+    for event in event_pump.poll_iter() {
+      for input in ui_inputs(&event, &sdl_window, opengl_ctx.viewport) {
+        world.resource_mut::<UiContext>().push(input);
+      }
+      // typing a name or capturing a key, the keys are the menu's
+      if world.resource::<UiContext>().wants_keys()
+        && matches!(event, Event::KeyDown { .. } | Event::ControllerButtonDown { .. })
+      {
+        continue;
+      }
+      match event {
+        Event::Quit { .. } => break 'running,
+        // while a menu is open Escape goes back in it, see ui_inputs()
+        Event::KeyDown {
+          keycode: Some(Keycode::Escape),
+          repeat: false,
+          ..
+        } if !world.resource::<UiContext>().is_open() => {
+          world.resource_mut::<Menus>().quit_dialog = true;
+          *world.resource_mut() = GameState::Paused;
+        }
+        Event::KeyDown {
+          keycode: Some(Keycode::M),
+          repeat: false,
+          ..
+        } => {
+          let mut minimap = world.resource_mut::<Minimap>();
+          minimap.visible = !minimap.visible;
+        }
+        Event::KeyDown {
+          keycode: Some(Keycode::I),
+          repeat: false,
+          ..
+        } => {
+          let mut display = world.resource_mut::<InputDisplay>();
+          display.visible = !display.visible;
+        }
+        Event::KeyDown {
+          keycode: Some(Keycode::H),
+          repeat: false,
+          ..
+        } => {
+          let mut heatmap = world.resource_mut::<Heatmap>();
+          heatmap.visible = !heatmap.visible;
+        }
+        Event::KeyDown {
+          keycode: Some(Keycode::F3),
+          repeat: false,
+          ..
+        } => {
+          let mut stats = world.resource_mut::<FrameStats>();
+          stats.visible = !stats.visible;
+        }
+        Event::KeyDown {
+          keycode: Some(Keycode::F4),
+          repeat: false,
+          ..
+        } => {
+          let mut cache = world.resource_mut::<GeometryCache>();
+          cache.enabled = !cache.enabled;
+        }
+        Event::KeyDown {
+          keycode: Some(Keycode::F6),
+          repeat: false,
+          ..
+        } => render::reload_shaders(&gl, &mut opengl_ctx),
+        Event::KeyDown {
+          keycode: Some(Keycode::F7),
+          repeat: false,
+          ..
+        } => world.resource_mut::<PostFx>().cycle(),
+        Event::KeyDown {
+          keycode: Some(Keycode::E),
+          repeat: false,
+          ..
+        } => {
+          let mut panel = world.resource_mut::<CurvePanel>();
+          panel.visible = !panel.visible;
+          if !panel.visible {
+            Inspector::select(&mut world, None);
+          }
+        }
+        Event::KeyDown {
+          keycode: Some(Keycode::F8),
+          repeat: false,
+          ..
+        } if world.resource::<CurvePanel>().visible => Inspector::select_next(&mut world),
+        Event::KeyDown {
+          keycode: Some(Keycode::F9),
+          repeat: false,
+          ..
+        } if world.resource::<CurvePanel>().visible => {
+          Inspector::cycle_easing(&mut world);
+        }
+        Event::Window {
+          win_event: WindowEvent::Resized(..) | WindowEvent::SizeChanged(..),
+          ..
+        } => update_window_size(&sdl_window, &mut opengl_ctx),
+        // nobody is playing while another window has the focus, pausing also keeps the idle countdown
+        // from running out in the background
+        Event::Window {
+          win_event: WindowEvent::FocusLost,
+          ..
+        } => *world.resource_mut() = GameState::Paused,
+        Event::MouseMotion { .. } | Event::MouseButtonDown { .. } => world.resource_mut::<Idle>().touch(),
+        Event::MouseWheel { y, direction, .. } => {
+          // flipped, "natural" scrolling reports the wheel turned the other way
+          wheel.add(if direction == MouseWheelDirection::Flipped { -y } else { y });
+          world.resource_mut::<Idle>().touch();
+        }
+        Event::KeyDown {
+          keycode: Some(Keycode::P),
+          repeat: false,
+          ..
+        }
+        | Event::ControllerButtonDown {
+          button: Button::Start,
+          ..
+        } => world.resource_mut::<GameState>().toggle(),
+        Event::ControllerDeviceAdded { which, .. } => {
+          gamepads.added(which);
+          world.resource_mut::<InputDevice>().pad_style = gamepads.style();
+        }
+        Event::ControllerDeviceRemoved { which, .. } => {
+          gamepads.removed(which);
+          world.resource_mut::<InputDevice>().pad_style = gamepads.style();
+        }
+        _ => {}
+      }
+    }
+
+    // every tick advances by exactly one step, what is left over is drawn interpolated
+    while accumulator >= step {
+      let mut dt = step;
+      let playback = match world.get_resource_mut::<Replay>() {
+        Some(mut replay) if replay.is_playing() => match replay.next_frame() {
+          Some(frame) => Some(frame),
+          None => {
+            match finish_replay(&mut world) {
+              Ok(ticks) => println!("replay matches the recording after {} ticks", ticks),
+              Err(mismatch) => eprintln!("replay diverged from the recording at {}", mismatch),
+            }
+            break 'running;
+          }
+        },
+        _ => None,
+      };
+      // a replayed tick takes as long as the recorded one, however long this frame is
+      if let Some(frame) = &playback {
+        dt = frame.dt;
+      }
+      sub_steps += 1;
 
       *world.resource_mut() = DurationWrapper(dt);
 
-      for event in event_pump.poll_iter() {
-        match event {
-          Event::Quit { .. }
-          | Event::KeyDown {
-            keycode: Some(Keycode::Escape),
-            ..
-          } => break 'running,
-          Event::Window {
-            win_event: WindowEvent::Resized(w, h),
-            ..
-          } => opengl_ctx.viewport = (w, h),
-          _ => {}
+      match playback {
+        Some(frame) => play_frame(&mut world, &frame),
+        None => {
+          let keycodes = event_pump
+            .keyboard_state()
+            .pressed_scancodes()
+            .filter_map(Keycode::from_scancode)
+            .collect::<HashSet<Keycode>>();
+          let (wheel_up, wheel_down) = wheel.take();
+          *world.resource_mut() = KeyCodes(keycodes);
+          *world.resource_mut() = MouseInput {
+            buttons: event_pump.mouse_state().pressed_mouse_buttons().map(|button| button as u8).collect(),
+            wheel_up,
+            wheel_down,
+          };
+          *world.resource_mut() = gamepads.poll();
+          record_frame(&mut world);
         }
       }
 
-      let keycodes = event_pump
-        .keyboard_state()
-        .pressed_scancodes()
-        .filter_map(Keycode::from_scancode)
-        .collect::<HashSet<Keycode>>();
-      *world.resource_mut() = KeyCodes(keycodes);
-
+      clear_draw_buffers(&mut world);
       game_schedule.run(&mut world);
+      tick += 1;
+      if let Some(mismatch) = replay_checkpoint(&mut world) {
+        eprintln!("replay diverged from the recording at {}", mismatch);
+        break 'running;
+      }
+
+      if let Some(divergence) = shadow.as_mut().and_then(|shadow| shadow.follow(&mut world, tick)) {
+        eprintln!("determinism audit failed at {}", divergence);
+        shadow = None;
+      }
 
-      frame_time -= dt;
+      accumulator = accumulator.saturating_sub(dt);
+    }
+    if world.resource::<Menus>().quit {
+      break 'running;
     }
+    world.resource::<Rumble>().drive(&mut gamepads);
+    world.resource_mut::<FrameStats>().record(frame_time, sub_steps);
+    world.resource_mut::<EventCounts>().end_frame();
 
-    render::render_gl(&gl, &opengl_ctx, render_state.get_mut(&mut world))?;
+    let tick_geometry = tick_geometry(&world);
+    *world.resource_mut() = RenderAlpha(accumulator.as_secs_f32() / step.as_secs_f32());
+    draw_schedule.run(&mut world);
+
+    let sim_time = current_time.elapsed();
+    let vertices = world.resource::<CircleGeometry>().vertex_buffer.vertices.len()
+      + world.resource::<QuadGeometry>().vertex_buffer.vertices.len()
+      + world.resource::<LineGeometry>().vertex_buffer.vertices.len()
+      + world.resource::<ParticleGeometry>().vertex_buffer.vertices.len()
+      + world.resource::<ScreenGeometry>().vertex_buffer.vertices.len();
+
+    if shader_watcher.changed() {
+      render::reload_shaders(&gl, &mut opengl_ctx);
+    }
+    if let Some(sdf_job) = world.remove_resource::<SdfAtlasJob>() {
+      world.resource_mut::<TextBuffers>().sdf = sdf_job.upload(&gl, &mut opengl_ctx);
+    }
+    render::render_gl(&gl, &mut opengl_ctx, render_state.get_mut(&mut world))?;
+    truncate_draw_buffers(&mut world, tick_geometry);
 
     sdl_window.gl_swap_window();
+
+    if stress {
+      stress_recorder.record(FrameSample {
+        frame_time: current_time.elapsed(),
+        sim_time,
+        render_time: current_time.elapsed() - sim_time,
+        vertices,
+        entities: world.entities().len(),
+      });
+    }
   }
 
+  // quitting mid-run still counts the run
+  let score = world.resource::<Score>().0;
+  let writer = world.remove_resource::<OutputWriter>();
+  world.resource_scope(|world, mut run: Mut<RunStats>| {
+    if let Some(mut lifetime) = world.get_resource_mut::<LifetimeStats>() {
+      finish_run(&mut run, score, &mut lifetime, writer.as_ref());
+    }
+  });
+  // purchases of a shop left open
+  if let Some(mut upgrades) = world.get_resource_mut::<PlayerUpgrades>() {
+    upgrades.save();
+  }
   render::delete(&gl, &opengl_ctx, render_state.get_mut(&mut world));
 
+  let practiced = world.get_resource::<Practice>().is_some_and(|practice| practice.used);
+  if practiced {
+    println!("practice tools were used, the replay is not kept");
+  } else if world.get_resource::<Replay>().is_some_and(|replay| !replay.is_playing()) {
+    // recording only keeps the checkpoint, it can't mismatch
+    finish_replay(&mut world).ok();
+    let mut bytes = Vec::new();
+    world.resource::<Replay>().write(&mut bytes)?;
+    let dir = std::path::Path::new(REPLAYS_DIR);
+    std::fs::create_dir_all(dir)?;
+    let job = WriteJob {
+      retention: Some(Retention {
+        prefix: "replay",
+        extension: "bin",
+        keep: REPLAYS_KEPT,
+      }),
+      ..WriteJob::new(unique_path(dir, "replay", "bin"), bytes, "replay")
+    };
+    job.submit(writer.as_ref());
+  }
+  // everything queued is written before the game quits
+  if let Some(writer) = writer {
+    for done in writer.finish() {
+      match done.result {
+        Ok(()) => println!("wrote {}", done.path.display()),
+        Err(e) => eprintln!("could not save the {} to {}: {}", done.what, done.path.display(), e),
+      }
+    }
+  }
+
+  if stress {
+    finish_stress(&stress_recorder, &world, budget_ms)?;
+  }
+
   Ok(())
 }
@@ -0,0 +1,170 @@
+use crate::units::{Angle, WorldPos, WorldVec};
+
+/// Closest point to `point` on the segment `a`-`b`. A degenerate segment collapses to `a`.
+pub fn closest_point_on_segment(a: WorldPos, b: WorldPos, point: WorldPos) -> WorldPos {
+  let ab = b - a;
+  let length_squared = ab.length_squared();
+  if length_squared <= f32::EPSILON {
+    return a;
+  }
+  let t = ((point - a).dot(ab) / length_squared).clamp(0.0, 1.0);
+  a + ab * t
+}
+
+pub fn segment_circle_overlap(a: WorldPos, b: WorldPos, center: WorldPos, radius: f32) -> bool {
+  closest_point_on_segment(a, b, center).distance_squared(center) <= radius * radius
+}
+
+/// Whether a circle of `radius` that moved from `from` to `to` touched the segment `a`-`b` anywhere
+/// along the way. Either it crossed the segment, or one of the four ends came within `radius`.
+pub fn swept_segment_contact(from: WorldPos, to: WorldPos, a: WorldPos, b: WorldPos, radius: f32) -> bool {
+  let (path, segment) = (to - from, b - a);
+  let side = |origin: WorldPos, direction: WorldVec, point: WorldPos| direction.perp_dot(point - origin);
+  let crossed =
+    side(a, segment, from) * side(a, segment, to) < 0.0 && side(from, path, a) * side(from, path, b) < 0.0;
+  crossed
+    || segment_circle_overlap(a, b, from, radius)
+    || segment_circle_overlap(a, b, to, radius)
+    || segment_circle_overlap(from, to, a, radius)
+    || segment_circle_overlap(from, to, b, radius)
+}
+
+/// Whether a circle of `radius` around `center` is touched by something that moved from `from` to
+/// `to`. Moves shorter than `min_sweep` only test `to`, longer ones test the whole segment.
+pub fn swept_contact(from: WorldPos, to: WorldPos, center: WorldPos, radius: f32, min_sweep: f32) -> bool {
+  if from.distance_squared(to) <= min_sweep * min_sweep {
+    return to.distance_squared(center) < radius * radius;
+  }
+  segment_circle_overlap(from, to, center, radius)
+}
+
+/// Whether a ring around `center`, growing from `inner` to `outer` during a tick, passed over a circle
+/// of `radius` that moved from `from` to `to` during the same tick. It did if the circle touched the
+/// ring at either end of the tick, or was outside the ring at the start and inside it at the end.
+/// Checking only the end would let a fast mover cross the ring between two ticks.
+pub fn ring_passed_over(center: WorldPos, inner: f32, outer: f32, from: WorldPos, to: WorldPos, radius: f32) -> bool {
+  let before = (from - center).length() - inner;
+  let after = (to - center).length() - outer;
+  before.abs() <= radius || after.abs() <= radius || (before > 0.0) != (after > 0.0)
+}
+
+/// How much to stretch a spawn interval so spawns per unit of `current` match those per unit of
+/// `reference`, e.g. areas or perimeters. Clamped to `1 / max_scale..=max_scale`.
+pub fn density_scale(reference: f32, current: f32, max_scale: f32) -> f32 {
+  if current <= f32::EPSILON {
+    return max_scale;
+  }
+  (reference / current).clamp(1.0 / max_scale, max_scale)
+}
+
+/// Spawn rate multiplier for a pickup kind. Ramps from 1 to `max_rate` over `ramp` seconds once
+/// `starving` passes `threshold`, and drops to `saturated_rate` while the player needs none.
+pub fn pity_rate(starving: f32, threshold: f32, ramp: f32, max_rate: f32, saturated: bool, saturated_rate: f32) -> f32 {
+  if saturated {
+    return saturated_rate;
+  }
+  let progress = ((starving - threshold) / ramp.max(f32::EPSILON)).clamp(0.0, 1.0);
+  1.0 + progress * (max_rate - 1.0)
+}
+
+/// `curve` over 0..=1 as a polyline of `samples` points filling the box at `min` with `size`. Values
+/// outside 0..=1 are clamped to the box.
+pub fn plot(curve: impl Fn(f32) -> f32, min: glam::Vec2, size: glam::Vec2, samples: usize) -> Vec<glam::Vec2> {
+  let last = samples.max(2) - 1;
+  (0..=last)
+    .map(|i| {
+      let x = i as f32 / last as f32;
+      min + glam::vec2(x, curve(x).clamp(0.0, 1.0)) * size
+    })
+    .collect()
+}
+
+/// Zero inside `radius`, rescaled so the length still reaches 1 at the rim outside of it.
+pub fn deadzone(stick: glam::Vec2, radius: f32) -> glam::Vec2 {
+  let length = stick.length();
+  if length <= radius {
+    return glam::Vec2::ZERO;
+  }
+  stick * ((length - radius) / (1.0 - radius)).min(1.0) / length
+}
+
+/// The turn from `rotation` at `position` towards `target`: `ease` of the signed angle between them,
+/// at most `max_turn` radians either way. Zero when facing it or standing on it, a target right
+/// behind turns counter clockwise.
+pub fn steer_towards(rotation: Angle, position: WorldPos, target: WorldPos, max_turn: f32, ease: f32) -> Angle {
+  let forward = rotation.forward();
+  let to_target = target - position;
+  let angle = forward.perp_dot(to_target).atan2(forward.dot(to_target));
+  // right behind, the perp dot can come out as -0 and atan2 as -π
+  let angle = if angle == -std::f32::consts::PI { std::f32::consts::PI } else { angle };
+  Angle::from_radians((angle * ease).clamp(-max_turn, max_turn))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::f32::consts::{FRAC_PI_2, PI};
+
+  /// Loose enough for the 1/256 steps of `fixed_point`.
+  #[track_caller]
+  fn assert_close(actual: f32, expected: f32) {
+    assert!((actual - expected).abs() < 0.01, "{} != {}", actual, expected);
+  }
+
+  /// The turn towards a target 100 units away in the direction `towards`, from facing `rotation`.
+  fn turn(rotation: f32, towards: f32, max_turn: f32, ease: f32) -> f32 {
+    let position = WorldPos::new(240.0, 135.0);
+    let target = position + Angle::from_radians(towards).forward_by(100.0);
+    steer_towards(Angle::from_radians(rotation), position, target, max_turn, ease).radians()
+  }
+
+  #[test]
+  fn steering_never_turns_past_the_target() {
+    // close enough, the whole turn lands on the target and no further
+    assert_close(turn(0.0, -0.1, 0.5, 1.0), -0.1);
+    assert_close(turn(0.0, 0.3, 0.5, 1.0), 0.3);
+    assert_close(turn(0.0, 0.3, 0.5, 0.5), 0.15);
+    // further, the turn stops at the limit
+    assert_close(turn(0.0, 1.0, 0.5, 1.0), 0.5);
+    // facing it or standing on it, there's nothing to turn
+    assert_close(turn(0.7, 0.7, 0.5, 1.0), 0.0);
+    let position = WorldPos::new(10.0, 10.0);
+    assert_eq!(steer_towards(Angle::from_radians(0.7), position, position, 0.5, 1.0).radians(), 0.0);
+  }
+
+  #[test]
+  fn steering_takes_the_shorter_way_around() {
+    assert_close(turn(0.0, FRAC_PI_2, 0.5, 1.0), 0.5);
+    assert_close(turn(0.0, -FRAC_PI_2, 0.5, 1.0), -0.5);
+    assert_close(turn(0.0, 3.0 * FRAC_PI_2, 0.5, 1.0), -0.5);
+    assert_close(turn(0.0, PI - 0.2, 0.5, 1.0), 0.5);
+    assert_close(turn(0.0, -PI + 0.2, 0.5, 1.0), -0.5);
+    // right behind it turns counter clockwise
+    let (position, behind) = (WorldPos::new(240.0, 135.0), WorldPos::new(240.0, 35.0));
+    assert_close(steer_towards(Angle::default(), position, behind, 0.5, 1.0).radians(), 0.5);
+  }
+
+  #[test]
+  fn steering_wraps_around_at_half_a_turn() {
+    // across ±π the short way is the small turn, not the one all the way back around
+    assert_close(turn(PI - 0.1, -PI + 0.1, 0.5, 1.0), 0.2);
+    assert_close(turn(-PI + 0.1, PI - 0.1, 0.5, 1.0), -0.2);
+    // however many turns the rotation has wound up
+    assert_close(turn(4.0 * PI + 0.1, -0.1, 0.5, 1.0), -0.2);
+  }
+
+  #[test]
+  fn a_fast_circle_touches_a_segment_it_jumped_over() {
+    let (a, b) = (WorldPos::new(100.0, 0.0), WorldPos::new(100.0, 200.0));
+    // both ends far from the segment, the path between them crosses it
+    assert!(swept_segment_contact(WorldPos::new(0.0, 100.0), WorldPos::new(300.0, 100.0), a, b, 4.0));
+    // passing just past its end, within the radius
+    assert!(swept_segment_contact(WorldPos::new(0.0, 203.0), WorldPos::new(300.0, 203.0), a, b, 4.0));
+    // passing beyond the radius, or running alongside it
+    assert!(!swept_segment_contact(WorldPos::new(0.0, 210.0), WorldPos::new(300.0, 210.0), a, b, 4.0));
+    assert!(!swept_segment_contact(WorldPos::new(90.0, 0.0), WorldPos::new(90.0, 200.0), a, b, 4.0));
+    // standing still next to it
+    let near = WorldPos::new(97.0, 50.0);
+    assert!(swept_segment_contact(near, near, a, b, 4.0));
+  }
+}
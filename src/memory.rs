@@ -0,0 +1,209 @@
+use crate::{
+  environment::{MEMORY_CHECK_INTERVAL, MEMORY_OVERSIZE_FACTOR, MEMORY_OVERSIZED_CHECKS},
+  resources::{FrameStats, GpuAllocation},
+};
+use bevy_ecs::prelude::*;
+
+/// A buffer whose capacity can be handed back once a burst is over.
+pub trait Recyclable: Send + Sync {
+  fn len(&self) -> usize;
+  fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+  fn capacity(&self) -> usize;
+  fn shrink_to(&mut self, capacity: usize);
+  /// Called after every sample, for a buffer whose length is a high water mark of its own.
+  fn sampled(&mut self) {}
+}
+
+impl<T: Send + Sync> Recyclable for Vec<T> {
+  fn len(&self) -> usize {
+    Vec::len(self)
+  }
+
+  fn capacity(&self) -> usize {
+    Vec::capacity(self)
+  }
+
+  fn shrink_to(&mut self, capacity: usize) {
+    Vec::shrink_to(self, capacity)
+  }
+}
+
+/// Filled while rendering, after the steward ran, so its length is the most the last frame uploaded.
+/// Shrinking only marks it, the next upload reallocates.
+impl Recyclable for GpuAllocation {
+  fn len(&self) -> usize {
+    self.used
+  }
+
+  fn capacity(&self) -> usize {
+    self.bytes
+  }
+
+  fn shrink_to(&mut self, capacity: usize) {
+    if capacity < self.bytes {
+      self.bytes = capacity;
+      self.stale = true;
+    }
+  }
+
+  fn sampled(&mut self) {
+    self.used = 0;
+  }
+}
+
+type Access = fn(&mut World) -> &mut dyn Recyclable;
+
+struct Tracked {
+  name: &'static str,
+  resting: usize,
+  access: Access,
+  high_water: usize,
+  oversized_checks: u32,
+}
+
+/// Buffers that keep their largest capacity ever, shrunk back once they stay oversized for a while.
+/// A buffer opts in with one [`MemorySteward::register`] call.
+#[derive(Resource, Default)]
+pub struct MemorySteward {
+  buffers: Vec<Tracked>,
+  elapsed: f32,
+}
+
+impl MemorySteward {
+  /// `resting` is the capacity the buffer is never shrunk below.
+  pub fn register(&mut self, name: &'static str, resting: usize, access: Access) {
+    self.buffers.push(Tracked {
+      name,
+      resting,
+      access,
+      high_water: 0,
+      oversized_checks: 0,
+    });
+  }
+}
+
+/// Samples every buffer once per frame and checks them every [`MEMORY_CHECK_INTERVAL`] seconds. A
+/// buffer whose capacity exceeds [`MEMORY_OVERSIZE_FACTOR`] times what it needed since the last check
+/// for [`MEMORY_OVERSIZED_CHECKS`] checks in a row is shrunk to that need. Runs after every system
+/// and before rendering, nothing holds on to the buffers then.
+pub fn memory_steward_system(world: &mut World) {
+  let mut steward = std::mem::take(&mut *world.resource_mut::<MemorySteward>());
+  steward.elapsed += world.resource::<FrameStats>().last_frame_time();
+  let check = steward.elapsed >= MEMORY_CHECK_INTERVAL;
+  if check {
+    steward.elapsed = 0.0;
+  }
+
+  for tracked in &mut steward.buffers {
+    let buffer = (tracked.access)(world);
+    let len = buffer.len();
+    buffer.sampled();
+    tracked.high_water = tracked.high_water.max(len);
+    if !check {
+      continue;
+    }
+
+    let needed = tracked.high_water.max(tracked.resting);
+    if buffer.capacity() > needed * MEMORY_OVERSIZE_FACTOR {
+      tracked.oversized_checks += 1;
+    } else {
+      tracked.oversized_checks = 0;
+    }
+    if tracked.oversized_checks >= MEMORY_OVERSIZED_CHECKS {
+      let before = buffer.capacity();
+      buffer.shrink_to(needed);
+      if cfg!(debug_assertions) {
+        eprintln!("shrunk {} from {} to {}", tracked.name, before, buffer.capacity());
+      }
+      tracked.oversized_checks = 0;
+    }
+    tracked.high_water = len;
+  }
+
+  *world.resource_mut::<MemorySteward>() = steward;
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::time::Duration;
+
+  #[derive(Default, Resource)]
+  struct Buffers {
+    vertices: Vec<u32>,
+    vbo: GpuAllocation,
+  }
+
+  fn world() -> World {
+    let mut world = World::default();
+    world.init_resource::<Buffers>();
+    world.init_resource::<FrameStats>();
+    let mut steward = MemorySteward::default();
+    steward.register("vertices", 16, |world| &mut world.resource_mut::<Buffers>().into_inner().vertices);
+    steward.register("vbo", 64, |world| &mut world.resource_mut::<Buffers>().into_inner().vbo);
+    world.insert_resource(steward);
+    world
+  }
+
+  /// `seconds` of one second frames, each filling the buffers with `len` vertices before the steward
+  /// runs and uploading them after, like the main loop.
+  fn frames(world: &mut World, seconds: u32, len: usize) {
+    for _ in 0..seconds {
+      world.resource_mut::<FrameStats>().record(Duration::from_secs(1), 1);
+      let mut buffers = world.resource_mut::<Buffers>();
+      buffers.vertices.clear();
+      buffers.vertices.extend(0..len as u32);
+      memory_steward_system(world);
+
+      let vbo = &mut world.resource_mut::<Buffers>().into_inner().vbo;
+      if len * 4 > vbo.bytes {
+        vbo.bytes = (len * 4).max(vbo.bytes * 2);
+      }
+      vbo.stale = false;
+      vbo.used = vbo.used.max(len * 4);
+    }
+  }
+
+  fn capacities(world: &World) -> (usize, usize) {
+    let buffers = world.resource::<Buffers>();
+    (buffers.vertices.capacity(), buffers.vbo.bytes)
+  }
+
+  #[test]
+  fn a_burst_is_given_back_after_three_oversized_checks() {
+    let mut world = world();
+    frames(&mut world, 2, 10_000);
+    let burst = capacities(&world);
+    assert!(burst.0 >= 10_000 && burst.1 >= 40_000);
+
+    // the check at 10 seconds still saw the burst, the ones at 20 and 30 are the first two oversized
+    let shrink_at = MEMORY_CHECK_INTERVAL as u32 * (MEMORY_OVERSIZED_CHECKS + 1);
+    frames(&mut world, shrink_at - 3, 20);
+    assert_eq!(capacities(&world), burst);
+
+    // the one at 40 shrinks both to the need, which the contents still fit
+    frames(&mut world, 1, 20);
+    let buffers = world.resource::<Buffers>();
+    assert!(buffers.vertices.capacity() < 40, "{}", buffers.vertices.capacity());
+    assert_eq!(buffers.vertices, (0..20).collect::<Vec<_>>());
+    assert_eq!(buffers.vbo.bytes, 80);
+  }
+
+  #[test]
+  fn buffers_stay_at_their_resting_size() {
+    let mut world = world();
+    world.resource_mut::<Buffers>().vertices.reserve_exact(30);
+    world.resource_mut::<Buffers>().vbo.bytes = 100;
+    frames(&mut world, MEMORY_CHECK_INTERVAL as u32 * (MEMORY_OVERSIZED_CHECKS + 2), 1);
+    // above the resting size but not twice what's needed
+    assert_eq!(capacities(&world), (30, 100));
+
+    // an empty one is no reason to shrink below it
+    world.resource_mut::<Buffers>().vertices.reserve_exact(500);
+    frames(&mut world, MEMORY_CHECK_INTERVAL as u32 * (MEMORY_OVERSIZED_CHECKS + 1), 0);
+    let capacity = world.resource::<Buffers>().vertices.capacity();
+    assert!((16..32).contains(&capacity), "{}", capacity);
+  }
+}
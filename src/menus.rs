@@ -0,0 +1,375 @@
+use crate::{
+  balance::{Console, GameBalance},
+  components::PlayerId,
+  environment::{
+    CONSOLE_LINE_MAX_LEN, CONTROLS_MENU_WIDTH, HUD_LINE_HEIGHT, HUD_TEXT_SCALE, PRACTICE_ROCKS, PROFILE_NAME_MAX_LEN,
+    SCREEN_RENDER_HEIGHT, SCREEN_RENDER_WIDTH, STATS_ROWS_SHOWN, UI_ROW_SPACING, UPGRADE_MAX_LEVEL,
+  },
+  error::Error,
+  input::{Action, InputSource},
+  practice::{Practice, PracticeCommand},
+  profiles::{Profile, Profiles},
+  prompts::{fill_prompts, InputDevice},
+  resources::{GameState, Idle, KeyBindings, Minimap},
+  rumble::Rumble,
+  shop::{PlayerUpgrades, Shop, Upgrade},
+  stats::{stats_rows, LifetimeStats, RunStats},
+  ui::{Align, Layout, UiContext},
+};
+use bevy_ecs::prelude::*;
+
+/// Which menus are open over the pause screen. main() opens the quit dialog on Escape and stops once
+/// `quit` is set.
+#[derive(Debug, Default, Resource)]
+pub struct Menus {
+  pub quit_dialog: bool,
+  pub quit: bool,
+  /// The bindings being edited, saved when the controls menu closes.
+  controls: Option<KeyBindings>,
+  profiles: Option<ProfilesMenu>,
+  /// The first row of the stats page on screen.
+  stats: Option<usize>,
+  /// With how the last purchase went.
+  shop: Option<(Shop, String)>,
+  practice: bool,
+  /// The command line being typed.
+  console: Option<String>,
+}
+
+/// The profiles menu as it reads the data dir, read again after every change.
+#[derive(Debug, Default)]
+struct ProfilesMenu {
+  names: Vec<String>,
+  /// Picked for the next start.
+  next: String,
+  new_name: String,
+  rename: String,
+  confirmation: String,
+  /// How the last change went.
+  status: String,
+}
+
+impl ProfilesMenu {
+  fn read(profiles: &Profiles, status: String) -> Self {
+    Self {
+      names: profiles.list(),
+      next: profiles.last_used(),
+      status,
+      ..Default::default()
+    }
+  }
+}
+
+/// Below the PAUSED of `pause_system`, text goes up the screen.
+fn menu_layout() -> Layout {
+  Layout {
+    anchor: glam::vec2(SCREEN_RENDER_WIDTH as f32, SCREEN_RENDER_HEIGHT as f32) / 2.0
+      - glam::vec2(0.0, 2.0 * HUD_LINE_HEIGHT),
+    align: Align::Center,
+    spacing: UI_ROW_SPACING,
+    scale: HUD_TEXT_SCALE,
+  }
+}
+
+/// Resumes, changes the settings that apply right away and opens the other menus. Only shown while
+/// none of them is, resuming closes them all.
+pub fn pause_menu_system(
+  mut state: ResMut<GameState>,
+  mut menus: ResMut<Menus>,
+  mut ui: ResMut<UiContext>,
+  mut minimap: ResMut<Minimap>,
+  mut rumble: ResMut<Rumble>,
+  idle: Res<Idle>,
+  bindings: Res<KeyBindings>,
+  device: Res<InputDevice>,
+  profile: Res<Profile>,
+  profiles: Option<Res<Profiles>>,
+  stats: Option<Res<LifetimeStats>>,
+  mut upgrades: Option<ResMut<PlayerUpgrades>>,
+  practice: Option<Res<Practice>>,
+  console: Option<Res<Console>>,
+) {
+  if *state != GameState::Paused {
+    menus.quit_dialog = false;
+    menus.controls = None;
+    menus.profiles = None;
+    menus.stats = None;
+    menus.practice = false;
+    menus.console = None;
+    if let (Some((shop, _)), Some(upgrades)) = (menus.shop.take(), &mut upgrades) {
+      shop.exit(upgrades);
+    }
+    return;
+  }
+  let open = menus.controls.is_some() || menus.profiles.is_some() || menus.stats.is_some() || menus.shop.is_some();
+  let open = open || menus.practice || menus.console.is_some();
+  if menus.quit_dialog || open {
+    return;
+  }
+  ui.begin("pause", menu_layout());
+  ui.label(fill_prompts("press {pause} to resume", &bindings, &device));
+  if idle.auto_paused {
+    ui.label("auto-paused due to inactivity");
+  }
+  if ui.button("RESUME") || ui.back() {
+    *state = GameState::Running;
+  }
+  ui.toggle("MINIMAP", &mut minimap.visible);
+  ui.slider("RUMBLE", &mut rumble.intensity, 0.0..=1.0);
+  if ui.button("CONTROLS") {
+    menus.controls = Some(bindings.clone());
+  }
+  if let Some(profiles) = profiles {
+    if ui.button(&format!("PROFILE {}", profile.name.to_uppercase())) {
+      menus.profiles = Some(ProfilesMenu::read(&profiles, String::new()));
+    }
+  }
+  if stats.is_some() && ui.button("STATS") {
+    menus.stats = Some(0);
+  }
+  if upgrades.is_some() && ui.button("SHOP") {
+    menus.shop = Some((Shop::default(), String::new()));
+  }
+  if practice.is_some() && ui.button("PRACTICE") {
+    menus.practice = true;
+  }
+  if practice.is_some() && console.is_some() && ui.button("CONSOLE") {
+    menus.console = Some(String::new());
+  }
+  if ui.button("QUIT") {
+    menus.quit_dialog = true;
+  }
+  ui.end();
+}
+
+/// Rebinds the first player's keys and mouse buttons, the first bound to each action. A source already
+/// bound elsewhere is refused. The bindings file is written on the way out and read on the next start.
+pub fn controls_menu_system(
+  mut menus: ResMut<Menus>,
+  mut ui: ResMut<UiContext>,
+  bindings: Res<KeyBindings>,
+  profile: Res<Profile>,
+) {
+  let Some(edited) = &mut menus.controls else {
+    return;
+  };
+  // the rows change width with every capture, lined up on the left they don't jump around
+  let layout = menu_layout();
+  ui.begin(
+    "controls",
+    Layout {
+      anchor: layout.anchor - glam::vec2(CONTROLS_MENU_WIDTH / 2.0, 0.0),
+      align: Align::Left,
+      ..layout
+    },
+  );
+  ui.label("applied on the next start");
+  for action in Action::ALL {
+    let name = action.name().replace('_', " ").to_uppercase();
+    let bound = edited.player(PlayerId(0)).iter().find(|(source, bound)| {
+      *bound == action && !matches!(source, InputSource::ControllerButton(_))
+    });
+    let Some(&(from, _)) = bound else {
+      ui.label(format!("{} UNBOUND", name));
+      continue;
+    };
+    let mut to = from;
+    if ui.key_capture(&name, &mut to) && !matches!(to, InputSource::ControllerButton(_)) {
+      let mut rebound = edited.clone();
+      rebound.rebind(PlayerId(0), from, to);
+      if rebound.conflict().is_none() {
+        *edited = rebound;
+      }
+    }
+  }
+  if ui.button("BACK") || ui.back() {
+    if *edited != *bindings {
+      edited.save(&profile);
+    }
+    menus.controls = None;
+  }
+  ui.end();
+}
+
+/// Picks the profile of the next start, adds, renames and deletes profiles. The one playing can't be
+/// renamed or deleted, the game still writes to it. Deleting takes the name typed out.
+pub fn profiles_menu_system(
+  mut menus: ResMut<Menus>,
+  mut ui: ResMut<UiContext>,
+  profile: Res<Profile>,
+  profiles: Option<Res<Profiles>>,
+) {
+  let (Some(menu), Some(profiles)) = (&mut menus.profiles, profiles) else {
+    return;
+  };
+  ui.begin("profiles", menu_layout());
+  ui.label(format!("playing as {}, picks apply on the next start", profile.name));
+  let mut change = None;
+  for name in &menu.names {
+    let marker = if *name == menu.next { " <" } else { "" };
+    if ui.button(&format!("{}{}", name.to_uppercase(), marker)) {
+      change = Some(profiles.select(name).map(drop));
+    }
+  }
+  if ui.text_input("NEW", &mut menu.new_name, PROFILE_NAME_MAX_LEN) {
+    change = Some(if menu.names.contains(&menu.new_name) {
+      Err(Error::InvalidProfile { reason: "a profile with that name exists" })
+    } else {
+      profiles.select(&menu.new_name).map(drop)
+    });
+  }
+  let in_use = || Err(Error::InvalidProfile { reason: "the profile is in use" });
+  let target = menu.next.to_uppercase();
+  if ui.text_input(&format!("RENAME {} TO", target), &mut menu.rename, PROFILE_NAME_MAX_LEN) {
+    change = Some(if menu.next == profile.name { in_use() } else { profiles.rename(&menu.next, &menu.rename) });
+  }
+  let prompt = format!("DELETE {}, TYPE ITS NAME", target);
+  if ui.text_input(&prompt, &mut menu.confirmation, PROFILE_NAME_MAX_LEN) {
+    change = Some(if menu.next == profile.name { in_use() } else { profiles.delete(&menu.next, &menu.confirmation) });
+  }
+  if !menu.status.is_empty() {
+    ui.label(menu.status.clone());
+  }
+  if ui.button("BACK") || ui.back() {
+    menus.profiles = None;
+  } else if let Some(change) = change {
+    let status = change.err().map(|e| e.to_string()).unwrap_or_default();
+    *menu = ProfilesMenu::read(&profiles, status);
+  }
+  ui.end();
+}
+
+/// The profile's lifetime stats, [`STATS_ROWS_SHOWN`] rows at a time scrolled with up and down.
+pub fn stats_menu_system(mut menus: ResMut<Menus>, mut ui: ResMut<UiContext>, stats: Option<Res<LifetimeStats>>) {
+  let (Some(first), Some(stats)) = (&mut menus.stats, stats) else {
+    return;
+  };
+  let rows = stats_rows(&stats);
+  let last = rows.len().saturating_sub(STATS_ROWS_SHOWN);
+  ui.begin("stats", menu_layout());
+  *first = (*first as i32 + ui.scroll()).clamp(0, last as i32) as usize;
+  ui.label(if *first > 0 { "^" } else { "" });
+  for (label, value) in rows.into_iter().skip(*first).take(STATS_ROWS_SHOWN) {
+    ui.pair(label, value);
+  }
+  ui.label(if *first < last { "v" } else { "" });
+  if ui.button("BACK") || ui.back() {
+    menus.stats = None;
+  }
+  ui.end();
+}
+
+/// Spends skill points on upgrades for the next start, the last purchase can be taken back until the
+/// shop is left. The upgrades are saved on the way out.
+pub fn shop_menu_system(mut menus: ResMut<Menus>, mut ui: ResMut<UiContext>, upgrades: Option<ResMut<PlayerUpgrades>>) {
+  let (Some((shop, status)), Some(mut upgrades)) = (&mut menus.shop, upgrades) else {
+    return;
+  };
+  ui.begin("shop", menu_layout());
+  ui.label("applied on the next start");
+  ui.pair("SKILL POINTS", upgrades.skill_points.to_string());
+  let mut change = None;
+  for upgrade in Upgrade::ALL {
+    let name = upgrade.name().replace('_', " ").to_uppercase();
+    let level = upgrades.level(upgrade);
+    let price = if level < UPGRADE_MAX_LEVEL { format!("{} SP", upgrade.cost(level)) } else { String::from("MAX") };
+    if ui.button(&format!("{} {}/{} {}", name, level, UPGRADE_MAX_LEVEL, price)) {
+      change = Some(shop.buy(&mut upgrades, upgrade));
+    }
+  }
+  // says exactly what the undo gives back
+  if let Some(last) = shop.undoable(&upgrades) {
+    let name = last.upgrade.name().replace('_', " ").to_uppercase();
+    if ui.button(&format!("UNDO {} +{}, REFUND {} SP", name, last.levels, last.cost)) {
+      change = Some(shop.undo(&mut upgrades).map(drop));
+    }
+  }
+  if let Some(change) = change {
+    *status = change.err().map(|e| e.to_string()).unwrap_or_default();
+  }
+  if !status.is_empty() {
+    ui.label(status.clone());
+  }
+  if ui.button("BACK") || ui.back() {
+    if let Some((shop, _)) = menus.shop.take() {
+      shop.exit(&mut upgrades);
+    }
+  }
+  ui.end();
+}
+
+/// The practice tools, using any of them keeps the run out of the high scores, the stats and the skill
+/// points. Only looking doesn't.
+pub fn practice_menu_system(
+  mut menus: ResMut<Menus>,
+  mut ui: ResMut<UiContext>,
+  practice: Option<ResMut<Practice>>,
+  run: Res<RunStats>,
+) {
+  let (true, Some(mut practice)) = (menus.practice, practice) else {
+    return;
+  };
+  ui.begin("practice", menu_layout());
+  ui.label(if run.practiced { "this run is practiced" } else { "using a tool makes this a practice run" });
+  if ui.button("SPAWN ROCKS") {
+    practice.dispatch(PracticeCommand::SpawnRocks(PRACTICE_ROCKS));
+  }
+  if ui.button("SPAWN LASER") {
+    practice.dispatch(PracticeCommand::SpawnLaser);
+  }
+  let mut invincible = practice.invincible;
+  if ui.toggle("INVINCIBLE", &mut invincible) {
+    practice.dispatch(PracticeCommand::Invincible(invincible));
+  }
+  if ui.button("REFILL BOOST AND AMMO") {
+    practice.dispatch(PracticeCommand::Refill);
+  }
+  if ui.button("BACK") || ui.back() {
+    menus.practice = false;
+  }
+  ui.end();
+}
+
+/// The balance console, its last lines above the command line. A change makes the run a practice run
+/// like the tools of the practice panel.
+pub fn console_menu_system(
+  mut menus: ResMut<Menus>,
+  mut ui: ResMut<UiContext>,
+  console: Option<ResMut<Console>>,
+  practice: Option<ResMut<Practice>>,
+  balance: Res<GameBalance>,
+  run: Res<RunStats>,
+) {
+  let (Some(line), Some(mut console), Some(mut practice)) = (&mut menus.console, console, practice) else {
+    return;
+  };
+  ui.begin("console", menu_layout());
+  ui.label(if run.practiced { "this run is practiced" } else { "changing the balance makes this a practice run" });
+  for logged in &console.log {
+    ui.label(logged.clone());
+  }
+  if ui.command_input(">", line, CONSOLE_LINE_MAX_LEN) && !line.is_empty() {
+    console.run(line, &balance, &mut practice);
+    line.clear();
+  }
+  if ui.button("BACK") || ui.back() {
+    menus.console = None;
+  }
+  ui.end();
+}
+
+/// Asks before quitting, no is the default.
+pub fn quit_dialog_system(mut menus: ResMut<Menus>, mut ui: ResMut<UiContext>) {
+  if !menus.quit_dialog {
+    return;
+  }
+  ui.begin("quit", menu_layout());
+  ui.label("QUIT BYTEPATH?");
+  if ui.button("NO") || ui.back() {
+    menus.quit_dialog = false;
+  }
+  if ui.button("YES") {
+    menus.quit = true;
+  }
+  ui.end();
+}
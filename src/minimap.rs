@@ -0,0 +1,156 @@
+use crate::{
+  color::ColorGl,
+  environment::{
+    MINIMAP_CELL_SIZE, MINIMAP_HEIGHT, MINIMAP_MARGIN, MINIMAP_MAX_DOTS, MINIMAP_WIDTH, SCREEN_HEIGHT, SCREEN_WIDTH,
+  },
+  resources::WorldBounds,
+};
+use std::collections::HashMap;
+
+pub struct Dot {
+  pub position: glam::Vec2,
+  pub color: ColorGl,
+  pub size: f32,
+}
+
+pub fn minimap_origin() -> glam::Vec2 {
+  glam::vec2(
+    SCREEN_WIDTH as f32 - MINIMAP_WIDTH - MINIMAP_MARGIN,
+    SCREEN_HEIGHT as f32 - MINIMAP_HEIGHT - MINIMAP_MARGIN,
+  )
+}
+
+pub fn playfield_to_minimap(bounds: &WorldBounds, position: glam::Vec2) -> glam::Vec2 {
+  let scale = glam::vec2(MINIMAP_WIDTH, MINIMAP_HEIGHT) / bounds.size();
+  let clamped = position.clamp(bounds.min, bounds.max) - bounds.min;
+  minimap_origin() + clamped * scale
+}
+
+fn brighten(color: ColorGl, amount: f32) -> ColorGl {
+  ColorGl {
+    r: color.r + (1.0 - color.r) * amount,
+    g: color.g + (1.0 - color.g) * amount,
+    b: color.b + (1.0 - color.b) * amount,
+    a: color.a,
+  }
+}
+
+/// Groups minimap positions per cell and color group. A cell holding more than three dots of the
+/// same group is collapsed into a single, brighter dot at the centroid. At most [`MINIMAP_MAX_DOTS`]
+/// are kept, taken from every group in turn so a crowded group can't crowd out the others. Groups
+/// earlier in `colors` go first when the cap doesn't divide evenly.
+pub fn cluster_dots(positions: impl Iterator<Item = (usize, glam::Vec2)>, colors: &[ColorGl]) -> Vec<Dot> {
+  let mut cells = HashMap::<(usize, i32, i32), Vec<glam::Vec2>>::new();
+  for (group, position) in positions {
+    let cell = (position / MINIMAP_CELL_SIZE).floor();
+    cells
+      .entry((group, cell.x as i32, cell.y as i32))
+      .or_default()
+      .push(position);
+  }
+
+  let mut keys = cells.keys().copied().collect::<Vec<_>>();
+  keys.sort_unstable();
+
+  let mut groups = colors.iter().map(|_| Vec::new()).collect::<Vec<_>>();
+  for key in keys {
+    let positions = &cells[&key];
+    let color = colors[key.0];
+    let dots = &mut groups[key.0];
+    if positions.len() > 3 {
+      let centroid = positions.iter().sum::<glam::Vec2>() / positions.len() as f32;
+      dots.push(Dot {
+        position: centroid,
+        color: brighten(color, 0.5),
+        size: 2.0,
+      });
+    } else {
+      dots.extend(positions.iter().map(|&position| Dot {
+        position,
+        color,
+        size: 1.0,
+      }));
+    }
+  }
+
+  let mut groups = groups.into_iter().map(Vec::into_iter).collect::<Vec<_>>();
+  let mut dots = Vec::new();
+  while dots.len() < MINIMAP_MAX_DOTS {
+    let taken = dots.len();
+    dots.extend(groups.iter_mut().filter_map(Iterator::next).take(MINIMAP_MAX_DOTS - taken));
+    if dots.len() == taken {
+      break;
+    }
+  }
+
+  dots
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn bounds() -> WorldBounds {
+    WorldBounds {
+      min: glam::vec2(0.0, 0.0),
+      max: glam::vec2(480.0, 270.0),
+    }
+  }
+
+  #[test]
+  fn the_playfield_fills_the_minimap_and_the_rest_is_clamped_to_its_border() {
+    let origin = minimap_origin();
+    let size = glam::vec2(MINIMAP_WIDTH, MINIMAP_HEIGHT);
+    let bounds = bounds();
+    assert_eq!(playfield_to_minimap(&bounds, bounds.min), origin);
+    assert_eq!(playfield_to_minimap(&bounds, bounds.max), origin + size);
+    assert_eq!(playfield_to_minimap(&bounds, bounds.center()), origin + size / 2.0);
+    assert_eq!(playfield_to_minimap(&bounds, glam::vec2(-100.0, 135.0)), origin + glam::vec2(0.0, size.y / 2.0));
+    assert_eq!(playfield_to_minimap(&bounds, glam::vec2(1_000.0, 1_000.0)), origin + size);
+
+    // a shrunken arena still fills the whole box
+    let shrunk = WorldBounds {
+      min: glam::vec2(120.0, 60.0),
+      max: glam::vec2(360.0, 210.0),
+    };
+    assert_eq!(playfield_to_minimap(&shrunk, shrunk.min), origin);
+    assert_eq!(playfield_to_minimap(&shrunk, shrunk.max), origin + size);
+  }
+
+  #[test]
+  fn more_than_three_dots_of_a_group_in_a_cell_collapse_into_one() {
+    let colors = [ColorGl::from((100, 0, 0)), ColorGl::from((0, 100, 0))];
+    let cell = |i: usize| glam::vec2(0.5 + i as f32 * 0.5, 1.0);
+
+    let three = cluster_dots((0..3).map(|i| (0, cell(i))), &colors);
+    assert_eq!(three.len(), 3);
+    assert!(three.iter().all(|dot| dot.size == 1.0));
+
+    let four = cluster_dots((0..4).map(|i| (0, cell(i))), &colors);
+    assert_eq!(four.len(), 1);
+    assert_eq!((four[0].position, four[0].size), (glam::vec2(1.25, 1.0), 2.0));
+    assert!(four[0].color.r > colors[0].r);
+
+    // other groups and other cells don't count towards it
+    let mixed = (0..3).map(|i| (0, cell(i))).chain((0..3).map(|i| (1, cell(i))));
+    assert_eq!(cluster_dots(mixed, &colors).len(), 6);
+    let spread = (0..4).map(|i| (0, glam::vec2(i as f32 * MINIMAP_CELL_SIZE, 0.0)));
+    assert_eq!(cluster_dots(spread, &colors).len(), 4);
+  }
+
+  #[test]
+  fn a_crowded_group_leaves_room_for_the_others() {
+    let colors = [ColorGl::from((100, 0, 0)), ColorGl::from((0, 100, 0)), ColorGl::from((0, 0, 100))];
+    // one dot per cell, far more than the cap for the first group
+    let grid = |group: usize, count: usize| {
+      (0..count).map(move |i| (group, glam::vec2((i % 20) as f32, (i / 20) as f32) * MINIMAP_CELL_SIZE))
+    };
+    let dots = cluster_dots(grid(0, 200).chain(grid(1, 200)).chain(grid(2, 5)), &colors);
+    assert_eq!(dots.len(), MINIMAP_MAX_DOTS);
+    let count = |color: ColorGl| dots.iter().filter(|dot| dot.color == color).count();
+    // the small group is shown whole, the rest is shared, the first group taking what doesn't divide
+    assert_eq!(count(colors[2]), 5);
+    let shared = MINIMAP_MAX_DOTS - 5;
+    assert_eq!((count(colors[0]), count(colors[1])), (shared.div_ceil(2), shared / 2));
+  }
+}
@@ -0,0 +1,196 @@
+use crate::{
+  components::Boost,
+  environment::AMMO_MAX,
+  error::{find_asset, Error},
+};
+use bevy_ecs::prelude::*;
+
+pub const BUILD_FILE: &str = "build.ron";
+
+/// The passive stats of a build, read once at startup from [`BUILD_FILE`], see [`Modifiers::parse`]
+/// for the format. Players read them as they spawn, their shots as they are fired and the pickup
+/// timers as they are set up, so a changed build only shows in the next run.
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+pub struct Modifiers {
+  pub movement_speed_mult: f32,
+  pub boost_max_add: f32,
+  pub projectile_speed_mult: f32,
+  pub ammo_max_add: i32,
+  /// Ammo, boost and attack pickups spawn this many times as often.
+  pub pickup_spawn_rate_mult: f32,
+}
+
+/// The `key: value` pairs of a flat RON struct, see [`Modifiers::parse`].
+pub fn ron_fields(text: &str) -> Result<Vec<(String, String)>, &'static str> {
+  let text = text
+    .lines()
+    .map(|line| line.split("//").next().unwrap_or_default())
+    .collect::<Vec<_>>()
+    .join("\n");
+  let body = text
+    .trim()
+    .trim_start_matches(|c: char| c.is_alphanumeric() || c == '_')
+    .trim_start()
+    .strip_prefix('(')
+    .and_then(|text| text.strip_suffix(')'))
+    .ok_or("expected a struct in parentheses")?;
+  body
+    .split(',')
+    .map(str::trim)
+    .filter(|field| !field.is_empty())
+    .map(|field| {
+      let (key, value) = field.split_once(':').ok_or("expected key: value")?;
+      Ok((key.trim().to_string(), value.trim().to_string()))
+    })
+    .collect()
+}
+
+impl Default for Modifiers {
+  fn default() -> Self {
+    Self {
+      movement_speed_mult: 1.0,
+      boost_max_add: 0.0,
+      projectile_speed_mult: 1.0,
+      ammo_max_add: 0,
+      pickup_spawn_rate_mult: 1.0,
+    }
+  }
+}
+
+impl Modifiers {
+  /// The subset of RON a flat struct needs, `(movement_speed_mult: 1.5, ammo_max_add: 20)` with an
+  /// optional name in front, `//` comments and a trailing comma. Keys left out keep their default.
+  pub fn parse(text: &str) -> Result<Self, Error> {
+    let invalid = |reason| Error::InvalidBuild { reason };
+    let mut modifiers = Self::default();
+    for (key, value) in ron_fields(text).map_err(invalid)? {
+      let float = || value.parse::<f32>().map_err(|_| invalid("expected a number"));
+      match key.as_str() {
+        "movement_speed_mult" => modifiers.movement_speed_mult = float()?,
+        "boost_max_add" => modifiers.boost_max_add = float()?,
+        "projectile_speed_mult" => modifiers.projectile_speed_mult = float()?,
+        "ammo_max_add" => modifiers.ammo_max_add = value.parse().map_err(|_| invalid("expected an integer"))?,
+        "pickup_spawn_rate_mult" => modifiers.pickup_spawn_rate_mult = float()?,
+        _ => return Err(invalid("unknown key")),
+      }
+    }
+    modifiers.validate()?;
+    Ok(modifiers)
+  }
+
+  /// Multipliers have to be positive and the adds have to leave the players some boost and ammo.
+  pub fn validate(&self) -> Result<(), Error> {
+    let invalid = |reason| Err(Error::InvalidBuild { reason });
+    let mults = [self.movement_speed_mult, self.projectile_speed_mult, self.pickup_spawn_rate_mult];
+    if !mults.iter().all(|mult| mult.is_finite() && *mult > 0.0) {
+      return invalid("multipliers have to be positive");
+    }
+    if !(Boost::default().max_boost + self.boost_max_add > 0.0 && self.boost_max_add.is_finite()) {
+      return invalid("boost_max_add leaves no boost");
+    }
+    if AMMO_MAX as i64 + self.ammo_max_add as i64 <= 0 {
+      return invalid("ammo_max_add leaves no ammo");
+    }
+    Ok(())
+  }
+
+  /// Reads [`BUILD_FILE`] if there is one. A broken file is reported and the run starts without a
+  /// build, like a broken settings file.
+  pub fn load() -> Self {
+    let Ok(path) = find_asset(BUILD_FILE) else {
+      return Self::default();
+    };
+    match std::fs::read_to_string(path).map_err(Error::from).and_then(|text| Self::parse(&text)) {
+      Ok(modifiers) => modifiers,
+      Err(e) => {
+        eprintln!("{}, playing without a build", e);
+        Self::default()
+      }
+    }
+  }
+
+  pub fn ammo_max(&self) -> u32 {
+    (AMMO_MAX as i64 + self.ammo_max_add as i64).clamp(1, u32::MAX as i64) as u32
+  }
+
+  /// One line per modifier that differs from its default, e.g. "MOVEMENT SPEED x1.5" or "AMMO MAX +20".
+  pub fn active(&self) -> Vec<String> {
+    let mults = [
+      ("MOVEMENT SPEED", self.movement_speed_mult),
+      ("PROJECTILE SPEED", self.projectile_speed_mult),
+      ("PICKUP SPAWN RATE", self.pickup_spawn_rate_mult),
+    ];
+    let adds = [("BOOST MAX", self.boost_max_add), ("AMMO MAX", self.ammo_max_add as f32)];
+    mults
+      .iter()
+      .filter(|(_, mult)| *mult != 1.0)
+      .map(|(name, mult)| format!("{} x{}", name, mult))
+      .chain(adds.iter().filter(|(_, add)| *add != 0.0).map(|(name, add)| format!("{} {:+}", name, add)))
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::shop::PlayerUpgrades;
+
+  #[test]
+  fn a_build_sets_the_keys_it_names_and_keeps_the_rest() {
+    let text = "Build(\n  movement_speed_mult: 1.5, // faster\n  ammo_max_add: -20,\n  boost_max_add: 25.5,\n)";
+    let modifiers = Modifiers::parse(text).unwrap();
+    assert_eq!(
+      modifiers,
+      Modifiers {
+        movement_speed_mult: 1.5,
+        boost_max_add: 25.5,
+        ammo_max_add: -20,
+        ..Modifiers::default()
+      }
+    );
+    assert_eq!(modifiers.ammo_max(), AMMO_MAX - 20);
+    assert_eq!(modifiers.active(), ["MOVEMENT SPEED x1.5", "BOOST MAX +25.5", "AMMO MAX -20"]);
+    assert_eq!(Modifiers::parse("()").unwrap(), Modifiers::default());
+    assert!(Modifiers::default().active().is_empty());
+  }
+
+  #[test]
+  fn a_build_that_leaves_nothing_is_refused() {
+    for text in [
+      "(movement_speed_mult: 0)",
+      "(projectile_speed_mult: -1)",
+      "(pickup_spawn_rate_mult: inf)",
+      "(boost_max_add: -1000)",
+      "(ammo_max_add: -1000)",
+      "(ammo_max_add: 1.5)",
+      "(movement_speed: 2)",
+      "movement_speed_mult: 2",
+    ] {
+      assert!(matches!(Modifiers::parse(text), Err(Error::InvalidBuild { .. })), "{}", text);
+    }
+  }
+
+  #[test]
+  fn upgrades_stack_on_the_build() {
+    let build = Modifiers::parse("(movement_speed_mult: 1.5, ammo_max_add: 20, boost_max_add: 10)").unwrap();
+    let mut upgrades = PlayerUpgrades::parse("movement_speed = 5\nammo_max = 2\nboost_max = 1").unwrap();
+    // only the levels in play count
+    assert_eq!(upgrades.applied_to(build), build);
+    upgrades.start_run();
+
+    let stacked = upgrades.applied_to(build);
+    // multipliers multiply, adds add up
+    assert!((stacked.movement_speed_mult - 2.25).abs() < 1e-6);
+    assert_eq!(stacked.ammo_max(), AMMO_MAX + 40);
+    assert_eq!(stacked.boost_max_add, 30.0);
+    assert_eq!(stacked.projectile_speed_mult, 1.0);
+    assert!(stacked.validate().is_ok());
+
+    // however far the adds go, there is always a shot
+    let drained = Modifiers {
+      ammo_max_add: i32::MIN,
+      ..build
+    };
+    assert_eq!(drained.ammo_max(), 1);
+  }
+}
@@ -0,0 +1,378 @@
+use crate::{
+  color::ColorGl,
+  components::{Player, Velocity},
+  environment::{
+    HUD_LINE_HEIGHT, HUD_TEXT_SCALE, KEEP_MOVING_GRACE, OBJECTIVE_BONUS_SCORE, OBJECTIVE_BONUS_SKILL_POINTS,
+    RGB_COLOR_HUD_BOOST, RGB_COLOR_HUD_DEAD, RGB_COLOR_PLAYER, SCREEN_RENDER_HEIGHT, SCREEN_RENDER_WIDTH,
+    STILL_SPEED, WAVE_DURATION,
+  },
+  events::{GameEvents, KilledBy, Notice, NotificationEvent, Pickup, PickupEvent},
+  resources::{Phase, Score, TextBuffers, TextStyle, Time, WorldBounds},
+  shop::PlayerUpgrades,
+  stats::RunStats,
+  units::WorldPos,
+};
+use bevy_ecs::prelude::*;
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+/// A bonus goal of a wave, the parameters are part of the definition. Counted ones are done once they
+/// are reached, the others once the wave ends without them broken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+  NoBombs,
+  CollectAmmo(u32),
+  Kills(u32),
+  /// Kills of a single bomb's shockwave.
+  BombKills(u32),
+  /// Never slower than [`STILL_SPEED`] for longer than [`KEEP_MOVING_GRACE`] gameplay seconds, so a
+  /// pause or a slow-mo doesn't break it.
+  KeepMoving,
+}
+
+/// What a wave picks its objectives from.
+pub const OBJECTIVE_POOL: [Objective; 6] = [
+  Objective::NoBombs,
+  Objective::CollectAmmo(5),
+  Objective::CollectAmmo(10),
+  Objective::Kills(5),
+  Objective::BombKills(3),
+  Objective::KeepMoving,
+];
+
+impl Objective {
+  pub fn text(self) -> String {
+    match self {
+      Objective::NoBombs => String::from("NO BOMBS"),
+      Objective::CollectAmmo(count) => format!("COLLECT {} AMMO", count),
+      Objective::Kills(count) => format!("SHOOT DOWN {} ROCKS", count),
+      Objective::BombKills(count) => format!("{} KILLS WITH ONE BOMB", count),
+      Objective::KeepMoving => String::from("NEVER STOP MOVING"),
+    }
+  }
+
+  fn target(self) -> Option<u32> {
+    match self {
+      Objective::CollectAmmo(count) | Objective::Kills(count) | Objective::BombKills(count) => Some(count),
+      Objective::NoBombs | Objective::KeepMoving => None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+  Open,
+  Done,
+  Failed,
+}
+
+/// An objective of the wave and how it's going, reset with every wave.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tracked {
+  pub objective: Objective,
+  pub outcome: Outcome,
+  pub count: u32,
+  /// Gameplay seconds a player has been standing still.
+  still: f32,
+}
+
+/// The wave of the run, a [`WAVE_DURATION`] slice of gameplay time, and its objectives.
+#[derive(Debug, Resource)]
+pub struct Objectives {
+  seed: u64,
+  pub wave: u32,
+  /// Gameplay seconds into the wave.
+  pub elapsed: f32,
+  pub tracked: Vec<Tracked>,
+}
+
+impl Objectives {
+  pub fn new(seed: u64) -> Self {
+    let mut objectives = Self {
+      seed,
+      wave: 0,
+      elapsed: 0.0,
+      tracked: Vec::new(),
+    };
+    objectives.start_wave(1);
+    objectives
+  }
+
+  /// One or two objectives of the pool, picked from the seed and the wave alone. A replay picks the same
+  /// and the simulation's random numbers aren't touched.
+  fn start_wave(&mut self, wave: u32) {
+    let mut rng = SmallRng::seed_from_u64(self.seed ^ (wave as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    let first = rng.gen_range(0..OBJECTIVE_POOL.len());
+    let mut picks = vec![first];
+    if rng.gen_bool(0.5) {
+      picks.push((first + rng.gen_range(1..OBJECTIVE_POOL.len())) % OBJECTIVE_POOL.len());
+    }
+    self.wave = wave;
+    self.elapsed = 0.0;
+    self.tracked = picks
+      .into_iter()
+      .map(|i| Tracked {
+        objective: OBJECTIVE_POOL[i],
+        outcome: Outcome::Open,
+        count: 0,
+        still: 0.0,
+      })
+      .collect();
+  }
+
+  /// Back to the first wave, for the next run.
+  pub fn restart(&mut self) {
+    self.start_wave(1);
+  }
+
+  pub fn observe(&mut self, event: &GameEvents) {
+    self.update_open(|tracked| match (event, tracked.objective) {
+      // a death fails them all
+      (GameEvents::PlayerDeath(..), _) | (GameEvents::BombDetonated, Objective::NoBombs) => {
+        tracked.outcome = Outcome::Failed
+      }
+      (GameEvents::BombDetonated, Objective::BombKills(_)) => tracked.count = 0,
+      (GameEvents::EnemyDeath(_, KilledBy::Projectile), Objective::Kills(_))
+      | (GameEvents::EnemyDeath(_, KilledBy::Bomb), Objective::BombKills(_)) => tracked.count += 1,
+      _ => {}
+    });
+  }
+
+  pub fn collected(&mut self, pickup: Pickup) {
+    self.update_open(|tracked| {
+      if let (Pickup::Ammo, Objective::CollectAmmo(_)) = (pickup, tracked.objective) {
+        tracked.count += 1;
+      }
+    });
+  }
+
+  /// Runs `update` on the objectives still open, those that reached their target are done.
+  fn update_open(&mut self, mut update: impl FnMut(&mut Tracked)) {
+    for tracked in self.tracked.iter_mut().filter(|tracked| tracked.outcome == Outcome::Open) {
+      update(tracked);
+      if tracked.objective.target().is_some_and(|target| tracked.count >= target) {
+        tracked.outcome = Outcome::Done;
+      }
+    }
+  }
+
+  /// Advances the wave by `dt` gameplay seconds, `still` whether a player stands still. At the end of
+  /// the wave settles the objectives still open, starts the next one and returns how many were done.
+  pub fn tick(&mut self, dt: f32, still: bool) -> Option<u32> {
+    for tracked in self.tracked.iter_mut().filter(|tracked| tracked.objective == Objective::KeepMoving) {
+      tracked.still = if still { tracked.still + dt } else { 0.0 };
+      if tracked.still > KEEP_MOVING_GRACE && tracked.outcome == Outcome::Open {
+        tracked.outcome = Outcome::Failed;
+      }
+    }
+
+    self.elapsed += dt;
+    if self.elapsed < WAVE_DURATION {
+      return None;
+    }
+    let done = self.tracked.iter().filter(|tracked| match tracked.outcome {
+      Outcome::Open => tracked.objective.target().is_none(),
+      outcome => outcome == Outcome::Done,
+    });
+    let done = done.count() as u32;
+    self.start_wave(self.wave + 1);
+    Some(done)
+  }
+}
+
+/// Tracks the objectives of the wave and pays out the bonus of those done when it ends. Starts over with
+/// every run and stands still on the game over screen.
+pub fn objectives_system(
+  mut event_reader: EventReader<GameEvents>,
+  mut pickups: EventReader<PickupEvent>,
+  mut notices: EventWriter<NotificationEvent>,
+  mut objectives: ResMut<Objectives>,
+  players: Query<&Velocity, With<Player>>,
+  time: Res<Time>,
+  phase: Res<Phase>,
+  bounds: Res<WorldBounds>,
+  run: Res<RunStats>,
+  mut score: ResMut<Score>,
+  upgrades: Option<ResMut<PlayerUpgrades>>,
+) {
+  if phase.is_changed() && !phase.is_added() && *phase == Phase::Run {
+    objectives.restart();
+  }
+  for event in event_reader.iter() {
+    objectives.observe(event);
+  }
+  for PickupEvent(pickup) in pickups.iter() {
+    objectives.collected(*pickup);
+  }
+  if *phase != Phase::Run {
+    return;
+  }
+
+  let still = players.iter().any(|velocity| match velocity {
+    Velocity::Linear(velocity) => velocity.length() < STILL_SPEED,
+    Velocity::Heading(speed) => speed.abs() < STILL_SPEED,
+  });
+  let Some(done) = objectives.tick(time.as_secs_f32(), still) else {
+    return;
+  };
+  if done == 0 {
+    return;
+  }
+  let bonus = done * OBJECTIVE_BONUS_SCORE;
+  score.0 = score.0.saturating_add(bonus);
+  if let Some(mut upgrades) = upgrades.filter(|_| !run.practiced) {
+    upgrades.award(u64::from(done) * OBJECTIVE_BONUS_SKILL_POINTS);
+    upgrades.save();
+  }
+  notices.send(NotificationEvent {
+    notice: Notice::WaveBonus(bonus),
+    position: WorldPos::from(bounds.center()),
+  });
+}
+
+/// The wave at the top of the screen, its objectives below it marked done or failed.
+pub fn objectives_hud_system(objectives: Res<Objectives>, phase: Res<Phase>, mut texts: ResMut<TextBuffers>) {
+  if *phase != Phase::Run {
+    return;
+  }
+  let centered = |texts: &mut TextBuffers, text: &str, y: f32, color| {
+    let x = (SCREEN_RENDER_WIDTH as f32 - texts.width(text, HUD_TEXT_SCALE, TextStyle::Bitmap)) / 2.0;
+    texts.build_text(text, x, y, HUD_TEXT_SCALE, ColorGl::from(color), TextStyle::Bitmap);
+  };
+  let top = SCREEN_RENDER_HEIGHT as f32 - 30.0;
+  let left = (WAVE_DURATION - objectives.elapsed).max(0.0).ceil();
+  centered(&mut texts, &format!("WAVE {} {:.0}", objectives.wave, left), top, RGB_COLOR_PLAYER);
+  for (i, tracked) in objectives.tracked.iter().enumerate() {
+    let (mark, color) = match tracked.outcome {
+      Outcome::Open => ("[ ]", RGB_COLOR_PLAYER),
+      Outcome::Done => ("[+]", RGB_COLOR_HUD_BOOST),
+      Outcome::Failed => ("[x]", RGB_COLOR_HUD_DEAD),
+    };
+    let progress = match tracked.objective.target() {
+      Some(target) if tracked.outcome == Outcome::Open => format!(" {}/{}", tracked.count, target),
+      _ => String::new(),
+    };
+    let text = format!("{} {}{}", mark, tracked.objective.text(), progress);
+    centered(&mut texts, &text, top - (i + 1) as f32 * HUD_LINE_HEIGHT, color);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::events::DeathCause;
+  use bevy_ecs::entity::Entity;
+
+  const DT: f32 = 1.0 / 60.0;
+
+  /// A wave of `objective` alone.
+  fn wave(objective: Objective) -> Objectives {
+    let mut objectives = Objectives::new(1);
+    objectives.tracked.truncate(1);
+    objectives.tracked[0] = Tracked {
+      objective,
+      outcome: Outcome::Open,
+      count: 0,
+      still: 0.0,
+    };
+    objectives
+  }
+
+  fn outcome(objectives: &Objectives) -> Outcome {
+    objectives.tracked[0].outcome
+  }
+
+  /// Plays out the rest of the wave without anyone standing still, returns how many were done.
+  fn finish(objectives: &mut Objectives) -> u32 {
+    loop {
+      if let Some(done) = objectives.tick(DT, false) {
+        return done;
+      }
+    }
+  }
+
+  #[test]
+  fn counted_objectives_are_done_once_reached() {
+    let kill = || GameEvents::EnemyDeath(WorldPos::new(0.0, 0.0), KilledBy::Projectile);
+    let bombed = || GameEvents::EnemyDeath(WorldPos::new(0.0, 0.0), KilledBy::Bomb);
+    let runs = [
+      (Objective::Kills(2), vec![kill(), bombed(), kill()]),
+      (Objective::BombKills(3), vec![GameEvents::BombDetonated, bombed(), bombed(), bombed()]),
+    ];
+    for (objective, events) in runs {
+      let mut objectives = wave(objective);
+      for event in &events[..events.len() - 1] {
+        objectives.observe(event);
+      }
+      assert_eq!(outcome(&objectives), Outcome::Open, "{:?}", objective);
+      objectives.observe(events.last().unwrap());
+      assert_eq!(outcome(&objectives), Outcome::Done, "{:?}", objective);
+      assert_eq!(finish(&mut objectives), 1);
+    }
+
+    let mut objectives = wave(Objective::CollectAmmo(5));
+    for _ in 0..4 {
+      objectives.collected(Pickup::Ammo);
+      objectives.collected(Pickup::Boost);
+    }
+    assert_eq!(outcome(&objectives), Outcome::Open);
+    objectives.collected(Pickup::Ammo);
+    assert_eq!(outcome(&objectives), Outcome::Done);
+
+    // the kills of two bombs don't add up
+    let mut objectives = wave(Objective::BombKills(3));
+    for event in [GameEvents::BombDetonated, bombed(), bombed(), GameEvents::BombDetonated, bombed()] {
+      objectives.observe(&event);
+    }
+    assert_eq!(finish(&mut objectives), 0);
+  }
+
+  #[test]
+  fn the_others_are_done_unless_broken_by_the_end_of_the_wave() {
+    let mut objectives = wave(Objective::NoBombs);
+    assert_eq!(finish(&mut objectives), 1);
+    let mut objectives = wave(Objective::NoBombs);
+    objectives.observe(&GameEvents::BombDetonated);
+    assert_eq!(outcome(&objectives), Outcome::Failed);
+    assert_eq!(finish(&mut objectives), 0);
+
+    // standing still is fine for the grace window, and paused ticks add no gameplay time
+    let mut objectives = wave(Objective::KeepMoving);
+    for _ in 0..(KEEP_MOVING_GRACE / DT) as usize - 1 {
+      objectives.tick(DT, true);
+    }
+    for _ in 0..600 {
+      objectives.tick(0.0, true);
+    }
+    assert_eq!(outcome(&objectives), Outcome::Open);
+    objectives.tick(DT, false);
+    objectives.tick(KEEP_MOVING_GRACE + DT, true);
+    assert_eq!(outcome(&objectives), Outcome::Failed);
+  }
+
+  #[test]
+  fn dying_fails_everything_and_the_next_wave_starts_over() {
+    let mut objectives = wave(Objective::CollectAmmo(1));
+    objectives.observe(&GameEvents::PlayerDeath(Entity::from_raw(0), DeathCause::Laser));
+    objectives.collected(Pickup::Ammo);
+    assert_eq!(outcome(&objectives), Outcome::Failed);
+    assert_eq!(finish(&mut objectives), 0);
+    assert_eq!(objectives.wave, 2);
+    assert!(objectives.tracked.iter().all(|tracked| tracked.outcome == Outcome::Open && tracked.count == 0));
+  }
+
+  #[test]
+  fn the_same_seed_picks_the_same_objectives() {
+    let picks = |seed| {
+      let mut objectives = Objectives::new(seed);
+      (0..20).map(|_| {
+        let picked = objectives.tracked.iter().map(|tracked| tracked.objective).collect::<Vec<_>>();
+        finish(&mut objectives);
+        picked
+      })
+      .collect::<Vec<_>>()
+    };
+    assert_eq!(picks(4), picks(4));
+    assert_ne!(picks(4), picks(5));
+    assert!(picks(4).iter().all(|picked| (1..=2).contains(&picked.len())));
+  }
+}
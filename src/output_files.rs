@@ -0,0 +1,383 @@
+use crate::{
+  events::{Notice, NotificationEvent},
+  resources::WorldBounds,
+  units::WorldPos,
+};
+use bevy_ecs::prelude::*;
+use std::{
+  collections::VecDeque,
+  fs::File,
+  io::{BufWriter, Write},
+  path::{Path, PathBuf},
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    mpsc::{self, Receiver, Sender},
+    Arc, Condvar, Mutex,
+  },
+  thread::JoinHandle,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A path in `dir` no other call hands out, e.g. `stress-1700000000-3-8f2a.csv`: seconds since the
+/// epoch, a per process counter and a random suffix against other processes.
+pub fn unique_path(dir: &Path, prefix: &str, extension: &str) -> PathBuf {
+  let seconds = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+  let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+  let suffix = rand::random::<u16>();
+  dir.join(format!("{}-{}-{}-{:04x}.{}", prefix, seconds, count, suffix, extension))
+}
+
+/// Writes into a temporary file next to `path` and renames it over `path` once complete, so readers
+/// never see a partial file. The temporary file is removed if writing fails.
+pub fn write_atomic(
+  path: &Path,
+  write: impl FnOnce(&mut BufWriter<File>) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+  let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+  let temporary = path.with_file_name(format!(
+    ".{}.{}.tmp",
+    file_name,
+    COUNTER.fetch_add(1, Ordering::Relaxed)
+  ));
+
+  let result = File::create(&temporary).and_then(|file| {
+    let mut file = BufWriter::new(file);
+    write(&mut file)?;
+    file.flush()?;
+    file.get_ref().sync_all()
+  });
+  match result.and_then(|_| std::fs::rename(&temporary, path)) {
+    Ok(()) => Ok(()),
+    Err(e) => {
+      let _ = std::fs::remove_file(&temporary);
+      Err(e)
+    }
+  }
+}
+
+/// Deletes the oldest files in `dir` named `prefix-*.extension` until at most `keep` are left, returns
+/// how many were deleted.
+pub fn prune(dir: &Path, prefix: &str, extension: &str, keep: usize) -> std::io::Result<usize> {
+  let mut files = Vec::new();
+  for entry in std::fs::read_dir(dir)? {
+    let entry = entry?;
+    let name = entry.file_name().to_string_lossy().into_owned();
+    if name.starts_with(&format!("{}-", prefix)) && name.ends_with(&format!(".{}", extension)) {
+      files.push((entry.metadata()?.modified()?, name, entry.path()));
+    }
+  }
+  files.sort();
+
+  let excess = files.len().saturating_sub(keep);
+  for (_, _, path) in &files[..excess] {
+    std::fs::remove_file(path)?;
+  }
+  Ok(excess)
+}
+
+/// What a full [`OutputWriter`] queue does with one more job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backpressure {
+  /// The oldest queued job for the same kind of file is dropped to make room, or this one if there is
+  /// none. For files a newer one replaces anyway, like the high scores and stats.
+  DropOldest,
+  /// Waits for room. For files that must not get lost, like replays.
+  Block,
+}
+
+/// Keeps the newest `keep` of the files named `prefix-*.extension` in the directory of a job, see
+/// [`prune`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Retention {
+  pub prefix: &'static str,
+  pub extension: &'static str,
+  pub keep: usize,
+}
+
+/// A file for the [`OutputWriter`], serialized on the main thread so the writer owns everything it
+/// touches.
+#[derive(Debug)]
+pub struct WriteJob {
+  pub path: PathBuf,
+  pub bytes: Vec<u8>,
+  /// What the file is, for the notification.
+  pub what: &'static str,
+  pub backpressure: Backpressure,
+  /// Pruned once the file is written.
+  pub retention: Option<Retention>,
+}
+
+/// How a [`WriteJob`] ended, an error says why it was written only partly or not at all.
+#[derive(Debug)]
+pub struct WriteDone {
+  pub path: PathBuf,
+  pub what: &'static str,
+  pub result: Result<(), String>,
+}
+
+impl WriteJob {
+  /// A job that waits under backpressure and prunes nothing.
+  pub fn new(path: PathBuf, bytes: Vec<u8>, what: &'static str) -> Self {
+    Self {
+      path,
+      bytes,
+      what,
+      backpressure: Backpressure::Block,
+      retention: None,
+    }
+  }
+
+  /// Queues the job on `writer`, or writes it right away without one, like in tests and headless runs.
+  /// Either way a failure ends up on stderr.
+  pub fn submit(self, writer: Option<&OutputWriter>) {
+    match writer {
+      Some(writer) => writer.submit(self),
+      None => report(&self.write()),
+    }
+  }
+
+  /// A failed prune only leaves old files behind, the file itself was saved and reported as such.
+  fn write(self) -> WriteDone {
+    let result = write_atomic(&self.path, |out| out.write_all(&self.bytes));
+    if let (Ok(()), Some(retention), Some(dir)) = (&result, self.retention, self.path.parent()) {
+      if let Err(e) = prune(dir, retention.prefix, retention.extension, retention.keep) {
+        eprintln!("could not prune old {} files in {}: {}", self.what, dir.display(), e);
+      }
+    }
+    self.done(result.map_err(|e| e.to_string()))
+  }
+
+  fn done(self, result: Result<(), String>) -> WriteDone {
+    WriteDone {
+      path: self.path,
+      what: self.what,
+      result,
+    }
+  }
+}
+
+fn report(done: &WriteDone) {
+  if let Err(e) = &done.result {
+    eprintln!("could not save the {} to {}: {}", done.what, done.path.display(), e);
+  }
+}
+
+#[derive(Default)]
+struct Queue {
+  jobs: VecDeque<WriteJob>,
+  closed: bool,
+}
+
+impl Queue {
+  /// Queues `job`, in a full queue in place of the oldest job of its kind, which is returned. Without
+  /// one `job` itself is.
+  fn push(&mut self, job: WriteJob, capacity: usize) -> Option<WriteJob> {
+    let mut dropped = None;
+    if self.jobs.len() >= capacity {
+      match self.jobs.iter().position(|queued| queued.what == job.what) {
+        Some(oldest) => dropped = self.jobs.remove(oldest),
+        None => return Some(job),
+      }
+    }
+    self.jobs.push_back(job);
+    dropped
+  }
+}
+
+struct Shared {
+  queue: Mutex<Queue>,
+  /// Signalled when a job is queued, taken or the queue closes.
+  changed: Condvar,
+  capacity: usize,
+}
+
+/// Writes files on a thread of its own, at most `capacity` queued at once, see [`Backpressure`]. How
+/// each ended comes back over a channel, [`output_files_system`] drains it every tick. Dropping it
+/// writes what is still queued before it returns.
+#[derive(Resource)]
+pub struct OutputWriter {
+  shared: Arc<Shared>,
+  done: Mutex<Receiver<WriteDone>>,
+  /// For the jobs dropped on the main thread.
+  dropped: Sender<WriteDone>,
+  thread: Option<JoinHandle<()>>,
+}
+
+impl OutputWriter {
+  pub fn spawn(capacity: usize) -> Self {
+    let shared = Arc::new(Shared {
+      queue: Mutex::new(Queue::default()),
+      changed: Condvar::new(),
+      capacity: capacity.max(1),
+    });
+    let (sender, done) = mpsc::channel();
+    let thread = std::thread::Builder::new().name(String::from("output files")).spawn({
+      let (shared, sender) = (shared.clone(), sender.clone());
+      move || loop {
+        let job = {
+          let mut queue = shared.queue.lock().unwrap();
+          while queue.jobs.is_empty() && !queue.closed {
+            queue = shared.changed.wait(queue).unwrap();
+          }
+          match queue.jobs.pop_front() {
+            Some(job) => job,
+            None => return,
+          }
+        };
+        shared.changed.notify_all();
+        // the receiving end only goes with the writer, which waits for this thread first
+        let _ = sender.send(job.write());
+      }
+    });
+    Self {
+      shared,
+      done: Mutex::new(done),
+      dropped: sender,
+      thread: thread.map_err(|e| eprintln!("could not start the output file writer: {}", e)).ok(),
+    }
+  }
+
+  pub fn submit(&self, job: WriteJob) {
+    if self.thread.is_none() {
+      let _ = self.dropped.send(job.write());
+      return;
+    }
+    let mut queue = self.shared.queue.lock().unwrap();
+    while job.backpressure == Backpressure::Block && queue.jobs.len() >= self.shared.capacity {
+      queue = self.shared.changed.wait(queue).unwrap();
+    }
+    if let Some(dropped) = queue.push(job, self.shared.capacity) {
+      let _ = self.dropped.send(dropped.done(Err(String::from("dropped, too many files were queued"))));
+    }
+    self.shared.changed.notify_all();
+  }
+
+  /// How the jobs ended since the last call.
+  pub fn drain(&self) -> Vec<WriteDone> {
+    self.done.lock().unwrap().try_iter().collect()
+  }
+
+  /// Writes what is still queued and returns how every job not drained yet ended.
+  pub fn finish(mut self) -> Vec<WriteDone> {
+    self.close();
+    self.drain()
+  }
+
+  fn close(&mut self) {
+    self.shared.queue.lock().unwrap().closed = true;
+    self.shared.changed.notify_all();
+    if let Some(thread) = self.thread.take() {
+      let _ = thread.join();
+    }
+  }
+}
+
+impl Drop for OutputWriter {
+  fn drop(&mut self) {
+    self.close();
+  }
+}
+
+/// Reports the files the [`OutputWriter`] couldn't write, on stderr and with a notice.
+pub fn output_files_system(
+  writer: Option<Res<OutputWriter>>,
+  mut notices: EventWriter<NotificationEvent>,
+  bounds: Res<WorldBounds>,
+) {
+  let Some(writer) = writer else {
+    return;
+  };
+  for done in writer.drain().iter().filter(|done| done.result.is_err()) {
+    report(done);
+    notices.send(NotificationEvent {
+      notice: Notice::NotSaved(done.what),
+      position: WorldPos::from(bounds.center()),
+    });
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::time::Duration;
+
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("bytepath-output-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  #[test]
+  fn paths_stay_unique_under_rapid_fire_from_several_threads() {
+    let dir = temp_dir("unique");
+    let threads = (0..4).map(|_| {
+      let dir = dir.clone();
+      std::thread::spawn(move || (0..250).map(|_| unique_path(&dir, "shot", "png")).collect::<Vec<_>>())
+    });
+    let mut paths = threads.flat_map(|thread| thread.join().unwrap()).collect::<Vec<_>>();
+    paths.sort();
+    paths.dedup();
+    assert_eq!(paths.len(), 1000);
+  }
+
+  #[test]
+  fn pruning_keeps_the_newest() {
+    let dir = temp_dir("prune");
+    let epoch = SystemTime::now() - Duration::from_secs(3600);
+    // named against their age, so the order can only come from the times
+    for (i, name) in ["replay-c.bin", "replay-b.bin", "replay-a.bin", "other-a.bin"].iter().enumerate() {
+      let file = File::create(dir.join(name)).unwrap();
+      file.set_modified(epoch + Duration::from_secs(i as u64 * 60)).unwrap();
+    }
+    assert_eq!(prune(&dir, "replay", "bin", 2).unwrap(), 1);
+    let mut left = std::fs::read_dir(&dir)
+      .unwrap()
+      .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+      .collect::<Vec<_>>();
+    left.sort();
+    assert_eq!(left, ["other-a.bin", "replay-a.bin", "replay-b.bin"]);
+  }
+
+  #[test]
+  fn the_writer_writes_everything_queued_and_reports_a_failure_once() {
+    let dir = temp_dir("writer");
+    let writer = OutputWriter::spawn(2);
+    for i in 0..5 {
+      writer.submit(WriteJob::new(dir.join(format!("file-{}.txt", i)), vec![b'0' + i], "file"));
+    }
+    writer.submit(WriteJob::new(dir.join("missing").join("file.txt"), Vec::new(), "file"));
+    let done = writer.finish();
+
+    assert_eq!(done.len(), 6);
+    let failed = done.iter().filter(|done| done.result.is_err()).collect::<Vec<_>>();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].path, dir.join("missing").join("file.txt"));
+    for i in 0..5 {
+      assert_eq!(std::fs::read(dir.join(format!("file-{}.txt", i))).unwrap(), [b'0' + i]);
+    }
+    // no temporary files left behind
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 5);
+  }
+
+  #[test]
+  fn a_full_queue_drops_the_oldest_of_the_kind() {
+    let job = |name: &str, what| WriteJob {
+      backpressure: Backpressure::DropOldest,
+      ..WriteJob::new(PathBuf::from(name), Vec::new(), what)
+    };
+    let mut queue = Queue::default();
+    assert!(queue.push(job("replay", "replay"), 3).is_none());
+    assert!(queue.push(job("scores-1", "high scores"), 3).is_none());
+    assert!(queue.push(job("stats", "stats"), 3).is_none());
+
+    let dropped = queue.push(job("scores-2", "high scores"), 3).unwrap();
+    assert_eq!(dropped.path, PathBuf::from("scores-1"));
+    // nothing of the kind queued, the new one goes
+    assert_eq!(queue.push(job("profile", "profile"), 3).unwrap().path, PathBuf::from("profile"));
+    let queued = queue.jobs.iter().map(|job| job.path.to_string_lossy().into_owned()).collect::<Vec<_>>();
+    assert_eq!(queued, ["replay", "stats", "scores-2"]);
+  }
+}
@@ -0,0 +1,517 @@
+use crate::{
+  error::{find_asset, Error},
+  output_files::write_atomic,
+};
+use std::{
+  borrow::Cow,
+  io::Write,
+  ops::Deref,
+  ops::Range,
+  path::{Path, PathBuf},
+};
+
+const MAGIC: &[u8; 4] = b"BPAK";
+const VERSION: u32 = 2;
+pub const PACK_FILE: &str = "bytepath.pak";
+
+/// The shortest repeat [`compress`] refers back to, and the longest one token covers.
+const MIN_MATCH: usize = 4;
+const MAX_MATCH: usize = MIN_MATCH + 0x7f;
+/// How far back a repeat may start, the distance is stored as a u16.
+const MAX_DISTANCE: usize = u16::MAX as usize;
+/// The most bytes one byte of a compressed blob can unpack to, a three byte token repeating
+/// [`MAX_MATCH`] bytes.
+const MAX_EXPANSION: usize = MAX_MATCH.div_ceil(3);
+const HASH_BITS: u32 = 12;
+
+/// Everything `--pack-assets` bundles, each with the copy compiled into the binary as the last resort.
+const ASSETS: [(&str, &[u8]); 12] = [
+  ("m5x7.ttf", include_bytes!("../m5x7.ttf")),
+  ("assets/shaders/fbo.vert", include_bytes!("../assets/shaders/fbo.vert")),
+  ("assets/shaders/fbo.frag", include_bytes!("../assets/shaders/fbo.frag")),
+  ("assets/shaders/scene.vert", include_bytes!("../assets/shaders/scene.vert")),
+  ("assets/shaders/scene.frag", include_bytes!("../assets/shaders/scene.frag")),
+  ("assets/shaders/text.vert", include_bytes!("../assets/shaders/text.vert")),
+  ("assets/shaders/text.frag", include_bytes!("../assets/shaders/text.frag")),
+  ("assets/shaders/sdf_text.frag", include_bytes!("../assets/shaders/sdf_text.frag")),
+  ("assets/sfx/shoot.wav", include_bytes!("../assets/sfx/shoot.wav")),
+  ("assets/sfx/pickup.wav", include_bytes!("../assets/sfx/pickup.wav")),
+  ("assets/sfx/explosion.wav", include_bytes!("../assets/sfx/explosion.wav")),
+  ("assets/sfx/boost_loop.wav", include_bytes!("../assets/sfx/boost_loop.wav")),
+];
+
+/// How a blob is stored, an entry is only compressed when that makes it smaller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Codec {
+  Stored = 0,
+  Compressed = 1,
+}
+
+/// Squeezes repeats out of `bytes`. The output is a run of tokens, each starting with a control
+/// byte: below 0x80 it's followed by that many plus one literal bytes, otherwise the low seven bits
+/// plus [`MIN_MATCH`] are the length of a repeat whose distance back follows as a u16.
+fn compress(bytes: &[u8]) -> Vec<u8> {
+  let hash = |at: usize| {
+    let word = u32::from_le_bytes(bytes[at..at + MIN_MATCH].try_into().unwrap());
+    (word.wrapping_mul(0x9e37_79b1) >> (32 - HASH_BITS)) as usize
+  };
+  let mut out = Vec::with_capacity(bytes.len() / 2);
+  let mut literals = Vec::new();
+  let flush = |out: &mut Vec<u8>, literals: &mut Vec<u8>| {
+    for run in literals.chunks(0x80) {
+      out.push((run.len() - 1) as u8);
+      out.extend_from_slice(run);
+    }
+    literals.clear();
+  };
+
+  let mut last_seen = vec![usize::MAX; 1 << HASH_BITS];
+  let mut at = 0;
+  while at < bytes.len() {
+    if at + MIN_MATCH > bytes.len() {
+      literals.push(bytes[at]);
+      at += 1;
+      continue;
+    }
+    let slot = &mut last_seen[hash(at)];
+    let candidate = std::mem::replace(slot, at);
+    let len = match candidate {
+      usize::MAX => 0,
+      start if at - start > MAX_DISTANCE => 0,
+      start => (0..MAX_MATCH.min(bytes.len() - at))
+        .take_while(|i| bytes[start + i] == bytes[at + i])
+        .count(),
+    };
+    if len < MIN_MATCH {
+      literals.push(bytes[at]);
+      at += 1;
+      continue;
+    }
+
+    flush(&mut out, &mut literals);
+    out.push(0x80 | (len - MIN_MATCH) as u8);
+    out.extend_from_slice(&((at - candidate) as u16).to_le_bytes());
+    at += len;
+  }
+  flush(&mut out, &mut literals);
+  out
+}
+
+/// Undoes [`compress`], expecting `len` bytes out of it. A `len` more than `bytes` could unpack to is
+/// corrupt, and refused before anything is allocated for it.
+fn decompress(bytes: &[u8], len: usize) -> Result<Vec<u8>, Error> {
+  let corrupt = || Error::CorruptPack { reason: "corrupt blob" };
+  if len > bytes.len().saturating_mul(MAX_EXPANSION) {
+    return Err(corrupt());
+  }
+  let mut out = Vec::with_capacity(len);
+  let mut reader = Reader { bytes, pos: 0 };
+  while reader.pos < bytes.len() {
+    let control = reader.take(1)?[0] as usize;
+    if control < 0x80 {
+      out.extend_from_slice(reader.take(control + 1)?);
+    } else {
+      let (len, distance) = ((control & 0x7f) + MIN_MATCH, reader.u16()? as usize);
+      let start = out.len().checked_sub(distance).filter(|_| distance > 0).ok_or_else(corrupt)?;
+      // a repeat may overlap what it writes, so byte by byte
+      for i in start..start + len {
+        out.push(out[i]);
+      }
+    }
+    if out.len() > len {
+      return Err(corrupt());
+    }
+  }
+  if out.len() != len {
+    return Err(corrupt());
+  }
+  Ok(out)
+}
+
+/// Writes `entries` as a pack. All integers are little endian: the magic, a u32 version and a u32
+/// entry count, then per entry a u16 name length, the name, a u8 [`Codec`], a u64 offset into the
+/// blobs, a u64 stored length and a u64 unpacked length, then the blobs back to back.
+pub fn write_pack(out: &mut impl Write, entries: &[(&str, Vec<u8>)]) -> std::io::Result<()> {
+  out.write_all(MAGIC)?;
+  out.write_all(&VERSION.to_le_bytes())?;
+  out.write_all(&(entries.len() as u32).to_le_bytes())?;
+
+  let blobs = entries
+    .iter()
+    .map(|(_, blob)| match compress(blob) {
+      compressed if compressed.len() < blob.len() => (Codec::Compressed, Cow::Owned(compressed)),
+      _ => (Codec::Stored, Cow::Borrowed(blob.as_slice())),
+    })
+    .collect::<Vec<_>>();
+  let mut offset = 0u64;
+  for ((name, blob), (codec, stored)) in entries.iter().zip(&blobs) {
+    out.write_all(&(name.len() as u16).to_le_bytes())?;
+    out.write_all(name.as_bytes())?;
+    out.write_all(&[*codec as u8])?;
+    out.write_all(&offset.to_le_bytes())?;
+    out.write_all(&(stored.len() as u64).to_le_bytes())?;
+    out.write_all(&(blob.len() as u64).to_le_bytes())?;
+    offset += stored.len() as u64;
+  }
+  for (_, stored) in &blobs {
+    out.write_all(stored)?;
+  }
+  Ok(())
+}
+
+struct Reader<'a> {
+  bytes: &'a [u8],
+  pos: usize,
+}
+
+impl<'a> Reader<'a> {
+  fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+    let end = self.pos.checked_add(len).filter(|end| *end <= self.bytes.len());
+    let end = end.ok_or(Error::CorruptPack { reason: "truncated index" })?;
+    let taken = &self.bytes[self.pos..end];
+    self.pos = end;
+    Ok(taken)
+  }
+
+  fn u8(&mut self) -> Result<u8, Error> {
+    Ok(self.take(1)?[0])
+  }
+
+  fn u16(&mut self) -> Result<u16, Error> {
+    Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+  }
+
+  fn u32(&mut self) -> Result<u32, Error> {
+    Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+  }
+
+  fn u64(&mut self) -> Result<u64, Error> {
+    Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+  }
+}
+
+/// A file mapped read only into memory, unmapped again on drop.
+#[cfg(unix)]
+struct Mapping {
+  ptr: *mut libc::c_void,
+  len: usize,
+}
+
+#[cfg(unix)]
+impl Mapping {
+  fn open(path: &Path) -> std::io::Result<Self> {
+    use std::os::unix::io::AsRawFd;
+    let file = std::fs::File::open(path)?;
+    let len = file.metadata()?.len() as usize;
+    if len == 0 {
+      return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "empty file"));
+    }
+    // SAFETY: a private read only mapping of a file we just opened, the pack is only ever read
+    let ptr = unsafe { libc::mmap(std::ptr::null_mut(), len, libc::PROT_READ, libc::MAP_PRIVATE, file.as_raw_fd(), 0) };
+    if ptr == libc::MAP_FAILED {
+      return Err(std::io::Error::last_os_error());
+    }
+    Ok(Self { ptr, len })
+  }
+}
+
+#[cfg(unix)]
+impl Deref for Mapping {
+  type Target = [u8];
+
+  fn deref(&self) -> &Self::Target {
+    // SAFETY: `ptr` is a live mapping of `len` bytes until drop
+    unsafe { std::slice::from_raw_parts(self.ptr as *const u8, self.len) }
+  }
+}
+
+#[cfg(unix)]
+impl Drop for Mapping {
+  fn drop(&mut self) {
+    // SAFETY: mapped in `open` and not used after this
+    unsafe { libc::munmap(self.ptr, self.len) };
+  }
+}
+
+/// Where the bytes of a [`Pack`] live.
+enum PackBytes {
+  #[cfg(unix)]
+  Mapped(Mapping),
+  Read(Vec<u8>),
+}
+
+impl Deref for PackBytes {
+  type Target = [u8];
+
+  fn deref(&self) -> &Self::Target {
+    match self {
+      #[cfg(unix)]
+      PackBytes::Mapped(mapping) => mapping,
+      PackBytes::Read(bytes) => bytes,
+    }
+  }
+}
+
+struct Entry {
+  name: String,
+  codec: Codec,
+  range: Range<usize>,
+  len: usize,
+}
+
+/// A pack mapped or read into memory, see [`write_pack`] for the layout.
+pub struct Pack {
+  bytes: PackBytes,
+  index: Vec<Entry>,
+}
+
+impl Pack {
+  /// Maps the pack at `path` into memory, where mapping isn't available or fails it's read instead.
+  pub fn open(path: &Path) -> Result<Self, Error> {
+    #[cfg(unix)]
+    if let Ok(mapping) = Mapping::open(path) {
+      return Self::index(PackBytes::Mapped(mapping));
+    }
+    Self::parse(std::fs::read(path)?)
+  }
+
+  pub fn parse(bytes: Vec<u8>) -> Result<Self, Error> {
+    Self::index(PackBytes::Read(bytes))
+  }
+
+  fn index(bytes: PackBytes) -> Result<Self, Error> {
+    let mut reader = Reader { bytes: &bytes, pos: 0 };
+    if reader.take(MAGIC.len())? != MAGIC {
+      return Err(Error::CorruptPack { reason: "not a pack" });
+    }
+    if reader.u32()? != VERSION {
+      return Err(Error::CorruptPack {
+        reason: "unsupported version",
+      });
+    }
+
+    let count = reader.u32()?;
+    let mut entries = Vec::new();
+    for _ in 0..count {
+      let len = reader.u16()? as usize;
+      let name = std::str::from_utf8(reader.take(len)?).map_err(|_| Error::CorruptPack { reason: "invalid name" })?;
+      let codec = match reader.u8()? {
+        0 => Codec::Stored,
+        1 => Codec::Compressed,
+        _ => return Err(Error::CorruptPack { reason: "unknown codec" }),
+      };
+      let (offset, stored, len) = (reader.u64()? as usize, reader.u64()? as usize, reader.u64()? as usize);
+      entries.push((name.to_string(), codec, offset, stored, len));
+    }
+
+    let blobs = reader.pos;
+    let mut index = Vec::new();
+    for (name, codec, offset, stored, len) in entries {
+      let range = blobs.checked_add(offset).and_then(|start| Some(start..start.checked_add(stored)?));
+      match range.filter(|range| range.end <= bytes.len()) {
+        Some(range) => index.push(Entry { name, codec, range, len }),
+        None => return Err(Error::CorruptPack { reason: "truncated blob" }),
+      }
+    }
+
+    Ok(Self { bytes, index })
+  }
+
+  /// The blob called `name`, borrowed from the pack unless it has to be decompressed.
+  pub fn get(&self, name: &str) -> Result<Option<Cow<'_, [u8]>>, Error> {
+    let Some(entry) = self.index.iter().find(|entry| entry.name == name) else {
+      return Ok(None);
+    };
+    let stored = &self.bytes[entry.range.clone()];
+    match entry.codec {
+      Codec::Stored if stored.len() == entry.len => Ok(Some(Cow::Borrowed(stored))),
+      Codec::Stored => Err(Error::CorruptPack { reason: "corrupt blob" }),
+      Codec::Compressed => Ok(Some(Cow::Owned(decompress(stored, entry.len)?))),
+    }
+  }
+}
+
+/// Loads an asset from a loose file, the pack or the copy compiled into the binary, in that order. A
+/// broken pack is reported and skipped.
+pub fn load_asset(name: &str) -> Result<Vec<u8>, Error> {
+  load_asset_with(name, find_asset)
+}
+
+/// [`load_asset`] looking for the loose file and the pack with `find`.
+fn load_asset_with(name: &str, find: impl Fn(&str) -> Result<PathBuf, Error>) -> Result<Vec<u8>, Error> {
+  if let Ok(path) = find(name) {
+    return Ok(std::fs::read(path)?);
+  }
+
+  if let Ok(path) = find(PACK_FILE) {
+    match Pack::open(&path).and_then(|pack| Ok(pack.get(name)?.map(Cow::into_owned))) {
+      Ok(Some(blob)) => return Ok(blob),
+      Ok(None) => {}
+      Err(e) => eprintln!("{}, falling back to embedded assets", e),
+    }
+  }
+
+  ASSETS
+    .iter()
+    .find(|(asset, _)| *asset == name)
+    .map(|(_, blob)| blob.to_vec())
+    .ok_or_else(|| Error::AssetNotFound {
+      path: name.into(),
+      searched: Vec::new(),
+    })
+}
+
+/// Bundles every asset into a pack at `path`, preferring loose files over the embedded copies.
+pub fn pack_assets(path: &str) -> Result<(), Error> {
+  let mut entries = Vec::new();
+  for (name, embedded) in ASSETS {
+    let blob = match find_asset(name) {
+      Ok(loose) => std::fs::read(loose)?,
+      Err(_) => embedded.to_vec(),
+    };
+    entries.push((name, blob));
+  }
+
+  Ok(write_atomic(Path::new(path), |file| write_pack(file, &entries))?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn entries() -> Vec<(&'static str, Vec<u8>)> {
+    ASSETS.iter().map(|(name, blob)| (*name, blob.to_vec())).collect()
+  }
+
+  fn packed(entries: &[(&str, Vec<u8>)]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    write_pack(&mut bytes, entries).unwrap();
+    bytes
+  }
+
+  #[test]
+  fn compression_round_trips_and_shrinks_repeats() {
+    let mut noise = 0x2545_f491u32;
+    let noise = (0..5_000)
+      .map(|_| {
+        noise ^= noise << 13;
+        noise ^= noise >> 17;
+        noise ^= noise << 5;
+        noise as u8
+      })
+      .collect::<Vec<_>>();
+    let repeats = b"uniform mat4 uProjection;\n".repeat(200);
+    let overlapping = vec![7; 1_000];
+    for bytes in [&b""[..], b"abc", &noise, &repeats, &overlapping, ASSETS[0].1] {
+      let compressed = compress(bytes);
+      assert_eq!(decompress(&compressed, bytes.len()).unwrap(), bytes);
+    }
+    assert!(compress(&repeats).len() < repeats.len() / 10);
+
+    // a repeat reaching back before the start, or a blob of the wrong length, is corrupt
+    assert!(decompress(&[0x80, 4, 0], 4).is_err());
+    assert!(decompress(&compress(&repeats), repeats.len() + 1).is_err());
+  }
+
+  #[test]
+  fn a_pack_round_trips_compressing_what_shrinks() {
+    let entries = entries();
+    let pack = Pack::parse(packed(&entries)).unwrap();
+    for (name, blob) in &entries {
+      assert_eq!(pack.get(name).unwrap().unwrap(), blob.as_slice(), "{}", name);
+    }
+    assert!(pack.get("missing").unwrap().is_none());
+    // the shaders are text and shrink, the font is stored when it doesn't
+    let shader = pack.index.iter().find(|entry| entry.name == "assets/shaders/scene.frag").unwrap();
+    assert_eq!(shader.codec, Codec::Compressed);
+    assert!(shader.range.len() < shader.len);
+  }
+
+  #[test]
+  fn a_mapped_pack_reads_the_same_as_one_in_memory() {
+    let dir = std::env::temp_dir().join(format!("bytepath-pack-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(PACK_FILE);
+    std::fs::write(&path, packed(&entries())).unwrap();
+
+    let pack = Pack::open(&path).unwrap();
+    #[cfg(unix)]
+    assert!(matches!(pack.bytes, PackBytes::Mapped(_)));
+    for (name, blob) in ASSETS {
+      assert_eq!(pack.get(name).unwrap().unwrap(), blob, "{}", name);
+    }
+
+    // an empty file can't be mapped and is read as a broken pack instead
+    std::fs::write(&path, b"").unwrap();
+    assert!(matches!(Pack::open(&path), Err(Error::CorruptPack { .. })));
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn a_broken_pack_is_an_error() {
+    let bytes = packed(&entries());
+    let mut old = bytes.clone();
+    old[4..8].copy_from_slice(&1u32.to_le_bytes());
+    assert!(Pack::parse(old).is_err());
+    assert!(Pack::parse(bytes[..bytes.len() - 1].to_vec()).is_err());
+    assert!(Pack::parse(bytes[..20].to_vec()).is_err());
+
+    // a flipped byte in a compressed blob shows up when it's read
+    let mut flipped = bytes.clone();
+    let pack = Pack::parse(bytes).unwrap();
+    let entry = pack.index.iter().find(|entry| entry.codec == Codec::Compressed).unwrap();
+    flipped[entry.range.start] ^= 0x80;
+    let name = entry.name.clone();
+    assert!(Pack::parse(flipped).unwrap().get(&name).is_err());
+  }
+
+  #[test]
+  fn an_index_promising_huge_blobs_is_an_error() {
+    let bytes = packed(&entries());
+    let pack = Pack::parse(bytes.clone()).unwrap();
+    let entry = pack.index.iter().find(|entry| entry.codec == Codec::Compressed).unwrap();
+    // the unpacked length is the last field of the entry, after the codec, the offset and the stored length
+    let name_at = bytes.windows(entry.name.len()).position(|window| window == entry.name.as_bytes()).unwrap();
+    let len_at = name_at + entry.name.len() + 1 + 8 + 8;
+    assert_eq!(bytes[len_at..len_at + 8], (entry.len as u64).to_le_bytes());
+
+    // more than the blob could ever unpack to is refused without allocating it
+    let bound = entry.range.len() * MAX_EXPANSION;
+    for len in [u64::MAX, usize::MAX as u64 / 2, bound as u64 + 1] {
+      let mut huge = bytes.clone();
+      huge[len_at..len_at + 8].copy_from_slice(&len.to_le_bytes());
+      let result = Pack::parse(huge).unwrap().get(&entry.name).map(|blob| blob.map(Cow::into_owned));
+      assert!(matches!(result, Err(Error::CorruptPack { .. })), "{}", len);
+    }
+  }
+
+  #[test]
+  fn every_loader_finds_its_assets_with_only_the_pack() {
+    use crate::{audio::Sfx, shaders::Program};
+
+    let dir = std::env::temp_dir().join(format!("bytepath-pak-only-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    // marked so they can't be mistaken for the embedded copies
+    let entries = ASSETS
+      .iter()
+      .map(|(name, blob)| (*name, [b"pak:", *blob].concat()))
+      .collect::<Vec<_>>();
+    std::fs::write(dir.join(PACK_FILE), packed(&entries)).unwrap();
+    let find = |path: &str| {
+      let candidate = dir.join(path);
+      candidate.is_file().then_some(candidate).ok_or_else(|| Error::AssetNotFound {
+        path: path.into(),
+        searched: vec![dir.clone()],
+      })
+    };
+
+    let font = String::from("m5x7.ttf");
+    let shaders = Program::ALL.iter().flat_map(|program| program.files());
+    let sounds = Sfx::ALL.iter().map(|sfx| sfx.path());
+    for name in std::iter::once(font).chain(shaders).chain(sounds) {
+      let loaded = load_asset_with(&name, find).unwrap();
+      assert!(loaded.starts_with(b"pak:"), "{} didn't come from the pack", name);
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+}
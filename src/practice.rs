@@ -0,0 +1,105 @@
+use crate::{
+  balance::GameBalance,
+  components::{Ammo, Boost, Interpolation, Invulnerable, Player},
+  easings::Easing,
+  environment::INVULNERABLE_DURATION,
+  resources::{Randoms, Timer, WorldBounds},
+  stats::RunStats,
+  systems::{spawn_laser_hazard, spawn_rock},
+};
+use bevy_ecs::prelude::*;
+
+/// What the practice panel does. Every one of them tags the run as practiced, see [`Practice::dispatch`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PracticeCommand {
+  SpawnRocks(u32),
+  SpawnLaser,
+  Invincible(bool),
+  Refill,
+  /// Replaces the live balance, from the console.
+  Balance(GameBalance),
+  /// Overrides the easing of an entity's [`Interpolation`], from the curve panel's inspector. None
+  /// reverts it.
+  Easing(Entity, Option<Easing>),
+}
+
+/// The practice tools of a normal run. Worlds without it, replays, stress runs and the lockstep audit,
+/// can't play the tools back and offer none.
+#[derive(Debug, Default, Resource)]
+pub struct Practice {
+  pub invincible: bool,
+  /// A tool was used this session, the replay can't reproduce it and isn't kept.
+  pub used: bool,
+  queued: Vec<PracticeCommand>,
+}
+
+impl Practice {
+  /// Runs `command` with the next tick. The one way in, so nothing can use a tool without the tag.
+  pub fn dispatch(&mut self, command: PracticeCommand) {
+    self.queued.push(command);
+  }
+
+  /// The balance the queued commands leave, `live` without any.
+  pub fn balance(&self, live: &GameBalance) -> GameBalance {
+    let queued = self.queued.iter().rev().find_map(|command| match command {
+      PracticeCommand::Balance(balance) => Some(*balance),
+      _ => None,
+    });
+    queued.unwrap_or(*live)
+  }
+}
+
+/// Runs the dispatched commands and tags the run, the tag sticks until the run ends even once the
+/// tools are off. A balance other than the defaults tags every run it's in play for. Practiced runs
+/// go into neither the high scores, the stats nor the skill points.
+pub fn practice_system(
+  mut commands: Commands,
+  practice: Option<ResMut<Practice>>,
+  mut run: ResMut<RunStats>,
+  mut balance: ResMut<GameBalance>,
+  mut rng: ResMut<Randoms>,
+  bounds: Res<WorldBounds>,
+  mut players: Query<(&mut Boost, &mut Ammo, Option<&Invulnerable>, Entity), With<Player>>,
+  mut interpolations: Query<&mut Interpolation>,
+) {
+  let Some(mut practice) = practice else {
+    return;
+  };
+  let practice = &mut *practice;
+  for command in practice.queued.drain(..) {
+    run.practiced = true;
+    practice.used = true;
+    match command {
+      PracticeCommand::SpawnRocks(count) => (0..count).for_each(|_| spawn_rock(&mut commands, &mut rng, &bounds)),
+      PracticeCommand::SpawnLaser => spawn_laser_hazard(&mut commands, &mut rng, &bounds),
+      PracticeCommand::Invincible(on) => practice.invincible = on,
+      PracticeCommand::Refill => {
+        for (mut boost, mut ammo, ..) in players.iter_mut() {
+          boost.boost = boost.max_boost;
+          boost.cooldown = None;
+          ammo.current = ammo.max;
+        }
+      }
+      PracticeCommand::Balance(changed) => *balance = changed,
+      PracticeCommand::Easing(entity, easing) => {
+        if let Ok(mut interpolation) = interpolations.get_mut(entity) {
+          interpolation.override_easing(easing);
+        }
+      }
+    }
+  }
+  if *balance != GameBalance::default() {
+    run.practiced = true;
+    practice.used = true;
+  }
+
+  if practice.invincible {
+    for (.., invulnerable, player) in players.iter() {
+      if invulnerable.is_none() {
+        commands.entity(player).insert(Invulnerable {
+          timer: Timer::from_seconds(INVULNERABLE_DURATION, false),
+        });
+      }
+    }
+  }
+}
@@ -0,0 +1,330 @@
+use crate::{
+  environment::{HIGH_SCORES_FILE, PROFILE_NAME_MAX_LEN, STATS_FILE, UPGRADES_FILE},
+  error::Error,
+  highscores,
+  output_files::write_atomic,
+  resources::{KeyBindings, BINDINGS_FILE},
+  settings::{Settings, SETTINGS_FILE},
+  shop::PlayerUpgrades,
+  stats::LifetimeStats,
+};
+use bevy_ecs::prelude::*;
+use std::{io::Write, path::PathBuf};
+
+pub const PROFILES_DIR: &str = "profiles";
+/// Holds the name of the profile picked last, in the data dir.
+pub const LAST_PROFILE_FILE: &str = "last_profile";
+pub const DEFAULT_PROFILE: &str = "default";
+/// What a profile keeps of its own, the rest of the data dir and the assets are shared by everyone on
+/// the machine. Its settings only override the player's keys, see [`Settings::overridden`].
+pub const PROFILE_FILES: [&str; 5] = [HIGH_SCORES_FILE, BINDINGS_FILE, SETTINGS_FILE, STATS_FILE, UPGRADES_FILE];
+const EXPORT_HEADER: &str = "bytepath profile 1";
+
+/// Lowercase letters, digits and dashes, they make a directory name on every platform.
+pub fn is_valid_name(name: &str) -> bool {
+  (1..=PROFILE_NAME_MAX_LEN).contains(&name.len())
+    && name.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+}
+
+/// The profile the game runs with. Without a data dir there is nothing to keep its files in and
+/// everything lasts for the session only.
+#[derive(Debug, Clone, PartialEq, Eq, Resource)]
+pub struct Profile {
+  pub name: String,
+  dir: Option<PathBuf>,
+}
+
+impl Profile {
+  pub fn session() -> Self {
+    Self {
+      name: String::from(DEFAULT_PROFILE),
+      dir: None,
+    }
+  }
+
+  /// Where the profile keeps `file`, one of [`PROFILE_FILES`].
+  pub fn file(&self, file: &str) -> Option<PathBuf> {
+    self.dir.as_ref().map(|dir| dir.join(file))
+  }
+}
+
+/// The profiles in the data dir, one directory each under [`PROFILES_DIR`].
+#[derive(Debug, Clone, Resource)]
+pub struct Profiles {
+  root: PathBuf,
+}
+
+impl Profiles {
+  /// `root` is the data dir, [`Self::migrate`] should run before anything else reads it.
+  pub fn new(root: impl Into<PathBuf>) -> Self {
+    Self { root: root.into() }
+  }
+
+  pub fn in_data_dir() -> Option<Self> {
+    sdl2::filesystem::pref_path("bytepath", "bytepath")
+      .map(Self::new)
+      .map_err(|e| eprintln!("no data dir, profiles are not kept: {}", e))
+      .ok()
+  }
+
+  fn dir(&self, name: &str) -> PathBuf {
+    self.root.join(PROFILES_DIR).join(name)
+  }
+
+  /// Moves the files of a data dir from before profiles into the default profile, once.
+  pub fn migrate(&self) -> std::io::Result<()> {
+    if self.root.join(PROFILES_DIR).exists() {
+      return Ok(());
+    }
+    let dir = self.dir(DEFAULT_PROFILE);
+    std::fs::create_dir_all(&dir)?;
+    for file in PROFILE_FILES {
+      let old = self.root.join(file);
+      if old.is_file() {
+        std::fs::rename(old, dir.join(file))?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Sorted by name.
+  pub fn list(&self) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(self.root.join(PROFILES_DIR)) else {
+      return Vec::new();
+    };
+    let names = entries.filter_map(|entry| entry.ok()?.file_name().into_string().ok());
+    let mut names = names.filter(|name| is_valid_name(name) && self.dir(name).is_dir()).collect::<Vec<_>>();
+    names.sort();
+    names
+  }
+
+  /// The profile picked last while it is still around, or [`DEFAULT_PROFILE`].
+  pub fn last_used(&self) -> String {
+    std::fs::read_to_string(self.root.join(LAST_PROFILE_FILE))
+      .map(|name| name.trim().to_string())
+      .ok()
+      .filter(|name| is_valid_name(name) && self.dir(name).is_dir())
+      .unwrap_or_else(|| String::from(DEFAULT_PROFILE))
+  }
+
+  /// Makes `name` the last used profile, creating it when it's new.
+  pub fn select(&self, name: &str) -> Result<Profile, Error> {
+    if !is_valid_name(name) {
+      return Err(Error::InvalidProfile { reason: "expected lowercase letters, digits and dashes" });
+    }
+    let dir = self.dir(name);
+    std::fs::create_dir_all(&dir)?;
+    write_atomic(&self.root.join(LAST_PROFILE_FILE), |out| writeln!(out, "{}", name))?;
+    Ok(Profile {
+      name: name.to_string(),
+      dir: Some(dir),
+    })
+  }
+
+  pub fn rename(&self, from: &str, to: &str) -> Result<(), Error> {
+    if !is_valid_name(to) {
+      return Err(Error::InvalidProfile { reason: "expected lowercase letters, digits and dashes" });
+    }
+    if self.dir(to).exists() {
+      return Err(Error::InvalidProfile { reason: "a profile with that name exists" });
+    }
+    std::fs::rename(self.dir(from), self.dir(to))?;
+    if self.last_used() == from || !self.dir(&self.last_used()).is_dir() {
+      self.select(to)?;
+    }
+    Ok(())
+  }
+
+  /// Only when `confirmation` is the name typed out again, there is no getting a profile back.
+  pub fn delete(&self, name: &str, confirmation: &str) -> Result<(), Error> {
+    if confirmation != name {
+      return Err(Error::InvalidProfile { reason: "the confirmation doesn't match the name" });
+    }
+    if !is_valid_name(name) || !self.dir(name).is_dir() {
+      return Err(Error::InvalidProfile { reason: "no such profile" });
+    }
+    Ok(std::fs::remove_dir_all(self.dir(name))?)
+  }
+
+  /// The profile's files in one file to carry to another machine, `bytepath profile 1`, a `name` line and
+  /// per file a `file <name> <length>` line followed by as many bytes and a newline.
+  pub fn export(&self, name: &str, out: &mut impl Write) -> Result<(), Error> {
+    if !is_valid_name(name) || !self.dir(name).is_dir() {
+      return Err(Error::InvalidProfile { reason: "no such profile" });
+    }
+    writeln!(out, "{}", EXPORT_HEADER)?;
+    writeln!(out, "name {}", name)?;
+    for file in PROFILE_FILES {
+      let path = self.dir(name).join(file);
+      if path.is_file() {
+        let contents = std::fs::read(path)?;
+        writeln!(out, "file {} {}", file, contents.len())?;
+        out.write_all(&contents)?;
+        writeln!(out)?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Adds the profile of an [`Self::export`], renamed `name-2`, `name-3` and so on when the name is
+  /// taken. Every file has to read as what it claims to be, nothing is written otherwise. The name it
+  /// was imported as.
+  pub fn import(&self, bytes: &[u8]) -> Result<String, Error> {
+    let (name, files) = parse_export(bytes)?;
+    let name = (1..)
+      .map(|i| if i == 1 { name.clone() } else { format!("{}-{}", name, i) })
+      .find(|candidate| candidate.len() > PROFILE_NAME_MAX_LEN || !self.dir(candidate).exists())
+      .filter(|candidate| is_valid_name(candidate))
+      .ok_or(Error::InvalidProfile { reason: "no free name to import as" })?;
+    let dir = self.dir(&name);
+    std::fs::create_dir_all(&dir)?;
+    for (file, contents) in files {
+      write_atomic(&dir.join(file), |out| out.write_all(contents))?;
+    }
+    Ok(name)
+  }
+}
+
+/// Takes the line `bytes` start with off them.
+fn next_line<'a>(bytes: &mut &'a [u8]) -> Result<&'a str, Error> {
+  let invalid = |reason| Error::InvalidProfile { reason };
+  let end = bytes.iter().position(|b| *b == b'\n').ok_or_else(|| invalid("unexpected end of file"))?;
+  let line = std::str::from_utf8(&bytes[..end]).map_err(|_| invalid("expected text"))?;
+  *bytes = &bytes[end + 1..];
+  Ok(line)
+}
+
+fn parse_export(mut bytes: &[u8]) -> Result<(String, Vec<(&'static str, &[u8])>), Error> {
+  let invalid = |reason| Error::InvalidProfile { reason };
+  if next_line(&mut bytes)? != EXPORT_HEADER {
+    return Err(invalid("not an exported profile"));
+  }
+  let name = next_line(&mut bytes)?.strip_prefix("name ").filter(|name| is_valid_name(name));
+  let name = name.ok_or_else(|| invalid("bad name"))?.to_string();
+
+  let mut files = Vec::<(&'static str, &[u8])>::new();
+  while !bytes.is_empty() {
+    let (file, length) = next_line(&mut bytes)?
+      .strip_prefix("file ")
+      .and_then(|rest| rest.split_once(' '))
+      .ok_or_else(|| invalid("expected file <name> <length>"))?;
+    let file = PROFILE_FILES.into_iter().find(|known| *known == file).ok_or_else(|| invalid("unknown file"))?;
+    if files.iter().any(|(seen, _)| *seen == file) {
+      return Err(invalid("file included twice"));
+    }
+    let length = length.parse::<usize>().ok().filter(|length| *length < bytes.len());
+    let length = length.ok_or_else(|| invalid("bad length"))?;
+    let (contents, rest) = bytes.split_at(length);
+    bytes = rest.strip_prefix(b"\n").ok_or_else(|| invalid("expected a newline after the file"))?;
+    validate(file, contents)?;
+    files.push((file, contents));
+  }
+  Ok((name, files))
+}
+
+/// Refuses what the game would refuse to load.
+fn validate(file: &str, contents: &[u8]) -> Result<(), Error> {
+  let text = std::str::from_utf8(contents).map_err(|_| Error::InvalidProfile { reason: "expected text" })?;
+  match file {
+    HIGH_SCORES_FILE => highscores::parse(text).map(drop),
+    BINDINGS_FILE => KeyBindings::parse(text).map(drop),
+    STATS_FILE => LifetimeStats::parse(text).map(drop),
+    UPGRADES_FILE => PlayerUpgrades::parse(text).map(drop),
+    _ => Settings::default().overridden(text, false).map(drop),
+  }
+}
+
+/// Parses `file` of the profile if there is one, reporting and skipping a broken one.
+pub fn load_profile_file<T>(profile: &Profile, file: &str, parse: impl FnOnce(&str) -> Result<T, Error>) -> Option<T> {
+  let path = profile.file(file)?;
+  let text = std::fs::read_to_string(&path).ok()?;
+  parse(&text).map_err(|e| eprintln!("{} in {}, ignoring it", e, path.display())).ok()
+}
+
+/// The profile `requested` or the one used last, created in the data dir if needed.
+pub fn open_profile(profiles: Option<&Profiles>, requested: Option<&str>) -> Result<Profile, Error> {
+  let Some(profiles) = profiles else {
+    return Ok(Profile::session());
+  };
+  if let Err(e) = profiles.migrate() {
+    eprintln!("could not move the old save into the default profile: {}", e);
+  }
+  profiles.select(requested.map_or_else(|| profiles.last_used(), str::to_string).as_str())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::highscores::HighScores;
+
+  /// A data dir of the test's own.
+  fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("bytepath-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  #[test]
+  fn scores_stay_with_their_profile() {
+    let root = scratch_dir("scoping");
+    let profiles = Profiles::new(&root);
+    for (name, score) in [("kid", 300), ("parent", 1200)] {
+      let mut scores = HighScores::load(&profiles.select(name).unwrap());
+      scores.insert(score);
+      scores.save(None);
+    }
+
+    let kid = HighScores::load(&profiles.select("kid").unwrap());
+    assert_eq!(kid.entries.iter().map(|entry| entry.score).collect::<Vec<_>>(), [300]);
+    assert_eq!(profiles.last_used(), "kid");
+    assert_eq!(profiles.list(), ["kid", "parent"]);
+  }
+
+  #[test]
+  fn an_old_save_moves_into_the_default_profile() {
+    let root = scratch_dir("migration");
+    std::fs::write(root.join(HIGH_SCORES_FILE), "[{\"score\": 50, \"timestamp\": 1}]").unwrap();
+    let profiles = Profiles::new(&root);
+
+    let profile = open_profile(Some(&profiles), None).unwrap();
+    assert_eq!(profile.name, DEFAULT_PROFILE);
+    assert_eq!(HighScores::load(&profile).entries[0].score, 50);
+    assert!(!root.join(HIGH_SCORES_FILE).exists());
+    // a second start leaves the profiles alone
+    profiles.migrate().unwrap();
+    assert_eq!(profiles.list(), [DEFAULT_PROFILE]);
+  }
+
+  #[test]
+  fn imports_are_validated_and_renamed_on_a_clash() {
+    let root = scratch_dir("import");
+    let profiles = Profiles::new(&root);
+    let profile = profiles.select("kid").unwrap();
+    std::fs::write(profile.file(BINDINGS_FILE).unwrap(), "1.shoot = key:Space\n").unwrap();
+    std::fs::write(profile.file(SETTINGS_FILE).unwrap(), "rumble = 40\n").unwrap();
+    let mut exported = Vec::new();
+    profiles.export("kid", &mut exported).unwrap();
+
+    assert_eq!(profiles.import(&exported).unwrap(), "kid-2");
+    let imported = root.join(PROFILES_DIR).join("kid-2").join(BINDINGS_FILE);
+    assert_eq!(std::fs::read_to_string(imported).unwrap(), "1.shoot = key:Space\n");
+
+    let text = String::from_utf8(exported).unwrap();
+    let broken = [
+      text.replace("bytepath profile 1", "bytepath profile 9"),
+      text.replace("name kid", "name ../kid"),
+      text.replace("rumble = 40", "vsync = no"),
+      text.replace("file settings.toml", "file build.ron"),
+      text[..text.len() - 3].to_string(),
+    ];
+    for broken in broken {
+      assert!(profiles.import(broken.as_bytes()).is_err(), "{}", broken);
+    }
+    assert_eq!(profiles.list(), ["kid", "kid-2"]);
+
+    assert!(profiles.delete("kid-2", "kid").is_err());
+    profiles.delete("kid-2", "kid-2").unwrap();
+    assert_eq!(profiles.list(), ["kid"]);
+  }
+}
@@ -0,0 +1,200 @@
+use crate::{
+  components::PlayerId,
+  environment::DEVICE_SWITCH_DEBOUNCE,
+  format::write_substituted,
+  input::{Action, InputSource},
+  resources::{GamepadState, KeyBindings, KeyCodes, MouseInput, Time},
+};
+use bevy_ecs::prelude::*;
+use sdl2::{controller::Button, keyboard::Keycode};
+
+/// main() toggles the pause on these, they aren't an [`Action`] and can't be rebound.
+pub const PAUSE_SOURCES: [InputSource; 2] = [
+  InputSource::Key(Keycode::P),
+  InputSource::ControllerButton(Button::Start),
+];
+
+/// What the player last played with, prompts name the keys or buttons of it. The mouse goes with the
+/// keyboard.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceKind {
+  #[default]
+  Keyboard,
+  Controller,
+}
+
+/// Which labels the controller's buttons carry, as far as SDL can tell.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PadStyle {
+  #[default]
+  Generic,
+  Xbox,
+  PlayStation,
+  Nintendo,
+}
+
+/// The device prompts are for, updated by [`input_device_system`].
+#[derive(Debug, Default, Resource)]
+pub struct InputDevice {
+  pub kind: DeviceKind,
+  /// The labels of the last controller plugged in.
+  pub pad_style: PadStyle,
+  /// Seconds the stick has been out of its deadzone without a controller button.
+  stick_held: f32,
+}
+
+impl InputDevice {
+  /// Keys, mouse buttons and controller buttons switch over right away. The stick only once it was held
+  /// for [`DEVICE_SWITCH_DEBOUNCE`] seconds, so a bumped controller next to the keyboard doesn't flip the
+  /// prompts back and forth. Both devices at once keep the one in use.
+  pub fn update(&mut self, dt: f32, keyboard: bool, buttons: bool, stick: bool) {
+    self.stick_held = if stick { self.stick_held + dt } else { 0.0 };
+    let controller = buttons || self.stick_held >= DEVICE_SWITCH_DEBOUNCE;
+    match (keyboard, controller) {
+      (true, false) => self.kind = DeviceKind::Keyboard,
+      (false, true) => self.kind = DeviceKind::Controller,
+      _ => {}
+    }
+  }
+}
+
+pub fn input_device_system(
+  keycodes: Res<KeyCodes>,
+  mouse: Res<MouseInput>,
+  gamepad: Res<GamepadState>,
+  time: Res<Time>,
+  mut device: ResMut<InputDevice>,
+) {
+  let keyboard = !keycodes.is_empty() || !mouse.buttons.is_empty() || mouse.wheel_up || mouse.wheel_down;
+  let stick = gamepad.stick != glam::Vec2::ZERO;
+  device.update(time.raw().as_secs_f32(), keyboard, !gamepad.buttons.is_empty(), stick);
+}
+
+/// How the source reads on screen, controller buttons with the labels of `style`.
+pub fn source_name(source: InputSource, style: PadStyle) -> String {
+  let button = match source {
+    InputSource::Key(keycode) => return keycode.name().to_uppercase(),
+    InputSource::MouseButton(1) => return String::from("LEFT MOUSE"),
+    InputSource::MouseButton(2) => return String::from("MIDDLE MOUSE"),
+    InputSource::MouseButton(3) => return String::from("RIGHT MOUSE"),
+    InputSource::MouseButton(button) => return format!("MOUSE {}", button),
+    InputSource::WheelUp => return String::from("WHEEL UP"),
+    InputSource::WheelDown => return String::from("WHEEL DOWN"),
+    InputSource::ControllerButton(button) => button,
+  };
+  let name = match (button, style) {
+    (Button::DPadUp, _) => "D-PAD UP",
+    (Button::DPadDown, _) => "D-PAD DOWN",
+    (Button::DPadLeft, _) => "D-PAD LEFT",
+    (Button::DPadRight, _) => "D-PAD RIGHT",
+    (Button::A, PadStyle::PlayStation) => "CROSS",
+    (Button::B, PadStyle::PlayStation) => "CIRCLE",
+    (Button::X, PadStyle::PlayStation) => "SQUARE",
+    (Button::Y, PadStyle::PlayStation) => "TRIANGLE",
+    (Button::Back, PadStyle::PlayStation) => "SHARE",
+    (Button::Start, PadStyle::PlayStation) => "OPTIONS",
+    (Button::LeftShoulder, PadStyle::PlayStation) => "L1",
+    (Button::RightShoulder, PadStyle::PlayStation) => "R1",
+    (Button::LeftStick, PadStyle::PlayStation) => "L3",
+    (Button::RightStick, PadStyle::PlayStation) => "R3",
+    (Button::Back, PadStyle::Xbox) => "VIEW",
+    (Button::Start, PadStyle::Xbox) => "MENU",
+    (Button::Back, PadStyle::Nintendo) => "MINUS",
+    (Button::Start, PadStyle::Nintendo) => "PLUS",
+    (Button::LeftShoulder, PadStyle::Nintendo) => "L",
+    (Button::RightShoulder, PadStyle::Nintendo) => "R",
+    (Button::A, _) => "A",
+    (Button::B, _) => "B",
+    (Button::X, _) => "X",
+    (Button::Y, _) => "Y",
+    (Button::Back, _) => "BACK",
+    (Button::Start, _) => "START",
+    (Button::Guide, _) => "HOME",
+    (Button::LeftShoulder, _) => "LB",
+    (Button::RightShoulder, _) => "RB",
+    (Button::LeftStick, _) => "LS",
+    (Button::RightStick, _) => "RS",
+    _ => "?",
+  };
+  String::from(name)
+}
+
+/// The first of `sources` on the device in use, or the first of any device when nothing on it is bound.
+fn pick(sources: impl Iterator<Item = InputSource> + Clone, device: &InputDevice) -> Option<String> {
+  let on_device = |source: &InputSource| {
+    matches!(source, InputSource::ControllerButton(_)) == (device.kind == DeviceKind::Controller)
+  };
+  let source = sources.clone().find(on_device).or_else(|| sources.clone().next())?;
+  Some(source_name(source, device.pad_style))
+}
+
+/// The key or button `player` triggers `action` with on the device in use, remapped ones included.
+pub fn prompt(bindings: &KeyBindings, player: PlayerId, action: Action, device: &InputDevice) -> String {
+  let sources = bindings.player(player).iter().filter(|(_, bound)| *bound == action).map(|(source, _)| *source);
+  pick(sources, device).unwrap_or_else(|| String::from("UNBOUND"))
+}
+
+/// `template` with every `{action}` replaced by the first player's [`prompt`] for it, named as in the
+/// bindings file, and `{pause}` by the pause key or button. E.g. `press {pause} to resume`.
+pub fn fill_prompts(template: &str, bindings: &KeyBindings, device: &InputDevice) -> String {
+  let mut text = String::new();
+  write_substituted(&mut text, template, |name| match name {
+    "pause" => pick(PAUSE_SOURCES.into_iter(), device),
+    name => Action::from_name(name).map(|action| prompt(bindings, PlayerId(0), action, device)),
+  })
+  .unwrap();
+  text
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const DT: f32 = 1.0 / 60.0;
+
+  #[test]
+  fn a_bumped_stick_leaves_the_prompts_alone() {
+    let mut device = InputDevice::default();
+    let ticks = |seconds: f32| (seconds / DT).round() as usize;
+
+    for _ in 0..ticks(DEVICE_SWITCH_DEBOUNCE / 2.0) {
+      device.update(DT, false, false, true);
+    }
+    device.update(DT, false, false, false);
+    assert_eq!(device.kind, DeviceKind::Keyboard);
+
+    for _ in 0..ticks(DEVICE_SWITCH_DEBOUNCE) + 1 {
+      device.update(DT, false, false, true);
+    }
+    assert_eq!(device.kind, DeviceKind::Controller);
+
+    // a key switches back at once, and a button over again while both are held keeps the keyboard
+    device.update(DT, true, false, false);
+    assert_eq!(device.kind, DeviceKind::Keyboard);
+    device.update(DT, true, true, false);
+    assert_eq!(device.kind, DeviceKind::Keyboard);
+    device.update(DT, false, true, false);
+    assert_eq!(device.kind, DeviceKind::Controller);
+  }
+
+  #[test]
+  fn prompts_name_the_binding_of_the_device_in_use() {
+    let mut device = InputDevice::default();
+    let default = KeyBindings::default();
+    let remapped = KeyBindings::parse("1.shoot = mouse:left, pad:x\n1.bomb = wheel:down").unwrap();
+
+    assert_eq!(prompt(&default, PlayerId(0), Action::Shoot, &device), "SPACE");
+    assert_eq!(prompt(&remapped, PlayerId(0), Action::Shoot, &device), "LEFT MOUSE");
+    assert_eq!(prompt(&remapped, PlayerId(0), Action::Boost, &device), "UNBOUND");
+    assert_eq!(fill_prompts("press {pause} to resume", &default, &device), "press P to resume");
+
+    device.kind = DeviceKind::Controller;
+    assert_eq!(prompt(&default, PlayerId(0), Action::Shoot, &device), "A");
+    assert_eq!(prompt(&remapped, PlayerId(0), Action::Shoot, &device), "X");
+    // nothing on the controller bombs, the prompt falls back to the wheel
+    assert_eq!(prompt(&remapped, PlayerId(0), Action::Bomb, &device), "WHEEL DOWN");
+    device.pad_style = PadStyle::PlayStation;
+    assert_eq!(prompt(&default, PlayerId(0), Action::Shoot, &device), "CROSS");
+    assert_eq!(fill_prompts("{boost} to boost, {pause} to pause", &default, &device), "R1 to boost, OPTIONS to pause");
+  }
+}
@@ -5,23 +5,37 @@ pub mod gl {
 
 use crate::{
   color::ColorGl,
-  environment::{SCREEN_HEIGHT, SCREEN_RENDER_HEIGHT, SCREEN_RENDER_WIDTH, SCREEN_WIDTH},
+  environment::{
+    CIRCLE_BUFFER_INDICES, CIRCLE_BUFFER_VERTICES, LINE_BUFFER_INDICES, LINE_BUFFER_VERTICES, PARTICLE_BUFFER_INDICES,
+    PARTICLE_BUFFER_VERTICES, QUAD_BUFFER_INDICES, QUAD_BUFFER_VERTICES, RGB_CLEAR_COLOR, RGB_COLOR_TEXT_OUTLINE,
+    SCREEN_BUFFER_INDICES, SCREEN_BUFFER_VERTICES, SCREEN_HEIGHT, SCREEN_RENDER_HEIGHT, SCREEN_RENDER_WIDTH,
+    SCREEN_WIDTH, SDF_FONT_SIZE, SDF_OUTLINE_WIDTH, SDF_SMOOTHING, SDF_SPREAD, TEXT_BUFFER_GLYPHS, TEXT_FONT_SIZE,
+  },
+  settings::Settings,
+  error::Error,
+  gl_state::{BlendMode, GlState},
+  pack::load_asset,
   render::gl::types::*,
-  resources::{Character, DrawBuffers, LineGeometry, QuadGeometry, TextBuffers},
-  Camera, CircleGeometry, RGB_CLEAR_COLOR,
+  resources::{
+    BufferCapacity, Camera, Character, CircleGeometry, Distortions, DrawBuffers, FrameStats, FramebufferMode,
+    GpuAllocation, LineGeometry, ParticleGeometry, PostFx, QuadGeometry, RenderAlpha, RendererCaps, ScreenGeometry,
+    TextBuffers, TextLayer,
+  },
+  sdf::{atlas_uv, distance_field},
+  shaders::Program,
+  tasks::TaskStatus,
+};
+use bevy_ecs::{
+  prelude::Resource,
+  system::{Res, ResMut},
 };
-use bevy_ecs::system::{Res, ResMut};
 use freetype as ft;
 use lyon::{
-  lyon_tessellation::{FillOptions, FillTessellator, FillVertex, FillVertexConstructor},
-  math::{point, Point},
-  path::Path,
-  tessellation::{
-    geometry_builder::simple_builder, StrokeOptions, StrokeTessellator, StrokeVertex, StrokeVertexConstructor,
-    VertexBuffers,
-  },
+  lyon_tessellation::{FillVertex, FillVertexConstructor},
+  math::Point,
+  tessellation::{StrokeVertex, StrokeVertexConstructor},
 };
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
 
 macro_rules! get_offset {
   ($type:ty, $field:tt) => {{
@@ -37,109 +51,6 @@ macro_rules! cstr {
   };
 }
 
-const FBO_VERTEX_SHADER: &str = r#"
-#version 330 core
-
-layout (location = 0) in vec2 Position;
-layout (location = 1) in vec2 TexCoords;
-
-out VERTEX_SHADER_OUTPUT {
-  vec2 TexCoords;
-} OUT;
-
-void main() {
-  OUT.TexCoords = TexCoords;
-  gl_Position = vec4(Position, 0.0, 1.0);
-}
-"#;
-
-const FBO_FRAGMENT_SHADER: &str = r#"
-#version 330 core
-
-in VERTEX_SHADER_OUTPUT {
-  vec2 TexCoords;
-} IN;
-
-out vec4 Color;
-
-uniform sampler2D uTexture;
-
-void main() {
-  Color = texture(uTexture, IN.TexCoords);
-}
-"#;
-
-const SCENE_VERTEX_SHADER: &str = r#"
-#version 330 core
-
-layout (location = 0) in mat4 Transform;
-layout (location = 4) in vec4 Color;
-layout (location = 5) in vec2 Position;
-
-uniform mat4 uMVP;
-
-out VERTEX_SHADER_OUTPUT {
-  vec4 Color;
-} OUT;
-
-void main() {
-  gl_Position = uMVP * Transform * vec4(Position, 0.0, 1.0);
-  OUT.Color = Color;
-}
-"#;
-
-const SCENE_FRAGMENT_SHADER: &str = r#"
-#version 330 core
-
-in VERTEX_SHADER_OUTPUT {
-  vec4 Color;
-} IN;
-
-out vec4 Color;
-
-void main() {
-  Color = IN.Color;
-}
-"#;
-
-const TEXT_VERTEX_SHADER: &str = r#"
-#version 330 core
-
-layout (location = 0) in vec4 PosTex;
-layout (location = 1) in vec4 Color;
-
-uniform mat4 uProjection;
-
-out VERTEX_SHADER_OUTPUT {
-  vec2 TexCoords;
-  vec4 Color;
-} OUT;
-
-void main() {
-  gl_Position = uProjection * vec4(PosTex.xy, 0.0, 1.0);
-  OUT.TexCoords = PosTex.zw;
-  OUT.Color = Color;
-}
-"#;
-
-const TEXT_FRAGMENT_SHADER: &str = r#"
-#version 330 core
-
-in VERTEX_SHADER_OUTPUT {
-  vec2 TexCoords;
-  vec4 Color;
-} IN;
-
-out vec4 Color;
-
-uniform sampler2D uTexture;
-
-void main() {
-  vec4 sampled = vec4(1.0, 1.0, 1.0, texture(uTexture, IN.TexCoords).r);
-  Color = IN.Color * sampled;
-}
-"#;
-
 #[rustfmt::skip]
 const LOW_RES_QUAD_VERTICES: [f32; 24] = [
   -1.0, 1.0, 0.0,
@@ -178,9 +89,147 @@ impl std::ops::Deref for Gl {
 pub struct LowResFrameBuffer {
   vao: GLuint,
   vbo: GLuint,
+  /// None when no format worked, the scene is then drawn straight into the window.
+  target: Option<LowResTarget>,
+  shader_program: GLuint,
+}
+
+struct LowResTarget {
   fbo: GLuint,
   texture2d: GLuint,
-  shader_program: GLuint,
+  rbo: GLuint,
+  /// Pixels, see [`Settings::internal_size`]. The scene is projected onto the same playfield whatever
+  /// the size.
+  size: (GLsizei, GLsizei),
+}
+
+struct FramebufferFormat {
+  color: GLenum,
+  depth: GLenum,
+  depth_attachment: GLenum,
+  mode: FramebufferMode,
+}
+
+/// Tried in order, constrained drivers fail on the depth stencil renderbuffer or the RGB texture.
+const FRAMEBUFFER_FORMATS: [FramebufferFormat; 3] = [
+  FramebufferFormat {
+    color: gl::RGB,
+    depth: gl::DEPTH24_STENCIL8,
+    depth_attachment: gl::DEPTH_STENCIL_ATTACHMENT,
+    mode: FramebufferMode::LowRes {
+      color: "RGB",
+      depth: "DEPTH24_STENCIL8",
+    },
+  },
+  FramebufferFormat {
+    color: gl::RGB,
+    depth: gl::DEPTH_COMPONENT16,
+    depth_attachment: gl::DEPTH_ATTACHMENT,
+    mode: FramebufferMode::LowRes {
+      color: "RGB",
+      depth: "DEPTH_COMPONENT16",
+    },
+  },
+  FramebufferFormat {
+    color: gl::RGBA,
+    depth: gl::DEPTH_COMPONENT16,
+    depth_attachment: gl::DEPTH_ATTACHMENT,
+    mode: FramebufferMode::LowRes {
+      color: "RGBA",
+      depth: "DEPTH_COMPONENT16",
+    },
+  },
+];
+
+unsafe fn create_low_res_target(
+  gl: &Gl,
+  state: &mut GlState,
+  format: &FramebufferFormat,
+  (width, height): (GLsizei, GLsizei),
+) -> Result<LowResTarget, Error> {
+  let mut fbo = 0;
+  gl.GenFramebuffers(1, &mut fbo);
+  gl.BindFramebuffer(gl::FRAMEBUFFER, fbo);
+
+  let mut texture2d = 0;
+  gl.GenTextures(1, &mut texture2d);
+  state.bind_texture(gl, 0, texture2d);
+  gl.TexImage2D(
+    gl::TEXTURE_2D,
+    0,
+    format.color as i32,
+    width,
+    height,
+    0,
+    format.color,
+    gl::UNSIGNED_BYTE,
+    std::ptr::null(),
+  );
+  gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+  gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+  gl.FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, texture2d, 0);
+
+  let mut rbo = 0;
+  gl.GenRenderbuffers(1, &mut rbo);
+  gl.BindRenderbuffer(gl::RENDERBUFFER, rbo);
+  gl.RenderbufferStorage(gl::RENDERBUFFER, format.depth, width, height);
+  gl.FramebufferRenderbuffer(gl::FRAMEBUFFER, format.depth_attachment, gl::RENDERBUFFER, rbo);
+  let status = gl.CheckFramebufferStatus(gl::FRAMEBUFFER);
+  gl.BindFramebuffer(gl::FRAMEBUFFER, 0);
+  state.bind_texture(gl, 0, 0);
+
+  if status != gl::FRAMEBUFFER_COMPLETE {
+    gl.DeleteFramebuffers(1, &fbo);
+    gl.DeleteTextures(1, &texture2d);
+    gl.DeleteRenderbuffers(1, &rbo);
+    return Err(Error::FramebufferIncomplete(status));
+  }
+  Ok(LowResTarget {
+    fbo,
+    texture2d,
+    rbo,
+    size: (width, height),
+  })
+}
+
+/// A rect in drawable pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+  pub x: GLint,
+  pub y: GLint,
+  pub width: GLsizei,
+  pub height: GLsizei,
+}
+
+impl Viewport {
+  /// The largest rect with the playfield's aspect ratio, centered in `size`. Empty for an empty
+  /// `size`, e.g. while minimized.
+  pub fn letterbox((w, h): (GLsizei, GLsizei)) -> Self {
+    let (w, h) = (w.max(0), h.max(0));
+    // w / h > SCREEN_WIDTH / SCREEN_HEIGHT, without dividing by a size that may be 0
+    let (width, height) = if w * SCREEN_HEIGHT > h * SCREEN_WIDTH {
+      (h * SCREEN_WIDTH / SCREEN_HEIGHT, h)
+    } else {
+      (w, w * SCREEN_HEIGHT / SCREEN_WIDTH)
+    };
+    Self {
+      x: (w - width) / 2,
+      y: (h - height) / 2,
+      width,
+      height,
+    }
+  }
+
+  /// A point in drawable pixels, y up, where the text laid out at SCREEN_RENDER_* is, e.g. the mouse.
+  pub fn to_text(self, point: glam::Vec2) -> glam::Vec2 {
+    let origin = glam::vec2(self.x as f32, self.y as f32);
+    let size = glam::vec2(self.width as f32, self.height as f32);
+    (point - origin) / size * glam::vec2(SCREEN_RENDER_WIDTH as f32, SCREEN_RENDER_HEIGHT as f32)
+  }
+
+  unsafe fn apply(self, gl: &Gl) {
+    gl.Viewport(self.x, self.y, self.width, self.height);
+  }
 }
 
 pub struct OpenglCtx {
@@ -188,7 +237,27 @@ pub struct OpenglCtx {
   frame_buffer: LowResFrameBuffer,
   scene_program: GLuint,
   text_program: GLuint,
-  pub viewport: (GLsizei, GLsizei),
+  sdf_text_program: GLuint,
+  /// Framebuffer pixels, what glViewport wants. Differs from the window size on high dpi displays.
+  pub drawable_size: (GLsizei, GLsizei),
+  /// Where the frame is shown within the drawable, with black bars around it. Everything that maps
+  /// between window and screen space goes through this.
+  pub viewport: Viewport,
+  pub caps: RendererCaps,
+  state: GlState,
+  /// Debug builds without the debug output callback check glGetError after each draw instead.
+  poll_errors: bool,
+}
+
+impl OpenglCtx {
+  fn program_mut(&mut self, program: Program) -> &mut GLuint {
+    match program {
+      Program::LowRes => &mut self.frame_buffer.shader_program,
+      Program::Scene => &mut self.scene_program,
+      Program::Text => &mut self.text_program,
+      Program::SdfText => &mut self.sdf_text_program,
+    }
+  }
 }
 
 #[repr(C)]
@@ -214,8 +283,8 @@ pub struct WithTransformColor {
   pub color_rgba: ColorGl,
 }
 
-impl StrokeVertexConstructor<MyVertex> for WithTransformColor {
-  fn new_vertex(&mut self, vertex: StrokeVertex) -> MyVertex {
+impl WithTransformColor {
+  pub fn vertex(&self, position: Point) -> MyVertex {
     let t = self.transform.to_cols_array_2d();
     MyVertex {
       transform_mat4_1: t[0],
@@ -223,22 +292,20 @@ impl StrokeVertexConstructor<MyVertex> for WithTransformColor {
       transform_mat4_3: t[2],
       transform_mat4_4: t[3],
       color_rgba: self.color_rgba.to_array(),
-      position: vertex.position().to_array(),
+      position: position.to_array(),
     }
   }
 }
 
+impl StrokeVertexConstructor<MyVertex> for WithTransformColor {
+  fn new_vertex(&mut self, vertex: StrokeVertex) -> MyVertex {
+    self.vertex(vertex.position())
+  }
+}
+
 impl FillVertexConstructor<MyVertex> for WithTransformColor {
   fn new_vertex(&mut self, vertex: FillVertex) -> MyVertex {
-    let t = self.transform.to_cols_array_2d();
-    MyVertex {
-      transform_mat4_1: t[0],
-      transform_mat4_2: t[1],
-      transform_mat4_3: t[2],
-      transform_mat4_4: t[3],
-      color_rgba: self.color_rgba.to_array(),
-      position: vertex.position().to_array(),
-    }
+    self.vertex(vertex.position())
   }
 }
 
@@ -248,7 +315,7 @@ unsafe fn create_error_buffer(length: usize) -> CString {
   CString::from_vec_unchecked(buffer)
 }
 
-fn compile_shader(gl: &gl::Gl, src: &str, kind: GLenum) -> Result<GLuint, String> {
+fn compile_shader(gl: &gl::Gl, src: &str, kind: GLenum) -> Result<GLuint, Error> {
   unsafe {
     let shader = gl.CreateShader(kind);
     let c_str_src = CString::new(src.as_bytes()).unwrap();
@@ -262,18 +329,24 @@ fn compile_shader(gl: &gl::Gl, src: &str, kind: GLenum) -> Result<GLuint, String
       gl.GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut len);
       let error = create_error_buffer(len as usize);
       gl.GetShaderInfoLog(shader, len, std::ptr::null_mut(), error.as_ptr() as *mut GLchar);
-      return Err(error.to_string_lossy().into_owned());
+      gl.DeleteShader(shader);
+      return Err(Error::ShaderCompile {
+        stage: if kind == gl::VERTEX_SHADER { "vertex" } else { "fragment" },
+        log: error.to_string_lossy().into_owned(),
+      });
     }
     Ok(shader)
   }
 }
 
-fn link_program(gl: &gl::Gl, vertex_shader: GLuint, fragment_shader: GLuint) -> Result<GLuint, String> {
+fn link_program(gl: &gl::Gl, vertex_shader: GLuint, fragment_shader: GLuint) -> Result<GLuint, Error> {
   unsafe {
     let program = gl.CreateProgram();
     gl.AttachShader(program, vertex_shader);
     gl.AttachShader(program, fragment_shader);
     gl.LinkProgram(program);
+    gl.DeleteShader(vertex_shader);
+    gl.DeleteShader(fragment_shader);
     let mut success = gl::TRUE as GLint;
     gl.GetProgramiv(program, gl::LINK_STATUS, &mut success);
 
@@ -282,92 +355,85 @@ fn link_program(gl: &gl::Gl, vertex_shader: GLuint, fragment_shader: GLuint) ->
       gl.GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut len);
       let error = create_error_buffer(len as usize);
       gl.GetProgramInfoLog(program, len, std::ptr::null_mut(), error.as_ptr() as *mut GLchar);
-      return Err(error.to_string_lossy().into_owned());
+      gl.DeleteProgram(program);
+      return Err(Error::ProgramLink {
+        log: error.to_string_lossy().into_owned(),
+      });
     }
 
-    gl.DeleteShader(vertex_shader);
-    gl.DeleteShader(fragment_shader);
-
     Ok(program)
   }
 }
 
-pub fn create_shader_program(gl: &gl::Gl, vertex_src: &str, fragment_src: &str) -> Result<GLuint, String> {
+pub fn create_shader_program(gl: &gl::Gl, vertex_src: &str, fragment_src: &str) -> Result<GLuint, Error> {
   let vertex_shader = compile_shader(gl, vertex_src, gl::VERTEX_SHADER)?;
-  let fragment_shader = compile_shader(gl, fragment_src, gl::FRAGMENT_SHADER)?;
+  let fragment_shader = compile_shader(gl, fragment_src, gl::FRAGMENT_SHADER)
+    .inspect_err(|_| unsafe { gl.DeleteShader(vertex_shader) })?;
   link_program(gl, vertex_shader, fragment_shader)
 }
 
-pub fn calculate_size_for_lines() -> VertexBuffers<Point, u16> {
-  let mut geometry: VertexBuffers<Point, u16> = VertexBuffers::new();
-  let mut vertex_builder = simple_builder(&mut geometry);
-  let mut tessellator = StrokeTessellator::new();
-  let mut builder = Path::builder();
-  builder.begin(point(0.0, 0.0));
-  builder.line_to(point(0.0, 1.0));
-  builder.close();
-
-  tessellator
-    .tessellate_path(&builder.build(), &StrokeOptions::default(), &mut vertex_builder)
-    .unwrap();
+/// Compiles and links `program` from its files, see [`load_asset`] for where they are looked up.
+fn load_program(gl: &gl::Gl, program: Program) -> Result<GLuint, Error> {
+  let [vertex_src, fragment_src] = program.files().map(|file| {
+    load_asset(&file).map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+  });
+  create_shader_program(gl, &vertex_src?, &fragment_src?)
+}
 
-  geometry
+pub fn calculate_size_for_circles() -> BufferCapacity {
+  BufferCapacity {
+    vertices: CIRCLE_BUFFER_VERTICES,
+    indices: CIRCLE_BUFFER_INDICES,
+  }
 }
 
-pub fn calculate_size_for_circles() -> VertexBuffers<Point, u16> {
-  let mut geometry: VertexBuffers<Point, u16> = VertexBuffers::new();
-  let mut vertex_builder = simple_builder(&mut geometry);
-  let mut tessellator = StrokeTessellator::new();
-  tessellator
-    .tessellate_circle(
-      Point::new(0.0, 0.0),
-      16.0,
-      &StrokeOptions::default(),
-      &mut vertex_builder,
-    )
-    .unwrap();
+pub fn calculate_size_for_quads() -> BufferCapacity {
+  BufferCapacity {
+    vertices: QUAD_BUFFER_VERTICES,
+    indices: QUAD_BUFFER_INDICES,
+  }
+}
 
-  geometry
+pub fn calculate_size_for_lines() -> BufferCapacity {
+  BufferCapacity {
+    vertices: LINE_BUFFER_VERTICES,
+    indices: LINE_BUFFER_INDICES,
+  }
 }
 
-pub fn calculate_size_for_quads() -> VertexBuffers<Point, u16> {
-  let mut geometry: VertexBuffers<Point, u16> = VertexBuffers::new();
-  let mut vertex_builder = simple_builder(&mut geometry);
-  let mut tessellator = FillTessellator::new();
-  tessellator
-    .tessellate_circle(Point::new(0.0, 0.0), 16.0, &FillOptions::default(), &mut vertex_builder)
-    .unwrap();
+pub fn calculate_size_for_particles() -> BufferCapacity {
+  BufferCapacity {
+    vertices: PARTICLE_BUFFER_VERTICES,
+    indices: PARTICLE_BUFFER_INDICES,
+  }
+}
 
-  geometry
+pub fn calculate_size_for_screen() -> BufferCapacity {
+  BufferCapacity {
+    vertices: SCREEN_BUFFER_VERTICES,
+    indices: SCREEN_BUFFER_INDICES,
+  }
 }
 
 pub fn create_draw_buffer<T>(
   gl: &Gl,
-  opengl_ctx: &OpenglCtx,
-  get_vertex_buffer: fn() -> VertexBuffers<Point, u16>,
+  opengl_ctx: &mut OpenglCtx,
+  get_capacity: fn() -> BufferCapacity,
 ) -> DrawBuffers<T> {
   unsafe {
     let (mut vao, mut vbo, mut ebo) = (0, 0, 0);
-    let vertex_buffer = get_vertex_buffer();
+    let capacity = get_capacity();
 
     gl.GenVertexArrays(1, &mut vao);
     gl.GenBuffers(1, &mut vbo);
     gl.GenBuffers(1, &mut ebo);
-    gl.BindVertexArray(vao);
+    let vbo_capacity = std::mem::size_of::<MyVertex>() * capacity.vertices;
+    let ebo_capacity = std::mem::size_of::<u32>() * capacity.indices;
+    opengl_ctx.state.bind_vao(gl, vao);
     gl.BindBuffer(gl::ARRAY_BUFFER, vbo);
-    gl.BufferData(
-      gl::ARRAY_BUFFER,
-      (std::mem::size_of::<MyVertex>() * vertex_buffer.vertices.len() * 10000) as GLsizeiptr,
-      std::ptr::null(),
-      gl::DYNAMIC_DRAW,
-    );
+    gl.BufferData(gl::ARRAY_BUFFER, vbo_capacity as GLsizeiptr, std::ptr::null(), gl::DYNAMIC_DRAW);
     gl.BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
-    gl.BufferData(
-      gl::ELEMENT_ARRAY_BUFFER,
-      (std::mem::size_of::<u16>() * vertex_buffer.indices.len() * 10000) as GLsizeiptr,
-      std::ptr::null(),
-      gl::DYNAMIC_DRAW,
-    );
+    gl.BufferData(gl::ELEMENT_ARRAY_BUFFER, ebo_capacity as GLsizeiptr, std::ptr::null(), gl::DYNAMIC_DRAW);
 
     let transform_attr = gl.GetAttribLocation(opengl_ctx.scene_program, cstr!("Transform").as_ptr()) as GLuint;
     gl.EnableVertexAttribArray(transform_attr);
@@ -430,32 +496,93 @@ pub fn create_draw_buffer<T>(
 
     gl.BindBuffer(gl::ARRAY_BUFFER, 0);
     gl.BindBuffer(gl::ELEMENT_ARRAY_BUFFER, 0);
-    gl.BindVertexArray(0);
+    opengl_ctx.state.bind_vao(gl, 0);
+
+    let mut buffers = DrawBuffers::<T>::new(vao, vbo, ebo);
+    buffers.capacity = capacity;
+    buffers.vbo_allocation = GpuAllocation::new(vbo_capacity);
+    buffers.ebo_allocation = GpuAllocation::new(ebo_capacity);
+    buffers
+  }
+}
 
-    DrawBuffers::<T>::new(vao, vbo, ebo)
+/// Uploads `data` to the start of the buffer bound to `target`. A buffer too small for it is
+/// reallocated first, at least doubled so a growing frame doesn't reallocate every time, and so is one
+/// the steward shrank.
+unsafe fn upload<T>(gl: &Gl, target: GLenum, allocation: &mut GpuAllocation, data: &[T]) {
+  let size = std::mem::size_of_val(data);
+  if size > allocation.bytes {
+    allocation.bytes = size.max(allocation.bytes * 2);
+    allocation.stale = true;
+  }
+  if std::mem::take(&mut allocation.stale) {
+    gl.BufferData(target, allocation.bytes as GLsizeiptr, std::ptr::null(), gl::DYNAMIC_DRAW);
   }
+  allocation.used = allocation.used.max(size);
+  gl.BufferSubData(target, 0, size as GLsizeiptr, data.as_ptr() as *const GLvoid);
 }
 
-pub fn create_text_buffer(gl: &Gl, opengl_ctx: &OpenglCtx) -> TextBuffers {
-  let path = std::path::Path::new("m5x7.ttf");
-  let library = ft::Library::init().unwrap();
-  let face = library.new_face(path, 0).unwrap();
-  face.set_pixel_sizes(0, 32).unwrap();
+struct Glyph {
+  c: char,
+  width: i32,
+  height: i32,
+  pixels: Vec<u8>,
+  bearing: glam::Vec2,
+  advance: f32,
+}
 
-  let (atlas_texture, characters) = unsafe {
-    let (mut w, mut h) = (0, 0);
-    for c in 32..127 {
-      if face.load_char(c, ft::face::LoadFlag::RENDER).is_ok() {
-        w += face.glyph().bitmap().width();
-        h = h.max(face.glyph().bitmap().rows());
-      } else {
-        eprintln!("could not load character {}", c as u8 as char);
-      }
+/// Renders the printable ascii range at `pixel_size`, each glyph tightly packed. `padding` is added
+/// to the size and metrics only, for a distance field to fill in.
+fn rasterize_glyphs(face: &ft::Face, pixel_size: u32, padding: i32) -> Result<Vec<Glyph>, Error> {
+  face.set_pixel_sizes(0, pixel_size)?;
+
+  let mut glyphs = Vec::new();
+  for c in 32..127 {
+    if face.load_char(c, ft::face::LoadFlag::RENDER).is_err() {
+      eprintln!("could not load character {}", c as u8 as char);
+      continue;
     }
 
+    let glyph = face.glyph();
+    let bitmap = glyph.bitmap();
+    let (width, rows, pitch) = (bitmap.width() as usize, bitmap.rows() as usize, bitmap.pitch() as usize);
+    let pixels = match width {
+      0 => Vec::new(),
+      _ => bitmap
+        .buffer()
+        .chunks(pitch)
+        .take(rows)
+        .flat_map(|row| &row[..width])
+        .copied()
+        .collect(),
+    };
+    glyphs.push(Glyph {
+      c: c as u8 as char,
+      width: width as i32 + 2 * padding,
+      height: rows as i32 + 2 * padding,
+      pixels,
+      bearing: glam::vec2(
+        (glyph.bitmap_left() - padding) as f32,
+        (glyph.bitmap_top() + padding) as f32,
+      ),
+      advance: (glyph.advance().x >> 6) as f32,
+    });
+  }
+
+  Ok(glyphs)
+}
+
+/// Uploads `glyphs` into a single row atlas. Metrics are scaled by `metric_scale` so they are given
+/// in [`TEXT_FONT_SIZE`] pixels whatever size the glyphs were rendered at.
+fn upload_glyph_atlas(gl: &Gl, state: &mut GlState, glyphs: Vec<Glyph>, metric_scale: f32) -> TextLayer {
+  let w = glyphs.iter().map(|glyph| glyph.width).sum::<i32>();
+  let h = glyphs.iter().map(|glyph| glyph.height).max().unwrap_or_default();
+  let mut characters = std::collections::HashMap::<char, Character>::new();
+
+  let texture = unsafe {
     let mut texture = 0;
     gl.GenTextures(1, &mut texture);
-    gl.BindTexture(gl::TEXTURE_2D, texture);
+    state.bind_texture(gl, 0, texture);
     gl.TexImage2D(
       gl::TEXTURE_2D,
       0,
@@ -471,67 +598,106 @@ pub fn create_text_buffer(gl: &Gl, opengl_ctx: &OpenglCtx) -> TextBuffers {
     gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
     gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
     gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+    gl.PixelStorei(gl::UNPACK_ALIGNMENT, 1);
 
     let mut x = 0;
-    let mut characters = std::collections::HashMap::<char, Character>::new();
-    gl.PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+    for glyph in glyphs {
+      gl.TexSubImage2D(
+        gl::TEXTURE_2D,
+        0,
+        x,
+        0,
+        glyph.width,
+        glyph.height,
+        gl::RED,
+        gl::UNSIGNED_BYTE,
+        glyph.pixels.as_ptr() as *const GLvoid,
+      );
+
+      let (tx, tx_1, ty) = atlas_uv(x, glyph.width, glyph.height, w, h);
+      let character = Character {
+        tx,
+        tx_1,
+        ty,
+        width: glyph.width as f32 * metric_scale,
+        height: glyph.height as f32 * metric_scale,
+        bearing: glyph.bearing * metric_scale,
+        advance: glyph.advance * metric_scale,
+      };
+      characters.insert(glyph.c, character);
+
+      x += glyph.width;
+    }
 
-    for c in 32..127 {
-      if face.load_char(c, ft::face::LoadFlag::RENDER).is_ok() {
-        gl.TexSubImage2D(
-          gl::TEXTURE_2D,
-          0,
-          x,
-          0,
-          face.glyph().bitmap().width(),
-          face.glyph().bitmap().rows(),
-          gl::RED,
-          gl::UNSIGNED_BYTE,
-          face.glyph().bitmap().buffer().as_ptr() as *const GLvoid,
-        );
+    state.bind_texture(gl, 0, 0);
 
-        let character = Character {
-          tx: x as f32 / w as f32,
-          tx_1: (x as f32 + face.glyph().bitmap().width() as f32) / w as f32,
-          ty: face.glyph().bitmap().rows() as f32 / h as f32,
-          width: face.glyph().bitmap().width() as f32,
-          height: face.glyph().bitmap().rows() as f32,
-          bearing: glam::vec2(face.glyph().bitmap_left() as f32, face.glyph().bitmap_top() as f32),
-          advance: (face.glyph().advance().x >> 6) as f32,
-        };
-        characters.insert(c as u8 as char, character);
-
-        x += face.glyph().bitmap().width();
-      } else {
-        eprintln!("could not load character {}", c as u8 as char);
-      }
+    texture
+  };
+
+  TextLayer {
+    atlas_texture: texture,
+    characters,
+    ..Default::default()
+  }
+}
+
+/// The distance field atlas under construction. Rendering the glyphs is cheap, their fields are
+/// computed one glyph per [`SdfAtlasJob::step`] so building the atlas never stalls a frame. Once every
+/// field is done the job is inserted as a resource for the main loop to [`SdfAtlasJob::upload`].
+#[derive(Resource, Default)]
+pub struct SdfAtlasJob {
+  glyphs: Vec<Glyph>,
+  next: usize,
+}
+
+impl SdfAtlasJob {
+  pub fn step(&mut self) -> TaskStatus {
+    let padding = SDF_SPREAD as i32;
+    if let Some(glyph) = self.glyphs.get_mut(self.next) {
+      let (width, rows) = ((glyph.width - 2 * padding) as usize, (glyph.height - 2 * padding) as usize);
+      glyph.pixels = distance_field(&glyph.pixels, width, rows, width, SDF_SPREAD);
+      self.next += 1;
     }
 
-    gl.BindTexture(gl::TEXTURE_2D, 0);
+    match self.next < self.glyphs.len() {
+      true => TaskStatus::Continue,
+      false => TaskStatus::Done,
+    }
+  }
+
+  pub fn upload(self, gl: &Gl, opengl_ctx: &mut OpenglCtx) -> TextLayer {
+    let metric_scale = TEXT_FONT_SIZE as f32 / SDF_FONT_SIZE as f32;
+    upload_glyph_atlas(gl, &mut opengl_ctx.state, self.glyphs, metric_scale)
+  }
+}
 
-    (texture, characters)
+
+
+/// The text buffers with the bitmap atlas uploaded and the distance field atlas left empty, the
+/// returned job builds that one.
+pub fn create_text_buffer(gl: &Gl, opengl_ctx: &mut OpenglCtx) -> Result<(TextBuffers, SdfAtlasJob), Error> {
+  let library = ft::Library::init()?;
+  let face = library.new_memory_face(load_asset("m5x7.ttf")?, 0)?;
+
+  let glyphs = rasterize_glyphs(&face, TEXT_FONT_SIZE, 0)?;
+  let bitmap = upload_glyph_atlas(gl, &mut opengl_ctx.state, glyphs, 1.0);
+  let sdf_job = SdfAtlasJob {
+    glyphs: rasterize_glyphs(&face, SDF_FONT_SIZE, SDF_SPREAD as i32)?,
+    next: 0,
   };
 
+  let vbo_capacity = 4 * std::mem::size_of::<MyTextVertex>() * TEXT_BUFFER_GLYPHS;
+  let ebo_capacity = 6 * std::mem::size_of::<u32>() * TEXT_BUFFER_GLYPHS;
   let (vao, vbo, ebo) = unsafe {
     let (mut vao, mut vbo, mut ebo) = (0, 0, 0);
     gl.GenVertexArrays(1, &mut vao);
     gl.GenBuffers(1, &mut vbo);
     gl.GenBuffers(1, &mut ebo);
-    gl.BindVertexArray(vao);
+    opengl_ctx.state.bind_vao(gl, vao);
     gl.BindBuffer(gl::ARRAY_BUFFER, vbo);
-    gl.BufferData(
-      gl::ARRAY_BUFFER,
-      (6 * std::mem::size_of::<MyVertex>() * 10000) as GLsizeiptr,
-      std::ptr::null(),
-      gl::DYNAMIC_DRAW,
-    );
+    gl.BufferData(gl::ARRAY_BUFFER, vbo_capacity as GLsizeiptr, std::ptr::null(), gl::DYNAMIC_DRAW);
     gl.BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
-    gl.BufferData(
-      gl::ELEMENT_ARRAY_BUFFER,
-      (6 * std::mem::size_of::<u16>() * 1000) as GLsizeiptr,
-      std::ptr::null(),
-      gl::DYNAMIC_DRAW,
-    );
+    gl.BufferData(gl::ELEMENT_ARRAY_BUFFER, ebo_capacity as GLsizeiptr, std::ptr::null(), gl::DYNAMIC_DRAW);
 
     let pos_tex_attr = gl.GetAttribLocation(opengl_ctx.text_program, cstr!("PosTex").as_ptr());
     gl.EnableVertexAttribArray(pos_tex_attr as u32);
@@ -557,31 +723,92 @@ pub fn create_text_buffer(gl: &Gl, opengl_ctx: &OpenglCtx) -> TextBuffers {
 
     gl.BindBuffer(gl::ARRAY_BUFFER, 0);
     gl.BindBuffer(gl::ELEMENT_ARRAY_BUFFER, 0);
-    gl.BindVertexArray(0);
+    opengl_ctx.state.bind_vao(gl, 0);
 
     (vao, vbo, ebo)
   };
 
-  TextBuffers {
-    vao,
-    vbo,
-    ebo,
-    atlas_texture,
-    characters,
-    vertex_buffer: Vec::new(),
-    index_buffer: Vec::new(),
+  Ok((
+    TextBuffers {
+      vao,
+      vbo,
+      ebo,
+      vbo_allocation: GpuAllocation::new(vbo_capacity),
+      ebo_allocation: GpuAllocation::new(ebo_capacity),
+      bitmap,
+      sdf: TextLayer::default(),
+    },
+    sdf_job,
+  ))
+}
+
+extern "system" fn log_debug_message(
+  _source: GLenum,
+  _gltype: GLenum,
+  id: GLuint,
+  severity: GLenum,
+  _length: GLsizei,
+  message: *const GLchar,
+  _user_param: *mut GLvoid,
+) {
+  let severity = match severity {
+    gl::DEBUG_SEVERITY_HIGH => "high",
+    gl::DEBUG_SEVERITY_MEDIUM => "medium",
+    gl::DEBUG_SEVERITY_LOW => "low",
+    // driver chatter about buffer placement and the like
+    _ => return,
+  };
+  let message = unsafe { CStr::from_ptr(message) }.to_string_lossy();
+  eprintln!("GL debug message {} ({} severity): {}", id, severity, message);
+}
+
+/// Registers [`log_debug_message`] when the context has debug output, core since 4.3 and otherwise
+/// from KHR_debug. Returns whether it did.
+unsafe fn enable_debug_output(gl: &Gl) -> bool {
+  let integer = |name: GLenum| {
+    let mut value = 0;
+    gl.GetIntegerv(name, &mut value);
+    value
+  };
+  let core = (integer(gl::MAJOR_VERSION), integer(gl::MINOR_VERSION)) >= (4, 3);
+  let khr_debug = (0..integer(gl::NUM_EXTENSIONS) as GLuint)
+    .any(|i| CStr::from_ptr(gl.GetStringi(gl::EXTENSIONS, i) as *const GLchar).to_bytes() == b"GL_KHR_debug");
+  if !(core || khr_debug) || !gl.DebugMessageCallback.is_loaded() {
+    return false;
+  }
+
+  gl.Enable(gl::DEBUG_OUTPUT);
+  // reported from within the call that caused it, so a breakpoint in the callback has the culprit
+  gl.Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
+  gl.DebugMessageCallback(Some(log_debug_message), std::ptr::null());
+  true
+}
+
+/// Logs every error GL queued since the last call.
+unsafe fn report_errors(gl: &Gl, what: &str) {
+  loop {
+    match gl.GetError() {
+      gl::NO_ERROR => break,
+      error => eprintln!("GL error 0x{:x} after drawing {}", error, what),
+    }
   }
 }
 
-pub fn init(gl: &Gl) -> Result<OpenglCtx, String> {
-  let low_res_prg = create_shader_program(gl, FBO_VERTEX_SHADER, FBO_FRAGMENT_SHADER)?;
-  let scene_prg = create_shader_program(gl, SCENE_VERTEX_SHADER, SCENE_FRAGMENT_SHADER)?;
-  let text_prg = create_shader_program(gl, TEXT_VERTEX_SHADER, TEXT_FRAGMENT_SHADER)?;
-  let (fbo_vao, fbo_vbo, fbo, fbo_texture) = unsafe {
+pub fn init(gl: &Gl, settings: &Settings) -> Result<OpenglCtx, Error> {
+  let low_res_prg = load_program(gl, Program::LowRes)?;
+  let scene_prg = load_program(gl, Program::Scene)?;
+  let text_prg = load_program(gl, Program::Text)?;
+  let sdf_text_prg = load_program(gl, Program::SdfText)?;
+  let mut state = GlState::default();
+  let poll_errors = cfg!(debug_assertions) && !unsafe { enable_debug_output(gl) };
+  if poll_errors {
+    eprintln!("no GL debug output, polling for errors after each draw");
+  }
+  let (fbo_vao, fbo_vbo, target, framebuffer) = unsafe {
     let (mut vao, mut vbo) = (0, 0);
     gl.GenVertexArrays(1, &mut vao);
     gl.GenBuffers(1, &mut vbo);
-    gl.BindVertexArray(vao);
+    state.bind_vao(gl, vao);
     gl.BindBuffer(gl::ARRAY_BUFFER, vbo);
     gl.BufferData(
       gl::ARRAY_BUFFER,
@@ -612,42 +839,25 @@ pub fn init(gl: &Gl) -> Result<OpenglCtx, String> {
       (2 * std::mem::size_of::<f32>()) as *const GLvoid,
     );
 
-    gl.UseProgram(low_res_prg);
-    gl.Uniform1i(gl.GetUniformLocation(low_res_prg, cstr!("uTexture").as_ptr()), 0);
-
-    let mut fbo = 0;
-    gl.GenFramebuffers(1, &mut fbo);
-    gl.BindFramebuffer(gl::FRAMEBUFFER, fbo);
-
-    let mut fbo_texture = 0;
-    gl.GenTextures(1, &mut fbo_texture);
-    gl.BindTexture(gl::TEXTURE_2D, fbo_texture);
-    gl.TexImage2D(
-      gl::TEXTURE_2D,
-      0,
-      gl::RGB as i32,
-      SCREEN_WIDTH,
-      SCREEN_HEIGHT,
-      0,
-      gl::RGB,
-      gl::UNSIGNED_BYTE,
-      std::ptr::null(),
-    );
-    gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
-    gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
-    gl.FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, fbo_texture, 0);
-
-    let mut rbo = 0;
-    gl.GenRenderbuffers(1, &mut rbo);
-    gl.BindRenderbuffer(gl::RENDERBUFFER, rbo);
-    gl.RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH24_STENCIL8, SCREEN_WIDTH, SCREEN_HEIGHT);
-    gl.FramebufferRenderbuffer(gl::FRAMEBUFFER, gl::DEPTH_STENCIL_ATTACHMENT, gl::RENDERBUFFER, rbo);
-    if gl.CheckFramebufferStatus(gl::FRAMEBUFFER) != gl::FRAMEBUFFER_COMPLETE {
-      println!("ERROR::FRAMEBUFFER:: Framebuffer is not complete!");
+    set_low_res_sampler(gl, &mut state, low_res_prg);
+
+    let mut target = None;
+    let mut mode = FramebufferMode::Direct;
+    for format in &FRAMEBUFFER_FORMATS {
+      match create_low_res_target(gl, &mut state, format, settings.internal_size()) {
+        Ok(created) => {
+          target = Some(created);
+          mode = format.mode;
+          break;
+        }
+        Err(e) => eprintln!("low res framebuffer with {}: {}", format.mode, e),
+      }
     }
-    gl.BindFramebuffer(gl::FRAMEBUFFER, 0);
 
-    (vao, vbo, fbo, fbo_texture)
+    state.bind_vao(gl, 0);
+    state.bind_program(gl, 0);
+
+    (vao, vbo, target, mode)
   };
 
   Ok(OpenglCtx {
@@ -655,80 +865,276 @@ pub fn init(gl: &Gl) -> Result<OpenglCtx, String> {
     frame_buffer: LowResFrameBuffer {
       vao: fbo_vao,
       vbo: fbo_vbo,
-      fbo,
-      texture2d: fbo_texture,
+      target,
       shader_program: low_res_prg,
     },
     scene_program: scene_prg,
     text_program: text_prg,
-    viewport: (SCREEN_RENDER_WIDTH as GLsizei, SCREEN_RENDER_HEIGHT as GLsizei),
+    sdf_text_program: sdf_text_prg,
+    drawable_size: (settings.window_width as GLsizei, settings.window_height as GLsizei),
+    viewport: Viewport::letterbox((settings.window_width as GLsizei, settings.window_height as GLsizei)),
+    caps: RendererCaps { framebuffer },
+    state,
+    poll_errors,
   })
 }
 
+/// The sampler is the only uniform set once instead of every frame, a new program needs it again.
+unsafe fn set_low_res_sampler(gl: &Gl, state: &mut GlState, program: GLuint) {
+  state.bind_program(gl, program);
+  gl.Uniform1i(gl.GetUniformLocation(program, cstr!("uTexture").as_ptr()), 0);
+}
+
+/// Compiles every program from its files again, for tweaking the shaders while the game runs. The new
+/// programs are only swapped in once all of them link and keep the attribute locations the vertex
+/// arrays were set up with, otherwise the error is reported and the old programs stay.
+pub fn reload_shaders(gl: &Gl, opengl_ctx: &mut OpenglCtx) {
+  let mut reloaded = Vec::new();
+  for program in Program::ALL {
+    let old = *opengl_ctx.program_mut(program);
+    match load_program(gl, program).and_then(|new| check_attributes(gl, program, old, new)) {
+      Ok(new) => reloaded.push(new),
+      Err(e) => {
+        eprintln!("{}, keeping the old shaders", e);
+        for new in reloaded {
+          unsafe { gl.DeleteProgram(new) };
+        }
+        return;
+      }
+    }
+  }
+
+  // unbound first, the tracker must not think a deleted program is still in use
+  opengl_ctx.state.bind_program(gl, 0);
+  for (program, new) in Program::ALL.into_iter().zip(reloaded) {
+    let old = std::mem::replace(opengl_ctx.program_mut(program), new);
+    unsafe { gl.DeleteProgram(old) };
+  }
+  unsafe { set_low_res_sampler(gl, &mut opengl_ctx.state, opengl_ctx.frame_buffer.shader_program) };
+  opengl_ctx.state.bind_program(gl, 0);
+  println!("shaders reloaded");
+}
+
+/// Passes `new` through if its attributes sit where they are in `old`, deletes it otherwise.
+fn check_attributes(gl: &Gl, program: Program, old: GLuint, new: GLuint) -> Result<GLuint, Error> {
+  for name in program.attributes() {
+    let name = CString::new(*name).unwrap();
+    let (before, after) = unsafe {
+      (
+        gl.GetAttribLocation(old, name.as_ptr()),
+        gl.GetAttribLocation(new, name.as_ptr()),
+      )
+    };
+    if before != after {
+      unsafe { gl.DeleteProgram(new) };
+      return Err(Error::ProgramLink {
+        log: format!("attribute {} moved from location {} to {}", name.to_string_lossy(), before, after),
+      });
+    }
+  }
+  Ok(new)
+}
+
 pub type RenderSystemState<'w, 's> = (
   Res<'w, Camera>,
   ResMut<'w, CircleGeometry>,
   ResMut<'w, QuadGeometry>,
   ResMut<'w, LineGeometry>,
+  ResMut<'w, ParticleGeometry>,
+  ResMut<'w, ScreenGeometry>,
   ResMut<'w, TextBuffers>,
+  Res<'w, Distortions>,
+  ResMut<'w, FrameStats>,
+  Res<'w, PostFx>,
+  Res<'w, RenderAlpha>,
 );
 
-pub fn render_gl(gl: &Gl, opengl_ctx: &OpenglCtx, render_state: RenderSystemState) -> Result<(), String> {
-  let (camera, mut circles, mut quads, mut lines, mut texts) = render_state;
+/// Every pass sets the state it depends on through [`GlState`], starting from a known state each
+/// frame, and debug builds check the tracker against GL after each pass.
+pub fn render_gl(gl: &Gl, opengl_ctx: &mut OpenglCtx, render_state: RenderSystemState) -> Result<(), Error> {
+  let (
+    camera,
+    mut circles,
+    mut quads,
+    mut lines,
+    mut particles,
+    mut screen,
+    mut texts,
+    distortions,
+    mut stats,
+    post_fx,
+    alpha,
+  ) = render_state;
+  stats.vertices = [
+    circles.vertex_buffer.vertices.len(),
+    quads.vertex_buffer.vertices.len(),
+    lines.vertex_buffer.vertices.len(),
+  ];
   let OpenglCtx {
     clear_color,
     frame_buffer,
     scene_program,
     text_program,
-    viewport: (w, h),
+    sdf_text_program,
+    viewport,
+    state,
+    poll_errors,
+    ..
   } = opengl_ctx;
+  let poll_errors = |gl: &Gl, what: &str| {
+    if *poll_errors {
+      unsafe { report_errors(gl, what) };
+    }
+  };
 
-  unsafe fn draw<T>(gl: &Gl, buffers: &mut DrawBuffers<T>) {
-    gl.BindVertexArray(buffers.vao);
-    gl.BindBuffer(gl::ARRAY_BUFFER, buffers.vbo);
-    gl.BufferSubData(
-      gl::ARRAY_BUFFER,
-      0,
-      (buffers.vertex_buffer.vertices.len() * std::mem::size_of::<MyVertex>()) as GLsizeiptr,
-      buffers.vertex_buffer.vertices.as_ptr() as *const GLvoid,
+  unsafe fn draw<T>(gl: &Gl, state: &mut GlState, buffers: &mut DrawBuffers<T>) {
+    let (vertices, indices) = (buffers.vertex_buffer.vertices.len(), buffers.vertex_buffer.indices.len());
+    debug_assert!(
+      vertices <= buffers.capacity.vertices && indices <= buffers.capacity.indices,
+      "{} {} vertices and {} indices drawn, the buffer is sized for {:?}",
+      std::any::type_name::<T>(),
+      vertices,
+      indices,
+      buffers.capacity,
     );
+    state.bind_vao(gl, buffers.vao);
+    gl.BindBuffer(gl::ARRAY_BUFFER, buffers.vbo);
+    upload(gl, gl::ARRAY_BUFFER, &mut buffers.vbo_allocation, &buffers.vertex_buffer.vertices);
     gl.BindBuffer(gl::ELEMENT_ARRAY_BUFFER, buffers.ebo);
-    gl.BufferSubData(
-      gl::ELEMENT_ARRAY_BUFFER,
-      0,
-      (buffers.vertex_buffer.indices.len() * std::mem::size_of::<u16>()) as GLsizeiptr,
-      buffers.vertex_buffer.indices.as_ptr() as *const GLvoid,
-    );
+    upload(gl, gl::ELEMENT_ARRAY_BUFFER, &mut buffers.ebo_allocation, &buffers.vertex_buffer.indices);
     gl.DrawElements(
       gl::TRIANGLES,
       buffers.vertex_buffer.indices.len() as i32,
-      gl::UNSIGNED_SHORT,
+      gl::UNSIGNED_INT,
       std::ptr::null(),
     );
-    buffers.vertex_buffer.vertices.clear();
-    buffers.vertex_buffer.indices.clear();
   }
 
+  unsafe fn draw_text(
+    gl: &Gl,
+    state: &mut GlState,
+    (vao, vbo, ebo): (GLuint, GLuint, GLuint),
+    (vbo_allocation, ebo_allocation): (&mut GpuAllocation, &mut GpuAllocation),
+    layer: &mut TextLayer,
+  ) {
+    state.bind_texture(gl, 0, layer.atlas_texture);
+    state.bind_vao(gl, vao);
+    gl.BindBuffer(gl::ARRAY_BUFFER, vbo);
+    upload(gl, gl::ARRAY_BUFFER, vbo_allocation, &layer.vertex_buffer);
+    gl.BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
+    upload(gl, gl::ELEMENT_ARRAY_BUFFER, ebo_allocation, &layer.index_buffer);
+
+    gl.DrawElements(
+      gl::TRIANGLES,
+      layer.index_buffer.len() as i32,
+      gl::UNSIGNED_INT,
+      std::ptr::null(),
+    );
+
+    gl.BindBuffer(gl::ARRAY_BUFFER, 0);
+  }
+
+  /// Both text layers in screen space, into whatever framebuffer is bound.
+  unsafe fn draw_texts(
+    gl: &Gl,
+    state: &mut GlState,
+    (text_program, sdf_text_program): (GLuint, GLuint),
+    texts: &mut TextBuffers,
+    poll_errors: &impl Fn(&Gl, &str),
+  ) {
+    let TextBuffers {
+      vao,
+      vbo,
+      ebo,
+      vbo_allocation,
+      ebo_allocation,
+      bitmap,
+      sdf,
+    } = texts;
+    // laid out at SCREEN_RENDER_* and scaled into the viewport with everything else
+    let projection =
+      glam::Mat4::orthographic_rh_gl(0.0, SCREEN_RENDER_WIDTH as f32, 0.0, SCREEN_RENDER_HEIGHT as f32, -10.0, 10.0);
+
+    state.set_blend(gl, BlendMode::Alpha);
+    state.bind_program(gl, text_program);
+    gl.UniformMatrix4fv(
+      gl.GetUniformLocation(text_program, cstr!("uProjection").as_ptr()),
+      1,
+      gl::FALSE,
+      projection.to_cols_array().as_ptr(),
+    );
+    draw_text(gl, state, (*vao, *vbo, *ebo), (vbo_allocation, ebo_allocation), bitmap);
+    poll_errors(gl, "bitmap text");
+
+    state.bind_program(gl, sdf_text_program);
+    gl.UniformMatrix4fv(
+      gl.GetUniformLocation(sdf_text_program, cstr!("uProjection").as_ptr()),
+      1,
+      gl::FALSE,
+      projection.to_cols_array().as_ptr(),
+    );
+    gl.Uniform1f(
+      gl.GetUniformLocation(sdf_text_program, cstr!("uSmoothing").as_ptr()),
+      SDF_SMOOTHING,
+    );
+    gl.Uniform1f(
+      gl.GetUniformLocation(sdf_text_program, cstr!("uOutlineWidth").as_ptr()),
+      SDF_OUTLINE_WIDTH,
+    );
+    gl.Uniform4fv(
+      gl.GetUniformLocation(sdf_text_program, cstr!("uOutlineColor").as_ptr()),
+      1,
+      ColorGl::from(RGB_COLOR_TEXT_OUTLINE).to_array().as_ptr(),
+    );
+    draw_text(gl, state, (*vao, *vbo, *ebo), (vbo_allocation, ebo_allocation), sdf);
+    poll_errors(gl, "sdf text");
+    state.validate(gl, "text");
+  }
+
+
+
+  state.reset_to_known(gl);
   unsafe {
-    gl.BindFramebuffer(gl::FRAMEBUFFER, frame_buffer.fbo);
-    gl.Viewport(0, 0, SCREEN_WIDTH, SCREEN_HEIGHT);
-    gl.Enable(gl::DEPTH_TEST);
+    // the bars, the frame is drawn over the middle
+    gl.BindFramebuffer(gl::FRAMEBUFFER, 0);
+    gl.ClearColor(0.0, 0.0, 0.0, 1.0);
+    gl.Clear(gl::COLOR_BUFFER_BIT);
+
+    match &frame_buffer.target {
+      Some(target) => {
+        gl.BindFramebuffer(gl::FRAMEBUFFER, target.fbo);
+        gl.Viewport(0, 0, target.size.0, target.size.1);
+      }
+      None => {
+        viewport.apply(gl);
+        gl.Enable(gl::SCISSOR_TEST);
+        gl.Scissor(viewport.x, viewport.y, viewport.width, viewport.height);
+      }
+    }
+    state.set_depth(gl, true);
     gl.ClearColor(clear_color.r, clear_color.g, clear_color.b, clear_color.a);
     gl.Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+    gl.Disable(gl::SCISSOR_TEST);
 
     //----------------------SCENE----------------------//
     let Camera {
       camera_pos,
+      previous_pos,
       camera_front,
       camera_up,
       camera_zoom,
       ..
     } = *camera;
+    let camera_pos = previous_pos.lerp(camera_pos, alpha.0);
     let view = glam::Mat4::look_at_rh(camera_pos, camera_pos + camera_front, camera_up);
+    // zooms around the middle of the screen rather than its bottom left corner
+    let center = glam::vec3(SCREEN_WIDTH as f32 / 2.0, SCREEN_HEIGHT as f32 / 2.0, 0.0);
     let projection = glam::Mat4::orthographic_rh_gl(0.0, SCREEN_WIDTH as f32, 0.0, SCREEN_HEIGHT as f32, -100.0, 100.0)
-      * glam::Mat4::from_scale(camera_zoom);
+      * glam::Mat4::from_translation(center)
+      * glam::Mat4::from_scale(camera_zoom)
+      * glam::Mat4::from_translation(-center);
 
-    gl.UseProgram(*scene_program);
+    state.bind_program(gl, *scene_program);
     let mvp_mat = {
       let model = glam::Mat4::from_rotation_z(0.0f32.to_radians());
       projection * view * model
@@ -740,92 +1146,136 @@ pub fn render_gl(gl: &Gl, opengl_ctx: &OpenglCtx, render_state: RenderSystemStat
       mvp_mat.to_cols_array().as_ptr(),
     );
 
-    draw(gl, &mut circles);
-    draw(gl, &mut quads);
-    draw(gl, &mut lines);
-
-    //----------------------SCENE----------------------//
-
-    gl.BindFramebuffer(gl::FRAMEBUFFER, 0);
-    gl.Viewport(0, 0, *w, *h);
-    gl.Disable(gl::DEPTH_TEST);
-    gl.UseProgram(frame_buffer.shader_program);
-    gl.BindVertexArray(frame_buffer.vao);
-    gl.ActiveTexture(gl::TEXTURE0);
-    gl.BindTexture(gl::TEXTURE_2D, frame_buffer.texture2d);
-    gl.DrawArrays(gl::TRIANGLES, 0, 6);
-
-    //----------------------TEXT----------------------//
-    gl.Enable(gl::BLEND);
-    gl.BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
-    gl.UseProgram(*text_program);
-    gl.ActiveTexture(gl::TEXTURE0);
-    gl.BindTexture(gl::TEXTURE_2D, texts.atlas_texture);
-
-    let projection = glam::Mat4::orthographic_rh_gl(0.0, *w as f32, 0.0, *h as f32, -10.0, 10.0);
+    draw(gl, state, &mut circles);
+    poll_errors(gl, "circles");
+    draw(gl, state, &mut quads);
+    poll_errors(gl, "quads");
+    draw(gl, state, &mut lines);
+    poll_errors(gl, "lines");
+
+    // after everything opaque, depth tested against it but not writing, so overlapping particles
+    // blend instead of hiding each other
+    state.set_blend(gl, BlendMode::Alpha);
+    state.set_depth_write(gl, false);
+    draw(gl, state, &mut particles);
+    poll_errors(gl, "particles");
+    state.set_depth_write(gl, true);
+    state.set_blend(gl, BlendMode::Disabled);
+
+    // screen space geometry ignores the camera so it doesn't shake or zoom
+    let screen_projection =
+      glam::Mat4::orthographic_rh_gl(0.0, SCREEN_WIDTH as f32, 0.0, SCREEN_HEIGHT as f32, -100.0, 100.0);
     gl.UniformMatrix4fv(
-      gl.GetUniformLocation(*text_program, cstr!("uProjection").as_ptr()),
+      gl.GetUniformLocation(*scene_program, cstr!("uMVP").as_ptr()),
       1,
       gl::FALSE,
-      projection.to_cols_array().as_ptr(),
+      screen_projection.to_cols_array().as_ptr(),
     );
 
-    gl.BindVertexArray(texts.vao);
-    gl.BindBuffer(gl::ARRAY_BUFFER, texts.vbo);
-    gl.BufferSubData(
-      gl::ARRAY_BUFFER,
-      0,
-      (texts.vertex_buffer.len() * std::mem::size_of::<MyTextVertex>()) as GLsizeiptr,
-      texts.vertex_buffer.as_ptr() as *const GLvoid,
-    );
-    gl.BindBuffer(gl::ELEMENT_ARRAY_BUFFER, texts.ebo);
-    gl.BufferData(
-      gl::ELEMENT_ARRAY_BUFFER,
-      (texts.index_buffer.len() * std::mem::size_of::<u16>()) as GLsizeiptr,
-      texts.index_buffer.as_ptr() as *const GLvoid,
-      gl::DYNAMIC_DRAW,
-    );
+    draw(gl, state, &mut screen);
+    poll_errors(gl, "screen");
+    state.validate(gl, "scene");
 
-    gl.DrawElements(
-      gl::TRIANGLES,
-      texts.index_buffer.len() as i32,
-      gl::UNSIGNED_SHORT,
-      std::ptr::null(),
-    );
+    //----------------------SCENE----------------------//
+
+    state.set_depth(gl, false);
+    let programs = (*text_program, *sdf_text_program);
+    // still at the low res pixel grid, and curved and scanned with everything else
+    let text_in_scene = post_fx.text_in_scene && frame_buffer.target.is_some();
+    if text_in_scene {
+      draw_texts(gl, state, programs, &mut texts, &poll_errors);
+    }
+
+    if let Some(target) = &frame_buffer.target {
+      gl.BindFramebuffer(gl::FRAMEBUFFER, 0);
+      viewport.apply(gl);
+      state.set_blend(gl, BlendMode::Disabled);
+      state.bind_program(gl, frame_buffer.shader_program);
+      let (scanline_intensity, curvature, aberration) = post_fx.uniforms();
+      gl.Uniform1f(
+        gl.GetUniformLocation(frame_buffer.shader_program, cstr!("uScanlineIntensity").as_ptr()),
+        scanline_intensity,
+      );
+      gl.Uniform1f(
+        gl.GetUniformLocation(frame_buffer.shader_program, cstr!("uCurvature").as_ptr()),
+        curvature,
+      );
+      gl.Uniform1f(
+        gl.GetUniformLocation(frame_buffer.shader_program, cstr!("uAberration").as_ptr()),
+        aberration,
+      );
+      let distortion_uniforms = distortions
+        .sources
+        .iter()
+        .flat_map(|source| [source.position.x, source.position.y, source.radius, source.current_strength()])
+        .collect::<Vec<_>>();
+      gl.Uniform1i(
+        gl.GetUniformLocation(frame_buffer.shader_program, cstr!("uDistortionCount").as_ptr()),
+        distortions.sources.len() as i32,
+      );
+      if !distortions.sources.is_empty() {
+        gl.Uniform2f(
+          gl.GetUniformLocation(frame_buffer.shader_program, cstr!("uResolution").as_ptr()),
+          SCREEN_WIDTH as f32,
+          SCREEN_HEIGHT as f32,
+        );
+        gl.Uniform1f(
+          gl.GetUniformLocation(frame_buffer.shader_program, cstr!("uTime").as_ptr()),
+          distortions.elapsed,
+        );
+        gl.Uniform4fv(
+          gl.GetUniformLocation(frame_buffer.shader_program, cstr!("uDistortions").as_ptr()),
+          distortions.sources.len() as i32,
+          distortion_uniforms.as_ptr(),
+        );
+      }
+      state.bind_vao(gl, frame_buffer.vao);
+      state.bind_texture(gl, 0, target.texture2d);
+      gl.DrawArrays(gl::TRIANGLES, 0, 6);
+      poll_errors(gl, "low res");
+      state.validate(gl, "low res");
+    }
+
+    if !text_in_scene {
+      draw_texts(gl, state, programs, &mut texts, &poll_errors);
+    }
 
-    gl.BindBuffer(gl::ARRAY_BUFFER, 0);
-    gl.BindBuffer(gl::ELEMENT_ARRAY_BUFFER, 0);
-    gl.BindVertexArray(0);
-    gl.BindTexture(gl::TEXTURE_2D, 0);
-    gl.Disable(gl::BLEND);
-    texts.vertex_buffer.clear();
-    texts.index_buffer.clear();
-    //----------------------TEXT----------------------//
   }
   Ok(())
 }
 
 pub fn delete(gl: &Gl, opengl_ctx: &OpenglCtx, render_state: RenderSystemState) {
-  let (_, circles, quads, lines, texts) = render_state;
+  let (_, circles, quads, lines, particles, screen, texts, _, _, _, _) = render_state;
   unsafe {
     gl.DeleteVertexArrays(1, &opengl_ctx.frame_buffer.vao);
     gl.DeleteVertexArrays(1, &circles.vao);
     gl.DeleteVertexArrays(1, &quads.vao);
     gl.DeleteVertexArrays(1, &lines.vao);
+    gl.DeleteVertexArrays(1, &particles.vao);
+    gl.DeleteVertexArrays(1, &screen.vao);
     gl.DeleteVertexArrays(1, &texts.vao);
     gl.DeleteBuffers(1, &opengl_ctx.frame_buffer.vbo);
-    gl.DeleteBuffers(1, &opengl_ctx.frame_buffer.texture2d);
     gl.DeleteBuffers(1, &circles.vbo);
     gl.DeleteBuffers(1, &quads.vbo);
     gl.DeleteBuffers(1, &lines.vbo);
+    gl.DeleteBuffers(1, &particles.vbo);
+    gl.DeleteBuffers(1, &screen.vbo);
     gl.DeleteBuffers(1, &texts.vbo);
     gl.DeleteBuffers(1, &circles.ebo);
     gl.DeleteBuffers(1, &quads.ebo);
     gl.DeleteBuffers(1, &lines.ebo);
-    gl.DeleteBuffers(1, &texts.atlas_texture);
+    gl.DeleteBuffers(1, &particles.ebo);
+    gl.DeleteBuffers(1, &screen.ebo);
+    gl.DeleteTextures(1, &texts.bitmap.atlas_texture);
+    gl.DeleteTextures(1, &texts.sdf.atlas_texture);
     gl.DeleteProgram(opengl_ctx.frame_buffer.shader_program);
     gl.DeleteProgram(opengl_ctx.scene_program);
     gl.DeleteProgram(opengl_ctx.text_program);
-    gl.DeleteFramebuffers(1, &opengl_ctx.frame_buffer.fbo);
+    gl.DeleteProgram(opengl_ctx.sdf_text_program);
+    if let Some(target) = &opengl_ctx.frame_buffer.target {
+      gl.DeleteFramebuffers(1, &target.fbo);
+      gl.DeleteTextures(1, &target.texture2d);
+      gl.DeleteRenderbuffers(1, &target.rbo);
+    }
   }
 }
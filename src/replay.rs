@@ -0,0 +1,302 @@
+use crate::{
+  audit::{Checkpoint, Mismatch, PARTS},
+  components::BoundsMode,
+  error::Error,
+  input::InputSource,
+  modifiers::Modifiers,
+  resources::{GameState, GamepadState, KeyBindings, MouseInput},
+};
+use bevy_ecs::prelude::*;
+use sdl2::keyboard::Keycode;
+use std::{collections::HashSet, io::Write, time::Duration};
+
+const MAGIC: &[u8; 4] = b"BRPL";
+const VERSION: u32 = 7;
+const HEADER_LEN: usize = 4 + 4 + 8 + 2 + 20 + 8 + 4 + 4;
+const FRAME_LEN: usize = 4 + 8 + 1 + 4 + 4;
+const CHECKPOINT_LEN: usize = 8 + 8 * PARTS.len();
+
+/// The input of one simulation tick, everything main() feeds the world besides the seed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplayFrame {
+  pub dt: Duration,
+  /// One bit per source of [`Replay::sources`].
+  pub held: u64,
+  pub paused: bool,
+  pub stick: glam::Vec2,
+}
+
+/// The input a [`ReplayFrame`] feeds the world, what main() polls for a tick.
+pub struct FrameInput {
+  pub keycodes: HashSet<Keycode>,
+  pub mouse: MouseInput,
+  pub gamepad: GamepadState,
+  pub state: GameState,
+}
+
+/// A run as the seed, the options that change the simulation and the input of every tick. Records
+/// during a normal run and feeds the world with `--replay`, see [`Replay::write`] for the file layout.
+#[derive(Debug, Resource)]
+pub struct Replay {
+  pub seed: u64,
+  pub bounds_mode: BoundsMode,
+  pub bindings: KeyBindings,
+  pub pure_rng: bool,
+  /// The build of the run, playback uses it instead of the build on disk.
+  pub modifiers: Modifiers,
+  pub frames: Vec<ReplayFrame>,
+  /// [`crate::audit::Snapshot::hash`] after the last frame, playback checks it got there too.
+  pub final_hash: u64,
+  /// The state every [`crate::environment::AUDIT_INTERVAL`] ticks and after the last frame, playback
+  /// compares against them to find where it went its own way.
+  pub checkpoints: Vec<Checkpoint>,
+  /// The next frame to play, `None` while recording.
+  cursor: Option<usize>,
+  /// Every source of `bindings` and the restart key, the bits of [`ReplayFrame::held`]. Other input only
+  /// toggles overlays and isn't recorded.
+  sources: Vec<InputSource>,
+}
+
+impl Replay {
+  pub fn record(
+    seed: u64,
+    bounds_mode: BoundsMode,
+    bindings: KeyBindings,
+    pure_rng: bool,
+    modifiers: Modifiers,
+  ) -> Self {
+    Self {
+      seed,
+      bounds_mode,
+      sources: replay_sources(&bindings),
+      bindings,
+      pure_rng,
+      modifiers,
+      frames: Vec::new(),
+      final_hash: 0,
+      checkpoints: Vec::new(),
+      cursor: None,
+    }
+  }
+
+  pub fn capture(
+    &self,
+    dt: Duration,
+    keycodes: &HashSet<Keycode>,
+    mouse: &MouseInput,
+    gamepad: &GamepadState,
+    state: GameState,
+  ) -> ReplayFrame {
+    let held = self
+      .sources
+      .iter()
+      .enumerate()
+      .filter(|(_, source)| source.is_held(keycodes, mouse, gamepad))
+      .fold(0, |mask, (i, _)| mask | 1 << i);
+    ReplayFrame {
+      dt,
+      held,
+      paused: state == GameState::Paused,
+      stick: gamepad.stick,
+    }
+  }
+
+  /// What `frame` was recorded from, as far as the simulation reads it.
+  pub fn input(&self, frame: &ReplayFrame) -> FrameInput {
+    let mut input = FrameInput {
+      keycodes: HashSet::new(),
+      mouse: MouseInput::default(),
+      gamepad: GamepadState {
+        stick: frame.stick,
+        buttons: HashSet::new(),
+      },
+      state: if frame.paused { GameState::Paused } else { GameState::Running },
+    };
+    let held = self.sources.iter().enumerate().filter(|(i, _)| frame.held & 1 << i != 0);
+    for (_, source) in held {
+      match *source {
+        InputSource::Key(keycode) => {
+          input.keycodes.insert(keycode);
+        }
+        InputSource::MouseButton(button) => {
+          input.mouse.buttons.insert(button);
+        }
+        InputSource::WheelUp => input.mouse.wheel_up = true,
+        InputSource::WheelDown => input.mouse.wheel_down = true,
+        InputSource::ControllerButton(button) => {
+          input.gamepad.buttons.insert(button);
+        }
+      }
+    }
+    input
+  }
+
+  pub fn is_playing(&self) -> bool {
+    self.cursor.is_some()
+  }
+
+  /// How far playback got, and how long the whole replay is.
+  pub fn progress(&self) -> (Duration, Duration) {
+    let played = self.cursor.unwrap_or_default();
+    let elapsed = self.frames[..played].iter().map(|frame| frame.dt).sum();
+    (elapsed, self.frames.iter().map(|frame| frame.dt).sum())
+  }
+
+  /// Ticks played or recorded so far.
+  pub fn tick(&self) -> u64 {
+    self.cursor.unwrap_or(self.frames.len()) as u64
+  }
+
+  /// Keeps `checkpoint` while recording. While playing compares it with the recorded one of the same
+  /// tick, if there is one.
+  pub fn check(&mut self, checkpoint: Checkpoint) -> Option<Mismatch> {
+    let recorded = self.checkpoints.iter().rfind(|recorded| recorded.tick == checkpoint.tick);
+    if self.is_playing() {
+      recorded?.diff(&checkpoint)
+    } else {
+      if recorded.is_none() {
+        self.checkpoints.push(checkpoint);
+      }
+      None
+    }
+  }
+
+  /// The input of the next tick, `None` once all are played.
+  pub fn next_frame(&mut self) -> Option<ReplayFrame> {
+    let cursor = self.cursor.as_mut()?;
+    let frame = self.frames.get(*cursor).copied()?;
+    *cursor += 1;
+    Some(frame)
+  }
+
+  /// All integers are little endian: the magic, a u32 version, the u64 seed, a u8 each for the bounds
+  /// mode and `--pure-rng`, the build as the movement speed, boost max and projectile speed f32, the ammo
+  /// max i32 and the pickup spawn rate f32, the u64 final hash, a u32 frame count and the u32 length of
+  /// the bindings. Then the bindings as [`KeyBindings::parse`] reads them. Per frame a u32 of nanoseconds,
+  /// the u64 mask of held sources, a u8 pause flag and the stick as two f32. After the frames a u32
+  /// checkpoint count, per checkpoint the u64 tick and a u64 digest for each of [`PARTS`].
+  pub fn write(&self, out: &mut impl Write) -> std::io::Result<()> {
+    out.write_all(MAGIC)?;
+    out.write_all(&VERSION.to_le_bytes())?;
+    out.write_all(&self.seed.to_le_bytes())?;
+    out.write_all(&[self.bounds_mode as u8, self.pure_rng as u8])?;
+    out.write_all(&self.modifiers.movement_speed_mult.to_le_bytes())?;
+    out.write_all(&self.modifiers.boost_max_add.to_le_bytes())?;
+    out.write_all(&self.modifiers.projectile_speed_mult.to_le_bytes())?;
+    out.write_all(&self.modifiers.ammo_max_add.to_le_bytes())?;
+    out.write_all(&self.modifiers.pickup_spawn_rate_mult.to_le_bytes())?;
+    out.write_all(&self.final_hash.to_le_bytes())?;
+    out.write_all(&(self.frames.len() as u32).to_le_bytes())?;
+    let bindings = self.bindings.to_string();
+    out.write_all(&(bindings.len() as u32).to_le_bytes())?;
+    out.write_all(bindings.as_bytes())?;
+    for frame in &self.frames {
+      out.write_all(&(frame.dt.as_nanos() as u32).to_le_bytes())?;
+      out.write_all(&frame.held.to_le_bytes())?;
+      out.write_all(&[frame.paused as u8])?;
+      out.write_all(&frame.stick.x.to_le_bytes())?;
+      out.write_all(&frame.stick.y.to_le_bytes())?;
+    }
+    out.write_all(&(self.checkpoints.len() as u32).to_le_bytes())?;
+    for checkpoint in &self.checkpoints {
+      out.write_all(&checkpoint.tick.to_le_bytes())?;
+      for digest in checkpoint.digests {
+        out.write_all(&digest.to_le_bytes())?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Reads a replay written by [`Replay::write`], ready to play from the first frame.
+  pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+    let invalid = |reason| Error::InvalidReplay { reason };
+    if bytes.len() < HEADER_LEN {
+      return Err(invalid("truncated header"));
+    }
+    let (header, body) = bytes.split_at(HEADER_LEN);
+    let u32_at = |at: usize| u32::from_le_bytes(header[at..at + 4].try_into().unwrap());
+    let u64_at = |at: usize| u64::from_le_bytes(header[at..at + 8].try_into().unwrap());
+    let f32_at = |at: usize| f32::from_le_bytes(header[at..at + 4].try_into().unwrap());
+    if &header[..4] != MAGIC {
+      return Err(invalid("not a replay"));
+    }
+    if u32_at(4) != VERSION {
+      return Err(invalid("unsupported version"));
+    }
+    let bounds_mode = match header[16] {
+      0 => BoundsMode::Wrap,
+      1 => BoundsMode::Clamp,
+      2 => BoundsMode::Free,
+      _ => return Err(invalid("unknown bounds mode")),
+    };
+    let modifiers = Modifiers {
+      movement_speed_mult: f32_at(18),
+      boost_max_add: f32_at(22),
+      projectile_speed_mult: f32_at(26),
+      ammo_max_add: i32::from_le_bytes(header[30..34].try_into().unwrap()),
+      pickup_spawn_rate_mult: f32_at(34),
+    };
+    modifiers.validate().map_err(|_| invalid("invalid build"))?;
+    let bindings_len = u32_at(50) as usize;
+    if body.len() < bindings_len {
+      return Err(invalid("truncated bindings"));
+    }
+    let (bindings, body) = body.split_at(bindings_len);
+    let bindings = std::str::from_utf8(bindings)
+      .ok()
+      .and_then(|text| KeyBindings::parse(text).ok())
+      .ok_or_else(|| invalid("invalid bindings"))?;
+    let frames_len = u32_at(46) as usize * FRAME_LEN;
+    if body.len() < frames_len + 4 {
+      return Err(invalid("truncated frames"));
+    }
+    let (frames, checkpoints) = body.split_at(frames_len);
+    let (count, checkpoints) = checkpoints.split_at(4);
+    if checkpoints.len() != u32::from_le_bytes(count.try_into().unwrap()) as usize * CHECKPOINT_LEN {
+      return Err(invalid("checkpoint count doesn't match the length"));
+    }
+
+    let u64_in = |bytes: &[u8], at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+    let checkpoints = checkpoints
+      .chunks_exact(CHECKPOINT_LEN)
+      .map(|checkpoint| Checkpoint {
+        tick: u64_in(checkpoint, 0),
+        digests: std::array::from_fn(|part| u64_in(checkpoint, 8 + 8 * part)),
+      })
+      .collect();
+    let frames = frames
+      .chunks_exact(FRAME_LEN)
+      .map(|frame| {
+        let f32_at = |at: usize| f32::from_le_bytes(frame[at..at + 4].try_into().unwrap());
+        ReplayFrame {
+          dt: Duration::from_nanos(u32::from_le_bytes(frame[..4].try_into().unwrap()) as u64),
+          held: u64::from_le_bytes(frame[4..12].try_into().unwrap()),
+          paused: frame[12] != 0,
+          stick: glam::vec2(f32_at(13), f32_at(17)),
+        }
+      })
+      .collect();
+
+    Ok(Self {
+      seed: u64_at(8),
+      bounds_mode,
+      sources: replay_sources(&bindings),
+      bindings,
+      pure_rng: header[17] != 0,
+      modifiers,
+      frames,
+      final_hash: u64_at(38),
+      checkpoints,
+      cursor: Some(0),
+    })
+  }
+}
+
+/// The sources a replay of `bindings` records, R restarts after the last player died.
+fn replay_sources(bindings: &KeyBindings) -> Vec<InputSource> {
+  let mut sources = bindings.sources();
+  if !sources.contains(&InputSource::Key(Keycode::R)) {
+    sources.push(InputSource::Key(Keycode::R));
+  }
+  sources
+}
@@ -1,99 +1,347 @@
 use crate::{
   color::ColorGl,
-  render::{gl::types::*, MyTextVertex, MyVertex},
+  components::{Active, GameScope, PlayerId, ScopeTag, SpawnSeq},
+  easings::ease_in_out_cubic,
+  environment::{
+    BOMB_CHARGE_SCORE, BOMB_MAX_CHARGES, DISTORTION_DURATION, FLASH_GUARD_CLAMP_DURATION, FLASH_GUARD_MAX_FLASHES,
+    FLASH_GUARD_WINDOW, FRAME_STATS_WINDOW, HEATMAP_COLUMNS, HEATMAP_ROWS, MAX_DISTORTIONS, MAX_SPAWN_DENSITY_SCALE,
+    PITY_MAX_RATE, PITY_RAMP, PITY_SATURATED_RATE, PITY_THRESHOLD, POST_FX_HEAVY, POST_FX_SUBTLE, PROJECTILE_INTERVAL,
+    RGB_COLOR_FLASH_DEATH, SCREEN_HEIGHT, SCREEN_WIDTH, SHAKE_FREQUENCY, SHAKE_MAX_ACTIVE, TRAIL_INTERVAL,
+    ZOOM_PUNCH_RISE,
+  },
+  events::{ShakeRequest, SlowMotion},
+  glyphs::{build_path, GlyphId},
+  error::{find_asset, Error},
+  input::{
+    merge_stick, resolve, Action, InputSource, PlayerInput, BINDINGS, CO_OP_BINDINGS, PAD_BINDINGS, WASD_BINDINGS,
+  },
+  input_display::InputHistory,
+  math::{density_scale, pity_rate},
+  modifiers::Modifiers,
+  output_files::write_atomic,
+  profiles::Profile,
+  render::{gl::types::*, MyTextVertex, MyVertex, WithTransformColor},
+  units::WorldPos,
+};
+use bevy_ecs::prelude::{Bundle, Commands, Entity, Resource};
+use lyon::{
+  math::{Box2D, Point},
+  path::PathEvent,
+  tessellation::{
+    geometry_builder::simple_builder, FillGeometryBuilder, FillOptions, FillTessellator, StrokeGeometryBuilder,
+    StrokeOptions, StrokeTessellator, TessellationResult, VertexBuffers,
+  },
 };
-use bevy_ecs::prelude::Resource;
-use lyon::tessellation::{FillTessellator, StrokeTessellator, VertexBuffers};
 use rand::rngs::SmallRng;
-use sdl2::keyboard::Keycode;
+use sdl2::{controller::Button, keyboard::Keycode};
 use std::{
-  collections::{HashMap, HashSet},
+  collections::{HashMap, HashSet, VecDeque},
+  fmt,
+  io::Write,
   marker::PhantomData,
   ops::{Deref, DerefMut},
+  path::PathBuf,
   time::Duration,
 };
 
 #[derive(Debug, Resource)]
 pub struct Camera {
   pub camera_pos: glam::Vec3,
+  /// `camera_pos` at the end of the tick before, the shake is interpolated like the entities.
+  pub previous_pos: glam::Vec3,
   pub camera_front: glam::Vec3,
   pub camera_up: glam::Vec3,
   pub camera_zoom: glam::Vec3,
-  pub camera_speed: f32,
 }
 
 impl Default for Camera {
   fn default() -> Self {
     Camera {
       camera_pos: glam::Vec3::new(0.0, 0.0, 3.0),
+      previous_pos: glam::Vec3::new(0.0, 0.0, 3.0),
       camera_front: glam::Vec3::new(0.0, 0.0, -1.0),
       camera_up: glam::Vec3::new(0.0, 1.0, 0.0),
       camera_zoom: glam::Vec3::new(1.0, 1.0, 1.0),
-      camera_speed: 2.5,
     }
   }
 }
 
+/// One shake playing, with noise samples for just its own duration.
+#[derive(Debug)]
+struct ActiveShake {
+  request: ShakeRequest,
+  time: f32,
+  samples: Vec<glam::Vec2>,
+}
+
+impl ActiveShake {
+  fn remaining(&self) -> f32 {
+    self.request.duration - self.time
+  }
+
+  /// What is left of the amplitude, fading out towards the end.
+  fn envelope(&self) -> f32 {
+    self.remaining().max(0.0) / self.request.duration * self.request.amplitude
+  }
+
+  /// Interpolated between the samples and fading out towards the end.
+  fn offset(&self) -> glam::Vec2 {
+    let s = self.time * SHAKE_FREQUENCY;
+    let s0 = s.floor();
+    let sample = |n: f32| self.samples.get(n as usize).copied().unwrap_or_default();
+    sample(s0).lerp(sample(s0 + 1.0), s - s0) * self.envelope()
+  }
+}
+
+/// Every camera shake playing, advanced in raw time by [`crate::systems::camera_shake_system`]. The
+/// offsets of overlapping shakes add up.
 #[derive(Debug, Resource)]
 pub struct Shake {
-  pub is_shaking: bool,
-  pub duration: f32,
-  pub frequency: f32,
+  active: Vec<ActiveShake>,
+  rng: SmallRng,
+}
+
+/// A quick zoom in and back out, eased both ways and advanced in raw time like [`Shake`]. A punch
+/// landing during another doesn't stack, the stronger of the two plays.
+#[derive(Debug, Default, Resource)]
+pub struct ZoomPunch {
+  /// Extra zoom at the peak, 0.05 zooms to 1.05.
   pub amplitude: f32,
+  pub duration: f32,
   pub time: f32,
-  pub samples_x: Vec<f32>,
-  pub samples_y: Vec<f32>,
 }
 
+impl ZoomPunch {
+  /// Replaces the current punch when `amplitude` is at least what is left of it. The new one starts
+  /// part way up its rise, so the zoom doesn't dip first.
+  pub fn punch(&mut self, amplitude: f32, duration: f32) {
+    let current = self.zoom() - 1.0;
+    if amplitude < current {
+      return;
+    }
+    self.amplitude = amplitude;
+    self.duration = duration;
+    self.time = ZOOM_PUNCH_RISE * current / amplitude;
+  }
+
+  pub fn zoom(&self) -> f32 {
+    if self.time >= self.duration {
+      return 1.0;
+    }
+    let envelope = if self.time < ZOOM_PUNCH_RISE {
+      ease_in_out_cubic(self.time / ZOOM_PUNCH_RISE)
+    } else {
+      1.0 - ease_in_out_cubic((self.time - ZOOM_PUNCH_RISE) / (self.duration - ZOOM_PUNCH_RISE))
+    };
+    1.0 + self.amplitude * envelope
+  }
+}
+
+/// Where the scene is drawn before it reaches the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FramebufferMode {
+  /// The low resolution framebuffer, with the formats the driver accepted.
+  LowRes { color: &'static str, depth: &'static str },
+  /// Straight into the window at native resolution, without pixelation or distortion.
+  Direct,
+}
+
+impl fmt::Display for FramebufferMode {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      FramebufferMode::LowRes { color, depth } => write!(f, "{} color and {} depth", color, depth),
+      FramebufferMode::Direct => write!(f, "no framebuffer"),
+    }
+  }
+}
+
+/// What the renderer fell back to on this driver.
+#[derive(Debug, Clone, Copy, Resource)]
+pub struct RendererCaps {
+  pub framebuffer: FramebufferMode,
+}
+
+/// Ticked in raw time, the default finishes on its first tick without showing.
 #[derive(Debug, Resource)]
 pub struct Flash {
-  pub frame_cnt: u8,
-  pub is_flashing: bool,
+  pub timer: Timer,
+  pub color: ColorGl,
 }
 
 impl Default for Flash {
   fn default() -> Self {
     Self {
-      frame_cnt: 4,
-      is_flashing: false,
+      timer: Timer::default(),
+      color: ColorGl::from(RGB_COLOR_FLASH_DEATH),
     }
   }
 }
 
-impl Default for Shake {
+/// Counts bright full screen transitions in raw time. When more than `max_flashes` land within
+/// `window` seconds the brightest output is clamped for `clamp_duration` seconds, whatever the
+/// settings say. It only ever dims, gameplay relevant visuals stay visible.
+#[derive(Debug, Resource)]
+pub struct FlashGuard {
+  pub window: f32,
+  pub max_flashes: usize,
+  pub clamp_duration: f32,
+  pub activations: u32,
+  now: f32,
+  flashes: VecDeque<f32>,
+  clamped_until: Option<f32>,
+}
+
+impl Default for FlashGuard {
   fn default() -> Self {
-    use rand::{Rng, SeedableRng};
+    Self {
+      window: FLASH_GUARD_WINDOW,
+      max_flashes: FLASH_GUARD_MAX_FLASHES,
+      clamp_duration: FLASH_GUARD_CLAMP_DURATION,
+      activations: 0,
+      now: 0.0,
+      flashes: VecDeque::new(),
+      clamped_until: None,
+    }
+  }
+}
+
+impl FlashGuard {
+  pub fn tick(&mut self, dt: f32) {
+    self.now += dt;
+    while self.flashes.front().is_some_and(|&at| self.now - at > self.window) {
+      self.flashes.pop_front();
+    }
+    if self.clamped_until.is_some_and(|until| self.now >= until) {
+      self.clamped_until = None;
+    }
+  }
+
+  /// Records a bright transition, returns whether it may be shown at full intensity.
+  pub fn flash(&mut self) -> bool {
+    self.flashes.push_back(self.now);
+    if self.flashes.len() > self.max_flashes {
+      if self.clamped_until.is_none() {
+        self.activations += 1;
+      }
+      self.clamped_until = Some(self.now + self.clamp_duration);
+    }
+    !self.is_clamped()
+  }
+
+  pub fn is_clamped(&self) -> bool {
+    self.clamped_until.is_some()
+  }
+}
 
-    let duration = 0.6;
-    let frequency = 60.0;
-    let amplitude = 10.0;
-    let sample_count = (duration * frequency) as usize;
-    let mut rng = SmallRng::from_entropy();
-    let samples_x = (0..sample_count).map(|_| rng.gen_range(0.0..1.0) * 2.0 - 1.0).collect();
-    let samples_y = (0..sample_count).map(|_| rng.gen_range(0.0..1.0) * 2.0 - 1.0).collect();
+impl Shake {
+  /// The noise comes from the run's seed, so a replay shakes the same.
+  pub fn new(seed: u64) -> Self {
+    use rand::SeedableRng;
 
     Shake {
-      is_shaking: false,
-      duration,
-      frequency,
-      amplitude,
+      active: Vec::new(),
+      rng: SmallRng::seed_from_u64(seed),
+    }
+  }
+
+  /// Starts `request` with noise of its own. With [`SHAKE_MAX_ACTIVE`] playing, the one closest to
+  /// its end makes room.
+  pub fn start(&mut self, request: ShakeRequest) {
+    use rand::Rng;
+
+    if self.active.len() >= SHAKE_MAX_ACTIVE {
+      let closest = self
+        .active
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.remaining().total_cmp(&b.remaining()))
+        .map(|(i, _)| i);
+      if let Some(i) = closest {
+        self.active.swap_remove(i);
+      }
+    }
+
+    let sample_count = (request.duration * SHAKE_FREQUENCY).ceil() as usize;
+    let rng = &mut self.rng;
+    let samples = (0..sample_count)
+      .map(|_| glam::vec2(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0)))
+      .collect();
+    self.active.push(ActiveShake {
+      request,
       time: 0.0,
-      samples_x,
-      samples_y,
+      samples,
+    });
+  }
+
+  /// Advances every shake by `dt` raw seconds and drops the finished ones. The sum of what is left,
+  /// zero once nothing shakes.
+  pub fn advance(&mut self, dt: f32) -> glam::Vec2 {
+    for shake in &mut self.active {
+      shake.time += dt;
     }
+    self.active.retain(|shake| shake.remaining() > 0.0);
+    self.active.iter().map(ActiveShake::offset).sum()
+  }
+
+  pub fn is_shaking(&self) -> bool {
+    !self.active.is_empty()
+  }
+
+  /// The amplitudes of every shake playing added up, without the noise.
+  pub fn amplitude(&self) -> f32 {
+    self.active.iter().map(ActiveShake::envelope).sum()
+  }
+
+  pub fn stop(&mut self) {
+    self.active.clear();
   }
 }
 
 pub type CircleGeometry = DrawBuffers<Circle>;
 pub type QuadGeometry = DrawBuffers<Quad>;
 pub type LineGeometry = DrawBuffers<Line>;
+pub type ScreenGeometry = DrawBuffers<Screen>;
+/// World space geometry with alpha, drawn blended after everything opaque.
+pub type ParticleGeometry = DrawBuffers<Particle>;
+
+/// The vertices and indices a draw buffer is sized for, one pair of constants per geometry in
+/// environment.rs.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BufferCapacity {
+  pub vertices: usize,
+  pub indices: usize,
+}
+
+/// Bytes allocated on the GPU for a vbo or ebo. Drawing grows the allocation when a frame needs more,
+/// the [`crate::memory::MemorySteward`] shrinks it back once it stays oversized.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GpuAllocation {
+  pub bytes: usize,
+  /// The most bytes one upload used since the steward last looked.
+  pub used: usize,
+  /// Set by shrinking, the next upload allocates `bytes` anew.
+  pub stale: bool,
+}
+
+impl GpuAllocation {
+  pub fn new(bytes: usize) -> Self {
+    Self {
+      bytes,
+      ..Default::default()
+    }
+  }
+}
 
 #[derive(Debug, Resource)]
 pub struct DrawBuffers<Geometry> {
   pub vao: GLuint,
   pub vbo: GLuint,
   pub ebo: GLuint,
-  pub vertex_buffer: VertexBuffers<MyVertex, u16>,
+  /// What a frame is expected to fit in, debug builds assert on it before drawing.
+  pub capacity: BufferCapacity,
+  pub vbo_allocation: GpuAllocation,
+  pub ebo_allocation: GpuAllocation,
+  pub vertex_buffer: VertexBuffers<MyVertex, u32>,
   _marker: PhantomData<Geometry>,
 }
 
@@ -103,10 +351,18 @@ impl<T> DrawBuffers<T> {
       vao,
       vbo,
       ebo,
+      capacity: BufferCapacity::default(),
+      vbo_allocation: GpuAllocation::default(),
+      ebo_allocation: GpuAllocation::default(),
       vertex_buffer: VertexBuffers::new(),
-      _marker: PhantomData::<T>::default(),
+      _marker: PhantomData::<T>,
     }
   }
+
+  pub fn clear(&mut self) {
+    self.vertex_buffer.vertices.clear();
+    self.vertex_buffer.indices.clear();
+  }
 }
 
 pub struct Character {
@@ -119,26 +375,74 @@ pub struct Character {
   pub advance: f32,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextStyle {
+  Bitmap,
+  /// Distance field glyphs with an outline, stays crisp when scaled up.
+  Sdf,
+}
+
+/// One glyph atlas and the text queued against it this frame.
+#[derive(Default)]
+pub struct TextLayer {
+  pub atlas_texture: GLuint,
+  pub characters: HashMap<char, Character>,
+  pub vertex_buffer: Vec<MyTextVertex>,
+  pub index_buffer: Vec<u32>,
+}
+
 #[derive(Resource)]
 pub struct TextBuffers {
   pub vao: GLuint,
   pub vbo: GLuint,
   pub ebo: GLuint,
-  pub atlas_texture: GLuint,
-  pub characters: HashMap<char, Character>,
-  pub vertex_buffer: Vec<MyTextVertex>,
-  pub index_buffer: Vec<u16>,
+  /// Both layers share `vbo` and `ebo` and grow them as needed.
+  pub vbo_allocation: GpuAllocation,
+  pub ebo_allocation: GpuAllocation,
+  pub bitmap: TextLayer,
+  pub sdf: TextLayer,
 }
 
 impl TextBuffers {
-  pub fn build_text(&mut self, text: &str, mut x: f32, y: f32, scale: f32, color: ColorGl) {
-    let mut offset = self.vertex_buffer.len() as u16;
+  /// Distance field text is drawn from the bitmap atlas until the distance field atlas is built.
+  pub fn build_text(&mut self, text: &str, x: f32, y: f32, scale: f32, color: ColorGl, style: TextStyle) {
+    match style {
+      TextStyle::Sdf if !self.sdf.characters.is_empty() => self.sdf.build_text(text, x, y, scale, color),
+      _ => self.bitmap.build_text(text, x, y, scale, color),
+    }
+  }
+
+  /// How far [`Self::build_text`] would advance for `text`.
+  pub fn width(&self, text: &str, scale: f32, style: TextStyle) -> f32 {
+    let layer = match style {
+      TextStyle::Sdf if !self.sdf.characters.is_empty() => &self.sdf,
+      _ => &self.bitmap,
+    };
+    text.chars().filter_map(|c| layer.characters.get(&c)).map(|ch| ch.advance * scale).sum()
+  }
+
+  pub fn clear(&mut self) {
+    self.bitmap.clear();
+    self.sdf.clear();
+  }
+}
+
+impl TextLayer {
+  pub fn clear(&mut self) {
+    self.vertex_buffer.clear();
+    self.index_buffer.clear();
+  }
+
+  fn build_text(&mut self, text: &str, mut x: f32, y: f32, scale: f32, color: ColorGl) {
+    let mut offset = self.vertex_buffer.len() as u32;
     for c in text.chars() {
-      let ch = self.characters.get(&c).unwrap();
-      let x_pos = (x + ch.bearing.x as f32 * scale).round();
+      let Some(ch) = self.characters.get(&c) else {
+        continue;
+      };
+      let x_pos = (x + ch.bearing.x * scale).round();
       let y_pos = (y - (ch.height - ch.bearing.y) * scale).round();
-      let w = ch.width as f32 * scale;
-      let h = ch.height as f32 * scale;
+      let w = ch.width * scale;
+      let h = ch.height * scale;
       let mut v = (0..4usize)
           .map(|i| {
             MyTextVertex {
@@ -155,9 +459,9 @@ impl TextBuffers {
           .collect::<Vec<_>>();
 
       let mut indices = vec![
-        0u16 + offset, // top right
-        1 + offset,    // bottom right
-        3 + offset,    // top left
+        offset,     // top right
+        1 + offset, // bottom right
+        3 + offset, // top left
         //
         1 + offset, // bottom right
         2 + offset, // bottom left
@@ -175,9 +479,6 @@ impl TextBuffers {
 #[derive(Debug, Resource)]
 pub struct Circle;
 
-#[derive(Debug, Resource)]
-pub struct Rectangle;
-
 #[derive(Debug, Resource)]
 pub struct Quad;
 
@@ -185,62 +486,644 @@ pub struct Quad;
 pub struct Line;
 
 #[derive(Debug, Resource)]
-pub struct EntitySpawnTimer {
+pub struct Screen;
+
+#[derive(Debug, Resource)]
+pub struct Particle;
+
+#[derive(Debug, Resource)]
+pub struct Minimap {
+  pub visible: bool,
+}
+
+impl Default for Minimap {
+  fn default() -> Self {
+    Self { visible: true }
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Distortion {
+  pub position: glam::Vec2,
+  pub radius: f32,
+  /// Peak sample offset in low res pixels.
+  pub strength: f32,
+  pub age: f32,
+}
+
+impl Distortion {
+  pub fn current_strength(&self) -> f32 {
+    self.strength * (1.0 - self.age / DISTORTION_DURATION).max(0.0)
+  }
+}
+
+/// How far a frame is between the last tick and the next one, from 0 to 1. Set by main() before the
+/// draw schedule runs, the draw systems and the camera interpolate from the tick before by it.
+#[derive(Debug, Default, Clone, Copy, Resource)]
+pub struct RenderAlpha(pub f32);
+
+/// The CRT look of the low res pass, cycled with F7.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PostFxPreset {
+  #[default]
+  Off,
+  Subtle,
+  Heavy,
+}
+
+/// Scanlines, barrel distortion and chromatic aberration, uploaded by [`crate::render::render_gl`] every
+/// frame. Without a low res framebuffer there is no post pass and nothing to apply them to.
+#[derive(Debug, Default, Resource)]
+pub struct PostFx {
+  pub preset: PostFxPreset,
+  /// Text is drawn into the low res framebuffer and goes through the effect, instead of over it.
+  pub text_in_scene: bool,
+}
+
+impl PostFx {
+  pub fn cycle(&mut self) {
+    self.preset = match self.preset {
+      PostFxPreset::Off => PostFxPreset::Subtle,
+      PostFxPreset::Subtle => PostFxPreset::Heavy,
+      PostFxPreset::Heavy => PostFxPreset::Off,
+    };
+  }
+
+  /// The scanline intensity, the curvature and the aberration in texels.
+  pub fn uniforms(&self) -> (f32, f32, f32) {
+    match self.preset {
+      PostFxPreset::Off => (0.0, 0.0, 0.0),
+      PostFxPreset::Subtle => POST_FX_SUBTLE,
+      PostFxPreset::Heavy => POST_FX_HEAVY,
+    }
+  }
+}
+
+/// Heat haze sources for the post pass, at most [`MAX_DISTORTIONS`] at a time.
+#[derive(Debug, Default, Resource)]
+pub struct Distortions {
+  pub sources: Vec<Distortion>,
+  pub elapsed: f32,
+}
+
+impl Distortions {
+  pub fn add(&mut self, position: glam::Vec2, radius: f32, strength: f32) {
+    let distortion = Distortion {
+      position,
+      radius,
+      strength,
+      age: 0.0,
+    };
+    if self.sources.len() < MAX_DISTORTIONS {
+      self.sources.push(distortion);
+    } else if let Some(weakest) = self
+      .sources
+      .iter_mut()
+      .min_by(|a, b| a.current_strength().total_cmp(&b.current_strength()))
+    {
+      *weakest = distortion;
+    }
+  }
+
+  pub fn update(&mut self, dt: f32) {
+    self.elapsed += dt;
+    for source in &mut self.sources {
+      source.age += dt;
+    }
+    self.sources.retain(|source| source.age < DISTORTION_DURATION);
+  }
+}
+
+/// The playfield in world units. Bounds tests, spawn ranges and the minimap go through this instead of
+/// the screen size, the camera viewport is a separate thing.
+#[derive(Debug, Clone, Copy, Resource)]
+pub struct WorldBounds {
+  pub min: glam::Vec2,
+  pub max: glam::Vec2,
+}
+
+impl Default for WorldBounds {
+  fn default() -> Self {
+    Self {
+      min: glam::Vec2::ZERO,
+      max: glam::vec2(SCREEN_WIDTH as f32, SCREEN_HEIGHT as f32),
+    }
+  }
+}
+
+impl WorldBounds {
+  pub fn size(&self) -> glam::Vec2 {
+    self.max - self.min
+  }
+
+  pub fn center(&self) -> glam::Vec2 {
+    self.min + self.size() / 2.0
+  }
+
+  /// The corners of the playfield grown by `offset` on every side, shrunk by a negative one.
+  pub fn corners(&self, offset: f32) -> (WorldPos, WorldPos) {
+    (WorldPos::from(self.min - offset), WorldPos::from(self.max + offset))
+  }
+
+  pub fn is_outside(&self, position: WorldPos, offset: f32) -> bool {
+    let (min, max) = self.corners(offset);
+    !position.is_within(min, max)
+  }
+
+  /// The edge `position` left the playfield through, through a corner the one it is further past, the
+  /// top or bottom one when it is as far past both.
+  pub fn crossed_edge(&self, position: WorldPos) -> Option<Edge> {
+    let (min, max) = self.corners(0.0);
+    let (below, above) = (min - position, position - max);
+    let past = [
+      (below.x(), Edge::Left),
+      (above.x(), Edge::Right),
+      (below.y(), Edge::Bottom),
+      (above.y(), Edge::Top),
+    ];
+    past
+      .into_iter()
+      .filter(|(distance, _)| *distance > 0.0)
+      .max_by(|(a, _), (b, _)| a.total_cmp(b))
+      .map(|(_, edge)| edge)
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+  Left,
+  Right,
+  Bottom,
+  Top,
+}
+
+/// Ticks the player spent per cell and where they died, for the whole session. Shown with H once the
+/// run is over.
+#[derive(Debug, Resource)]
+pub struct Heatmap {
+  pub visible: bool,
+  pub visits: Vec<u32>,
+  pub deaths: Vec<glam::Vec2>,
+}
+
+impl Default for Heatmap {
+  fn default() -> Self {
+    Self {
+      visible: false,
+      visits: vec![0; HEATMAP_COLUMNS * HEATMAP_ROWS],
+      deaths: Vec::new(),
+    }
+  }
+}
+
+/// The vector "BYTEPATH" logo on the game over screen, drawn in over [`LOGO_REVEAL_DURATION`].
+#[derive(Debug, Default, Resource)]
+pub struct TitleLogo {
+  pub elapsed: f32,
+}
+
+/// Plots of every easing in [`crate::easings::Easing::ALL`], toggled with E. While it's open F8 selects
+/// the next entity for the [`crate::inspector::Inspector`] and F9 cycles its easing.
+#[derive(Debug, Default, Resource)]
+pub struct CurvePanel {
+  pub visible: bool,
+}
+
+/// Shows the input every tick consumed, for streams and for debugging input handling.
+#[derive(Debug, Default, Resource)]
+pub struct InputDisplay {
+  pub visible: bool,
+  pub history: InputHistory,
+}
+
+/// Raw frame times of the last [`FRAME_STATS_WINDOW`] frames and what the last frame did, shown with F3.
+/// Fed by the main loop and `render_gl`, so it lags the overlay by a frame.
+#[derive(Debug, Default, Resource)]
+pub struct FrameStats {
+  pub visible: bool,
+  frame_times: VecDeque<f32>,
+  /// Fixed timestep ticks the last frame ran.
+  pub sub_steps: u32,
+  /// Vertices submitted per scene buffer, circles, quads and lines.
+  pub vertices: [usize; 3],
+}
+
+impl FrameStats {
+  pub fn record(&mut self, frame_time: Duration, sub_steps: u32) {
+    if self.frame_times.len() == FRAME_STATS_WINDOW {
+      self.frame_times.pop_front();
+    }
+    self.frame_times.push_back(frame_time.as_secs_f32());
+    self.sub_steps = sub_steps;
+  }
+
+  /// Seconds the last frame took.
+  pub fn last_frame_time(&self) -> f32 {
+    self.frame_times.back().copied().unwrap_or_default()
+  }
+
+  pub fn average_fps(&self) -> f32 {
+    let total = self.frame_times.iter().sum::<f32>();
+    if total <= 0.0 {
+      return 0.0;
+    }
+    self.frame_times.len() as f32 / total
+  }
+
+  /// The frame rate of the slowest 1% of frames, at least the slowest one.
+  pub fn one_percent_low(&self) -> f32 {
+    let mut slowest = self.frame_times.iter().copied().collect::<Vec<_>>();
+    slowest.sort_by(|a, b| b.total_cmp(a));
+    slowest.truncate(slowest.len().div_ceil(100));
+    let total = slowest.iter().sum::<f32>();
+    if total <= 0.0 {
+      return 0.0;
+    }
+    slowest.len() as f32 / total
+  }
+}
+
+/// The timers that follow one player's ship, kept across respawns.
+#[derive(Debug)]
+pub struct PlayerTimers {
   pub projectile: Timer,
   pub tick_effect: Timer,
-  pub ammo_pickup: Timer,
-  pub boost_pickup: Timer,
+  /// Shortened while boosting, see [`crate::systems::trail_effect_spawn_system`].
+  pub trail: Timer,
 }
 
-impl Default for EntitySpawnTimer {
+impl Default for PlayerTimers {
   fn default() -> Self {
     Self {
-      projectile: Timer::from_seconds(0.25, true),
+      projectile: Timer::from_seconds(PROJECTILE_INTERVAL, true),
       tick_effect: Timer::from_seconds(5.0, true),
-      ammo_pickup: Timer::from_seconds(1.0, true),
-      boost_pickup: Timer::from_seconds(2.0, true),
+      trail: Timer::from_seconds(TRAIL_INTERVAL, true),
     }
   }
 }
 
+#[derive(Debug, Resource)]
+pub struct EntitySpawnTimer {
+  pub ammo_pickup: Timer,
+  pub boost_pickup: Timer,
+  pub laser_hazard: Timer,
+  pub enemy: Timer,
+  pub attack_pickup: Timer,
+  /// Indexed by [`PlayerId`], grown by [`Self::player_mut`].
+  pub players: Vec<PlayerTimers>,
+}
+
 impl EntitySpawnTimer {
-  pub fn as_array(&mut self) -> [&mut Timer; 4] {
+  /// The intervals for the default playfield, pickups sped up by the build.
+  pub fn new(modifiers: &Modifiers) -> Self {
+    let pickup = |seconds: f32| Timer::from_seconds(seconds / modifiers.pickup_spawn_rate_mult, true);
+    Self {
+      ammo_pickup: pickup(1.0),
+      boost_pickup: pickup(2.0),
+      laser_hazard: Timer::from_seconds(12.0, true),
+      enemy: Timer::from_seconds(3.0, true),
+      attack_pickup: pickup(10.0),
+      players: Vec::new(),
+    }
+  }
+
+  /// The shared timers, without the per player ones.
+  pub fn as_array(&mut self) -> [&mut Timer; 5] {
     [
-      &mut self.projectile,
-      &mut self.tick_effect,
       &mut self.ammo_pickup,
       &mut self.boost_pickup,
+      &mut self.laser_hazard,
+      &mut self.enemy,
+      &mut self.attack_pickup,
     ]
   }
+
+  pub fn player_mut(&mut self, id: PlayerId) -> &mut PlayerTimers {
+    let index = id.0 as usize;
+    if self.players.len() <= index {
+      self.players.resize_with(index + 1, PlayerTimers::default);
+    }
+    &mut self.players[index]
+  }
+
+  /// Rescales the intervals so the spawn density matches the default playfield. Pickups spawning
+  /// inside the bounds scale by area, boost and attack pickups and enemies entering from the edges by
+  /// perimeter. The projectile interval belongs to the player's attack.
+  pub fn normalize(&mut self, bounds: &WorldBounds, modifiers: &Modifiers) {
+    let base = Self::new(modifiers);
+    let (reference, current) = (WorldBounds::default().size(), bounds.size());
+    let per_area = density_scale(reference.x * reference.y, current.x * current.y, MAX_SPAWN_DENSITY_SCALE);
+    let per_perimeter = density_scale(reference.x + reference.y, current.x + current.y, MAX_SPAWN_DENSITY_SCALE);
+
+    self.ammo_pickup.duration = base.ammo_pickup.duration.mul_f32(per_area);
+    self.laser_hazard.duration = base.laser_hazard.duration.mul_f32(per_area);
+    self.boost_pickup.duration = base.boost_pickup.duration.mul_f32(per_perimeter);
+    self.enemy.duration = base.enemy.duration.mul_f32(per_perimeter);
+    self.attack_pickup.duration = base.attack_pickup.duration.mul_f32(per_perimeter);
+  }
+}
+
+/// Seconds since the player last collected each pickup kind. Speeds up the spawn timer of a kind the
+/// player starves on and slows down boost pickups while boost is full. Off with `--pure-rng`.
+#[derive(Debug, Resource)]
+pub struct PickupPity {
+  pub enabled: bool,
+  pub since_ammo: f32,
+  pub since_boost: f32,
+  pub boost_full: bool,
+}
+
+impl Default for PickupPity {
+  fn default() -> Self {
+    Self {
+      enabled: true,
+      since_ammo: 0.0,
+      since_boost: 0.0,
+      boost_full: true,
+    }
+  }
+}
+
+impl PickupPity {
+  /// Tick rate per timer, in [`EntitySpawnTimer::as_array`] order.
+  pub fn rates(&self) -> [f32; 5] {
+    if !self.enabled {
+      return [1.0; 5];
+    }
+    let rate = |since, saturated| {
+      pity_rate(since, PITY_THRESHOLD, PITY_RAMP, PITY_MAX_RATE, saturated, PITY_SATURATED_RATE)
+    };
+    [rate(self.since_ammo, false), rate(self.since_boost, self.boost_full), 1.0, 1.0, 1.0]
+  }
+}
+
+/// Counts down to the next player after a death, in raw time so the death slow-mo doesn't stretch it.
+#[derive(Debug, Default, Resource)]
+pub struct Respawn {
+  pub timer: Option<Timer>,
+}
+
+/// Players killed during the current tick. Their `Despawning` only lands once the stage's commands
+/// apply, until then every other hazard still sees them alive. Cleared by `player_system`, which
+/// starts the tick.
+#[derive(Debug, Default, Resource)]
+pub struct Deaths(Vec<Entity>);
+
+impl Deaths {
+  /// Records `player` as killed, false if it already was this tick.
+  pub fn kill(&mut self, player: Entity) -> bool {
+    if self.contains(player) {
+      return false;
+    }
+    self.0.push(player);
+    true
+  }
+
+  pub fn contains(&self, player: Entity) -> bool {
+    self.0.contains(&player)
+  }
+
+  pub fn clear(&mut self) {
+    self.0.clear();
+  }
+}
+
+/// Paused freezes every [`TimeDomain`] and drops the player's input, everything keeps drawing.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Resource)]
+pub enum GameState {
+  #[default]
+  Running,
+  Paused,
+}
+
+/// Where the run is, changed by `player_respawn_system`. Entering one despawns the entities it doesn't
+/// keep, see [`SCOPES_KEPT`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Resource)]
+pub enum Phase {
+  #[default]
+  Run,
+  /// From the last player's death until the next run, the playfield stays as it was under the table.
+  GameOver,
+}
+
+/// The scopes entities outlive entering each phase in.
+pub const SCOPES_KEPT: [(Phase, &[GameScope]); 2] = [
+  (Phase::Run, &[GameScope::Persistent]),
+  (Phase::GameOver, &[GameScope::Run, GameScope::Persistent]),
+];
+
+impl Phase {
+  pub fn keeps(self, scope: GameScope) -> bool {
+    SCOPES_KEPT.iter().any(|(phase, kept)| *phase == self && kept.contains(&scope))
+  }
+}
+
+impl GameState {
+  pub fn toggle(&mut self) {
+    *self = match self {
+      GameState::Running => GameState::Paused,
+      GameState::Paused => GameState::Running,
+    };
+  }
+}
+
+/// When the last input came, in raw seconds since the start. Past `timeout` without any the game
+/// pauses itself, see `idle_system`. The attract mode and a screensaver dim would read
+/// [`Idle::idle_for`] too once there are some.
+#[derive(Debug, Default, Resource)]
+pub struct Idle {
+  /// None never pauses, headless runs and the stress scenario have nobody at the keys.
+  pub timeout: Option<Duration>,
+  /// Set by the pause it caused, cleared once the game runs again.
+  pub auto_paused: bool,
+  now: Duration,
+  last_activity: Duration,
+}
+
+impl Idle {
+  pub fn idle_for(&self) -> Duration {
+    self.now - self.last_activity
+  }
+
+  /// Input that doesn't reach the simulation, the mouse for now.
+  pub fn touch(&mut self) {
+    self.last_activity = self.now;
+  }
+
+  /// Advances by `dt` raw time. Input restarts the countdown, and so does every tick that isn't
+  /// `counting`, paused or over. True on the tick the countdown runs out.
+  pub fn update(&mut self, dt: Duration, active: bool, counting: bool) -> bool {
+    self.now += dt;
+    if active || !counting {
+      self.last_activity = self.now;
+    }
+    if !counting {
+      return false;
+    }
+    self.auto_paused = self.timeout.is_some_and(|timeout| self.idle_for() >= timeout);
+    self.auto_paused
+  }
 }
 
+/// Bomb charges, shared by the players. A run earns one every [`BOMB_CHARGE_SCORE`] points, up to
+/// [`BOMB_MAX_CHARGES`] at a time.
 #[derive(Debug, Default, Resource)]
+pub struct Bomb {
+  pub charges: u32,
+  earned: u32,
+}
+
+impl Bomb {
+  /// Adds the charges `score` earned since the last call.
+  pub fn accrue(&mut self, score: u32) {
+    let earned = score / BOMB_CHARGE_SCORE;
+    self.charges = (self.charges + earned.saturating_sub(self.earned)).min(BOMB_MAX_CHARGES);
+    self.earned = self.earned.max(earned);
+  }
+
+  /// Spends a charge if there is one.
+  pub fn spend(&mut self) -> bool {
+    match self.charges.checked_sub(1) {
+      Some(left) => {
+        self.charges = left;
+        true
+      }
+      None => false,
+    }
+  }
+}
+
+/// Points of the current run, kept after the player dies until the next player spawns.
+#[derive(Debug, Default, Resource)]
+pub struct Score(pub u32);
+
+/// Which clock a timer follows:
+/// - Gameplay: movement, spawn timers, pickups, lasers, projectile splats, everything the death
+///   slow-mo should stretch
+/// - Ui: text, notices and the logo, they read the same with or without slow-mo
+/// - Background: effects once the run is over, see [`Time::effects`]
+/// - raw, outside of any domain: camera shake, the flash guard and the stress scenario, which
+///   count wall clock time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeDomain {
+  Gameplay,
+  Ui,
+  Background,
+}
+
+/// Frame time scaled per [`TimeDomain`], each domain has its own scale. Derefs to the gameplay domain.
+#[derive(Debug, Resource)]
 pub struct Time {
-  pub duration: Duration,
-  pub slow_down_timer: Option<Duration>,
+  pub effects_domain: TimeDomain,
+  scales: [f32; 3],
+  durations: [Duration; 3],
+  raw: Duration,
+}
+
+impl Default for Time {
+  fn default() -> Self {
+    Self {
+      effects_domain: TimeDomain::Gameplay,
+      scales: [1.0; 3],
+      durations: [Duration::default(); 3],
+      raw: Duration::default(),
+    }
+  }
+}
+
+impl Time {
+  pub fn domain(&self, domain: TimeDomain) -> Duration {
+    self.durations[domain as usize]
+  }
+
+  /// Effects follow gameplay during a run and the background once it is over.
+  pub fn effects(&self) -> Duration {
+    self.domain(self.effects_domain)
+  }
+
+  pub fn scale(&self, domain: TimeDomain) -> f32 {
+    self.scales[domain as usize]
+  }
+
+  pub fn set_scale(&mut self, domain: TimeDomain, scale: f32) {
+    self.scales[domain as usize] = scale.max(0.0);
+  }
+
+  /// The frame time before any scale.
+  pub fn raw(&self) -> Duration {
+    self.raw
+  }
+
+  pub fn advance(&mut self, raw_time: Duration) {
+    self.raw = raw_time;
+    // mul_f32 rounds through f32 seconds, an unscaled domain has to match the raw time exactly
+    for (duration, scale) in self.durations.iter_mut().zip(self.scales) {
+      *duration = if scale == 1.0 { raw_time } else { raw_time.mul_f32(scale) };
+    }
+  }
 }
 
 impl Deref for Time {
   type Target = Duration;
 
   fn deref(&self) -> &Self::Target {
-    &self.duration
+    &self.durations[TimeDomain::Gameplay as usize]
   }
 }
 
-impl DerefMut for Time {
-  fn deref_mut(&mut self) -> &mut Self::Target {
-    &mut self.duration
+/// Every slow motion playing, with the raw time it has played for. [`crate::systems::timing_system`]
+/// scales gameplay by the slowest of them.
+#[derive(Debug, Default, Resource)]
+pub struct TimeScale {
+  active: Vec<(SlowMotion, Duration)>,
+}
+
+impl TimeScale {
+  pub fn start(&mut self, slow_motion: SlowMotion) {
+    self.active.push((slow_motion, Duration::ZERO));
+  }
+
+  /// Advances every slow motion by `raw_time` and drops the finished ones. The slowest factor left,
+  /// exactly 1.0 once none is.
+  pub fn advance(&mut self, raw_time: Duration) -> f32 {
+    for (_, elapsed) in &mut self.active {
+      *elapsed += raw_time;
+    }
+    self
+      .active
+      .retain(|(slow_motion, elapsed)| elapsed.as_secs_f32() <= slow_motion.duration);
+    self
+      .active
+      .iter()
+      .map(|(slow_motion, elapsed)| slow_motion.factor_at(elapsed.as_secs_f32()))
+      .fold(1.0, f32::min)
+  }
+
+  pub fn is_slowed(&self) -> bool {
+    !self.active.is_empty()
+  }
+
+  pub fn active(&self) -> &[(SlowMotion, Duration)] {
+    &self.active
+  }
+
+  pub fn clear(&mut self) {
+    self.active.clear();
   }
 }
 
+/// A repeating timer wraps around the tick it completes on and carries the overshoot into the next
+/// period, so its interval doesn't drift with the frame rate. A tick spanning several periods still
+/// completes once, [`Timer::times_finished`] tells how many. A one shot timer stays finished until reset.
 #[derive(Debug, Default, Resource)]
 pub struct Timer {
   pub elapsed: Duration,
   pub duration: Duration,
   pub finished: bool,
+  just_finished: bool,
+  times_finished: u32,
   repeating: bool,
-  pub checkpoint: Duration,
+  paused: bool,
 }
 
 impl Timer {
@@ -253,55 +1136,188 @@ impl Timer {
   }
 
   pub fn tick(&mut self, delta: Duration) {
-    self.elapsed = (self.elapsed + delta).min(self.duration);
+    self.just_finished = false;
+    self.times_finished = 0;
+    if self.repeating {
+      self.finished = false;
+    }
+    if self.paused || self.finished {
+      return;
+    }
 
-    if self.repeating && self.finished {
-      self.reset();
+    self.elapsed += delta;
+    if self.elapsed < self.duration {
+      return;
+    }
+    self.finished = true;
+    self.just_finished = true;
+    self.times_finished = 1;
+    if self.repeating && !self.duration.is_zero() {
+      self.times_finished = (self.elapsed.as_nanos() / self.duration.as_nanos()) as u32;
+      self.elapsed = Duration::from_nanos((self.elapsed.as_nanos() % self.duration.as_nanos()) as u64);
+    } else {
+      self.elapsed = self.duration;
     }
+  }
+
+  /// Only true for the tick the timer completed on.
+  pub fn just_finished(&self) -> bool {
+    self.just_finished
+  }
 
-    self.finished = self.elapsed >= self.duration;
+  /// Periods completed by the last tick, more than one when it was longer than the duration.
+  pub fn times_finished(&self) -> u32 {
+    self.times_finished
   }
 
-  pub fn add_checkpoint(&mut self, t: Duration) {
-    self.checkpoint = self.elapsed + t;
+  pub fn percent(&self) -> f32 {
+    if self.duration.is_zero() {
+      return 1.0;
+    }
+    (self.elapsed.as_secs_f32() / self.duration.as_secs_f32()).clamp(0.0, 1.0)
   }
 
-  pub fn reset(&mut self) {
-    self.finished = false;
-    self.elapsed = Duration::from_secs_f32(0.0);
-    self.checkpoint = self.elapsed;
+  pub fn pause(&mut self) {
+    self.paused = true;
+  }
+
+  pub fn unpause(&mut self) {
+    self.paused = false;
   }
 }
 
+/// Every glyph tessellated once, stroked and filled. Drawing copies the cached vertices with a transform.
 #[derive(Resource)]
-pub struct Fills(pub FillTessellator);
+pub struct GlyphMeshes {
+  stroked: HashMap<GlyphId, VertexBuffers<Point, u16>>,
+  filled: HashMap<GlyphId, VertexBuffers<Point, u16>>,
+}
 
-impl Deref for Fills {
-  type Target = FillTessellator;
-  fn deref(&self) -> &FillTessellator {
-    &self.0
+impl Default for GlyphMeshes {
+  fn default() -> Self {
+    let (mut strokes, mut fills) = (StrokeTessellator::new(), FillTessellator::new());
+    let (mut stroked, mut filled) = (HashMap::new(), HashMap::new());
+    for glyph in GlyphId::ALL {
+      let path = build_path(glyph.commands());
+      let mut mesh = VertexBuffers::new();
+      strokes
+        .tessellate_path(&path, &StrokeOptions::default(), &mut simple_builder(&mut mesh))
+        .unwrap();
+      stroked.insert(glyph, mesh);
+
+      let mut mesh = VertexBuffers::new();
+      fills
+        .tessellate_path(&path, &FillOptions::default(), &mut simple_builder(&mut mesh))
+        .unwrap();
+      filled.insert(glyph, mesh);
+    }
+
+    Self { stroked, filled }
   }
 }
 
-impl DerefMut for Fills {
-  fn deref_mut(&mut self) -> &mut Self::Target {
-    &mut self.0
+impl GlyphMeshes {
+  pub fn draw(&self, glyph: GlyphId, fill: bool, buffers: &mut VertexBuffers<MyVertex, u32>, with: WithTransformColor) {
+    let mesh = if fill { &self.filled[&glyph] } else { &self.stroked[&glyph] };
+    let offset = buffers.vertices.len() as u32;
+    buffers.vertices.extend(mesh.vertices.iter().map(|position| with.vertex(*position)));
+    buffers.indices.extend(mesh.indices.iter().map(|index| u32::from(*index) + offset));
   }
 }
 
-#[derive(Resource)]
-pub struct Strokes(pub StrokeTessellator);
+/// Shapes lyon could not tessellate. Only the first failure is logged, a shape failing every frame
+/// would flood the log.
+#[derive(Debug, Default)]
+struct DroppedShapes(u32);
 
-impl Deref for Strokes {
-  type Target = StrokeTessellator;
-  fn deref(&self) -> &StrokeTessellator {
-    &self.0
+impl DroppedShapes {
+  fn check(&mut self, shape: &str, result: TessellationResult) -> TessellationResult {
+    if let Err(e) = &result {
+      if self.0 == 0 {
+        eprintln!("could not tessellate {}: {:?}, dropping it and counting further failures", shape, e);
+      }
+      self.0 += 1;
+    }
+    result
   }
 }
 
-impl DerefMut for Strokes {
-  fn deref_mut(&mut self) -> &mut Self::Target {
-    &mut self.0
+/// Wraps the tessellator so a shape it fails on is logged, skipped and counted instead of panicking
+/// the game. Callers have nothing to clean up and usually drop the result.
+#[derive(Resource, Default)]
+pub struct Fills {
+  tessellator: FillTessellator,
+  dropped: DroppedShapes,
+}
+
+impl Fills {
+  pub fn tessellate_rectangle(
+    &mut self,
+    rect: &Box2D,
+    options: &FillOptions,
+    output: &mut dyn FillGeometryBuilder,
+  ) -> TessellationResult {
+    let result = self.tessellator.tessellate_rectangle(rect, options, output);
+    self.dropped.check("filled rectangle", result)
+  }
+
+  pub fn tessellate_circle(
+    &mut self,
+    center: Point,
+    radius: f32,
+    options: &FillOptions,
+    output: &mut dyn FillGeometryBuilder,
+  ) -> TessellationResult {
+    let result = self.tessellator.tessellate_circle(center, radius, options, output);
+    self.dropped.check("filled circle", result)
+  }
+
+  pub fn dropped(&self) -> u32 {
+    self.dropped.0
+  }
+}
+
+/// The outline counterpart of [`Fills`].
+#[derive(Resource, Default)]
+pub struct Strokes {
+  tessellator: StrokeTessellator,
+  dropped: DroppedShapes,
+}
+
+impl Strokes {
+  pub fn tessellate_rectangle(
+    &mut self,
+    rect: &Box2D,
+    options: &StrokeOptions,
+    output: &mut dyn StrokeGeometryBuilder,
+  ) -> TessellationResult {
+    let result = self.tessellator.tessellate_rectangle(rect, options, output);
+    self.dropped.check("stroked rectangle", result)
+  }
+
+  pub fn tessellate_circle(
+    &mut self,
+    center: Point,
+    radius: f32,
+    options: &StrokeOptions,
+    output: &mut dyn StrokeGeometryBuilder,
+  ) -> TessellationResult {
+    let result = self.tessellator.tessellate_circle(center, radius, options, output);
+    self.dropped.check("stroked circle", result)
+  }
+
+  pub fn tessellate_path(
+    &mut self,
+    path: impl IntoIterator<Item = PathEvent>,
+    options: &StrokeOptions,
+    output: &mut dyn StrokeGeometryBuilder,
+  ) -> TessellationResult {
+    let result = self.tessellator.tessellate(path, options, output);
+    self.dropped.check("stroked path", result)
+  }
+
+  pub fn dropped(&self) -> u32 {
+    self.dropped.0
   }
 }
 
@@ -322,6 +1338,296 @@ impl DerefMut for KeyCodes {
   }
 }
 
+pub const BINDINGS_FILE: &str = "bindings.cfg";
+
+/// How many sources the bindings of all players may use together, a replay frame keeps one bit per
+/// source and one more for the restart key.
+const MAX_BOUND_SOURCES: usize = 63;
+
+/// Which source triggers which action, an action can have several sources of any kind. One set per
+/// player, as many players spawn as there are sets. See [`KeyBindings::parse`] for the file format.
+#[derive(Debug, Clone, PartialEq, Resource)]
+pub struct KeyBindings(Vec<Vec<(InputSource, Action)>>);
+
+impl KeyBindings {
+  /// One player, the controller buttons of [`PAD_BINDINGS`] come on top.
+  pub fn new(bindings: impl IntoIterator<Item = (InputSource, Action)>) -> Self {
+    Self(vec![bindings.into_iter().chain(PAD_BINDINGS).collect()])
+  }
+
+  /// The controller steers the first player.
+  pub fn co_op() -> Self {
+    let mut players = CO_OP_BINDINGS.map(|bindings| bindings.to_vec());
+    players[0].extend(PAD_BINDINGS);
+    Self(players.to_vec())
+  }
+
+  /// The bindings `--co-op` and `--keys` pick, co-op brings a layout per player whatever the keys say.
+  pub fn layout(co_op: bool, wasd: bool) -> Self {
+    if co_op {
+      Self::co_op()
+    } else if wasd {
+      Self::new(WASD_BINDINGS)
+    } else {
+      Self::default()
+    }
+  }
+
+  pub fn players(&self) -> u8 {
+    self.0.len() as u8
+  }
+
+  /// Nothing for players without bindings.
+  pub fn player(&self, id: PlayerId) -> &[(InputSource, Action)] {
+    self.0.get(id.0 as usize).map_or(&[], Vec::as_slice)
+  }
+
+  /// Every bound source once, in the order the players bind them.
+  pub fn sources(&self) -> Vec<InputSource> {
+    let mut sources = Vec::new();
+    for (source, _) in self.0.iter().flatten() {
+      if !sources.contains(source) {
+        sources.push(*source);
+      }
+    }
+    sources
+  }
+
+  /// Binds `to` instead of `from` for `player`, in the same place so the order of the sources stays.
+  pub fn rebind(&mut self, id: PlayerId, from: InputSource, to: InputSource) {
+    let bindings = self.0.get_mut(id.0 as usize).into_iter().flatten();
+    for (source, _) in bindings.filter(|(source, _)| *source == from) {
+      *source = to;
+    }
+  }
+
+  /// A source bound to more than one action, or for more than one player. Both would press what the
+  /// player didn't mean to, however the source is spelled.
+  pub fn conflict(&self) -> Option<InputSource> {
+    let bindings = self.0.iter().enumerate().flat_map(|(player, bindings)| bindings.iter().map(move |b| (player, b)));
+    let mut seen = HashMap::new();
+    for (player, (source, action)) in bindings {
+      if *seen.entry(*source).or_insert((player, *action)) != (player, *action) {
+        return Some(*source);
+      }
+    }
+    None
+  }
+
+  /// One `player.action = source, source` per line with players counting from 1, lines starting with `#`
+  /// are comments. E.g. `1.shoot = key:Space, mouse:left, pad:a`, see [`InputSource::parse`] for the
+  /// sources and [`Action::name`] for the actions. The file is the whole layout, controller buttons
+  /// included, and as many players spawn as it numbers.
+  pub fn parse(text: &str) -> Result<Self, Error> {
+    let mut players = Vec::<Vec<(InputSource, Action)>>::new();
+    for (i, line) in text.lines().enumerate() {
+      let line = line.trim();
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+      let invalid = |reason| Error::InvalidBindings { line: i + 1, reason };
+      let (key, value) = line.split_once('=').ok_or_else(|| invalid("expected player.action = sources"))?;
+      let (player, action) = key.trim().split_once('.').ok_or_else(|| invalid("expected player.action"))?;
+      let player = player
+        .parse::<usize>()
+        .ok()
+        .filter(|player| *player > 0 && *player <= players.len() + 1)
+        .ok_or_else(|| invalid("expected the players numbered from 1 without gaps"))?;
+      let action = Action::from_name(action).ok_or_else(|| invalid("unknown action"))?;
+      if player > players.len() {
+        players.push(Vec::new());
+      }
+      for source in value.split(',') {
+        let source = InputSource::parse(source).ok_or_else(|| invalid("unknown source"))?;
+        players[player - 1].push((source, action));
+      }
+      let bindings = Self(players.clone());
+      if bindings.conflict().is_some() {
+        return Err(invalid("source bound twice"));
+      }
+      if bindings.sources().len() > MAX_BOUND_SOURCES {
+        return Err(invalid("too many sources"));
+      }
+    }
+    if players.is_empty() {
+      let line = text.lines().count().max(1);
+      return Err(Error::InvalidBindings { line, reason: "expected at least one binding" });
+    }
+    Ok(Self(players))
+  }
+
+  /// Reads the profile's [`BINDINGS_FILE`], or the shared one next to the assets, if there is one. It
+  /// replaces the layout the flags pick. A broken file is reported and replaced by `fallback` as a whole.
+  pub fn load(fallback: Self, profile: &Profile) -> Self {
+    let path = profile.file(BINDINGS_FILE).filter(|path| path.is_file());
+    let Some(path) = path.or_else(|| find_asset(BINDINGS_FILE).ok()) else {
+      return fallback;
+    };
+    match std::fs::read_to_string(path).map_err(Error::from).and_then(|text| Self::parse(&text)) {
+      Ok(bindings) => bindings,
+      Err(e) => {
+        eprintln!("{}, falling back to the default bindings", e);
+        fallback
+      }
+    }
+  }
+
+  /// Writes the profile's [`BINDINGS_FILE`], or the shared one without a data dir.
+  pub fn save(&self, profile: &Profile) {
+    let path = profile.file(BINDINGS_FILE);
+    let path = path.unwrap_or_else(|| find_asset(BINDINGS_FILE).unwrap_or_else(|_| PathBuf::from(BINDINGS_FILE)));
+    if let Err(e) = write_atomic(&path, |out| write!(out, "{}", self)) {
+      eprintln!("could not save the bindings to {}: {}", path.display(), e);
+    }
+  }
+}
+
+impl Default for KeyBindings {
+  fn default() -> Self {
+    Self::new(BINDINGS)
+  }
+}
+
+/// Writes what [`KeyBindings::parse`] reads, one line per binding so they read back in the same order.
+/// A replay relies on that, see [`crate::replay::Replay::write`].
+impl fmt::Display for KeyBindings {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for (player, bindings) in self.0.iter().enumerate() {
+      for (source, action) in bindings {
+        writeln!(f, "{}.{} = {}", player + 1, action.name(), source)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+/// The mouse buttons held this tick by SDL's numbers, and the wheel's pulses, see
+/// [`crate::input::WheelPulses`]. Polled each tick like [`KeyCodes`].
+#[derive(Debug, Default, Clone, Resource)]
+pub struct MouseInput {
+  pub buttons: HashSet<u8>,
+  pub wheel_up: bool,
+  pub wheel_down: bool,
+}
+
+/// Every connected controller merged into one, polled each tick like [`KeyCodes`].
+#[derive(Debug, Default, Clone, Resource)]
+pub struct GamepadState {
+  /// Left stick with the deadzone cut out, x right and y up.
+  pub stick: glam::Vec2,
+  pub buttons: HashSet<Button>,
+}
+
+impl GamepadState {
+  /// Any button held or the stick out of its deadzone.
+  pub fn is_active(&self) -> bool {
+    !self.buttons.is_empty() || self.stick != glam::Vec2::ZERO
+  }
+}
+
+/// Remembers the tick every held source went down on, the input of every player for a tick is resolved
+/// from it.
+#[derive(Debug, Default, Resource)]
+pub struct InputState {
+  tick: u64,
+  pressed_at: HashMap<InputSource, u64>,
+  players: Vec<PlayerInput>,
+}
+
+impl InputState {
+  /// The stick of every controller steers the first player.
+  pub fn update(
+    &mut self,
+    keycodes: &HashSet<Keycode>,
+    mouse: &MouseInput,
+    gamepad: &GamepadState,
+    bindings: &KeyBindings,
+  ) {
+    self.tick += 1;
+    let is_held = |source: &InputSource| source.is_held(keycodes, mouse, gamepad);
+    self.pressed_at.retain(|source, _| is_held(source));
+    for source in bindings.sources().into_iter().filter(is_held) {
+      self.pressed_at.entry(source).or_insert(self.tick);
+    }
+    let held = || self.pressed_at.iter().map(|(source, tick)| (*source, *tick));
+    self.players = bindings.0.iter().map(|bindings| resolve(held(), bindings)).collect();
+    if let Some(first) = self.players.first_mut() {
+      *first = merge_stick(*first, gamepad.stick);
+    }
+  }
+
+  /// Nothing held for players without bindings.
+  pub fn player(&self, id: PlayerId) -> PlayerInput {
+    self.players.get(id.0 as usize).copied().unwrap_or_default()
+  }
+
+  /// Lets go of everything for this tick.
+  pub fn release(&mut self) {
+    self.players.fill(PlayerInput::default());
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParticleKind {
+  Trail,
+  Explosion,
+}
+
+/// Trail puffs and explosion lines are reused instead of spawned and despawned a few frames later. A
+/// released particle stays an entity with [`Active`] false, the draw systems skip it. Each kind keeps
+/// the same components, so reusing one never moves it to another archetype.
+#[derive(Debug, Resource)]
+pub struct ParticlePool {
+  free: [Vec<Entity>; 2],
+  spawned: [usize; 2],
+  cap: usize,
+}
+
+impl ParticlePool {
+  pub fn new(cap: usize) -> Self {
+    Self {
+      free: Default::default(),
+      spawned: [0; 2],
+      cap,
+    }
+  }
+
+  /// Reuses a released particle of `kind`, or spawns one while there are fewer than the cap. Once all
+  /// are in use the particle is dropped. `bundle` has to hold the same components every time.
+  pub fn acquire(&mut self, commands: &mut Commands, kind: ParticleKind, bundle: impl Bundle) {
+    if let Some(entity) = self.free[kind as usize].pop() {
+      commands.entity(entity).insert((bundle, Active(true)));
+    } else if self.spawned[kind as usize] < self.cap {
+      self.spawned[kind as usize] += 1;
+      // the pool holds on to them, nothing else may despawn them
+      commands.spawn((bundle, Active(true), ScopeTag(GameScope::Persistent)));
+    }
+  }
+
+  /// Call after setting the particle's [`Active`] to false.
+  pub fn release(&mut self, kind: ParticleKind, entity: Entity) {
+    self.free[kind as usize].push(entity);
+  }
+
+  /// Particles ever spawned, it stops growing once the pool covers the busiest moment so far.
+  pub fn spawned(&self) -> usize {
+    self.spawned.iter().sum()
+  }
+}
+
+/// Hands out [`SpawnSeq`]s.
+#[derive(Debug, Default, Resource)]
+pub struct SpawnSequence {
+  next: u64,
+}
+
+impl SpawnSequence {
+  pub fn take(&mut self) -> SpawnSeq {
+    self.next += 1;
+    SpawnSeq(self.next - 1)
+  }
+}
+
 #[derive(Debug, Resource)]
 pub struct Randoms(pub SmallRng);
 
@@ -355,3 +1661,269 @@ impl DerefMut for DurationWrapper {
     &mut self.0
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::easings::linear;
+
+  fn assert_close(actual: f32, expected: f32) {
+    assert!((actual - expected).abs() < 1e-5, "{} != {}", actual, expected);
+  }
+
+  #[test]
+  fn a_tick_spanning_several_periods_counts_them_and_carries_the_overshoot() {
+    let mut timer = Timer::from_seconds(0.25, true);
+    timer.tick(Duration::from_millis(800));
+    assert!(timer.just_finished());
+    assert_eq!(timer.times_finished(), 3);
+    assert_eq!(timer.elapsed, Duration::from_millis(50));
+
+    // the overshoot counts towards the next period
+    timer.tick(Duration::from_millis(150));
+    assert!(!timer.just_finished());
+    assert_eq!(timer.times_finished(), 0);
+    timer.tick(Duration::from_millis(60));
+    assert_eq!((timer.times_finished(), timer.elapsed), (1, Duration::from_millis(10)));
+
+    // one that doesn't repeat finishes once and stays at its duration
+    let mut once = Timer::from_seconds(0.25, false);
+    once.tick(Duration::from_millis(800));
+    assert_eq!((once.times_finished(), once.elapsed), (1, Duration::from_millis(250)));
+    once.tick(Duration::from_millis(800));
+    assert!(once.finished && !once.just_finished());
+  }
+
+  #[test]
+  fn overlapping_slow_motions_take_the_slowest_and_end_at_raw_time() {
+    let step = Duration::from_millis(100);
+    let long = SlowMotion {
+      factor: 0.6,
+      duration: 1.0,
+      easing: linear,
+    };
+    let short = SlowMotion {
+      factor: 0.1,
+      duration: 0.25,
+      easing: linear,
+    };
+    let mut scale = TimeScale::default();
+
+    scale.start(long);
+    assert_close(scale.advance(step), 0.64);
+    // the later, deeper one wins while it lasts
+    scale.start(short);
+    assert_close(scale.advance(step), 0.46);
+    // it eases out faster and the long one is the slowest again
+    assert_close(scale.advance(step), 0.72);
+    assert_close(scale.advance(step), 0.76);
+    assert_eq!(scale.active().len(), 1);
+
+    for _ in 0..6 {
+      scale.advance(step);
+    }
+    let raw = DurationWrapper(Duration::from_nanos(1_000_000_000 / 60));
+    let factor = scale.advance(*raw);
+    assert!(!scale.is_slowed());
+    assert_eq!(factor, 1.0);
+
+    let mut time = Time::default();
+    time.set_scale(TimeDomain::Gameplay, factor);
+    time.advance(*raw);
+    assert_eq!(*time, *raw);
+  }
+
+  #[test]
+  fn text_indexes_past_the_u16_range() {
+    let mut layer = TextLayer::default();
+    let glyph = Character {
+      tx: 0.0,
+      tx_1: 1.0,
+      ty: 1.0,
+      width: 4.0,
+      height: 4.0,
+      bearing: glam::Vec2::ZERO,
+      advance: 5.0,
+    };
+    layer.characters.insert('a', glyph);
+
+    // 20000 glyphs are 80000 vertices, the last ones don't fit a u16 index
+    layer.build_text(&"a".repeat(20_000), 0.0, 0.0, 1.0, ColorGl::from((255, 255, 255)));
+
+    assert_eq!(layer.vertex_buffer.len(), 80_000);
+    assert_eq!(layer.index_buffer.iter().max(), Some(&79_999));
+  }
+
+  #[test]
+  fn a_bomb_charge_comes_every_2000_points_up_to_three() {
+    let mut bomb = Bomb::default();
+    bomb.accrue(1_999);
+    assert_eq!(bomb.charges, 0);
+    bomb.accrue(2_000);
+    assert_eq!(bomb.charges, 1);
+
+    // the points past the cap don't come back as charges once one is spent
+    bomb.accrue(9_000);
+    assert_eq!(bomb.charges, BOMB_MAX_CHARGES);
+    assert!(bomb.spend());
+    bomb.accrue(9_500);
+    assert_eq!(bomb.charges, BOMB_MAX_CHARGES - 1);
+    bomb.accrue(10_000);
+    assert_eq!(bomb.charges, BOMB_MAX_CHARGES);
+  }
+
+  #[test]
+  fn idling_only_counts_while_it_may_pause() {
+    let second = Duration::from_secs(1);
+    let mut idle = Idle {
+      timeout: Some(Duration::from_secs(3)),
+      ..Default::default()
+    };
+    let gap = |idle: &mut Idle, seconds, counting| (0..seconds).any(|_| idle.update(second, false, counting));
+
+    // input halfway restarts the countdown
+    assert!(!gap(&mut idle, 2, true));
+    assert!(!idle.update(second, true, true));
+    assert!(!gap(&mut idle, 2, true));
+    assert!(gap(&mut idle, 1, true) && idle.auto_paused);
+
+    // paused or on the game over screen nothing counts, and it starts over afterwards
+    assert!(!gap(&mut idle, 10, false) && idle.auto_paused);
+    assert_eq!(idle.idle_for(), Duration::ZERO);
+    assert!(!gap(&mut idle, 2, true) && !idle.auto_paused);
+    assert_eq!(idle.idle_for(), Duration::from_secs(2));
+
+    idle.timeout = None;
+    assert!(!gap(&mut idle, 100, true));
+  }
+
+  #[test]
+  fn bindings_read_back_what_they_write() {
+    let file = "# a mouse player\n1.shoot = mouse:left\n1.bomb = wheel:down, mouse:x2\n1.boost = key:Comma, pad:a\n";
+    let layouts = [KeyBindings::default(), KeyBindings::co_op(), KeyBindings::parse(file).unwrap()];
+
+    for bindings in layouts {
+      assert_eq!(KeyBindings::parse(&bindings.to_string()).unwrap(), bindings);
+    }
+  }
+
+  #[test]
+  fn a_source_bound_twice_is_a_conflict_however_it_is_spelled() {
+    // several sources of different kinds for one action are fine
+    assert!(KeyBindings::parse("1.shoot = key:Space, mouse:left, pad:a, wheel:up").is_ok());
+
+    let conflicts = [
+      "1.shoot = mouse:left\n1.bomb = mouse:left",
+      "1.shoot = key:Space\n2.shoot = key:Space",
+      "1.turn_left = pad:dpleft\n2.turn_left = pad:dpleft",
+      "1.bomb = wheel:up\n1.boost = wheel:up",
+    ];
+    for text in conflicts {
+      let Err(Error::InvalidBindings { line, reason }) = KeyBindings::parse(text) else {
+        panic!("{:?} has no conflict", text);
+      };
+      assert_eq!((line, reason), (2, "source bound twice"), "{:?}", text);
+    }
+    assert!(KeyBindings::default().conflict().is_none());
+    assert!(KeyBindings::co_op().conflict().is_none());
+  }
+
+  #[test]
+  fn a_wheel_pulse_presses_once_per_frame_the_wheel_turned_in() {
+    let bindings = KeyBindings::parse("1.bomb = wheel:up\n1.shoot = wheel:down").unwrap();
+    let mut wheel = crate::input::WheelPulses::default();
+    let mut input = InputState::default();
+    // per frame the wheel notches since the last one and how many ticks it runs
+    let frames = [(vec![1, 1], 3), (vec![-1], 0), (vec![], 2), (vec![1, -1], 1)];
+
+    let mut pressed = Vec::new();
+    for (notches, ticks) in frames {
+      notches.into_iter().for_each(|y| wheel.add(y));
+      for _ in 0..ticks {
+        let (wheel_up, wheel_down) = wheel.take();
+        let mouse = MouseInput {
+          wheel_up,
+          wheel_down,
+          ..Default::default()
+        };
+        input.update(&HashSet::new(), &mouse, &GamepadState::default(), &bindings);
+        let player = input.player(PlayerId(0));
+        pressed.push((player.bomb, player.shoot));
+      }
+    }
+
+    // two notches are one press, the notch of a frame without a tick comes with the next one
+    let expected = [(true, false), (false, false), (false, false), (false, true), (false, false), (true, true)];
+    assert_eq!(pressed, expected);
+  }
+
+  #[test]
+  fn a_corner_exit_goes_through_the_edge_it_is_further_past() {
+    let bounds = WorldBounds {
+      min: glam::vec2(0.0, 0.0),
+      max: glam::vec2(100.0, 50.0),
+    };
+    let edge = |x, y| bounds.crossed_edge(WorldPos::new(x, y));
+    // inside, or right on the edge, it hasn't left
+    assert_eq!(edge(50.0, 25.0), None);
+    assert_eq!(edge(0.0, 0.0), None);
+    assert_eq!(edge(100.0, 50.0), None);
+    // through a side
+    assert_eq!(edge(-1.0, 25.0), Some(Edge::Left));
+    assert_eq!(edge(101.0, 25.0), Some(Edge::Right));
+    assert_eq!(edge(50.0, -1.0), Some(Edge::Bottom));
+    assert_eq!(edge(50.0, 51.0), Some(Edge::Top));
+    // past two at once, the one it is further past wins
+    assert_eq!(edge(-5.0, 52.0), Some(Edge::Left));
+    assert_eq!(edge(-2.0, 55.0), Some(Edge::Top));
+    assert_eq!(edge(103.0, -1.0), Some(Edge::Right));
+    assert_eq!(edge(101.0, -8.0), Some(Edge::Bottom));
+    // right through the corner the top or bottom edge wins, the same every time
+    assert_eq!(edge(-3.0, -3.0), Some(Edge::Bottom));
+    assert_eq!(edge(103.0, 53.0), Some(Edge::Top));
+  }
+
+  #[test]
+  fn a_burst_of_flashes_clamps_once_until_it_calms_down() {
+    let mut guard = FlashGuard {
+      window: 1.0,
+      max_flashes: 3,
+      clamp_duration: 2.0,
+      ..FlashGuard::default()
+    };
+    for _ in 0..3 {
+      assert!(guard.flash());
+      guard.tick(0.25);
+    }
+    assert!(!guard.is_clamped());
+
+    // the fourth within a second clamps, and more while clamped don't count as another activation
+    assert!(!guard.flash());
+    guard.tick(0.5);
+    assert!(!guard.flash());
+    assert_eq!(guard.activations, 1);
+
+    // each flash pushes the end out to two seconds after it
+    guard.tick(1.75);
+    assert!(guard.is_clamped());
+    guard.tick(0.25);
+    assert!(!guard.is_clamped());
+    // the burst is forgotten by now
+    assert!(guard.flash());
+    assert_eq!(guard.activations, 1);
+  }
+
+  #[test]
+  fn flashes_spread_out_over_the_window_never_clamp() {
+    let mut guard = FlashGuard {
+      window: 1.0,
+      max_flashes: 2,
+      ..FlashGuard::default()
+    };
+    for _ in 0..20 {
+      assert!(guard.flash());
+      guard.tick(0.75);
+    }
+    assert_eq!(guard.activations, 0);
+  }
+}
@@ -0,0 +1,281 @@
+use crate::{
+  components::Attack,
+  environment::{RUMBLE_BURST, RUMBLE_DUTY_CYCLE, SHAKE_AMPLITUDE_DEATH},
+  events::{GameEvents, PickupEvent},
+  resources::{GameState, Shake, Time, TimeDomain},
+};
+use bevy_ecs::prelude::*;
+
+/// How hard the low and high frequency motors run, 0 to 1.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Motors {
+  pub low: f32,
+  pub high: f32,
+}
+
+impl Motors {
+  pub const OFF: Self = Self { low: 0.0, high: 0.0 };
+
+  pub fn is_off(self) -> bool {
+    self.low <= 0.0 && self.high <= 0.0
+  }
+
+  fn max(self, other: Self) -> Self {
+    Self {
+      low: self.low.max(other.low),
+      high: self.high.max(other.high),
+    }
+  }
+
+  fn scale(self, k: f32) -> Self {
+    Self {
+      low: (self.low * k).clamp(0.0, 1.0),
+      high: (self.high * k).clamp(0.0, 1.0),
+    }
+  }
+}
+
+/// A pulse on the motors for `duration` raw seconds, fading out over it when it `decays`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RumbleEffect {
+  pub motors: Motors,
+  pub duration: f32,
+  pub decays: bool,
+}
+
+impl RumbleEffect {
+  pub const PICKUP: Self = Self {
+    motors: Motors { low: 0.0, high: 0.2 },
+    duration: 0.05,
+    decays: false,
+  };
+  pub const WALL: Self = Self {
+    motors: Motors { low: 0.5, high: 0.3 },
+    duration: 0.15,
+    decays: true,
+  };
+  /// The hit itself, the camera shake carries the long tail, see [`rumble_system`].
+  pub const DEATH: Self = Self {
+    motors: Motors { low: 0.6, high: 0.6 },
+    duration: 0.25,
+    decays: true,
+  };
+
+  /// Light, heavier volleys a bit stronger and rapid fire softer so it doesn't buzz.
+  pub fn shot(attack: Attack) -> Self {
+    let high = match attack {
+      Attack::Rapid => 0.1,
+      Attack::Neutral | Attack::Homing => 0.15,
+      Attack::Double => 0.2,
+      Attack::Triple => 0.25,
+    };
+    Self {
+      motors: Motors { low: 0.0, high },
+      duration: 0.06,
+      decays: false,
+    }
+  }
+
+  fn motors_at(&self, time: f32) -> Motors {
+    if self.decays {
+      self.motors.scale(1.0 - time / self.duration)
+    } else {
+      self.motors
+    }
+  }
+}
+
+/// Drives the motors of a controller, the connected controllers in the game and a mock in the tests.
+pub trait Rumbler {
+  fn rumble(&mut self, motors: Motors);
+  fn stop(&mut self);
+}
+
+/// Every rumble effect playing, advanced in raw time by [`rumble_system`]. The strongest of them sets
+/// the motors, effects don't add up.
+#[derive(Debug, Resource)]
+pub struct Rumble {
+  /// Scales every effect, 0 turns rumble off. The `rumble` setting in percent.
+  pub intensity: f32,
+  active: Vec<(RumbleEffect, f32)>,
+  /// Seconds the motors ran past [`RUMBLE_DUTY_CYCLE`], see [`Self::advance`].
+  busy: f32,
+  muted: bool,
+  output: Motors,
+}
+
+impl Default for Rumble {
+  fn default() -> Self {
+    Self {
+      intensity: 1.0,
+      active: Vec::new(),
+      busy: 0.0,
+      muted: false,
+      output: Motors::OFF,
+    }
+  }
+}
+
+impl Rumble {
+  pub fn play(&mut self, effect: RumbleEffect) {
+    self.active.push((effect, 0.0));
+  }
+
+  /// Advances every effect by `dt` raw seconds and mixes them with `shake`, a level from 0 to 1 the
+  /// low motor follows. Running the motors fills a bucket that drains while they rest, once
+  /// [`RUMBLE_BURST`] seconds pile up they stay off until half of it drained. That keeps them from
+  /// running more than [`RUMBLE_DUTY_CYCLE`] of the time.
+  pub fn advance(&mut self, dt: f32, shake: f32) -> Motors {
+    for (_, time) in &mut self.active {
+      *time += dt;
+    }
+    self.active.retain(|(effect, time)| *time < effect.duration);
+    let mixed = self
+      .active
+      .iter()
+      .map(|(effect, time)| effect.motors_at(*time))
+      .fold(Motors { low: shake, high: 0.0 }, Motors::max)
+      .scale(self.intensity);
+
+    if self.busy >= RUMBLE_BURST {
+      self.muted = true;
+    } else if self.busy <= RUMBLE_BURST / 2.0 {
+      self.muted = false;
+    }
+    self.output = if self.muted { Motors::OFF } else { mixed };
+    self.busy = if self.output.is_off() {
+      (self.busy - dt * RUMBLE_DUTY_CYCLE).max(0.0)
+    } else {
+      self.busy + dt * (1.0 - RUMBLE_DUTY_CYCLE)
+    };
+    self.output
+  }
+
+  /// Drops every effect, the motors are off from the next [`Self::drive`] on.
+  pub fn stop(&mut self) {
+    self.active.clear();
+    self.output = Motors::OFF;
+  }
+
+  pub fn drive(&self, device: &mut impl Rumbler) {
+    if self.output.is_off() {
+      device.stop();
+    } else {
+      device.rumble(self.output);
+    }
+  }
+}
+
+/// Plays a rumble for the events of the tick, and rumbles along with the camera shake so the death
+/// rumble decays with the death shake. Stops on pause and once the game over screen starts.
+pub fn rumble_system(
+  mut rumble: ResMut<Rumble>,
+  mut event_reader: EventReader<GameEvents>,
+  mut pickups: EventReader<PickupEvent>,
+  shake: Res<Shake>,
+  state: Res<GameState>,
+  time: Res<Time>,
+) {
+  // the effects domain turns to background time with the game over screen
+  if *state == GameState::Paused || time.effects_domain == TimeDomain::Background {
+    event_reader.clear();
+    pickups.clear();
+    rumble.stop();
+    return;
+  }
+
+  for event in event_reader.iter() {
+    let effect = match event {
+      GameEvents::Shot(attack) => RumbleEffect::shot(*attack),
+      GameEvents::PlayerDeath(..) | GameEvents::BombDetonated => RumbleEffect::DEATH,
+      GameEvents::WallImpact => RumbleEffect::WALL,
+      GameEvents::EnemyDeath(..)
+      | GameEvents::AmmoChanged
+      | GameEvents::PlayerContact { .. }
+      | GameEvents::Flash { .. }
+      | GameEvents::Shake(_)
+      | GameEvents::SlowMotion(_) => continue,
+    };
+    rumble.play(effect);
+  }
+  for _ in pickups.iter() {
+    rumble.play(RumbleEffect::PICKUP);
+  }
+  let shaking = (shake.amplitude() / SHAKE_AMPLITUDE_DEATH).min(1.0);
+  rumble.advance(time.raw().as_secs_f32(), shaking);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const DT: f32 = 1.0 / 60.0;
+
+  #[derive(Default)]
+  struct MockRumbler {
+    calls: Vec<Option<Motors>>,
+  }
+
+  impl Rumbler for MockRumbler {
+    fn rumble(&mut self, motors: Motors) {
+      self.calls.push(Some(motors));
+    }
+
+    fn stop(&mut self) {
+      self.calls.push(None);
+    }
+  }
+
+  #[test]
+  fn the_strongest_effect_sets_the_motors_until_it_runs_out() {
+    let mut rumble = Rumble::default();
+    rumble.play(RumbleEffect::shot(Attack::Neutral));
+    rumble.play(RumbleEffect::DEATH);
+
+    let first = rumble.advance(DT, 0.0);
+    assert!(first.low > 0.5 && first.high > 0.5, "{:?}", first);
+
+    // the death pulse decays, the shot is over after a few ticks and the pulse soon after
+    let mut advance = |ticks| (0..ticks).map(|_| rumble.advance(DT, 0.0)).last().unwrap();
+    assert!(advance(3).high < first.high);
+    assert!(advance(15).is_off());
+  }
+
+  #[test]
+  fn the_shake_drives_the_low_motor_and_the_intensity_scales_everything() {
+    let mut rumble = Rumble {
+      intensity: 0.5,
+      ..Default::default()
+    };
+    assert_eq!(rumble.advance(DT, 0.8), Motors { low: 0.4, high: 0.0 });
+
+    rumble.intensity = 0.0;
+    rumble.play(RumbleEffect::DEATH);
+    assert!(rumble.advance(DT, 1.0).is_off());
+  }
+
+  #[test]
+  fn the_motors_never_run_past_the_duty_cycle() {
+    let mut rumble = Rumble::default();
+    let ticks = (60.0 / DT) as usize;
+    let running = (0..ticks).filter(|_| !rumble.advance(DT, 1.0).is_off()).count();
+
+    let duty = running as f32 / ticks as f32;
+    assert!(duty <= RUMBLE_DUTY_CYCLE + 0.05, "ran {:.2} of the time", duty);
+    assert!(duty > 0.0);
+  }
+
+  #[test]
+  fn stopping_turns_the_device_off_right_away() {
+    let mut rumble = Rumble::default();
+    let mut device = MockRumbler::default();
+    rumble.play(RumbleEffect::WALL);
+    rumble.advance(DT, 0.0);
+    rumble.drive(&mut device);
+
+    rumble.stop();
+    rumble.drive(&mut device);
+
+    assert!(matches!(device.calls[..], [Some(_), None]));
+  }
+}
@@ -0,0 +1,313 @@
+use crate::{
+  color::ColorGl,
+  components::*,
+  environment::*,
+  events::ShakeRequest,
+  format::{write_abbreviated, write_score, write_time},
+  output_files::write_atomic,
+  resources::*,
+  units::{Angle, WorldPos},
+};
+use bevy_ecs::prelude::*;
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use std::{fmt, io::Write, path::Path, time::Duration};
+
+const STRESS_SEED: u64 = 0x5eed_b17e;
+const STRESS_DURATION: f32 = 60.0;
+const STRESS_DEATH_ACTIONS: [DeathAction; 4] = [
+  DeathAction::Splat,
+  DeathAction::Fizzle,
+  DeathAction::RadialBurst { count: 6, speed: 150.0 },
+  DeathAction::Nothing,
+];
+const STRESS_TEXT: &str = "the quick brown fox jumps over the lazy dog 0123456789 !?#%&*()[]{}<>";
+
+/// Deterministically ramps up the load over [`STRESS_DURATION`] seconds of raw time.
+#[derive(Resource)]
+pub struct StressScenario {
+  elapsed: Duration,
+  rng: SmallRng,
+  wave_timer: Timer,
+}
+
+impl Default for StressScenario {
+  fn default() -> Self {
+    Self {
+      elapsed: Duration::default(),
+      rng: SmallRng::seed_from_u64(STRESS_SEED),
+      wave_timer: Timer::from_seconds(0.5, true),
+    }
+  }
+}
+
+impl StressScenario {
+  pub fn finished(&self) -> bool {
+    self.elapsed.as_secs_f32() >= STRESS_DURATION
+  }
+
+  fn progress(&self) -> f32 {
+    (self.elapsed.as_secs_f32() / STRESS_DURATION).min(1.0)
+  }
+}
+
+pub fn stress_scenario_system(
+  mut commands: Commands,
+  mut scenario: ResMut<StressScenario>,
+  mut shake: ResMut<Shake>,
+  time: Res<Time>,
+  bounds: Res<WorldBounds>,
+) {
+  scenario.elapsed += time.raw();
+  scenario.wave_timer.tick(time.raw());
+  if !shake.is_shaking() {
+    shake.start(ShakeRequest::DEATH);
+  }
+
+  if !scenario.wave_timer.just_finished() {
+    return;
+  }
+
+  let progress = scenario.progress();
+  let StressScenario { rng, .. } = &mut *scenario;
+  let mut random_position = || {
+    WorldPos::new(
+      rng.gen_range(bounds.min.x..bounds.max.x),
+      rng.gen_range(bounds.min.y..bounds.max.y),
+    )
+  };
+
+  for i in 0..(20.0 + progress * 300.0) as usize {
+    let position = random_position();
+    let rotation = Angle::from_radians(position.to_render().x);
+    commands
+      .spawn_empty()
+      .insert(Transform {
+        position,
+        z: Z_INDEX_PLAYER,
+        rotation,
+        ..Default::default()
+      })
+      .insert(Projectile::bundle(rotation.forward_by(200.0)))
+      .insert(STRESS_DEATH_ACTIONS[i % STRESS_DEATH_ACTIONS.len()]);
+  }
+
+  for _ in 0..(2.0 + progress * 30.0) as usize {
+    let position = random_position();
+    for i in 0..10 {
+      commands
+        .spawn_empty()
+        .insert(ScopeTag(GameScope::Persistent))
+        .insert(Transform {
+          position,
+          z: Z_INDEX_PLAYER,
+          rotation: Angle::from_radians(i as f32 * std::f32::consts::PI / 5.0),
+          ..Default::default()
+        })
+        .insert(ExplosionEffect::bundle(
+          ColorGl::from(RGB_COLOR_PLAYER),
+          Interpolation::new(vec![(150.0, 0.0), (8.0, 0.0), (3.0, 0.0)], 0.5, InterpolationMode::Once),
+        ));
+    }
+  }
+
+  for _ in 0..(2.0 + progress * 20.0) as usize {
+    commands
+      .spawn_empty()
+      .insert(ScopeTag(GameScope::Run))
+      .insert(AmmoPickup {
+        rotation_speed: std::f32::consts::PI,
+        timer: Timer::from_seconds(0.15, false),
+      })
+      .insert((Velocity::Heading(15.0), AngularVelocity(std::f32::consts::PI)))
+      .insert(Transform {
+        position: random_position(),
+        z: Z_INDEX_AMMO_PICKUP,
+        ..Default::default()
+      })
+      .insert(Collider {
+        radius: AMMO_PICKUP_RADIUS,
+      });
+  }
+
+  for _ in 0..(1.0 + progress * 10.0) as usize {
+    commands
+      .spawn_empty()
+      .insert(ScopeTag(GameScope::Persistent))
+      .insert(Text {
+        text: String::from(STRESS_TEXT),
+        timer: Timer::from_seconds(0.5, false),
+      })
+      .insert(Transform {
+        position: random_position(),
+        ..Default::default()
+      });
+  }
+}
+
+pub struct FrameSample {
+  pub frame_time: Duration,
+  pub sim_time: Duration,
+  pub render_time: Duration,
+  pub vertices: usize,
+  pub entities: u32,
+}
+
+#[derive(Default)]
+pub struct StressRecorder {
+  samples: Vec<FrameSample>,
+}
+
+impl StressRecorder {
+  pub fn record(&mut self, sample: FrameSample) {
+    self.samples.push(sample);
+  }
+
+  pub fn write_csv(&self, path: &Path) -> std::io::Result<()> {
+    write_atomic(path, |file| self.write_rows(file))
+  }
+
+  fn write_rows(&self, file: &mut impl Write) -> std::io::Result<()> {
+    writeln!(file, "frame,frame_ms,sim_ms,render_ms,vertices,entities")?;
+    for (i, sample) in self.samples.iter().enumerate() {
+      writeln!(
+        file,
+        "{},{:.3},{:.3},{:.3},{},{}",
+        i,
+        sample.frame_time.as_secs_f64() * 1000.0,
+        sample.sim_time.as_secs_f64() * 1000.0,
+        sample.render_time.as_secs_f64() * 1000.0,
+        sample.vertices,
+        sample.entities
+      )?;
+    }
+    Ok(())
+  }
+
+  pub fn summary(&self) -> StressSummary {
+    let frame_times = self
+      .samples
+      .iter()
+      .map(|sample| sample.frame_time.as_secs_f32() * 1000.0)
+      .collect::<Vec<_>>();
+
+    StressSummary {
+      frames: self.samples.len() as u64,
+      duration: self.samples.iter().map(|sample| sample.frame_time).sum(),
+      avg_ms: average(&frame_times),
+      p95_ms: percentile(&frame_times, 0.95),
+      p99_ms: percentile(&frame_times, 0.99),
+      peak_vertices: self.samples.iter().map(|sample| sample.vertices).max().unwrap_or_default(),
+      peak_entities: self.samples.iter().map(|sample| sample.entities).max().unwrap_or_default(),
+    }
+  }
+}
+
+pub struct StressSummary {
+  pub frames: u64,
+  pub duration: Duration,
+  pub avg_ms: f32,
+  pub p95_ms: f32,
+  pub p99_ms: f32,
+  pub peak_vertices: usize,
+  pub peak_entities: u32,
+}
+
+impl fmt::Display for StressSummary {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "frames:        ")?;
+    write_score(f, self.frames)?;
+    write!(f, " in ")?;
+    write_time(f, self.duration)?;
+    writeln!(f)?;
+    writeln!(f, "avg frame:     {:.3}ms", self.avg_ms)?;
+    writeln!(f, "p95 frame:     {:.3}ms", self.p95_ms)?;
+    writeln!(f, "p99 frame:     {:.3}ms", self.p99_ms)?;
+    write!(f, "peak vertices: ")?;
+    write_score(f, self.peak_vertices as u64)?;
+    write!(f, " (")?;
+    write_abbreviated(f, self.peak_vertices as u64)?;
+    writeln!(f, ")")?;
+    write!(f, "peak entities: ")?;
+    write_score(f, self.peak_entities as u64)
+  }
+}
+
+pub fn average(values: &[f32]) -> f32 {
+  if values.is_empty() {
+    return 0.0;
+  }
+  values.iter().sum::<f32>() / values.len() as f32
+}
+
+/// Nearest-rank percentile, `p` in [0, 1].
+pub fn percentile(values: &[f32], p: f32) -> f32 {
+  if values.is_empty() {
+    return 0.0;
+  }
+  let mut sorted = values.to_vec();
+  sorted.sort_by(f32::total_cmp);
+  let rank = (p.clamp(0.0, 1.0) * sorted.len() as f32).ceil() as usize;
+  sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn frame(ms: u64, vertices: usize, entities: u32) -> FrameSample {
+    FrameSample {
+      frame_time: Duration::from_millis(ms),
+      sim_time: Duration::ZERO,
+      render_time: Duration::ZERO,
+      vertices,
+      entities,
+    }
+  }
+
+  #[test]
+  fn no_samples_are_all_zero() {
+    assert_eq!(average(&[]), 0.0);
+    assert_eq!(percentile(&[], 0.5), 0.0);
+    let summary = StressRecorder::default().summary();
+    assert_eq!((summary.frames, summary.duration), (0, Duration::ZERO));
+    assert_eq!((summary.avg_ms, summary.p95_ms, summary.p99_ms), (0.0, 0.0, 0.0));
+    assert_eq!((summary.peak_vertices, summary.peak_entities), (0, 0));
+  }
+
+  #[test]
+  fn a_single_sample_is_every_statistic() {
+    assert_eq!(average(&[4.0]), 4.0);
+    for p in [0.0, 0.5, 0.99, 1.0] {
+      assert_eq!(percentile(&[4.0], p), 4.0);
+    }
+  }
+
+  #[test]
+  fn percentiles_take_the_nearest_rank() {
+    // 1 to 100 in no particular order
+    let values = (1..=100).map(|i| ((i * 37) % 100 + 1) as f32).collect::<Vec<_>>();
+    assert_eq!(average(&values), 50.5);
+    assert_eq!(percentile(&values, 0.5), 50.0);
+    assert_eq!(percentile(&values, 0.99), 99.0);
+    assert_eq!(percentile(&values, 1.0), 100.0);
+    assert_eq!(percentile(&values, 0.0), 1.0);
+    // the rank rounds up, never between two samples
+    let four = [10.0, 20.0, 30.0, 40.0];
+    assert_eq!(percentile(&four, 0.5), 20.0);
+    assert_eq!(percentile(&four, 0.51), 30.0);
+    assert_eq!(percentile(&four, 0.99), 40.0);
+  }
+
+  #[test]
+  fn the_summary_covers_every_frame() {
+    let mut recorder = StressRecorder::default();
+    for (ms, vertices, entities) in [(10, 300, 5), (30, 100, 9), (20, 200, 7)] {
+      recorder.record(frame(ms, vertices, entities));
+    }
+    let summary = recorder.summary();
+    assert_eq!((summary.frames, summary.duration), (3, Duration::from_millis(60)));
+    assert!((summary.avg_ms - 20.0).abs() < 1e-3);
+    assert!((summary.p99_ms - 30.0).abs() < 1e-3);
+    assert_eq!((summary.peak_vertices, summary.peak_entities), (300, 9));
+  }
+}
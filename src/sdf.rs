@@ -0,0 +1,157 @@
+const FAR: f64 = 1e20;
+
+/// Squared euclidean distance transform of a sampled function along one line (Felzenszwalb and
+/// Huttenlocher). `f` holds 0 for feature pixels and [`FAR`] everywhere else.
+fn transform_line(f: &[f64], out: &mut [f64]) {
+  let n = f.len();
+  let mut v = vec![0usize; n];
+  let mut z = vec![0f64; n + 1];
+  let parabola = |q: usize, p: usize| ((f[q] + (q * q) as f64) - (f[p] + (p * p) as f64)) / (2 * q - 2 * p) as f64;
+
+  let mut k = 0;
+  z[0] = -FAR;
+  z[1] = FAR;
+  for q in 1..n {
+    let mut s = parabola(q, v[k]);
+    while s <= z[k] {
+      k -= 1;
+      s = parabola(q, v[k]);
+    }
+    k += 1;
+    v[k] = q;
+    z[k] = s;
+    z[k + 1] = FAR;
+  }
+
+  k = 0;
+  for (q, out) in out.iter_mut().enumerate() {
+    while z[k + 1] < q as f64 {
+      k += 1;
+    }
+    *out = (q as f64 - v[k] as f64).powi(2) + f[v[k]];
+  }
+}
+
+fn squared_distances(grid: &mut [f64], width: usize, height: usize) {
+  let mut line = vec![0f64; width.max(height)];
+  let mut out = vec![0f64; width.max(height)];
+
+  for x in 0..width {
+    for y in 0..height {
+      line[y] = grid[y * width + x];
+    }
+    transform_line(&line[..height], &mut out[..height]);
+    for y in 0..height {
+      grid[y * width + x] = out[y];
+    }
+  }
+
+  for row in grid.chunks_mut(width) {
+    line[..width].copy_from_slice(row);
+    transform_line(&line[..width], &mut out[..width]);
+    row.copy_from_slice(&out[..width]);
+  }
+}
+
+/// Turns a coverage bitmap into a signed distance field padded by `spread` pixels on every side.
+/// The edge maps to 128, values above are inside the glyph and `spread` pixels away saturate.
+pub fn distance_field(bitmap: &[u8], width: usize, height: usize, pitch: usize, spread: usize) -> Vec<u8> {
+  let padded_width = width + 2 * spread;
+  let padded_height = height + 2 * spread;
+  let is_inside = |x: usize, y: usize| {
+    x >= spread
+      && y >= spread
+      && x - spread < width
+      && y - spread < height
+      && bitmap[(y - spread) * pitch + x - spread] >= 128
+  };
+
+  let mut outside = vec![0f64; padded_width * padded_height];
+  let mut inside = vec![0f64; padded_width * padded_height];
+  for y in 0..padded_height {
+    for x in 0..padded_width {
+      let i = y * padded_width + x;
+      if is_inside(x, y) {
+        outside[i] = 0.0;
+        inside[i] = FAR;
+      } else {
+        outside[i] = FAR;
+        inside[i] = 0.0;
+      }
+    }
+  }
+  squared_distances(&mut outside, padded_width, padded_height);
+  squared_distances(&mut inside, padded_width, padded_height);
+
+  outside
+    .iter()
+    .zip(&inside)
+    .map(|(outside, inside)| {
+      let distance = outside.sqrt() - inside.sqrt();
+      (128.0 - distance * 127.0 / spread as f64).round().clamp(0.0, 255.0) as u8
+    })
+    .collect()
+}
+
+/// Where a glyph quad lands in an atlas laid out left to right, as `(tx, tx_1, ty)`.
+pub fn atlas_uv(x: i32, width: i32, height: i32, atlas_width: i32, atlas_height: i32) -> (f32, f32, f32) {
+  (
+    x as f32 / atlas_width as f32,
+    (x + width) as f32 / atlas_width as f32,
+    height as f32 / atlas_height as f32,
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// A 4x4 square, padded by 4 on every side. Its pitch has two bytes past the row that don't count.
+  fn square() -> Vec<u8> {
+    let mut bitmap = vec![0; 6 * 4];
+    for row in bitmap.chunks_mut(6) {
+      row[..4].fill(255);
+    }
+    distance_field(&bitmap, 4, 4, 6, 4)
+  }
+
+  #[test]
+  fn inside_is_above_the_edge_and_outside_below() {
+    let field = square();
+    assert_eq!(field.len(), 12 * 12);
+    let at = |x: usize, y: usize| field[y * 12 + x];
+    // one pixel to either side of the left edge
+    assert_eq!(at(3, 5), 96);
+    assert_eq!(at(4, 5), 160);
+    // deeper in gets further from 128 both ways
+    assert_eq!(at(5, 5), 192);
+    assert_eq!(at(1, 5), 33);
+    // the padding past the row is outside, not part of the square
+    assert!(at(8, 5) < 128);
+    // beyond the spread it saturates
+    assert_eq!(at(0, 0), 0);
+  }
+
+  #[test]
+  fn the_field_is_symmetric_around_a_symmetric_glyph() {
+    let field = square();
+    for y in 0..12 {
+      for x in 0..12 {
+        assert_eq!(field[y * 12 + x], field[y * 12 + 11 - x], "{}, {}", x, y);
+        assert_eq!(field[y * 12 + x], field[x * 12 + y], "{}, {}", x, y);
+      }
+    }
+  }
+
+  #[test]
+  fn a_blank_bitmap_is_outside_everywhere() {
+    assert!(distance_field(&[0; 9], 3, 3, 3, 2).iter().all(|&value| value == 0));
+    assert_eq!(distance_field(&[], 0, 0, 0, 2), vec![0; 16]);
+  }
+
+  #[test]
+  fn atlas_uvs_span_the_glyph() {
+    assert_eq!(atlas_uv(0, 16, 32, 64, 64), (0.0, 0.25, 0.5));
+    assert_eq!(atlas_uv(48, 16, 64, 64, 64), (0.75, 1.0, 1.0));
+  }
+}
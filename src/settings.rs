@@ -0,0 +1,144 @@
+use crate::{
+  camera::CameraMode,
+  environment::{SCREEN_RENDER_HEIGHT, SCREEN_RENDER_WIDTH, TICK_RATE},
+  error::{find_asset, Error},
+  profiles::{load_profile_file, Profile},
+};
+use std::time::Duration;
+
+pub const SETTINGS_FILE: &str = "settings.toml";
+/// What a profile's [`SETTINGS_FILE`] may set.
+const PLAYER_KEYS: [&str; 3] = ["rumble", "idle_pause", "camera"];
+
+/// Window and rendering options read once at startup, see [`Settings::parse`] for the format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Settings {
+  pub window_width: u32,
+  pub window_height: u32,
+  /// Borderless at the desktop resolution.
+  pub fullscreen: bool,
+  pub vsync: bool,
+  /// The low res framebuffer is `SCREEN_RENDER_*` divided by this, larger means chunkier pixels.
+  pub internal_scale: u32,
+  /// Text goes through the post-processing too, see [`crate::resources::PostFx::text_in_scene`].
+  pub post_fx_text: bool,
+  /// Simulation ticks per second, see [`Settings::fixed_timestep`].
+  pub tick_rate: u32,
+  /// Controller rumble strength in percent, 0 turns it off.
+  pub rumble: u32,
+  /// Seconds without input until the game pauses itself, 0 never does.
+  pub idle_pause: u32,
+  /// `fixed` or `smart`, see [`CameraMode`].
+  pub camera: CameraMode,
+}
+
+impl Default for Settings {
+  fn default() -> Self {
+    Self {
+      window_width: SCREEN_RENDER_WIDTH,
+      window_height: SCREEN_RENDER_HEIGHT,
+      fullscreen: false,
+      vsync: true,
+      internal_scale: 2,
+      post_fx_text: false,
+      tick_rate: TICK_RATE,
+      rumble: 100,
+      idle_pause: 60,
+      camera: CameraMode::Fixed,
+    }
+  }
+}
+
+impl Settings {
+  /// A flat subset of TOML, one `key = value` per line with `#` comments, e.g. `vsync = false`. Keys
+  /// left out keep their default.
+  pub fn parse(text: &str) -> Result<Self, Error> {
+    Self::default().overridden(text, true)
+  }
+
+  /// `self` with the keys `text` sets, in the format of [`Self::parse`]. Without `machine` the keys of
+  /// the machine, the window, rendering and tick rate, are refused, a profile only sets the player's.
+  pub fn overridden(self, text: &str, machine: bool) -> Result<Self, Error> {
+    let mut settings = self;
+    for (i, line) in text.lines().enumerate() {
+      let line = line.split('#').next().unwrap_or_default().trim();
+      if line.is_empty() {
+        continue;
+      }
+      let invalid = |reason| Error::InvalidSettings { line: i + 1, reason };
+      let (key, value) = line.split_once('=').ok_or_else(|| invalid("expected key = value"))?;
+      if !machine && !PLAYER_KEYS.contains(&key.trim()) {
+        return Err(invalid("not a setting of the player, it goes in the shared settings"));
+      }
+      let size = || value.trim().parse::<u32>().ok().filter(|size| *size > 0);
+      let flag = || value.trim().parse::<bool>().ok();
+      match key.trim() {
+        "window_width" => settings.window_width = size().ok_or_else(|| invalid("expected a positive integer"))?,
+        "window_height" => settings.window_height = size().ok_or_else(|| invalid("expected a positive integer"))?,
+        "fullscreen" => settings.fullscreen = flag().ok_or_else(|| invalid("expected true or false"))?,
+        "vsync" => settings.vsync = flag().ok_or_else(|| invalid("expected true or false"))?,
+        "post_fx_text" => settings.post_fx_text = flag().ok_or_else(|| invalid("expected true or false"))?,
+        "tick_rate" => {
+          settings.tick_rate = size()
+            .filter(|rate| *rate <= 1000)
+            .ok_or_else(|| invalid("expected a positive integer up to 1000"))?
+        }
+        "rumble" => {
+          settings.rumble = value
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|percent| *percent <= 100)
+            .ok_or_else(|| invalid("expected a percentage from 0 to 100"))?
+        }
+        "idle_pause" => {
+          settings.idle_pause = value.trim().parse::<u32>().map_err(|_| invalid("expected seconds, 0 or more"))?
+        }
+        "camera" => {
+          settings.camera = match value.trim() {
+            "fixed" => CameraMode::Fixed,
+            "smart" => CameraMode::Smart,
+            _ => return Err(invalid("expected fixed or smart")),
+          }
+        }
+        "internal_scale" => {
+          settings.internal_scale = size()
+            .filter(|scale| SCREEN_RENDER_HEIGHT / scale > 0)
+            .ok_or_else(|| invalid("expected a positive integer smaller than the screen"))?
+        }
+        _ => return Err(invalid("unknown key")),
+      }
+    }
+    Ok(settings)
+  }
+
+  /// Reads [`SETTINGS_FILE`] if there is one, then the profile's on top of it. A broken file is
+  /// reported and replaced by the defaults as a whole, half applied settings are harder to make sense
+  /// of.
+  pub fn load(profile: &Profile) -> Self {
+    let shared = match find_asset(SETTINGS_FILE) {
+      Ok(path) => match std::fs::read_to_string(path).map_err(Error::from).and_then(|text| Self::parse(&text)) {
+        Ok(settings) => settings,
+        Err(e) => {
+          eprintln!("{}, falling back to default settings", e);
+          Self::default()
+        }
+      },
+      Err(_) => Self::default(),
+    };
+    load_profile_file(profile, SETTINGS_FILE, |text| shared.overridden(text, false)).unwrap_or(shared)
+  }
+
+  /// What every tick advances the simulation by, frames in between are interpolated.
+  pub fn fixed_timestep(&self) -> Duration {
+    Duration::from_secs(1) / self.tick_rate
+  }
+
+  /// Pixels of the low res framebuffer.
+  pub fn internal_size(&self) -> (i32, i32) {
+    (
+      (SCREEN_RENDER_WIDTH / self.internal_scale) as i32,
+      (SCREEN_RENDER_HEIGHT / self.internal_scale) as i32,
+    )
+  }
+}
@@ -0,0 +1,95 @@
+use crate::{
+  environment::{SHADER_DIR, SHADER_POLL_INTERVAL},
+  error::find_asset,
+};
+use std::{
+  path::{Path, PathBuf},
+  time::{Instant, SystemTime},
+};
+
+/// The shader programs, each linked from a vertex and a fragment shader file in [`SHADER_DIR`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Program {
+  LowRes,
+  Scene,
+  Text,
+  SdfText,
+}
+
+impl Program {
+  pub const ALL: [Program; 4] = [Program::LowRes, Program::Scene, Program::Text, Program::SdfText];
+
+  /// The asset paths of the vertex and the fragment shader.
+  pub fn files(self) -> [String; 2] {
+    let (vertex, fragment) = match self {
+      Program::LowRes => ("fbo.vert", "fbo.frag"),
+      Program::Scene => ("scene.vert", "scene.frag"),
+      Program::Text => ("text.vert", "text.frag"),
+      Program::SdfText => ("text.vert", "sdf_text.frag"),
+    };
+    [vertex, fragment].map(|file| format!("{}/{}", SHADER_DIR, file))
+  }
+
+  /// The vertex arrays are set up once with these locations, a reloaded program has to keep them.
+  pub fn attributes(self) -> &'static [&'static str] {
+    match self {
+      Program::LowRes => &["Position", "TexCoords"],
+      Program::Scene => &["Transform", "Color", "Position"],
+      Program::Text | Program::SdfText => &["PosTex", "Color"],
+    }
+  }
+}
+
+/// Checks the loose shader files for changes every [`SHADER_POLL_INTERVAL`] seconds. Shaders only in
+/// the pack or the binary can't change, and files that show up later aren't picked up before a restart.
+pub struct ShaderWatcher {
+  files: Vec<(PathBuf, Option<SystemTime>)>,
+  last_poll: Instant,
+}
+
+impl ShaderWatcher {
+  pub fn new() -> Self {
+    let mut files = Vec::new();
+    for program in Program::ALL {
+      for path in program.files().iter().filter_map(|file| find_asset(file).ok()) {
+        if !files.iter().any(|(watched, _)| *watched == path) {
+          let modified = modified(&path);
+          files.push((path, modified));
+        }
+      }
+    }
+
+    Self {
+      files,
+      last_poll: Instant::now(),
+    }
+  }
+
+  /// Whether a file was modified since the last poll, false between polls.
+  pub fn changed(&mut self) -> bool {
+    if self.last_poll.elapsed().as_secs_f32() < SHADER_POLL_INTERVAL {
+      return false;
+    }
+    self.last_poll = Instant::now();
+
+    let mut changed = false;
+    for (path, last_modified) in &mut self.files {
+      let modified = modified(path);
+      if modified != *last_modified {
+        *last_modified = modified;
+        changed = true;
+      }
+    }
+    changed
+  }
+}
+
+impl Default for ShaderWatcher {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+  std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
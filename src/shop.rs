@@ -0,0 +1,297 @@
+use crate::{
+  components::Player,
+  environment::{SCORE_PER_SKILL_POINT, UPGRADES_FILE, UPGRADE_MAX_LEVEL},
+  error::Error,
+  events::GameEvents,
+  modifiers::Modifiers,
+  output_files::write_atomic,
+  profiles::Profile,
+  resources::Score,
+  stats::RunStats,
+};
+use bevy_ecs::prelude::*;
+use std::{io::Write, path::PathBuf};
+
+/// What skill points buy, each level stacking on the build of [`Modifiers`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Upgrade {
+  BoostMax,
+  AmmoMax,
+  MovementSpeed,
+}
+
+impl Upgrade {
+  pub const ALL: [Upgrade; 3] = [Upgrade::BoostMax, Upgrade::AmmoMax, Upgrade::MovementSpeed];
+
+  /// The key in [`UPGRADES_FILE`].
+  pub fn name(self) -> &'static str {
+    match self {
+      Upgrade::BoostMax => "boost_max",
+      Upgrade::AmmoMax => "ammo_max",
+      Upgrade::MovementSpeed => "movement_speed",
+    }
+  }
+
+  /// Skill points the level after `level` costs.
+  pub fn cost(self, level: u32) -> u64 {
+    100 * (level as u64 + 1)
+  }
+}
+
+/// The upgrades the profile bought and the skill points it has left, kept in its [`UPGRADES_FILE`].
+#[derive(Debug, Default, Clone, PartialEq, Resource)]
+pub struct PlayerUpgrades {
+  levels: [u32; Upgrade::ALL.len()],
+  pub skill_points: u64,
+  /// The levels the players spawn with, set by [`Self::start_run`].
+  in_play: [u32; Upgrade::ALL.len()],
+  /// Changed since the last save.
+  dirty: bool,
+  path: Option<PathBuf>,
+}
+
+impl PlayerUpgrades {
+  pub fn level(&self, upgrade: Upgrade) -> u32 {
+    self.levels[upgrade as usize]
+  }
+
+  /// One `key = value` per line with `#` comments, `skill_points` and a level per upgrade.
+  pub fn parse(text: &str) -> Result<Self, Error> {
+    let mut upgrades = Self::default();
+    for (i, line) in text.lines().enumerate() {
+      let line = line.split('#').next().unwrap_or_default().trim();
+      if line.is_empty() {
+        continue;
+      }
+      let invalid = |reason| Error::InvalidUpgrades { line: i + 1, reason };
+      let (key, value) = line.split_once('=').ok_or_else(|| invalid("expected key = value"))?;
+      let value = value.trim().parse::<u64>().map_err(|_| invalid("expected an unsigned integer"))?;
+      match key.trim() {
+        "skill_points" => upgrades.skill_points = value,
+        key => {
+          let upgrade = Upgrade::ALL.into_iter().find(|upgrade| upgrade.name() == key);
+          let upgrade = upgrade.ok_or_else(|| invalid("unknown upgrade"))?;
+          let level = u32::try_from(value).ok().filter(|level| *level <= UPGRADE_MAX_LEVEL);
+          upgrades.levels[upgrade as usize] = level.ok_or_else(|| invalid("level out of range"))?;
+        }
+      }
+    }
+    Ok(upgrades)
+  }
+
+  /// Reads the profile's upgrades, a broken file is reported and the profile starts without any.
+  pub fn load(profile: &Profile) -> Self {
+    let path = profile.file(UPGRADES_FILE);
+    let upgrades = match path.as_ref().map(std::fs::read_to_string) {
+      Some(Ok(text)) => Self::parse(&text).unwrap_or_else(|e| {
+        eprintln!("{}, starting without upgrades", e);
+        Self::default()
+      }),
+      _ => Self::default(),
+    };
+    Self { path, ..upgrades }
+  }
+
+  /// Writes the file if anything changed since the last save.
+  pub fn save(&mut self) {
+    let Some(path) = self.path.as_ref().filter(|_| self.dirty) else {
+      return;
+    };
+    let written = write_atomic(path, |out| {
+      writeln!(out, "skill_points = {}", self.skill_points)?;
+      Upgrade::ALL.iter().try_for_each(|upgrade| writeln!(out, "{} = {}", upgrade.name(), self.level(*upgrade)))
+    });
+    match written {
+      Ok(()) => self.dirty = false,
+      Err(e) => eprintln!("could not save the upgrades to {}: {}", path.display(), e),
+    }
+  }
+
+  /// The one way levels and skill points change, buying, refunding and earning alike.
+  fn change(&mut self, upgrade: Option<Upgrade>, levels: i32, skill_points: i64) {
+    if let Some(upgrade) = upgrade {
+      let level = &mut self.levels[upgrade as usize];
+      *level = level.saturating_add_signed(levels);
+    }
+    self.skill_points = self.skill_points.saturating_add_signed(skill_points);
+    self.dirty = true;
+  }
+
+  /// Adds the skill points of a run that scored `score`.
+  pub fn earn(&mut self, score: u32) {
+    let earned = (score / SCORE_PER_SKILL_POINT) as i64;
+    if earned > 0 {
+      self.change(None, 0, earned);
+    }
+  }
+
+  /// Adds skill points given out during a run, like the wave bonuses.
+  pub fn award(&mut self, skill_points: u64) {
+    self.change(None, 0, skill_points as i64);
+  }
+
+  /// The players spawn with the levels bought so far, purchases from here on can't be undone.
+  pub fn start_run(&mut self) {
+    self.in_play = self.levels;
+  }
+
+  /// `modifiers` with the levels in play added.
+  pub fn applied_to(&self, modifiers: Modifiers) -> Modifiers {
+    let level = |upgrade: Upgrade| self.in_play[upgrade as usize];
+    Modifiers {
+      boost_max_add: modifiers.boost_max_add + 20.0 * level(Upgrade::BoostMax) as f32,
+      ammo_max_add: modifiers.ammo_max_add.saturating_add(10 * level(Upgrade::AmmoMax) as i32),
+      movement_speed_mult: modifiers.movement_speed_mult * (1.0 + 0.1 * level(Upgrade::MovementSpeed) as f32),
+      ..modifiers
+    }
+  }
+}
+
+/// A purchase the shop can still take back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Purchase {
+  pub upgrade: Upgrade,
+  pub levels: u32,
+  pub cost: u64,
+}
+
+/// The shop screen, open from its menu until it's left. It remembers the last purchase for one
+/// undo, until the next purchase or the way out.
+#[derive(Debug, Default)]
+pub struct Shop {
+  last: Option<Purchase>,
+}
+
+impl Shop {
+  pub fn buy(&mut self, upgrades: &mut PlayerUpgrades, upgrade: Upgrade) -> Result<(), Error> {
+    let level = upgrades.level(upgrade);
+    if level >= UPGRADE_MAX_LEVEL {
+      return Err(Error::Purchase { reason: "the upgrade is maxed out" });
+    }
+    let cost = upgrade.cost(level);
+    if upgrades.skill_points < cost {
+      return Err(Error::Purchase { reason: "not enough skill points" });
+    }
+    upgrades.change(Some(upgrade), 1, -(cost as i64));
+    self.last = Some(Purchase { upgrade, levels: 1, cost });
+    Ok(())
+  }
+
+  /// The purchase [`Self::undo`] would refund, none once its levels are in play.
+  pub fn undoable(&self, upgrades: &PlayerUpgrades) -> Option<Purchase> {
+    self.last.filter(|last| {
+      let upgrade = last.upgrade as usize;
+      upgrades.levels[upgrade] >= upgrades.in_play[upgrade] + last.levels
+    })
+  }
+
+  pub fn undo(&mut self, upgrades: &mut PlayerUpgrades) -> Result<Purchase, Error> {
+    let last = self.undoable(upgrades).ok_or(Error::Purchase { reason: "nothing to undo" })?;
+    upgrades.change(Some(last.upgrade), -(last.levels as i32), last.cost as i64);
+    self.last = None;
+    Ok(last)
+  }
+
+  /// Leaving the shop keeps what was bought.
+  pub fn exit(self, upgrades: &mut PlayerUpgrades) {
+    upgrades.save();
+  }
+}
+
+/// Pays out the skill points of a run once its last player died, practiced runs earn none.
+pub fn skill_points_system(
+  mut event_reader: EventReader<GameEvents>,
+  upgrades: Option<ResMut<PlayerUpgrades>>,
+  players: Query<Entity, With<Player>>,
+  score: Res<Score>,
+  run: Res<RunStats>,
+) {
+  let Some(mut upgrades) = upgrades else {
+    return;
+  };
+  // the dead are despawned after the stage, they are still among the players
+  let dead = event_reader
+    .iter()
+    .filter_map(|event| match event {
+      GameEvents::PlayerDeath(player, _) => Some(*player),
+      _ => None,
+    })
+    .collect::<Vec<_>>();
+  if !dead.is_empty() && players.iter().all(|player| dead.contains(&player)) && !run.practiced {
+    upgrades.earn(score.0);
+    upgrades.save();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn upgrades(skill_points: u64) -> PlayerUpgrades {
+    PlayerUpgrades {
+      skill_points,
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn undo_refunds_the_last_purchase_only() {
+    let mut upgrades = upgrades(1000);
+    let mut shop = Shop::default();
+
+    shop.buy(&mut upgrades, Upgrade::BoostMax).unwrap();
+    shop.buy(&mut upgrades, Upgrade::BoostMax).unwrap();
+    assert_eq!((upgrades.level(Upgrade::BoostMax), upgrades.skill_points), (2, 700));
+    let refund = Purchase {
+      upgrade: Upgrade::BoostMax,
+      levels: 1,
+      cost: 200,
+    };
+    assert_eq!(shop.undoable(&upgrades), Some(refund));
+    assert_eq!(shop.undo(&mut upgrades).unwrap(), refund);
+    assert_eq!((upgrades.level(Upgrade::BoostMax), upgrades.skill_points), (1, 900));
+    // no undo of the undo, nor of the purchase before
+    assert!(shop.undo(&mut upgrades).is_err());
+
+    shop.buy(&mut upgrades, Upgrade::AmmoMax).unwrap();
+    shop.buy(&mut upgrades, Upgrade::MovementSpeed).unwrap();
+    shop.undo(&mut upgrades).unwrap();
+    assert_eq!(upgrades.level(Upgrade::AmmoMax), 1);
+    assert_eq!(upgrades.level(Upgrade::MovementSpeed), 0);
+    assert_eq!(upgrades.skill_points, 800);
+
+    assert!(shop.buy(&mut PlayerUpgrades::default(), Upgrade::AmmoMax).is_err());
+  }
+
+  #[test]
+  fn levels_in_play_cannot_be_undone() {
+    let mut upgrades = upgrades(500);
+    let mut shop = Shop::default();
+    shop.buy(&mut upgrades, Upgrade::AmmoMax).unwrap();
+    upgrades.start_run();
+    assert_eq!(shop.undoable(&upgrades), None);
+    assert!(shop.undo(&mut upgrades).is_err());
+    assert_eq!(upgrades.applied_to(Modifiers::default()).ammo_max_add, 10);
+  }
+
+  #[test]
+  fn the_final_state_is_saved_on_the_way_out() {
+    let dir = std::env::temp_dir().join(format!("bytepath-shop-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut upgrades = PlayerUpgrades {
+      path: Some(dir.join(UPGRADES_FILE)),
+      ..upgrades(400)
+    };
+    let mut shop = Shop::default();
+    shop.buy(&mut upgrades, Upgrade::BoostMax).unwrap();
+    shop.buy(&mut upgrades, Upgrade::AmmoMax).unwrap();
+    shop.undo(&mut upgrades).unwrap();
+    shop.exit(&mut upgrades);
+    assert!(!upgrades.dirty);
+
+    let saved = PlayerUpgrades::parse(&std::fs::read_to_string(dir.join(UPGRADES_FILE)).unwrap()).unwrap();
+    assert_eq!(saved.skill_points, 300);
+    assert_eq!(saved.levels, [1, 0, 0]);
+    assert!(PlayerUpgrades::parse("boost_max = 99").is_err());
+  }
+}
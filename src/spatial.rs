@@ -0,0 +1,201 @@
+use crate::{
+  environment::{SPATIAL_GRID_CELL_SIZE, SPATIAL_GRID_SPARE},
+  resources::WorldBounds,
+  units::{WorldPos, WorldVec},
+};
+use bevy_ecs::prelude::*;
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+  entity: Entity,
+  position: WorldPos,
+  radius: f32,
+}
+
+/// Every collider of the tick in a uniform grid of [`SPATIAL_GRID_CELL_SIZE`] cells over the playfield,
+/// rebuilt by [`crate::systems::spatial_index_system`]. A collider goes into the cell of its center,
+/// colliders outside of the playfield into the nearest edge cell.
+#[derive(Debug, Resource)]
+pub struct SpatialGrid {
+  min: WorldPos,
+  columns: usize,
+  rows: usize,
+  cells: Vec<Vec<Entry>>,
+  /// Queries look this much further, a collider reaching in from a cell next to the query isn't missed.
+  max_radius: f32,
+  /// A task giving back the room of the cells is queued, see [`Self::is_oversized`].
+  pub shrinking: bool,
+}
+
+impl Default for SpatialGrid {
+  fn default() -> Self {
+    let mut grid = Self {
+      min: WorldPos::default(),
+      columns: 0,
+      rows: 0,
+      cells: Vec::new(),
+      max_radius: 0.0,
+      shrinking: false,
+    };
+    grid.reset(&WorldBounds::default());
+    grid
+  }
+}
+
+impl SpatialGrid {
+  /// Empties the grid and fits it to `bounds`, the cells keep their allocations.
+  pub fn reset(&mut self, bounds: &WorldBounds) {
+    let size = bounds.size();
+    self.min = bounds.corners(0.0).0;
+    self.columns = (size.x / SPATIAL_GRID_CELL_SIZE).ceil().max(1.0) as usize;
+    self.rows = (size.y / SPATIAL_GRID_CELL_SIZE).ceil().max(1.0) as usize;
+    self.cells.resize_with(self.columns * self.rows, Vec::new);
+    for cell in &mut self.cells {
+      cell.clear();
+    }
+    self.max_radius = 0.0;
+  }
+
+  pub fn insert(&mut self, entity: Entity, position: WorldPos, radius: f32) {
+    let (column, row) = self.cell(position);
+    self.cells[row * self.columns + column].push(Entry {
+      entity,
+      position,
+      radius,
+    });
+    self.max_radius = self.max_radius.max(radius);
+  }
+
+  pub fn rows(&self) -> usize {
+    self.rows
+  }
+
+  /// Whether the cells hold on to far more room than the tick needs, as they do after a huge wave was
+  /// cleared, see [`SPATIAL_GRID_SPARE`].
+  pub fn is_oversized(&self) -> bool {
+    let (len, capacity) = self.cells.iter().fold((0, 0), |(len, capacity), cell| {
+      (len + cell.len(), capacity + cell.capacity())
+    });
+    capacity > len * 4 + SPATIAL_GRID_SPARE
+  }
+
+  /// Gives back the room of the cells in `row` beyond what they hold.
+  pub fn shrink_row(&mut self, row: usize) {
+    for cell in &mut self.cells[row * self.columns..(row + 1) * self.columns] {
+      cell.shrink_to_fit();
+    }
+  }
+
+  /// Every entity whose collider overlaps the circle.
+  pub fn query_circle(&self, center: WorldPos, radius: f32) -> impl Iterator<Item = Entity> + '_ {
+    let reach = WorldVec::splat(radius);
+    self
+      .candidates(center - reach, center + reach)
+      .filter(move |entry| entry.position.distance_squared(center) <= (radius + entry.radius).powi(2))
+      .map(|entry| entry.entity)
+  }
+
+  /// Every entity whose collider overlaps the box from `min` to `max`.
+  pub fn query_aabb(&self, min: WorldPos, max: WorldPos) -> impl Iterator<Item = Entity> + '_ {
+    self
+      .candidates(min, max)
+      .filter(move |entry| entry.position.clamp(min, max).distance_squared(entry.position) <= entry.radius.powi(2))
+      .map(|entry| entry.entity)
+  }
+
+  fn cell(&self, position: WorldPos) -> (usize, usize) {
+    let (column, row) = (position - self.min).floor_div(SPATIAL_GRID_CELL_SIZE);
+    let column = column.clamp(0, self.columns as i64 - 1) as usize;
+    let row = row.clamp(0, self.rows as i64 - 1) as usize;
+    (column, row)
+  }
+
+  /// The entries of every cell a collider overlapping the box could be in.
+  fn candidates(&self, min: WorldPos, max: WorldPos) -> impl Iterator<Item = &Entry> {
+    let reach = WorldVec::splat(self.max_radius);
+    let (min_column, min_row) = self.cell(min - reach);
+    let (max_column, max_row) = self.cell(max + reach);
+    (min_row..=max_row).flat_map(move |row| {
+      let cells = &self.cells[row * self.columns..(row + 1) * self.columns];
+      cells[min_column..=max_column].iter().flatten()
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+  /// 1000 colliders of mixed sizes, some of them off the playfield.
+  fn colliders(rng: &mut SmallRng) -> Vec<Entry> {
+    let size = WorldBounds::default().size();
+    (0..1000)
+      .map(|i| Entry {
+        entity: Entity::from_raw(i),
+        position: WorldPos::new(rng.gen_range(-20.0..size.x + 20.0), rng.gen_range(-20.0..size.y + 20.0)),
+        radius: rng.gen_range(1.0..12.0),
+      })
+      .collect()
+  }
+
+  fn sorted(entities: impl Iterator<Item = Entity>) -> Vec<Entity> {
+    let mut entities = entities.collect::<Vec<_>>();
+    entities.sort();
+    entities
+  }
+
+  #[test]
+  fn queries_match_brute_force() {
+    let mut rng = SmallRng::seed_from_u64(7);
+    let size = WorldBounds::default().size();
+    let entries = colliders(&mut rng);
+    let mut grid = SpatialGrid::default();
+    for entry in &entries {
+      grid.insert(entry.entity, entry.position, entry.radius);
+    }
+
+    for _ in 0..200 {
+      let center = WorldPos::new(rng.gen_range(-30.0..size.x + 30.0), rng.gen_range(-30.0..size.y + 30.0));
+      let radius = rng.gen_range(0.0..40.0);
+      let brute_force = entries
+        .iter()
+        .filter(|entry| entry.position.distance_squared(center) <= (radius + entry.radius).powi(2))
+        .map(|entry| entry.entity);
+      assert_eq!(sorted(grid.query_circle(center, radius)), sorted(brute_force));
+
+      let (min, max) = (center, center + WorldVec::new(rng.gen_range(0.0..60.0), rng.gen_range(0.0..60.0)));
+      let brute_force = entries
+        .iter()
+        .filter(|entry| entry.position.clamp(min, max).distance_squared(entry.position) <= entry.radius.powi(2))
+        .map(|entry| entry.entity);
+      assert_eq!(sorted(grid.query_aabb(min, max)), sorted(brute_force));
+    }
+  }
+
+  #[test]
+  fn the_room_of_a_cleared_wave_is_given_back_row_by_row() {
+    let mut rng = SmallRng::seed_from_u64(7);
+    let mut grid = SpatialGrid::default();
+    for _ in 0..4 {
+      for entry in colliders(&mut rng) {
+        grid.insert(entry.entity, entry.position, entry.radius);
+      }
+    }
+    assert!(!grid.is_oversized());
+
+    // the wave is cleared, a few stragglers are left
+    grid.reset(&WorldBounds::default());
+    let stragglers = colliders(&mut rng).into_iter().take(20).collect::<Vec<_>>();
+    for entry in &stragglers {
+      grid.insert(entry.entity, entry.position, entry.radius);
+    }
+    assert!(grid.is_oversized());
+    for row in 0..grid.rows() {
+      grid.shrink_row(row);
+    }
+    assert!(!grid.is_oversized());
+    let everything = grid.query_aabb(WorldPos::new(-100.0, -100.0), WorldPos::new(1000.0, 1000.0));
+    assert_eq!(sorted(everything), sorted(stragglers.iter().map(|entry| entry.entity)));
+  }
+}
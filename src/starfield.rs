@@ -0,0 +1,111 @@
+use crate::{
+  color::ColorGl,
+  environment::{RGB_CLEAR_COLOR, RGB_COLOR_STAR, STARFIELD_CHUNK, STARFIELD_STARS, Z_INDEX_STARFIELD},
+  render::WithTransformColor,
+  resources::{Fills, QuadGeometry, WorldBounds},
+  tasks::TaskStatus,
+};
+use bevy_ecs::prelude::*;
+use lyon::{
+  geom::{Box2D, Size},
+  tessellation::{BuffersBuilder, FillOptions},
+};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Star {
+  pub position: glam::Vec2,
+  pub size: f32,
+  /// How far the star is from the background color towards [`RGB_COLOR_STAR`].
+  pub brightness: f32,
+}
+
+/// The dim stars behind the playfield, scattered from the seed of the run with a generator of their
+/// own, so they never touch the simulation's random numbers. Filled in [`STARFIELD_CHUNK`] stars per
+/// step of a [`crate::tasks::MainThreadTasks`] task.
+#[derive(Debug, Resource)]
+pub struct Starfield {
+  rng: SmallRng,
+  pub stars: Vec<Star>,
+}
+
+impl Starfield {
+  pub fn new(seed: u64) -> Self {
+    Self {
+      rng: SmallRng::seed_from_u64(seed),
+      stars: Vec::with_capacity(STARFIELD_STARS),
+    }
+  }
+
+  /// Scatters the next chunk over `bounds`, done once there are [`STARFIELD_STARS`].
+  pub fn generate(&mut self, bounds: &WorldBounds) -> TaskStatus {
+    let count = STARFIELD_CHUNK.min(STARFIELD_STARS - self.stars.len());
+    for _ in 0..count {
+      let star = Star {
+        position: glam::vec2(
+          self.rng.gen_range(bounds.min.x..bounds.max.x),
+          self.rng.gen_range(bounds.min.y..bounds.max.y),
+        ),
+        size: self.rng.gen_range(0.5..1.5),
+        brightness: self.rng.gen_range(0.15..0.5),
+      };
+      self.stars.push(star);
+    }
+    if self.stars.len() < STARFIELD_STARS {
+      TaskStatus::Continue
+    } else {
+      TaskStatus::Done
+    }
+  }
+}
+
+/// The stars scattered so far, behind everything else of the scene.
+pub fn draw_starfield_system(
+  starfield: Option<Res<Starfield>>,
+  mut quads: ResMut<QuadGeometry>,
+  mut fills: ResMut<Fills>,
+) {
+  let Some(starfield) = starfield else {
+    return;
+  };
+  for star in &starfield.stars {
+    fills
+      .tessellate_rectangle(
+        &Box2D::from_size(Size::new(star.size, star.size)),
+        &FillOptions::default(),
+        &mut BuffersBuilder::new(
+          &mut quads.vertex_buffer,
+          WithTransformColor {
+            transform: glam::Mat4::from_translation(star.position.extend(Z_INDEX_STARFIELD)),
+            color_rgba: ColorGl::from(RGB_CLEAR_COLOR).lerp(ColorGl::from(RGB_COLOR_STAR), star.brightness),
+          },
+        ),
+      )
+      .ok();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn the_same_seed_scatters_the_same_stars_a_chunk_per_step() {
+    let bounds = WorldBounds::default();
+    let generate = |seed| {
+      let mut starfield = Starfield::new(seed);
+      let mut steps = 1;
+      while starfield.generate(&bounds) == TaskStatus::Continue {
+        assert_eq!(starfield.stars.len(), steps * STARFIELD_CHUNK);
+        steps += 1;
+      }
+      assert_eq!(steps, STARFIELD_STARS.div_ceil(STARFIELD_CHUNK));
+      starfield.stars
+    };
+    let stars = generate(4);
+    assert_eq!(stars.len(), STARFIELD_STARS);
+    assert_eq!(stars, generate(4));
+    assert_ne!(stars, generate(5));
+    assert!(stars.iter().all(|star| (bounds.min.cmple(star.position) & star.position.cmple(bounds.max)).all()));
+  }
+}
@@ -0,0 +1,336 @@
+use crate::{
+  components::{Attack, Player, PreviousTransform, Transform},
+  environment::{SCREEN_HEIGHT, STATS_FILE},
+  error::Error,
+  format::{write_abbreviated, write_score, write_time},
+  events::{DeathCause, GameEvents, KilledBy, Pickup, PickupEvent},
+  output_files::{Backpressure, OutputWriter, WriteJob},
+  profiles::Profile,
+  resources::{GameState, Score, Time},
+};
+use bevy_ecs::prelude::*;
+use std::{collections::BTreeMap, io::Write, path::PathBuf, time::Duration};
+
+/// A step longer than this went across the playfield, e.g. wrapping around it, and isn't flown.
+const MAX_STEP: f32 = SCREEN_HEIGHT as f32 / 2.0;
+
+/// What the run so far did, folded into the [`LifetimeStats`] when it ends.
+#[derive(Debug, Default, Clone, PartialEq, Resource)]
+pub struct RunStats {
+  /// Gameplay seconds with a player alive.
+  pub play_time: f32,
+  pub distance: f32,
+  /// Volleys, however many projectiles each had.
+  pub shots: u64,
+  /// Enemies shot down, each takes one projectile.
+  pub hits: u64,
+  pub ammo_pickups: u64,
+  pub boost_pickups: u64,
+  pub attack_pickups: u64,
+  pub deaths: [u64; DeathCause::ALL.len()],
+  /// Seconds played with each attack, in the order of [`Attack::ALL`].
+  pub attack_time: [f32; Attack::ALL.len()],
+  /// A practice tool was used, see [`crate::practice::Practice`].
+  pub practiced: bool,
+}
+
+/// How a counter of the run goes into the lifetime one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fold {
+  Sum,
+  Max,
+}
+
+impl RunStats {
+  /// Every counter of the run under its key in [`STATS_FILE`], times in milliseconds.
+  fn counters(&self, score: u32) -> Vec<(String, u64, Fold)> {
+    let ms = |seconds: f32| (seconds * 1000.0) as u64;
+    let mut counters = vec![
+      (String::from("runs"), 1, Fold::Sum),
+      (String::from("play_time_ms"), ms(self.play_time), Fold::Sum),
+      (String::from("longest_run_ms"), ms(self.play_time), Fold::Max),
+      (String::from("total_score"), score.into(), Fold::Sum),
+      (String::from("best_score"), score.into(), Fold::Max),
+      (String::from("distance"), self.distance as u64, Fold::Sum),
+      (String::from("shots"), self.shots, Fold::Sum),
+      (String::from("hits"), self.hits, Fold::Sum),
+      (String::from("pickups_ammo"), self.ammo_pickups, Fold::Sum),
+      (String::from("pickups_boost"), self.boost_pickups, Fold::Sum),
+      (String::from("pickups_attack"), self.attack_pickups, Fold::Sum),
+    ];
+    for (cause, deaths) in DeathCause::ALL.iter().zip(self.deaths) {
+      counters.push((format!("deaths_{}", cause.name()), deaths, Fold::Sum));
+    }
+    for (attack, seconds) in Attack::ALL.iter().zip(self.attack_time) {
+      counters.push((format!("attack_ms_{}", attack.name().to_lowercase()), ms(seconds), Fold::Sum));
+    }
+    counters
+  }
+}
+
+/// The runs of the profile added up, in its [`STATS_FILE`]. Keys the game doesn't know, from a newer
+/// version, are kept as they are, missing ones count as 0.
+#[derive(Debug, Default, Clone, PartialEq, Resource)]
+pub struct LifetimeStats {
+  counters: BTreeMap<String, u64>,
+  path: Option<PathBuf>,
+}
+
+impl LifetimeStats {
+  pub fn get(&self, key: &str) -> u64 {
+    self.counters.get(key).copied().unwrap_or_default()
+  }
+
+  /// One `key = value` per line with `#` comments, the values unsigned integers.
+  pub fn parse(text: &str) -> Result<Self, Error> {
+    let mut counters = BTreeMap::new();
+    for (i, line) in text.lines().enumerate() {
+      let line = line.split('#').next().unwrap_or_default().trim();
+      if line.is_empty() {
+        continue;
+      }
+      let invalid = |reason| Error::InvalidStats { line: i + 1, reason };
+      let (key, value) = line.split_once('=').ok_or_else(|| invalid("expected key = value"))?;
+      let key = key.trim();
+      if key.is_empty() || !key.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_') {
+        return Err(invalid("expected a key of lowercase letters, digits and underscores"));
+      }
+      let value = value.trim().parse::<u64>().map_err(|_| invalid("expected an unsigned integer"))?;
+      counters.insert(key.to_string(), value);
+    }
+    Ok(Self { counters, path: None })
+  }
+
+  /// Reads the profile's stats. A missing file starts them at 0, a broken one is reported and
+  /// overwritten by the next save.
+  pub fn load(profile: &Profile) -> Self {
+    let path = profile.file(STATS_FILE);
+    let stats = match path.as_ref().map(std::fs::read_to_string) {
+      Some(Ok(text)) => Self::parse(&text).unwrap_or_else(|e| {
+        eprintln!("{}, starting the stats over", e);
+        Self::default()
+      }),
+      _ => Self::default(),
+    };
+    Self { path, ..stats }
+  }
+
+  pub fn save(&self, writer: Option<&OutputWriter>) {
+    let Some(path) = &self.path else {
+      return;
+    };
+    let mut bytes = Vec::new();
+    for (key, value) in &self.counters {
+      writeln!(bytes, "{} = {}", key, value).unwrap();
+    }
+    let job = WriteJob {
+      backpressure: Backpressure::DropOldest,
+      ..WriteJob::new(path.clone(), bytes, "stats")
+    };
+    job.submit(writer);
+  }
+}
+
+/// `lifetime` with the run that scored `score` added, the counters saturate instead of wrapping.
+pub fn aggregate(lifetime: &LifetimeStats, run: &RunStats, score: u32) -> LifetimeStats {
+  let mut aggregated = lifetime.clone();
+  for (key, value, fold) in run.counters(score) {
+    let counter = aggregated.counters.entry(key).or_default();
+    *counter = match fold {
+      Fold::Sum => counter.saturating_add(value),
+      Fold::Max => (*counter).max(value),
+    };
+  }
+  aggregated
+}
+
+/// Adds the run to the lifetime stats and saves them, the next run starts from 0. Once the last
+/// player died and when the game quits mid-run, a run that didn't get to play or was practiced isn't
+/// counted.
+pub fn finish_run(run: &mut RunStats, score: u32, lifetime: &mut LifetimeStats, writer: Option<&OutputWriter>) {
+  if run.play_time > 0.0 && !run.practiced {
+    *lifetime = aggregate(lifetime, run, score);
+    lifetime.save(writer);
+  }
+  *run = RunStats::default();
+}
+
+/// The stats page, a label and a value per row.
+pub fn stats_rows(stats: &LifetimeStats) -> Vec<(String, String)> {
+  let time = |key: &str| {
+    let mut text = String::new();
+    write_time(&mut text, Duration::from_millis(stats.get(key))).unwrap();
+    text
+  };
+  let score = |key: &str| {
+    let mut text = String::new();
+    write_score(&mut text, stats.get(key)).unwrap();
+    text
+  };
+  let abbreviated = |key: &str| {
+    let mut text = String::new();
+    write_abbreviated(&mut text, stats.get(key)).unwrap();
+    text
+  };
+  let accuracy = match stats.get("shots") {
+    0 => String::from("-"),
+    shots => format!("{}%", stats.get("hits").saturating_mul(100) / shots),
+  };
+  let favorite = Attack::ALL
+    .into_iter()
+    .map(|attack| (attack, stats.get(&format!("attack_ms_{}", attack.name().to_lowercase()))))
+    .filter(|(_, ms)| *ms > 0)
+    .max_by_key(|(_, ms)| *ms)
+    .map_or_else(|| String::from("-"), |(attack, _)| attack.name().to_uppercase());
+
+  let mut rows = vec![
+    ("RUNS", score("runs")),
+    ("PLAY TIME", time("play_time_ms")),
+    ("LONGEST RUN", time("longest_run_ms")),
+    ("TOTAL SCORE", score("total_score")),
+    ("BEST SCORE", score("best_score")),
+    ("DISTANCE", abbreviated("distance")),
+    ("SHOTS FIRED", score("shots")),
+    ("ACCURACY", accuracy),
+    ("AMMO PICKUPS", score("pickups_ammo")),
+    ("BOOST PICKUPS", score("pickups_boost")),
+    ("ATTACK PICKUPS", score("pickups_attack")),
+    ("FAVORITE ATTACK", favorite),
+  ];
+  for cause in DeathCause::ALL {
+    let label = match cause {
+      DeathCause::SelfDestruct => "SELF DESTRUCTS",
+      DeathCause::Laser => "LASER DEATHS",
+      DeathCause::Projectile => "PROJECTILE DEATHS",
+    };
+    rows.push((label, score(&format!("deaths_{}", cause.name()))));
+  }
+  rows.into_iter().map(|(label, value)| (String::from(label), value)).collect()
+}
+
+/// Counts what the players do into [`RunStats`] and finishes the run with the last of them. Worlds
+/// without [`LifetimeStats`], the lockstep audit's, stress runs and replays, keep none.
+pub fn run_stats_system(
+  mut event_reader: EventReader<GameEvents>,
+  mut pickups: EventReader<PickupEvent>,
+  players: Query<(&Transform, Option<&PreviousTransform>, &Attack, Entity), With<Player>>,
+  time: Res<Time>,
+  state: Res<GameState>,
+  score: Res<Score>,
+  mut run: ResMut<RunStats>,
+  lifetime: Option<ResMut<LifetimeStats>>,
+  writer: Option<Res<OutputWriter>>,
+) {
+  let Some(mut lifetime) = lifetime else {
+    return;
+  };
+  let mut dead = Vec::new();
+  for event in event_reader.iter() {
+    match event {
+      GameEvents::Shot(_) => run.shots += 1,
+      GameEvents::EnemyDeath(_, KilledBy::Projectile) => run.hits += 1,
+      GameEvents::PlayerDeath(player, cause) => {
+        run.deaths[*cause as usize] += 1;
+        dead.push(*player);
+      }
+      _ => {}
+    }
+  }
+  for PickupEvent(pickup) in pickups.iter() {
+    match pickup {
+      Pickup::Ammo => run.ammo_pickups += 1,
+      Pickup::Boost => run.boost_pickups += 1,
+      Pickup::Attack => run.attack_pickups += 1,
+    }
+  }
+
+  if *state == GameState::Running && !players.is_empty() {
+    let dt = time.as_secs_f32();
+    run.play_time += dt;
+    for (transform, previous, attack, _) in players.iter() {
+      let position = transform.position.to_render();
+      let step = previous.map_or(0.0, |previous| position.distance(previous.0.position.to_render()));
+      if step < MAX_STEP {
+        run.distance += step;
+      }
+      run.attack_time[*attack as usize] += dt;
+    }
+  }
+
+  // the dead are despawned after the stage, they are still among the players
+  if !dead.is_empty() && players.iter().all(|(.., player)| dead.contains(&player)) {
+    finish_run(&mut run, score.0, &mut lifetime, writer.as_deref());
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn run(play_time: f32, shots: u64) -> RunStats {
+    RunStats {
+      play_time,
+      shots,
+      hits: shots / 2,
+      deaths: [0, 1, 0],
+      attack_time: [play_time, 0.0, 0.0, 0.0, 0.0],
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn runs_add_up_and_the_records_keep_the_best() {
+    let lifetime = aggregate(&LifetimeStats::default(), &run(30.0, 10), 500);
+    let lifetime = aggregate(&lifetime, &run(12.5, 4), 900);
+
+    assert_eq!(lifetime.get("runs"), 2);
+    assert_eq!(lifetime.get("play_time_ms"), 42_500);
+    assert_eq!(lifetime.get("longest_run_ms"), 30_000);
+    assert_eq!(lifetime.get("total_score"), 1400);
+    assert_eq!(lifetime.get("best_score"), 900);
+    assert_eq!(lifetime.get("shots"), 14);
+    assert_eq!(lifetime.get("hits"), 7);
+    assert_eq!(lifetime.get("deaths_laser"), 2);
+    assert_eq!(lifetime.get("attack_ms_neutral"), 42_500);
+  }
+
+  #[test]
+  fn counters_saturate_instead_of_wrapping() {
+    let full = LifetimeStats::parse(&format!("shots = {}\nruns = {}", u64::MAX, u64::MAX - 1)).unwrap();
+    let lifetime = aggregate(&full, &run(1.0, 10), 0);
+    assert_eq!(lifetime.get("shots"), u64::MAX);
+    assert_eq!(lifetime.get("runs"), u64::MAX);
+  }
+
+  #[test]
+  fn old_files_gain_the_new_counters_and_keep_unknown_ones() {
+    // from before the attack times were kept, and with a counter of a newer version
+    let old = "runs = 3\nshots = 40\n# a comment\nbosses_beaten = 2\n";
+    let lifetime = aggregate(&LifetimeStats::parse(old).unwrap(), &run(2.0, 2), 10);
+    assert_eq!(lifetime.get("runs"), 4);
+    assert_eq!(lifetime.get("attack_ms_neutral"), 2000);
+    assert_eq!(lifetime.get("bosses_beaten"), 2);
+
+    let mut written = String::new();
+    for (key, value) in &lifetime.counters {
+      written.push_str(&format!("{} = {}\n", key, value));
+    }
+    assert_eq!(LifetimeStats::parse(&written).unwrap(), lifetime);
+    assert!(LifetimeStats::parse("runs = -1").is_err());
+    assert!(LifetimeStats::parse("Runs = 1").is_err());
+  }
+
+  #[test]
+  fn the_page_reads_the_counters() {
+    let page = |text: &str| stats_rows(&LifetimeStats::parse(text).unwrap()).into_iter().collect::<BTreeMap<_, _>>();
+    let fresh = page("");
+    assert_eq!(fresh["ACCURACY"], "-");
+    assert_eq!(fresh["FAVORITE ATTACK"], "-");
+    assert_eq!(fresh["PLAY TIME"], "0:00.0");
+
+    let played = page("shots = 8\nhits = 6\nattack_ms_neutral = 100\nattack_ms_homing = 900\nplay_time_ms = 4000000");
+    assert_eq!(played["ACCURACY"], "75%");
+    assert_eq!(played["FAVORITE ATTACK"], "HOMING");
+    assert_eq!(played["PLAY TIME"], "1:06:40");
+  }
+}
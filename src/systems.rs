@@ -1,8 +1,41 @@
 use crate::{
-  color::ColorGl, components::*, easings::*, environment::*, render::WithTransformColor, resources::*, GameEvents,
+  balance::GameBalance,
+  camera::CameraFollow,
+  color::ColorGl,
+  components::*,
+  draw::{draw_circle, fade_alpha, CircleStyle, GeometryCache},
+  easings::*,
+  format::{write_score, write_time},
+  glyphs::GlyphId,
+  environment::*,
+  events::{
+    DamageEvent, DeathCause, EventCounts, GameEvents, KilledBy, Notice, NotificationEvent, Pickup, PickupEvent,
+    ShakeRequest, SlowMotion, SpawnRequest, CHANNELS,
+  },
+  health::SystemHealth,
+  heatmap::{cell_index, heat_color, normalize},
+  highscores::HighScores,
+  input::Thrust,
+  input_display::{input_mask, ACTIONS},
+  inspector::Inspector,
+  logo::{logo_width, polyline_length, truncate_polyline, LETTERS, LETTER_HEIGHT, LETTER_SPACING, LETTER_WIDTH},
+  math::{
+    closest_point_on_segment, plot, ring_passed_over, segment_circle_overlap, steer_towards, swept_contact,
+    swept_segment_contact,
+  },
+  minimap::{cluster_dots, minimap_origin, playfield_to_minimap},
+  modifiers::Modifiers,
+  output_files::OutputWriter,
+  prompts::{fill_prompts, InputDevice},
+  render::WithTransformColor,
+  replay::Replay,
+  resources::*,
+  spatial::SpatialGrid,
+  stats::RunStats,
+  tasks::{MainThreadTasks, TaskStatus},
+  units::{Angle, WorldPos, WorldVec},
 };
-use bevy_ecs::prelude::*;
-use glam::Vec3Swizzles;
+use bevy_ecs::{prelude::*, query::ReadOnlyWorldQuery};
 use lyon::{
   geom::{Box2D, Size},
   lyon_tessellation::FillOptions,
@@ -12,284 +45,605 @@ use lyon::{
 };
 use rand::Rng;
 use sdl2::keyboard::Keycode;
-use std::time::Duration;
+use std::{collections::HashMap, time::Duration};
 
-fn screen_ouf_of_bounds_test(position: glam::Vec2, offset: Option<f32>) -> bool {
-  let offset = offset.unwrap_or_default();
-  position.x < -offset
-    || position.x > SCREEN_WIDTH as f32 + offset
-    || position.y < -offset
-    || position.y > SCREEN_HEIGHT as f32 + offset
+pub fn player_spawn_system(
+  mut commands: Commands,
+  bounds: Res<WorldBounds>,
+  bounds_mode: Res<BoundsMode>,
+  bindings: Res<KeyBindings>,
+  modifiers: Res<Modifiers>,
+  mut score: ResMut<Score>,
+) {
+  score.0 = 0;
+  spawn_players(&mut commands, &bounds, *bounds_mode, bindings.players(), &modifiers);
 }
 
-pub fn player_spawn_system(mut commands: Commands) {
-  commands
+/// One ship per player, side by side around the center of the playfield, with the stats of the build.
+fn spawn_players(
+  commands: &mut Commands,
+  bounds: &WorldBounds,
+  bounds_mode: BoundsMode,
+  players: u8,
+  modifiers: &Modifiers,
+) {
+  let boost = Boost::default();
+  let max_boost = boost.max_boost + modifiers.boost_max_add;
+  for i in 0..players {
+    let offset = (i as f32 - (players - 1) as f32 / 2.0) * CO_OP_SPAWN_SPACING;
+    let id = PlayerId(i);
+    commands
       .spawn_empty()
+      .insert(ScopeTag(GameScope::Run))
       .insert(Player {
-        movement_speed: 100.0,
+        movement_speed: 100.0 * modifiers.movement_speed_mult,
         rotation_speed: 360.0f32.to_radians(),
+        brake_turn: None,
+        bounds_mode,
+        against_wall: false,
       })
+      .insert((id, PlayerAppearance::of(id)))
+      .insert(Player::SHAPE)
+      .insert(Velocity::Linear(WorldVec::ZERO))
       .insert(Transform {
-        translation: glam::Vec3::new(SCREEN_WIDTH as f32 / 2.0, SCREEN_HEIGHT as f32 / 2.0, Z_INDEX_PLAYER),
+        position: WorldPos::from(bounds.center()) + WorldVec::new(offset, 0.0),
+        z: Z_INDEX_PLAYER,
         ..Default::default()
       })
-      .insert(Boost::default())
-      .insert(Interpolation::new(vec![(8.0, 0.0)], 0.24, true));
+      .insert(Boost {
+        boost: max_boost,
+        max_boost,
+        ..boost
+      })
+      .insert(Sweep::default())
+      .insert(Collider { radius: PLAYER_RADIUS })
+      .insert(Attack::default())
+      .insert(Ammo::new(modifiers.ammo_max()))
+      .insert(Invulnerable {
+        timer: Timer::from_seconds(INVULNERABLE_DURATION, false),
+      })
+      .insert(Interpolation::new(vec![(8.0, 0.0)], 0.24, InterpolationMode::Loop));
+  }
 }
 
-pub fn shooting_system(
-  mut query: Query<(&Player, &Transform, &mut Interpolation)>,
-  mut quads: ResMut<QuadGeometry>,
-  mut tessellator: ResMut<Fills>,
+/// Starts [`Respawn`] once the last player died and spawns every player again once it runs out, or
+/// right away with R. Clears the death effects first, entering [`Phase::Run`] clears the playfield.
+pub fn player_respawn_system(
+  mut commands: Commands,
+  mut event_reader: EventReader<GameEvents>,
+  mut respawn: ResMut<Respawn>,
+  keycodes: Res<KeyCodes>,
+  raw_time: Res<DurationWrapper>,
+  mut phase: ResMut<Phase>,
+  players: Query<(), With<Player>>,
+  mut time_scale: ResMut<TimeScale>,
+  mut flash: ResMut<Flash>,
+  mut shake: ResMut<Shake>,
+  mut camera: ResMut<Camera>,
+  mut score: ResMut<Score>,
+  mut bomb: ResMut<Bomb>,
+  // one parameter, a system takes at most 16
+  (bounds, bounds_mode, bindings, modifiers): (Res<WorldBounds>, Res<BoundsMode>, Res<KeyBindings>, Res<Modifiers>),
+  state: Res<GameState>,
+) {
+  // the dead are despawned by now, a co-op run goes on while anyone is left
+  let died = event_reader.iter().any(|event| matches!(event, GameEvents::PlayerDeath(..)));
+  if died && players.is_empty() {
+    respawn.timer = Some(Timer::from_seconds(RESPAWN_DELAY, false));
+    *phase = Phase::GameOver;
+  }
+
+  let Some(timer) = respawn.timer.as_mut().filter(|_| *state == GameState::Running) else {
+    return;
+  };
+  timer.tick(**raw_time);
+  if !timer.finished && !keycodes.contains(&Keycode::R) {
+    return;
+  }
+
+  respawn.timer = None;
+  // scope_cleanup_system clears the playfield of the run before
+  *phase = Phase::Run;
+  time_scale.clear();
+  *flash = Flash::default();
+  shake.stop();
+  camera.camera_pos = glam::vec3(0.0, 0.0, camera.camera_pos.z);
+  score.0 = 0;
+  *bomb = Bomb::default();
+  spawn_players(&mut commands, &bounds, *bounds_mode, bindings.players(), &modifiers);
+}
+
+/// Despawns what entering the new [`Phase`] doesn't keep, see [`SCOPES_KEPT`]. Runs right after
+/// `player_respawn_system`, before its commands spawn the players of the new run.
+pub fn scope_cleanup_system(mut commands: Commands, phase: Res<Phase>, scoped: Query<(&ScopeTag, Entity)>) {
+  if !phase.is_changed() || phase.is_added() {
+    return;
+  }
+  for (scope, entity) in scoped.iter() {
+    if !phase.keeps(scope.0) {
+      commands.entity(entity).despawn();
+    }
+  }
+}
+
+/// Every entity has to say how long it lives, see [`ScopeTag`]. Empty ones are what a panicked cosmetic
+/// system's dropped commands leave behind.
+pub fn untagged_spawn_system(world: &World) {
+  let scope = world.component_id::<ScopeTag>();
+  let untagged = world.archetypes().iter().find(|archetype| {
+    !archetype.is_empty()
+      && archetype.components().next().is_some()
+      && !scope.is_some_and(|scope| archetype.contains(scope))
+  });
+  if let Some(archetype) = untagged {
+    let names = archetype.components().filter_map(|id| world.components().get_info(id)).map(|info| info.name());
+    panic!("spawned without a ScopeTag: {}", names.collect::<Vec<_>>().join(", "));
+  }
+}
+
+/// Advances the muzzle flash of every player, [`draw_shooting_system`] draws it.
+pub fn shooting_system(mut query: Query<&mut Interpolation, With<Player>>, time: Res<Time>) {
+  for mut interpolation in query.iter_mut() {
+    interpolation.eval(time.as_secs_f32(), Easing::InOutCubic);
+  }
+}
+
+pub fn input_system(
+  keycodes: Res<KeyCodes>,
+  mouse: Res<MouseInput>,
+  gamepad: Res<GamepadState>,
+  bindings: Res<KeyBindings>,
+  state: Res<GameState>,
+  mut input: ResMut<InputState>,
+) {
+  input.update(&keycodes, &mouse, &gamepad, &bindings);
+  if *state == GameState::Paused {
+    input.release();
+  }
+}
+
+/// Pauses the game once nobody touched a key, button or stick for [`Idle::timeout`]. Runs last so the
+/// pause starts with the next tick, which records it like one from the pause key. Doesn't count while
+/// paused, on the game over screen or while a replay plays, the replay has its own pauses.
+pub fn idle_system(
+  keycodes: Res<KeyCodes>,
+  gamepad: Res<GamepadState>,
   time: Res<Time>,
+  replay: Option<Res<Replay>>,
+  mut state: ResMut<GameState>,
+  mut idle: ResMut<Idle>,
 ) {
-  for (_, transform, mut interpolation) in query.iter_mut() {
-    let (values, _) = interpolation.eval(time.as_secs_f32(), ease_in_out_cubic);
-    let mat4 =
-      glam::Mat4::from_rotation_translation(
-        transform.rotation * glam::Quat::from_rotation_z(45.0f32.to_radians()),
-        transform.translation,
-      ) * glam::Mat4::from_translation(glam::vec3(8.0 - values[0] / 2.0, 8.0 - values[0] / 2.0, Z_INDEX_PLAYER));
+  let active = !keycodes.is_empty() || gamepad.is_active();
+  let counting = *state == GameState::Running
+    && time.effects_domain != TimeDomain::Background
+    && !replay.is_some_and(|replay| replay.is_playing());
+  if idle.update(time.raw(), active, counting) {
+    *state = GameState::Paused;
+  }
+}
 
-    tessellator
-      .tessellate_rectangle(
-        &Box2D::from_size(Size::new(values[0], values[0])),
-        &FillOptions::default(),
-        &mut BuffersBuilder::new(
-          &mut quads.vertex_buffer,
-          WithTransformColor {
-            transform: mat4,
-            color_rgba: ColorGl::from(RGB_COLOR_PLAYER),
-          },
-        ),
-      )
-      .unwrap();
+/// "PAUSED" in the middle of the screen, the pause menu goes below it, see
+/// [`crate::menus::pause_menu_system`].
+pub fn pause_system(state: Res<GameState>, mut texts: ResMut<TextBuffers>) {
+  if *state != GameState::Paused {
+    return;
   }
+  let color = ColorGl::from(RGB_COLOR_PLAYER);
+  let width = texts.width("PAUSED", PAUSED_TEXT_SCALE, TextStyle::Sdf);
+  let (x, y) = (SCREEN_RENDER_WIDTH as f32 - width, SCREEN_RENDER_HEIGHT as f32);
+  texts.build_text("PAUSED", x / 2.0, y / 2.0, PAUSED_TEXT_SCALE, color, TextStyle::Sdf);
 }
 
+/// Steers the player and sets its [`Velocity`], `movement_system` moves it and [`player_bounds_system`]
+/// keeps it inside the arena. The [`Sweep`] starts where the ship stands before that move, so a boosted
+/// ship is tested along the whole of it.
 pub fn player_system(
   mut commands: Commands,
-  mut query: Query<(&Player, &mut Transform, &mut Boost, Entity)>,
+  mut query: Query<(
+    &mut Player,
+    &mut Transform,
+    &mut Velocity,
+    &mut Boost,
+    &mut Sweep,
+    &PlayerId,
+    Option<&mut Invulnerable>,
+    Entity,
+  )>,
   mut event_writer: EventWriter<GameEvents>,
-  mut circles: ResMut<CircleGeometry>,
-  mut tessellator: ResMut<Strokes>,
-  keycodes: Res<KeyCodes>,
+  input: Res<InputState>,
   time: Res<Time>,
+  mut rng: ResMut<Randoms>,
+  mut pool: ResMut<ParticlePool>,
+  mut deaths: ResMut<Deaths>,
 ) {
-  for (player, mut transform, mut boost, entity) in query.iter_mut() {
-    let mut rotation_factor = 0.0;
+  deaths.clear();
+  for (mut player, mut transform, mut velocity, mut boost, mut sweep, id, invulnerable, entity) in query.iter_mut() {
+    let input = input.player(*id);
+    if let Some(mut invulnerable) = invulnerable {
+      invulnerable.timer.tick(**time);
+      if invulnerable.timer.finished {
+        commands.entity(entity).remove::<Invulnerable>();
+      }
+    }
     let mut movement_factor = 1.0;
+    let mut braking = false;
     let time = time.as_secs_f32();
 
-    for keycode in keycodes.iter() {
-      match keycode {
-        Keycode::Up => {
-          if boost.can_boost() {
-            movement_factor = 1.5;
-            boost.boost -= boost.dec_amount * time;
-          }
-        }
-        Keycode::Down => {
-          if boost.can_boost() {
-            movement_factor = 0.5;
-            boost.boost -= boost.dec_amount * time;
-          }
-        }
-        Keycode::Left => rotation_factor += 1.0,
-        Keycode::Right => rotation_factor -= 1.0,
-        Keycode::S => {
-          event_writer.send(GameEvents::PlayerDeath);
-          commands.entity(entity).despawn();
+    match input.thrust {
+      Thrust::Boost if boost.can_boost() => {
+        movement_factor = 1.5;
+        boost.drain(time);
+      }
+      Thrust::Brake if boost.can_boost() => {
+        movement_factor = 0.5;
+        boost.drain(time * BRAKE_BOOST_COST);
+        braking = true;
+      }
+      _ => {}
+    }
+    if input.self_destruct {
+      kill_player(&mut commands, &mut event_writer, &mut deaths, entity, DeathCause::SelfDestruct);
+    }
+
+    boost.update_cooldown(time);
+    boost.regen(time);
+
+    let rotation_speed = if braking {
+      player.rotation_speed * BRAKE_TURN_MULTIPLIER
+    } else {
+      player.rotation_speed
+    };
+    let rotation = input.turn * rotation_speed * time;
+    transform.rotation += Angle::from_radians(rotation);
+
+    if braking {
+      let turn = player.brake_turn.get_or_insert_with(BrakeTurn::default);
+      turn.rotation += rotation;
+      turn.elapsed += time;
+      if !turn.triggered && turn.elapsed <= HANDBRAKE_WINDOW && turn.rotation.abs() >= HANDBRAKE_ANGLE.to_radians() {
+        turn.triggered = true;
+        for _ in 0..rng.gen_range(6usize..10usize) {
+          spawn_explosion_line(
+            &mut commands,
+            &mut pool,
+            Transform {
+              rotation: Angle::from_radians(rng.gen_range(0.0..2.0 * std::f32::consts::PI)),
+              ..*transform
+            },
+            ColorGl::from(RGB_COLOR_BOOST),
+            Interpolation::new(
+              vec![(rng.gen_range(60.0..120.0), 0.0), (rng.gen_range(2.0..5.0), 0.0), (2.0, 0.0)],
+              rng.gen_range(0.2..0.35),
+              InterpolationMode::Once,
+            ),
+          );
         }
-        _ => {}
       }
+    } else {
+      player.brake_turn = None;
     }
+    *velocity = Velocity::Linear(transform.rotation.forward_by(movement_factor * player.movement_speed));
+    sweep.from = transform.position;
+  }
+}
+
+/// Ends `player` with the death's flash, shake and slow motion. A player already killed this tick is
+/// left alone, two hazards landing at once count as one death.
+fn kill_player(
+  commands: &mut Commands,
+  event_writer: &mut EventWriter<GameEvents>,
+  deaths: &mut Deaths,
+  player: Entity,
+  cause: DeathCause,
+) {
+  if !deaths.kill(player) {
+    return;
+  }
+  event_writer.send(GameEvents::PlayerDeath(player, cause));
+  event_writer.send(GameEvents::Flash {
+    color: ColorGl::from(RGB_COLOR_FLASH_DEATH),
+    duration: FLASH_DURATION_DEATH,
+  });
+  event_writer.send(GameEvents::Shake(ShakeRequest::DEATH));
+  event_writer.send(GameEvents::SlowMotion(SlowMotion::DEATH));
+  commands.entity(player).insert(Despawning::now());
+}
 
-    if boost.is_empty() && boost.no_cooldown() {
-      boost.cooldown = boost.cooldown_sec;
-    } else if let Some(mut cooldown) = boost.cooldown.take() {
-      cooldown -= time;
-      if cooldown > 0.0 {
-        boost.cooldown.replace(cooldown);
+/// Applies the player's [`BoundsMode`] once it has moved.
+pub fn player_bounds_system(
+  mut query: Query<(&mut Player, &mut Transform, &mut Sweep)>,
+  mut event_writer: EventWriter<GameEvents>,
+  bounds: Res<WorldBounds>,
+) {
+  for (mut player, mut transform, mut sweep) in query.iter_mut() {
+    let position = transform.position;
+    match player.bounds_mode {
+      BoundsMode::Wrap => {
+        let (min, max) = bounds.corners(PLAYER_RADIUS);
+        let wrapped = position.wrap(min, max);
+        if wrapped != position {
+          // the sweep moves along, or collisions and the trail would run across the whole screen
+          transform.position = wrapped;
+          sweep.from += wrapped - position;
+        }
+      }
+      BoundsMode::Clamp => {
+        let (min, max) = bounds.corners(-PLAYER_RADIUS);
+        let clamped = position.clamp(min, max);
+        let touching = clamped != position;
+        if touching {
+          transform.position = clamped;
+          if !player.against_wall {
+            event_writer.send(GameEvents::WallImpact);
+          }
+        }
+        player.against_wall = touching;
       }
+      BoundsMode::Free => {}
     }
-    boost.boost = boost.max_boost.min(boost.boost + boost.inc_amount * time);
+  }
+}
 
-    transform.rotation *= glam::Quat::from_rotation_z(rotation_factor * player.rotation_speed * time);
-    let movement_direction = transform.rotation * glam::Vec3::Y;
-    let movement_distance = movement_factor * player.movement_speed * time;
-    let translation_delta = movement_direction * movement_distance;
-    transform.translation += translation_delta;
+/// Moves every entity matching `F` by its [`Velocity`] and spins it by its [`AngularVelocity`].
+/// Registered once per entity group, right after the system that sets the group's velocity.
+pub fn movement_system<F: ReadOnlyWorldQuery + 'static>(
+  mut query: Query<(&Velocity, Option<&AngularVelocity>, &mut Transform), (F, Without<Despawning>)>,
+  time: Res<Time>,
+) {
+  let time = time.as_secs_f32();
+  for (velocity, angular_velocity, mut transform) in query.iter_mut() {
+    integrate(&mut transform, *velocity, angular_velocity, time);
+  }
+}
 
-    let mut options = StrokeOptions::default();
-    options.line_width = 1.5;
-    tessellator
-      .tessellate_circle(
-        Point::new(0.0, 0.0),
-        12.0,
-        &options,
-        &mut BuffersBuilder::new(
-          &mut circles.vertex_buffer,
-          WithTransformColor {
-            transform: transform.mat4(),
-            color_rgba: ColorGl::from(RGB_COLOR_PLAYER),
-          },
-        ),
-      )
-      .unwrap();
+/// [`movement_system`] for explosion lines, they run on effect time and pooled ones rest while inactive.
+pub fn effect_movement_system(
+  mut query: Query<(&Velocity, Option<&AngularVelocity>, &mut Transform, Option<&Active>), With<ExplosionEffect>>,
+  time: Res<Time>,
+) {
+  let time = time.effects().as_secs_f32();
+  for (velocity, angular_velocity, mut transform, active) in query.iter_mut() {
+    if active.is_some_and(|active| !active.0) {
+      continue;
+    }
+    integrate(&mut transform, *velocity, angular_velocity, time);
+  }
+}
+
+fn integrate(transform: &mut Transform, velocity: Velocity, angular_velocity: Option<&AngularVelocity>, time: f32) {
+  if let Some(angular_velocity) = angular_velocity {
+    transform.center_rotation += Angle::from_radians(angular_velocity.0 * time);
   }
+  transform.position += match velocity {
+    Velocity::Linear(velocity) => velocity * time,
+    Velocity::Heading(speed) => transform.rotation.forward_by(speed * time),
+  };
 }
 
+/// Puffs at the [`PlayerTimers::trail`] interval whatever the tick length. A tick spanning several
+/// intervals spreads its puffs along the distance the player moved, so the spacing stays even.
 pub fn trail_effect_spawn_system(
   mut commands: Commands,
-  query: Query<(&Player, &Transform)>,
+  query: Query<(&PlayerId, &Transform, &Sweep, &Boost, Entity), With<Player>>,
   mut rng: ResMut<Randoms>,
+  mut pool: ResMut<ParticlePool>,
+  mut timers: ResMut<EntitySpawnTimer>,
+  input: Res<InputState>,
+  state: Res<GameState>,
 ) {
-  for (_, transform) in query.iter() {
-    let radius = rng.gen_range(4.0..6.0);
-    let movement_direction = transform.rotation * glam::Vec3::Y;
-    let translation_delta = movement_direction * (12.0 + 2.0);
-    let translation = transform.translation - translation_delta + glam::vec3(0.0, 0.0, Z_INDEX_TRAIL_EFFECT);
-    let time_to_live = rng.gen_range(0.15..0.25);
+  // the puffs would pile up on the spot, none of them shrinking
+  if *state == GameState::Paused {
+    return;
+  }
+  for (id, transform, sweep, boost, entity) in query.iter() {
+    let boosting = input.player(*id).thrust == Thrust::Boost && boost.can_boost();
+    let interval = if boosting { TRAIL_BOOST_INTERVAL } else { TRAIL_INTERVAL };
+    let trail = &mut timers.player_mut(*id).trail;
+    trail.duration = Duration::from_secs_f32(interval);
+
+    let puffs = trail.times_finished();
+    for i in 0..puffs {
+      let along = sweep.from + (transform.position - sweep.from) * ((i + 1) as f32 / puffs as f32);
+      let radius = rng.gen_range(4.0..6.0);
+      let movement_direction = transform.rotation.forward();
+      let position = along - movement_direction * (12.0 + 2.0);
+      let time_to_live = rng.gen_range(0.15..0.25);
+      spawn_trail_puff(&mut commands, &mut pool, entity, transform, position, radius, time_to_live);
+    }
+  }
+}
 
-    commands
-        .spawn_empty()
-        .insert(TrailEffect)
-        .insert(Interpolation::new(vec![(radius, 0.0)], time_to_live, true))
-      .insert(Transform {
-        translation,
+/// One pooled circle for [`trail_effect_system`], shrinking from `radius` to nothing.
+fn spawn_trail_puff(
+  commands: &mut Commands,
+  pool: &mut ParticlePool,
+  owner: Entity,
+  transform: &Transform,
+  position: WorldPos,
+  radius: f32,
+  time_to_live: f32,
+) {
+  pool.acquire(
+    commands,
+    ParticleKind::Trail,
+    (
+      TrailEffect(owner),
+      Interpolation::new(vec![(radius, 0.0)], time_to_live, InterpolationMode::Once),
+      Transform {
+        position,
+        z: transform.z + Z_INDEX_TRAIL_EFFECT,
         ..*transform
-      });
+      },
+    ),
+  );
+}
+
+/// Keeps where everything was at the end of the tick before, see [`RenderAlpha`]. Runs before anything
+/// moves.
+pub fn previous_transform_system(
+  mut commands: Commands,
+  mut query: Query<(&Transform, Option<&mut PreviousTransform>, Entity)>,
+  mut camera: ResMut<Camera>,
+) {
+  for (transform, previous, entity) in query.iter_mut() {
+    match previous {
+      Some(mut previous) => previous.0 = *transform,
+      None => {
+        commands.entity(entity).insert(PreviousTransform(*transform));
+      }
+    }
   }
+  camera.previous_pos = camera.camera_pos;
 }
 
-pub fn trail_effect_system(
+/// Numbers the effects spawned or taken from the [`ParticlePool`] since the last run. The systems
+/// spawning effects don't have to agree on a counter, numbering afterwards in entity order is the
+/// same on every run.
+pub fn spawn_seq_system(
   mut commands: Commands,
-  mut query: Query<(&TrailEffect, &mut Interpolation, &Transform, Entity)>,
-  boost: Query<&Boost>,
-  mut circles: ResMut<CircleGeometry>,
-  mut tessellator: ResMut<Fills>,
-  keycodes: Res<KeyCodes>,
+  mut sequence: ResMut<SpawnSequence>,
+  query: Query<
+    (Entity, Option<&Active>),
+    (
+      Or<(Without<SpawnSeq>, Changed<Active>)>,
+      Or<(With<ExplosionEffect>, With<TrailEffect>, With<TickEffect>)>,
+    ),
+  >,
+) {
+  let mut spawned = query
+    .iter()
+    .filter(|(_, active)| active.is_none_or(|active| active.0))
+    .map(|(entity, _)| entity)
+    .collect::<Vec<_>>();
+  spawned.sort();
+  for entity in spawned {
+    commands.entity(entity).insert(sequence.take());
+  }
+}
+
+/// Shrinks the trail puffs and hands the finished ones back to the pool, [`draw_trail_effects_system`]
+/// draws them.
+pub fn trail_effect_system(
+  mut query: Query<(&mut Interpolation, &mut Active, Entity), With<TrailEffect>>,
+  mut pool: ResMut<ParticlePool>,
   time: Res<Time>,
 ) {
-  for (_, mut interpolation, transform, entity) in query.iter_mut() {
-    let (values, done) = interpolation.eval(time.as_secs_f32(), linear);
-    if done {
-      commands.entity(entity).despawn();
+  for (mut interpolation, mut active, entity) in query.iter_mut() {
+    if !active.0 {
       continue;
     }
-
-    let mut color_rgba = ColorGl::from(RGB_COLOR_TRAIL);
-
-    if let Ok(boost) = boost.get_single() {
-      if boost.can_boost() {
-        for keycode in keycodes.iter() {
-          match keycode {
-            Keycode::Up => color_rgba = ColorGl::from(RGB_COLOR_BOOST),
-            Keycode::Down => color_rgba = ColorGl::from(RGB_COLOR_BOOST),
-            _ => {}
-          }
-        }
-      }
+    let (_, done) = interpolation.eval(time.effects().as_secs_f32(), Easing::Linear);
+    if done {
+      active.0 = false;
+      pool.release(ParticleKind::Trail, entity);
     }
+  }
+}
 
-    tessellator
-      .tessellate_circle(
-        Point::new(0.0, 0.0),
-        values[0],
-        &FillOptions::default(),
-        &mut BuffersBuilder::new(
-          &mut circles.vertex_buffer,
-          WithTransformColor {
-            transform: transform.mat4(),
-            color_rgba,
-          },
-        ),
-      )
-      .unwrap();
+/// Lines flying apart from `transform`, like a ship breaking up.
+fn spawn_explosion_lines(
+  commands: &mut Commands,
+  pool: &mut ParticlePool,
+  rng: &mut Randoms,
+  transform: &Transform,
+  color: ColorGl,
+) {
+  for _ in 0..rng.gen_range(8usize..12usize) {
+    let length = rng.gen_range(2.0..8.0);
+    let width = 3.0;
+    let time_to_live = rng.gen_range(0.3..0.5);
+    let movement_speed = rng.gen_range(75.0..150.0);
+    let z_angle = rng.gen_range(0.0..2.0 * std::f32::consts::PI);
+    spawn_explosion_line(
+      commands,
+      pool,
+      Transform {
+        rotation: Angle::from_radians(z_angle),
+        ..*transform
+      },
+      color,
+      Interpolation::new(
+        vec![(movement_speed, 0.0), (length, 0.0), (width, 0.0)],
+        time_to_live,
+        InterpolationMode::Once,
+      ),
+    );
   }
 }
 
+/// One pooled line for [`explosion_system`], the interpolation tracks speed, length and width.
+fn spawn_explosion_line(
+  commands: &mut Commands,
+  pool: &mut ParticlePool,
+  transform: Transform,
+  color: ColorGl,
+  interpolation: Interpolation,
+) {
+  pool.acquire(
+    commands,
+    ParticleKind::Explosion,
+    (transform, ExplosionEffect::bundle(color, interpolation)),
+  );
+}
+
 pub fn player_explosion_spawn_system(
   mut commands: Commands,
   mut event_reader: EventReader<GameEvents>,
-  query: Query<(&Player, &Transform)>,
+  query: Query<(&PlayerAppearance, &Transform), With<Player>>,
   mut rng: ResMut<Randoms>,
+  mut distortions: ResMut<Distortions>,
+  mut pool: ResMut<ParticlePool>,
 ) {
   for event in event_reader.iter() {
-    match event {
-      GameEvents::PlayerDeath => {
-        for (_, transform) in query.iter() {
-          for _ in 0..rng.gen_range(8usize..12usize) {
-            let length = rng.gen_range(2.0..8.0);
-            let width = 3.0;
-            let time_to_live = rng.gen_range(0.3..0.5);
-            let movement_speed = rng.gen_range(75.0..150.0);
-            let z_angle = rng.gen_range(0.0..2.0 * std::f32::consts::PI);
-
-            commands
-                .spawn_empty()
-              .insert(Transform {
-                rotation: glam::Quat::from_rotation_z(z_angle),
-                ..*transform
-              })
-              .insert(ExplosionEffect {
-                color: ColorGl::from(RGB_COLOR_PLAYER),
-              })
-              .insert(Interpolation::new(
-                vec![(movement_speed, 0.0), (length, 0.0), (width, 0.0)],
-                time_to_live,
-                false,
-              ));
-          }
-        }
+    if let GameEvents::PlayerDeath(player, _) = event {
+      if let Ok((appearance, transform)) = query.get(*player) {
+        distortions.add(transform.position.to_render(), 40.0, 1.5);
+        spawn_explosion_lines(&mut commands, &mut pool, &mut rng, transform, appearance.color);
       }
     }
   }
 }
 
+/// Effects spawned this frame have no sequence yet and are drawn unbiased.
+fn z_biased(transform: glam::Mat4, seq: Option<&SpawnSeq>) -> glam::Mat4 {
+  let bias = seq.map_or(0.0, |seq| seq.z_bias());
+  glam::Mat4::from_translation(glam::vec3(0.0, 0.0, bias)) * transform
+}
+
+/// Sets the speed of explosion lines and sizes their [`Shape`]. Pooled ones go back to the
+/// [`ParticlePool`] once done, the rest are despawned.
 pub fn explosion_system(
   mut commands: Commands,
-  mut query: Query<(&ExplosionEffect, &mut Transform, &mut Interpolation, Entity)>,
-  mut lines: ResMut<LineGeometry>,
-  mut tessellator: ResMut<Strokes>,
+  mut query: Query<
+    (&mut Shape, &mut Velocity, &mut Interpolation, Option<&mut Active>, Entity),
+    With<ExplosionEffect>,
+  >,
+  mut pool: ResMut<ParticlePool>,
   time: Res<Time>,
 ) {
-  for (explosion, mut transform, mut interpolation, entity) in query.iter_mut() {
-    let (values, done) = interpolation.eval(time.as_secs_f32(), linear);
-    if done {
-      commands.entity(entity).despawn();
+  let time = time.effects();
+  for (mut shape, mut velocity, mut interpolation, active, entity) in query.iter_mut() {
+    if active.as_ref().is_some_and(|active| !active.0) {
       continue;
     }
-
-    let movement_speed = values[0];
-    let length = values[1];
-    let width = values[2];
-    let movement_direction = transform.rotation * glam::Vec3::Y;
-    let movement_distance = movement_speed * time.as_secs_f32();
-    let translation_delta = movement_direction * movement_distance;
-    transform.translation += translation_delta;
-
-    let mut builder = Path::builder();
-    builder.begin(point(0.0, 0.0));
-    builder.line_to(point(0.0, length));
-    builder.close();
-
-    let mut options = StrokeOptions::default();
-    options.line_width = width;
-    tessellator
-      .tessellate_path(
-        &builder.build(),
-        &options,
-        &mut BuffersBuilder::new(
-          &mut lines.vertex_buffer,
-          WithTransformColor {
-            transform: transform.mat4(),
-            color_rgba: explosion.color,
-          },
-        ),
-      )
-      .unwrap();
+    let (values, done) = interpolation.eval(time.as_secs_f32(), Easing::Linear);
+    let (movement_speed, length, width) = match (done, values.as_slice()) {
+      (false, &[movement_speed, length, width]) => (movement_speed, length, width),
+      _ => {
+        match active {
+          Some(mut active) => {
+            active.0 = false;
+            pool.release(ParticleKind::Explosion, entity);
+          }
+          None => {
+            commands.entity(entity).insert(Despawning::now());
+          }
+        }
+        continue;
+      }
+    };
+    *velocity = Velocity::Heading(movement_speed);
+    *shape = Shape::Line { length, width };
   }
 }
 
@@ -297,223 +651,306 @@ pub fn camera_shake_system(
   mut event_reader: EventReader<GameEvents>,
   mut camera: ResMut<Camera>,
   mut shake: ResMut<Shake>,
-  raw_time: Res<DurationWrapper>, // don't use Res<Time> here because I don't want to apply slow motion to camera shake
+  follow: Res<CameraFollow>,
+  time: Res<Time>, // raw time, I don't want to apply slow motion to camera shake
 ) {
-  let Shake { is_shaking, .. } = *shake;
-
   for event in event_reader.iter() {
-    match event {
-      GameEvents::PlayerDeath => shake.is_shaking = true,
+    if let GameEvents::Shake(request) = event {
+      shake.start(*request);
     }
   }
 
-  if is_shaking {
-    shake.time += raw_time.as_secs_f32();
-    if shake.time > shake.duration {
-      shake.time = 0.0;
-      shake.is_shaking = false;
-      return;
-    }
-
-    let s = shake.time * shake.frequency;
-    let s0 = f32::floor(s);
-    let s1 = s0 + 1.0;
-    let k = if shake.time >= shake.duration {
-      0.0
-    } else {
-      (shake.duration - shake.time) / shake.duration
-    };
+  // the offset is zero once the last shake ends, so the camera is back exactly where it follows to
+  let offset = shake.advance(time.raw().as_secs_f32());
+  camera.camera_pos = Camera::default().camera_pos + (follow.offset() + offset).extend(0.0);
+}
 
-    fn noise(samples: &[f32]) -> impl Fn(f32) -> f32 + '_ {
-      move |n| {
-        let n = n as usize;
-        if n >= samples.len() { 0.0 } else { samples[n] }
-      }
+pub fn camera_zoom_system(
+  mut event_reader: EventReader<GameEvents>,
+  mut camera: ResMut<Camera>,
+  mut punch: ResMut<ZoomPunch>,
+  follow: Res<CameraFollow>,
+  time: Res<Time>, // raw time, same as the shake
+) {
+  punch.time += time.raw().as_secs_f32();
+  for event in event_reader.iter() {
+    match event {
+      GameEvents::PlayerDeath(..) => punch.punch(ZOOM_PUNCH_DEATH, ZOOM_PUNCH_DURATION),
+      GameEvents::EnemyDeath(..) => punch.punch(ZOOM_PUNCH_KILL, ZOOM_PUNCH_DURATION),
+      GameEvents::BombDetonated => punch.punch(ZOOM_PUNCH_BOMB, ZOOM_PUNCH_DURATION),
+      _ => {}
     }
-    let noise_x = noise(&shake.samples_x);
-    let noise_y = noise(&shake.samples_y);
-    let amplitude = |noise_fn: &dyn Fn(f32) -> f32| -> f32 {
-      (noise_fn(s0) + (s - s0) * (noise_fn(s1) - noise_fn(s0))) * k * shake.amplitude
-    };
-
-    camera.camera_pos = glam::Vec3::new(amplitude(&noise_x), amplitude(&noise_y), 0.0);
   }
+
+  let zoom = follow.zoom * punch.zoom();
+  camera.camera_zoom = glam::vec3(zoom, zoom, 1.0);
 }
 
+/// Starts and runs out the [`Flash`], [`draw_screen_flash_system`] draws it.
 pub fn screen_flash_system(
   mut event_reader: EventReader<GameEvents>,
   mut flash: ResMut<Flash>,
-  mut quads: ResMut<QuadGeometry>,
-  mut tessellator: ResMut<Fills>,
+  mut flash_guard: ResMut<FlashGuard>,
+  raw_time: Res<DurationWrapper>,
 ) {
   for event in event_reader.iter() {
-    match event {
-      GameEvents::PlayerDeath => flash.is_flashing = true,
+    if let GameEvents::Flash { color, duration } = event {
+      if flash_guard.flash() {
+        *flash = Flash {
+          timer: Timer::from_seconds(*duration, false),
+          color: *color,
+        };
+      }
     }
   }
 
-  if flash.is_flashing {
-    flash.frame_cnt -= 1;
+  flash.timer.tick(**raw_time);
+}
 
-    if flash.frame_cnt > 0 {
-      tessellator
-        .tessellate_rectangle(
-          &Box2D::from_size(Size::new(SCREEN_WIDTH as f32, SCREEN_HEIGHT as f32)),
-          &FillOptions::default(),
-          &mut BuffersBuilder::new(
-            &mut quads.vertex_buffer,
-            WithTransformColor {
-              transform: glam::Mat4::from_translation(glam::vec3(0.0, 0.0, 100.0)),
-              color_rgba: ColorGl::from(RGB_COLOR_PLAYER),
-            },
-          ),
-        )
-        .unwrap();
-    } else {
-      *flash = Flash::default();
-    }
+pub fn draw_screen_flash_system(flash: Res<Flash>, mut quads: ResMut<QuadGeometry>, mut fills: ResMut<Fills>) {
+  if flash.timer.finished {
+    return;
   }
+  fills
+    .tessellate_rectangle(
+      &Box2D::from_size(Size::new(SCREEN_WIDTH as f32, SCREEN_HEIGHT as f32)),
+      &FillOptions::default(),
+      &mut BuffersBuilder::new(
+        &mut quads.vertex_buffer,
+        WithTransformColor {
+          transform: glam::Mat4::from_translation(glam::vec3(0.0, 0.0, 100.0)),
+          color_rgba: flash.color,
+        },
+      ),
+    )
+    .ok();
 }
 
+/// Fires every player's [`Attack`] and sets the projectile interval to match it. Held shoot adds two
+/// diagonal shots to any attack.
 pub fn projectile_spawn_system(
-  query: Query<(&Player, &Transform)>,
+  mut query: Query<(&PlayerId, &Velocity, &Transform, &mut Attack, &mut Ammo), With<Player>>,
   mut commands: Commands,
-  timer: Res<EntitySpawnTimer>,
-  keycodes: Res<KeyCodes>,
+  mut event_writer: EventWriter<GameEvents>,
+  mut timers: ResMut<EntitySpawnTimer>,
+  input: Res<InputState>,
+  modifiers: Res<Modifiers>,
 ) {
-  for (player, transform) in query.iter() {
-    if timer.projectile.finished {
-      let movement_direction = transform.rotation * glam::Vec3::Y;
-      let translation_delta = movement_direction * 12.0;
-      let translation = transform.translation + translation_delta;
-
-      commands
-          .spawn_empty()
-        .insert(Transform {
-          translation,
-          ..*transform
-        })
-        .insert(Projectile {
-          movement_speed: player.movement_speed * 2.0,
-        });
-
-      if keycodes.contains(&Keycode::Space) {
-        let movement_direction = transform.rotation * glam::vec3(1.0, 1.0, 0.0);
-        let translation_delta = movement_direction * 12.0;
-        let translation = transform.translation + translation_delta;
-
-        commands
-            .spawn_empty()
-          .insert(Transform {
-            translation,
-            ..*transform
-          })
-          .insert(Projectile {
-            movement_speed: player.movement_speed * 2.0,
-          });
+  let projectile_speed = PROJECTILE_SPEED * modifiers.projectile_speed_mult;
+  for (id, player_velocity, transform, mut attack, mut ammo) in query.iter_mut() {
+    let timer = &mut timers.player_mut(*id).projectile;
+    if timer.just_finished() {
+      let mut shots = attack
+        .angles()
+        .iter()
+        .map(|degrees| (WorldVec::new(0.0, 1.0), Angle::from_degrees(*degrees)))
+        .collect::<Vec<_>>();
+      if input.player(*id).shoot {
+        shots.extend([WorldVec::new(1.0, 1.0), WorldVec::new(-1.0, 1.0)].map(|offset| (offset, Angle::default())));
+      }
 
-        let movement_direction = transform.rotation * glam::vec3(-1.0, 1.0, 0.0);
-        let translation_delta = movement_direction * 12.0;
-        let translation = transform.translation + translation_delta;
+      let before = ammo.current;
+      for (offset, angle) in shots {
+        if !ammo.consume(AMMO_PER_SHOT) {
+          break;
+        }
 
-        commands
-            .spawn_empty()
-          .insert(Transform {
-            translation,
+        // faces where it flies, so homing turns it the right way
+        let velocity =
+          player_velocity.linear(transform.rotation) + (transform.rotation + angle).forward_by(projectile_speed);
+        let mut projectile = commands.spawn((
+          Transform {
+            position: transform.position + transform.rotation.rotate(offset) * 12.0,
+            rotation: Angle::facing(velocity),
             ..*transform
-          })
-          .insert(Projectile {
-            movement_speed: player.movement_speed * 2.0,
-          });
+          },
+          Projectile::bundle(velocity),
+          attack.death_action(),
+        ));
+        if *attack == Attack::Homing {
+          projectile.insert((Homing, Lifetime(Timer::from_seconds(HOMING_PROJECTILE_LIFETIME, false))));
+        }
+      }
+      if ammo.current != before {
+        event_writer.send(GameEvents::Shot(*attack));
+        event_writer.send(GameEvents::AmmoChanged);
       }
+      if ammo.current == 0 {
+        *attack = Attack::Neutral;
+      }
+    }
+    timer.duration = Duration::from_secs_f32(attack.interval());
+  }
+}
+
+pub fn homing_projectile_system(
+  mut query: Query<(&mut Velocity, &mut Transform), (With<Homing>, With<Projectile>, Without<Despawning>)>,
+  targets: Query<
+    &Transform,
+    (
+      Or<(With<Enemy>, With<AmmoPickup>, With<BoostPickup>, With<AttackPickup>)>,
+      Without<Projectile>,
+      Without<Despawning>,
+    ),
+  >,
+  time: Res<Time>,
+) {
+  for (mut velocity, mut transform) in query.iter_mut() {
+    let nearest = targets
+      .iter()
+      .map(|target| target.position)
+      .min_by(|a, b| {
+        let (a, b) = (a.distance_squared(transform.position), b.distance_squared(transform.position));
+        a.total_cmp(&b)
+      });
+    if let Some(target) = nearest {
+      let max_turn = HOMING_TURN_SPEED * time.as_secs_f32();
+      let turn = steer_towards(transform.rotation, transform.position, target, max_turn, 1.0);
+      transform.rotation += turn;
+      velocity.turn(turn);
     }
   }
 }
 
+/// Drops a puff of trail where a projectile is every [`PROJECTILE_TRAIL_INTERVAL`] and ends the ones
+/// that left the arena or whose [`Lifetime`] ran out.
 pub fn projectile_system(
   mut commands: Commands,
-  mut query: Query<(&Projectile, &mut Transform, Entity)>,
-  mut circles: ResMut<CircleGeometry>,
-  mut tessellator: ResMut<Strokes>,
+  mut event_writer: EventWriter<GameEvents>,
+  mut query: Query<
+    (&mut Projectile, Option<&mut Lifetime>, &DeathAction, &Transform, Entity),
+    Without<Despawning>,
+  >,
+  mut rng: ResMut<Randoms>,
+  mut pool: ResMut<ParticlePool>,
   time: Res<Time>,
+  bounds: Res<WorldBounds>,
 ) {
-  for (projectile, mut transform, entity) in query.iter_mut() {
-    let pos = transform.translation.xy();
-    if screen_ouf_of_bounds_test(pos, None) {
-      commands.entity(entity).despawn();
-
-      let clamped_x = pos.x.clamp(0.0, SCREEN_WIDTH as f32 - DEAD_PROJECTILE_HEIGHT);
-      let clamped_y = pos.y.clamp(0.0, SCREEN_HEIGHT as f32 - DEAD_PROJECTILE_HEIGHT);
-      let translation = glam::vec3(clamped_x, clamped_y, 1.0);
-      let rotation = if pos.x < 0.0 || pos.x > SCREEN_WIDTH as f32 {
-        glam::Quat::from_rotation_z(-std::f32::consts::PI / 2.0)
-      } else {
-        glam::Quat::from_rotation_z(0.0)
-      };
+  for (mut projectile, lifetime, death_action, transform, entity) in query.iter_mut() {
+    let expired = lifetime.is_some_and(|mut lifetime| {
+      lifetime.0.tick(**time);
+      lifetime.0.finished
+    });
+    if expired || bounds.is_outside(transform.position, 0.0) {
+      let position = transform.position;
+      resolve_projectile_death(&mut commands, &mut pool, &mut event_writer, entity, *death_action, position, &bounds);
+      continue;
+    }
 
-      commands
-          .spawn_empty()
-          .insert(Transform {
-            translation,
-            rotation,
-            ..Default::default()
-          })
+    projectile.trail.tick(**time);
+    for _ in 0..projectile.trail.times_finished() {
+      let radius = rng.gen_range(1.0..2.0);
+      let time_to_live = rng.gen_range(0.1..0.2);
+      spawn_trail_puff(&mut commands, &mut pool, entity, transform, transform.position, radius, time_to_live);
+    }
+  }
+}
+
+/// Removes a projectile, performs its [`DeathAction`] and gives the camera a small shake. Every path
+/// that ends a projectile goes through here.
+fn resolve_projectile_death(
+  commands: &mut Commands,
+  pool: &mut ParticlePool,
+  event_writer: &mut EventWriter<GameEvents>,
+  entity: Entity,
+  death_action: DeathAction,
+  position: WorldPos,
+  bounds: &WorldBounds,
+) {
+  commands.entity(entity).insert(Despawning::now());
+  event_writer.send(GameEvents::Shake(ShakeRequest::PROJECTILE_DEATH));
+
+  // a splat lies along the edge it hit, on the side walls it is turned upright and spans
+  // [0, height] x [-width, 0] instead of [0, width] x [0, height]
+  let upright = matches!(bounds.crossed_edge(position), Some(Edge::Left | Edge::Right));
+  let (rotation, min, max) = if upright {
+    (
+      Angle::from_radians(-std::f32::consts::PI / 2.0),
+      bounds.min + glam::vec2(0.0, DEAD_PROJECTILE_WIDTH),
+      bounds.max - glam::vec2(DEAD_PROJECTILE_HEIGHT, 0.0),
+    )
+  } else {
+    (
+      Angle::from_radians(0.0),
+      bounds.min,
+      bounds.max - glam::vec2(DEAD_PROJECTILE_WIDTH, DEAD_PROJECTILE_HEIGHT),
+    )
+  };
+  let clamped = position.clamp(WorldPos::from(min), WorldPos::from(max));
+  let transform = Transform {
+    position: clamped,
+    z: 1.0,
+    ..Default::default()
+  };
+
+  match death_action {
+    DeathAction::Splat => {
+      commands
+        .spawn_empty()
+        .insert(ScopeTag(GameScope::Run))
+        .insert(Transform { rotation, ..transform })
         .insert(DeadProjectile {
           timer: Timer::from_seconds(0.25, false),
-        });
+        })
+        .insert(DeadProjectile::SHAPE);
     }
-
-    let movement_direction = transform.rotation * glam::Vec3::Y;
-    let movement_distance = projectile.movement_speed * time.as_secs_f32();
-    let translation_delta = movement_direction * movement_distance;
-    transform.translation += translation_delta;
-
-    tessellator
-      .tessellate_circle(
-        Point::new(0.0, 0.0),
-        2.5,
-        &StrokeOptions::default(),
-        &mut BuffersBuilder::new(
-          &mut circles.vertex_buffer,
-          WithTransformColor {
-            transform: transform.mat4(),
-            color_rgba: ColorGl::from(RGB_COLOR_PLAYER),
+    DeathAction::Fizzle => {
+      for i in 0..4 {
+        spawn_explosion_line(
+          commands,
+          pool,
+          Transform {
+            rotation: Angle::from_radians(i as f32 * std::f32::consts::PI / 2.0),
+            ..transform
           },
-        ),
-      )
-      .unwrap();
+          ColorGl::from(RGB_COLOR_DEATH),
+          Interpolation::new(vec![(20.0, 0.0), (3.0, 0.0), (1.5, 0.0)], 0.2, InterpolationMode::Once),
+        );
+      }
+    }
+    // the children start inside the bounds, otherwise they would die on their first tick
+    DeathAction::RadialBurst { count, speed } => {
+      for i in 0..count {
+        let rotation = Angle::from_radians(i as f32 * 2.0 * std::f32::consts::PI / count as f32);
+        commands
+          .spawn_empty()
+          .insert(Transform { rotation, ..transform })
+          .insert(Projectile::bundle(rotation.forward_by(speed)))
+          .insert(DeathAction::Splat);
+      }
+    }
+    DeathAction::Nothing => {}
   }
 }
 
 pub fn projectile_death_system(
   mut commands: Commands,
-  mut query: Query<(&mut DeadProjectile, &Transform, Entity)>,
-  mut quads: ResMut<QuadGeometry>,
-  mut tessellator: ResMut<Fills>,
+  mut query: Query<(&mut DeadProjectile, &Transform, Entity), Without<Despawning>>,
   time: Res<Time>,
 ) {
   for (mut dead_projectile, transform, entity) in query.iter_mut() {
     dead_projectile.timer.tick(**time);
 
     if dead_projectile.timer.finished {
-      commands.entity(entity).despawn();
-      continue;
+      commands.entity(entity).insert(Despawning::bundle(
+        ExitAnim::Shrink,
+        transform.mat4(),
+        glam::vec2(DEAD_PROJECTILE_WIDTH, DEAD_PROJECTILE_HEIGHT),
+        ColorGl::from(RGB_COLOR_DEATH),
+      ));
     }
+  }
+}
 
-    let color_rgba = if dead_projectile.timer.elapsed.as_secs_f32() >= 0.1 {
-      ColorGl::from(RGB_COLOR_DEATH)
-    } else {
-      ColorGl::from(RGB_COLOR_PLAYER)
-    };
-    let transform = glam::Mat4::from_rotation_translation(transform.rotation, transform.translation);
-    tessellator
-      .tessellate_rectangle(
-        &Box2D::from_size(Size::new(DEAD_PROJECTILE_WIDTH, DEAD_PROJECTILE_HEIGHT)),
-        &FillOptions::default(),
-        &mut BuffersBuilder::new(&mut quads.vertex_buffer, WithTransformColor { transform, color_rgba }),
-      )
-      .unwrap();
+/// Counts how long the players go without each pickup, only while any is alive. Boost only counts as
+/// full once every player's is.
+pub fn pickup_pity_system(mut pity: ResMut<PickupPity>, player_query: Query<&Boost, With<Player>>, time: Res<Time>) {
+  if player_query.is_empty() {
+    return;
   }
+  pity.since_ammo += time.as_secs_f32();
+  pity.since_boost += time.as_secs_f32();
+  pity.boost_full = player_query.iter().all(|boost| boost.boost >= boost.max_boost);
 }
 
 pub fn timing_system(
@@ -521,162 +958,334 @@ pub fn timing_system(
   mut timers: ResMut<EntitySpawnTimer>,
   raw_time: Res<DurationWrapper>, // this is set in main() with *world.resource_mut() = dt;
   mut time: ResMut<Time>,
+  mut time_scale: ResMut<TimeScale>,
+  players: Query<&PlayerId, With<Player>>,
+  bounds: Res<WorldBounds>,
+  modifiers: Res<Modifiers>,
+  pity: Res<PickupPity>,
+  state: Res<GameState>,
 ) {
   for event in event_reader.iter() {
-    match event {
-      GameEvents::PlayerDeath => time.slow_down_timer = Some(Duration::default()),
+    if let GameEvents::SlowMotion(slow_motion) = event {
+      time_scale.start(*slow_motion);
     }
   }
 
-  if let Some(mut timer) = time.slow_down_timer.take() {
-    timer += **raw_time;
-    if timer.as_secs_f32() <= SLOW_DOWN_DURATION_ON_DEATH {
-      let easing = ease_in_out_cubic(timer.as_secs_f32() / SLOW_DOWN_DURATION_ON_DEATH);
-      let slow_amount = (1.0 - easing) * 0.15 + easing * 1.0;
-      **time = Duration::from_secs_f32(raw_time.as_secs_f32() * slow_amount);
-      time.slow_down_timer.replace(timer);
-    }
+  // every domain stands still, the slow motions and the spawn timers included
+  if *state == GameState::Paused {
+    time.advance(Duration::ZERO);
+    return;
+  }
+
+  time.set_scale(TimeDomain::Gameplay, time_scale.advance(**raw_time));
+
+  let game_over = players.is_empty() && !time_scale.is_slowed();
+  if game_over {
+    time.set_scale(TimeDomain::Background, BACKGROUND_TIME_SCALE);
+    time.effects_domain = TimeDomain::Background;
   } else {
-    **time = **raw_time;
+    time.set_scale(TimeDomain::Background, 1.0);
+    time.effects_domain = TimeDomain::Gameplay;
   }
+  time.advance(**raw_time);
 
-  for timer in timers.as_array() {
-    timer.tick(**time);
+  if bounds.is_changed() {
+    timers.normalize(&bounds, &modifiers);
+  }
+  // a player's own timers freeze while its ship is gone, a respawned player starts where it stopped
+  for id in players.iter() {
+    timers.player_mut(*id);
+  }
+  for (i, player) in timers.players.iter_mut().enumerate() {
+    let alive = players.iter().any(|id| id.0 as usize == i);
+    for timer in [&mut player.projectile, &mut player.tick_effect, &mut player.trail] {
+      if alive {
+        timer.unpause();
+      } else {
+        timer.pause();
+      }
+      timer.tick(**time);
+    }
+  }
+  for (timer, rate) in timers.as_array().into_iter().zip(pity.rates()) {
+    timer.tick(time.mul_f32(rate));
   }
 }
 
-pub fn tick_effect_spawn_system(query: Query<&Player>, mut commands: Commands, timer: Res<EntitySpawnTimer>) {
-  for _ in query.iter() {
-    if timer.tick_effect.finished {
+pub fn tick_effect_spawn_system(
+  query: Query<(&PlayerId, Entity), With<Player>>,
+  mut commands: Commands,
+  mut timers: ResMut<EntitySpawnTimer>,
+) {
+  for (id, player) in query.iter() {
+    if timers.player_mut(*id).tick_effect.just_finished() {
       commands
           .spawn_empty()
-          .insert(TickEffect)
-          .insert(Interpolation::new(vec![(32.0, 0.0)], 0.13, true));
+          .insert(ScopeTag(GameScope::Persistent))
+          .insert(TickEffect(player))
+          .insert(Interpolation::new(vec![(32.0, 0.0)], 0.13, InterpolationMode::Once));
     }
   }
 }
 
+/// Runs out every tick effect, the ones of a dead player go with it. [`draw_tick_effects_system`] draws
+/// them.
 pub fn tick_effect_system(
   mut commands: Commands,
-  player_query: Query<(&Player, &Transform)>,
-  mut tick_effect_query: Query<(&TickEffect, &mut Interpolation, Entity)>,
-  mut quads: ResMut<QuadGeometry>,
-  mut tessellator: ResMut<Fills>,
+  player_query: Query<(), With<Player>>,
+  mut tick_effect_query: Query<(&TickEffect, &mut Interpolation, Entity), Without<Despawning>>,
   time: Res<Time>,
 ) {
-  for (_, transform) in player_query.iter() {
-    for (_, mut interpolation, entity) in tick_effect_query.iter_mut() {
-      let (values, done) = interpolation.eval(time.as_secs_f32(), ease_in_out_cubic);
-      if done {
-        commands.entity(entity).despawn();
-        continue;
-      }
-
-      let mat4 = glam::Mat4::from_translation(transform.translation)
-        * glam::Mat4::from_translation(glam::vec3(48.0 / -2.0, 32.0 / 2.0 - values[0], Z_INDEX_PLAYER));
-      tessellator
-        .tessellate_rectangle(
-          &Box2D::from_size(Size::new(48.0, values[0])),
-          &FillOptions::default(),
-          &mut BuffersBuilder::new(
-            &mut quads.vertex_buffer,
-            WithTransformColor {
-              transform: mat4,
-              color_rgba: ColorGl::from(RGB_COLOR_PLAYER),
-            },
-          ),
-        )
-        .unwrap();
+  for (tick_effect, mut interpolation, entity) in tick_effect_query.iter_mut() {
+    let (_, done) = interpolation.eval(time.effects().as_secs_f32(), Easing::InOutCubic);
+    if done || !player_query.contains(tick_effect.0) {
+      commands.entity(entity).insert(Despawning::now());
     }
   }
 }
 
-pub fn ammo_pickup_spawn_system(mut commands: Commands, timer: Res<EntitySpawnTimer>, mut rng: ResMut<Randoms>) {
-  if timer.ammo_pickup.finished {
-    let x = rng.gen_range(8.0..SCREEN_WIDTH as f32 - 8.0);
-    let y = rng.gen_range(8.0..SCREEN_HEIGHT as f32 - 8.0);
-    let rotation = glam::Quat::from_rotation_z(rng.gen_range(0.0..2.0 * std::f32::consts::PI));
+/// Every tick effect over its player.
+pub fn draw_tick_effects_system(
+  player_query: Query<(&PlayerAppearance, &Transform, Option<&PreviousTransform>), With<Player>>,
+  tick_effect_query: Query<(&TickEffect, &Interpolation, Option<&SpawnSeq>), Without<Despawning>>,
+  mut quads: ResMut<QuadGeometry>,
+  mut fills: ResMut<Fills>,
+  alpha: Res<RenderAlpha>,
+) {
+  for (tick_effect, interpolation, seq) in tick_effect_query.iter() {
+    let values = interpolation.values(Easing::InOutCubic);
+    let (Ok((appearance, transform, previous)), &[height]) = (player_query.get(tick_effect.0), values.as_slice()) else {
+      continue;
+    };
+
+    let transform = interpolated(transform, previous, *alpha);
+    let mat4 = glam::Mat4::from_translation(transform.translation())
+      * glam::Mat4::from_translation(glam::vec3(48.0 / -2.0, 32.0 / 2.0 - height, Z_INDEX_PLAYER));
+    fills
+      .tessellate_rectangle(
+        &Box2D::from_size(Size::new(48.0, height)),
+        &FillOptions::default(),
+        &mut BuffersBuilder::new(
+          &mut quads.vertex_buffer,
+          WithTransformColor {
+            transform: z_biased(mat4, seq),
+            color_rgba: appearance.color,
+          },
+        ),
+      )
+      .ok();
+  }
+}
+
+pub fn ammo_pickup_spawn_system(
+  mut commands: Commands,
+  timer: Res<EntitySpawnTimer>,
+  mut rng: ResMut<Randoms>,
+  bounds: Res<WorldBounds>,
+) {
+  if timer.ammo_pickup.just_finished() {
+    let x = rng.gen_range(bounds.min.x + 8.0..bounds.max.x - 8.0);
+    let y = rng.gen_range(bounds.min.y + 8.0..bounds.max.y - 8.0);
+    let rotation = Angle::from_radians(rng.gen_range(0.0..2.0 * std::f32::consts::PI));
     let movement_speed = rng.gen_range(10.0..20.0);
     let rotation_speed = std::f32::consts::PI;
 
     commands
         .spawn_empty()
+        .insert(ScopeTag(GameScope::Run))
         .insert(AmmoPickup {
-          movement_speed,
           rotation_speed,
-          center_rotation_speed: rng.gen_range(-2.0 * std::f32::consts::PI..2.0 * std::f32::consts::PI),
           timer: Timer::from_seconds(0.15, false),
         })
+        .insert(Velocity::Heading(movement_speed))
+        .insert(AngularVelocity(rng.gen_range(-2.0 * std::f32::consts::PI..2.0 * std::f32::consts::PI)))
       .insert(Transform {
-        translation: glam::vec3(x, y, Z_INDEX_AMMO_PICKUP),
+        position: WorldPos::new(x, y),
+        z: Z_INDEX_AMMO_PICKUP,
         rotation,
         ..Default::default()
+      })
+      .insert(Collider {
+        radius: AMMO_PICKUP_RADIUS,
       });
   }
 }
 
+/// Puts every collider into the [`SpatialGrid`], once everything that moves before the collision checks
+/// has moved. Once a huge wave is cleared the room its cells grew to is given back a row per task step.
+pub fn spatial_index_system(
+  query: Query<(&Transform, &Collider, Entity)>,
+  mut grid: ResMut<SpatialGrid>,
+  mut tasks: ResMut<MainThreadTasks>,
+  bounds: Res<WorldBounds>,
+) {
+  grid.reset(&bounds);
+  for (transform, collider, entity) in query.iter() {
+    grid.insert(entity, transform.position, collider.radius);
+  }
+
+  if grid.shrinking || !grid.is_oversized() {
+    return;
+  }
+  grid.shrinking = true;
+  let mut row = 0;
+  tasks.push("spatial grid", SPATIAL_GRID_ROW_COST, move |world| {
+    let mut grid = world.resource_mut::<SpatialGrid>();
+    if row < grid.rows() {
+      grid.shrink_row(row);
+      row += 1;
+      return TaskStatus::Continue;
+    }
+    grid.shrinking = false;
+    TaskStatus::Done
+  });
+}
+
+/// Sends a [`GameEvents::PlayerContact`] for every collider a player swept into, including colliders
+/// it passed through during the tick. Only the colliders the [`SpatialGrid`] has near the sweep are
+/// checked, the players' own projectiles never touch them.
+pub fn collision_system(
+  player_query: Query<(&Transform, &Sweep, &Collider, Entity), With<Player>>,
+  query: Query<(&Transform, &Collider), (Without<Player>, Without<Projectile>, Without<Despawning>)>,
+  grid: Res<SpatialGrid>,
+  mut event_writer: EventWriter<GameEvents>,
+) {
+  for (player_transform, sweep, player_collider, player) in player_query.iter() {
+    let (from, to) = (sweep.from, player_transform.position);
+    let near = if from.distance_squared(to) <= MIN_SWEEP_DISTANCE * MIN_SWEEP_DISTANCE {
+      grid.query_circle(to, player_collider.radius).collect::<Vec<_>>()
+    } else {
+      let reach = WorldVec::splat(player_collider.radius);
+      grid.query_aabb(from.min(to) - reach, from.max(to) + reach).collect()
+    };
+    // in the order the sweep reached them, a fast ship through several collects them front to back
+    let mut touched = near
+      .into_iter()
+      .filter_map(|other| {
+        let (transform, collider) = query.get(other).ok()?;
+        let radius = player_collider.radius + collider.radius;
+        swept_contact(from, to, transform.position, radius, MIN_SWEEP_DISTANCE)
+          .then(|| (closest_point_on_segment(from, to, transform.position).distance_squared(from), other))
+      })
+      .collect::<Vec<_>>();
+    touched.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    for (_, other) in touched {
+      event_writer.send(GameEvents::PlayerContact { player, other });
+    }
+  }
+}
+
+/// Remembers where every [`Hostile`] projectile starts the tick, before it moves.
+pub fn hostile_sweep_system(mut query: Query<(&Transform, &mut Sweep), With<Hostile>>) {
+  for (transform, mut sweep) in query.iter_mut() {
+    sweep.from = transform.position;
+  }
+}
+
+/// Kills every player a [`Hostile`] projectile touched during the tick, and the projectile with it. Both
+/// moved, so the player's move is swept relative to the projectile's, a projectile flying through a
+/// player between two ticks still hits.
+pub fn hostile_projectile_system(
+  mut commands: Commands,
+  projectiles: Query<(&Transform, &Sweep, &Collider, &DeathAction, Entity), (With<Hostile>, Without<Despawning>)>,
+  players: Query<(&Transform, &Sweep, &Collider, Entity), (With<Player>, Without<Invulnerable>, Without<Despawning>)>,
+  mut event_writer: EventWriter<GameEvents>,
+  mut pool: ResMut<ParticlePool>,
+  bounds: Res<WorldBounds>,
+  mut deaths: ResMut<Deaths>,
+) {
+  for (transform, sweep, collider, death_action, projectile) in projectiles.iter() {
+    let target = players.iter().find(|(player_transform, player_sweep, player_collider, player)| {
+      let from = WorldPos::default() + (player_sweep.from - sweep.from);
+      let to = WorldPos::default() + (player_transform.position - transform.position);
+      let radius = player_collider.radius + collider.radius;
+      !deaths.contains(*player) && swept_contact(from, to, WorldPos::default(), radius, MIN_SWEEP_DISTANCE)
+    });
+    let Some((.., player)) = target else {
+      continue;
+    };
+    kill_player(&mut commands, &mut event_writer, &mut deaths, player, DeathCause::Projectile);
+    let position = transform.position;
+    resolve_projectile_death(&mut commands, &mut pool, &mut event_writer, projectile, *death_action, position, &bounds);
+  }
+}
+
+/// What the players touched this tick, each with the player who touched it first, see
+/// [`collision_system`]. The pickup systems read the contacts and send events of their own, so they
+/// take the reader and the writer as a `ParamSet`.
+fn player_contacts(event_reader: &mut EventReader<GameEvents>) -> HashMap<Entity, Entity> {
+  let mut contacts = HashMap::new();
+  for event in event_reader.iter() {
+    if let GameEvents::PlayerContact { player, other } = event {
+      contacts.entry(*other).or_insert(*player);
+    }
+  }
+  contacts
+}
+
 pub fn ammo_pickup_system(
   mut commands: Commands,
-  player_query: Query<&Transform, With<Player>>,
-  mut query: Query<(&mut AmmoPickup, &mut Transform, Entity), Without<Player>>,
-  mut quads: ResMut<QuadGeometry>,
-  mut strokes: ResMut<Strokes>,
-  mut fills: ResMut<Fills>,
+  mut player_query: Query<(&Transform, &mut Ammo), With<Player>>,
+  mut query: Query<
+    (&mut AmmoPickup, &mut Transform, &mut Velocity, &mut AngularVelocity, Entity),
+    (Without<Player>, Without<Despawning>),
+  >,
   time: Res<Time>,
   mut rng: ResMut<Randoms>,
+  bounds: Res<WorldBounds>,
+  mut pity: ResMut<PickupPity>,
+  mut pool: ResMut<ParticlePool>,
+  mut events: ParamSet<(EventReader<GameEvents>, EventWriter<GameEvents>)>,
+  mut pickups: EventWriter<PickupEvent>,
 ) {
-  for (mut ammo, mut transform, entity) in query.iter_mut() {
-    let pos = transform.translation.xy();
-    if screen_ouf_of_bounds_test(pos, Some(8.0)) {
-      commands.entity(entity).despawn();
+  let contacts = player_contacts(&mut events.p0());
+  let mut event_writer = events.p1();
+  for (mut ammo, mut transform, mut velocity, mut angular_velocity, entity) in query.iter_mut() {
+    if bounds.is_outside(transform.position, 8.0) {
+      commands.entity(entity).insert(Despawning::now());
       continue;
     }
 
     if ammo.timer.finished {
-      commands.entity(entity).despawn();
+      commands.entity(entity).insert(Despawning::bundle(
+        ExitAnim::Pop,
+        transform.mat4_center() * glam::Mat4::from_translation(glam::vec3(9.5 / -2.0, 9.5 / -2.0, 1.0)),
+        glam::vec2(9.5, 9.5),
+        ColorGl::from(RGB_COLOR_AMMO_PICKUP),
+      ));
       continue;
     }
 
+    // collected, it stays put while it pops
     if ammo.timer.elapsed.as_secs_f32() > 0.0 {
       ammo.timer.tick(**time);
-      let mat4 = transform.mat4_center() * glam::Mat4::from_translation(glam::vec3(9.5 / -2.0, 9.5 / -2.0, 1.0));
-
-      fills
-          .tessellate_rectangle(
-            &Box2D::from_size(Size::new(9.5, 9.5)),
-            &FillOptions::default(),
-            &mut BuffersBuilder::new(
-              &mut quads.vertex_buffer,
-              WithTransformColor {
-                transform: mat4,
-                color_rgba: ColorGl::from(RGB_COLOR_AMMO_PICKUP),
-              },
-            ),
-          )
-        .unwrap();
+      *velocity = Velocity::Heading(0.0);
+      angular_velocity.0 = 0.0;
       continue;
     }
 
-    if let Ok(player) = player_query.get_single() {
-      let player_translation = player.translation.xy();
-      let ammo_forward = (transform.rotation * glam::Vec3::Y).xy();
-      let to_player = (player_translation - transform.translation.xy()).normalize();
-      let forward_dot_player = ammo_forward.dot(to_player);
-
-      if (forward_dot_player - 1.0).abs() < f32::EPSILON {
-        continue;
-      }
-
-      let ammo_right = (transform.rotation * glam::Vec3::X).xy();
-      let right_to_player = ammo_right.dot(to_player);
-      let rotation_sign = -f32::copysign(1.0, right_to_player);
-      let max_angle = forward_dot_player.clamp(-1.0, 1.0).acos();
-      let rotation_angle = rotation_sign * (ammo.rotation_speed * time.as_secs_f32()).min(max_angle);
-      transform.rotation *= glam::Quat::from_rotation_z(rotation_angle);
-
-      let distance = (transform.translation - player.translation).length();
-      if distance < 8.0 + 12.0 {
+    let nearest = player_query.iter().map(|(player, _)| player.position).min_by(|a, b| {
+      let distance = |position: &WorldPos| position.distance_squared(transform.position);
+      distance(a).total_cmp(&distance(b))
+    });
+    if let Some(target) = nearest {
+      // eases onto the nearest player instead of snapping, a nearly aligned pickup no longer twitches
+      let dt = time.as_secs_f32();
+      let ease = 1.0 - (-AMMO_PICKUP_STEER_SMOOTHING * dt).exp();
+      let turn = steer_towards(transform.rotation, transform.position, target, ammo.rotation_speed * dt, ease);
+      transform.rotation += turn;
+
+      if let Some(player) = contacts.get(&entity) {
+        commands.entity(entity).remove::<Collider>();
         ammo.timer.tick(**time);
+        pity.since_ammo = 0.0;
+        pickups.send(PickupEvent(Pickup::Ammo));
+        // the ammo goes to whoever picked it up
+        if let Ok((_, mut shots)) = player_query.get_mut(*player) {
+          if shots.add(AMMO_PICKUP_AMOUNT) {
+            event_writer.send(GameEvents::AmmoChanged);
+          }
+        }
 
         for _ in 0..rng.gen_range(4usize..8usize) {
           let length = 5.0;
@@ -685,192 +1294,189 @@ pub fn ammo_pickup_system(
           let movement_speed = rng.gen_range(75.0..150.0);
           let z_angle = rng.gen_range(0.0..2.0 * std::f32::consts::PI);
 
-          commands
-              .spawn_empty()
-            .insert(Transform {
-              rotation: glam::Quat::from_rotation_z(z_angle),
+          spawn_explosion_line(
+            &mut commands,
+            &mut pool,
+            Transform {
+              rotation: Angle::from_radians(z_angle),
               ..*transform
-            })
-            .insert(ExplosionEffect {
-              color: ColorGl::from(RGB_COLOR_AMMO_PICKUP),
-            })
-            .insert(Interpolation::new(
+            },
+            ColorGl::from(RGB_COLOR_AMMO_PICKUP),
+            Interpolation::new(
               vec![(movement_speed, 0.0), (length, 0.0), (width, 0.0)],
               time_to_live,
-              false,
-            ));
+              InterpolationMode::Once,
+            ),
+          );
         }
       }
     }
-
-    transform.center_rotation *= glam::Quat::from_rotation_z(ammo.center_rotation_speed * time.as_secs_f32());
-    let movement_direction = transform.rotation * glam::Vec3::Y;
-    let movement_distance = ammo.movement_speed * time.as_secs_f32();
-    let translation_delta = movement_direction * movement_distance;
-    transform.translation += translation_delta;
-
-    let mat4 = transform.mat4_center() * glam::Mat4::from_translation(glam::vec3(8.0 / -2.0, 8.0 / -2.0, 1.0));
-
-    strokes
-      .tessellate_rectangle(
-        &Box2D::from_size(Size::new(8.0, 8.0)),
-        &StrokeOptions::default(),
-        &mut BuffersBuilder::new(
-          &mut quads.vertex_buffer,
-          WithTransformColor {
-            transform: mat4,
-            color_rgba: ColorGl::from(RGB_COLOR_AMMO_PICKUP),
-          },
-        ),
-      )
-        .unwrap();
   }
 }
 
-pub fn boost_pickup_spawn_system(mut commands: Commands, timer: Res<EntitySpawnTimer>, mut rng: ResMut<Randoms>) {
-  if timer.boost_pickup.finished {
+pub fn boost_pickup_spawn_system(
+  mut commands: Commands,
+  timer: Res<EntitySpawnTimer>,
+  mut rng: ResMut<Randoms>,
+  bounds: Res<WorldBounds>,
+) {
+  if timer.boost_pickup.just_finished() {
     let movement_direction = if rng.gen_bool(1.0 / 2.0) { -1.0 } else { 1.0 };
     let x = if movement_direction > 0.0 {
-      -12.0
+      bounds.min.x - 12.0
     } else {
-      SCREEN_WIDTH as f32 + 12.0
+      bounds.max.x + 12.0
     };
-    let y = rng.gen_range(12.0..SCREEN_HEIGHT as f32 - 12.0);
+    let y = rng.gen_range(bounds.min.y + 12.0..bounds.max.y - 12.0);
     let movement_speed = rng.gen_range(20.0..40.0);
 
     commands
         .spawn_empty()
-        .insert(BoostPickup {
-          movement_direction,
-          movement_speed,
-          center_rotation_speed: rng.gen_range(-2.0 * std::f32::consts::PI..2.0 * std::f32::consts::PI),
-          visible: true,
-          timer: Timer::from_seconds(0.55, true),
-        })
+        .insert(ScopeTag(GameScope::Run))
+        .insert(BoostPickup::default())
+        .insert(Velocity::Linear(WorldVec::new(movement_direction, 0.0) * movement_speed))
+        .insert(AngularVelocity(rng.gen_range(-2.0 * std::f32::consts::PI..2.0 * std::f32::consts::PI)))
         .insert(Transform {
-          translation: glam::vec3(x, y, Z_INDEX_BOOST_PICKUP),
+          position: WorldPos::new(x, y),
+          z: Z_INDEX_BOOST_PICKUP,
           ..Default::default()
+        })
+        .insert(Collider {
+          radius: BOOST_PICKUP_RADIUS,
         });
   }
 }
 
 pub fn boost_pickup_system(
   mut commands: Commands,
-  player_query: Query<&Transform, With<Player>>,
   mut set: ParamSet<(
-    Query<(&BoostPickup, &mut Transform, Entity), (Without<Player>, Without<Interpolation>)>,
-    Query<(&mut BoostPickup, &Transform, &mut Interpolation, Entity), Without<Player>>,
+    Query<(&Transform, Entity), (With<BoostPickup>, Without<Interpolation>)>,
+    Query<(&mut BoostPickup, &Transform, &mut Interpolation, Entity), (Without<Player>, Without<Despawning>)>,
   )>,
-  mut quads: ResMut<QuadGeometry>,
-  mut strokes: ResMut<Strokes>,
-  mut fills: ResMut<Fills>,
   time: Res<Time>,
+  bounds: Res<WorldBounds>,
+  mut pity: ResMut<PickupPity>,
+  mut events: ParamSet<(EventReader<GameEvents>, EventWriter<GameEvents>)>,
+  mut pickups: EventWriter<PickupEvent>,
+  mut notices: EventWriter<NotificationEvent>,
 ) {
+  let contacts = player_contacts(&mut events.p0());
+  let mut event_writer = events.p1();
   for (mut boost, transform, mut interpolation, entity) in set.p1().iter_mut() {
-    boost.timer.tick(**time);
-    let time = time.as_secs_f32();
-    let (values, done) = interpolation.eval(time, ease_in_out_cubic);
-    let color = if boost.timer.elapsed >= Duration::from_secs_f32(0.15) {
-      RGB_COLOR_BOOST
-    } else {
-      RGB_COLOR_PLAYER
-    };
+    if bounds.is_outside(transform.position, 12.0 * 1.5) {
+      commands.entity(entity).insert(Despawning::now());
+      continue;
+    }
 
-    if boost.timer.finished {
-      commands.entity(entity).despawn();
+    boost.collected_timer.tick(**time);
+    interpolation.eval(time.as_secs_f32(), Easing::InOutCubic);
+
+    if boost.collected_timer.just_finished() {
+      commands.entity(entity).insert(Despawning::bundle(
+        ExitAnim::Shatter(8),
+        transform.mat4_center(),
+        glam::vec2(12.0, 12.0),
+        ColorGl::from(RGB_COLOR_BOOST),
+      ));
+      continue;
+    }
+
+    if boost.is_blinking() {
+      boost.blink_timer.tick(**time);
+      if boost.blink_timer.times_finished() % 2 == 1 {
+        boost.visible = !boost.visible;
+      }
+    }
+  }
+
+  for (transform, entity) in set.p0().iter() {
+    if bounds.is_outside(transform.position, 12.0 * 1.5) {
+      commands.entity(entity).insert(Despawning::now());
       continue;
     }
 
-    if boost.timer.elapsed >= Duration::from_secs_f32(0.15) && boost.timer.elapsed >= boost.timer.checkpoint
-    {
-      boost.timer.add_checkpoint(Duration::from_secs_f32(0.05));
-      boost.visible = !boost.visible;
+    if contacts.contains_key(&entity) {
+      commands
+          .entity(entity)
+          .remove::<Collider>()
+          .insert(
+            Interpolation::new(vec![(1.0, 2.0)], 0.3, InterpolationMode::Once).with_delay(BOOST_PICKUP_BLINK_DELAY),
+          );
+      pity.since_boost = 0.0;
+      pickups.send(PickupEvent(Pickup::Boost));
+      event_writer.send(GameEvents::Flash {
+        color: ColorGl::from(RGB_COLOR_FLASH_BOOST),
+        duration: FLASH_DURATION_BOOST,
+      });
+      notices.send(NotificationEvent {
+        notice: Notice::Boost,
+        position: transform.position,
+      });
     }
+  }
+}
 
+/// A collected boost pickup pulses in the player color, then blinks in the boost color until it shatters.
+pub fn draw_collected_boost_pickups_system(
+  query: Query<(&BoostPickup, &Transform, Option<&PreviousTransform>, &Interpolation), Without<Despawning>>,
+  mut quads: ResMut<QuadGeometry>,
+  mut fills: ResMut<Fills>,
+  mut strokes: ResMut<Strokes>,
+  alpha: Res<RenderAlpha>,
+) {
+  for (boost, transform, previous, interpolation) in query.iter() {
     if !boost.visible {
       continue;
     }
+    let transform = interpolated(transform, previous, *alpha);
+    let color_rgba = ColorGl::from(if boost.is_blinking() { RGB_COLOR_BOOST } else { RGB_COLOR_PLAYER });
 
     let size = 12.0;
     let mat4 = transform.mat4_center() * glam::Mat4::from_translation(glam::vec3(size / -2.0, size / -2.0, 1.0));
     fills
-        .tessellate_rectangle(
-          &Box2D::from_size(Size::new(size, size)),
-          &FillOptions::default(),
-          &mut BuffersBuilder::new(
-            &mut quads.vertex_buffer,
-            WithTransformColor {
-              transform: mat4,
-              color_rgba: ColorGl::from(color),
-            },
-          ),
-        )
-        .unwrap();
+      .tessellate_rectangle(
+        &Box2D::from_size(Size::new(size, size)),
+        &FillOptions::default(),
+        &mut BuffersBuilder::new(&mut quads.vertex_buffer, WithTransformColor { transform: mat4, color_rgba }),
+      )
+      .ok();
 
-    let size = if done { 12.0 * 1.5 * 2.0 } else { 12.0 * 1.5 * values[0] };
+    let &[scale] = interpolation.values(Easing::InOutCubic).as_slice() else {
+      continue;
+    };
+    let size = 12.0 * 1.5 * scale;
     let mat4 = transform.mat4_center() * glam::Mat4::from_translation(glam::vec3(size / -2.0, size / -2.0, 1.0));
     strokes
-        .tessellate_rectangle(
-          &Box2D::from_size(Size::new(size, size)),
-          &StrokeOptions::default(),
-          &mut BuffersBuilder::new(
-            &mut quads.vertex_buffer,
-            WithTransformColor {
-              transform: mat4,
-              color_rgba: ColorGl::from(color),
-            },
-          ),
-        )
-        .unwrap();
+      .tessellate_rectangle(
+        &Box2D::from_size(Size::new(size, size)),
+        &StrokeOptions::default(),
+        &mut BuffersBuilder::new(&mut quads.vertex_buffer, WithTransformColor { transform: mat4, color_rgba }),
+      )
+      .ok();
   }
+}
 
-  let time = time.as_secs_f32();
-
-  for (boost, mut transform, entity) in set.p0().iter_mut() {
-    if screen_ouf_of_bounds_test(transform.translation.xy(), Some(12.0 * 1.5)) {
-      commands.entity(entity).despawn();
-      continue;
-    }
-
-    if let Ok(player) = player_query.get_single() {
-      let distance = (transform.translation - player.translation).length();
-      if distance < 12.0 * 0.5 + 12.0 {
-        commands
-            .entity(entity)
-            .insert(Interpolation::new(vec![(1.0, 2.0)], 0.3, false));
-
-        commands
-            .spawn_empty()
-            .insert(Text {
-              text: String::from("+Boost"),
-              timer: Timer::from_seconds(1.0, true),
-            })
-            .insert(transform.clone());
-        continue;
-      }
-    }
-
-    transform.center_rotation *= glam::Quat::from_rotation_z(boost.center_rotation_speed * time);
-    let movement_direction = glam::Vec3::X * boost.movement_direction;
-    let movement_distance = boost.movement_speed * time;
-    let translation_delta = movement_direction * movement_distance;
-    transform.translation += translation_delta;
-
-    let size = 12.0 * 0.5;
-    let mat4 = transform.mat4_center() * glam::Mat4::from_translation(glam::vec3(size / -2.0, size / -2.0, 1.0));
-    fills
-        .tessellate_rectangle(
-          &Box2D::from_size(Size::new(size, size)),
-          &FillOptions::default(),
-          &mut BuffersBuilder::new(
-            &mut quads.vertex_buffer,
-            WithTransformColor {
-              transform: mat4,
-              color_rgba: ColorGl::from(RGB_COLOR_BOOST),
-            },
-          ),
-        )
-        .unwrap();
+pub fn draw_boost_pickups_system(
+  query: Query<
+    (&Transform, Option<&PreviousTransform>),
+    (With<BoostPickup>, Without<Interpolation>, Without<Despawning>),
+  >,
+  mut quads: ResMut<QuadGeometry>,
+  mut strokes: ResMut<Strokes>,
+  glyphs: Res<GlyphMeshes>,
+  alpha: Res<RenderAlpha>,
+) {
+  for (transform, previous) in query.iter() {
+    let transform = interpolated(transform, previous, *alpha);
+    glyphs.draw(
+      GlyphId::Boost,
+      true,
+      &mut quads.vertex_buffer,
+      WithTransformColor {
+        transform: transform.mat4_center() * glam::Mat4::from_translation(glam::Vec3::Z),
+        color_rgba: ColorGl::from(RGB_COLOR_BOOST),
+      },
+    );
 
     let size = 12.0 * 1.5;
     let mat4 = transform.mat4_center() * glam::Mat4::from_translation(glam::vec3(size / -2.0, size / -2.0, 1.0));
@@ -886,7 +1492,25 @@ pub fn boost_pickup_system(
             },
           ),
         )
-        .unwrap();
+        .ok();
+  }
+}
+
+/// Spawns the floating text of every [`NotificationEvent`] of the tick, once the systems sending them
+/// ran.
+pub fn notification_system(mut commands: Commands, mut event_reader: EventReader<NotificationEvent>) {
+  for NotificationEvent { notice, position } in event_reader.iter().copied() {
+    commands.spawn((
+      ScopeTag(GameScope::Persistent),
+      Text {
+        text: notice.text(),
+        timer: Timer::from_seconds(notice.duration(), true),
+      },
+      Transform {
+        position,
+        ..Default::default()
+      },
+    ));
   }
 }
 
@@ -897,19 +1521,1949 @@ pub fn draw_text_system(
   time: Res<Time>,
 ) {
   for (e, mut text, transform) in query.iter_mut() {
-    text.timer.tick(**time);
+    text.timer.tick(time.domain(TimeDomain::Ui));
 
     if text.timer.finished {
-      commands.entity(e).despawn();
+      commands.entity(e).insert(Despawning::now());
       continue;
     }
 
     texts.build_text(
       text.text.as_str(),
-      transform.translation.x * 2.0,
-      transform.translation.y * 2.0 - 10.0,
+      transform.position.to_render().x * 2.0,
+      transform.position.to_render().y * 2.0 - 10.0,
       1.0,
       ColorGl::from(RGB_COLOR_BOOST),
+      TextStyle::Sdf,
     )
   }
 }
+
+/// Hidden while no player is alive. Enemies and hostile projectiles are red, the pickups in their own
+/// colors, see [`cluster_dots`] for how a busy arena is kept to a bounded number of dots.
+pub fn minimap_system(
+  minimap: Res<Minimap>,
+  player_query: Query<(&PlayerAppearance, &Transform), With<Player>>,
+  hostile_query: Query<&Transform, (Or<(With<Enemy>, With<Hostile>)>, Without<Despawning>)>,
+  ammo_query: Query<&Transform, (With<AmmoPickup>, Without<Despawning>)>,
+  boost_query: Query<&Transform, (With<BoostPickup>, Without<Despawning>)>,
+  attack_query: Query<&Transform, (With<AttackPickup>, Without<Despawning>)>,
+  mut screen: ResMut<ScreenGeometry>,
+  mut strokes: ResMut<Strokes>,
+  mut fills: ResMut<Fills>,
+  bounds: Res<WorldBounds>,
+) {
+  if !minimap.visible || player_query.is_empty() {
+    return;
+  }
+
+  let origin = minimap_origin();
+  strokes
+    .tessellate_rectangle(
+      &Box2D::from_size(Size::new(MINIMAP_WIDTH, MINIMAP_HEIGHT)),
+      &StrokeOptions::default(),
+      &mut BuffersBuilder::new(
+        &mut screen.vertex_buffer,
+        WithTransformColor {
+          transform: glam::Mat4::from_translation(origin.extend(Z_INDEX_MINIMAP)),
+          color_rgba: ColorGl::from(RGB_COLOR_PLAYER),
+        },
+      ),
+    )
+    .ok();
+
+  // the threats first, they keep their share of the dots however many pickups there are
+  let bounds = &*bounds;
+  let dot = |group: usize| {
+    move |transform: &Transform| (group, playfield_to_minimap(bounds, transform.position.to_render()))
+  };
+  let positions = hostile_query
+    .iter()
+    .map(dot(0))
+    .chain(ammo_query.iter().map(dot(1)))
+    .chain(boost_query.iter().map(dot(2)))
+    .chain(attack_query.iter().map(dot(3)));
+  let colors = [
+    ColorGl::from(RGB_COLOR_ENEMY),
+    ColorGl::from(RGB_COLOR_AMMO_PICKUP),
+    ColorGl::from(RGB_COLOR_BOOST),
+    ColorGl::from(RGB_COLOR_ATTACK_PICKUP),
+  ];
+
+  for dot in cluster_dots(positions, &colors) {
+    let translation = (dot.position - glam::Vec2::splat(dot.size / 2.0)).extend(Z_INDEX_MINIMAP);
+    fills
+      .tessellate_rectangle(
+        &Box2D::from_size(Size::new(dot.size, dot.size)),
+        &FillOptions::default(),
+        &mut BuffersBuilder::new(
+          &mut screen.vertex_buffer,
+          WithTransformColor {
+            transform: glam::Mat4::from_translation(translation),
+            color_rgba: dot.color,
+          },
+        ),
+      )
+      .ok();
+  }
+
+  for (appearance, player) in player_query.iter() {
+    let position = playfield_to_minimap(bounds, player.position.to_render());
+    fills
+      .tessellate_rectangle(
+        &Box2D::from_size(Size::new(2.0, 2.0)),
+        &FillOptions::default(),
+        &mut BuffersBuilder::new(
+          &mut screen.vertex_buffer,
+          WithTransformColor {
+            transform: glam::Mat4::from_translation((position - glam::Vec2::ONE).extend(Z_INDEX_MINIMAP + 1.0)),
+            color_rgba: appearance.color,
+          },
+        ),
+      )
+      .ok();
+
+    let mut builder = Path::builder();
+    builder.begin(point(0.0, 0.0));
+    builder.line_to(point(0.0, 3.0));
+    builder.end(false);
+
+    let mut options = StrokeOptions::default();
+    options.line_width = 0.5;
+    strokes
+      .tessellate_path(
+        &builder.build(),
+        &options,
+        &mut BuffersBuilder::new(
+          &mut screen.vertex_buffer,
+          WithTransformColor {
+            transform: glam::Mat4::from_rotation_translation(
+              player.rotation.to_render(),
+              position.extend(Z_INDEX_MINIMAP + 1.0),
+            ),
+            color_rgba: appearance.color,
+          },
+        ),
+      )
+      .ok();
+  }
+}
+
+pub fn enemy_spawn_system(
+  mut commands: Commands,
+  timer: Res<EntitySpawnTimer>,
+  mut rng: ResMut<Randoms>,
+  bounds: Res<WorldBounds>,
+) {
+  if timer.enemy.just_finished() {
+    spawn_rock(&mut commands, &mut rng, &bounds);
+  }
+}
+
+/// A rock drifting in from the left or the right edge, the practice panel spawns them too.
+pub fn spawn_rock(commands: &mut Commands, rng: &mut Randoms, bounds: &WorldBounds) {
+  let movement_direction = if rng.gen_bool(1.0 / 2.0) { -1.0 } else { 1.0 };
+  let offset = ROCK_RADIUS * 1.5;
+  let x = if movement_direction > 0.0 {
+    bounds.min.x - offset
+  } else {
+    bounds.max.x + offset
+  };
+  let y = rng.gen_range(bounds.min.y + offset..bounds.max.y - offset);
+  let movement_speed = rng.gen_range(20.0..40.0);
+
+  commands.spawn((
+    ScopeTag(GameScope::Run),
+    Enemy { kind: EnemyKind::Rock },
+    Velocity::Linear(WorldVec::new(movement_direction, 0.0) * movement_speed),
+    AngularVelocity(rng.gen_range(-std::f32::consts::PI..std::f32::consts::PI)),
+    Transform {
+      position: WorldPos::new(x, y),
+      z: Z_INDEX_ENEMY,
+      ..Default::default()
+    },
+    Collider { radius: ROCK_RADIUS },
+    Faction::Enemies,
+    Health(ROCK_HEALTH),
+  ));
+}
+
+pub fn attack_pickup_spawn_system(
+  mut commands: Commands,
+  timer: Res<EntitySpawnTimer>,
+  mut rng: ResMut<Randoms>,
+  bounds: Res<WorldBounds>,
+) {
+  if timer.attack_pickup.just_finished() {
+    let movement_direction = if rng.gen_bool(1.0 / 2.0) { -1.0 } else { 1.0 };
+    let offset = ATTACK_PICKUP_RADIUS * 1.5;
+    let x = if movement_direction > 0.0 {
+      bounds.min.x - offset
+    } else {
+      bounds.max.x + offset
+    };
+    let y = rng.gen_range(bounds.min.y + offset..bounds.max.y - offset);
+    let attack = Attack::PICKUPS[rng.gen_range(0..Attack::PICKUPS.len())];
+    let movement_speed = rng.gen_range(20.0..40.0);
+
+    commands.spawn((
+      ScopeTag(GameScope::Run),
+      AttackPickup { attack },
+      Velocity::Linear(WorldVec::new(movement_direction, 0.0) * movement_speed),
+      AngularVelocity(rng.gen_range(-std::f32::consts::PI..std::f32::consts::PI)),
+      Transform {
+        position: WorldPos::new(x, y),
+        z: Z_INDEX_ATTACK_PICKUP,
+        ..Default::default()
+      },
+      Collider {
+        radius: ATTACK_PICKUP_RADIUS,
+      },
+    ));
+  }
+}
+
+/// Touching an attack pickup swaps the attack of the player touching it for its own.
+/// [`movement_system`] moves them.
+pub fn attack_pickup_system(
+  mut commands: Commands,
+  mut player_query: Query<&mut Attack, With<Player>>,
+  query: Query<(&AttackPickup, &Transform, Entity), (Without<Player>, Without<Despawning>)>,
+  bounds: Res<WorldBounds>,
+  mut event_reader: EventReader<GameEvents>,
+  mut pickups: EventWriter<PickupEvent>,
+  mut notices: EventWriter<NotificationEvent>,
+) {
+  let contacts = player_contacts(&mut event_reader);
+  for (pickup, transform, entity) in query.iter() {
+    if bounds.is_outside(transform.position, ATTACK_PICKUP_RADIUS * 2.0) {
+      commands.entity(entity).insert(Despawning::now());
+      continue;
+    }
+
+    if let Some(player) = contacts.get(&entity) {
+      if let Ok(mut attack) = player_query.get_mut(*player) {
+        *attack = pickup.attack;
+      }
+      commands.entity(entity).insert(Despawning::bundle(
+        ExitAnim::Shatter(8),
+        transform.mat4_center(),
+        glam::Vec2::splat(ATTACK_PICKUP_RADIUS * 2.0),
+        ColorGl::from(RGB_COLOR_ATTACK_PICKUP),
+      ));
+      notices.send(NotificationEvent {
+        notice: Notice::Attack(pickup.attack),
+        position: transform.position,
+      });
+      pickups.send(PickupEvent(Pickup::Attack));
+    }
+  }
+}
+
+/// A projectile inside the playfield touching an enemy kills both. [`movement_system`] moves them. Runs
+/// before [`projectile_system`], which would resolve a projectile that left the playfield a second time.
+pub fn enemy_system(
+  mut commands: Commands,
+  query: Query<(&Collider, &Transform, Entity), (With<Enemy>, Without<Despawning>)>,
+  projectile_query: Query<(&DeathAction, &Transform), (With<Projectile>, Without<Enemy>, Without<Despawning>)>,
+  grid: Res<SpatialGrid>,
+  mut event_writer: EventWriter<GameEvents>,
+  mut spawns: EventWriter<SpawnRequest>,
+  mut rng: ResMut<Randoms>,
+  mut distortions: ResMut<Distortions>,
+  mut pool: ResMut<ParticlePool>,
+  bounds: Res<WorldBounds>,
+) {
+  let mut spent = Vec::new();
+  for (collider, transform, entity) in query.iter() {
+    if bounds.is_outside(transform.position, collider.radius * 2.0) {
+      commands.entity(entity).insert(Despawning::now());
+      continue;
+    }
+
+    // the grid is from the end of the last tick, the projectiles haven't moved since
+    let reach = collider.radius + PROJECTILE_RADIUS;
+    let hit = grid.query_circle(transform.position, collider.radius).find_map(|projectile_entity| {
+      let (death_action, projectile) = projectile_query.get(projectile_entity).ok()?;
+      let hit = !spent.contains(&projectile_entity)
+        && !bounds.is_outside(projectile.position, 0.0)
+        && projectile.position.distance_squared(transform.position) < reach * reach;
+      hit.then_some((death_action, projectile, projectile_entity))
+    });
+    if let Some((death_action, projectile, projectile_entity)) = hit {
+      spent.push(projectile_entity);
+      resolve_projectile_death(
+        &mut commands,
+        &mut pool,
+        &mut event_writer,
+        projectile_entity,
+        *death_action,
+        projectile.position,
+        &bounds,
+      );
+
+      break_enemy(&mut commands, &mut pool, &mut rng, &mut distortions, entity, transform);
+      event_writer.send(GameEvents::EnemyDeath(transform.position, KilledBy::Projectile));
+      event_writer.send(GameEvents::SlowMotion(SlowMotion::KILL));
+      spawns.send(SpawnRequest::BurnZone(transform.position));
+    }
+  }
+}
+
+/// Despawns an enemy and breaks it apart where it was, the caller sends the [`GameEvents::EnemyDeath`].
+fn break_enemy(
+  commands: &mut Commands,
+  pool: &mut ParticlePool,
+  rng: &mut Randoms,
+  distortions: &mut Distortions,
+  entity: Entity,
+  transform: &Transform,
+) {
+  commands.entity(entity).insert(Despawning::now());
+  distortions.add(transform.position.to_render(), 24.0, 1.0);
+  spawn_explosion_lines(commands, pool, rng, transform, ColorGl::from(RGB_COLOR_ENEMY));
+}
+
+/// Sets off a bomb for every player that pressed its bomb key while there is a charge left. A
+/// [`Shockwave`] grows from the player and [`shockwave_system`] clears what it passes, the player can't
+/// be hit for a moment.
+pub fn bomb_system(
+  mut commands: Commands,
+  mut query: Query<(&PlayerId, &Transform, Option<&mut Invulnerable>, Entity), (With<Player>, Without<Despawning>)>,
+  mut bomb: ResMut<Bomb>,
+  score: Res<Score>,
+  balance: Res<GameBalance>,
+  input: Res<InputState>,
+  // whether each player held the bomb key the tick before, holding it down sets off one bomb
+  mut held: Local<Vec<bool>>,
+  mut event_writer: EventWriter<GameEvents>,
+) {
+  bomb.accrue(score.0);
+  for (id, transform, invulnerable, entity) in query.iter_mut() {
+    let slot = id.0 as usize;
+    if held.len() <= slot {
+      held.resize(slot + 1, false);
+    }
+    let pressed = input.player(*id).bomb;
+    let was_held = std::mem::replace(&mut held[slot], pressed);
+    if !pressed || was_held || !bomb.spend() {
+      continue;
+    }
+
+    commands.spawn((
+      ScopeTag(GameScope::Run),
+      Shockwave::default(),
+      Transform {
+        position: transform.position,
+        ..Default::default()
+      },
+      Interpolation::new(vec![(0.0, balance.bomb_radius)], balance.bomb_duration, InterpolationMode::Once),
+    ));
+    let timer = Timer::from_seconds(BOMB_INVULNERABLE_DURATION, false);
+    match invulnerable {
+      Some(mut invulnerable) => {
+        // a fresh spawn's longer invulnerability stays
+        if invulnerable.timer.duration - invulnerable.timer.elapsed < timer.duration {
+          invulnerable.timer = timer;
+        }
+      }
+      None => {
+        commands.entity(entity).insert(Invulnerable { timer });
+      }
+    }
+    event_writer.send(GameEvents::BombDetonated);
+    event_writer.send(GameEvents::Shake(ShakeRequest::BOMB));
+  }
+}
+
+/// Grows every [`Shockwave`] and clears what its ring passed over during the tick. Hostile projectiles
+/// die through [`resolve_projectile_death`], enemies break apart unless they are
+/// [`EnemyKind::bomb_proof`], those are pushed away instead. The ring sweeps the band between its
+/// radius of the tick before and the current one, see [`ring_passed_over`].
+pub fn shockwave_system(
+  mut commands: Commands,
+  mut query: Query<(&mut Shockwave, &mut Interpolation, &Transform, Entity)>,
+  mut enemy_query: Query<
+    (&Enemy, &Transform, Option<&PreviousTransform>, &Collider, &mut Velocity, Entity),
+    Without<Despawning>,
+  >,
+  projectile_query: Query<
+    (&DeathAction, &Transform, Option<&PreviousTransform>, Entity),
+    (With<Projectile>, With<Hostile>, Without<Despawning>),
+  >,
+  mut event_writer: EventWriter<GameEvents>,
+  mut spawns: EventWriter<SpawnRequest>,
+  mut rng: ResMut<Randoms>,
+  mut distortions: ResMut<Distortions>,
+  mut pool: ResMut<ParticlePool>,
+  bounds: Res<WorldBounds>,
+  balance: Res<GameBalance>,
+  time: Res<Time>,
+) {
+  let mut spent = Vec::new();
+  for (mut shockwave, mut interpolation, transform, entity) in query.iter_mut() {
+    let (values, finished) = interpolation.eval(time.as_secs_f32(), Easing::Linear);
+    let &[radius] = values.as_slice() else {
+      continue;
+    };
+    let center = transform.position;
+    let passed_over = |from: Option<&PreviousTransform>, to: &Transform, reach: f32| {
+      let from = from.map_or(to.position, |previous| previous.0.position);
+      ring_passed_over(center, shockwave.radius, radius, from, to.position, reach)
+    };
+
+    for (death_action, projectile, previous, projectile_entity) in projectile_query.iter() {
+      if spent.contains(&projectile_entity) || !passed_over(previous, projectile, PROJECTILE_RADIUS) {
+        continue;
+      }
+      spent.push(projectile_entity);
+      resolve_projectile_death(
+        &mut commands,
+        &mut pool,
+        &mut event_writer,
+        projectile_entity,
+        *death_action,
+        projectile.position,
+        &bounds,
+      );
+    }
+
+    for (enemy, enemy_transform, previous, collider, mut velocity, enemy_entity) in enemy_query.iter_mut() {
+      if spent.contains(&enemy_entity) || !passed_over(previous, enemy_transform, collider.radius) {
+        continue;
+      }
+      if enemy.kind.bomb_proof() {
+        let away = enemy_transform.position - center;
+        let distance = away.length();
+        if let (Velocity::Linear(linear), true) = (*velocity, distance > f32::EPSILON) {
+          *velocity = Velocity::Linear(linear + away * (balance.bomb_impulse / distance));
+        }
+        continue;
+      }
+      spent.push(enemy_entity);
+      break_enemy(&mut commands, &mut pool, &mut rng, &mut distortions, enemy_entity, enemy_transform);
+      event_writer.send(GameEvents::EnemyDeath(enemy_transform.position, KilledBy::Bomb));
+      spawns.send(SpawnRequest::BurnZone(enemy_transform.position));
+    }
+
+    shockwave.radius = radius;
+    if finished {
+      commands.entity(entity).insert(Despawning::now());
+    }
+  }
+}
+
+/// Leaves the [`BurnZone`]s asked for by [`SpawnRequest::BurnZone`]. Rocks worn down by a zone ask for
+/// none, one zone doesn't set off the next.
+pub fn burn_zone_spawn_system(
+  mut commands: Commands,
+  mut event_reader: EventReader<SpawnRequest>,
+  balance: Res<GameBalance>,
+) {
+  for SpawnRequest::BurnZone(position) in event_reader.iter() {
+    commands.spawn((
+      ScopeTag(GameScope::Run),
+      BurnZone {
+        radius: balance.burn_zone_radius,
+        tick: Timer::from_seconds(BURN_TICK, true),
+        faction: Faction::Players,
+      },
+      Lifetime(Timer::from_seconds(balance.burn_zone_duration, false)),
+      Transform {
+        position: *position,
+        z: Z_INDEX_BURN_ZONE,
+        ..Default::default()
+      },
+    ));
+  }
+}
+
+/// Burns what a [`BurnZone`] overlaps every time its tick finishes, not continuously, so the damage
+/// lands on a steady beat. What a zone burnt is [`Burnt`] for a tick and skipped by every zone. The
+/// [`SpatialGrid`] has the candidates.
+pub fn burn_zone_system(
+  mut commands: Commands,
+  mut query: Query<(&mut BurnZone, &mut Lifetime, &Transform, Entity), Without<Despawning>>,
+  mut victim_query: Query<
+    (&Faction, &Transform, &Collider, Option<&mut Burnt>),
+    (With<Health>, Without<BurnZone>, Without<Despawning>),
+  >,
+  grid: Res<SpatialGrid>,
+  balance: Res<GameBalance>,
+  time: Res<Time>,
+  mut event_writer: EventWriter<DamageEvent>,
+) {
+  for (.., burnt) in victim_query.iter_mut() {
+    if let Some(mut burnt) = burnt {
+      burnt.0.tick(**time);
+    }
+  }
+
+  let mut burnt_now = Vec::new();
+  for (mut zone, mut lifetime, transform, entity) in query.iter_mut() {
+    zone.tick.tick(**time);
+    lifetime.0.tick(**time);
+    if lifetime.0.finished {
+      commands.entity(entity).insert(Despawning::now());
+    }
+    if !zone.tick.just_finished() {
+      continue;
+    }
+
+    for victim in grid.query_circle(transform.position, zone.radius) {
+      let Ok((faction, victim_transform, collider, burnt)) = victim_query.get(victim) else {
+        continue;
+      };
+      let reach = zone.radius + collider.radius;
+      let inside = transform.position.distance_squared(victim_transform.position) < reach * reach;
+      let burning = burnt_now.contains(&victim) || burnt.is_some_and(|burnt| !burnt.0.finished);
+      if *faction == zone.faction || !inside || burning {
+        continue;
+      }
+      burnt_now.push(victim);
+      // counted from the tick's boundary like the zones, not from the end of the frame it landed in
+      let mut timer = Timer::from_seconds(BURN_TICK, false);
+      timer.elapsed = zone.tick.elapsed;
+      commands.entity(victim).insert(Burnt(timer));
+      event_writer.send(DamageEvent {
+        target: victim,
+        amount: balance.burn_damage,
+      });
+    }
+  }
+}
+
+/// Takes the [`DamageEvent`]s of the tick off the [`Health`] of their target. An enemy out of health
+/// breaks apart like a shot one, anything else just goes.
+pub fn damage_system(
+  mut commands: Commands,
+  mut event_reader: EventReader<DamageEvent>,
+  mut event_writer: EventWriter<GameEvents>,
+  mut query: Query<(&mut Health, &Transform, Option<&Enemy>), Without<Despawning>>,
+  mut rng: ResMut<Randoms>,
+  mut distortions: ResMut<Distortions>,
+  mut pool: ResMut<ParticlePool>,
+) {
+  for DamageEvent { target, amount } in event_reader.iter().copied() {
+    let Ok((mut health, transform, enemy)) = query.get_mut(target) else {
+      continue;
+    };
+    if health.0 == 0 {
+      continue;
+    }
+    health.0 = health.0.saturating_sub(amount);
+    if health.0 > 0 {
+      continue;
+    }
+    if enemy.is_none() {
+      commands.entity(target).insert(Despawning::now());
+      continue;
+    }
+    break_enemy(&mut commands, &mut pool, &mut rng, &mut distortions, target, transform);
+    event_writer.send(GameEvents::EnemyDeath(transform.position, KilledBy::Burn));
+  }
+}
+
+/// Boost, attack and ammo of every player under the minimap, or "DEAD" once all players are gone. In
+/// co-op each player's lines start with its number.
+pub fn hud_system(
+  query: Query<(&PlayerId, &Boost, &Attack, &Ammo), With<Player>>,
+  bindings: Res<KeyBindings>,
+  device: Res<InputDevice>,
+  bomb: Res<Bomb>,
+  fills: Res<Fills>,
+  strokes: Res<Strokes>,
+  mut texts: ResMut<TextBuffers>,
+) {
+  // text is laid out at twice the screen resolution
+  let origin = minimap_origin() * 2.0;
+  let line = |i: usize| origin.y - (i + 1) as f32 * HUD_LINE_HEIGHT;
+
+  if query.is_empty() {
+    let color = ColorGl::from(RGB_COLOR_HUD_DEAD);
+    texts.build_text("DEAD", origin.x, line(0), HUD_TEXT_SCALE, color, TextStyle::Bitmap);
+    return;
+  }
+
+  let mut lines = Vec::new();
+  for id in (0..bindings.players()).map(PlayerId) {
+    let prefix = if bindings.players() > 1 { format!("P{} ", id.0 + 1) } else { String::new() };
+    match query.iter().find(|(player, ..)| **player == id) {
+      Some((_, boost, attack, ammo)) => lines.extend([
+        (format!("{}BOOST {:.0}/{:.0}", prefix, boost.boost.max(0.0), boost.max_boost), RGB_COLOR_HUD_BOOST),
+        (format!("{}ATTACK {}", prefix, attack.name().to_uppercase()), RGB_COLOR_ATTACK_PICKUP),
+        (format!("{}AMMO {}/{}", prefix, ammo.current, ammo.max), RGB_COLOR_HUD_AMMO),
+      ]),
+      None => lines.push((format!("{}DEAD", prefix), RGB_COLOR_HUD_DEAD)),
+    }
+  }
+  // one pip per charge the players share, the empty slots as dashes, and what sets one off once there is one
+  let mut pips = "*".repeat(bomb.charges as usize) + &"-".repeat((BOMB_MAX_CHARGES - bomb.charges) as usize);
+  if bomb.charges > 0 {
+    pips += &fill_prompts(" [{bomb}]", &bindings, &device);
+  }
+  lines.push((format!("BOMB {}", pips), RGB_COLOR_HUD_BOMB));
+  // only there once something went wrong, see the log for the first failure
+  let dropped = fills.dropped() + strokes.dropped();
+  if dropped > 0 {
+    lines.push((format!("DROPPED {}", dropped), RGB_COLOR_HUD_DEAD));
+  }
+  for (i, (text, color)) in lines.iter().enumerate() {
+    texts.build_text(text, origin.x, line(i), HUD_TEXT_SCALE, ColorGl::from(*color), TextStyle::Bitmap);
+  }
+}
+
+/// Right aligned in the bottom right corner, one line per number and one per event channel.
+pub fn frame_stats_system(
+  stats: Res<FrameStats>,
+  cache: Res<GeometryCache>,
+  events: Res<EventCounts>,
+  mut texts: ResMut<TextBuffers>,
+) {
+  if !stats.visible {
+    return;
+  }
+
+  let [circles, quads, lines] = stats.vertices;
+  let events = CHANNELS.iter().zip(events.last_frame).map(|(name, sent)| format!("{} EVENTS {}", name, sent));
+  let rows = [
+    format!("FPS {:.0}", stats.average_fps()),
+    format!("1% LOW {:.0}", stats.one_percent_low()),
+    format!("STEPS {}", stats.sub_steps),
+    format!("CIRCLES {}", circles),
+    format!("QUADS {}", quads),
+    format!("LINES {}", lines),
+    if cache.enabled {
+      format!("CIRCLE CACHE {}", cache.circles())
+    } else {
+      "CIRCLE CACHE OFF".to_string()
+    },
+  ];
+  let rows = rows.into_iter().chain(events).collect::<Vec<_>>();
+  let color = ColorGl::from(RGB_COLOR_FRAME_STATS);
+  for (i, row) in rows.iter().rev().enumerate() {
+    let x = SCREEN_RENDER_WIDTH as f32 - 10.0 - texts.width(row, HUD_TEXT_SCALE, TextStyle::Bitmap);
+    let y = 10.0 + i as f32 * HUD_LINE_HEIGHT;
+    texts.build_text(row, x, y, HUD_TEXT_SCALE, color, TextStyle::Bitmap);
+  }
+}
+
+/// "REPLAY" with the time played and the total in the top right corner, while `--replay` plays.
+pub fn replay_system(replay: Option<Res<Replay>>, mut texts: ResMut<TextBuffers>) {
+  let Some(replay) = replay.filter(|replay| replay.is_playing()) else {
+    return;
+  };
+
+  let (elapsed, total) = replay.progress();
+  let mut text = String::from("REPLAY ");
+  write_time(&mut text, elapsed).unwrap();
+  text.push_str(" / ");
+  write_time(&mut text, total).unwrap();
+  let x = SCREEN_RENDER_WIDTH as f32 - 10.0 - texts.width(&text, HUD_TEXT_SCALE, TextStyle::Bitmap);
+  let y = SCREEN_RENDER_HEIGHT as f32 - 30.0;
+  texts.build_text(&text, x, y, HUD_TEXT_SCALE, ColorGl::from(RGB_COLOR_REPLAY), TextStyle::Bitmap);
+}
+
+/// The outline stays put, the fill shrinks with the boost left and turns red during the cooldown. One
+/// gauge per player, side by side in [`PlayerId`] order.
+pub fn boost_ui_system(
+  query: Query<(&PlayerId, &Boost), With<Player>>,
+  mut screen: ResMut<ScreenGeometry>,
+  mut fills: ResMut<Fills>,
+  mut strokes: ResMut<Strokes>,
+) {
+  for (id, boost) in query.iter() {
+    let origin = glam::vec2(BOOST_BAR_MARGIN + id.0 as f32 * (BOOST_BAR_WIDTH + BOOST_BAR_MARGIN), BOOST_BAR_MARGIN);
+    let transform = glam::Mat4::from_translation(origin.extend(Z_INDEX_BOOST_BAR));
+    strokes
+      .tessellate_rectangle(
+        &Box2D::from_size(Size::new(BOOST_BAR_WIDTH, BOOST_BAR_HEIGHT)),
+        &StrokeOptions::default().with_line_width(0.5),
+        &mut BuffersBuilder::new(
+          &mut screen.vertex_buffer,
+          WithTransformColor {
+            transform,
+            color_rgba: ColorGl::from(RGB_COLOR_BOOST),
+          },
+        ),
+      )
+      .ok();
+
+    let fraction = (boost.boost / boost.max_boost).clamp(0.0, 1.0);
+    let color = if boost.cooldown.is_some() { RGB_COLOR_DEATH } else { RGB_COLOR_BOOST };
+    fills
+      .tessellate_rectangle(
+        &Box2D::new(
+          Point::new(1.0, 1.0),
+          Point::new(1.0 + (BOOST_BAR_WIDTH - 2.0) * fraction, BOOST_BAR_HEIGHT - 1.0),
+        ),
+        &FillOptions::default(),
+        &mut BuffersBuilder::new(
+          &mut screen.vertex_buffer,
+          WithTransformColor {
+            transform,
+            color_rgba: ColorGl::from(color),
+          },
+        ),
+      )
+      .ok();
+  }
+}
+
+pub fn input_display_system(
+  keycodes: Res<KeyCodes>,
+  mut display: ResMut<InputDisplay>,
+  mut screen: ResMut<ScreenGeometry>,
+  mut strokes: ResMut<Strokes>,
+  mut fills: ResMut<Fills>,
+) {
+  display.history.push(input_mask(&keycodes));
+  if !display.visible {
+    return;
+  }
+
+  let color_rgba = ColorGl::from(RGB_COLOR_PLAYER);
+  // above the boost gauge
+  let origin = glam::vec2(INPUT_DISPLAY_MARGIN, BOOST_BAR_MARGIN + BOOST_BAR_HEIGHT + INPUT_DISPLAY_MARGIN);
+  let pitch = INPUT_KEY_SIZE + 1.0;
+  let latest = display.history.latest();
+  for (i, (_, (column, row))) in ACTIONS.iter().enumerate() {
+    let translation = origin + glam::vec2(*column, *row) * pitch;
+    let transform = glam::Mat4::from_translation(translation.extend(Z_INDEX_INPUT_DISPLAY));
+    let key = Box2D::from_size(Size::new(INPUT_KEY_SIZE, INPUT_KEY_SIZE));
+    let mut builder = BuffersBuilder::new(&mut screen.vertex_buffer, WithTransformColor { transform, color_rgba });
+    if latest & 1 << i != 0 {
+      fills.tessellate_rectangle(&key, &FillOptions::default(), &mut builder).ok();
+    } else {
+      strokes
+        .tessellate_rectangle(&key, &StrokeOptions::default().with_line_width(0.5), &mut builder)
+        .ok();
+    }
+  }
+
+  // one pixel per tick and a row per action, the newest tick on the right
+  let runs = display.history.runs();
+  let strip = origin + glam::vec2(4.0 * pitch + 2.0, 0.0);
+  let mut x = (INPUT_HISTORY_TICKS - runs.iter().map(|(_, length)| length).sum::<usize>()) as f32;
+  for (mask, length) in runs {
+    for i in (0..ACTIONS.len()).filter(|i| mask & 1 << i != 0) {
+      let translation = strip + glam::vec2(x, i as f32 * 3.0);
+      let transform = glam::Mat4::from_translation(translation.extend(Z_INDEX_INPUT_DISPLAY));
+      fills
+        .tessellate_rectangle(
+          &Box2D::from_size(Size::new(length as f32, 2.0)),
+          &FillOptions::default(),
+          &mut BuffersBuilder::new(&mut screen.vertex_buffer, WithTransformColor { transform, color_rgba }),
+        )
+        .ok();
+    }
+    x += length as f32;
+  }
+}
+
+/// Scores events and time alive, draws the score top left. `survived` counts seconds towards the
+/// next [`SCORE_SURVIVAL`] and restarts with the next player.
+pub fn score_system(
+  mut event_reader: EventReader<GameEvents>,
+  mut pickups: EventReader<PickupEvent>,
+  mut notices: EventWriter<NotificationEvent>,
+  player_query: Query<(), With<Player>>,
+  mut score: ResMut<Score>,
+  mut survived: Local<f32>,
+  mut texts: ResMut<TextBuffers>,
+  balance: Res<GameBalance>,
+  time: Res<Time>,
+) {
+  for event in event_reader.iter() {
+    let points = match event {
+      GameEvents::EnemyDeath(position, killed_by) => {
+        let points = match killed_by {
+          KilledBy::Projectile => balance.score_enemy,
+          KilledBy::Bomb | KilledBy::Burn => balance.score_enemy_bombed,
+        };
+        notices.send(NotificationEvent {
+          notice: Notice::Points(points),
+          position: *position,
+        });
+        points
+      }
+      GameEvents::PlayerDeath(..)
+      | GameEvents::Shot(_)
+      | GameEvents::BombDetonated
+      | GameEvents::AmmoChanged
+      | GameEvents::WallImpact
+      | GameEvents::PlayerContact { .. }
+      | GameEvents::Flash { .. }
+      | GameEvents::Shake(_)
+      | GameEvents::SlowMotion(_) => 0,
+    };
+    score.0 = score.0.saturating_add(points);
+  }
+  for PickupEvent(pickup) in pickups.iter() {
+    let points = match pickup {
+      Pickup::Ammo => SCORE_AMMO_PICKUP,
+      Pickup::Boost => SCORE_BOOST_PICKUP,
+      Pickup::Attack => SCORE_ATTACK_PICKUP,
+    };
+    score.0 = score.0.saturating_add(points);
+  }
+
+  if player_query.is_empty() {
+    *survived = 0.0;
+  } else {
+    *survived += time.as_secs_f32();
+    while *survived >= SCORE_SURVIVAL_INTERVAL {
+      *survived -= SCORE_SURVIVAL_INTERVAL;
+      score.0 = score.0.saturating_add(SCORE_SURVIVAL);
+    }
+  }
+
+  let mut text = String::from("SCORE ");
+  write_score(&mut text, score.0 as u64).unwrap();
+  let y = SCREEN_RENDER_HEIGHT as f32 - 30.0;
+  texts.build_text(&text, 10.0, y, HUD_TEXT_SCALE, ColorGl::from(RGB_COLOR_PLAYER), TextStyle::Bitmap);
+}
+
+/// Enters the run's [`Score`] into the [`HighScores`] once the last player died and saves them. Worlds
+/// without [`HighScores`], the lockstep audit's and stress runs, keep no table.
+pub fn high_score_system(
+  mut event_reader: EventReader<GameEvents>,
+  high_scores: Option<ResMut<HighScores>>,
+  players: Query<Entity, With<Player>>,
+  score: Res<Score>,
+  run: Res<RunStats>,
+  writer: Option<Res<OutputWriter>>,
+) {
+  let Some(mut high_scores) = high_scores else {
+    return;
+  };
+  // the dead are despawned after the stage, they are still among the players
+  let dead = event_reader
+    .iter()
+    .filter_map(|event| match event {
+      GameEvents::PlayerDeath(player, _) => Some(*player),
+      _ => None,
+    })
+    .collect::<Vec<_>>();
+  // practiced runs don't count
+  if !dead.is_empty() && players.iter().all(|player| dead.contains(&player)) && !run.practiced {
+    high_scores.insert(score.0);
+    high_scores.save(writer.as_deref());
+  }
+}
+
+/// The top [`HIGH_SCORES_SHOWN`] entries in the middle of the screen during the death slow-mo, the
+/// run that just ended highlighted.
+pub fn high_score_table_system(
+  high_scores: Option<Res<HighScores>>,
+  players: Query<(), With<Player>>,
+  time_scale: Res<TimeScale>,
+  mut texts: ResMut<TextBuffers>,
+) {
+  let Some(high_scores) = high_scores.filter(|_| players.is_empty() && time_scale.is_slowed()) else {
+    return;
+  };
+
+  let mut lines = vec![(String::from("HIGH SCORES"), RGB_COLOR_PLAYER)];
+  for (i, entry) in high_scores.entries.iter().take(HIGH_SCORES_SHOWN).enumerate() {
+    let mut text = format!("{}. ", i + 1);
+    write_score(&mut text, entry.score as u64).unwrap();
+    let color = if high_scores.latest == Some(i) {
+      RGB_COLOR_HIGH_SCORE_LATEST
+    } else {
+      RGB_COLOR_PLAYER
+    };
+    lines.push((text, color));
+  }
+
+  let top = (SCREEN_RENDER_HEIGHT as f32 + lines.len() as f32 * HUD_LINE_HEIGHT) / 2.0;
+  for (i, (text, color)) in lines.iter().enumerate() {
+    let width = texts.width(text, HUD_TEXT_SCALE, TextStyle::Bitmap);
+    let (x, y) = ((SCREEN_RENDER_WIDTH as f32 - width) / 2.0, top - i as f32 * HUD_LINE_HEIGHT);
+    texts.build_text(text, x, y, HUD_TEXT_SCALE, ColorGl::from(*color), TextStyle::Bitmap);
+  }
+}
+
+/// The active modifiers of the build in the top center for [`MODIFIERS_SHOWN_DURATION`] seconds, every
+/// time the players spawn. A run without a build shows nothing.
+pub fn modifiers_system(
+  modifiers: Res<Modifiers>,
+  spawned: Query<(), Added<Player>>,
+  mut shown: Local<Option<f32>>,
+  time: Res<Time>,
+  mut texts: ResMut<TextBuffers>,
+) {
+  if !spawned.is_empty() {
+    *shown = Some(0.0);
+  }
+  let Some(elapsed) = shown.as_mut() else {
+    return;
+  };
+  *elapsed += time.raw().as_secs_f32();
+  if *elapsed > MODIFIERS_SHOWN_DURATION {
+    *shown = None;
+    return;
+  }
+
+  for (i, text) in modifiers.active().iter().enumerate() {
+    let width = texts.width(text, HUD_TEXT_SCALE, TextStyle::Bitmap);
+    let x = (SCREEN_RENDER_WIDTH as f32 - width) / 2.0;
+    let y = SCREEN_RENDER_HEIGHT as f32 - 30.0 - i as f32 * HUD_LINE_HEIGHT;
+    texts.build_text(text, x, y, HUD_TEXT_SCALE, ColorGl::from(RGB_COLOR_PLAYER), TextStyle::Bitmap);
+  }
+}
+
+pub fn flash_guard_system(
+  mut flash_guard: ResMut<FlashGuard>,
+  time: Res<Time>,
+  mut texts: ResMut<TextBuffers>,
+) {
+  flash_guard.tick(time.raw().as_secs_f32());
+
+  // only the first time, a notice every time would be just as noisy as the flashes
+  if flash_guard.is_clamped() && flash_guard.activations == 1 {
+    texts.build_text(
+      "flashes reduced",
+      10.0,
+      SCREEN_RENDER_HEIGHT as f32 - 30.0 - HUD_LINE_HEIGHT,
+      0.75,
+      ColorGl::from(RGB_COLOR_PLAYER),
+      TextStyle::Bitmap,
+    );
+  }
+}
+
+pub fn heatmap_system(
+  mut event_reader: EventReader<GameEvents>,
+  mut heatmap: ResMut<Heatmap>,
+  player_query: Query<&Transform, With<Player>>,
+  bounds: Res<WorldBounds>,
+  mut screen: ResMut<ScreenGeometry>,
+  mut fills: ResMut<Fills>,
+  mut strokes: ResMut<Strokes>,
+) {
+  for event in event_reader.iter() {
+    if let GameEvents::PlayerDeath(player, _) = event {
+      if let Ok(transform) = player_query.get(*player) {
+        heatmap.deaths.push(transform.position.to_render());
+      }
+    }
+  }
+
+  for transform in player_query.iter() {
+    if let Some(i) = cell_index(&bounds, transform.position) {
+      heatmap.visits[i] = heatmap.visits[i].saturating_add(1);
+    }
+  }
+
+  if !heatmap.visible || !player_query.is_empty() {
+    return;
+  }
+
+  let screen_size = glam::vec2(SCREEN_WIDTH as f32, SCREEN_HEIGHT as f32);
+  let cell_size = screen_size / glam::vec2(HEATMAP_COLUMNS as f32, HEATMAP_ROWS as f32);
+  let max = heatmap.visits.iter().copied().max().unwrap_or_default();
+  for (i, &count) in heatmap.visits.iter().enumerate().filter(|(_, &count)| count > 0) {
+    let cell = glam::vec2((i % HEATMAP_COLUMNS) as f32, (i / HEATMAP_COLUMNS) as f32);
+    fills
+      .tessellate_rectangle(
+        &Box2D::from_size(Size::new(cell_size.x, cell_size.y)),
+        &FillOptions::default(),
+        &mut BuffersBuilder::new(
+          &mut screen.vertex_buffer,
+          WithTransformColor {
+            transform: glam::Mat4::from_translation((cell * cell_size).extend(Z_INDEX_HEATMAP)),
+            color_rgba: heat_color(normalize(count, max)),
+          },
+        ),
+      )
+      .ok();
+  }
+
+  let mut builder = Path::builder();
+  for (from, to) in [((-2.0, -2.0), (2.0, 2.0)), ((-2.0, 2.0), (2.0, -2.0))] {
+    builder.begin(point(from.0, from.1));
+    builder.line_to(point(to.0, to.1));
+    builder.end(false);
+  }
+  let cross = builder.build();
+  for death in &heatmap.deaths {
+    let position = (*death - bounds.min) / bounds.size() * screen_size;
+    strokes
+      .tessellate_path(
+        &cross,
+        &StrokeOptions::default(),
+        &mut BuffersBuilder::new(
+          &mut screen.vertex_buffer,
+          WithTransformColor {
+            transform: glam::Mat4::from_translation(position.extend(Z_INDEX_HEATMAP + 1.0)),
+            color_rgba: ColorGl::from(RGB_COLOR_PLAYER),
+          },
+        ),
+      )
+      .ok();
+  }
+}
+
+pub fn title_logo_system(
+  time: Res<Time>,
+  mut logo: ResMut<TitleLogo>,
+  player_query: Query<(), With<Player>>,
+  mut screen: ResMut<ScreenGeometry>,
+  mut strokes: ResMut<Strokes>,
+) {
+  if !player_query.is_empty() {
+    logo.elapsed = 0.0;
+    return;
+  }
+  logo.elapsed += time.domain(TimeDomain::Ui).as_secs_f32();
+
+  let scale = SCREEN_WIDTH as f32 * 0.6 / logo_width();
+  let origin = glam::vec2(
+    (SCREEN_WIDTH as f32 - logo_width() * scale) / 2.0,
+    SCREEN_HEIGHT as f32 * 0.7 - LETTER_HEIGHT * scale / 2.0,
+  );
+  let total_length: f32 = LETTERS.iter().flat_map(|letter| letter.iter()).map(|stroke| polyline_length(stroke)).sum();
+  // every stroke is drawn in one after the other, so the reveal reads left to right
+  let mut remaining = total_length * (logo.elapsed / LOGO_REVEAL_DURATION).min(1.0);
+  let bob_time = (logo.elapsed - LOGO_REVEAL_DURATION).max(0.0);
+
+  for (i, letter) in LETTERS.iter().enumerate() {
+    let bob = (bob_time * 3.0 + i as f32 * 0.6).sin() * bob_time.min(1.0) * 0.15;
+    let offset = glam::vec2(i as f32 * (LETTER_WIDTH + LETTER_SPACING), bob);
+
+    let mut builder = Path::builder();
+    for stroke in letter.iter() {
+      let points = truncate_polyline(stroke, remaining);
+      remaining -= polyline_length(stroke);
+      if points.len() < 2 {
+        continue;
+      }
+      builder.begin(point(points[0].x, points[0].y));
+      for p in &points[1..] {
+        builder.line_to(point(p.x, p.y));
+      }
+      builder.end(false);
+    }
+
+    strokes
+      .tessellate_path(
+        &builder.build(),
+        &StrokeOptions::default().with_line_width(0.4),
+        &mut BuffersBuilder::new(
+          &mut screen.vertex_buffer,
+          WithTransformColor {
+            transform: glam::Mat4::from_translation((origin + offset * scale).extend(Z_INDEX_LOGO))
+              * glam::Mat4::from_scale(glam::Vec3::splat(scale)),
+            color_rgba: ColorGl::from(RGB_COLOR_PLAYER),
+          },
+        ),
+      )
+      .ok();
+  }
+}
+
+/// One box per easing in the bottom right corner, the curve inside and its name below. The entity the
+/// [`Inspector`] has selected is a dot moving along the curve it eases with, whose box is outlined
+/// thicker, and a line above the boxes.
+pub fn curve_panel_system(
+  panel: Res<CurvePanel>,
+  inspector: Res<Inspector>,
+  interpolations: Query<&Interpolation>,
+  mut screen: ResMut<ScreenGeometry>,
+  mut fills: ResMut<Fills>,
+  mut strokes: ResMut<Strokes>,
+  mut texts: ResMut<TextBuffers>,
+) {
+  if !panel.visible {
+    return;
+  }
+
+  let color_rgba = ColorGl::from(RGB_COLOR_PLAYER);
+  let size = glam::Vec2::splat(CURVE_PLOT_SIZE);
+  let pitch = CURVE_PLOT_SIZE + CURVE_PLOT_MARGIN;
+  let inspected = inspector.selected.and_then(|entity| Some((entity, interpolations.get(entity).ok()?)));
+  if let Some((entity, interpolation)) = inspected {
+    let easing = interpolation.easing().map_or("not evaluated yet", Easing::name);
+    let overridden = if interpolation.easing_override().is_some() { " override" } else { "" };
+    let text = format!("entity {} {}{} {:.2}", entity.index(), easing, overridden, interpolation.phase());
+    let line = glam::vec2(SCREEN_WIDTH as f32 - Easing::ALL.len() as f32 * pitch, pitch + CURVE_PLOT_MARGIN * 2.0);
+    texts.build_text(&text, line.x * 2.0, line.y * 2.0, 0.5, color_rgba, TextStyle::Bitmap);
+  }
+  for (i, easing) in Easing::ALL.into_iter().enumerate() {
+    let min = glam::vec2(SCREEN_WIDTH as f32 - (Easing::ALL.len() - i) as f32 * pitch, CURVE_PLOT_MARGIN * 2.0);
+    let points = plot(easing.function(), min, size, CURVE_PLOT_SAMPLES);
+    let inspected = inspected.map(|(_, interpolation)| interpolation).filter(|i| i.easing() == Some(easing));
+
+    let mut builder = Path::builder();
+    builder.begin(point(points[0].x, points[0].y));
+    for p in &points[1..] {
+      builder.line_to(point(p.x, p.y));
+    }
+    builder.end(false);
+
+    let transform = glam::Mat4::from_translation(glam::vec3(0.0, 0.0, Z_INDEX_CURVE_PLOT));
+    let mut buffers = BuffersBuilder::new(&mut screen.vertex_buffer, WithTransformColor { transform, color_rgba });
+    strokes
+      .tessellate_path(&builder.build(), &StrokeOptions::default().with_line_width(0.5), &mut buffers)
+      .ok();
+    strokes
+      .tessellate_rectangle(
+        &Box2D::new(point(min.x, min.y), point(min.x + size.x, min.y + size.y)),
+        &StrokeOptions::default().with_line_width(if inspected.is_some() { 0.75 } else { 0.25 }),
+        &mut buffers,
+      )
+      .ok();
+    if let Some(interpolation) = inspected {
+      let phase = interpolation.phase();
+      let dot = min + glam::vec2(phase, easing.function()(phase).clamp(0.0, 1.0)) * size;
+      fills
+        .tessellate_rectangle(
+          &Box2D::new(point(dot.x - 1.0, dot.y - 1.0), point(dot.x + 1.0, dot.y + 1.0)),
+          &FillOptions::default(),
+          &mut buffers,
+        )
+        .ok();
+    }
+
+    // text is laid out at twice the screen resolution
+    let label = (min - glam::vec2(0.0, CURVE_PLOT_MARGIN)) * 2.0;
+    texts.build_text(easing.name(), label.x, label.y, 0.5, color_rgba, TextStyle::Bitmap);
+  }
+}
+
+pub fn system_health_system(health: Res<SystemHealth>, tasks: Res<MainThreadTasks>, mut texts: ResMut<TextBuffers>) {
+  let failing = health.failing();
+  for (i, (name, state)) in failing.iter().enumerate() {
+    let name = name.rsplit("::").next().unwrap_or_default();
+    let text = if state.disabled {
+      format!("{} disabled", name)
+    } else {
+      format!("{} failed {}x", name, state.failures)
+    };
+    texts.build_text(
+      &text,
+      10.0,
+      10.0 + i as f32 * 20.0,
+      0.75,
+      ColorGl::from(RGB_COLOR_DEATH),
+      TextStyle::Bitmap,
+    );
+  }
+
+  if !tasks.is_empty() {
+    let text = format!("{} tasks queued, {:.2}ms", tasks.len(), tasks.spent.as_secs_f32() * 1000.0);
+    let y = 10.0 + failing.len() as f32 * 20.0;
+    texts.build_text(&text, 10.0, y, 0.75, ColorGl::from(RGB_COLOR_PLAYER), TextStyle::Bitmap);
+  }
+}
+
+pub fn laser_hazard_spawn_system(
+  mut commands: Commands,
+  timer: Res<EntitySpawnTimer>,
+  mut rng: ResMut<Randoms>,
+  bounds: Res<WorldBounds>,
+) {
+  if timer.laser_hazard.just_finished() {
+    spawn_laser_hazard(&mut commands, &mut rng, &bounds);
+  }
+}
+
+/// A laser across the whole playfield through a random point, starting with its telegraph.
+pub fn spawn_laser_hazard(commands: &mut Commands, rng: &mut Randoms, bounds: &WorldBounds) {
+  let through = WorldPos::new(
+    rng.gen_range(bounds.min.x..bounds.max.x),
+    rng.gen_range(bounds.min.y..bounds.max.y),
+  );
+  let angle = rng.gen_range(0.0..std::f32::consts::PI);
+  let direction = WorldVec::new(angle.cos(), angle.sin());
+  let reach = bounds.size().length();
+
+  commands
+    .spawn_empty()
+    .insert(ScopeTag(GameScope::Run))
+    .insert(LaserHazard {
+      phase: LaserPhase::Telegraph,
+      timer: Timer::from_seconds(LASER_TELEGRAPH_DURATION, false),
+      start: through - direction * reach,
+      end: through + direction * reach,
+    })
+    .insert(Interpolation::new(vec![(0.5, 1.5)], 0.25, InterpolationMode::PingPong));
+}
+
+pub fn laser_hazard_system(
+  mut commands: Commands,
+  mut event_writer: EventWriter<GameEvents>,
+  mut query: Query<(&mut LaserHazard, &mut Interpolation, Entity)>,
+  player_query: Query<(&Transform, &Sweep, Entity), (With<Player>, Without<Invulnerable>)>,
+  target_query: Query<
+    (&Transform, Entity),
+    (
+      Or<(With<Projectile>, With<AmmoPickup>, With<BoostPickup>, With<AttackPickup>)>,
+      Without<Despawning>,
+    ),
+  >,
+  mut rng: ResMut<Randoms>,
+  time: Res<Time>,
+  bounds: Res<WorldBounds>,
+  mut distortions: ResMut<Distortions>,
+  mut flash_guard: ResMut<FlashGuard>,
+  mut pool: ResMut<ParticlePool>,
+  mut deaths: ResMut<Deaths>,
+) {
+  for (mut laser, mut interpolation, entity) in query.iter_mut() {
+    laser.timer.tick(**time);
+    interpolation.eval(time.as_secs_f32(), Easing::Linear);
+    let color = ColorGl::from(RGB_COLOR_DEATH);
+
+    match laser.phase {
+      LaserPhase::Telegraph => {
+        if laser.timer.finished {
+          laser.phase = LaserPhase::Firing;
+          laser.timer = Timer::from_seconds(LASER_FIRING_DURATION, false);
+          flash_guard.flash();
+
+          let steps = ((laser.end - laser.start).length() as usize / 24).max(1);
+          for i in 0..=steps {
+            let position = laser.start.lerp(laser.end, i as f32 / steps as f32);
+            if bounds.is_outside(position, 0.0) {
+              continue;
+            }
+            if i % 3 == 0 {
+              distortions.add(position.to_render(), 24.0, 1.0);
+            }
+            spawn_explosion_line(
+              &mut commands,
+              &mut pool,
+              Transform {
+                position,
+                z: Z_INDEX_LASER,
+                rotation: Angle::from_radians(rng.gen_range(0.0..2.0 * std::f32::consts::PI)),
+                ..Default::default()
+              },
+              color,
+              Interpolation::new(
+                vec![(rng.gen_range(50.0..100.0), 0.0), (3.0, 0.0), (2.0, 0.0)],
+                rng.gen_range(0.2..0.35),
+                InterpolationMode::Once,
+              ),
+            );
+          }
+        }
+      }
+      LaserPhase::Firing => {
+        for (transform, sweep, player) in player_query.iter() {
+          if deaths.contains(player) {
+            continue;
+          }
+          // the whole move of the tick, a boosted ship can't slip through the beam between two ticks
+          if swept_segment_contact(sweep.from, transform.position, laser.start, laser.end, PLAYER_RADIUS) {
+            kill_player(&mut commands, &mut event_writer, &mut deaths, player, DeathCause::Laser);
+          }
+        }
+
+        for (transform, target) in target_query.iter() {
+          if segment_circle_overlap(laser.start, laser.end, transform.position, LASER_WIDTH / 2.0) {
+            commands.entity(target).insert(Despawning::bundle(
+              ExitAnim::Shatter(4),
+              transform.mat4(),
+              glam::Vec2::ZERO,
+              color,
+            ));
+          }
+        }
+
+        if laser.timer.finished {
+          laser.phase = LaserPhase::Fade;
+          laser.timer = Timer::from_seconds(LASER_FADE_DURATION, false);
+        }
+      }
+      LaserPhase::Fade => {
+        if laser.timer.finished {
+          commands.entity(entity).insert(Despawning::now());
+        }
+      }
+    }
+  }
+}
+
+/// A pulsing dim line while the laser telegraphs, full width while it fires, then narrowing away. A
+/// clamped [`FlashGuard`] keeps the firing laser dim.
+pub fn draw_lasers_system(
+  query: Query<(&LaserHazard, &Interpolation), Without<Despawning>>,
+  mut lines: ResMut<LineGeometry>,
+  mut strokes: ResMut<Strokes>,
+  flash_guard: Res<FlashGuard>,
+) {
+  for (laser, interpolation) in query.iter() {
+    let &[pulse] = interpolation.values(Easing::Linear).as_slice() else {
+      continue;
+    };
+    let color = ColorGl::from(RGB_COLOR_DEATH);
+    let dim = ColorGl {
+      r: color.r * 0.5,
+      g: color.g * 0.5,
+      b: color.b * 0.5,
+      ..color
+    };
+    let (width, color_rgba) = match laser.phase {
+      LaserPhase::Telegraph => (pulse, dim),
+      LaserPhase::Firing => (LASER_WIDTH, if flash_guard.is_clamped() { dim } else { color }),
+      LaserPhase::Fade => (LASER_WIDTH * (1.0 - laser.timer.percent()), color),
+    };
+
+    let (start, end) = (laser.start.to_render(), laser.end.to_render());
+    let mut builder = Path::builder();
+    builder.begin(point(start.x, start.y));
+    builder.line_to(point(end.x, end.y));
+    builder.end(false);
+
+    let mut options = StrokeOptions::default();
+    options.line_width = width;
+    strokes
+      .tessellate_path(
+        &builder.build(),
+        &options,
+        &mut BuffersBuilder::new(
+          &mut lines.vertex_buffer,
+          WithTransformColor {
+            transform: glam::Mat4::from_translation(glam::vec3(0.0, 0.0, Z_INDEX_LASER)),
+            color_rgba,
+          },
+        ),
+      )
+      .ok();
+  }
+}
+
+pub fn distortion_system(mut distortions: ResMut<Distortions>, time: Res<Time>) {
+  distortions.update(time.effects().as_secs_f32());
+}
+
+/// Despawns what the game stage marked [`Despawning`] without an exit animation. Its own stage, so the
+/// marks of every system are applied and each entity is despawned exactly once.
+pub fn despawn_system(mut commands: Commands, query: Query<(&Despawning, Entity)>) {
+  for (despawning, entity) in query.iter() {
+    if despawning.exit.is_none() {
+      commands.entity(entity).despawn();
+    }
+  }
+}
+
+/// Shatters what leaves with [`ExitAnim::Shatter`] and runs out the other exit animations,
+/// [`draw_despawn_animations_system`] draws those.
+pub fn despawn_animation_system(
+  mut commands: Commands,
+  mut query: Query<(&Despawning, &Transform, &mut Interpolation, Entity)>,
+  mut rng: ResMut<Randoms>,
+  mut pool: ResMut<ParticlePool>,
+  time: Res<Time>,
+) {
+  for (despawning, transform, mut interpolation, entity) in query.iter_mut() {
+    let Some(exit) = &despawning.exit else {
+      continue;
+    };
+    if let ExitAnim::Shatter(count) = exit.animation {
+      for _ in 0..count {
+        let z_angle = rng.gen_range(0.0..2.0 * std::f32::consts::PI);
+        spawn_explosion_line(
+          &mut commands,
+          &mut pool,
+          Transform {
+            rotation: Angle::from_radians(z_angle),
+            ..*transform
+          },
+          exit.color,
+          Interpolation::new(
+            vec![(rng.gen_range(75.0..150.0), 0.0), (4.0, 0.0), (2.0, 0.0)],
+            rng.gen_range(0.2..0.3),
+            InterpolationMode::Once,
+          ),
+        );
+      }
+      commands.entity(entity).despawn();
+      continue;
+    }
+
+    let (values, done) = interpolation.eval(time.effects().as_secs_f32(), Easing::InOutCubic);
+    if values.len() != 1 || done {
+      commands.entity(entity).despawn();
+    }
+  }
+}
+
+pub fn draw_despawn_animations_system(
+  query: Query<(&Despawning, &Interpolation)>,
+  mut quads: ResMut<QuadGeometry>,
+  mut fills: ResMut<Fills>,
+  mut strokes: ResMut<Strokes>,
+) {
+  for (despawning, interpolation) in query.iter() {
+    let Some(exit) = despawning.exit.as_ref().filter(|exit| !matches!(exit.animation, ExitAnim::Shatter(_))) else {
+      continue;
+    };
+    let &[scale] = interpolation.values(Easing::InOutCubic).as_slice() else {
+      continue;
+    };
+
+    let half_size = (exit.size / 2.0).extend(0.0);
+    let mat4 = exit.transform
+      * glam::Mat4::from_translation(half_size)
+      * glam::Mat4::from_scale(glam::vec3(scale, scale, 1.0))
+      * glam::Mat4::from_translation(-half_size);
+    let rect = Box2D::from_size(Size::new(exit.size.x, exit.size.y));
+    let mut builder = BuffersBuilder::new(
+      &mut quads.vertex_buffer,
+      WithTransformColor {
+        transform: mat4,
+        color_rgba: exit.color,
+      },
+    );
+
+    let _ = match exit.animation {
+      ExitAnim::Shrink => fills.tessellate_rectangle(&rect, &FillOptions::default(), &mut builder),
+      ExitAnim::Pop => strokes.tessellate_rectangle(&rect, &StrokeOptions::default(), &mut builder),
+      ExitAnim::Shatter(_) => unreachable!(),
+    };
+  }
+}
+
+/// Where the draw systems show `transform` this frame, between the tick before and the last one.
+fn interpolated(transform: &Transform, previous: Option<&PreviousTransform>, alpha: RenderAlpha) -> Transform {
+  match previous {
+    Some(previous) => transform.lerp_from(&previous.0, alpha.0),
+    None => *transform,
+  }
+}
+
+/// Tessellates `shape` into `buffers`, the caller picks the buffer matching the kind of shape. Circles
+/// go through the [`GeometryCache`].
+fn tessellate_shape<G>(
+  shape: Shape,
+  transform: glam::Mat4,
+  color_rgba: ColorGl,
+  buffers: &mut DrawBuffers<G>,
+  cache: &mut GeometryCache,
+  fills: &mut Fills,
+  strokes: &mut Strokes,
+) {
+  let with = WithTransformColor { transform, color_rgba };
+  if let Shape::Circle { radius, stroke } = shape {
+    draw_circle(buffers, cache, fills, strokes, radius, CircleStyle::Stroke(stroke), with);
+    return;
+  }
+
+  let mut builder = BuffersBuilder::new(&mut buffers.vertex_buffer, with);
+  let _ = match shape {
+    Shape::Rect { size, fill: true } => fills.tessellate_rectangle(
+      &Box2D::from_size(Size::new(size.x, size.y)),
+      &FillOptions::default(),
+      &mut builder,
+    ),
+    Shape::Rect { size, fill: false } => strokes.tessellate_rectangle(
+      &Box2D::from_size(Size::new(size.x, size.y)),
+      &StrokeOptions::default(),
+      &mut builder,
+    ),
+    Shape::Line { length, width } => {
+      let mut path = Path::builder();
+      path.begin(point(0.0, 0.0));
+      path.line_to(point(0.0, length));
+      path.close();
+      strokes.tessellate_path(&path.build(), &StrokeOptions::default().with_line_width(width), &mut builder)
+    }
+    Shape::Circle { .. } => return,
+  };
+}
+
+/// Skips the frames an [`Invulnerable`] player blinks off.
+pub fn draw_player_system(
+  query: Query<
+    (&Shape, &Transform, Option<&PreviousTransform>, &PlayerAppearance, Option<&Invulnerable>),
+    With<Player>,
+  >,
+  mut circles: ResMut<CircleGeometry>,
+  mut cache: ResMut<GeometryCache>,
+  mut fills: ResMut<Fills>,
+  mut strokes: ResMut<Strokes>,
+  alpha: Res<RenderAlpha>,
+) {
+  for (shape, transform, previous, appearance, invulnerable) in query.iter() {
+    if invulnerable.is_some_and(|invulnerable| !invulnerable.visible()) {
+      continue;
+    }
+    let transform = interpolated(transform, previous, *alpha);
+    let color = appearance.color;
+    tessellate_shape(*shape, transform.mat4(), color, &mut circles, &mut cache, &mut fills, &mut strokes);
+  }
+}
+
+pub fn draw_projectiles_system(
+  query: Query<(&Shape, &Transform, Option<&PreviousTransform>), (With<Projectile>, Without<Despawning>)>,
+  mut circles: ResMut<CircleGeometry>,
+  mut cache: ResMut<GeometryCache>,
+  mut fills: ResMut<Fills>,
+  mut strokes: ResMut<Strokes>,
+  alpha: Res<RenderAlpha>,
+) {
+  for (shape, transform, previous) in query.iter() {
+    let transform = interpolated(transform, previous, *alpha);
+    let color = ColorGl::from(RGB_COLOR_PLAYER);
+    tessellate_shape(*shape, transform.mat4(), color, &mut circles, &mut cache, &mut fills, &mut strokes);
+  }
+}
+
+/// Splats flash in the projectile color, which fades out over the death color.
+pub fn draw_dead_projectiles_system(
+  query: Query<(&Shape, &DeadProjectile, &Transform, Option<&PreviousTransform>), Without<Despawning>>,
+  mut quads: ResMut<QuadGeometry>,
+  mut particles: ResMut<ParticleGeometry>,
+  mut cache: ResMut<GeometryCache>,
+  mut fills: ResMut<Fills>,
+  mut strokes: ResMut<Strokes>,
+  alpha: Res<RenderAlpha>,
+) {
+  for (shape, dead_projectile, transform, previous) in query.iter() {
+    let transform = interpolated(transform, previous, *alpha);
+    let color = ColorGl::from(RGB_COLOR_DEATH);
+    tessellate_shape(*shape, transform.mat4(), color, &mut quads, &mut cache, &mut fills, &mut strokes);
+
+    let alpha = fade_alpha(dead_projectile.timer.elapsed.as_secs_f32() / DEAD_PROJECTILE_FADE_DURATION);
+    if alpha > 0.0 {
+      // half a layer up, an equal depth would fail the depth test
+      let flash = glam::Mat4::from_translation(glam::vec3(0.0, 0.0, 0.5)) * transform.mat4();
+      let color = ColorGl::from(RGB_COLOR_PLAYER).with_alpha(alpha);
+      tessellate_shape(*shape, flash, color, &mut particles, &mut cache, &mut fills, &mut strokes);
+    }
+  }
+}
+
+/// Fading lines go into the blended [`ParticleGeometry`], the rest stay opaque.
+pub fn draw_explosions_system(
+  query: Query<(
+    &Shape,
+    &ExplosionEffect,
+    &Interpolation,
+    &Transform,
+    Option<&PreviousTransform>,
+    Option<&SpawnSeq>,
+    Option<&Active>,
+  )>,
+  mut lines: ResMut<LineGeometry>,
+  mut particles: ResMut<ParticleGeometry>,
+  mut cache: ResMut<GeometryCache>,
+  mut fills: ResMut<Fills>,
+  mut strokes: ResMut<Strokes>,
+  alpha: Res<RenderAlpha>,
+) {
+  for (shape, explosion, interpolation, transform, previous, seq, active) in query.iter() {
+    if active.is_some_and(|active| !active.0) {
+      continue;
+    }
+    let transform = z_biased(interpolated(transform, previous, *alpha).mat4(), seq);
+    if explosion.fade {
+      let color = explosion.color.with_alpha(fade_alpha(interpolation.progress()));
+      tessellate_shape(*shape, transform, color, &mut particles, &mut cache, &mut fills, &mut strokes);
+    } else {
+      tessellate_shape(*shape, transform, explosion.color, &mut lines, &mut cache, &mut fills, &mut strokes);
+    }
+  }
+}
+
+/// The square at the nose of a player that just shot.
+pub fn draw_shooting_system(
+  query: Query<(&PlayerAppearance, &Transform, Option<&PreviousTransform>, &Interpolation), With<Player>>,
+  mut quads: ResMut<QuadGeometry>,
+  mut fills: ResMut<Fills>,
+  alpha: Res<RenderAlpha>,
+) {
+  for (appearance, transform, previous, interpolation) in query.iter() {
+    let &[size] = interpolation.values(Easing::InOutCubic).as_slice() else {
+      continue;
+    };
+    let transform = interpolated(transform, previous, *alpha);
+    let mat4 =
+      glam::Mat4::from_rotation_translation(
+        (transform.rotation + Angle::from_degrees(45.0)).to_render(),
+        transform.translation(),
+      ) * glam::Mat4::from_translation(glam::vec3(8.0 - size / 2.0, 8.0 - size / 2.0, Z_INDEX_PLAYER));
+
+    fills
+      .tessellate_rectangle(
+        &Box2D::from_size(Size::new(size, size)),
+        &FillOptions::default(),
+        &mut BuffersBuilder::new(
+          &mut quads.vertex_buffer,
+          WithTransformColor {
+            transform: mat4,
+            color_rgba: appearance.color,
+          },
+        ),
+      )
+      .ok();
+  }
+}
+
+/// Trail puffs in the trail color, or the boost color while their player boosts. The puffs of a dead
+/// player keep the trail color while they shrink.
+pub fn draw_trail_effects_system(
+  query: Query<(&TrailEffect, &Interpolation, &Transform, Option<&SpawnSeq>, &Active)>,
+  owners: Query<(&PlayerId, &Boost)>,
+  mut circles: ResMut<CircleGeometry>,
+  mut cache: ResMut<GeometryCache>,
+  mut fills: ResMut<Fills>,
+  mut strokes: ResMut<Strokes>,
+  input: Res<InputState>,
+) {
+  for (trail, interpolation, transform, seq, active) in query.iter() {
+    if !active.0 {
+      continue;
+    }
+    let &[radius] = interpolation.values(Easing::Linear).as_slice() else {
+      continue;
+    };
+
+    let boosting = owners
+      .get(trail.0)
+      .is_ok_and(|(id, boost)| boost.can_boost() && input.player(*id).thrust != Thrust::Cruise);
+    let color_rgba = ColorGl::from(if boosting { RGB_COLOR_BOOST } else { RGB_COLOR_TRAIL });
+
+    draw_circle(
+      &mut circles,
+      &mut cache,
+      &mut fills,
+      &mut strokes,
+      radius,
+      CircleStyle::Fill,
+      WithTransformColor {
+        transform: z_biased(transform.mat4(), seq),
+        color_rgba,
+      },
+    );
+  }
+}
+
+pub fn draw_attack_pickups_system(
+  query: Query<(&Transform, Option<&PreviousTransform>), (With<AttackPickup>, Without<Despawning>)>,
+  mut quads: ResMut<QuadGeometry>,
+  glyphs: Res<GlyphMeshes>,
+  alpha: Res<RenderAlpha>,
+) {
+  for (transform, previous) in query.iter() {
+    let transform = interpolated(transform, previous, *alpha);
+    glyphs.draw(
+      GlyphId::Attack,
+      false,
+      &mut quads.vertex_buffer,
+      WithTransformColor {
+        transform: transform.mat4_center(),
+        color_rgba: ColorGl::from(RGB_COLOR_ATTACK_PICKUP),
+      },
+    );
+  }
+}
+
+pub fn draw_enemies_system(
+  query: Query<(&Enemy, &Transform, Option<&PreviousTransform>), Without<Despawning>>,
+  mut quads: ResMut<QuadGeometry>,
+  glyphs: Res<GlyphMeshes>,
+  alpha: Res<RenderAlpha>,
+) {
+  for (enemy, transform, previous) in query.iter() {
+    let transform = interpolated(transform, previous, *alpha);
+    let glyph = match enemy.kind {
+      EnemyKind::Rock => GlyphId::Rock,
+    };
+    glyphs.draw(
+      glyph,
+      false,
+      &mut quads.vertex_buffer,
+      WithTransformColor {
+        transform: transform.mat4_center(),
+        color_rgba: ColorGl::from(RGB_COLOR_ENEMY),
+      },
+    );
+  }
+}
+
+/// The dashed ring of every [`BurnZone`] turning slowly, with embers flickering inside it. The embers
+/// stay in place per zone, their flicker comes from a random stream of the draw stage's own. The zone
+/// fades out over its last tick.
+pub fn draw_burn_zones_system(
+  query: Query<(&BurnZone, &Lifetime, &Transform, Entity), Without<Despawning>>,
+  mut lines: ResMut<LineGeometry>,
+  mut circles: ResMut<CircleGeometry>,
+  mut cache: ResMut<GeometryCache>,
+  mut fills: ResMut<Fills>,
+  mut strokes: ResMut<Strokes>,
+  mut flicker: Local<Option<rand::rngs::SmallRng>>,
+) {
+  use rand::SeedableRng;
+
+  let flicker = flicker.get_or_insert_with(|| rand::rngs::SmallRng::seed_from_u64(0));
+  let color = ColorGl::from(RGB_COLOR_BURN);
+  for (zone, lifetime, transform, entity) in query.iter() {
+    let elapsed = lifetime.0.elapsed.as_secs_f32();
+    let left = lifetime.0.duration.as_secs_f32() - elapsed;
+    let alpha = (left / BURN_TICK).clamp(0.0, 1.0);
+    let center = transform.position.to_render();
+
+    let mut builder = Path::builder();
+    let dash = std::f32::consts::TAU / BURN_ZONE_DASHES as f32;
+    for i in 0..BURN_ZONE_DASHES {
+      let start = elapsed * BURN_ZONE_SPIN + i as f32 * dash;
+      let (steps, length) = (4, dash * 0.6);
+      for step in 0..=steps {
+        let angle = start + length * step as f32 / steps as f32;
+        let at = center + glam::Vec2::from_angle(angle) * zone.radius;
+        if step == 0 {
+          builder.begin(point(at.x, at.y));
+        } else {
+          builder.line_to(point(at.x, at.y));
+        }
+      }
+      builder.end(false);
+    }
+    strokes
+      .tessellate_path(
+        &builder.build(),
+        &StrokeOptions::default(),
+        &mut BuffersBuilder::new(
+          &mut lines.vertex_buffer,
+          WithTransformColor {
+            transform: glam::Mat4::from_translation(glam::vec3(0.0, 0.0, Z_INDEX_BURN_ZONE)),
+            color_rgba: color.with_alpha(alpha),
+          },
+        ),
+      )
+      .ok();
+
+    let mut embers = rand::rngs::SmallRng::seed_from_u64(entity.to_bits());
+    for _ in 0..BURN_ZONE_EMBERS {
+      let at = glam::Vec2::from_angle(embers.gen_range(0.0..std::f32::consts::TAU))
+        * zone.radius
+        * embers.gen_range(0.0f32..0.8).sqrt();
+      draw_circle(
+        &mut circles,
+        &mut cache,
+        &mut fills,
+        &mut strokes,
+        1.5,
+        CircleStyle::Fill,
+        WithTransformColor {
+          transform: glam::Mat4::from_translation((center + at).extend(Z_INDEX_BURN_ZONE)),
+          color_rgba: color.with_alpha(alpha * flicker.gen_range(0.2..1.0)),
+        },
+      );
+    }
+  }
+}
+
+/// The ring of every [`Shockwave`], fading out as it grows.
+pub fn draw_shockwaves_system(
+  query: Query<(&Interpolation, &Transform), (With<Shockwave>, Without<Despawning>)>,
+  mut circles: ResMut<CircleGeometry>,
+  mut cache: ResMut<GeometryCache>,
+  mut fills: ResMut<Fills>,
+  mut strokes: ResMut<Strokes>,
+) {
+  for (interpolation, transform) in query.iter() {
+    let &[radius] = interpolation.values(Easing::Linear).as_slice() else {
+      continue;
+    };
+    let color_rgba = ColorGl::from(RGB_COLOR_SHOCKWAVE).with_alpha(fade_alpha(interpolation.progress()));
+    draw_circle(
+      &mut circles,
+      &mut cache,
+      &mut fills,
+      &mut strokes,
+      radius,
+      CircleStyle::Stroke(2.0),
+      WithTransformColor {
+        transform: glam::Mat4::from_translation(transform.position.to_render().extend(Z_INDEX_SHOCKWAVE)),
+        color_rgba,
+      },
+    );
+  }
+}
+
+/// The ammo glyph, or a square once collected until the pop starts.
+pub fn draw_ammo_pickups_system(
+  query: Query<(&AmmoPickup, &Transform, Option<&PreviousTransform>), Without<Despawning>>,
+  mut quads: ResMut<QuadGeometry>,
+  mut fills: ResMut<Fills>,
+  glyphs: Res<GlyphMeshes>,
+  alpha: Res<RenderAlpha>,
+) {
+  let color_rgba = ColorGl::from(RGB_COLOR_AMMO_PICKUP);
+  for (ammo, transform, previous) in query.iter() {
+    let transform = interpolated(transform, previous, *alpha);
+    if ammo.timer.elapsed.as_secs_f32() > 0.0 {
+      let transform = transform.mat4_center() * glam::Mat4::from_translation(glam::vec3(9.5 / -2.0, 9.5 / -2.0, 1.0));
+      fills
+        .tessellate_rectangle(
+          &Box2D::from_size(Size::new(9.5, 9.5)),
+          &FillOptions::default(),
+          &mut BuffersBuilder::new(&mut quads.vertex_buffer, WithTransformColor { transform, color_rgba }),
+        )
+        .ok();
+      continue;
+    }
+
+    glyphs.draw(
+      GlyphId::Ammo,
+      false,
+      &mut quads.vertex_buffer,
+      WithTransformColor {
+        transform: transform.mat4_center() * glam::Mat4::from_translation(glam::Vec3::Z),
+        color_rgba,
+      },
+    );
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// A minute of 60 Hz ticks, integrated once through the units and once in plain f32. The default
+  /// build has to match it bit for bit. With `fixed_point` every tick rounds once, by at most half of
+  /// 1 / 256 per axis, so that is how far apart the two may drift per tick.
+  #[test]
+  fn integrating_a_minute_matches_f32() {
+    let (ticks, step) = (3600, 1.0 / 60.0);
+    let tolerance = if cfg!(feature = "fixed_point") { ticks as f32 * 0.5 / 256.0 } else { 0.0 };
+
+    let velocity = WorldVec::new(37.5, -12.25);
+    let mut linear = Transform::default();
+    let mut heading = Transform {
+      rotation: Angle::from_degrees(30.0),
+      ..Default::default()
+    };
+    let (sin, cos) = heading.rotation.radians().sin_cos();
+    let (mut expected_linear, mut expected_heading) = (glam::Vec2::ZERO, glam::Vec2::ZERO);
+    for _ in 0..ticks {
+      integrate(&mut linear, Velocity::Linear(velocity), None, step);
+      integrate(&mut heading, Velocity::Heading(90.0), None, step);
+      expected_linear += velocity.to_render() * step;
+      expected_heading += glam::vec2(-sin, cos) * (90.0 * step);
+    }
+
+    for (actual, expected) in [(linear, expected_linear), (heading, expected_heading)] {
+      let drift = (actual.position.to_render() - expected).abs().max_element();
+      assert!(drift <= tolerance, "{} drifted {} from {}", actual.position.to_render(), drift, expected);
+    }
+  }
+
+  #[test]
+  fn effects_are_numbered_in_entity_order_and_again_when_the_pool_reuses_them() {
+    let mut world = World::new();
+    world.insert_resource(SpawnSequence::default());
+    let owner = world.spawn_empty().id();
+    let tick = world.spawn(TickEffect(owner)).id();
+    let trail = world.spawn((TrailEffect(owner), Active(true))).id();
+    let pooled = world.spawn((TrailEffect(owner), Active(false))).id();
+    let mut stage = SystemStage::single_threaded().with_system(spawn_seq_system);
+    let seq = |world: &World, entity| world.get::<SpawnSeq>(entity).copied();
+
+    stage.run(&mut world);
+    let numbered = [tick, trail, pooled, owner].map(|entity| seq(&world, entity));
+    assert_eq!(numbered, [Some(SpawnSeq(0)), Some(SpawnSeq(1)), None, None]);
+
+    // nothing new, nothing renumbered
+    stage.run(&mut world);
+    assert_eq!(seq(&world, trail), Some(SpawnSeq(1)));
+
+    // taken from the pool it goes on top, as does one released and taken again
+    world.get_mut::<Active>(pooled).unwrap().0 = true;
+    world.get_mut::<Active>(trail).unwrap().0 = false;
+    stage.run(&mut world);
+    world.get_mut::<Active>(trail).unwrap().0 = true;
+    stage.run(&mut world);
+    assert_eq!(seq(&world, pooled), Some(SpawnSeq(2)));
+    assert_eq!(seq(&world, trail), Some(SpawnSeq(3)));
+    assert_eq!(seq(&world, tick), Some(SpawnSeq(0)));
+  }
+
+  /// Just what [`score_system`] reads and writes.
+  fn scoring_world() -> (World, SystemStage) {
+    let mut world = World::new();
+    crate::app::insert_detached_buffers(&mut world);
+    world.init_resource::<Events<GameEvents>>();
+    world.init_resource::<Events<PickupEvent>>();
+    world.init_resource::<Events<NotificationEvent>>();
+    world.insert_resource(Score::default());
+    world.insert_resource(GameBalance::default());
+    world.insert_resource(Time::default());
+    (world, SystemStage::single_threaded().with_system(score_system))
+  }
+
+  fn score_after(world: &mut World, stage: &mut SystemStage, seconds: f32) -> u32 {
+    world.resource_mut::<Time>().advance(Duration::from_secs_f32(seconds));
+    stage.run(world);
+    world.resource::<Score>().0
+  }
+
+  fn spawn_player(world: &mut World) -> Entity {
+    let player = Player {
+      movement_speed: 0.0,
+      rotation_speed: 0.0,
+      brake_turn: None,
+      bounds_mode: BoundsMode::Free,
+      against_wall: false,
+    };
+    world.spawn(player).id()
+  }
+
+  #[test]
+  fn kills_and_pickups_score_once_each() {
+    let (mut world, mut stage) = scoring_world();
+    let position = WorldPos::new(100.0, 50.0);
+    for killed_by in [KilledBy::Projectile, KilledBy::Bomb, KilledBy::Burn] {
+      world.send_event(GameEvents::EnemyDeath(position, killed_by));
+    }
+    world.send_event(GameEvents::BombDetonated);
+    for pickup in [Pickup::Ammo, Pickup::Boost, Pickup::Attack] {
+      world.send_event(PickupEvent(pickup));
+    }
+
+    let expected = SCORE_ENEMY + 2 * SCORE_ENEMY_BOMBED + SCORE_AMMO_PICKUP + SCORE_BOOST_PICKUP + SCORE_ATTACK_PICKUP;
+    assert_eq!(score_after(&mut world, &mut stage, 1.0), expected);
+    // every kill pops its points where the enemy died
+    let notices = world.resource::<Events<NotificationEvent>>();
+    let points = notices.get_reader().iter(notices).map(|event| (event.notice, event.position)).collect::<Vec<_>>();
+    let popped = [SCORE_ENEMY, SCORE_ENEMY_BOMBED, SCORE_ENEMY_BOMBED].map(|points| (Notice::Points(points), position));
+    assert_eq!(points, popped);
+    // read once, they don't count again
+    assert_eq!(score_after(&mut world, &mut stage, 1.0), expected);
+  }
+
+  #[test]
+  fn time_alive_pays_out_per_interval_and_restarts_with_the_next_player() {
+    let (mut world, mut stage) = scoring_world();
+    let part = SCORE_SURVIVAL_INTERVAL * 0.4;
+    // no player, no points
+    assert_eq!(score_after(&mut world, &mut stage, SCORE_SURVIVAL_INTERVAL * 2.0), 0);
+
+    let player = spawn_player(&mut world);
+    assert_eq!(score_after(&mut world, &mut stage, part), 0);
+    assert_eq!(score_after(&mut world, &mut stage, part), 0);
+    assert_eq!(score_after(&mut world, &mut stage, part), SCORE_SURVIVAL);
+
+    // what the last player had towards the next interval is lost
+    world.despawn(player);
+    assert_eq!(score_after(&mut world, &mut stage, part), SCORE_SURVIVAL);
+    spawn_player(&mut world);
+    assert_eq!(score_after(&mut world, &mut stage, part * 2.0), SCORE_SURVIVAL);
+    // a long frame pays out every interval it covers
+    assert_eq!(score_after(&mut world, &mut stage, SCORE_SURVIVAL_INTERVAL * 2.0), 3 * SCORE_SURVIVAL);
+  }
+
+  #[test]
+  fn the_score_stops_at_the_top_instead_of_wrapping() {
+    let (mut world, mut stage) = scoring_world();
+    world.resource_mut::<Score>().0 = u32::MAX - 1;
+    world.send_event(GameEvents::EnemyDeath(WorldPos::default(), KilledBy::Projectile));
+    assert_eq!(score_after(&mut world, &mut stage, 0.0), u32::MAX);
+  }
+}
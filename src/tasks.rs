@@ -0,0 +1,143 @@
+use crate::environment::MAIN_THREAD_TASK_BUDGET;
+use bevy_ecs::prelude::*;
+use std::{
+  collections::VecDeque,
+  time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskStatus {
+  /// Call again next time there is budget left.
+  Continue,
+  Done,
+}
+
+type Task = Box<dyn FnMut(&mut World) -> TaskStatus + Send + Sync>;
+
+/// Heavy one off work that needs the main thread, split into steps that run within
+/// [`MAIN_THREAD_TASK_BUDGET`] at the end of every frame. Tasks run in the order they were pushed.
+#[derive(Resource, Default)]
+pub struct MainThreadTasks {
+  queue: VecDeque<(&'static str, Duration, Task)>,
+  /// Time the drain took last frame.
+  pub spent: Duration,
+}
+
+impl MainThreadTasks {
+  /// Queues `task`, `cost` estimates how long one of its steps takes.
+  pub fn push(
+    &mut self,
+    name: &'static str,
+    cost: Duration,
+    task: impl FnMut(&mut World) -> TaskStatus + Send + Sync + 'static,
+  ) {
+    self.queue.push_back((name, cost, Box::new(task)));
+  }
+
+  pub fn len(&self) -> usize {
+    self.queue.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.queue.is_empty()
+  }
+}
+
+/// Runs queued steps until the next one is estimated to overrun the budget. The first step of a frame
+/// always runs, so a task costlier than the whole budget still makes progress.
+pub fn main_thread_tasks_system(world: &mut World) {
+  let start = Instant::now();
+  let mut queue = std::mem::take(&mut world.resource_mut::<MainThreadTasks>().queue);
+
+  let mut ran = false;
+  while let Some((name, cost, mut task)) = queue.pop_front() {
+    if ran && start.elapsed() + cost > MAIN_THREAD_TASK_BUDGET {
+      queue.push_front((name, cost, task));
+      break;
+    }
+    ran = true;
+    if task(world) == TaskStatus::Continue {
+      queue.push_front((name, cost, task));
+    }
+  }
+
+  // tasks pushed by the steps that just ran go behind the ones that were waiting already
+  let mut tasks = world.resource_mut::<MainThreadTasks>();
+  queue.append(&mut tasks.queue);
+  tasks.queue = queue;
+  tasks.spent = start.elapsed();
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Names of the steps that ran, in order.
+  #[derive(Resource, Default)]
+  struct Ran(Vec<&'static str>);
+
+  fn world() -> World {
+    let mut world = World::new();
+    world.insert_resource(MainThreadTasks::default());
+    world.insert_resource(Ran::default());
+    world
+  }
+
+  /// A task of `steps` steps that log `name`.
+  fn steps(name: &'static str, mut steps: u32) -> impl FnMut(&mut World) -> TaskStatus + Send + Sync {
+    move |world: &mut World| {
+      world.resource_mut::<Ran>().0.push(name);
+      steps -= 1;
+      if steps == 0 {
+        TaskStatus::Done
+      } else {
+        TaskStatus::Continue
+      }
+    }
+  }
+
+  #[test]
+  fn tasks_run_in_order_and_each_to_its_end_before_the_next() {
+    let mut world = world();
+    let mut tasks = world.resource_mut::<MainThreadTasks>();
+    tasks.push("a", Duration::ZERO, steps("a", 2));
+    tasks.push("b", Duration::ZERO, steps("b", 1));
+    assert_eq!(tasks.len(), 2);
+
+    main_thread_tasks_system(&mut world);
+    assert_eq!(world.resource::<Ran>().0, ["a", "a", "b"]);
+    assert!(world.resource::<MainThreadTasks>().is_empty());
+  }
+
+  #[test]
+  fn a_step_over_the_budget_waits_for_the_next_frame_unless_it_is_the_first() {
+    let mut world = world();
+    let mut tasks = world.resource_mut::<MainThreadTasks>();
+    tasks.push("a", MAIN_THREAD_TASK_BUDGET * 2, steps("a", 1));
+    tasks.push("b", MAIN_THREAD_TASK_BUDGET * 2, steps("b", 1));
+
+    main_thread_tasks_system(&mut world);
+    assert_eq!(world.resource::<Ran>().0, ["a"]);
+    assert_eq!(world.resource::<MainThreadTasks>().len(), 1);
+    main_thread_tasks_system(&mut world);
+    assert_eq!(world.resource::<Ran>().0, ["a", "b"]);
+    assert!(world.resource::<MainThreadTasks>().is_empty());
+  }
+
+  #[test]
+  fn tasks_pushed_by_a_step_queue_up_behind_the_waiting_ones() {
+    let mut world = world();
+    let mut tasks = world.resource_mut::<MainThreadTasks>();
+    tasks.push("a", Duration::ZERO, |world: &mut World| {
+      world.resource_mut::<Ran>().0.push("a");
+      world.resource_mut::<MainThreadTasks>().push("c", Duration::ZERO, steps("c", 1));
+      TaskStatus::Done
+    });
+    tasks.push("b", MAIN_THREAD_TASK_BUDGET * 2, steps("b", 1));
+
+    main_thread_tasks_system(&mut world);
+    assert_eq!(world.resource::<Ran>().0, ["a"]);
+    main_thread_tasks_system(&mut world);
+    assert_eq!(world.resource::<Ran>().0, ["a", "b", "c"]);
+  }
+}
@@ -0,0 +1,616 @@
+use crate::{
+  color::ColorGl,
+  environment::{RGB_COLOR_PLAYER, RGB_COLOR_UI_FOCUS, UI_COLUMN_GAP, Z_INDEX_UI},
+  input::InputSource,
+  prompts::{source_name, PadStyle},
+  render::WithTransformColor,
+  resources::{ScreenGeometry, Strokes, TextBuffers, TextStyle},
+};
+use bevy_ecs::prelude::*;
+use lyon::{
+  math::{point, Box2D},
+  tessellation::{BuffersBuilder, StrokeOptions},
+};
+use sdl2::{controller::Button, keyboard::Keycode};
+use std::{collections::HashMap, ops::RangeInclusive};
+
+/// One press or pointer move for the UI, queued by main() between ticks. Keys, buttons and the wheel
+/// come as the sources they are so [`UiContext::key_capture`] can take any of them, the pointer in text
+/// coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UiInput {
+  Press(InputSource),
+  Pointer(glam::Vec2),
+  Click(glam::Vec2),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Nav {
+  Up,
+  Down,
+  Left,
+  Right,
+  Confirm,
+  Back,
+}
+
+fn nav(source: InputSource) -> Option<Nav> {
+  use InputSource::{ControllerButton, Key};
+  match source {
+    Key(Keycode::Up | Keycode::W) | ControllerButton(Button::DPadUp) | InputSource::WheelUp => Some(Nav::Up),
+    Key(Keycode::Down | Keycode::S) | ControllerButton(Button::DPadDown) | InputSource::WheelDown => Some(Nav::Down),
+    Key(Keycode::Left | Keycode::A) | ControllerButton(Button::DPadLeft) => Some(Nav::Left),
+    Key(Keycode::Right | Keycode::D) | ControllerButton(Button::DPadRight) => Some(Nav::Right),
+    Key(Keycode::Return | Keycode::KpEnter | Keycode::Space) | ControllerButton(Button::A) => Some(Nav::Confirm),
+    Key(Keycode::Escape | Keycode::Backspace) | ControllerButton(Button::B) => Some(Nav::Back),
+    _ => None,
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+  Left,
+  Center,
+}
+
+/// Where the rows of a panel go, in text coordinates. They stack down from `anchor`, the baseline of the
+/// first row at its left end or its middle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layout {
+  pub anchor: glam::Vec2,
+  pub align: Align,
+  /// From one baseline to the next.
+  pub spacing: f32,
+  pub scale: f32,
+}
+
+/// The box of every row, given how wide each is. A box reaches a quarter of the spacing below the
+/// baseline and the rest of it above, so the boxes of a panel touch without overlapping.
+pub fn layout_rows(layout: &Layout, widths: impl IntoIterator<Item = f32>) -> Vec<Box2D> {
+  widths
+    .into_iter()
+    .enumerate()
+    .map(|(i, width)| {
+      let baseline = layout.anchor.y - i as f32 * layout.spacing;
+      let left = match layout.align {
+        Align::Left => layout.anchor.x,
+        Align::Center => layout.anchor.x - width / 2.0,
+      };
+      Box2D::new(
+        point(left, baseline - layout.spacing / 4.0),
+        point(left + width, baseline + layout.spacing * 3.0 / 4.0),
+      )
+    })
+    .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+  pub text: String,
+  /// A widget the focus can land on, labels can't.
+  pub interactive: bool,
+  pub focused: bool,
+  /// The second column of a [`UiContext::pair`].
+  pub value: Option<String>,
+}
+
+#[derive(Debug)]
+struct Panel {
+  id: &'static str,
+  layout: Layout,
+  rows: Vec<Row>,
+}
+
+/// What a panel remembers between frames.
+#[derive(Debug, Default)]
+struct PanelState {
+  /// Counts the interactive rows only.
+  focus: usize,
+  interactive: usize,
+  /// Of the interactive rows as last drawn, what the pointer is tested against.
+  boxes: Vec<Box2D>,
+  capturing: bool,
+  /// Typing into a text input, see [`UiContext::text_input`].
+  editing: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+  Type(char),
+  Erase,
+  Finish,
+  Cancel,
+}
+
+/// What typing `keycode` adds to a text input: letters, lowercase, digits, dashes, dots and spaces.
+/// The widget decides which it takes.
+fn typed(keycode: Keycode) -> Option<char> {
+  if keycode == Keycode::Space {
+    return Some(' ');
+  }
+  let name = keycode.name();
+  let mut chars = name.chars();
+  match (chars.next(), chars.next()) {
+    (Some(c), None) if c.is_ascii_alphanumeric() || c == '-' || c == '.' => Some(c.to_ascii_lowercase()),
+    _ => None,
+  }
+}
+
+/// The navigation of the panel on top this frame, used up by the focused widget.
+#[derive(Debug, Default)]
+struct Actions {
+  confirm: bool,
+  left: bool,
+  right: bool,
+  back: bool,
+  captured: Option<InputSource>,
+  edits: Vec<Edit>,
+  scroll: i32,
+}
+
+/// Immediate mode widgets over the screen space text. Systems open a panel with [`Self::begin`] every
+/// tick they want it shown, declare its widgets in order and close it with [`Self::end`]. The panel
+/// opened last is on top, only it reads input, from the next frame on so a panel opened by a press
+/// doesn't take the same press. [`draw_ui_system`] draws the panels.
+#[derive(Debug, Default, Resource)]
+pub struct UiContext {
+  queued: Vec<UiInput>,
+  input: Vec<UiInput>,
+  top: Option<&'static str>,
+  states: HashMap<&'static str, PanelState>,
+  panels: Vec<Panel>,
+  /// The interactive rows of the open panel so far.
+  widgets: usize,
+  actions: Actions,
+}
+
+impl UiContext {
+  pub fn push(&mut self, input: UiInput) {
+    self.queued.push(input);
+  }
+
+  /// Starts a frame, the panel on top of the last one gets the input queued since. Panels that weren't
+  /// shown last frame forget their focus.
+  pub fn begin_frame(&mut self) {
+    self.top = self.panels.last().map(|panel| panel.id);
+    let shown = self.panels.drain(..).map(|panel| panel.id).collect::<Vec<_>>();
+    self.states.retain(|id, _| shown.contains(id));
+    self.input = std::mem::take(&mut self.queued);
+  }
+
+  /// While a key capture or a text input waits for keys, main() leaves its hotkeys alone.
+  pub fn wants_keys(&self) -> bool {
+    self.states.values().any(|state| state.capturing || state.editing)
+  }
+
+  /// Whether anything was shown the last tick, main() leaves Escape to the panels while it was.
+  pub fn is_open(&self) -> bool {
+    !self.panels.is_empty()
+  }
+
+  pub fn begin(&mut self, id: &'static str, layout: Layout) {
+    self.panels.push(Panel {
+      id,
+      layout,
+      rows: Vec::new(),
+    });
+    self.widgets = 0;
+    self.actions = Actions::default();
+    let input = if self.top == Some(id) { std::mem::take(&mut self.input) } else { Vec::new() };
+    let state = self.states.entry(id).or_default();
+    let count = state.interactive.max(1);
+    for input in input {
+      match input {
+        // the click that comes with a captured mouse button mustn't start another capture
+        UiInput::Press(source) if state.capturing => {
+          state.capturing = false;
+          if source != InputSource::Key(Keycode::Escape) {
+            self.actions.captured = Some(source);
+          }
+          break;
+        }
+        UiInput::Press(InputSource::Key(keycode)) if state.editing => {
+          let edit = match keycode {
+            Keycode::Return | Keycode::KpEnter => Edit::Finish,
+            Keycode::Escape => Edit::Cancel,
+            Keycode::Backspace => Edit::Erase,
+            keycode => match typed(keycode) {
+              Some(c) => Edit::Type(c),
+              None => continue,
+            },
+          };
+          state.editing &= !matches!(edit, Edit::Finish | Edit::Cancel);
+          self.actions.edits.push(edit);
+        }
+        // only keys type, the rest waits until the text is done
+        _ if state.editing => {}
+        UiInput::Press(source) => match nav(source) {
+          Some(Nav::Up) => {
+            state.focus = (state.focus + count - 1) % count;
+            self.actions.scroll -= 1;
+          }
+          Some(Nav::Down) => {
+            state.focus = (state.focus + 1) % count;
+            self.actions.scroll += 1;
+          }
+          Some(Nav::Left) => self.actions.left = true,
+          Some(Nav::Right) => self.actions.right = true,
+          Some(Nav::Confirm) => self.actions.confirm = true,
+          Some(Nav::Back) => self.actions.back = true,
+          None => {}
+        },
+        UiInput::Pointer(at) | UiInput::Click(at) => {
+          let hit = state.boxes.iter().position(|bounds| bounds.contains(point(at.x, at.y)));
+          if let Some(hit) = hit {
+            state.focus = hit;
+            self.actions.confirm |= matches!(input, UiInput::Click(_));
+          }
+        }
+      }
+    }
+  }
+
+  pub fn end(&mut self) {
+    let panel = self.panels.last().expect("end without begin");
+    self.states.entry(panel.id).or_default().interactive = self.widgets;
+  }
+
+  /// Back pressed on the open panel, e.g. Escape.
+  pub fn back(&self) -> bool {
+    self.actions.back
+  }
+
+  fn panel(&mut self) -> (&mut Panel, &mut PanelState) {
+    let panel = self.panels.last_mut().expect("widget outside a panel");
+    let state = self.states.get_mut(panel.id).unwrap();
+    (panel, state)
+  }
+
+  pub fn label(&mut self, text: impl Into<String>) {
+    let (panel, _) = self.panel();
+    panel.rows.push(Row {
+      text: text.into(),
+      interactive: false,
+      focused: false,
+      value: None,
+    });
+  }
+
+  /// A label and its value in two columns either side of the anchor, whatever the alignment.
+  pub fn pair(&mut self, label: impl Into<String>, value: impl Into<String>) {
+    let (panel, _) = self.panel();
+    panel.rows.push(Row {
+      text: label.into(),
+      interactive: false,
+      focused: false,
+      value: Some(value.into()),
+    });
+  }
+
+  /// Up and down pressed on the open panel, less the ups, e.g. to scroll a panel of labels.
+  pub fn scroll(&self) -> i32 {
+    self.actions.scroll
+  }
+
+  /// Adds an interactive row, whether it has the focus.
+  fn widget(&mut self, text: String) -> bool {
+    let index = self.widgets;
+    self.widgets += 1;
+    let top = self.top;
+    let (panel, state) = self.panel();
+    let focused = state.focus == index && top == Some(panel.id);
+    panel.rows.push(Row {
+      text,
+      interactive: true,
+      focused,
+      value: None,
+    });
+    focused
+  }
+
+  /// Whether it was pressed.
+  pub fn button(&mut self, text: &str) -> bool {
+    self.widget(text.to_string()) && self.actions.confirm
+  }
+
+  /// Flips `value` on confirm, left or right. Whether it changed.
+  pub fn toggle(&mut self, text: &str, value: &mut bool) -> bool {
+    let row = format!("{} {}", text, if *value { "ON" } else { "OFF" });
+    let changed = self.widget(row) && (self.actions.confirm || self.actions.left || self.actions.right);
+    if changed {
+      *value = !*value;
+    }
+    changed
+  }
+
+  /// Left and right step `value` by a tenth of `range`, staying inside it. Whether it changed.
+  pub fn slider(&mut self, text: &str, value: &mut f32, range: RangeInclusive<f32>) -> bool {
+    let fraction = (*value - range.start()) / (range.end() - range.start());
+    let row = format!("{} < {:.0}% >", text, fraction * 100.0);
+    if !self.widget(row) {
+      return false;
+    }
+    let step = (range.end() - range.start()) / 10.0;
+    let delta = if self.actions.left { -step } else if self.actions.right { step } else { 0.0 };
+    let stepped = (*value + delta).clamp(*range.start(), *range.end());
+    let changed = stepped != *value;
+    *value = stepped;
+    changed
+  }
+
+  /// Confirm waits for the next key, button or wheel notch and puts it in `source`, Escape cancels.
+  /// Whether it changed.
+  pub fn key_capture(&mut self, text: &str, source: &mut InputSource) -> bool {
+    let capturing = {
+      let (_, state) = self.panel();
+      state.capturing
+    };
+    let shown = if capturing { String::from("...") } else { source_name(*source, PadStyle::Generic) };
+    if !self.widget(format!("{} {}", text, shown)) {
+      return false;
+    }
+    if self.actions.confirm && !capturing {
+      self.panel().1.capturing = true;
+      return false;
+    }
+    match self.actions.captured.take() {
+      Some(captured) => {
+        *source = captured;
+        true
+      }
+      None => false,
+    }
+  }
+
+  /// Confirm starts typing into `value`, up to `max_len` letters, digits and dashes. Return finishes,
+  /// Escape clears it. Whether it was finished.
+  pub fn text_input(&mut self, text: &str, value: &mut String, max_len: usize) -> bool {
+    self.input(text, value, max_len, |c| c.is_ascii_alphanumeric() || c == '-')
+  }
+
+  /// A [`Self::text_input`] that takes dots and spaces too, for a command with a number.
+  pub fn command_input(&mut self, text: &str, value: &mut String, max_len: usize) -> bool {
+    self.input(text, value, max_len, |_| true)
+  }
+
+  fn input(&mut self, text: &str, value: &mut String, max_len: usize, takes: fn(char) -> bool) -> bool {
+    let editing = self.panel().1.editing;
+    let cursor = if editing { "_" } else { "" };
+    if !self.widget(format!("{} {}{}", text, value, cursor)) {
+      return false;
+    }
+    if self.actions.confirm && !editing {
+      self.panel().1.editing = true;
+      return false;
+    }
+    let mut finished = false;
+    for edit in self.actions.edits.drain(..) {
+      match edit {
+        Edit::Type(c) if value.len() < max_len && takes(c) => value.push(c),
+        Edit::Type(_) => {}
+        Edit::Erase => {
+          value.pop();
+        }
+        Edit::Finish => finished = true,
+        Edit::Cancel => value.clear(),
+      }
+    }
+    finished
+  }
+
+  fn set_boxes(&mut self, id: &'static str, boxes: Vec<Box2D>) {
+    if let Some(state) = self.states.get_mut(id) {
+      state.boxes = boxes;
+    }
+  }
+}
+
+pub fn ui_frame_system(mut ui: ResMut<UiContext>) {
+  ui.begin_frame();
+}
+
+/// The panels of the frame in the order they were opened, the focused row of the one on top outlined.
+pub fn draw_ui_system(
+  mut ui: ResMut<UiContext>,
+  mut screen: ResMut<ScreenGeometry>,
+  mut strokes: ResMut<Strokes>,
+  mut texts: ResMut<TextBuffers>,
+) {
+  let mut drawn = Vec::new();
+  for panel in &ui.panels {
+    let widths = panel.rows.iter().map(|row| texts.width(&row.text, panel.layout.scale, TextStyle::Bitmap));
+    let boxes = layout_rows(&panel.layout, widths);
+    let color = ColorGl::from(RGB_COLOR_PLAYER);
+    for (row, bounds) in panel.rows.iter().zip(&boxes) {
+      let (baseline, scale) = (bounds.min.y + panel.layout.spacing / 4.0, panel.layout.scale);
+      if let Some(value) = &row.value {
+        let x = panel.layout.anchor.x - UI_COLUMN_GAP / 2.0 - texts.width(&row.text, scale, TextStyle::Bitmap);
+        texts.build_text(&row.text, x, baseline, scale, color, TextStyle::Bitmap);
+        let x = panel.layout.anchor.x + UI_COLUMN_GAP / 2.0;
+        texts.build_text(value, x, baseline, scale, color, TextStyle::Bitmap);
+        continue;
+      }
+      texts.build_text(&row.text, bounds.min.x, baseline, scale, color, TextStyle::Bitmap);
+      if !row.focused {
+        continue;
+      }
+      // the screen geometry is at half the resolution of the text
+      strokes
+        .tessellate_rectangle(
+          &Box2D::new(bounds.min / 2.0, bounds.max / 2.0).inflate(2.0, 1.0),
+          &StrokeOptions::default().with_line_width(0.5),
+          &mut BuffersBuilder::new(
+            &mut screen.vertex_buffer,
+            WithTransformColor {
+              transform: glam::Mat4::from_translation(glam::vec3(0.0, 0.0, Z_INDEX_UI)),
+              color_rgba: ColorGl::from(RGB_COLOR_UI_FOCUS),
+            },
+          ),
+        )
+        .ok();
+    }
+    let interactive = panel.rows.iter().zip(boxes).filter(|(row, _)| row.interactive).map(|(_, bounds)| bounds);
+    drawn.push((panel.id, interactive.collect()));
+  }
+  for (id, boxes) in drawn {
+    ui.set_boxes(id, boxes);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const LAYOUT: Layout = Layout {
+    anchor: glam::Vec2::new(100.0, 200.0),
+    align: Align::Center,
+    spacing: 20.0,
+    scale: 1.0,
+  };
+
+  fn press(ui: &mut UiContext, keycode: Keycode) {
+    ui.push(UiInput::Press(InputSource::Key(keycode)));
+  }
+
+  /// One frame of a menu with a label, three buttons and a toggle, the pressed buttons of it.
+  fn menu(ui: &mut UiContext, flag: &mut bool) -> Vec<&'static str> {
+    ui.begin_frame();
+    ui.begin("menu", LAYOUT);
+    ui.label("TITLE");
+    let pressed = ["ONE", "TWO", "THREE"].into_iter().filter(|text| ui.button(text)).collect();
+    ui.toggle("FLAG", flag);
+    ui.end();
+    pressed
+  }
+
+  fn focused(ui: &UiContext) -> Vec<&str> {
+    let rows = ui.panels.iter().flat_map(|panel| &panel.rows);
+    rows.filter(|row| row.focused).map(|row| row.text.as_str()).collect()
+  }
+
+  #[test]
+  fn rows_stack_down_from_the_anchor() {
+    let boxes = layout_rows(&LAYOUT, [40.0, 20.0]);
+    assert_eq!(boxes[0], Box2D::new(point(80.0, 195.0), point(120.0, 215.0)));
+    assert_eq!(boxes[1], Box2D::new(point(90.0, 175.0), point(110.0, 195.0)));
+
+    let left = Layout {
+      align: Align::Left,
+      ..LAYOUT
+    };
+    assert_eq!(layout_rows(&left, [40.0])[0].min.x, 100.0);
+  }
+
+  #[test]
+  fn the_focus_skips_labels_and_wraps_around() {
+    let mut ui = UiContext::default();
+    let mut flag = false;
+    menu(&mut ui, &mut flag);
+    menu(&mut ui, &mut flag);
+    assert_eq!(focused(&ui), ["ONE"]);
+
+    press(&mut ui, Keycode::Up);
+    menu(&mut ui, &mut flag);
+    assert_eq!(focused(&ui), ["FLAG OFF"]);
+
+    press(&mut ui, Keycode::Down);
+    press(&mut ui, Keycode::Down);
+    press(&mut ui, Keycode::Return);
+    assert_eq!(menu(&mut ui, &mut flag), ["TWO"]);
+
+    press(&mut ui, Keycode::Up);
+    press(&mut ui, Keycode::Up);
+    press(&mut ui, Keycode::Right);
+    menu(&mut ui, &mut flag);
+    assert!(flag);
+  }
+
+  #[test]
+  fn only_the_panel_on_top_takes_input() {
+    let mut ui = UiContext::default();
+    let frame = |ui: &mut UiContext, dialog: bool| {
+      ui.begin_frame();
+      ui.begin("menu", LAYOUT);
+      let menu = ui.button("MENU");
+      ui.end();
+      let mut confirmed = false;
+      if dialog {
+        ui.begin("dialog", LAYOUT);
+        confirmed = ui.button("YES");
+        ui.end();
+      }
+      (menu, confirmed)
+    };
+    frame(&mut ui, false);
+
+    // the press that opens the dialog goes to the menu, the next one to the dialog
+    press(&mut ui, Keycode::Return);
+    assert_eq!(frame(&mut ui, true), (true, false));
+    press(&mut ui, Keycode::Return);
+    assert_eq!(frame(&mut ui, true), (false, true));
+    assert_eq!(focused(&ui), ["YES"]);
+
+    // the pointer hits the rows as they were drawn last
+    ui.set_boxes("dialog", layout_rows(&LAYOUT, [40.0]));
+    ui.push(UiInput::Click(glam::vec2(300.0, 200.0)));
+    assert_eq!(frame(&mut ui, true), (false, false));
+    ui.push(UiInput::Click(glam::vec2(100.0, 200.0)));
+    assert_eq!(frame(&mut ui, true), (false, true));
+  }
+
+  #[test]
+  fn text_input_types_until_return_and_leaves_navigation_alone() {
+    let mut ui = UiContext::default();
+    let mut name = String::new();
+    let frame = |ui: &mut UiContext, name: &mut String| {
+      ui.begin_frame();
+      ui.begin("profiles", LAYOUT);
+      let finished = ui.text_input("NEW", name, 4);
+      let back = ui.button("BACK");
+      ui.end();
+      (finished, back)
+    };
+    frame(&mut ui, &mut name);
+
+    press(&mut ui, Keycode::Return);
+    frame(&mut ui, &mut name);
+    assert!(ui.wants_keys());
+    // s moves the focus down outside a text input, the pad's d-pad doesn't type
+    for keycode in [Keycode::S, Keycode::Minus, Keycode::Num2, Keycode::Backspace, Keycode::K, Keycode::Space] {
+      press(&mut ui, keycode);
+    }
+    ui.push(UiInput::Press(InputSource::ControllerButton(Button::DPadDown)));
+    for keycode in [Keycode::I, Keycode::D, Keycode::Return] {
+      press(&mut ui, keycode);
+    }
+    assert_eq!(frame(&mut ui, &mut name), (true, false));
+    assert_eq!(name, "s-ki");
+    assert!(!ui.wants_keys());
+  }
+
+  #[test]
+  fn key_capture_takes_the_next_source_and_escape_cancels() {
+    let mut ui = UiContext::default();
+    let mut source = InputSource::Key(Keycode::Space);
+    let frame = |ui: &mut UiContext, source: &mut InputSource| {
+      ui.begin_frame();
+      ui.begin("controls", LAYOUT);
+      let changed = ui.key_capture("SHOOT", source);
+      ui.end();
+      changed
+    };
+    frame(&mut ui, &mut source);
+
+    press(&mut ui, Keycode::Return);
+    frame(&mut ui, &mut source);
+    ui.push(UiInput::Press(InputSource::MouseButton(1)));
+    assert!(frame(&mut ui, &mut source));
+    assert_eq!(source, InputSource::MouseButton(1));
+
+    press(&mut ui, Keycode::Return);
+    frame(&mut ui, &mut source);
+    press(&mut ui, Keycode::Escape);
+    assert!(!frame(&mut ui, &mut source));
+    assert_eq!(source, InputSource::MouseButton(1));
+  }
+}
@@ -0,0 +1,451 @@
+use std::ops::{Add, AddAssign, Mul, Neg, Sub};
+
+/// The number behind every world unit. An `f32` by default, with the `fixed_point` feature an `i32`
+/// with [`Raw::FRACTION_BITS`] fractional bits, to measure how much float drift matters.
+#[cfg(not(feature = "fixed_point"))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+struct Raw(f32);
+
+#[cfg(feature = "fixed_point")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+struct Raw(i32);
+
+#[cfg(not(feature = "fixed_point"))]
+impl Raw {
+  const ZERO: Raw = Raw(0.0);
+
+  fn new(value: f32) -> Self {
+    Raw(value)
+  }
+
+  fn get(self) -> f32 {
+    self.0
+  }
+
+  fn scale(self, factor: f32) -> Self {
+    Raw(self.0 * factor)
+  }
+}
+
+#[cfg(feature = "fixed_point")]
+impl Raw {
+  const ZERO: Raw = Raw(0);
+  const FRACTION_BITS: u32 = 8;
+  const ONE: f32 = (1 << Self::FRACTION_BITS) as f32;
+
+  fn new(value: f32) -> Self {
+    Raw((value * Self::ONE).round() as i32)
+  }
+
+  fn get(self) -> f32 {
+    self.0 as f32 / Self::ONE
+  }
+
+  /// Rounds once, after scaling, a `factor` like a tick length would lose most of its digits in
+  /// [`Self::new`].
+  fn scale(self, factor: f32) -> Self {
+    Raw((self.0 as f64 * factor as f64).round() as i32)
+  }
+}
+
+impl Raw {
+  fn min(self, other: Raw) -> Raw {
+    Raw(self.0.min(other.0))
+  }
+
+  fn max(self, other: Raw) -> Raw {
+    Raw(self.0.max(other.0))
+  }
+
+  fn clamp(self, min: Raw, max: Raw) -> Raw {
+    Raw(self.0.clamp(min.0, max.0))
+  }
+
+  fn wrap(self, min: Raw, max: Raw) -> Raw {
+    Raw(min.0 + (self.0 - min.0).rem_euclid(max.0 - min.0))
+  }
+
+  fn floor_div(self, divisor: f32) -> i64 {
+    (self.get() / divisor).floor() as i64
+  }
+}
+
+impl Add for Raw {
+  type Output = Raw;
+
+  fn add(self, rhs: Raw) -> Raw {
+    Raw(self.0 + rhs.0)
+  }
+}
+
+impl Sub for Raw {
+  type Output = Raw;
+
+  fn sub(self, rhs: Raw) -> Raw {
+    Raw(self.0 - rhs.0)
+  }
+}
+
+impl Neg for Raw {
+  type Output = Raw;
+
+  fn neg(self) -> Raw {
+    Raw(-self.0)
+  }
+}
+
+impl Mul for Raw {
+  type Output = Raw;
+
+  #[cfg(not(feature = "fixed_point"))]
+  fn mul(self, rhs: Raw) -> Raw {
+    Raw(self.0 * rhs.0)
+  }
+
+  #[cfg(feature = "fixed_point")]
+  fn mul(self, rhs: Raw) -> Raw {
+    Raw(((self.0 as i64 * rhs.0 as i64) >> Self::FRACTION_BITS) as i32)
+  }
+}
+
+/// A position on the playfield. Gameplay code moves it with [`WorldVec`]s, only [`Self::to_render`]
+/// hands out floats.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct WorldPos {
+  x: Raw,
+  y: Raw,
+}
+
+impl WorldPos {
+  pub fn new(x: f32, y: f32) -> Self {
+    Self {
+      x: Raw::new(x),
+      y: Raw::new(y),
+    }
+  }
+
+  pub fn distance_squared(self, other: WorldPos) -> f32 {
+    (self - other).length_squared()
+  }
+
+  /// The point `t` of the way to `other`.
+  pub fn lerp(self, other: WorldPos, t: f32) -> WorldPos {
+    self + (other - self) * t
+  }
+
+  /// Component wise minimum, with [`Self::max`] the corners of the box around two points.
+  pub fn min(self, other: WorldPos) -> WorldPos {
+    WorldPos {
+      x: self.x.min(other.x),
+      y: self.y.min(other.y),
+    }
+  }
+
+  pub fn max(self, other: WorldPos) -> WorldPos {
+    WorldPos {
+      x: self.x.max(other.x),
+      y: self.y.max(other.y),
+    }
+  }
+
+  /// Panics if a component of `min` is greater than the one of `max`.
+  pub fn clamp(self, min: WorldPos, max: WorldPos) -> WorldPos {
+    WorldPos {
+      x: self.x.clamp(min.x, max.x),
+      y: self.y.clamp(min.y, max.y),
+    }
+  }
+
+  /// Wraps each component around into `min..max`, leaving on one side enters on the other.
+  pub fn wrap(self, min: WorldPos, max: WorldPos) -> WorldPos {
+    WorldPos {
+      x: self.x.wrap(min.x, max.x),
+      y: self.y.wrap(min.y, max.y),
+    }
+  }
+
+  /// Whether no component is below `min` or above `max`.
+  pub fn is_within(self, min: WorldPos, max: WorldPos) -> bool {
+    min.x <= self.x && self.x <= max.x && min.y <= self.y && self.y <= max.y
+  }
+
+  pub fn to_render(self) -> glam::Vec2 {
+    glam::vec2(self.x.get(), self.y.get())
+  }
+}
+
+impl From<glam::Vec2> for WorldPos {
+  fn from(position: glam::Vec2) -> Self {
+    WorldPos::new(position.x, position.y)
+  }
+}
+
+impl Add<WorldVec> for WorldPos {
+  type Output = WorldPos;
+
+  fn add(self, rhs: WorldVec) -> WorldPos {
+    WorldPos {
+      x: self.x + rhs.x,
+      y: self.y + rhs.y,
+    }
+  }
+}
+
+impl AddAssign<WorldVec> for WorldPos {
+  fn add_assign(&mut self, rhs: WorldVec) {
+    *self = *self + rhs;
+  }
+}
+
+impl Sub<WorldVec> for WorldPos {
+  type Output = WorldPos;
+
+  fn sub(self, rhs: WorldVec) -> WorldPos {
+    self + -rhs
+  }
+}
+
+impl Sub for WorldPos {
+  type Output = WorldVec;
+
+  fn sub(self, rhs: WorldPos) -> WorldVec {
+    WorldVec {
+      x: self.x - rhs.x,
+      y: self.y - rhs.y,
+    }
+  }
+}
+
+/// A displacement or velocity in world units.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct WorldVec {
+  x: Raw,
+  y: Raw,
+}
+
+impl WorldVec {
+  pub const ZERO: WorldVec = WorldVec {
+    x: Raw::ZERO,
+    y: Raw::ZERO,
+  };
+
+  pub fn new(x: f32, y: f32) -> Self {
+    Self {
+      x: Raw::new(x),
+      y: Raw::new(y),
+    }
+  }
+
+  pub fn splat(value: f32) -> Self {
+    WorldVec::new(value, value)
+  }
+
+  /// Component along x, for comparing distances. Anything moving goes through the operators.
+  pub fn x(self) -> f32 {
+    self.x.get()
+  }
+
+  pub fn y(self) -> f32 {
+    self.y.get()
+  }
+
+  pub fn dot(self, other: WorldVec) -> f32 {
+    (self.x * other.x + self.y * other.y).get()
+  }
+
+  /// The z of the cross product, positive when `other` is counter clockwise of `self`.
+  pub fn perp_dot(self, other: WorldVec) -> f32 {
+    (self.x * other.y - self.y * other.x).get()
+  }
+
+  pub fn length_squared(self) -> f32 {
+    self.dot(self)
+  }
+
+  pub fn length(self) -> f32 {
+    self.length_squared().sqrt()
+  }
+
+  /// How many whole `size` cells this spans along x and y, rounded towards negative infinity.
+  pub fn floor_div(self, size: f32) -> (i64, i64) {
+    (self.x.floor_div(size), self.y.floor_div(size))
+  }
+
+  pub fn to_render(self) -> glam::Vec2 {
+    glam::vec2(self.x.get(), self.y.get())
+  }
+}
+
+impl Add for WorldVec {
+  type Output = WorldVec;
+
+  fn add(self, rhs: WorldVec) -> WorldVec {
+    WorldVec {
+      x: self.x + rhs.x,
+      y: self.y + rhs.y,
+    }
+  }
+}
+
+impl Neg for WorldVec {
+  type Output = WorldVec;
+
+  fn neg(self) -> WorldVec {
+    WorldVec { x: -self.x, y: -self.y }
+  }
+}
+
+impl Mul<f32> for WorldVec {
+  type Output = WorldVec;
+
+  fn mul(self, rhs: f32) -> WorldVec {
+    WorldVec {
+      x: self.x.scale(rhs),
+      y: self.y.scale(rhs),
+    }
+  }
+}
+
+/// A rotation around z, counter clockwise. Zero faces up the y axis, like the ship does unrotated.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Angle(Raw);
+
+impl Angle {
+  pub fn from_radians(radians: f32) -> Self {
+    Angle(Raw::new(radians))
+  }
+
+  pub fn from_degrees(degrees: f32) -> Self {
+    Angle::from_radians(degrees.to_radians())
+  }
+
+  pub fn radians(self) -> f32 {
+    self.0.get()
+  }
+
+  /// The angle whose [`Self::forward`] points along `v`, zero for a zero vector.
+  pub fn facing(v: WorldVec) -> Self {
+    Angle::from_radians(f32::atan2(-v.x.get(), v.y.get()))
+  }
+
+  /// The unit vector this angle faces.
+  pub fn forward(self) -> WorldVec {
+    self.forward_by(1.0)
+  }
+
+  /// [`Self::forward`] stretched to `length`. Scaled before it becomes world units, so with
+  /// `fixed_point` a short step isn't built from an already rounded unit vector.
+  pub fn forward_by(self, length: f32) -> WorldVec {
+    let (x, y) = self.turn(0.0, 1.0);
+    WorldVec::new(x * length, y * length)
+  }
+
+  pub fn rotate(self, v: WorldVec) -> WorldVec {
+    let (x, y) = self.turn(v.x.get(), v.y.get());
+    WorldVec::new(x, y)
+  }
+
+  fn turn(self, x: f32, y: f32) -> (f32, f32) {
+    let (sin, cos) = self.radians().sin_cos();
+    (x * cos - y * sin, x * sin + y * cos)
+  }
+
+  pub fn to_render(self) -> glam::Quat {
+    glam::Quat::from_rotation_z(self.radians())
+  }
+}
+
+impl Add for Angle {
+  type Output = Angle;
+
+  fn add(self, rhs: Angle) -> Angle {
+    Angle(self.0 + rhs.0)
+  }
+}
+
+impl AddAssign for Angle {
+  fn add_assign(&mut self, rhs: Angle) {
+    *self = *self + rhs;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// How far one conversion into world units may round.
+  #[cfg(feature = "fixed_point")]
+  const EPSILON: f32 = 0.5 / Raw::ONE;
+  #[cfg(not(feature = "fixed_point"))]
+  const EPSILON: f32 = 1e-5;
+
+  fn assert_close(actual: WorldVec, expected: glam::Vec2) {
+    let actual = actual.to_render();
+    assert!(
+      (actual - expected).abs().max_element() <= EPSILON,
+      "{} is not {}",
+      actual,
+      expected
+    );
+  }
+
+  #[test]
+  fn positions_move_by_vectors() {
+    let mut position = WorldPos::new(1.5, -2.0);
+    assert_eq!(position + WorldVec::new(0.25, 4.0), WorldPos::new(1.75, 2.0));
+    assert_eq!(position - WorldVec::new(0.25, 4.0), WorldPos::new(1.25, -6.0));
+    assert_eq!(WorldPos::new(4.0, 4.0) - position, WorldVec::new(2.5, 6.0));
+    position += -WorldVec::new(1.5, -2.0);
+    assert_eq!(position, WorldPos::default());
+    assert_eq!(WorldVec::new(1.0, 2.0) + WorldVec::new(-1.0, -2.0), WorldVec::ZERO);
+  }
+
+  #[test]
+  fn vector_products() {
+    let (a, b) = (WorldVec::new(3.0, 4.0), WorldVec::new(-4.0, 3.0));
+    assert_eq!(a.dot(b), 0.0);
+    assert_eq!(a.perp_dot(b), 25.0);
+    assert_eq!(b.perp_dot(a), -25.0);
+    assert_eq!(a.length(), 5.0);
+    assert_eq!(WorldPos::new(1.0, 1.0).distance_squared(WorldPos::new(4.0, 5.0)), 25.0);
+    assert_eq!(a * 0.5, WorldVec::new(1.5, 2.0));
+  }
+
+  #[test]
+  fn scaling_by_a_tick_length_rounds_once() {
+    let step = 1.0 / 60.0;
+    assert_close(WorldVec::new(120.0, -45.0) * step, glam::vec2(2.0, -0.75));
+    let heading = Angle::from_degrees(30.0);
+    let (sin, cos) = heading.radians().sin_cos();
+    assert_close(heading.forward_by(200.0 * step), glam::vec2(-sin, cos) * 200.0 * step);
+  }
+
+  #[test]
+  fn boxes_and_wrapping() {
+    let (min, max) = (WorldPos::new(0.0, 0.0), WorldPos::new(100.0, 50.0));
+    assert_eq!(WorldPos::new(-10.0, 5.0).wrap(min, max), WorldPos::new(90.0, 5.0));
+    assert_eq!(WorldPos::new(-110.0, 120.0).wrap(min, max), WorldPos::new(90.0, 20.0));
+    assert_eq!(WorldPos::new(-10.0, 60.0).clamp(min, max), WorldPos::new(0.0, 50.0));
+    assert!(WorldPos::new(100.0, 0.0).is_within(min, max));
+    assert!(!WorldPos::new(100.5, 0.0).is_within(min, max));
+    assert_eq!(WorldPos::new(5.0, 1.0).min(WorldPos::new(2.0, 3.0)), WorldPos::new(2.0, 1.0));
+    assert_eq!(WorldPos::new(5.0, 1.0).max(WorldPos::new(2.0, 3.0)), WorldPos::new(5.0, 3.0));
+    assert_eq!(min.lerp(max, 0.25), WorldPos::new(25.0, 12.5));
+    assert_eq!(WorldVec::new(-0.5, 33.0).floor_div(16.0), (-1, 2));
+  }
+
+  #[test]
+  fn angles_turn_counter_clockwise() {
+    let quarter = Angle::from_degrees(90.0);
+    let (sin, cos) = quarter.radians().sin_cos();
+    assert_close(quarter.rotate(WorldVec::new(1.0, 0.0)), glam::vec2(cos, sin));
+    assert_close(Angle::default().forward(), glam::vec2(0.0, 1.0));
+    assert_close(quarter.forward(), glam::vec2(-sin, cos));
+    assert_eq!(Angle::facing(WorldVec::new(0.0, 2.0)), Angle::default());
+    assert_eq!(Angle::facing(WorldVec::new(-1.0, 0.0)), Angle::from_radians(std::f32::consts::FRAC_PI_2));
+
+    let mut angle = quarter;
+    angle += quarter;
+    assert_eq!(angle, quarter + quarter);
+  }
+}
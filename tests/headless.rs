@@ -0,0 +1,983 @@
+use bevy_ecs::prelude::*;
+use bytepath::{
+  app::*,
+  audit::Snapshot,
+  balance::GameBalance,
+  color::ColorGl,
+  components::{
+    Active, AmmoPickup, AngularVelocity, Ammo, Attack, AttackPickup, Boost, BoundsMode, BurnZone, Collider,
+    DeadProjectile, DeathAction, Despawning, Enemy, EnemyKind, ExitAnim, ExplosionEffect, Faction, GameScope, Health,
+    Hostile, Interpolation, InterpolationMode, Invulnerable, LaserHazard, LaserPhase, Lifetime, Player, PlayerId,
+    Projectile, ScopeTag, Shockwave, Sweep, Text, Transform, Velocity,
+  },
+  environment::{
+    AMMO_MAX, AMMO_PICKUP_RADIUS, ATTACK_PICKUP_RADIUS, AUDIT_INTERVAL, BOMB_CHARGE_SCORE, BOMB_DURATION, BOMB_RADIUS,
+    BURN_TICK, BURN_ZONE_DURATION, BURN_ZONE_RADIUS, INVULNERABLE_DURATION, LASER_FIRING_DURATION,
+    PARTICLE_BUFFER_INDICES, PARTICLE_BUFFER_VERTICES, PROJECTILE_SPEED, RESPAWN_DELAY, RGB_COLOR_DEATH, ROCK_HEALTH,
+    ROCK_RADIUS, SCORE_ENEMY_BOMBED, SLOW_DOWN_DURATION_ON_DEATH,
+  },
+  events::{DeathCause, EventCounts, GameEvents},
+  health::SystemHealth,
+  input::InputSource,
+  modifiers::Modifiers,
+  replay::Replay,
+  resources::*,
+  stats::{LifetimeStats, RunStats},
+  units::{Angle, WorldPos, WorldVec},
+};
+use rand::RngCore;
+use sdl2::keyboard::Keycode;
+use std::{collections::HashSet, time::Duration};
+
+const STEP: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+fn ticks(seconds: f32) -> u64 {
+  (seconds / STEP.as_secs_f32()).ceil() as u64
+}
+
+/// A run as `--headless` starts it, one player in the middle of the playfield.
+fn headless_run(seed: u64) -> (World, Schedule) {
+  let health = SystemHealth::default();
+  let mut world = build_headless_world(seed, &health, false, &Modifiers::default());
+  world.insert_resource(KeyBindings::default());
+  world.insert_resource(BoundsMode::default());
+  build_startup_schedule().run(&mut world);
+  (world, build_game_schedule(&health, false))
+}
+
+/// Shoots the whole time and turns for half of every second, like `--headless`.
+fn shoot_and_turn(tick: u64) -> HashSet<Keycode> {
+  let mut keys = HashSet::from([Keycode::Space]);
+  if tick % 60 < 30 {
+    keys.insert(Keycode::Left);
+  }
+  keys
+}
+
+/// `seconds` of [`shoot_and_turn`] recorded as main() records them, written out and read back.
+fn recorded_run(seed: u64, seconds: f32) -> Replay {
+  let (mut world, mut schedule) = headless_run(seed);
+  let replay = Replay::record(seed, BoundsMode::default(), KeyBindings::default(), false, Modifiers::default());
+  world.insert_resource(replay);
+  run_ticks(&mut world, &mut schedule, ticks(seconds), STEP, shoot_and_turn);
+  finish_replay(&mut world).unwrap();
+
+  let mut bytes = Vec::new();
+  world.resource::<Replay>().write(&mut bytes).unwrap();
+  Replay::parse(&bytes).unwrap()
+}
+
+fn count<F: bevy_ecs::query::ReadOnlyWorldQuery>(world: &mut World) -> usize {
+  world.query_filtered::<(), F>().iter(world).count()
+}
+
+#[test]
+fn the_same_seed_plays_out_the_same() {
+  let mut runs = [headless_run(4), headless_run(4)];
+
+  for second in 0..10 {
+    let [a, b] = runs.each_mut().map(|(world, schedule)| {
+      run_ticks(world, schedule, ticks(1.0), STEP, |tick| shoot_and_turn(second * ticks(1.0) + tick));
+      Snapshot::capture(world, second)
+    });
+    assert!(a.diff(&b).is_none(), "{}", a.diff(&b).unwrap());
+  }
+}
+
+#[test]
+fn the_lockstep_audit_catches_hash_map_order() {
+  // every map gets its own random keys, so each world draws as often as its map happens to order them
+  fn hash_map_order_system(mut randoms: ResMut<Randoms>) {
+    let map = (0..64u32).map(|key| (key, ())).collect::<std::collections::HashMap<_, _>>();
+    for _ in 0..map.keys().next().copied().unwrap_or_default() {
+      randoms.0.next_u32();
+    }
+  }
+  let [(mut world, mut schedule), (shadow_world, mut shadow_schedule)] = [headless_run(5), headless_run(5)];
+  schedule.add_system_to_stage("game", hash_map_order_system);
+  shadow_schedule.add_system_to_stage("game", hash_map_order_system);
+  let mut lockstep = Lockstep::new(shadow_world, shadow_schedule);
+
+  let divergence = (1..=ticks(5.0)).find_map(|tick| {
+    run_ticks(&mut world, &mut schedule, 1, STEP, |_| shoot_and_turn(tick));
+    lockstep.follow(&mut world, tick)
+  });
+
+  assert_eq!(divergence.expect("the audit missed the hash map order").tick, AUDIT_INTERVAL);
+}
+
+#[test]
+fn a_recording_verifies_against_its_checkpoints() {
+  let replay = recorded_run(2, 5.0);
+  assert_eq!(replay.checkpoints.len() as u64, ticks(5.0) / AUDIT_INTERVAL + 1);
+
+  let health = SystemHealth::default();
+  let mut world = build_replay_world(replay, &health);
+  let mut schedule = build_game_schedule(&health, false);
+
+  assert_eq!(play_replay(&mut world, &mut schedule).unwrap(), ticks(5.0));
+}
+
+#[test]
+fn thirty_seconds_of_recorded_keys_end_on_the_recorded_hash() {
+  let replay = recorded_run(5, 30.0);
+  assert_eq!(replay.frames.len() as u64, ticks(30.0));
+  assert_ne!(replay.final_hash, 0);
+
+  // fed back by hand, without the replay resource checking along the way
+  let (mut world, mut schedule) = headless_run(replay.seed);
+  let keycodes = |tick: u64| replay.input(&replay.frames[tick as usize]).keycodes;
+  run_ticks(&mut world, &mut schedule, replay.frames.len() as u64, STEP, keycodes);
+
+  assert_eq!(Snapshot::capture(&mut world, ticks(30.0)).hash(), replay.final_hash);
+}
+
+#[test]
+fn replays_record_every_bound_source() {
+  let file = KeyBindings::parse("1.shoot = mouse:x1, wheel:down, pad:x\n2.bomb = key:J, mouse:middle\n").unwrap();
+  let layouts = [KeyBindings::layout(false, false), KeyBindings::layout(false, true), KeyBindings::co_op(), file];
+  for bindings in layouts {
+    let sources = bindings.sources();
+    let mut everything = (HashSet::new(), MouseInput::default(), GamepadState::default());
+    for source in &sources {
+      match *source {
+        InputSource::Key(keycode) => drop(everything.0.insert(keycode)),
+        InputSource::MouseButton(button) => drop(everything.1.buttons.insert(button)),
+        InputSource::WheelUp => everything.1.wheel_up = true,
+        InputSource::WheelDown => everything.1.wheel_down = true,
+        InputSource::ControllerButton(button) => drop(everything.2.buttons.insert(button)),
+      }
+    }
+    let mut replay = Replay::record(1, BoundsMode::default(), bindings, false, Modifiers::default());
+    let (keycodes, mouse, gamepad) = &everything;
+    let frame = replay.capture(STEP, keycodes, mouse, gamepad, GameState::Running);
+    replay.frames.push(frame);
+    let mut bytes = Vec::new();
+    replay.write(&mut bytes).unwrap();
+
+    let replay = Replay::parse(&bytes).unwrap();
+    let input = replay.input(&replay.frames[0]);
+    for source in sources {
+      assert!(source.is_held(&input.keycodes, &input.mouse, &input.gamepad), "{} isn't recorded", source);
+    }
+  }
+}
+
+#[test]
+fn verifying_reports_the_first_checkpoint_that_differs() {
+  let mut replay = recorded_run(2, 5.0);
+  for frame in &mut replay.frames[100..] {
+    frame.held = 0;
+  }
+
+  let health = SystemHealth::default();
+  let mut world = build_replay_world(replay, &health);
+  let mut schedule = build_game_schedule(&health, false);
+  let mismatch = play_replay(&mut world, &mut schedule).unwrap_err();
+
+  assert_eq!(mismatch.tick, 2 * AUDIT_INTERVAL);
+  assert!(mismatch.parts.contains(&"Transform"), "{}", mismatch);
+}
+
+#[test]
+fn players_spawn_with_the_stats_of_the_build() {
+  let health = SystemHealth::default();
+  let modifiers = Modifiers {
+    movement_speed_mult: 1.5,
+    boost_max_add: 20.0,
+    ammo_max_add: 10,
+    ..Modifiers::default()
+  };
+  let mut world = build_headless_world(3, &health, false, &modifiers);
+  world.insert_resource(KeyBindings::default());
+  world.insert_resource(BoundsMode::default());
+  build_startup_schedule().run(&mut world);
+
+  let (player, boost, ammo) = world.query::<(&Player, &Boost, &Ammo)>().single(&world);
+  assert_eq!(player.movement_speed, 150.0);
+  assert_eq!((boost.max_boost, boost.boost), (Boost::default().max_boost + 20.0, boost.max_boost));
+  assert_eq!((ammo.max, ammo.current), (AMMO_MAX + 10, AMMO_MAX + 10));
+}
+
+#[test]
+fn shooting_spends_ammo_on_projectiles() {
+  let (mut world, mut schedule) = headless_run(1);
+  let max = world.query_filtered::<&Ammo, With<Player>>().single(&world).max;
+
+  run_ticks(&mut world, &mut schedule, ticks(1.0), STEP, |_| HashSet::from([Keycode::Space]));
+
+  assert!(count::<With<Projectile>>(&mut world) > 0);
+  assert!(world.query_filtered::<&Ammo, With<Player>>().single(&world).current < max);
+}
+
+#[test]
+fn co_op_players_fire_at_their_own_pace_from_their_own_ammo() {
+  let health = SystemHealth::default();
+  let mut world = build_headless_world(7, &health, false, &Modifiers::default());
+  world.insert_resource(KeyBindings::co_op());
+  world.insert_resource(BoundsMode::default());
+  build_startup_schedule().run(&mut world);
+  let mut schedule = build_game_schedule(&health, false);
+  for (id, mut attack) in world.query::<(&PlayerId, &mut Attack)>().iter_mut(&mut world) {
+    *attack = if id.0 == 0 { Attack::Rapid } else { Attack::Neutral };
+  }
+
+  run_ticks(&mut world, &mut schedule, ticks(1.0), STEP, |_| HashSet::new());
+
+  let mut spent = world
+    .query::<(&PlayerId, &Ammo)>()
+    .iter(&world)
+    .map(|(id, ammo)| (id.0, ammo.max - ammo.current))
+    .collect::<Vec<_>>();
+  spent.sort();
+  let [(0, rapid), (1, neutral)] = spent[..] else {
+    panic!("expected two players, got {:?}", spent);
+  };
+  assert!(neutral > 0);
+  assert!(rapid > neutral, "rapid spent {}, neutral spent {}", rapid, neutral);
+}
+
+#[test]
+fn death_slows_time_down_until_the_respawn() {
+  let (mut world, mut schedule) = headless_run(2);
+
+  run_ticks(&mut world, &mut schedule, 1, STEP, |_| HashSet::from([Keycode::S]));
+  run_ticks(&mut world, &mut schedule, 1, STEP, |_| HashSet::new());
+  assert_eq!(count::<With<Player>>(&mut world), 0);
+  assert!(world.resource::<TimeScale>().is_slowed());
+  assert!(world.resource::<Time>().scale(TimeDomain::Gameplay) < 0.5);
+
+  let wait = SLOW_DOWN_DURATION_ON_DEATH.max(RESPAWN_DELAY) + 0.5;
+  run_ticks(&mut world, &mut schedule, ticks(wait), STEP, |_| HashSet::new());
+  assert!(!world.resource::<TimeScale>().is_slowed());
+  assert_eq!(world.resource::<Time>().scale(TimeDomain::Gameplay), 1.0);
+  assert_eq!(count::<With<Player>>(&mut world), 1);
+}
+
+#[test]
+fn touching_an_ammo_pickup_collects_it() {
+  let (mut world, mut schedule) = headless_run(3);
+  world.query_filtered::<&mut Ammo, With<Player>>().single_mut(&mut world).current = 0;
+  let position = world.query_filtered::<&Transform, With<Player>>().single(&world).position;
+  let pickup = world
+    .spawn((
+      ScopeTag(GameScope::Run),
+      AmmoPickup {
+        rotation_speed: std::f32::consts::PI,
+        timer: Timer::from_seconds(0.15, false),
+      },
+      Velocity::Heading(0.0),
+      AngularVelocity(0.0),
+      Transform {
+        position: position + WorldVec::new(2.0, 0.0),
+        ..Default::default()
+      },
+      Collider {
+        radius: AMMO_PICKUP_RADIUS,
+      },
+    ))
+    .id();
+
+  run_ticks(&mut world, &mut schedule, 2, STEP, |_| HashSet::new());
+  assert!(world.get::<Collider>(pickup).is_none());
+  assert!(world.query_filtered::<&Ammo, With<Player>>().single(&world).current > 0);
+
+  run_ticks(&mut world, &mut schedule, ticks(1.0), STEP, |_| HashSet::new());
+  assert!(world.get_entity(pickup).is_none() || world.get::<Despawning>(pickup).is_some());
+}
+
+#[test]
+fn enemies_and_attack_pickups_drift_by_their_velocity() {
+  let (mut world, mut schedule) = headless_run(6);
+  let start = WorldPos::new(40.0, 40.0);
+  let run = ScopeTag(GameScope::Run);
+  let drifting = [
+    world.spawn((run, Enemy { kind: EnemyKind::Rock }, Collider { radius: ROCK_RADIUS })).id(),
+    world.spawn((run, AttackPickup { attack: Attack::Rapid }, Collider { radius: ATTACK_PICKUP_RADIUS })).id(),
+  ];
+  for entity in drifting {
+    world.entity_mut(entity).insert((
+      Velocity::Linear(WorldVec::new(30.0, 0.0)),
+      AngularVelocity(1.0),
+      Transform {
+        position: start,
+        ..Default::default()
+      },
+    ));
+  }
+
+  run_ticks(&mut world, &mut schedule, 120, STEP, |_| HashSet::new());
+
+  // with `fixed_point` every tick rounds once, by at most half of the smallest step
+  let tolerance = if cfg!(feature = "fixed_point") { 120.0 * 0.5 / 256.0 } else { 0.01 };
+  for entity in drifting {
+    let transform = world.get::<Transform>(entity).unwrap();
+    let elapsed = STEP.as_secs_f32() * 120.0;
+    assert!((transform.position.to_render().x - (start.to_render().x + 30.0 * elapsed)).abs() < tolerance);
+    assert!((transform.center_rotation.radians() - elapsed).abs() < tolerance);
+  }
+}
+
+#[test]
+fn a_projectile_flies_straight_from_the_nose_at_the_ship_speed_plus_its_own() {
+  let (mut world, mut schedule) = headless_run(3);
+  let mut projectiles = world.query_filtered::<(Entity, &Velocity, &Transform), With<Projectile>>();
+  let (player_velocity, heading) = loop {
+    run_ticks(&mut world, &mut schedule, 1, STEP, |_| HashSet::new());
+    if projectiles.iter(&world).next().is_some() {
+      let (velocity, transform) = world.query_filtered::<(&Velocity, &Transform), With<Player>>().single(&world);
+      break (velocity.linear(transform.rotation), transform.rotation);
+    }
+  };
+  let (projectile, velocity, start) = projectiles
+    .iter(&world)
+    .map(|(entity, velocity, transform)| (entity, velocity.linear(transform.rotation), *transform))
+    .next()
+    .unwrap();
+
+  let tolerance = if cfg!(feature = "fixed_point") { 0.05 } else { 0.001 };
+  let own = velocity.to_render() - player_velocity.to_render();
+  assert!((own.length() - PROJECTILE_SPEED).abs() < tolerance, "{}", own);
+  assert!(own.normalize().distance(heading.forward().to_render()) < tolerance);
+  assert!(start.rotation.forward().to_render().distance(velocity.to_render().normalize()) < tolerance);
+
+  run_ticks(&mut world, &mut schedule, 20, STEP, |_| HashSet::new());
+
+  let (now, transform) = world.query::<(&Velocity, &Transform)>().get(&world, projectile).unwrap();
+  assert_eq!(now.linear(transform.rotation), velocity);
+  // with `fixed_point` every tick rounds once, by at most half of the smallest step
+  let tolerance = if cfg!(feature = "fixed_point") { 20.0 * 0.5 / 256.0 } else { 0.01 };
+  let expected = start.position.to_render() + velocity.to_render() * STEP.as_secs_f32() * 20.0;
+  let drift = (transform.position.to_render() - expected).abs().max_element();
+  assert!(drift < tolerance, "{} drifted {} from {}", transform.position.to_render(), drift, expected);
+}
+
+#[test]
+fn only_the_draw_stage_tessellates_the_world() {
+  let (mut world, mut schedule) = headless_run(7);
+  world.spawn((
+    ScopeTag(GameScope::Run),
+    Enemy { kind: EnemyKind::Rock },
+    Velocity::Linear(WorldVec::ZERO),
+    Transform::default(),
+    Collider { radius: ROCK_RADIUS },
+  ));
+  run_ticks(&mut world, &mut schedule, ticks(1.0), STEP, |_| HashSet::from([Keycode::Space]));
+
+  schedule.run(&mut world);
+  let vertices = |world: &World| {
+    world.resource::<CircleGeometry>().vertex_buffer.vertices.len()
+      + world.resource::<QuadGeometry>().vertex_buffer.vertices.len()
+      + world.resource::<LineGeometry>().vertex_buffer.vertices.len()
+      + world.resource::<ParticleGeometry>().vertex_buffer.vertices.len()
+  };
+  assert_eq!(vertices(&world), 0);
+
+  build_draw_schedule(&SystemHealth::default()).run(&mut world);
+  assert!(vertices(&world) > 0);
+}
+
+/// The world with nobody shooting, a placed projectile is the only one.
+fn without_players(seed: u64) -> (World, Schedule) {
+  let (mut world, schedule) = headless_run(seed);
+  let players = world.query_filtered::<Entity, With<Player>>().iter(&world).collect::<Vec<_>>();
+  for player in players {
+    world.despawn(player);
+  }
+  (world, schedule)
+}
+
+fn live_projectiles(world: &mut World) -> usize {
+  count::<(With<Projectile>, Without<Despawning>)>(world)
+}
+
+#[test]
+fn an_expired_projectile_performs_its_death_action() {
+  let (mut world, mut schedule) = without_players(8);
+  let center = WorldPos::from(world.resource::<WorldBounds>().center());
+  let projectile = world
+    .spawn((
+      Transform {
+        position: center,
+        ..Default::default()
+      },
+      Projectile::bundle(WorldVec::ZERO),
+      DeathAction::RadialBurst { count: 6, speed: 50.0 },
+      Lifetime(Timer::from_seconds(0.5, false)),
+    ))
+    .id();
+
+  run_ticks(&mut world, &mut schedule, ticks(0.4), STEP, |_| HashSet::new());
+  assert_eq!(live_projectiles(&mut world), 1);
+
+  run_ticks(&mut world, &mut schedule, ticks(0.2), STEP, |_| HashSet::new());
+  assert!(world.get_entity(projectile).is_none() || world.get::<Despawning>(projectile).is_some());
+  assert_eq!(live_projectiles(&mut world), 6);
+}
+
+#[test]
+fn an_enemy_hit_performs_the_death_action_once() {
+  let (mut world, mut schedule) = without_players(9);
+  let center = WorldPos::from(world.resource::<WorldBounds>().center());
+  let transform = Transform {
+    position: center,
+    ..Default::default()
+  };
+  let enemy = world
+    .spawn((
+      ScopeTag(GameScope::Run),
+      Enemy { kind: EnemyKind::Rock },
+      Velocity::Linear(WorldVec::ZERO),
+      transform,
+      Collider { radius: ROCK_RADIUS },
+    ))
+    .id();
+  world.spawn((transform, Projectile::bundle(WorldVec::ZERO), DeathAction::RadialBurst { count: 6, speed: 50.0 }));
+
+  run_ticks(&mut world, &mut schedule, 3, STEP, |_| HashSet::new());
+  assert!(world.get_entity(enemy).is_none() || world.get::<Despawning>(enemy).is_some());
+  assert_eq!(live_projectiles(&mut world), 6);
+
+  // the burst starts on the dying rock and flies off without setting anything else off
+  run_ticks(&mut world, &mut schedule, ticks(0.5), STEP, |_| HashSet::new());
+  assert_eq!(live_projectiles(&mut world), 6);
+}
+
+#[test]
+fn a_fizzle_reuses_the_lines_of_the_one_before() {
+  let (mut world, mut schedule) = without_players(10);
+  let center = WorldPos::from(world.resource::<WorldBounds>().center());
+  let fizzle = |world: &mut World| {
+    world.spawn((
+      Transform {
+        position: center,
+        ..Default::default()
+      },
+      Projectile::bundle(WorldVec::ZERO),
+      DeathAction::Fizzle,
+      Lifetime(Timer::from_seconds(0.1, false)),
+    ));
+  };
+
+  let lines = |world: &mut World| {
+    let mut lines = world.query_filtered::<Entity, With<ExplosionEffect>>().iter(world).collect::<Vec<_>>();
+    lines.sort();
+    lines
+  };
+
+  fizzle(&mut world);
+  run_ticks(&mut world, &mut schedule, ticks(0.2), STEP, |_| HashSet::new());
+  let first = lines(&mut world);
+  assert_eq!(first.len(), 4);
+
+  // with nobody left the effects run at the background pace, the lines are long done by now
+  run_ticks(&mut world, &mut schedule, ticks(2.0), STEP, |_| HashSet::new());
+  fizzle(&mut world);
+  run_ticks(&mut world, &mut schedule, ticks(0.2), STEP, |_| HashSet::new());
+  assert_eq!(lines(&mut world), first);
+}
+
+#[test]
+fn five_hundred_explosions_fit_the_particle_budget() {
+  let (mut world, mut schedule) = without_players(11);
+  let size = world.resource::<WorldBounds>().size();
+  let (columns, rows) = (25, 20);
+  for i in 0..columns * rows {
+    let cell = glam::vec2((i % columns) as f32 + 0.5, (i / columns) as f32 + 0.5);
+    let transform = Transform {
+      position: WorldPos::from(cell * size / glam::vec2(columns as f32, rows as f32)),
+      ..Default::default()
+    };
+    world.spawn((
+      ScopeTag(GameScope::Run),
+      Enemy { kind: EnemyKind::Rock },
+      Velocity::Linear(WorldVec::ZERO),
+      transform,
+      Collider { radius: ROCK_RADIUS },
+    ));
+    world.spawn((transform, Projectile::bundle(WorldVec::ZERO), DeathAction::Nothing));
+  }
+
+  run_ticks(&mut world, &mut schedule, 3, STEP, |_| HashSet::new());
+  assert_eq!(count::<With<Enemy>>(&mut world), 0);
+  // every rock breaks into at least 8 lines, none of them dropped by the pool
+  assert!(count::<With<ExplosionEffect>>(&mut world) >= 8 * columns * rows);
+
+  build_draw_schedule(&SystemHealth::default()).run(&mut world);
+  let particles = &world.resource::<ParticleGeometry>().vertex_buffer;
+  assert!(particles.vertices.len() <= PARTICLE_BUFFER_VERTICES, "{} vertices", particles.vertices.len());
+  assert!(particles.indices.len() <= PARTICLE_BUFFER_INDICES, "{} indices", particles.indices.len());
+}
+
+#[test]
+fn a_bomb_takes_one_charge_per_press_and_grants_i_frames() {
+  let (mut world, mut schedule) = headless_run(13);
+  // the spawn invulnerability runs out first
+  run_ticks(&mut world, &mut schedule, ticks(INVULNERABLE_DURATION + 0.1), STEP, |_| HashSet::new());
+  let player = world.query_filtered::<Entity, With<Player>>().single(&world);
+  assert!(world.get::<Invulnerable>(player).is_none());
+  let position = world.get::<Transform>(player).unwrap().position;
+  world.spawn((
+    ScopeTag(GameScope::Run),
+    Enemy { kind: EnemyKind::Rock },
+    Velocity::Linear(WorldVec::ZERO),
+    Transform {
+      position: position + WorldVec::new(40.0, 0.0),
+      ..Default::default()
+    },
+    Collider { radius: ROCK_RADIUS },
+  ));
+  world.resource_mut::<Score>().0 = 2 * BOMB_CHARGE_SCORE;
+
+  // held for a while, it goes off once
+  run_ticks(&mut world, &mut schedule, 10, STEP, |_| HashSet::from([Keycode::B]));
+  assert_eq!(world.resource::<Bomb>().charges, 1);
+  assert_eq!(count::<With<Shockwave>>(&mut world), 1);
+  assert!(world.get::<Invulnerable>(player).is_some());
+
+  run_ticks(&mut world, &mut schedule, ticks(BOMB_DURATION), STEP, |_| HashSet::new());
+  assert_eq!(count::<With<Enemy>>(&mut world), 0);
+  let bombed = format!("+{}", SCORE_ENEMY_BOMBED);
+  assert!(world.query::<&Text>().iter(&world).any(|text| text.text == bombed));
+  assert!(world.get::<Invulnerable>(player).is_none());
+  assert_eq!(count::<With<Shockwave>>(&mut world), 0);
+}
+
+#[test]
+fn a_shockwave_catches_a_projectile_crossing_it_between_two_ticks() {
+  let (mut world, mut schedule) = without_players(14);
+  let center = WorldPos::from(world.resource::<WorldBounds>().center());
+  world.spawn((
+    ScopeTag(GameScope::Run),
+    Shockwave::default(),
+    Transform {
+      position: center,
+      ..Default::default()
+    },
+    Interpolation::new(vec![(0.0, BOMB_RADIUS)], BOMB_DURATION, InterpolationMode::Once),
+  ));
+  // 50 units a tick towards the center, the ring grows by less than 5. At the end of one tick the
+  // projectile is well outside of it and at the end of the next well inside
+  let projectile = world
+    .spawn((
+      Transform {
+        position: center + WorldVec::new(200.0, 0.0),
+        ..Default::default()
+      },
+      Projectile::bundle(WorldVec::new(-3_000.0, 0.0)),
+      DeathAction::Splat,
+      Hostile,
+    ))
+    .id();
+
+  run_ticks(&mut world, &mut schedule, 8, STEP, |_| HashSet::new());
+  assert!(world.get::<Despawning>(projectile).is_some() || world.get_entity(projectile).is_none());
+  assert_eq!(count::<With<DeadProjectile>>(&mut world), 1);
+}
+
+#[test]
+fn the_game_pauses_itself_after_a_gap_in_the_input() {
+  let (mut world, mut schedule) = headless_run(12);
+  world.resource_mut::<Idle>().timeout = Some(Duration::from_secs(2));
+  let state = |world: &World| *world.resource::<GameState>();
+
+  // a tap after 1.5 s restarts the countdown, it runs out 2 s after the tap
+  run_ticks(&mut world, &mut schedule, ticks(1.5), STEP, |_| HashSet::new());
+  run_ticks(&mut world, &mut schedule, 1, STEP, |_| HashSet::from([Keycode::Left]));
+  run_ticks(&mut world, &mut schedule, ticks(1.9), STEP, |_| HashSet::new());
+  assert_eq!(state(&world), GameState::Running);
+  run_ticks(&mut world, &mut schedule, ticks(0.2), STEP, |_| HashSet::new());
+  assert_eq!(state(&world), GameState::Paused);
+  assert!(world.resource::<Idle>().auto_paused);
+
+  // held keys count as input, and nothing counts once the run is over
+  world.insert_resource(GameState::Running);
+  run_ticks(&mut world, &mut schedule, ticks(3.0), STEP, |_| HashSet::from([Keycode::Space]));
+  assert_eq!(state(&world), GameState::Running);
+  let (mut world, mut schedule) = without_players(12);
+  world.resource_mut::<Idle>().timeout = Some(Duration::from_secs(2));
+  run_ticks(&mut world, &mut schedule, ticks(3.0), STEP, |_| HashSet::new());
+  assert_eq!(state(&world), GameState::Running);
+}
+
+#[test]
+fn practiced_runs_stay_out_of_the_scores_stats_and_skill_points() {
+  use bytepath::{
+    highscores::HighScores,
+    practice::{Practice, PracticeCommand},
+    shop::PlayerUpgrades,
+  };
+
+  // opening the panel without using a tool doesn't count, turning a tool off again or setting the
+  // balance back does
+  let changed = GameBalance {
+    bomb_radius: 200.0,
+    ..Default::default()
+  };
+  let tools = [
+    vec![],
+    vec![PracticeCommand::Invincible(true), PracticeCommand::Invincible(false)],
+    vec![PracticeCommand::Balance(changed), PracticeCommand::Balance(GameBalance::default())],
+  ];
+  for commands in tools {
+    let (mut world, mut schedule) = headless_run(3);
+    world.insert_resource(HighScores::default());
+    world.insert_resource(LifetimeStats::default());
+    world.insert_resource(PlayerUpgrades::default());
+    let mut practice = Practice::default();
+    let practiced = !commands.is_empty();
+    commands.into_iter().for_each(|command| practice.dispatch(command));
+    world.insert_resource(practice);
+
+    run_ticks(&mut world, &mut schedule, ticks(1.0), STEP, |_| HashSet::new());
+    assert_eq!(world.resource::<RunStats>().practiced, practiced);
+    world.resource_mut::<Score>().0 = 500;
+    run_ticks(&mut world, &mut schedule, 1, STEP, |_| HashSet::from([Keycode::S]));
+
+    assert_eq!(count::<With<Player>>(&mut world), 0);
+    let counted = usize::from(!practiced);
+    assert_eq!(world.resource::<HighScores>().entries.len(), counted);
+    assert_eq!(world.resource::<LifetimeStats>().get("runs"), counted as u64);
+    assert_eq!(world.resource::<PlayerUpgrades>().skill_points, 50 * counted as u64);
+    // the next run starts untagged
+    assert!(!world.resource::<RunStats>().practiced);
+  }
+}
+
+#[test]
+fn entering_a_phase_keeps_only_its_scopes() {
+  let (mut world, mut schedule) = headless_run(9);
+  run_ticks(&mut world, &mut schedule, ticks(3.0), STEP, shoot_and_turn);
+  let scoped = |world: &mut World, scope: GameScope| {
+    let mut query = world.query::<(&ScopeTag, Entity)>();
+    let mut entities = query.iter(world).filter(|(tag, _)| tag.0 == scope).map(|(_, e)| e).collect::<Vec<_>>();
+    entities.sort();
+    entities
+  };
+  let persistent = scoped(&mut world, GameScope::Persistent);
+  assert!(!persistent.is_empty());
+
+  // the playfield stays under the game over table
+  run_ticks(&mut world, &mut schedule, 1, STEP, |_| HashSet::from([Keycode::S]));
+  assert_eq!(*world.resource::<Phase>(), Phase::GameOver);
+  let playfield = scoped(&mut world, GameScope::Run);
+  assert!(count::<With<Projectile>>(&mut world) > 0);
+  assert_eq!(count::<With<Player>>(&mut world), 0);
+
+  // the next run starts on an empty one, the pooled particles and the effects stay
+  run_ticks(&mut world, &mut schedule, 1, STEP, |_| HashSet::from([Keycode::R]));
+  assert_eq!(*world.resource::<Phase>(), Phase::Run);
+  let run = scoped(&mut world, GameScope::Run);
+  assert!(run.iter().all(|entity| !playfield.contains(entity)));
+  assert_eq!(run.len(), count::<With<Player>>(&mut world));
+  assert_eq!(run.len(), 1);
+  let kept = scoped(&mut world, GameScope::Persistent);
+  let pooled = persistent.iter().filter(|entity| world.get::<Active>(**entity).is_some());
+  assert!(pooled.clone().count() > 0);
+  assert!(pooled.into_iter().all(|entity| kept.contains(entity)));
+}
+
+/// A [`BurnZone`] of `faction` in the middle of the playfield, its tick `offset` seconds along.
+fn burn_zone(world: &mut World, faction: Faction, offset: f32) {
+  let mut tick = Timer::from_seconds(BURN_TICK, true);
+  tick.elapsed = Duration::from_secs_f32(offset);
+  let center = WorldPos::from(world.resource::<WorldBounds>().center());
+  world.spawn((
+    ScopeTag(GameScope::Run),
+    BurnZone {
+      radius: BURN_ZONE_RADIUS,
+      tick,
+      faction,
+    },
+    Lifetime(Timer::from_seconds(BURN_ZONE_DURATION, false)),
+    Transform {
+      position: center,
+      ..Default::default()
+    },
+  ));
+}
+
+/// Something of `faction` with ten health standing in the middle of the playfield.
+fn burn_victim(world: &mut World, faction: Faction) -> Entity {
+  let center = WorldPos::from(world.resource::<WorldBounds>().center());
+  let transform = Transform {
+    position: center,
+    ..Default::default()
+  };
+  world.spawn((ScopeTag(GameScope::Run), faction, Health(10), Collider { radius: 4.0 }, transform)).id()
+}
+
+fn health(world: &World, entity: Entity) -> u32 {
+  world.get::<Health>(entity).unwrap().0
+}
+
+#[test]
+fn a_burn_zone_burns_on_its_tick_only() {
+  let (mut world, mut schedule) = without_players(11);
+  burn_zone(&mut world, Faction::Players, 0.0);
+  let victim = burn_victim(&mut world, Faction::Enemies);
+
+  run_ticks(&mut world, &mut schedule, ticks(0.4), STEP, |_| HashSet::new());
+  assert_eq!(health(&world, victim), 10);
+  run_ticks(&mut world, &mut schedule, ticks(0.2), STEP, |_| HashSet::new());
+  assert_eq!(health(&world, victim), 9);
+  run_ticks(&mut world, &mut schedule, ticks(1.5), STEP, |_| HashSet::new());
+  assert_eq!(health(&world, victim), 6);
+
+  // one burn a tick for the zone's lifetime, then it's gone
+  run_ticks(&mut world, &mut schedule, ticks(1.5), STEP, |_| HashSet::new());
+  assert_eq!(count::<With<BurnZone>>(&mut world), 0);
+  assert_eq!(health(&world, victim), 10 - (BURN_ZONE_DURATION / BURN_TICK) as u32);
+}
+
+#[test]
+fn overlapping_burn_zones_burn_no_faster_than_one() {
+  let (mut world, mut schedule) = without_players(12);
+  // the second zone ticks halfway between the first one's ticks
+  burn_zone(&mut world, Faction::Players, 0.0);
+  burn_zone(&mut world, Faction::Players, BURN_TICK / 2.0);
+  let victim = burn_victim(&mut world, Faction::Enemies);
+
+  run_ticks(&mut world, &mut schedule, ticks(2.1), STEP, |_| HashSet::new());
+  assert_eq!(health(&world, victim), 6);
+}
+
+/// How the two mark systems mark an entity for despawn within one tick, the first always without an
+/// exit animation.
+#[derive(Component, Clone, Copy)]
+enum Marked {
+  Twice,
+  ThenPopped,
+}
+
+fn mark_system(mut commands: Commands, query: Query<Entity, With<Marked>>) {
+  for entity in query.iter() {
+    commands.entity(entity).insert(Despawning::now());
+  }
+}
+
+fn mark_again_system(mut commands: Commands, query: Query<(Entity, &Marked, &Transform)>) {
+  for (entity, marked, transform) in query.iter() {
+    let mut entity = commands.entity(entity);
+    match marked {
+      Marked::Twice => entity.insert(Despawning::now()),
+      Marked::ThenPopped => {
+        let color = ColorGl::from(RGB_COLOR_DEATH);
+        entity.insert(Despawning::bundle(ExitAnim::Pop, transform.mat4(), glam::Vec2::ONE, color))
+      }
+    };
+    entity.remove::<Marked>();
+  }
+}
+
+fn marked_rock(world: &mut World, marked: Marked) -> Entity {
+  let transform = Transform {
+    position: WorldPos::new(100.0, 100.0),
+    ..Default::default()
+  };
+  let enemy = Enemy { kind: EnemyKind::Rock };
+  world.spawn((ScopeTag(GameScope::Run), enemy, Velocity::Linear(WorldVec::ZERO), transform, marked)).id()
+}
+
+#[test]
+fn two_systems_marking_one_entity_in_a_tick_despawn_it_once() {
+  let (mut world, mut schedule) = headless_run(16);
+  let (twice, popped) = (marked_rock(&mut world, Marked::Twice), marked_rock(&mut world, Marked::ThenPopped));
+  schedule.add_system_to_stage("game", mark_system);
+  schedule.add_system_to_stage("game", mark_again_system.after(mark_system));
+
+  run_ticks(&mut world, &mut schedule, 1, STEP, |_| HashSet::new());
+  assert!(world.get_entity(twice).is_none());
+  // the later mark wins, so it pops before it is gone
+  assert!(world.get::<Despawning>(popped).is_some_and(|despawning| despawning.exit.is_some()));
+
+  run_ticks(&mut world, &mut schedule, ticks(0.2), STEP, |_| HashSet::new());
+  assert!(world.get_entity(popped).is_none());
+}
+
+#[test]
+fn a_burn_zone_spares_its_own_faction() {
+  let (mut world, mut schedule) = without_players(13);
+  burn_zone(&mut world, Faction::Players, 0.0);
+  let ally = burn_victim(&mut world, Faction::Players);
+  let center = WorldPos::from(world.resource::<WorldBounds>().center());
+  let rock = world
+    .spawn((
+      ScopeTag(GameScope::Run),
+      Enemy { kind: EnemyKind::Rock },
+      Velocity::Linear(WorldVec::ZERO),
+      Transform {
+        position: center,
+        ..Default::default()
+      },
+      Collider { radius: ROCK_RADIUS },
+      Faction::Enemies,
+      Health(ROCK_HEALTH),
+    ))
+    .id();
+
+  run_ticks(&mut world, &mut schedule, ticks(1.1), STEP, |_| HashSet::new());
+  assert_eq!(health(&world, ally), 10);
+  assert!(world.get_entity(rock).is_none() || world.get::<Despawning>(rock).is_some());
+  assert_eq!(world.resource::<Score>().0, SCORE_ENEMY_BOMBED);
+  // a burnt rock leaves no zone of its own
+  assert_eq!(count::<With<BurnZone>>(&mut world), 1);
+}
+
+#[test]
+fn each_channel_delivers_what_game_events_carried_before_the_split() {
+  // damage, pickups, burn zones and floating texts of a minute of `--headless`, counted off GameEvents
+  // and the Text entities before each got its own channel. `fixed_point` plays out a different run
+  let before = if cfg!(feature = "fixed_point") { (0, 34, 7, 14) } else { (3, 60, 16, 27) };
+  let (mut world, mut schedule) = headless_run(4);
+  run_ticks(&mut world, &mut schedule, ticks(60.0), STEP, shoot_and_turn);
+  let [_, damage, pickups, spawns, notices] = world.resource::<EventCounts>().total;
+  assert_eq!((damage, pickups, spawns, notices), before);
+}
+
+fn ammo_pickup(world: &mut World, position: WorldPos) -> Entity {
+  let pickup = AmmoPickup {
+    rotation_speed: 0.0,
+    timer: Timer::from_seconds(0.15, false),
+  };
+  let transform = Transform {
+    position,
+    ..Default::default()
+  };
+  let collider = Collider {
+    radius: AMMO_PICKUP_RADIUS,
+  };
+  let bundle = (ScopeTag(GameScope::Run), pickup, Velocity::Heading(0.0), AngularVelocity(0.0), transform, collider);
+  world.spawn(bundle).id()
+}
+
+/// Who died this tick and of what.
+fn deaths(world: &World) -> Vec<(Entity, DeathCause)> {
+  let events = world.resource::<Events<GameEvents>>();
+  events
+    .get_reader()
+    .iter(events)
+    .filter_map(|event| match event {
+      GameEvents::PlayerDeath(player, cause) => Some((*player, *cause)),
+      _ => None,
+    })
+    .collect()
+}
+
+/// The player past its spawn protection, in the middle of the arena and fast enough that one boosted
+/// tick takes it `step` units ahead.
+fn boosted_player(seed: u64, step: f32) -> (World, Schedule, Entity) {
+  let (mut world, mut schedule) = headless_run(seed);
+  run_ticks(&mut world, &mut schedule, ticks(INVULNERABLE_DURATION + 0.1), STEP, |_| HashSet::new());
+  let player = world.query_filtered::<Entity, With<Player>>().single(&world);
+  let center = WorldPos::from(world.resource::<WorldBounds>().center());
+  world.get_mut::<Transform>(player).unwrap().position = center;
+  world.get_mut::<Player>(player).unwrap().movement_speed = step / 1.5 / STEP.as_secs_f32();
+  (world, schedule, player)
+}
+
+/// A laser already firing from `start` to `end`.
+fn firing_laser(world: &mut World, start: WorldPos, end: WorldPos) {
+  world.spawn((
+    ScopeTag(GameScope::Run),
+    LaserHazard {
+      phase: LaserPhase::Firing,
+      timer: Timer::from_seconds(LASER_FIRING_DURATION, false),
+      start,
+      end,
+    },
+    Interpolation::new(vec![(0.5, 1.5)], 0.25, InterpolationMode::PingPong),
+  ));
+}
+
+#[test]
+fn a_boosted_step_collects_the_pickups_it_passed_in_order() {
+  let (mut world, mut schedule, player) = boosted_player(15, 100.0);
+  let transform = *world.get::<Transform>(player).unwrap();
+  // both far behind where the ship ends the tick, only the sweep reaches them
+  let ahead = |distance| transform.position + transform.rotation.forward_by(distance);
+  let pickups = [40.0, 70.0].map(|distance| ammo_pickup(&mut world, ahead(distance)));
+
+  run_ticks(&mut world, &mut schedule, 1, STEP, |_| HashSet::from([Keycode::Up]));
+  let moved = world.get::<Transform>(player).unwrap().position - transform.position;
+  assert!(moved.length() > 99.0, "moved {:?}", moved);
+  let events = world.resource::<Events<GameEvents>>();
+  let contacts = events
+    .get_reader()
+    .iter(events)
+    .filter_map(|event| match event {
+      GameEvents::PlayerContact { other, .. } => Some(*other),
+      _ => None,
+    })
+    .collect::<Vec<_>>();
+  assert_eq!(contacts, pickups);
+}
+
+#[test]
+fn a_boosted_step_across_a_firing_laser_kills() {
+  let (mut world, mut schedule, player) = boosted_player(17, 100.0);
+  let transform = *world.get::<Transform>(player).unwrap();
+  // a beam across the heading halfway along the step, the ship starts and ends the tick 50 units clear
+  let through = transform.position + transform.rotation.forward_by(50.0);
+  let across = (transform.rotation + Angle::from_radians(std::f32::consts::FRAC_PI_2)).forward_by(200.0);
+  firing_laser(&mut world, through - across, through + across);
+
+  run_ticks(&mut world, &mut schedule, 1, STEP, |_| HashSet::from([Keycode::Up]));
+  assert_eq!(deaths(&world), [(player, DeathCause::Laser)]);
+}
+
+#[test]
+fn two_lethal_hits_in_one_tick_are_one_death() {
+  let (mut world, mut schedule) = headless_run(18);
+  run_ticks(&mut world, &mut schedule, ticks(INVULNERABLE_DURATION + 0.1), STEP, |_| HashSet::new());
+  let player = world.query_filtered::<Entity, With<Player>>().single(&world);
+  let position = world.get::<Transform>(player).unwrap().position;
+  let across = WorldVec::new(400.0, 400.0);
+  // a laser through the player and a projectile sitting on it, both land during the same tick
+  firing_laser(&mut world, position - across, position + across);
+  world.spawn((
+    Transform {
+      position,
+      ..Default::default()
+    },
+    Projectile::bundle(WorldVec::new(0.0, -60.0)),
+    DeathAction::Splat,
+    Hostile,
+    Sweep::default(),
+  ));
+
+  // the run stats count deaths only with a profile to add them up in, the last death ends the run
+  world.insert_resource(LifetimeStats::default());
+  run_ticks(&mut world, &mut schedule, 1, STEP, |_| HashSet::new());
+  assert_eq!(deaths(&world), [(player, DeathCause::Laser)]);
+  let lifetime = world.resource::<LifetimeStats>();
+  assert_eq!((lifetime.get("deaths_laser"), lifetime.get("deaths_projectile")), (1, 0));
+}
+
+#[test]
+fn a_hostile_projectile_hits_the_player_at_any_speed() {
+  // from a unit a tick to four times the player's diameter, flying right through it
+  for speed in [60.0, 600.0, 3_000.0, 12_000.0] {
+    let (mut world, mut schedule) = headless_run(16);
+    run_ticks(&mut world, &mut schedule, ticks(INVULNERABLE_DURATION + 0.1), STEP, |_| HashSet::new());
+    let player = world.query_filtered::<Entity, With<Player>>().single(&world);
+    let position = world.get::<Transform>(player).unwrap().position;
+    let half_a_tick = speed * STEP.as_secs_f32() / 2.0;
+    let projectile = world
+      .spawn((
+        Transform {
+          position: position + WorldVec::new(0.0, half_a_tick),
+          ..Default::default()
+        },
+        Projectile::bundle(WorldVec::new(0.0, -speed)),
+        DeathAction::Splat,
+        Hostile,
+        Sweep::default(),
+      ))
+      .id();
+
+    run_ticks(&mut world, &mut schedule, 1, STEP, |_| HashSet::new());
+    assert_eq!(deaths(&world), [(player, DeathCause::Projectile)], "{} units per second", speed);
+    assert!(world.get::<Projectile>(projectile).is_none() || world.get::<Despawning>(projectile).is_some());
+  }
+}